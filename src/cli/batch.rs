@@ -0,0 +1,231 @@
+//! Batch Input
+//!
+//! Bank states read from a file, one per record, for scoring a whole
+//! portfolio in one run. Every record is a full `BankState` plus an `id`
+//! naming the institution. The format follows the file extension:
+//!
+//! | Extension          | Layout                                          |
+//! |--------------------|-------------------------------------------------|
+//! | `.csv`             | Header row, then one state per row              |
+//! | `.json`            | One array of state objects                      |
+//! | `.ndjson`, `.jsonl`| One state object per line, blank lines skipped  |
+//!
+//! A malformed record does not stop the others being read; it is returned
+//! in its place with where it was found, a line for CSV and NDJSON, a
+//! position in the array for JSON.
+
+use olo_core::BankState;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Layout of a batch file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl InputFormat {
+    /// Format named by `path`'s extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(InputFormat::Csv),
+            "json" => Some(InputFormat::Json),
+            "ndjson" | "jsonl" => Some(InputFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// One institution's state
+#[derive(Debug, Clone)]
+pub struct BankRecord {
+    pub id: String,
+    pub state: BankState,
+}
+
+/// A record as written in the file
+#[derive(Deserialize)]
+struct Row {
+    id: String,
+    tier1_capital: f64,
+    total_assets: f64,
+    liquidity_coverage: f64,
+    entropy_index: f64,
+}
+
+impl Row {
+    /// The record, if its values can be scored
+    fn into_record(self) -> Result<BankRecord, String> {
+        if self.id.trim().is_empty() {
+            return Err("empty id".to_string());
+        }
+        let fields = [
+            ("tier1_capital", self.tier1_capital),
+            ("total_assets", self.total_assets),
+            ("liquidity_coverage", self.liquidity_coverage),
+            ("entropy_index", self.entropy_index),
+        ];
+        if let Some((name, _)) = fields.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(format!("{} is not a finite number", name));
+        }
+        if self.total_assets <= 0.0 {
+            return Err("total_assets must be positive".to_string());
+        }
+        if self.liquidity_coverage <= 0.0 {
+            return Err("liquidity_coverage must be positive".to_string());
+        }
+        Ok(BankRecord {
+            id: self.id,
+            state: BankState {
+                tier1_capital: self.tier1_capital,
+                total_assets: self.total_assets,
+                liquidity_coverage: self.liquidity_coverage,
+                entropy_index: self.entropy_index,
+            },
+        })
+    }
+}
+
+/// Where a record was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// Line of a CSV or NDJSON file, from 1
+    Line(u64),
+    /// Position in a JSON array, from 1
+    Record(usize),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Line(line) => write!(f, "line {}", line),
+            Location::Record(index) => write!(f, "record {}", index),
+        }
+    }
+}
+
+/// A record that could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordError {
+    pub location: Location,
+    pub reason: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.reason)
+    }
+}
+
+/// A batch file that could not be read at all
+#[derive(Debug)]
+pub enum BatchError {
+    Io { path: PathBuf, source: io::Error },
+    UnknownFormat { path: PathBuf },
+    Malformed { path: PathBuf, reason: String },
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Io { path, source } => write!(f, "cannot read {}: {}", path.display(), source),
+            BatchError::UnknownFormat { path } => {
+                write!(f, "{}: unknown input format, expected .csv, .json, .ndjson or .jsonl", path.display())
+            }
+            BatchError::Malformed { path, reason } => write!(f, "{}: {}", path.display(), reason),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BatchError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Every record of the file at `path`, in file order
+pub fn read_records(path: &Path) -> Result<Vec<Result<BankRecord, RecordError>>, BatchError> {
+    let format = InputFormat::from_path(path).ok_or_else(|| BatchError::UnknownFormat { path: path.to_path_buf() })?;
+    let text = fs::read_to_string(path).map_err(|source| BatchError::Io { path: path.to_path_buf(), source })?;
+    let malformed = |reason: String| BatchError::Malformed { path: path.to_path_buf(), reason };
+    match format {
+        InputFormat::Csv => read_csv(&text).map_err(|e| malformed(e.to_string())),
+        InputFormat::Json => read_json(&text).map_err(|e| malformed(e.to_string())),
+        InputFormat::Ndjson => Ok(read_ndjson(&text)),
+    }
+}
+
+fn record_at(location: Location, row: Result<Row, String>) -> Result<BankRecord, RecordError> {
+    row.and_then(Row::into_record).map_err(|reason| RecordError { location, reason })
+}
+
+fn read_csv(text: &str) -> Result<Vec<Result<BankRecord, RecordError>>, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut records = Vec::new();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |position| position.line());
+                records.push(Err(RecordError { location: Location::Line(line), reason: e.to_string() }));
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let row = record.deserialize(Some(&headers)).map_err(|e| e.to_string());
+        records.push(record_at(Location::Line(line), row));
+    }
+    Ok(records)
+}
+
+fn read_json(text: &str) -> Result<Vec<Result<BankRecord, RecordError>>, serde_json::Error> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(text)?;
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| record_at(Location::Record(i + 1), serde_json::from_value(value).map_err(|e| e.to_string())))
+        .collect())
+}
+
+fn read_ndjson(text: &str) -> Vec<Result<BankRecord, RecordError>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            record_at(Location::Line(i as u64 + 1), serde_json::from_str(line).map_err(|e| e.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_records_keep_their_place() {
+        let csv = "id,tier1_capital,total_assets,liquidity_coverage,entropy_index\n\
+                   a,10000,100000,1.2,2.5\n\
+                   b,lots,100000,1.2,2.5\n\
+                   c,10000,0,1.2,2.5\n";
+        let records = read_csv(csv).unwrap();
+        assert_eq!(records[0].as_ref().unwrap().id, "a");
+        assert_eq!(records[1].as_ref().unwrap_err().location, Location::Line(3));
+        assert_eq!(records[2].as_ref().unwrap_err().reason, "total_assets must be positive");
+
+        let json = r#"[{"id": "a", "tier1_capital": 1, "total_assets": 2, "liquidity_coverage": 1, "entropy_index": 0},
+                       {"id": "b"}]"#;
+        let records = read_json(json).unwrap();
+        assert!(records[0].is_ok());
+        assert_eq!(records[1].as_ref().unwrap_err().location, Location::Record(2));
+        assert_eq!(InputFormat::from_path(Path::new("banks.JSONL")), Some(InputFormat::Ndjson));
+    }
+}
@@ -0,0 +1,114 @@
+//! Compare Subcommand
+//!
+//! Scores two states of one bank, typically consecutive quarters, and
+//! attributes the change in score to the capital, entropy and liquidity
+//! stress components. A positive change is a deterioration.
+
+use clap::Args;
+use olo_core::{attribute_fragility, fragility_components, BankState, FragilityComponents, LagrangianConfig};
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::{OutputFormat, Report, RiskLevel, EXIT_OVER_THRESHOLD};
+
+#[derive(Debug, Clone, Args)]
+pub struct CompareArgs {
+    /// JSON file holding the earlier bank state
+    #[arg(long)]
+    pub before: PathBuf,
+    /// JSON file holding the later bank state
+    #[arg(long)]
+    pub after: PathBuf,
+    /// Exit 2 if the score worsened by more than this many points
+    #[arg(long)]
+    pub fail_on_deterioration: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct CompareInputs<'a> {
+    before: &'a BankState,
+    after: &'a BankState,
+}
+
+/// Score of one side of the comparison
+#[derive(Serialize)]
+struct Scored {
+    score: f64,
+    classification: RiskLevel,
+    components: FragilityComponents,
+}
+
+#[derive(Serialize)]
+struct CompareOutputs {
+    before: Scored,
+    after: Scored,
+    delta: f64,
+    /// Points of `delta` each component accounts for
+    attribution: FragilityComponents,
+    classification_changed: bool,
+}
+
+fn read_state(path: &Path) -> Result<BankState, Box<dyn Error>> {
+    let file = File::open(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    Ok(serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))?)
+}
+
+pub fn compare(args: &CompareArgs, config: &LagrangianConfig, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let before = read_state(&args.before)?;
+    let after = read_state(&args.after)?;
+    let attribution = attribute_fragility(&before, &after, config);
+    let scored = |state: &BankState, score: f64| Scored {
+        score,
+        classification: RiskLevel::of_fragility(score),
+        components: fragility_components(state, config),
+    };
+    let outputs = CompareOutputs {
+        before: scored(&before, attribution.before),
+        after: scored(&after, attribution.after),
+        delta: attribution.delta,
+        attribution: attribution.contributions,
+        classification_changed: RiskLevel::of_fragility(attribution.before)
+            != RiskLevel::of_fragility(attribution.after),
+    };
+
+    match format {
+        OutputFormat::Json => {
+            let inputs = CompareInputs { before: &before, after: &after };
+            Report { command: "compare", inputs, config, outputs: &outputs }.print()?
+        }
+        OutputFormat::Text => {
+            println!("Fragility Comparison:");
+            println!("  Before: {:.4} ({})", outputs.before.score, outputs.before.classification);
+            println!("  After:  {:.4} ({})", outputs.after.score, outputs.after.classification);
+            println!("  Change: {:+.4}", outputs.delta);
+            println!();
+            println!("Attribution:");
+            println!("  Capital:   {:+.4}", outputs.attribution.capital);
+            println!("  Entropy:   {:+.4}", outputs.attribution.entropy);
+            println!("  Liquidity: {:+.4}", outputs.attribution.liquidity);
+            println!();
+            if outputs.classification_changed {
+                println!(
+                    "Classification changed: {} → {}",
+                    outputs.before.classification, outputs.after.classification
+                );
+            } else {
+                println!("Classification unchanged ({})", outputs.after.classification);
+            }
+        }
+    }
+
+    if let Some(points) = args.fail_on_deterioration {
+        if outputs.delta > points {
+            eprintln!(
+                "fragility worsened by {:.4} points, more than --fail-on-deterioration {}",
+                outputs.delta, points
+            );
+            std::process::exit(EXIT_OVER_THRESHOLD);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,374 @@
+//! Configuration Files
+//!
+//! Settings a command would otherwise need flags for on every run, read
+//! from a TOML file with a table per library configuration:
+//!
+//! ```toml
+//! [lagrangian]
+//! regulatory_min_capital = 0.105
+//!
+//! [monte_carlo]
+//! num_simulations = 50000
+//!
+//! [network]
+//! bootstrap_peers = ["/dns4/seed.example/tcp/9000"]
+//! ```
+//!
+//! The file is `--config` if given, else `./olo.toml`, else
+//! `$XDG_CONFIG_HOME/olo/config.toml` (`~/.config/olo/config.toml` without
+//! `XDG_CONFIG_HOME`), whichever exists first. Each setting can also be
+//! given as an environment variable named `OLO_` and its table and key in
+//! capitals, such as `OLO_MONTE_CARLO_SEED`; lists there are
+//! comma-separated. A flag beats the environment, which beats the file,
+//! which beats the library default.
+
+use clap::Subcommand;
+use olo_core::{EntropyConfig, LagrangianConfig, MonteCarloConfig, NetworkConfig, TopicConfig};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective configuration and where each value came from
+    Show,
+}
+
+/// Type of a setting's value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Float,
+    Count,
+    Bool,
+    Text,
+    List,
+}
+
+impl Kind {
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Float => "a finite number",
+            Kind::Count => "a non-negative integer",
+            Kind::Bool => "true or false",
+            Kind::Text => "a string",
+            Kind::List => "a list of strings",
+        }
+    }
+}
+
+/// Every setting that can be configured, as `table.key`, in the order shown
+const KEYS: [(&str, Kind); 15] = [
+    ("lagrangian.lambda_sensitivity", Kind::Float),
+    ("lagrangian.regulatory_min_capital", Kind::Float),
+    ("monte_carlo.num_simulations", Kind::Count),
+    ("monte_carlo.seed", Kind::Count),
+    ("monte_carlo.shock_size", Kind::Float),
+    ("monte_carlo.num_threads", Kind::Count),
+    ("monte_carlo.store_samples", Kind::Bool),
+    ("entropy.min_weight", Kind::Float),
+    ("entropy.normalize", Kind::Bool),
+    ("network.listen_addr", Kind::Text),
+    ("network.bootstrap_peers", Kind::List),
+    ("network.topics", Kind::List),
+    ("network.identity_path", Kind::Text),
+    ("network.store_path", Kind::Text),
+    ("network.enable_mdns", Kind::Bool),
+];
+
+/// Value of one setting
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Setting {
+    Float(f64),
+    Count(u64),
+    Bool(bool),
+    Text(String),
+    List(Vec<String>),
+}
+
+impl fmt::Display for Setting {
+    /// The value as TOML
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Setting::Float(value) => write!(f, "{:?}", value),
+            Setting::Count(value) => write!(f, "{}", value),
+            Setting::Bool(value) => write!(f, "{}", value),
+            Setting::Text(value) => write!(f, "{:?}", value),
+            Setting::List(values) => write!(f, "{:?}", values),
+        }
+    }
+}
+
+impl Setting {
+    fn from_toml(kind: Kind, value: &toml::Value) -> Option<Self> {
+        Some(match (kind, value) {
+            (Kind::Float, toml::Value::Float(value)) if value.is_finite() => Setting::Float(*value),
+            (Kind::Float, toml::Value::Integer(value)) => Setting::Float(*value as f64),
+            (Kind::Count, toml::Value::Integer(value)) => Setting::Count(u64::try_from(*value).ok()?),
+            (Kind::Bool, toml::Value::Boolean(value)) => Setting::Bool(*value),
+            (Kind::Text, toml::Value::String(value)) => Setting::Text(value.clone()),
+            (Kind::List, toml::Value::Array(values)) => Setting::List(
+                values.iter().map(|value| value.as_str().map(str::to_string)).collect::<Option<_>>()?,
+            ),
+            _ => return None,
+        })
+    }
+
+    fn from_env(kind: Kind, value: &str) -> Option<Self> {
+        Some(match kind {
+            Kind::Float => Setting::Float(value.trim().parse().ok().filter(|value: &f64| value.is_finite())?),
+            Kind::Count => Setting::Count(value.trim().parse().ok()?),
+            Kind::Bool => Setting::Bool(value.trim().parse().ok()?),
+            Kind::Text => Setting::Text(value.to_string()),
+            Kind::List => Setting::List(
+                value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect(),
+            ),
+        })
+    }
+}
+
+/// Where a setting's effective value came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Source {
+    Default,
+    File { path: PathBuf },
+    Env { var: String },
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => f.write_str("default"),
+            Source::File { path } => write!(f, "file {}", path.display()),
+            Source::Env { var } => write!(f, "env {}", var),
+        }
+    }
+}
+
+/// A configuration that could not be read
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, reason: String },
+    UnknownKey { path: PathBuf, key: String },
+    Invalid { source: Source, key: String, expected: &'static str },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "cannot read {}: {}", path.display(), source),
+            ConfigError::Parse { path, reason } => write!(f, "{}: {}", path.display(), reason),
+            ConfigError::UnknownKey { path, key } => write!(f, "{}: unknown setting {}", path.display(), key),
+            ConfigError::Invalid { source, key, expected } => {
+                write!(f, "{} (from {}) must be {}", key, source, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Environment variable giving `key`
+pub fn env_var(key: &str) -> String {
+    format!("OLO_{}", key.replace('.', "_").to_ascii_uppercase())
+}
+
+/// Configuration file to read: `explicit` if given, else the first of the
+/// default locations that exists
+pub fn find_file(explicit: Option<&Path>, env: &impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+    let config_home = env("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| Path::new(&home).join(".config")));
+    [Some(PathBuf::from("olo.toml")), config_home.map(|dir| dir.join("olo").join("config.toml"))]
+        .into_iter()
+        .flatten()
+        .find(|path| path.is_file())
+}
+
+/// The library configurations with every layer applied, and each setting's source
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub lagrangian: LagrangianConfig,
+    pub monte_carlo: MonteCarloConfig,
+    pub entropy: EntropyConfig,
+    pub network: NetworkConfig,
+    /// File read, if any
+    pub file: Option<PathBuf>,
+    /// Source of each of `KEYS`, in order
+    sources: Vec<Source>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            lagrangian: LagrangianConfig::default(),
+            monte_carlo: MonteCarloConfig::default(),
+            entropy: EntropyConfig::default(),
+            network: NetworkConfig::default(),
+            file: None,
+            sources: vec![Source::Default; KEYS.len()],
+        }
+    }
+}
+
+impl Settings {
+    /// Settings from `file`, if any, overridden by the variables `env` finds
+    pub fn load(file: Option<&Path>, env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut settings = Settings { file: file.map(Path::to_path_buf), ..Settings::default() };
+        if let Some(path) = file {
+            settings.apply_file(path)?;
+        }
+        for (i, &(key, kind)) in KEYS.iter().enumerate() {
+            let var = env_var(key);
+            let Some(value) = env(&var).filter(|value| !value.is_empty()) else {
+                continue;
+            };
+            let source = Source::Env { var };
+            let setting = Setting::from_env(kind, &value)
+                .ok_or_else(|| ConfigError::Invalid { source: source.clone(), key: key.to_string(), expected: kind.describe() })?;
+            settings.set(key, setting);
+            settings.sources[i] = source;
+        }
+        Ok(settings)
+    }
+
+    fn apply_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let text = fs::read_to_string(path).map_err(|source| ConfigError::Io { path: path.to_path_buf(), source })?;
+        let tables: toml::Table =
+            toml::from_str(&text).map_err(|e| ConfigError::Parse { path: path.to_path_buf(), reason: e.to_string() })?;
+        for (table, values) in &tables {
+            let Some(values) = values.as_table() else {
+                return Err(ConfigError::UnknownKey { path: path.to_path_buf(), key: table.clone() });
+            };
+            for (name, value) in values {
+                let key = format!("{}.{}", table, name);
+                let Some(i) = KEYS.iter().position(|&(known, _)| known == key) else {
+                    return Err(ConfigError::UnknownKey { path: path.to_path_buf(), key });
+                };
+                let kind = KEYS[i].1;
+                let source = Source::File { path: path.to_path_buf() };
+                let Some(setting) = Setting::from_toml(kind, value) else {
+                    return Err(ConfigError::Invalid { source, key, expected: kind.describe() });
+                };
+                self.set(&key, setting);
+                self.sources[i] = source;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `key` to `setting`, which is of the key's kind
+    fn set(&mut self, key: &str, setting: Setting) {
+        match (key, setting) {
+            ("lagrangian.lambda_sensitivity", Setting::Float(value)) => self.lagrangian.lambda_sensitivity = value,
+            ("lagrangian.regulatory_min_capital", Setting::Float(value)) => {
+                self.lagrangian.regulatory_min_capital = value
+            }
+            ("monte_carlo.num_simulations", Setting::Count(value)) => self.monte_carlo.num_simulations = value as usize,
+            ("monte_carlo.seed", Setting::Count(value)) => self.monte_carlo.seed = value,
+            ("monte_carlo.shock_size", Setting::Float(value)) => self.monte_carlo.shock_size = value,
+            ("monte_carlo.num_threads", Setting::Count(value)) => self.monte_carlo.num_threads = value as usize,
+            ("monte_carlo.store_samples", Setting::Bool(value)) => self.monte_carlo.store_samples = value,
+            ("entropy.min_weight", Setting::Float(value)) => self.entropy.min_weight = value,
+            ("entropy.normalize", Setting::Bool(value)) => self.entropy.normalize = value,
+            ("network.listen_addr", Setting::Text(value)) => self.network.listen_addr = value,
+            ("network.bootstrap_peers", Setting::List(values)) => self.network.bootstrap_peers = values,
+            ("network.topics", Setting::List(values)) => {
+                self.network.topics = values.into_iter().map(TopicConfig::new).collect()
+            }
+            ("network.identity_path", Setting::Text(value)) => self.network.identity_path = Some(value.into()),
+            ("network.store_path", Setting::Text(value)) => self.network.store_path = Some(value.into()),
+            ("network.enable_mdns", Setting::Bool(value)) => self.network.enable_mdns = value,
+            (key, setting) => unreachable!("{} is not of the kind of {}", setting, key),
+        }
+    }
+
+    /// Effective value of `key`, `None` if unset
+    fn get(&self, key: &str) -> Option<Setting> {
+        let path = |path: &Option<PathBuf>| path.as_ref().map(|path| Setting::Text(path.display().to_string()));
+        Some(match key {
+            "lagrangian.lambda_sensitivity" => Setting::Float(self.lagrangian.lambda_sensitivity),
+            "lagrangian.regulatory_min_capital" => Setting::Float(self.lagrangian.regulatory_min_capital),
+            "monte_carlo.num_simulations" => Setting::Count(self.monte_carlo.num_simulations as u64),
+            "monte_carlo.seed" => Setting::Count(self.monte_carlo.seed),
+            "monte_carlo.shock_size" => Setting::Float(self.monte_carlo.shock_size),
+            "monte_carlo.num_threads" => Setting::Count(self.monte_carlo.num_threads as u64),
+            "monte_carlo.store_samples" => Setting::Bool(self.monte_carlo.store_samples),
+            "entropy.min_weight" => Setting::Float(self.entropy.min_weight),
+            "entropy.normalize" => Setting::Bool(self.entropy.normalize),
+            "network.listen_addr" => Setting::Text(self.network.listen_addr.clone()),
+            "network.bootstrap_peers" => Setting::List(self.network.bootstrap_peers.clone()),
+            "network.topics" => Setting::List(self.network.topics.iter().map(|topic| topic.name.clone()).collect()),
+            "network.identity_path" => return path(&self.network.identity_path),
+            "network.store_path" => return path(&self.network.store_path),
+            "network.enable_mdns" => Setting::Bool(self.network.enable_mdns),
+            _ => unreachable!("{} is not a setting", key),
+        })
+    }
+
+    /// Every setting with its effective value and source, in `KEYS` order
+    pub fn entries(&self) -> Vec<Entry> {
+        KEYS.iter()
+            .zip(&self.sources)
+            .map(|(&(key, _), source)| Entry { key, value: self.get(key), source: source.clone() })
+            .collect()
+    }
+}
+
+/// One line of `olo config show`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entry {
+    pub key: &'static str,
+    pub value: Option<Setting>,
+    pub source: Source,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_overrides_the_file() {
+        let path = std::env::temp_dir().join(format!("olo-config-{}.toml", std::process::id()));
+        fs::write(&path, "[monte_carlo]\nseed = 3\nshock_size = 1\n\n[network]\ntopics = [\"a\", \"b\"]\n").unwrap();
+        let env = |var: &str| match var {
+            "OLO_MONTE_CARLO_SEED" => Some("5".to_string()),
+            "OLO_NETWORK_BOOTSTRAP_PEERS" => Some("/ip4/10.0.0.1/tcp/1, /ip4/10.0.0.2/tcp/1".to_string()),
+            _ => None,
+        };
+        let settings = Settings::load(Some(&path), env).unwrap();
+        assert_eq!(settings.monte_carlo.seed, 5);
+        assert_eq!(settings.monte_carlo.shock_size, 1.0);
+        assert_eq!(settings.network.topics[1].name, "b");
+        assert_eq!(settings.network.bootstrap_peers.len(), 2);
+
+        let entries = settings.entries();
+        let entry = |key: &str| entries.iter().find(|entry| entry.key == key).unwrap().clone();
+        assert_eq!(entry("monte_carlo.seed").source, Source::Env { var: "OLO_MONTE_CARLO_SEED".to_string() });
+        assert_eq!(entry("monte_carlo.shock_size").source, Source::File { path: path.clone() });
+        assert_eq!(entry("monte_carlo.num_threads").source, Source::Default);
+        assert_eq!(entry("network.identity_path").value, None);
+
+        fs::write(&path, "[monte_carlo]\nsede = 3\n").unwrap();
+        let error = Settings::load(Some(&path), |_| None).unwrap_err();
+        assert!(error.to_string().ends_with("unknown setting monte_carlo.sede"), "{}", error);
+        let error = Settings::load(None, |_| Some("-1".to_string())).unwrap_err();
+        assert!(error.to_string().contains("num_simulations (from env OLO_MONTE_CARLO_NUM_SIMULATIONS) must be a non-negative"), "{}", error);
+        fs::remove_file(&path).unwrap();
+    }
+}
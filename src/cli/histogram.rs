@@ -0,0 +1,66 @@
+//! Distribution Chart
+//!
+//! The `simulate --histogram` view: the fragility distribution as a bar
+//! per bin, then a table of its percentiles. Bars fill the width of the
+//! terminal, taken from `COLUMNS` if set, else from the terminal stdout is
+//! attached to, else `FALLBACK_COLUMNS`.
+
+use olo_core::Histogram;
+
+/// Percentiles of the table, with their labels
+pub const TABLE_PERCENTILES: [(f64, &str); 6] =
+    [(0.5, "50th"), (0.75, "75th"), (0.9, "90th"), (0.95, "95th"), (0.99, "99th"), (0.999, "99.9th")];
+
+/// Columns assumed when stdout is not a terminal
+pub const FALLBACK_COLUMNS: usize = 80;
+
+/// Columns of a bin's label and count, before its bar
+const LABEL_COLUMNS: usize = 28;
+
+/// Narrowest bar drawn, however narrow the terminal
+const MIN_BAR: usize = 10;
+
+/// Columns to render for
+pub fn terminal_columns() -> usize {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|columns| columns.trim().parse().ok()) {
+        return columns;
+    }
+    terminal_size::terminal_size().map_or(FALLBACK_COLUMNS, |(terminal_size::Width(width), _)| width as usize)
+}
+
+/// The chart of `histogram` and the table of `percentiles`, fragilities at
+/// the labelled percentiles, for a terminal `columns` wide
+pub fn render(histogram: &Histogram, percentiles: &[(&str, f64)], columns: usize) -> String {
+    let mut out = histogram.render_ascii(columns.saturating_sub(LABEL_COLUMNS).max(MIN_BAR));
+    out.push('\n');
+    out.push_str(&format!("{:>10}  {:>9}\n", "Percentile", "Fragility"));
+    for (label, fragility) in percentiles {
+        out.push_str(&format!("{:>10}  {:>9.4}\n", label, fragility));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_snapshot() {
+        let histogram =
+            Histogram { edges: vec![0.0, 10.0, 20.0, 30.0], counts: vec![2, 8, 4], underflow: 0, overflow: 1 };
+        let percentiles = [("50th", 15.25), ("99.9th", 31.5)];
+        let expected = "\
+[   0.00,   10.00)        2 #####
+[  10.00,   20.00)        8 ####################
+[  20.00,   30.00)        4 ##########
+         >= 30.00        1
+
+Percentile  Fragility
+      50th    15.2500
+    99.9th    31.5000
+";
+        assert_eq!(render(&histogram, &percentiles, 48), expected);
+        // Bars keep a minimum length on narrow terminals
+        assert!(render(&histogram, &percentiles, 20).contains("8 ##########\n"));
+    }
+}
@@ -0,0 +1,10 @@
+//! Building blocks of the `olo` binary
+
+pub mod batch;
+pub mod compare;
+pub mod config;
+pub mod histogram;
+pub mod node;
+pub mod proof;
+pub mod simulate;
+pub mod state;
@@ -0,0 +1,324 @@
+//! Node Subcommand
+//!
+//! Runs an ingestion engine until Ctrl-C: listens, dials the bootstrap
+//! peers, and logs what arrives. With `--format json` every log line is a
+//! JSON object with an `event` field, one per line. On Ctrl-C the engine
+//! shuts down gracefully, flushing what its senders still had queued.
+//!
+//! With `--publish-file`, the node publishes the states in an NDJSON file
+//! of `BankState`s, one per `--interval`, in file order. The file is read
+//! again on every tick, so states appended while the node runs are
+//! published too; once every line is published the node waits for more.
+
+use clap::Args;
+use olo_core::{
+    compute_fragility, BankState, BootstrapOutcome, DataPacket, EngineBuilder, EngineEvent, IngestionEngine,
+    LagrangianConfig, NetworkConfig, TopicConfig,
+};
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cli::config::Settings;
+use crate::OutputFormat;
+
+/// Longest a shutdown spends flushing queued packets
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Args)]
+pub struct NodeArgs {
+    /// Address to listen on [default: /ip4/0.0.0.0/tcp/0]
+    #[arg(long)]
+    pub listen: Option<String>,
+    /// Peer to dial at startup, as a multiaddr; repeat for several
+    #[arg(long)]
+    pub bootstrap: Vec<String>,
+    /// Topic to subscribe to; repeat for several, the first is published to [default: olo-fragility]
+    #[arg(long)]
+    pub topic: Vec<String>,
+    /// Keyfile holding the node's identity, created if missing (default: a new identity each run)
+    #[arg(long)]
+    pub identity: Option<PathBuf>,
+    /// Log file keeping packets across restarts (default: in memory)
+    #[arg(long)]
+    pub store: Option<PathBuf>,
+    /// NDJSON file of bank states to publish, one per interval
+    #[arg(long)]
+    pub publish_file: Option<PathBuf>,
+    /// Time between publishes from --publish-file, such as 500ms, 60s, 5m or 1h
+    #[arg(long, default_value = "60s", value_parser = parse_duration, requires = "publish_file")]
+    pub interval: Duration,
+}
+
+/// `value` as a count of `ms`, `s`, `m` or `h`
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (count, unit) = value.split_at(split);
+    let count: u64 = count.parse().map_err(|_| format!("no count in duration {:?}", value))?;
+    let seconds = |scale: u64| count.checked_mul(scale).map(Duration::from_secs);
+    let duration = match unit {
+        "ms" => Some(Duration::from_millis(count)),
+        "s" => Some(Duration::from_secs(count)),
+        "m" => seconds(60),
+        "h" => seconds(3600),
+        _ => return Err(format!("unit of {:?} is not ms, s, m or h", value)),
+    };
+    let duration = duration.ok_or_else(|| format!("duration {:?} is too long", value))?;
+    if duration.is_zero() {
+        return Err("duration must be above 0".to_string());
+    }
+    Ok(duration)
+}
+
+/// What the node logs, as NDJSON under `--format json`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum NodeLog {
+    Started { peer_id: String, listen_addrs: Vec<String> },
+    Bootstrap { addr: String, connected: bool, detail: String },
+    PacketReceived { topic: String, source: String, timestamp: u64, fragility: f64, state: BankState },
+    Published { timestamp: u64, fragility: f64 },
+    PublishFailed { error: String },
+    PeerConnected { peer: String },
+    PeerDisconnected { peer: String },
+    PacketRejected { reason: String },
+    Stopped { flushed: usize, dropped: usize },
+}
+
+impl NodeLog {
+    /// Log the event as JSON on stdout, or as text: the node's identity and
+    /// packets on stdout, everything else on stderr
+    fn emit(&self, format: OutputFormat) {
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string(self).expect("log events are always serializable"));
+            return;
+        }
+        match self {
+            NodeLog::Started { peer_id, listen_addrs } => {
+                println!("Local Peer ID: {}", peer_id);
+                for addr in listen_addrs {
+                    println!("Listening on {}/p2p/{}", addr, peer_id);
+                }
+            }
+            NodeLog::PacketReceived { topic, source, fragility, .. } => {
+                println!("[{}] fragility {:.4} from {}", topic, fragility, source)
+            }
+            NodeLog::Bootstrap { addr, connected: true, detail } => eprintln!("Bootstrap {}: {}", addr, detail),
+            NodeLog::Bootstrap { addr, connected: false, detail } => {
+                eprintln!("Bootstrap {} failed: {}", addr, detail)
+            }
+            NodeLog::Published { fragility, .. } => eprintln!("Published fragility {:.4}", fragility),
+            NodeLog::PublishFailed { error } => eprintln!("Publish failed: {}", error),
+            NodeLog::PeerConnected { peer } => eprintln!("Connected to {}", peer),
+            NodeLog::PeerDisconnected { peer } => eprintln!("Disconnected from {}", peer),
+            NodeLog::PacketRejected { reason } => eprintln!("Rejected packet: {}", reason),
+            NodeLog::Stopped { flushed, dropped } => {
+                eprintln!("Stopped ({} queued packets flushed, {} dropped)", flushed, dropped)
+            }
+        }
+    }
+
+    /// Log line for an engine event, if it is one worth logging
+    fn of_event(event: EngineEvent) -> Option<Self> {
+        Some(match event {
+            EngineEvent::PacketReceived { topic, packet } | EngineEvent::DeltaApplied { topic, packet } => {
+                NodeLog::PacketReceived {
+                    topic,
+                    source: packet.source,
+                    timestamp: packet.timestamp,
+                    fragility: packet.fragility,
+                    state: packet.state,
+                }
+            }
+            EngineEvent::PublishFailed { error } => NodeLog::PublishFailed { error },
+            EngineEvent::PeerConnected(peer) => NodeLog::PeerConnected { peer: peer.to_string() },
+            EngineEvent::PeerDisconnected(peer) => NodeLog::PeerDisconnected { peer: peer.to_string() },
+            EngineEvent::PacketRejected { reason } => NodeLog::PacketRejected { reason },
+            _ => return None,
+        })
+    }
+}
+
+/// States of an NDJSON file, published one at a time in file order
+#[derive(Debug)]
+pub struct PublishFeed {
+    path: PathBuf,
+    /// Scores the published states
+    config: LagrangianConfig,
+    /// Lines of the file already published, blank ones included
+    published: usize,
+}
+
+impl PublishFeed {
+    pub fn new(path: impl Into<PathBuf>, config: LagrangianConfig) -> Self {
+        Self { path: path.into(), config, published: 0 }
+    }
+
+    /// Next unpublished state in the file, if any, skipping blank lines
+    fn next_state(&mut self) -> Result<Option<BankState>, Box<dyn Error>> {
+        let text = fs::read_to_string(&self.path).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        // Only whole lines; a writer may be partway through the last one
+        let complete = text.rfind('\n').map_or("", |end| &text[..=end]);
+        for line in complete.lines().skip(self.published) {
+            self.published += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let state = serde_json::from_str(line)
+                .map_err(|e| format!("{}: line {}: {}", self.path.display(), self.published, e))?;
+            return Ok(Some(state));
+        }
+        Ok(None)
+    }
+
+    /// Sign and publish the next state as a packet from `engine`, returning
+    /// it, or `None` if every state in the file is published
+    pub async fn publish_next(&mut self, engine: &mut IngestionEngine) -> Result<Option<DataPacket>, Box<dyn Error>> {
+        let Some(state) = self.next_state()? else {
+            return Ok(None);
+        };
+        let packet = DataPacket {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
+            source: engine.local_peer_id().to_string(),
+            fragility: compute_fragility(&state, &self.config),
+            state,
+            signature: Vec::new(),
+            proof: None,
+            period: None,
+            envelope: None,
+            expires_at: None,
+        };
+        engine.publish(packet.clone()).await?;
+        Ok(Some(packet))
+    }
+}
+
+/// Engine configured by `base` with the flags in `args` applied
+fn build_engine(args: &NodeArgs, base: &NetworkConfig) -> Result<IngestionEngine, Box<dyn Error>> {
+    let mut builder = EngineBuilder::from_config(base.clone());
+    if let Some(addr) = &args.listen {
+        builder = builder.listen_addr(addr);
+    }
+    if !args.bootstrap.is_empty() {
+        builder = builder.bootstrap_peers(&args.bootstrap);
+    }
+    if !args.topic.is_empty() {
+        builder = builder.topics(args.topic.iter().map(TopicConfig::new));
+    }
+    if let Some(path) = &args.identity {
+        builder = builder.identity_path(path);
+    }
+    if let Some(path) = &args.store {
+        builder = builder.store_path(path);
+    }
+    Ok(builder.build()?)
+}
+
+/// Run a node until Ctrl-C
+pub async fn run(args: &NodeArgs, settings: &Settings, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let mut engine = build_engine(args, &settings.network)?;
+    let listen_addrs = engine.listen_configured().await?;
+    NodeLog::Started {
+        peer_id: engine.local_peer_id().to_string(),
+        listen_addrs: listen_addrs.iter().map(ToString::to_string).collect(),
+    }
+    .emit(format);
+
+    for (addr, outcome) in engine.connect_bootstrap().await.outcomes {
+        let (connected, detail) = match outcome {
+            BootstrapOutcome::Connected { peer, attempts } => {
+                (true, format!("connected to {} after {} attempts", peer, attempts))
+            }
+            BootstrapOutcome::InvalidAddress { reason } => (false, reason),
+            BootstrapOutcome::Unreachable { attempts, error } => {
+                (false, format!("unreachable after {} attempts: {}", attempts, error))
+            }
+        };
+        NodeLog::Bootstrap { addr, connected, detail }.emit(format);
+    }
+
+    let mut feed = args.publish_file.as_deref().map(|path| PublishFeed::new(path, settings.lagrangian.clone()));
+    let mut ticks = tokio::time::interval(args.interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        tokio::select! {
+            event = engine.next_event() => {
+                if let Some(log) = NodeLog::of_event(event) {
+                    log.emit(format);
+                }
+            }
+            _ = ticks.tick(), if feed.is_some() => {
+                let feed = feed.as_mut().expect("ticks only with a feed");
+                match feed.publish_next(&mut engine).await {
+                    Ok(Some(packet)) => {
+                        NodeLog::Published { timestamp: packet.timestamp, fragility: packet.fragility }.emit(format)
+                    }
+                    Ok(None) => {}
+                    Err(e) => NodeLog::PublishFailed { error: e.to_string() }.emit(format),
+                }
+            }
+            signal = &mut ctrl_c => {
+                signal?;
+                break;
+            }
+        }
+    }
+
+    let report = engine.shutdown(SHUTDOWN_TIMEOUT).await;
+    NodeLog::Stopped { flushed: report.flushed, dropped: report.dropped }.emit(format);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("60s"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("60").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX / 60)).is_err());
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[tokio::test]
+    async fn test_publish_feed_follows_the_file() {
+        use olo_core::network::testing::TestMesh;
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("olo-feed-{}.ndjson", std::process::id()));
+        let state = r#"{"tier1_capital": 10000, "total_assets": 100000, "liquidity_coverage": 1.2, "entropy_index": 2.5}"#;
+        fs::write(&path, format!("{}\n\n{}\n{{\"tier1_capital\"", state, state)).unwrap();
+
+        let mut mesh = TestMesh::new(2).await.unwrap();
+        let mut feed = PublishFeed::new(&path, LagrangianConfig::default());
+        for _ in 0..2 {
+            assert!(feed.publish_next(mesh.engine_mut(0)).await.unwrap().is_some());
+            // Peers drop a second packet from the same signer stamped the same millisecond
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        // The partial last line waits until it is finished
+        assert!(feed.publish_next(mesh.engine_mut(0)).await.unwrap().is_none());
+        let received = mesh.collect_on(1, Duration::from_secs(2)).await;
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].source, mesh.peer_id(0).to_string());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, r#": 1, "total_assets": 2, "liquidity_coverage": 1, "entropy_index": 0}}"#).unwrap();
+        writeln!(file, "not json").unwrap();
+        let packet = feed.publish_next(mesh.engine_mut(0)).await.unwrap().unwrap();
+        assert_eq!(packet.state.tier1_capital, 1.0);
+        let error = feed.publish_next(mesh.engine_mut(0)).await.unwrap_err();
+        assert!(error.to_string().contains("line 5"), "{}", error);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+//! Proof Subcommands
+//!
+//! `prove` attests to a bank state's fragility score with a Groth16 proof,
+//! written as a JSON `ProofEnvelope`; `verify` checks such an envelope
+//! against exported verifying keys. Proving parameters are read from the
+//! `--params` file, or generated and saved there on first use, so every
+//! later proof made with the file verifies under the same keys.
+//!
+//! Progress and timings go to stderr. `verify` exits 0 for a valid proof
+//! and 1 for anything else, invalid or unreadable alike.
+
+use clap::Args;
+use olo_core::{
+    reference_fragility, validate_witness, BankState, CircuitId, FixedPoint, FragilityProver, FragilityVerifier,
+    LagrangianConfig, ProofEnvelope,
+};
+use serde::Serialize;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::{OutputFormat, Report};
+
+/// How far `--expect-fragility` may be from the attested score: half the
+/// last of the four decimals `prove` prints
+const EXPECT_TOLERANCE: f64 = 0.5e-4;
+
+#[derive(Debug, Clone, Args)]
+pub struct ProveArgs {
+    /// JSON file holding the bank state to prove
+    #[arg(long)]
+    pub input: PathBuf,
+    /// Proving parameters; generated and saved here if the file is missing
+    #[arg(long)]
+    pub params: PathBuf,
+    /// Where to write the proof envelope (JSON)
+    #[arg(long)]
+    pub out: PathBuf,
+    /// Also export the verifying keys here, for `olo verify --vk`
+    #[arg(long)]
+    pub vk: Option<PathBuf>,
+    /// Reporting period to bind the proof to (not 0)
+    #[arg(long)]
+    pub period: Option<u64>,
+    /// Identifier of the prover recorded in the envelope
+    #[arg(long, default_value = "olo-cli")]
+    pub prover: String,
+    /// Generate missing parameters from this seed; anyone knowing it can forge proofs
+    #[arg(long, hide = true)]
+    pub insecure_test_seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct VerifyArgs {
+    /// Proof envelope written by `olo prove`
+    #[arg(long)]
+    pub proof: PathBuf,
+    /// Verifying keys exported by `olo prove --vk`
+    #[arg(long)]
+    pub vk: PathBuf,
+    /// Require the proof to attest to this fragility score, to the four
+    /// decimals `prove` prints
+    #[arg(long)]
+    pub expect_fragility: Option<f64>,
+    /// Reporting period the proof must be bound to
+    #[arg(long)]
+    pub period: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ProveOutputs {
+    fragility: f64,
+    proof: PathBuf,
+    vk: Option<PathBuf>,
+    params_fingerprint: String,
+    proving_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct VerifyInputs<'a> {
+    proof: &'a Path,
+    vk: &'a Path,
+    expect_fragility: Option<f64>,
+    period: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct VerifyOutputs {
+    valid: bool,
+    /// Why the proof was rejected
+    reason: Option<String>,
+    circuit: Option<CircuitId>,
+    fragility: Option<f64>,
+    period: Option<u64>,
+}
+
+/// Proving parameters at `path`, generated and saved there if missing
+fn load_or_setup(path: &Path, insecure_seed: Option<u64>) -> Result<FragilityProver, Box<dyn Error>> {
+    let Some(seed) = insecure_seed else {
+        return Ok(FragilityProver::load_or_setup(path)?);
+    };
+    if path.exists() {
+        return Ok(FragilityProver::load_or_setup(path)?);
+    }
+    #[cfg(feature = "insecure-test-setup")]
+    {
+        let prover = FragilityProver::setup_deterministic(seed);
+        // Write to a sibling, then rename, so readers never see a partial file
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut bytes = Vec::new();
+        prover.save_params(&mut bytes)?;
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(prover)
+    }
+    #[cfg(not(feature = "insecure-test-setup"))]
+    {
+        Err(format!("--insecure-test-seed {} needs a build with the insecure-test-setup feature", seed).into())
+    }
+}
+
+pub fn prove(args: &ProveArgs, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let state: BankState = serde_json::from_reader(BufReader::new(File::open(&args.input)?))
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+    validate_witness(&state)?;
+    let fragility = reference_fragility(&state, &LagrangianConfig::default())?;
+
+    let started = Instant::now();
+    eprintln!("Loading proving parameters from {}...", args.params.display());
+    let prover = load_or_setup(&args.params, args.insecure_test_seed)?;
+    eprintln!("  ready in {:.2?}", started.elapsed());
+
+    eprintln!("Proving fragility {:.4}...", fragility);
+    let envelope = prover.prove_enveloped(&state, fragility, &args.prover, args.period)?;
+    eprintln!("  proved in {} ms", envelope.proving_ms.unwrap_or_default());
+
+    fs::write(&args.out, serde_json::to_vec_pretty(&envelope)?)?;
+    if let Some(vk) = &args.vk {
+        fs::write(vk, prover.export_verifying_key())?;
+    }
+
+    let outputs = ProveOutputs {
+        fragility,
+        proof: args.out.clone(),
+        vk: args.vk.clone(),
+        params_fingerprint: hex::encode(prover.params_fingerprint()),
+        proving_ms: envelope.proving_ms,
+    };
+    match format {
+        OutputFormat::Json => {
+            Report { command: "prove", inputs: &state, config: LagrangianConfig::default(), outputs }.print()
+        }
+        OutputFormat::Text => {
+            println!("Proof written to {}", outputs.proof.display());
+            println!("  Fragility Score: {:.4}", outputs.fragility);
+            if let Some(period) = args.period {
+                println!("  Period: {}", period);
+            }
+            if let Some(vk) = &outputs.vk {
+                println!("  Verifying keys: {}", vk.display());
+            }
+            println!("  Parameters: {}", outputs.params_fingerprint);
+            Ok(())
+        }
+    }
+}
+
+/// Whether the envelope at `args.proof` verifies, and if not why not
+fn check(args: &VerifyArgs, envelope: &ProofEnvelope) -> Result<Option<String>, Box<dyn Error>> {
+    let verifier = FragilityVerifier::read(BufReader::new(File::open(&args.vk)?))?;
+    if let Some(expected) = args.expect_fragility {
+        if envelope.circuit != CircuitId::Fragility {
+            return Ok(Some(format!("proof is for the {:?} circuit, not a fragility score", envelope.circuit)));
+        }
+        let attested = envelope.public_inputs.first().map(|units| *units as f64 / FixedPoint::SCALE as f64);
+        if !attested.is_some_and(|attested| (attested - expected).abs() <= EXPECT_TOLERANCE) {
+            return Ok(Some(format!("proof does not attest to fragility {}", expected)));
+        }
+    }
+    Ok(match verifier.verify_envelope(envelope, args.period) {
+        Ok(true) => None,
+        Ok(false) => Some("proof does not check against the verifying keys".to_string()),
+        Err(e) => Some(e.to_string()),
+    })
+}
+
+/// Verify a proof envelope, exiting 1 unless it is valid
+pub fn verify(args: &VerifyArgs, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
+    let envelope: Result<ProofEnvelope, Box<dyn Error>> = File::open(&args.proof)
+        .map_err(Into::into)
+        .and_then(|file| serde_json::from_reader(BufReader::new(file)).map_err(Into::into));
+    let reason = match &envelope {
+        Ok(envelope) => check(args, envelope).unwrap_or_else(|e| Some(e.to_string())),
+        Err(e) => Some(format!("{}: {}", args.proof.display(), e)),
+    };
+    eprintln!("Verified in {:.2?}", started.elapsed());
+
+    let envelope = envelope.ok();
+    let outputs = VerifyOutputs {
+        valid: reason.is_none(),
+        reason,
+        circuit: envelope.as_ref().map(|envelope| envelope.circuit),
+        fragility: envelope
+            .as_ref()
+            .filter(|envelope| envelope.circuit == CircuitId::Fragility)
+            .and_then(|envelope| envelope.public_inputs.first())
+            .map(|&units| units as f64 / FixedPoint::SCALE as f64),
+        period: envelope.as_ref().and_then(|envelope| envelope.period),
+    };
+    match format {
+        OutputFormat::Json => {
+            let inputs = VerifyInputs {
+                proof: &args.proof,
+                vk: &args.vk,
+                expect_fragility: args.expect_fragility,
+                period: args.period,
+            };
+            Report { command: "verify", inputs, config: (), outputs: &outputs }.print()?
+        }
+        OutputFormat::Text => match (&outputs.reason, outputs.fragility) {
+            (None, Some(fragility)) => println!("✅ VALID - Proof attests to fragility score {:.4}", fragility),
+            (None, None) => println!("✅ VALID - Proof checks against the verifying keys"),
+            (Some(reason), _) => println!("❌ INVALID - {}", reason),
+        },
+    }
+    if !outputs.valid {
+        std::process::exit(1);
+    }
+    Ok(())
+}
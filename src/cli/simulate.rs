@@ -0,0 +1,160 @@
+//! Simulation Flags
+//!
+//! Flags of the `simulate` subcommand, each setting one `MonteCarloConfig`
+//! field. Unset flags keep the configured values (see `olo config show`),
+//! else the library defaults. A non-normal
+//! `--distribution` applies to every shock draw, with `--shock-size` as its
+//! scale; the options it needs are required with it and refused without it.
+//!
+//! `--export-csv` writes a row per path as the run goes, from a writer
+//! thread fed over a bounded channel, so adding `--no-store-samples` keeps
+//! memory bounded however many paths there are.
+
+use clap::error::ErrorKind;
+use clap::{Args, ValueEnum};
+use olo_core::{ExportConfig, ExportFormat, MonteCarloConfig, MultiplicativeShock, ShockDistribution, ShockModel};
+use std::path::PathBuf;
+
+/// Shape of the shock draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Distribution {
+    /// N(0, shock size²)
+    Normal,
+    /// Skew-normal with shape `--skew`
+    SkewNormal,
+    /// Student's t on `--df` degrees of freedom, scaled by the shock size
+    StudentT,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SimulationArgs {
+    /// Number of simulated paths [default: 10000]
+    #[arg(short, long, value_parser = positive_count)]
+    pub iterations: Option<usize>,
+    /// Seed of the shock draws; runs with equal seeds give equal results [default: 42]
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Scale of the shock draws, the standard deviation for normal draws [default: 2.0]
+    #[arg(long, value_parser = positive)]
+    pub shock_size: Option<f64>,
+    /// Distribution of every shock draw
+    #[arg(long, value_enum, default_value_t = Distribution::Normal)]
+    pub distribution: Distribution,
+    /// Skew-normal shape: below 0 fattens the left tail, above 0 the right
+    #[arg(long, allow_negative_numbers = true, value_parser = finite, required_if_eq("distribution", "skew-normal"))]
+    pub skew: Option<f64>,
+    /// Student's t degrees of freedom, above 2 so shocks have a finite variance
+    #[arg(long, value_parser = degrees_of_freedom, required_if_eq("distribution", "student-t"))]
+    pub df: Option<f64>,
+    /// Percentiles of the fragility distribution to report, comma-separated
+    #[arg(long, value_delimiter = ',', value_parser = probability, default_values_t = [0.95, 0.99])]
+    pub percentiles: Vec<f64>,
+    /// Worker threads (0 = one per core)
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Keep every path's fragility, for exact statistics (the default)
+    #[arg(long, overrides_with = "no_store_samples")]
+    pub store_samples: bool,
+    /// Keep only running statistics and a quantile sketch, in bounded memory
+    #[arg(long, overrides_with = "store_samples")]
+    pub no_store_samples: bool,
+    /// Write each path's fragility to this CSV file, created or truncated
+    #[arg(long, value_name = "PATH")]
+    pub export_csv: Option<PathBuf>,
+    /// Also write each path's shock draws to the --export-csv file
+    #[arg(long, requires = "export_csv")]
+    pub export_shocks: bool,
+}
+
+pub(crate) fn positive_count(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(count) => Ok(count),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn finite(value: &str) -> Result<f64, String> {
+    let number: f64 = value.parse().map_err(|e: std::num::ParseFloatError| e.to_string())?;
+    if number.is_finite() {
+        Ok(number)
+    } else {
+        Err("must be a finite number".to_string())
+    }
+}
+
+fn positive(value: &str) -> Result<f64, String> {
+    finite(value).and_then(|number| if number > 0.0 { Ok(number) } else { Err("must be above 0".to_string()) })
+}
+
+fn degrees_of_freedom(value: &str) -> Result<f64, String> {
+    finite(value).and_then(|df| {
+        if df > 2.0 {
+            Ok(df)
+        } else {
+            Err("degrees of freedom must be above 2 for a finite variance".to_string())
+        }
+    })
+}
+
+fn probability(value: &str) -> Result<f64, String> {
+    finite(value).and_then(|p| if p > 0.0 && p < 1.0 { Ok(p) } else { Err("must be between 0 and 1".to_string()) })
+}
+
+impl SimulationArgs {
+    /// `base` with the flags given applied
+    ///
+    /// Errors are unformatted; `clap::Error::format` them with the command.
+    pub fn to_config(&self, base: &MonteCarloConfig) -> Result<MonteCarloConfig, clap::Error> {
+        let conflict = |flag: &str| {
+            clap::Error::raw(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "{} does not apply to --distribution {}\n",
+                    flag,
+                    self.distribution.to_possible_value().expect("no variant is skipped").get_name()
+                ),
+            )
+        };
+        if self.skew.is_some() && self.distribution != Distribution::SkewNormal {
+            return Err(conflict("--skew"));
+        }
+        if self.df.is_some() && self.distribution != Distribution::StudentT {
+            return Err(conflict("--df"));
+        }
+
+        let shock_size = self.shock_size.unwrap_or(base.shock_size);
+        let distribution = match self.distribution {
+            // N(0, shock_size) is what no distributions at all mean
+            Distribution::Normal => None,
+            Distribution::SkewNormal => Some(ShockDistribution::SkewNormal {
+                location: 0.0,
+                scale: shock_size,
+                shape: self.skew.expect("clap requires --skew"),
+            }),
+            Distribution::StudentT => Some(ShockDistribution::StudentT {
+                location: 0.0,
+                scale: shock_size,
+                df: self.df.expect("clap requires --df"),
+            }),
+        };
+        Ok(MonteCarloConfig {
+            num_simulations: self.iterations.unwrap_or(base.num_simulations),
+            seed: self.seed.unwrap_or(base.seed),
+            shock_size,
+            num_threads: self.threads.unwrap_or(base.num_threads),
+            shock_distributions: distribution
+                .map_or_else(Vec::new, |distribution| vec![distribution; MultiplicativeShock.dimension()]),
+            store_samples: match (self.store_samples, self.no_store_samples) {
+                (true, _) => true,
+                (_, true) => false,
+                _ => base.store_samples,
+            },
+            export: match &self.export_csv {
+                Some(path) => Some(ExportConfig::new(path, ExportFormat::Csv).with_draws(self.export_shocks)),
+                None => base.export.clone(),
+            },
+            ..base.clone()
+        })
+    }
+}
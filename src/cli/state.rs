@@ -0,0 +1,141 @@
+//! Bank State Flags
+//!
+//! The `fragility` and `simulate` subcommands take a `BankState` as its four
+//! regulatory fields. Older scripts passed balance sheet figures instead;
+//! `--balance-sheet` still accepts them, mapped onto the state as the
+//! balance sheet circuit reads it:
+//!
+//! | Balance sheet   | `BankState`                                          |
+//! |-----------------|------------------------------------------------------|
+//! | `--equity`      | `tier1_capital`                                      |
+//! | `--assets`      | `total_assets`                                       |
+//! | `--liabilities` | not carried; must equal assets minus equity          |
+//! | `--leverage`    | not carried; if given, must equal assets over equity |
+//!
+//! A balance sheet says nothing of liquidity or diversification, so
+//! `--liquidity-coverage` defaults to 1.0, the Basel III minimum, and
+//! `--entropy-index` to 0.0 in that mode.
+
+use clap::error::ErrorKind;
+use clap::Args;
+use olo_core::BankState;
+use serde::Serialize;
+
+/// Relative tolerance of the balance sheet consistency checks
+const BALANCE_TOLERANCE: f64 = 1e-6;
+
+/// Liquidity coverage assumed for a balance sheet
+const DEFAULT_LIQUIDITY_COVERAGE: f64 = 1.0;
+
+/// Entropy index assumed for a balance sheet
+const DEFAULT_ENTROPY_INDEX: f64 = 0.0;
+
+/// Flags describing one bank state
+#[derive(Debug, Clone, Args)]
+pub struct StateArgs {
+    /// Tier 1 (CET1) capital: common equity available to absorb losses
+    #[arg(short = 'c', long)]
+    pub tier1_capital: Option<f64>,
+    /// Total risk-weighted assets, in the same currency as the capital
+    #[arg(short = 'a', long)]
+    pub total_assets: Option<f64>,
+    /// Liquidity coverage ratio: liquid assets over 30-day net outflows (1.0 = Basel III minimum)
+    #[arg(short = 'l', long)]
+    pub liquidity_coverage: Option<f64>,
+    /// Shannon entropy of the asset mix, in bits (see `olo entropy`)
+    #[arg(short = 'e', long)]
+    pub entropy_index: Option<f64>,
+
+    /// Give the state as balance sheet figures (--assets, --liabilities, --equity) instead
+    #[arg(
+        long,
+        conflicts_with_all = ["tier1_capital", "total_assets"],
+        requires_all = ["assets", "liabilities", "equity"]
+    )]
+    pub balance_sheet: bool,
+    /// Balance sheet total assets, read as the total risk-weighted assets
+    #[arg(long, requires = "balance_sheet")]
+    pub assets: Option<f64>,
+    /// Balance sheet liabilities; must equal assets minus equity
+    #[arg(long, requires = "balance_sheet")]
+    pub liabilities: Option<f64>,
+    /// Balance sheet equity, read as Tier 1 capital
+    #[arg(long, requires = "balance_sheet")]
+    pub equity: Option<f64>,
+    /// Leverage, assets over equity; checked against the others if given
+    #[arg(long, requires = "balance_sheet")]
+    pub leverage: Option<f64>,
+}
+
+/// Balance sheet figures a state was converted from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BalanceSheet {
+    pub assets: f64,
+    pub liabilities: f64,
+    pub equity: f64,
+    pub leverage: f64,
+}
+
+/// Whether `actual` is within `BALANCE_TOLERANCE` of `expected`, relative to the larger
+fn close(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() <= BALANCE_TOLERANCE * actual.abs().max(expected.abs()).max(1.0)
+}
+
+impl StateArgs {
+    /// The state the flags describe, and the balance sheet it came from in
+    /// `--balance-sheet` mode
+    ///
+    /// Errors are unformatted; `clap::Error::format` them with the command.
+    pub fn to_state(&self) -> Result<(BankState, Option<BalanceSheet>), clap::Error> {
+        if self.balance_sheet {
+            return self.balance_sheet_state().map(|(state, sheet)| (state, Some(sheet)));
+        }
+        let required = |value: Option<f64>, flag: &str| {
+            value.ok_or_else(|| {
+                clap::Error::raw(
+                    ErrorKind::MissingRequiredArgument,
+                    format!("{} is required (or give --balance-sheet)\n", flag),
+                )
+            })
+        };
+        let state = BankState {
+            tier1_capital: required(self.tier1_capital, "--tier1-capital")?,
+            total_assets: required(self.total_assets, "--total-assets")?,
+            liquidity_coverage: required(self.liquidity_coverage, "--liquidity-coverage")?,
+            entropy_index: required(self.entropy_index, "--entropy-index")?,
+        };
+        Ok((state, None))
+    }
+
+    fn balance_sheet_state(&self) -> Result<(BankState, BalanceSheet), clap::Error> {
+        let (Some(assets), Some(liabilities), Some(equity)) = (self.assets, self.liabilities, self.equity) else {
+            unreachable!("clap requires the balance sheet figures with --balance-sheet");
+        };
+        let invalid = |message: String| clap::Error::raw(ErrorKind::ValueValidation, message + "\n");
+        if !close(liabilities, assets - equity) {
+            return Err(invalid(format!(
+                "balance sheet does not balance: assets {} minus equity {} is {}, not liabilities {}",
+                assets,
+                equity,
+                assets - equity,
+                liabilities
+            )));
+        }
+        let implied_leverage = assets / equity;
+        if let Some(leverage) = self.leverage {
+            if !close(leverage, implied_leverage) {
+                return Err(invalid(format!(
+                    "--leverage {} does not match assets over equity, {}",
+                    leverage, implied_leverage
+                )));
+            }
+        }
+        let state = BankState {
+            tier1_capital: equity,
+            total_assets: assets,
+            liquidity_coverage: self.liquidity_coverage.unwrap_or(DEFAULT_LIQUIDITY_COVERAGE),
+            entropy_index: self.entropy_index.unwrap_or(DEFAULT_ENTROPY_INDEX),
+        };
+        Ok((state, BalanceSheet { assets, liabilities, equity, leverage: implied_leverage }))
+    }
+}
@@ -3,17 +3,17 @@
 //! Measures information diversity in portfolio allocations using Shannon entropy.
 //! Higher entropy = more diversified portfolio = lower concentration risk.
 
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 
 /// Portfolio position with weight
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub asset: String,
     pub weight: f64,
 }
 
 /// Entropy configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntropyConfig {
     /// Minimum weight threshold (ignore positions below this)
     pub min_weight: f64,
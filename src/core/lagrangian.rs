@@ -4,7 +4,6 @@
 //! This module implements the Omni-Lagrangian Fragility Score using exponential barrier functions
 //! and thermodynamic entropy penalties.
 
-use ndarray::{Array2, Array1};
 use serde::{Deserialize, Serialize};
 
 /// Bank state vector containing regulatory metrics
@@ -25,7 +24,7 @@ pub struct BankState {
 }
 
 /// Configuration for Lagrangian multiplier calculation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LagrangianConfig {
     /// Lambda sensitivity parameter - controls stress spike rate
     /// Higher values = faster exponential growth as constraints approach violation
@@ -81,6 +80,41 @@ impl Default for LagrangianConfig {
 /// let fragility = compute_fragility(&bank, &config);
 /// ```
 pub fn compute_fragility(bank: &BankState, config: &LagrangianConfig) -> f64 {
+    fragility_components(bank, config).score()
+}
+
+/// Stress components the fragility score sums before normalization
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FragilityComponents {
+    /// Lagrangian multiplier λ of the capital constraint
+    pub capital: f64,
+    /// Entropy penalty of the asset mix
+    pub entropy: f64,
+    /// Liquidity stress from the coverage ratio
+    pub liquidity: f64,
+}
+
+impl FragilityComponents {
+    /// Composite raw score, the sum of the components
+    pub fn raw(&self) -> f64 {
+        self.capital + self.entropy + self.liquidity
+    }
+
+    /// Fragility score of the components, as `compute_fragility` gives it
+    pub fn score(&self) -> f64 {
+        // STEP 6: Sigmoid Normalization to [0, 100]
+        // Maps (0, ∞) → (0, 100) using logistic function
+        // This ensures interpretable scores regardless of input magnitudes
+        let raw_score = self.raw();
+        let normalized_score = 100.0 * (raw_score / (raw_score + 50.0));
+
+        // Clamp to valid range (defensive programming)
+        normalized_score.clamp(0.0, 100.0)
+    }
+}
+
+/// The stress components of a bank's fragility score
+pub fn fragility_components(bank: &BankState, config: &LagrangianConfig) -> FragilityComponents {
     // STEP 1: Calculate Capital Constraint Distance g(x)
     // Constraint: tier1_capital >= regulatory_min * total_assets
     // If violated (distance < 0), bank is technically insolvent
@@ -95,7 +129,7 @@ pub fn compute_fragility(bank: &BankState, config: &LagrangianConfig) -> f64 {
         1000.0
     } else {
         // Exponential barrier: stress spikes as constraint approaches
-        config.lambda_sensitivity * (-1.0 * constraint_distance).exp()
+        config.lambda_sensitivity * (-constraint_distance).exp()
     };
 
     // STEP 3: Thermodynamic Entropy Penalty
@@ -110,16 +144,54 @@ pub fn compute_fragility(bank: &BankState, config: &LagrangianConfig) -> f64 {
     let liquidity_stress = (1.0 / bank.liquidity_coverage) * 10.0;
 
     // STEP 5: Composite Raw Score
-    // Sum all stress components
-    let raw_score = lambda + entropy_penalty + liquidity_stress;
-    
-    // STEP 6: Sigmoid Normalization to [0, 100]
-    // Maps (0, ∞) → (0, 100) using logistic function
-    // This ensures interpretable scores regardless of input magnitudes
-    let normalized_score = 100.0 * (raw_score / (raw_score + 50.0));
-    
-    // Clamp to valid range (defensive programming)
-    normalized_score.max(0.0).min(100.0)
+    // Sum all stress components (see `FragilityComponents::raw`)
+    FragilityComponents {
+        capital: lambda,
+        entropy: entropy_penalty,
+        liquidity: liquidity_stress,
+    }
+}
+
+/// Change in fragility score between two bank states, split among the
+/// stress components that caused it
+///
+/// The score is not linear in the components, so each is credited with
+/// the score change per unit of raw score times its own change. The
+/// contributions then add up to `delta` exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FragilityAttribution {
+    /// Score of the earlier state
+    pub before: f64,
+    /// Score of the later state
+    pub after: f64,
+    /// `after - before`; positive when fragility worsened
+    pub delta: f64,
+    /// Points of `delta` each component accounts for
+    pub contributions: FragilityComponents,
+}
+
+/// Attribute the change in fragility from `before` to `after` to its components
+pub fn attribute_fragility(before: &BankState, after: &BankState, config: &LagrangianConfig) -> FragilityAttribution {
+    let (from, to) = (fragility_components(before, config), fragility_components(after, config));
+    let delta = to.score() - from.score();
+    let raw_delta = to.raw() - from.raw();
+    // Score change per unit of raw score; its derivative when the raw score
+    // did not move, so offsetting changes are still shown
+    let slope = if raw_delta.abs() > f64::EPSILON * from.raw().abs().max(1.0) {
+        delta / raw_delta
+    } else {
+        100.0 * 50.0 / (from.raw() + 50.0).powi(2)
+    };
+    FragilityAttribution {
+        before: from.score(),
+        after: to.score(),
+        delta,
+        contributions: FragilityComponents {
+            capital: slope * (to.capital - from.capital),
+            entropy: slope * (to.entropy - from.entropy),
+            liquidity: slope * (to.liquidity - from.liquidity),
+        },
+    }
 }
 
 /// Calculate capital adequacy ratio (CAR)
@@ -183,4 +255,28 @@ mod tests {
         let car = capital_adequacy_ratio(&bank);
         assert_eq!(car, 0.10);
     }
+
+    #[test]
+    fn test_attribution_adds_up_to_the_score_change() {
+        let before = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 5.0,
+            entropy_index: 0.5,
+        };
+        let after = BankState { liquidity_coverage: 1.2, entropy_index: 2.5, ..before.clone() };
+        let config = LagrangianConfig::default();
+
+        let attribution = attribute_fragility(&before, &after, &config);
+        assert_eq!(attribution.before, compute_fragility(&before, &config));
+        assert_eq!(attribution.after, compute_fragility(&after, &config));
+        let contributions = attribution.contributions;
+        assert!((contributions.raw() - attribution.delta).abs() < 1e-9);
+        assert_eq!(contributions.capital, 0.0);
+        assert!(contributions.liquidity > contributions.entropy && contributions.entropy > 0.0);
+
+        let unchanged = attribute_fragility(&before, &before, &config);
+        assert_eq!(unchanged.delta, 0.0);
+        assert_eq!(unchanged.contributions.raw(), 0.0);
+    }
 }
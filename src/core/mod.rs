@@ -8,4 +8,4 @@ pub mod entropy;
 
 // Re-export key types
 pub use lagrangian::{BankState, LagrangianConfig, compute_fragility};
-pub use entropy::{calculate_portfolio_entropy, EntropyConfig};
+pub use entropy::{calculate_entropy, EntropyConfig};
@@ -9,11 +9,70 @@ pub mod proofs;
 pub mod network;
 
 // Re-export key types
-pub use core::lagrangian::{BankState, LagrangianConfig, compute_fragility};
+pub use core::lagrangian::{BankState, FragilityAttribution, FragilityComponents, LagrangianConfig, attribute_fragility, compute_fragility, fragility_components};
 pub use core::entropy::{Position, EntropyConfig, calculate_entropy, concentration_risk};
-pub use simulation::monte_carlo::{MonteCarloConfig, SimulationResult, run_simulation};
-pub use proofs::prover::{FragilityProver, FragilityCircuit};
-pub use network::ingestion::{IngestionEngine, NetworkConfig, DataPacket};
+pub use simulation::monte_carlo::{MonteCarloConfig, SimulationResult, estimate_memory_bytes, exceedance_curve, run_simulation, run_simulation_with_model, threshold_for_exceedance, try_run_simulation};
+pub use simulation::checkpoint::{CheckpointConfig, CheckpointOutcome, run_simulation_checkpointed, run_simulation_checkpointed_with_model, resume_from_checkpoint, resume_from_checkpoint_with_model};
+pub use simulation::error::SimulationError;
+pub use simulation::shock::{MultiplicativeShock, ShockModel};
+pub use simulation::paths::{GarchVolatility, HorizonVar, PathConfig, PathSimulationResult, VolatilityModel, horizon_var, run_path_simulation};
+pub use simulation::system::{Dependence, SystemSimulationResult, run_system_simulation};
+pub use simulation::sampler::{Sampler, ShockDistribution, StateShock, load_shock_history};
+pub use simulation::variance::VarianceReduction;
+pub use simulation::export::{ExportConfig, ExportFormat};
+pub use simulation::histogram::{Binning, Histogram};
+pub use simulation::shock_set::{ShockSet, generate_shocks, run_simulation_with_shocks};
+pub use simulation::streaming::QuantileSketch;
+pub use simulation::meta::SimulationMeta;
+pub use proofs::circuit::{FragilityCircuit, FragilityWitness, ThresholdCircuit, reference_fragility, state_commitment};
+pub use proofs::entropy_circuit::{EntropyCircuit, EntropyWitness, MAX_POSITIONS, reference_entropy};
+pub use proofs::group_circuit::{GroupSolvencyCircuit, GroupWitness, MAX_SUBSIDIARIES, group_commitment};
+pub use proofs::lcr_circuit::{LcrCircuit, LcrInputs, LcrWitness};
+pub use proofs::balance_circuit::{BalanceSheetCircuit, BalanceSheetProver, BalanceSheetWitness};
+pub use proofs::var_circuit::{VAR_PATHS, VarCircuit, VarProver, VarWitness, reference_var, shock_root, var_rank};
+pub use proofs::prover::{BatchProveConfig, FragilityProver, deserialize_proof, proof_from_hex, proof_to_hex, serialize_proof, validate_witness};
+pub use proofs::verifier::{BatchOutcome, FragilityVerifier, VERIFYING_KEY_VERSION};
+pub use proofs::error::ProofError;
+pub use proofs::encoding::{EncodingError, FixedPoint};
+pub use proofs::envelope::{BackendId, CircuitId, ProofEnvelope};
+pub use proofs::public_inputs::PublicInputs;
+pub use proofs::packet::{PacketVerdict, packet_identity, verify_packet};
+pub use proofs::nullifier::{NullifierSet, nullifier};
+pub use proofs::constraints::{ConstraintViolation, check_constraints};
+pub use proofs::cache::{ProofCache, cache_key};
+pub use proofs::stats::{CircuitStats, ProverStats, StatsSnapshot};
+pub use proofs::ceremony::{Contribution, ContributionHash, Transcript, verify_contribution};
+pub use proofs::registry::{CircuitRegistry, PERIOD_VERSION, key_file_name};
+pub use proofs::estimate::{CircuitConfig, CircuitEstimate, estimate_circuit};
+pub use proofs::backend::ProvingBackend;
+#[cfg(feature = "backend-arkworks")]
+pub use proofs::arkworks::{ArkworksProver, Bn254Scalar};
+#[cfg(feature = "backend-arkworks")]
+pub use proofs::evm::{export_proof_evm_json, import_proof_evm_json, import_vk_evm_json, EvmProof, EvmProofPoints, EvmVerifyingKey};
+pub use network::ingestion::{BootstrapOutcome, BootstrapReport, DhtMode, GossipsubTuning, IdentityError, IngestionEngine, NetworkConfig, NetworkError, NodeRole, EngineEvent, DataPacket, PacketError, ProofPolicy, ShutdownReport, TopicConfig, identity_verdict, load_or_create_identity};
+pub use network::builder::EngineBuilder;
+pub use network::validation::{PacketRule, PacketValidator};
+pub use network::dedup::{SeenCache, packet_digest};
+pub use network::message::{MessageKind, NetworkMessage, Severity, SimulationSummary};
+pub use network::wire::{Compression, WireError, WireFormat};
+pub use network::rate_limit::RateLimiter;
+pub use network::reputation::{Conduct, PeerReputation};
+pub use network::store::{FileStore, InMemoryStore, PacketStore, StoreError};
+pub use network::history::{HistoryError, HistoryRequest, HistoryResponse};
+pub use network::sync::{SyncRequest, SyncResponse};
+pub use network::filter::{PacketFilter, PacketPredicate};
+pub use network::aggregate::{AggregateSnapshot, AggregationState};
+pub use network::metrics::{MetricsSnapshot, NetworkMetrics, RejectReason};
+pub use network::outbound::{OverflowPolicy, PacketSender, SendError, TrySendError};
+pub use network::acl::{AccessDenial, AccessList};
+pub use network::freshness::{FreshnessError, FreshnessGuard};
+pub use network::nat::{Reachability, ReachabilityTracker};
+pub use network::health::{HealthTracker, PeerHealth, PeerStatus};
+pub use network::psk::{OpenError, TopicCipher};
+pub use network::retry::RetryPolicy;
+pub use network::outbox::{Outbox, OutboxEntry};
+pub use network::peer_info::{Capabilities, PeerInfo};
+pub use network::delta::{StateDelta, state_hash};
 
 #[cfg(test)]
 mod tests {
@@ -23,10 +82,10 @@ mod tests {
     fn test_full_pipeline() {
         // Create bank state
         let state = BankState {
-            assets: 1000.0,
-            liabilities: 900.0,
-            equity: 100.0,
-            leverage: 9.0,
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
         };
 
         // Compute fragility
@@ -40,9 +99,9 @@ mod tests {
     #[test]
     fn test_entropy_calculation() {
         let positions = vec![
-            Position { asset: \"BTC\".to_string(), weight: 0.5 },
-            Position { asset: \"ETH\".to_string(), weight: 0.3 },
-            Position { asset: \"SOL\".to_string(), weight: 0.2 },
+            Position { asset: "BTC".to_string(), weight: 0.5 },
+            Position { asset: "ETH".to_string(), weight: 0.3 },
+            Position { asset: "SOL".to_string(), weight: 0.2 },
         ];
 
         let config = EntropyConfig::default();
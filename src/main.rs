@@ -1,119 +1,508 @@
 //! Sovereign Architect CLI
 //!
 //! Command-line interface for OLO Core fragility analysis.
+//!
+//! Results go to stdout, as text or, with `--format json`, as a single JSON
+//! object per command holding its inputs, the configuration used and its
+//! outputs. Progress and other messages go to stderr.
+//!
+//! Settings not given as flags come from the environment or a configuration
+//! file; see `cli::config` and `olo config show`.
+//!
+//! Exit codes are a contract for scripts: 0 for success, 1 for any
+//! operational error, usage errors included, and 2 only when a
+//! `--fail-above` or `--fail-on-deterioration` threshold was crossed.
 
-use clap::{Parser, Subcommand};
-use sovereign_architect::*;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use olo_core::core::entropy::normalized_entropy;
+use olo_core::core::lagrangian::capital_adequacy_ratio;
+use olo_core::*;
+use serde::Serialize;
 use std::error::Error;
+use std::path::{Path, PathBuf};
+
+mod cli;
+
+use cli::batch::{read_records, BankRecord};
+use cli::compare::CompareArgs;
+use cli::config::{ConfigAction, Entry, Settings};
+use cli::histogram::{render, terminal_columns, TABLE_PERCENTILES};
+use cli::node::NodeArgs;
+use cli::proof::{ProveArgs, VerifyArgs};
+use cli::simulate::SimulationArgs;
+use cli::state::{BalanceSheet, StateArgs};
+
+/// Exit code of a run whose result crossed a `--fail-above` or
+/// `--fail-on-deterioration` threshold
+const EXIT_OVER_THRESHOLD: i32 = 2;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  Success, and within any --fail-above or --fail-on-deterioration threshold
+  1  Operational error: invalid arguments, unreadable input, failed run
+  2  A --fail-above or --fail-on-deterioration threshold was crossed";
 
 #[derive(Parser)]
 #[command(name = "olo")]
 #[command(about = "Omni-Lagrangian Oracle - Financial Fragility Detection", long_about = None)]
+#[command(after_help = EXIT_CODES)]
 struct Cli {
+    /// Output format of command results
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Configuration file [default: ./olo.toml, else $XDG_CONFIG_HOME/olo/config.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable summary
+    Text,
+    /// One JSON object: `{command, inputs, config, outputs}`
+    Json,
+}
+
+/// Risk band of a fragility score or concentration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    /// Band of `value` given the lower bounds of the medium and high bands
+    fn classify(value: f64, medium: f64, high: f64) -> Self {
+        if value > high {
+            RiskLevel::High
+        } else if value > medium {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        }
+    }
+
+    fn of_fragility(score: f64) -> Self {
+        Self::classify(score, 10.0, 20.0)
+    }
+
+    fn of_concentration(risk: f64) -> Self {
+        Self::classify(risk, 0.4, 0.7)
+    }
+}
+
+impl std::fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        })
+    }
+}
+
+/// Machine-readable result of a command
+#[derive(Serialize)]
+struct Report<I: Serialize, C: Serialize, O: Serialize> {
+    command: &'static str,
+    inputs: I,
+    config: C,
+    outputs: O,
+}
+
+impl<I: Serialize, C: Serialize, O: Serialize> Report<I, C, O> {
+    fn print(&self) -> Result<(), Box<dyn Error>> {
+        println!("{}", serde_json::to_string(self)?);
+        Ok(())
+    }
+}
+
+/// A bank state given by flags, as reported
+#[derive(Serialize)]
+struct StateInputs<'a> {
+    #[serde(flatten)]
+    state: &'a BankState,
+    /// Figures the state was converted from in `--balance-sheet` mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    balance_sheet: Option<BalanceSheet>,
+}
+
+/// Report a usage error and exit 1, as every operational error does
+fn usage_error(error: clap::Error) -> ! {
+    let _ = error.format(&mut Cli::command()).print();
+    std::process::exit(1)
+}
+
+/// The state `args` describe, exiting with a usage error if they describe none
+fn bank_state(args: &StateArgs) -> (BankState, Option<BalanceSheet>) {
+    args.to_state().unwrap_or_else(|e| usage_error(e))
+}
+
+/// Whether `value` is above `threshold`, reporting it on stderr if so
+fn crosses(value: f64, threshold: Option<f64>, what: &str, flag: &str) -> bool {
+    match threshold {
+        Some(threshold) if value > threshold => {
+            eprintln!("{} {:.4} is above {} {}", what, value, flag, threshold);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn print_state(state: &BankState, balance_sheet: Option<&BalanceSheet>) {
+    println!("Bank State:");
+    if let Some(sheet) = balance_sheet {
+        println!(
+            "  (from balance sheet: assets ${:.2}, liabilities ${:.2}, equity ${:.2}, leverage {:.2}x)",
+            sheet.assets, sheet.liabilities, sheet.equity, sheet.leverage
+        );
+    }
+    println!("  Tier 1 Capital: ${:.2}", state.tier1_capital);
+    println!("  Risk-Weighted Assets: ${:.2}", state.total_assets);
+    println!("  Capital Ratio: {:.2}%", capital_adequacy_ratio(state) * 100.0);
+    println!("  Liquidity Coverage: {:.2}", state.liquidity_coverage);
+    println!("  Entropy Index: {:.4} bits", state.entropy_index);
+}
+
+#[derive(Serialize)]
+struct FragilityOutputs {
+    score: f64,
+    classification: RiskLevel,
+}
+
+/// Score of one record of a batch
+#[derive(Serialize)]
+struct BatchScore {
+    id: String,
+    score: f64,
+    classification: RiskLevel,
+}
+
+/// Score every state in the file at `path`, reporting malformed records on
+/// stderr; fail if `strict` and any were malformed, else exit
+/// `EXIT_OVER_THRESHOLD` if any score is above `fail_above`
+fn fragility_batch(
+    path: &Path,
+    strict: bool,
+    fail_above: Option<f64>,
+    config: &LagrangianConfig,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut scores = Vec::new();
+    let mut malformed = 0;
+    for record in read_records(path)? {
+        match record {
+            Ok(BankRecord { id, state }) => {
+                let score = compute_fragility(&state, config);
+                scores.push(BatchScore { id, score, classification: RiskLevel::of_fragility(score) });
+            }
+            Err(e) => {
+                eprintln!("{}:{}", path.display(), e);
+                malformed += 1;
+            }
+        }
+    }
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string(&scores)?),
+        OutputFormat::Text => {
+            let width = scores.iter().map(|score| score.id.len()).max().unwrap_or(0).max("ID".len());
+            println!("{:<width$}  {:>8}  CLASSIFICATION", "ID", "SCORE", width = width);
+            for score in &scores {
+                println!("{:<width$}  {:>8.4}  {}", score.id, score.score, score.classification, width = width);
+            }
+        }
+    }
+
+    if malformed > 0 {
+        eprintln!("{} of {} records malformed", malformed, malformed + scores.len());
+        if strict {
+            std::process::exit(1);
+        }
+    }
+    let over = scores
+        .iter()
+        .filter(|score| crosses(score.score, fail_above, &format!("{}: fragility", score.id), "--fail-above"))
+        .count();
+    if over > 0 {
+        std::process::exit(EXIT_OVER_THRESHOLD);
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SimulationConfig<'a> {
+    lagrangian: &'a LagrangianConfig,
+    monte_carlo: &'a MonteCarloConfig,
+}
+
+#[derive(Serialize)]
+struct SimulationOutputs {
+    mean: f64,
+    std_dev: f64,
+    std_error: f64,
+    var_95: f64,
+    var_99: f64,
+    max_fragility: f64,
+    percentiles: Vec<PercentileValue>,
+    /// Bin edges and counts under `--histogram`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    histogram: Option<Histogram>,
+    /// File written under `--export-csv`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    export: Option<ExportOutputs>,
+}
+
+#[derive(Serialize)]
+struct ExportOutputs {
+    path: PathBuf,
+    rows: usize,
+    bytes: u64,
+}
+
+/// `bytes` in the largest binary unit that keeps it at least 1
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Fragility at a percentile of the simulated distribution
+#[derive(Serialize)]
+struct PercentileValue {
+    percentile: f64,
+    fragility: f64,
+}
+
+#[derive(Serialize)]
+struct EntropyOutputs {
+    shannon_entropy: f64,
+    normalized_entropy: f64,
+    concentration_risk: f64,
+    classification: RiskLevel,
+}
+
 #[derive(Subcommand)]
 enum Commands {
-    /// Compute fragility score for a bank state
+    /// Compute fragility score for a bank state, or for every state in a file
+    #[command(after_help = EXIT_CODES)]
     Fragility {
-        #[arg(short, long)]
-        assets: f64,
-        #[arg(short, long)]
-        liabilities: f64,
-        #[arg(short, long)]
-        equity: f64,
-        #[arg(short = 'v', long)]
-        leverage: f64,
+        #[command(flatten)]
+        state: StateArgs,
+        /// File of bank states with an `id` each (.csv, .json, .ndjson or .jsonl)
+        #[arg(
+            long,
+            conflicts_with_all = ["tier1_capital", "total_assets", "liquidity_coverage", "entropy_index", "balance_sheet"]
+        )]
+        input: Option<PathBuf>,
+        /// Fail with a non-zero exit code if any record of `--input` is malformed
+        #[arg(long, requires = "input")]
+        strict: bool,
+        /// Exit 2 if the fragility score, or any score of `--input`, is above this
+        #[arg(long)]
+        fail_above: Option<f64>,
     },
     /// Run Monte Carlo simulation
+    #[command(after_help = EXIT_CODES)]
     Simulate {
-        #[arg(short, long)]
-        assets: f64,
-        #[arg(short, long)]
-        liabilities: f64,
-        #[arg(short, long)]
-        equity: f64,
-        #[arg(short = 'v', long)]
-        leverage: f64,
-        #[arg(short, long, default_value_t = 10000)]
-        iterations: usize,
+        #[command(flatten)]
+        state: StateArgs,
+        #[command(flatten)]
+        simulation: SimulationArgs,
+        /// Exit 2 if the 99% VaR of the fragility score is above this
+        #[arg(long)]
+        fail_above_var99: Option<f64>,
+        /// Chart the distribution in this many bins, with a percentile table
+        #[arg(
+            long,
+            num_args = 0..=1,
+            default_missing_value = "20",
+            value_name = "BINS",
+            value_parser = cli::simulate::positive_count,
+            conflicts_with = "no_store_samples"
+        )]
+        histogram: Option<usize>,
     },
+    /// Compare the fragility of two bank states, attributing the change
+    #[command(after_help = EXIT_CODES)]
+    Compare(CompareArgs),
     /// Calculate portfolio entropy
     Entropy {
         #[arg(short, long)]
         weights: Vec<f64>,
     },
+    /// Prove a bank state's fragility score in zero knowledge
+    Prove(ProveArgs),
+    /// Verify a proof written by `prove`, exiting 1 unless it is valid
+    Verify(VerifyArgs),
+    /// Run a P2P node ingesting fragility packets until Ctrl-C
+    Node(NodeArgs),
+    /// Inspect the configuration read from the environment and files
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Serialize)]
+struct ConfigInputs<'a> {
+    file: Option<&'a Path>,
+}
+
+/// Print every setting with its effective value and where it came from
+fn config_show(settings: &Settings, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let entries = settings.entries();
+    if format == OutputFormat::Json {
+        let inputs = ConfigInputs { file: settings.file.as_deref() };
+        return Report { command: "config show", inputs, config: (), outputs: &entries }.print();
+    }
+    let lines: Vec<(String, &Entry)> = entries
+        .iter()
+        .map(|entry| match &entry.value {
+            Some(value) => (format!("{} = {}", entry.key, value), entry),
+            None => (format!("# {} unset", entry.key), entry),
+        })
+        .collect();
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, entry) in &lines {
+        println!("{:<width$}  # {}", line, entry.source, width = width);
+    }
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let cli = Cli::parse();
+    let cli = Cli::try_parse().unwrap_or_else(|e| match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => e.exit(),
+        _ => usage_error(e),
+    });
+    let format = cli.format;
+    let env = |var: &str| std::env::var(var).ok();
+    let settings = Settings::load(cli::config::find_file(cli.config.as_deref(), &env).as_deref(), env)?;
 
     match cli.command {
         Commands::Fragility {
-            assets,
-            liabilities,
-            equity,
-            leverage,
-        } => {
-            let state = BankState {
-                assets,
-                liabilities,
-                equity,
-                leverage,
-            };
+            input: Some(path),
+            strict,
+            fail_above,
+            ..
+        } => fragility_batch(&path, strict, fail_above, &settings.lagrangian, format)?,
 
-            let config = LagrangianConfig::default();
-            let fragility = compute_fragility(&state, &config);
-
-            println!(\"Bank State:\");
-            println!(\"  Assets: ${:.2}\", assets);
-            println!(\"  Liabilities: ${:.2}\", liabilities);
-            println!(\"  Equity: ${:.2}\", equity);
-            println!(\"  Leverage: {:.2}x\", leverage);
-            println!(\"\");
-            println!(\"Fragility Score: {:.4}\", fragility);
-
-            if fragility > 20.0 {
-                println!(\"⚠️  HIGH RISK - System approaching critical instability\");
-            } else if fragility > 10.0 {
-                println!(\"⚡ MEDIUM RISK - Elevated fragility detected\");
+        Commands::Fragility { state, fail_above, .. } => {
+            let (state, balance_sheet) = bank_state(&state);
+
+            let config = &settings.lagrangian;
+            let fragility = compute_fragility(&state, config);
+            let classification = RiskLevel::of_fragility(fragility);
+
+            if format == OutputFormat::Json {
+                let outputs = FragilityOutputs { score: fragility, classification };
+                let inputs = StateInputs { state: &state, balance_sheet };
+                Report { command: "fragility", inputs, config, outputs }.print()?;
             } else {
-                println!(\"✅ LOW RISK - System appears stable\");
+                print_state(&state, balance_sheet.as_ref());
+                println!();
+                println!("Fragility Score: {:.4}", fragility);
+
+                match classification {
+                    RiskLevel::High => println!("⚠️  HIGH RISK - System approaching critical instability"),
+                    RiskLevel::Medium => println!("⚡ MEDIUM RISK - Elevated fragility detected"),
+                    RiskLevel::Low => println!("✅ LOW RISK - System appears stable"),
+                }
+            }
+
+            if crosses(fragility, fail_above, "fragility", "--fail-above") {
+                std::process::exit(EXIT_OVER_THRESHOLD);
             }
         }
 
-        Commands::Simulate {
-            assets,
-            liabilities,
-            equity,
-            leverage,
-            iterations,
-        } => {
-            let state = BankState {
-                assets,
-                liabilities,
-                equity,
-                leverage,
-            };
+        Commands::Simulate { state, simulation, fail_above_var99, histogram } => {
+            let (state, balance_sheet) = bank_state(&state);
+
+            let lag_config = &settings.lagrangian;
+            let mc_config = simulation
+                .to_config(&settings.monte_carlo)
+                .unwrap_or_else(|e| usage_error(e));
+            if histogram.is_some() && !mc_config.store_samples {
+                usage_error(clap::Error::raw(
+                    ErrorKind::ArgumentConflict,
+                    "--histogram needs every path's fragility, but monte_carlo.store_samples is off\n",
+                ));
+            }
 
-            let lag_config = LagrangianConfig::default();
-            let mc_config = MonteCarloConfig {
-                num_simulations: iterations,
-                ..Default::default()
+            eprintln!("Running {} Monte Carlo simulations...", mc_config.num_simulations);
+            let result = try_run_simulation(&state, lag_config, &mc_config)?;
+            let percentiles: Vec<PercentileValue> = simulation
+                .percentiles
+                .iter()
+                .map(|&p| PercentileValue { percentile: p, fragility: threshold_for_exceedance(&result, 1.0 - p) })
+                .collect();
+            let chart = histogram.map(|bins| result.histogram(Binning::EqualWidth(bins)));
+            let export = match &mc_config.export {
+                Some(export) => Some(ExportOutputs {
+                    path: export.path.clone(),
+                    // Every path is exported, or the run fails
+                    rows: mc_config.num_simulations,
+                    bytes: std::fs::metadata(&export.path)?.len(),
+                }),
+                None => None,
             };
 
-            println!(\"Running {} Monte Carlo simulations...\", iterations);
-            let result = run_simulation(&state, &lag_config, &mc_config);
+            if format == OutputFormat::Json {
+                let config = SimulationConfig { lagrangian: lag_config, monte_carlo: &mc_config };
+                let outputs = SimulationOutputs {
+                    mean: result.mean,
+                    std_dev: result.std_dev,
+                    std_error: result.std_error,
+                    var_95: result.var_95,
+                    var_99: result.var_99,
+                    max_fragility: result.max_fragility,
+                    percentiles,
+                    histogram: chart,
+                    export,
+                };
+                let inputs = StateInputs { state: &state, balance_sheet };
+                Report { command: "simulate", inputs, config, outputs }.print()?;
+            } else {
+                println!();
+                println!("Simulation Results:");
+                println!("  Mean Fragility: {:.4}", result.mean);
+                println!("  Std Deviation: {:.4}", result.std_dev);
+                println!("  95% VaR: {:.4}", result.var_95);
+                println!("  99% VaR: {:.4}", result.var_99);
+                println!("  Max Fragility: {:.4}", result.max_fragility);
+                for PercentileValue { percentile, fragility } in percentiles {
+                    println!("  {}th Percentile: {:.4}", percentile * 100.0, fragility);
+                }
+                if let Some(chart) = chart {
+                    let table = TABLE_PERCENTILES.map(|(p, label)| (label, threshold_for_exceedance(&result, 1.0 - p)));
+                    println!();
+                    println!("Distribution:");
+                    print!("{}", render(&chart, &table, terminal_columns()));
+                }
+                if let Some(ExportOutputs { path, rows, bytes }) = export {
+                    println!();
+                    println!("Exported {} rows to {} ({})", rows, path.display(), human_size(bytes));
+                }
+            }
 
-            println!(\"\");
-            println!(\"Simulation Results:\");
-            println!(\"  Mean Fragility: {:.4}\", result.mean);
-            println!(\"  Std Deviation: {:.4}\", result.std_dev);
-            println!(\"  95% VaR: {:.4}\", result.var_95);
-            println!(\"  99% VaR: {:.4}\", result.var_99);
-            println!(\"  Max Fragility: {:.4}\", result.max_fragility);
+            if crosses(result.var_99, fail_above_var99, "99% VaR", "--fail-above-var99") {
+                std::process::exit(EXIT_OVER_THRESHOLD);
+            }
         }
 
         Commands::Entropy { weights } => {
@@ -121,27 +510,46 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .iter()
                 .enumerate()
                 .map(|(i, &w)| Position {
-                    asset: format!(\"Asset{}\", i + 1),
+                    asset: format!("Asset{}", i + 1),
                     weight: w,
                 })
                 .collect();
 
-            let config = EntropyConfig::default();
-            let entropy = calculate_entropy(&positions, &config);
-            let conc_risk = concentration_risk(&positions, &config);
+            let config = &settings.entropy;
+            let entropy = calculate_entropy(&positions, config);
+            let conc_risk = concentration_risk(&positions, config);
+            let classification = RiskLevel::of_concentration(conc_risk);
 
-            println!(\"Portfolio Entropy Analysis:\");
-            println!(\"  Shannon Entropy: {:.4} bits\", entropy);
-            println!(\"  Concentration Risk: {:.2}%\", conc_risk * 100.0);
+            if format == OutputFormat::Json {
+                let outputs = EntropyOutputs {
+                    shannon_entropy: entropy,
+                    normalized_entropy: normalized_entropy(&positions, config),
+                    concentration_risk: conc_risk,
+                    classification,
+                };
+                return Report { command: "entropy", inputs: &positions, config, outputs }.print();
+            }
 
-            if conc_risk > 0.7 {
-                println!(\"⚠️  HIGH CONCENTRATION - Portfolio highly concentrated\");
-            } else if conc_risk > 0.4 {
-                println!(\"⚡ MEDIUM CONCENTRATION - Consider diversification\");
-            } else {
-                println!(\"✅ WELL DIVERSIFIED - Healthy portfolio distribution\");
+            println!("Portfolio Entropy Analysis:");
+            println!("  Shannon Entropy: {:.4} bits", entropy);
+            println!("  Concentration Risk: {:.2}%", conc_risk * 100.0);
+
+            match classification {
+                RiskLevel::High => println!("⚠️  HIGH CONCENTRATION - Portfolio highly concentrated"),
+                RiskLevel::Medium => println!("⚡ MEDIUM CONCENTRATION - Consider diversification"),
+                RiskLevel::Low => println!("✅ WELL DIVERSIFIED - Healthy portfolio distribution"),
             }
         }
+
+        Commands::Compare(args) => cli::compare::compare(&args, &settings.lagrangian, format)?,
+
+        Commands::Prove(args) => cli::proof::prove(&args, format)?,
+
+        Commands::Verify(args) => cli::proof::verify(&args, format)?,
+
+        Commands::Node(args) => tokio::runtime::Runtime::new()?.block_on(cli::node::run(&args, &settings, format))?,
+
+        Commands::Config { action: ConfigAction::Show } => config_show(&settings, format)?,
     }
 
     Ok(())
@@ -149,9 +557,38 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    fn test_cli() {
-        // CLI test would go here
-        assert!(true);
+    fn test_risk_bands_are_exclusive_at_their_bounds() {
+        assert_eq!(RiskLevel::of_fragility(10.0), RiskLevel::Low);
+        assert_eq!(RiskLevel::of_fragility(20.0), RiskLevel::Medium);
+        assert_eq!(RiskLevel::of_fragility(20.5), RiskLevel::High);
+        assert_eq!(RiskLevel::of_concentration(0.5), RiskLevel::Medium);
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 << 20), "5.0 MiB");
+    }
+
+    #[test]
+    fn test_node_arguments() {
+        let bootstrap = ["--bootstrap", "/ip4/10.0.0.1/tcp/9000", "--bootstrap", "/dns4/seed/tcp/9000"];
+        let cli = Cli::try_parse_from(["olo", "node"].into_iter().chain(bootstrap)).unwrap();
+        let Commands::Node(args) = cli.command else { panic!("not the node command") };
+        assert_eq!(args.bootstrap.len(), 2);
+        assert!(args.topic.is_empty());
+        assert!(args.publish_file.is_none());
+
+        let cli = Cli::try_parse_from(["olo", "node", "--publish-file", "states.ndjson", "--interval", "5m"]).unwrap();
+        let Commands::Node(args) = cli.command else { panic!("not the node command") };
+        assert_eq!(args.interval, std::time::Duration::from_secs(300));
+
+        // An interval means nothing without a file to publish from
+        assert!(Cli::try_parse_from(["olo", "node", "--interval", "5m"]).is_err());
+        assert!(Cli::try_parse_from(["olo", "node", "--publish-file", "s.ndjson", "--interval", "soon"]).is_err());
     }
 }
@@ -0,0 +1,85 @@
+//! Peer Access Control
+//!
+//! Private consortium deployments only take traffic from known members. An
+//! `AccessList` holds an optional allowlist and a denylist of peer IDs:
+//! with an allowlist, only the peers on it are admitted, and denylisted
+//! peers are never admitted whatever the allowlist says. The engine applies
+//! it to connections, closing those to peers it does not admit, and to the
+//! authors of gossip messages, which may reach it relayed by a member.
+
+use libp2p::PeerId;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Why a peer is not admitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDenial {
+    /// An allowlist is set and the peer is not on it
+    NotAllowlisted,
+    /// The peer is on the denylist
+    Denylisted,
+}
+
+impl fmt::Display for AccessDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessDenial::NotAllowlisted => write!(f, "peer is not on the allowlist"),
+            AccessDenial::Denylisted => write!(f, "peer is on the denylist"),
+        }
+    }
+}
+
+/// Peers admitted to connect and gossip
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessList {
+    /// `None` admits every peer not denylisted
+    allow: Option<HashSet<PeerId>>,
+    deny: HashSet<PeerId>,
+}
+
+impl AccessList {
+    /// Admit only `allowlist`, or anyone if it is empty, except `denylist`
+    pub fn new(allowlist: impl IntoIterator<Item = PeerId>, denylist: impl IntoIterator<Item = PeerId>) -> Self {
+        let allow: HashSet<PeerId> = allowlist.into_iter().collect();
+        Self {
+            allow: (!allow.is_empty()).then_some(allow),
+            deny: denylist.into_iter().collect(),
+        }
+    }
+
+    /// Whether only allowlisted peers are admitted
+    pub fn is_restricted(&self) -> bool {
+        self.allow.is_some()
+    }
+
+    /// Whether `peer` is admitted, and why not if it is not
+    pub fn check(&self, peer: &PeerId) -> Result<(), AccessDenial> {
+        if self.deny.contains(peer) {
+            return Err(AccessDenial::Denylisted);
+        }
+        if self.allow.as_ref().is_some_and(|allow| !allow.contains(peer)) {
+            return Err(AccessDenial::NotAllowlisted);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_denylist_overrides_allowlist() {
+        let (member, outsider, revoked) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let open = AccessList::new([], [revoked]);
+        assert!(!open.is_restricted());
+        assert_eq!(open.check(&outsider), Ok(()));
+        assert_eq!(open.check(&revoked), Err(AccessDenial::Denylisted));
+
+        let consortium = AccessList::new([member, revoked], [revoked]);
+        assert!(consortium.is_restricted());
+        assert_eq!(consortium.check(&member), Ok(()));
+        assert_eq!(consortium.check(&outsider), Err(AccessDenial::NotAllowlisted));
+        assert_eq!(consortium.check(&revoked), Err(AccessDenial::Denylisted));
+    }
+}
@@ -0,0 +1,206 @@
+//! Network-Wide Aggregation
+//!
+//! Every node sees the same gossip, so each can keep its own picture of the
+//! whole system without a central collector. `AggregationState` keeps the
+//! latest packet from each source and summarizes them:
+//!
+//! - the mean fragility weighted by total assets, so a large bank counts
+//!   for more than a small one
+//! - the number of sources above the alert threshold of 70
+//! - the change in the weighted mean over the last hour
+//!
+//! A source whose latest packet is older than the staleness limit has
+//! stopped reporting and drops out of the aggregate until it reports again,
+//! as does a source whose latest packet has passed its expiry.
+//! Times are Unix epoch milliseconds, compared against packet timestamps.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::network::ingestion::DataPacket;
+
+/// Fragility above which a source counts as high
+pub const HIGH_FRAGILITY: f64 = 70.0;
+
+/// Span of the weighted-mean trend
+const TREND_WINDOW: Duration = Duration::from_secs(3600);
+
+/// System-wide fragility at one moment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregateSnapshot {
+    /// When the snapshot was taken
+    pub taken_at: u64,
+    /// Sources with a packet within the staleness limit
+    pub sources: usize,
+    /// Total assets of those sources
+    pub total_assets: f64,
+    /// Asset-weighted mean fragility; `None` without sources
+    pub weighted_fragility: Option<f64>,
+    /// Sources with fragility above `HIGH_FRAGILITY`
+    pub high_fragility_sources: usize,
+    /// Weighted mean now less the earliest weighted mean in the last hour;
+    /// `None` without both
+    pub hourly_trend: Option<f64>,
+}
+
+/// What the aggregate needs of a source's latest packet
+#[derive(Debug, Clone, Copy)]
+struct Latest {
+    timestamp: u64,
+    fragility: f64,
+    total_assets: f64,
+    expires_at: Option<u64>,
+}
+
+impl Latest {
+    /// Whether the packet still counts at `now`, given the staleness `cutoff`
+    fn current(&self, cutoff: u64, now: u64) -> bool {
+        self.timestamp >= cutoff && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Latest packet per source, with the weighted-mean history for the trend
+#[derive(Debug, Clone)]
+pub struct AggregationState {
+    staleness: Duration,
+    latest: HashMap<String, Latest>,
+    /// Weighted mean after each update within the trend window, oldest first
+    trend: VecDeque<(u64, f64)>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+impl AggregationState {
+    /// Drop sources from the aggregate once their latest packet is `staleness` old
+    pub fn new(staleness: Duration) -> Self {
+        Self { staleness, latest: HashMap::new(), trend: VecDeque::new() }
+    }
+
+    /// Take `packet` as its source's latest, unless a later one is already in
+    pub fn record(&mut self, packet: &DataPacket) {
+        self.record_at(packet, now_millis())
+    }
+
+    fn record_at(&mut self, packet: &DataPacket, now: u64) {
+        if self.latest.get(&packet.source).is_some_and(|latest| packet.timestamp <= latest.timestamp) {
+            return;
+        }
+        self.latest.insert(
+            packet.source.clone(),
+            Latest {
+                timestamp: packet.timestamp,
+                fragility: packet.fragility,
+                total_assets: packet.state.total_assets,
+                expires_at: packet.expires_at,
+            },
+        );
+        let cutoff = now.saturating_sub(self.staleness.as_millis() as u64);
+        self.latest.retain(|_, latest| latest.current(cutoff, now));
+
+        let window_start = now.saturating_sub(TREND_WINDOW.as_millis() as u64);
+        while self.trend.front().is_some_and(|(at, _)| *at < window_start) {
+            self.trend.pop_front();
+        }
+        if let Some(mean) = self.snapshot_at(now).weighted_fragility {
+            self.trend.push_back((now, mean));
+        }
+    }
+
+    /// The aggregate as of now
+    pub fn snapshot(&self) -> AggregateSnapshot {
+        self.snapshot_at(now_millis())
+    }
+
+    fn snapshot_at(&self, now: u64) -> AggregateSnapshot {
+        let cutoff = now.saturating_sub(self.staleness.as_millis() as u64);
+        let current: Vec<&Latest> = self.latest.values().filter(|latest| latest.current(cutoff, now)).collect();
+        let total_assets: f64 = current.iter().map(|latest| latest.total_assets).sum();
+        let weighted_fragility = (total_assets > 0.0).then(|| {
+            current.iter().map(|latest| latest.fragility * latest.total_assets).sum::<f64>() / total_assets
+        });
+        let window_start = now.saturating_sub(TREND_WINDOW.as_millis() as u64);
+        let earliest = self.trend.iter().find(|(at, _)| *at >= window_start).map(|(_, mean)| *mean);
+        AggregateSnapshot {
+            taken_at: now,
+            sources: current.len(),
+            total_assets,
+            weighted_fragility,
+            high_fragility_sources: current.iter().filter(|latest| latest.fragility > HIGH_FRAGILITY).count(),
+            hourly_trend: weighted_fragility.zip(earliest).map(|(now, then)| now - then),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    const MINUTE: u64 = 60_000;
+
+    fn packet(source: &str, timestamp: u64, fragility: f64, total_assets: f64) -> DataPacket {
+        fixtures::packet()
+            .source(source)
+            .timestamp(timestamp)
+            .tier1_capital(total_assets / 10.0)
+            .total_assets(total_assets)
+            .fragility(fragility)
+            .build()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_weighted_mean_counts_and_expiry() {
+        let start = 1_700_000_000_000;
+        let mut state = AggregationState::new(Duration::from_secs(600));
+        state.record_at(&packet("bank-c", start, 50.0, 100.0), start);
+        state.record_at(&packet("bank-a", start + 5 * MINUTE, 20.0, 100.0), start + 5 * MINUTE);
+        state.record_at(&packet("bank-b", start + 6 * MINUTE, 80.0, 300.0), start + 6 * MINUTE);
+
+        let all = state.snapshot_at(start + 6 * MINUTE);
+        assert_eq!(all.sources, 3);
+        assert_eq!(all.total_assets, 500.0);
+        // (50 * 100 + 20 * 100 + 80 * 300) / 500
+        assert_close(all.weighted_fragility, 62.0);
+        assert_eq!(all.high_fragility_sources, 1);
+        // The first sample, with bank-c alone, was 50
+        assert_close(all.hourly_trend, 12.0);
+
+        // bank-c's packet is now more than ten minutes old
+        let expired = state.snapshot_at(start + 11 * MINUTE);
+        assert_eq!(expired.sources, 2);
+        assert_close(expired.weighted_fragility, 65.0);
+        assert_eq!(expired.high_fragility_sources, 1);
+
+        // An older packet does not replace a source's latest
+        state.record_at(&packet("bank-b", start, 10.0, 300.0), start + 11 * MINUTE);
+        assert_close(state.snapshot_at(start + 11 * MINUTE).weighted_fragility, 65.0);
+
+        // A packet past its expiry drops out before it goes stale
+        let brief = DataPacket { expires_at: Some(start + 13 * MINUTE), ..packet("bank-a", start + 12 * MINUTE, 40.0, 100.0) };
+        state.record_at(&brief, start + 12 * MINUTE);
+        assert_eq!(state.snapshot_at(start + 12 * MINUTE).sources, 2);
+        assert_eq!(state.snapshot_at(start + 13 * MINUTE).sources, 1);
+    }
+
+    #[test]
+    fn test_trend_spans_last_hour() {
+        let start = 1_700_000_000_000;
+        let mut state = AggregationState::new(Duration::from_secs(7200));
+        assert_eq!(state.snapshot_at(start).weighted_fragility, None);
+        assert_eq!(state.snapshot_at(start).hourly_trend, None);
+        for (i, fragility) in [30.0, 40.0, 45.0].into_iter().enumerate() {
+            let at = start + i as u64 * 40 * MINUTE;
+            state.record_at(&packet("bank-a", at, fragility, 100.0), at);
+        }
+        // At 80 minutes the 30 from the start has left the window
+        assert_close(state.snapshot_at(start + 80 * MINUTE).hourly_trend, 5.0);
+    }
+}
@@ -0,0 +1,355 @@
+//! Engine Builder
+//!
+//! `IngestionEngine::builder()` sets up an engine field by field instead of
+//! through a `NetworkConfig` literal. Each setter mirrors the config field
+//! of the same name, and fields left unset keep their defaults. `build`
+//! checks the configuration as the engine is assembled and reports the
+//! first problem as a `NetworkError` rather than panicking.
+
+use libp2p::PeerId;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::network::ingestion::{
+    DhtMode, GossipsubTuning, IngestionEngine, NetworkConfig, NetworkError, NodeRole, TopicConfig,
+};
+use crate::network::outbound::OverflowPolicy;
+use crate::network::retry::RetryPolicy;
+use crate::network::wire::{Compression, WireFormat};
+
+/// Configuration of an engine being set up, from `IngestionEngine::builder`
+#[derive(Debug, Clone, Default)]
+pub struct EngineBuilder {
+    config: NetworkConfig,
+}
+
+impl EngineBuilder {
+    /// Start from `config` instead of the defaults
+    pub fn from_config(config: NetworkConfig) -> Self {
+        Self { config }
+    }
+
+    /// Listen address, checked when the engine is built
+    pub fn listen_addr(mut self, addr: impl Into<String>) -> Self {
+        self.config.listen_addr = addr.into();
+        self
+    }
+
+    /// Add the WebSocket transport, to listen on and dial `/ws` addresses
+    pub fn enable_websocket(mut self, enable: bool) -> Self {
+        self.config.enable_websocket = enable;
+        self
+    }
+
+    /// WebSocket listen address, checked when the engine is built with WebSocket enabled
+    pub fn ws_listen_addr(mut self, addr: impl Into<String>) -> Self {
+        self.config.ws_listen_addr = addr.into();
+        self
+    }
+
+    /// Whether the node publishes, consumes received packets, or both
+    pub fn role(mut self, role: NodeRole) -> Self {
+        self.config.role = role;
+        self
+    }
+
+    /// Bootstrap peers, as multiaddrs dialed by `connect_bootstrap`
+    pub fn bootstrap_peers(mut self, peers: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.bootstrap_peers = peers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Topics subscribed to at startup, replacing the default; the first is
+    /// where `publish` and the sender publish
+    pub fn topics(mut self, topics: impl IntoIterator<Item = TopicConfig>) -> Self {
+        self.config.topics = topics.into_iter().collect();
+        self
+    }
+
+    /// Gossipsub parameters
+    pub fn gossipsub(mut self, tuning: GossipsubTuning) -> Self {
+        self.config.gossipsub = Some(tuning);
+        self
+    }
+
+    /// Dial attempts per bootstrap peer before giving up
+    pub fn bootstrap_attempts(mut self, attempts: u32) -> Self {
+        self.config.bootstrap_attempts = attempts;
+        self
+    }
+
+    /// Wait after the first failed dial, doubled after each further failure
+    pub fn bootstrap_backoff(mut self, backoff: Duration) -> Self {
+        self.config.bootstrap_backoff = backoff;
+        self
+    }
+
+    /// Discover and dial peers on the local network with mDNS
+    pub fn enable_mdns(mut self, enable: bool) -> Self {
+        self.config.enable_mdns = enable;
+        self
+    }
+
+    /// Only peers mDNS may report and dial
+    pub fn discovery_allowlist(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.config.discovery_allowlist = Some(peers.into_iter().collect());
+        self
+    }
+
+    /// Only peers that may connect and whose gossip is accepted; empty allows any
+    pub fn allowlist(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.config.allowlist = peers.into_iter().collect();
+        self
+    }
+
+    /// Peers never allowed to connect or have their gossip accepted
+    pub fn denylist(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.config.denylist = peers.into_iter().collect();
+        self
+    }
+
+    /// Probe whether peers can dial this node with AutoNAT
+    pub fn enable_autonat(mut self, enable: bool) -> Self {
+        self.config.enable_autonat = enable;
+        self
+    }
+
+    /// Circuit relays to reserve a slot on when the node is not publicly
+    /// reachable, as multiaddrs ending in the relay's `/p2p/` peer ID
+    pub fn relay_addresses(mut self, addrs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.relay_addresses = addrs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How often each connected peer is pinged
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.config.ping_interval = interval;
+        self
+    }
+
+    /// How long a connected peer may go unheard before it is reported stale
+    pub fn stale_after(mut self, silence: Duration) -> Self {
+        self.config.stale_after = silence;
+        self
+    }
+
+    /// Kademlia protocol name; only nodes using the same name share a DHT
+    pub fn kad_protocol(mut self, name: impl Into<String>) -> Self {
+        self.config.kad_protocol = name.into();
+        self
+    }
+
+    /// Whether this node serves DHT records or only queries them
+    pub fn kad_mode(mut self, mode: DhtMode) -> Self {
+        self.config.kad_mode = mode;
+        self
+    }
+
+    /// Keyfile holding the node's identity, created on first use
+    pub fn identity_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.identity_path = Some(path.into());
+        self
+    }
+
+    /// Delivered reports remembered for duplicate suppression
+    pub fn dedup_capacity(mut self, capacity: usize) -> Self {
+        self.config.dedup_capacity = capacity;
+        self
+    }
+
+    /// How long a delivered report is remembered
+    pub fn dedup_ttl(mut self, ttl: Duration) -> Self {
+        self.config.dedup_ttl = ttl;
+        self
+    }
+
+    /// How far ahead of local time a packet may be stamped
+    pub fn max_future_skew(mut self, skew: Duration) -> Self {
+        self.config.max_future_skew = skew;
+        self
+    }
+
+    /// How far behind local time a packet may be stamped
+    pub fn max_packet_age(mut self, age: Duration) -> Self {
+        self.config.max_packet_age = age;
+        self
+    }
+
+    /// Delivered (source, timestamp) pairs remembered to reject replays
+    pub fn replay_capacity(mut self, capacity: usize) -> Self {
+        self.config.replay_capacity = capacity;
+        self
+    }
+
+    /// Encoding of published packets
+    pub fn wire_format(mut self, format: WireFormat) -> Self {
+        self.config.wire_format = format;
+        self
+    }
+
+    /// Compress published messages as `compression` says
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.config.compression = Some(compression);
+        self
+    }
+
+    /// Largest size a received compressed message may expand to
+    pub fn max_decompressed_size(mut self, size: usize) -> Self {
+        self.config.max_decompressed_size = size;
+        self
+    }
+
+    /// Packets accepted from each source per minute; `None` is unlimited
+    pub fn max_packets_per_peer_per_minute(mut self, limit: Option<u32>) -> Self {
+        self.config.max_packets_per_peer_per_minute = limit;
+        self
+    }
+
+    /// Packets a source may send at once before the per-minute rate applies
+    pub fn rate_limit_burst(mut self, burst: u32) -> Self {
+        self.config.rate_limit_burst = burst;
+        self
+    }
+
+    /// Sources exempt from rate limiting
+    pub fn rate_limit_exempt(mut self, peers: impl IntoIterator<Item = PeerId>) -> Self {
+        self.config.rate_limit_exempt = peers.into_iter().collect();
+        self
+    }
+
+    /// Reputation score at or below which a source is banned
+    pub fn ban_threshold(mut self, threshold: i32) -> Self {
+        self.config.ban_threshold = threshold;
+        self
+    }
+
+    /// How long a banned source stays disconnected and ignored
+    pub fn ban_duration(mut self, duration: Duration) -> Self {
+        self.config.ban_duration = duration;
+        self
+    }
+
+    /// Age at which a source's latest packet drops out of the aggregate
+    pub fn aggregate_staleness(mut self, staleness: Duration) -> Self {
+        self.config.aggregate_staleness = staleness;
+        self
+    }
+
+    /// Publish the aggregate snapshot every `interval`
+    pub fn aggregate_publish_interval(mut self, interval: Duration) -> Self {
+        self.config.aggregate_publish_interval = Some(interval);
+        self
+    }
+
+    /// Log file keeping delivered and published packets across restarts
+    pub fn store_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.store_path = Some(path.into());
+        self
+    }
+
+    /// Packets kept in memory when no store path is set
+    pub fn store_capacity(mut self, capacity: usize) -> Self {
+        self.config.store_capacity = capacity;
+        self
+    }
+
+    /// Reconcile packets from the last `horizon` with each newly connected peer
+    pub fn sync_horizon(mut self, horizon: Duration) -> Self {
+        self.config.sync_horizon = Some(horizon);
+        self
+    }
+
+    /// Most encoded bytes of packets taken from a peer in one sync
+    pub fn sync_max_bytes(mut self, bytes: usize) -> Self {
+        self.config.sync_max_bytes = bytes;
+        self
+    }
+
+    /// Keep messages published while no peer subscribes to their topic, and
+    /// publish them once one does
+    pub fn publish_retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.publish_retry = Some(policy);
+        self
+    }
+
+    /// Log file keeping published messages until gossipsub accepts them
+    pub fn outbox_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.outbox_path = Some(path.into());
+        self
+    }
+
+    /// Packets the sender queue holds before the overflow policy applies
+    pub fn outbound_capacity(mut self, capacity: usize) -> Self {
+        self.config.outbound_capacity = capacity;
+        self
+    }
+
+    /// What a full sender queue does with another packet
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.config.overflow_policy = policy;
+        self
+    }
+
+    /// The configuration as set so far
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    /// Build the engine, checking the configuration
+    pub fn build(self) -> Result<IngestionEngine, NetworkError> {
+        IngestionEngine::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_error(builder: EngineBuilder) -> NetworkError {
+        builder.build().err().expect("configuration should be refused")
+    }
+
+    #[test]
+    fn test_setters_mirror_config() {
+        let builder = IngestionEngine::builder()
+            .listen_addr("/ip4/127.0.0.1/tcp/0")
+            .topics([TopicConfig::new("olo-fragility-eu"), TopicConfig::new("olo-alerts")])
+            .sync_horizon(Duration::from_secs(600))
+            .max_packets_per_peer_per_minute(None);
+        let config = builder.config();
+        assert_eq!(config.listen_addr, "/ip4/127.0.0.1/tcp/0");
+        assert_eq!(config.topics.len(), 2);
+        assert_eq!(config.sync_horizon, Some(Duration::from_secs(600)));
+        assert_eq!(config.max_packets_per_peer_per_minute, None);
+        assert_eq!(config.store_capacity, NetworkConfig::default().store_capacity);
+
+        let engine = builder.build().unwrap();
+        assert_eq!(engine.topics(), vec!["olo-alerts", "olo-fragility-eu"]);
+    }
+
+    #[test]
+    fn test_malformed_config_reported() {
+        let error = build_error(IngestionEngine::builder().listen_addr("localhost:4001"));
+        assert!(matches!(error, NetworkError::InvalidListenAddr { ref addr, .. } if addr == "localhost:4001"));
+
+        let crossed = GossipsubTuning { mesh_n_low: 8, mesh_n: 6, ..GossipsubTuning::default() };
+        let error = build_error(IngestionEngine::builder().gossipsub(crossed));
+        assert!(matches!(error, NetworkError::GossipsubConfig { .. }));
+
+        let error = build_error(IngestionEngine::builder().topics([]));
+        assert!(matches!(error, NetworkError::NoTopics));
+
+        let error = build_error(IngestionEngine::builder().kad_protocol("olo-kad"));
+        assert!(matches!(error, NetworkError::InvalidKadProtocol { .. }));
+
+        let error = build_error(IngestionEngine::builder().relay_addresses(["/ip4/127.0.0.1/tcp/1"]));
+        assert!(matches!(error, NetworkError::InvalidRelayAddress { .. }));
+
+        #[cfg(unix)]
+        {
+            let error = build_error(IngestionEngine::builder().identity_path("/dev/null/olo.key"));
+            assert!(matches!(error, NetworkError::IdentityIo { .. }));
+            assert!(std::error::Error::source(&error).is_some());
+        }
+    }
+}
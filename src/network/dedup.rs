@@ -0,0 +1,146 @@
+//! Duplicate Suppression
+//!
+//! The same report can reach a node more than once, relayed along several
+//! gossip paths or republished by its source, and counting it twice skews
+//! anything aggregated downstream. `SeenCache` remembers the digests of
+//! recently delivered packets so repeats are dropped.
+//!
+//! A packet's digest is `SHA-256(domain ‖ DataPacket::signing_bytes)`. The
+//! signing bytes are a fixed-layout encoding of the source, timestamp,
+//! state, and fragility, so the digest depends only on those values and
+//! not on how the packet was serialized on the wire.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::network::ingestion::DataPacket;
+
+/// Domain separator for packet digests
+const DIGEST_DOMAIN: &[u8] = b"olo-core/packet-digest/v1";
+
+/// Digest identifying the report `packet` carries
+pub fn packet_digest(packet: &DataPacket) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(DIGEST_DOMAIN);
+    hasher.update(packet.signing_bytes());
+    hasher.finalize().into()
+}
+
+struct Entry {
+    first_seen: Instant,
+    last_used: u64,
+}
+
+/// Least-recently-used set of packet digests, each kept for a fixed time
+pub struct SeenCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<[u8; 32], Entry>,
+    tick: u64,
+}
+
+impl SeenCache {
+    /// Remember up to `capacity` packets (at least one), each for `ttl` after it is first seen
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            entries: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Record `packet`, returning whether it was already seen within the TTL
+    pub fn check(&mut self, packet: &DataPacket) -> bool {
+        self.check_at(packet_digest(packet), Instant::now())
+    }
+
+    fn check_at(&mut self, digest: [u8; 32], now: Instant) -> bool {
+        self.tick += 1;
+        let ttl = self.ttl;
+        if let Some(entry) = self.entries.get_mut(&digest) {
+            if now.saturating_duration_since(entry.first_seen) < ttl {
+                entry.last_used = self.tick;
+                return true;
+            }
+            self.entries.remove(&digest);
+        }
+
+        if self.entries.len() >= self.capacity {
+            self.entries.retain(|_, entry| now.saturating_duration_since(entry.first_seen) < ttl);
+        }
+        if self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(digest, _)| *digest);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(digest, Entry { first_seen: now, last_used: self.tick });
+        false
+    }
+
+    /// Number of packets remembered
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no packets are remembered
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    fn packet(timestamp: u64) -> DataPacket {
+        fixtures::packet().timestamp(timestamp).build()
+    }
+
+    #[test]
+    fn test_digest_ignores_unsigned_fields_and_encoding() {
+        let packet = packet(1);
+        let resigned = DataPacket { signature: vec![9; 64], proof: Some(vec![0; 192]), ..packet.clone() };
+        assert_eq!(packet_digest(&packet), packet_digest(&resigned));
+
+        // The same report with its fields in another order on the wire
+        let reordered = r#"{"fragility":21.5,"state":{"entropy_index":2.0,"liquidity_coverage":1.2,
+            "total_assets":100000.0,"tier1_capital":10000.0},"source":"12D3KooWnode","signature":[],"timestamp":1}"#;
+        let decoded: DataPacket = serde_json::from_str(reordered).unwrap();
+        assert_eq!(packet_digest(&packet), packet_digest(&decoded));
+        assert_ne!(packet_digest(&packet), packet_digest(&DataPacket { fragility: 21.6, ..packet.clone() }));
+    }
+
+    #[test]
+    fn test_repeats_expire_after_ttl() {
+        let mut cache = SeenCache::new(16, Duration::from_secs(60));
+        let start = Instant::now();
+        let digest = packet_digest(&packet(1));
+        assert!(!cache.check_at(digest, start));
+        assert!(cache.check_at(digest, start + Duration::from_secs(59)));
+        assert!(!cache.check_at(digest, start + Duration::from_secs(60)));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_seen_evicted_at_capacity() {
+        let mut cache = SeenCache::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        let [first, second, third] = [1, 2, 3].map(|t| packet_digest(&packet(t)));
+        cache.check_at(first, now);
+        cache.check_at(second, now);
+        // Seeing the first again makes the second the least recently used
+        assert!(cache.check_at(first, now));
+        cache.check_at(third, now);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.check_at(first, now));
+        assert!(!cache.check_at(second, now));
+    }
+}
@@ -0,0 +1,193 @@
+//! State Deltas
+//!
+//! A bank's state usually changes in a field or two between reports, yet
+//! every `Fragility` packet carries all of it. A `StateDelta` carries only
+//! the fields that changed since a base state, which it names by content
+//! hash (`state_hash`). Receivers keep the latest state of each source,
+//! seeded from the packet store, and apply a delta only on top of the state
+//! it was made from. A delta on a base they do not hold means they missed
+//! something, so they sync the source's full packets instead.
+//!
+//! Deltas apply in timestamp order: a delta no newer than the state it
+//! would update is ignored, so applying one twice changes nothing. The
+//! state a delta produces is not signed as a whole, so it is delivered but
+//! never stored or served to peers. It must still pass the checks a full
+//! packet would, including a fragility matching its state, before it
+//! replaces the known one.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::core::lagrangian::BankState;
+use crate::network::ingestion::DataPacket;
+
+/// Content hash of `state`, over the bit patterns of its fields in order
+pub fn state_hash(state: &BankState) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for field in [state.tier1_capital, state.total_assets, state.liquidity_coverage, state.entropy_index] {
+        hasher.update(field.to_bits().to_le_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// The fields of a source's state that changed since a base state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDelta {
+    /// Peer ID of the node reporting the state
+    pub source: String,
+    /// Timestamp of the new state (Unix epoch milliseconds)
+    pub timestamp: u64,
+    /// `state_hash` of the state the delta was made from
+    pub base: [u8; 32],
+    pub tier1_capital: Option<f64>,
+    pub total_assets: Option<f64>,
+    pub liquidity_coverage: Option<f64>,
+    pub entropy_index: Option<f64>,
+    /// Fragility of the new state, if it changed
+    pub fragility: Option<f64>,
+    /// Expiry of the new state, as in `DataPacket::expires_at`
+    pub expires_at: Option<u64>,
+}
+
+/// `new` if it differs from `old`
+fn changed(old: f64, new: f64) -> Option<f64> {
+    (old.to_bits() != new.to_bits()).then_some(new)
+}
+
+impl StateDelta {
+    /// Delta taking `base` to `next`, stamped with `next`'s source and timestamp
+    pub fn between(base: &DataPacket, next: &DataPacket) -> Self {
+        Self {
+            source: next.source.clone(),
+            timestamp: next.timestamp,
+            base: state_hash(&base.state),
+            tier1_capital: changed(base.state.tier1_capital, next.state.tier1_capital),
+            total_assets: changed(base.state.total_assets, next.state.total_assets),
+            liquidity_coverage: changed(base.state.liquidity_coverage, next.state.liquidity_coverage),
+            entropy_index: changed(base.state.entropy_index, next.state.entropy_index),
+            fragility: changed(base.fragility, next.fragility),
+            expires_at: next.expires_at,
+        }
+    }
+
+    /// `base` with the delta applied, or `None` if the delta was not made from it
+    ///
+    /// The result is unsigned and carries no proof.
+    pub fn apply(&self, base: &DataPacket) -> Option<DataPacket> {
+        if state_hash(&base.state) != self.base {
+            return None;
+        }
+        let state = &base.state;
+        Some(DataPacket {
+            timestamp: self.timestamp,
+            source: self.source.clone(),
+            state: BankState {
+                tier1_capital: self.tier1_capital.unwrap_or(state.tier1_capital),
+                total_assets: self.total_assets.unwrap_or(state.total_assets),
+                liquidity_coverage: self.liquidity_coverage.unwrap_or(state.liquidity_coverage),
+                entropy_index: self.entropy_index.unwrap_or(state.entropy_index),
+            },
+            fragility: self.fragility.unwrap_or(base.fragility),
+            signature: Vec::new(),
+            proof: None,
+            period: None,
+            envelope: None,
+            expires_at: self.expires_at,
+        })
+    }
+}
+
+/// What became of a received delta
+#[derive(Debug, Clone)]
+pub(crate) enum DeltaOutcome {
+    /// The source's new state
+    Applied(Box<DataPacket>),
+    /// The source's known state is as new or newer; nothing changed
+    Stale,
+    /// The source's state is unknown or not the delta's base
+    Mismatch,
+}
+
+/// Latest known state of each source, full or rebuilt from deltas
+#[derive(Debug, Default)]
+pub(crate) struct StateTracker {
+    latest: HashMap<String, DataPacket>,
+}
+
+impl StateTracker {
+    /// Take a full packet as its source's state, if it is newer than the known one
+    pub(crate) fn record(&mut self, packet: &DataPacket) {
+        if self.latest.get(&packet.source).is_none_or(|known| known.timestamp < packet.timestamp) {
+            self.latest.insert(packet.source.clone(), packet.clone());
+        }
+    }
+
+    pub(crate) fn knows(&self, source: &str) -> bool {
+        self.latest.contains_key(source)
+    }
+
+    /// State `delta` makes of its source's known state
+    ///
+    /// The known state is left as is until the new one is `record`ed, so a
+    /// state the receiver rejects does not become the base of later deltas.
+    pub(crate) fn rebuild(&self, delta: &StateDelta) -> DeltaOutcome {
+        let Some(known) = self.latest.get(&delta.source) else {
+            return DeltaOutcome::Mismatch;
+        };
+        if delta.timestamp <= known.timestamp {
+            return DeltaOutcome::Stale;
+        }
+        match delta.apply(known) {
+            Some(packet) => DeltaOutcome::Applied(Box::new(packet)),
+            None => DeltaOutcome::Mismatch,
+        }
+    }
+
+    /// Timestamp of `source`'s known state
+    pub(crate) fn timestamp(&self, source: &str) -> Option<u64> {
+        self.latest.get(source).map(|packet| packet.timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    fn packet(timestamp: u64, tier1_capital: f64, fragility: f64) -> DataPacket {
+        fixtures::packet()
+            .timestamp(timestamp)
+            .tier1_capital(tier1_capital)
+            .entropy_index(0.8)
+            .fragility(fragility)
+            .signature(vec![1])
+            .build()
+    }
+
+    #[test]
+    fn test_delta_carries_changes_and_applies_once_in_order() {
+        let (base, next) = (packet(1_000, 10_000.0, 0.2), packet(2_000, 9_000.0, 0.2));
+        let delta = StateDelta::between(&base, &next);
+        assert_eq!(delta.tier1_capital, Some(9_000.0));
+        assert_eq!((delta.total_assets, delta.fragility), (None, None));
+        let applied = delta.apply(&base).unwrap();
+        assert_eq!(state_hash(&applied.state), state_hash(&next.state));
+        assert!(applied.signature.is_empty());
+        assert!(delta.apply(&next).is_none());
+
+        let mut tracker = StateTracker::default();
+        assert!(matches!(tracker.rebuild(&delta), DeltaOutcome::Mismatch));
+        tracker.record(&base);
+        let DeltaOutcome::Applied(rebuilt) = tracker.rebuild(&delta) else {
+            panic!("delta on the known state not applied");
+        };
+        // Until recorded, the known state is unchanged
+        assert_eq!(tracker.timestamp("12D3KooWnode"), Some(1_000));
+        tracker.record(&rebuilt);
+        assert!(matches!(tracker.rebuild(&delta), DeltaOutcome::Stale));
+        // A full packet older than the rebuilt state does not replace it
+        tracker.record(&base);
+        assert_eq!(tracker.timestamp("12D3KooWnode"), Some(2_000));
+    }
+}
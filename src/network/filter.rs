@@ -0,0 +1,141 @@
+//! Subscription Filters
+//!
+//! Most consumers only care about some of the packets a node delivers: an
+//! alerting node wants high fragility from the banks it watches, not every
+//! report on the topic. A `PacketFilter` selects packets by fragility range,
+//! source, and any further predicate, and each filtered subscription gets
+//! its own channel of the packets that match.
+//!
+//! Filters see packets after validation and duplicate suppression, so a
+//! subscriber receives each report at most once.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::network::ingestion::DataPacket;
+
+/// Default number of matching packets buffered for a subscriber
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// Extra condition on a delivered packet
+pub type PacketPredicate = Box<dyn Fn(&DataPacket) -> bool + Send + Sync>;
+
+/// Which delivered packets a subscription receives
+///
+/// The default filter matches every packet; each `with_*` call narrows it.
+pub struct PacketFilter {
+    min_fragility: Option<f64>,
+    max_fragility: Option<f64>,
+    sources: Option<HashSet<String>>,
+    predicates: Vec<PacketPredicate>,
+    capacity: usize,
+}
+
+impl Default for PacketFilter {
+    fn default() -> Self {
+        Self {
+            min_fragility: None,
+            max_fragility: None,
+            sources: None,
+            predicates: Vec::new(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+
+impl fmt::Debug for PacketFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketFilter")
+            .field("min_fragility", &self.min_fragility)
+            .field("max_fragility", &self.max_fragility)
+            .field("sources", &self.sources)
+            .field("predicates", &self.predicates.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl PacketFilter {
+    /// Only match packets with fragility at least `min`
+    pub fn with_min_fragility(mut self, min: f64) -> Self {
+        self.min_fragility = Some(min);
+        self
+    }
+
+    /// Only match packets with fragility at most `max`
+    pub fn with_max_fragility(mut self, max: f64) -> Self {
+        self.max_fragility = Some(max);
+        self
+    }
+
+    /// Only match packets whose source is one of `sources`
+    pub fn with_sources<S: Into<String>>(mut self, sources: impl IntoIterator<Item = S>) -> Self {
+        self.sources = Some(sources.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Also require `predicate` to hold
+    pub fn with_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&DataPacket) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Buffer up to `capacity` (at least one) matching packets for the subscriber
+    ///
+    /// Matching packets arriving while the buffer is full are dropped and
+    /// counted against the subscription.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Number of matching packets buffered for the subscriber
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Whether `packet` passes every condition
+    pub fn matches(&self, packet: &DataPacket) -> bool {
+        !(self.min_fragility.is_some_and(|min| packet.fragility < min)
+            || self.max_fragility.is_some_and(|max| packet.fragility > max)
+            || self.sources.as_ref().is_some_and(|sources| !sources.contains(&packet.source)))
+            && self.predicates.iter().all(|predicate| predicate(packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+    use crate::core::lagrangian::BankState;
+
+    fn packet(source: &str, fragility: f64) -> DataPacket {
+        fixtures::packet().source(source).fragility(fragility).build()
+    }
+
+    #[test]
+    fn test_conditions_combine() {
+        assert!(PacketFilter::default().matches(&packet("bank-a", 0.0)));
+
+        let filter = PacketFilter::default()
+            .with_min_fragility(70.0)
+            .with_sources(["bank-a", "bank-b"])
+            .with_predicate(|packet| packet.state.liquidity_coverage > 1.0);
+        assert!(filter.matches(&packet("bank-a", 70.0)));
+        assert!(filter.matches(&packet("bank-b", 95.0)));
+        assert!(!filter.matches(&packet("bank-a", 69.9)));
+        assert!(!filter.matches(&packet("bank-c", 95.0)));
+        let thin = DataPacket {
+            state: BankState { liquidity_coverage: 0.8, ..packet("bank-a", 80.0).state },
+            ..packet("bank-a", 80.0)
+        };
+        assert!(!filter.matches(&thin));
+
+        let calm = PacketFilter::default().with_max_fragility(30.0);
+        assert!(calm.matches(&packet("bank-c", 30.0)));
+        assert!(!calm.matches(&packet("bank-c", 30.1)));
+    }
+}
@@ -0,0 +1,217 @@
+//! Timestamp Freshness
+//!
+//! A correctly signed packet can still be stale: a report replayed from last
+//! month, or one stamped hours ahead by a broken clock, would poison the
+//! latest-per-source aggregate. `FreshnessGuard` rejects packets stamped too
+//! far in the future or too long ago, and packets repeating the signer and
+//! timestamp of one already delivered, which a signer only sends by
+//! replaying or contradicting an earlier report. The signer is the peer whose
+//! key verified the packet, not its self-declared `source`, so nobody can
+//! shadow another node's reports by claiming its name. Messages that carry an
+//! expiry are refused once it has passed, allowing the same clock skew as a
+//! timestamp ahead of local time.
+//!
+//! Delivered (signer, timestamp) pairs are only remembered while they are
+//! within the age limit, since older packets are rejected anyway, and at
+//! most a fixed number of them; when full, the oldest pair is forgotten
+//! first. Times are Unix epoch milliseconds.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libp2p::PeerId;
+
+use crate::network::ingestion::DataPacket;
+
+/// Why a packet's timestamp was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FreshnessError {
+    /// Stamped `ahead` after local time, beyond the allowed skew
+    Future { timestamp: u64, ahead: Duration, limit: Duration },
+    /// Stamped `age` before local time, beyond the allowed age
+    TooOld { timestamp: u64, age: Duration, limit: Duration },
+    /// A packet from the same signer with the same timestamp was delivered
+    Replayed { signer: PeerId, timestamp: u64 },
+    /// Expired `ago` before local time, beyond the allowed skew
+    Expired { expires_at: u64, ago: Duration, limit: Duration },
+}
+
+impl fmt::Display for FreshnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreshnessError::Future { timestamp, ahead, limit } => {
+                write!(f, "future timestamp {} is {:?} ahead, over the {:?} skew limit", timestamp, ahead, limit)
+            }
+            FreshnessError::TooOld { timestamp, age, limit } => {
+                write!(f, "stale timestamp {} is {:?} old, over the {:?} age limit", timestamp, age, limit)
+            }
+            FreshnessError::Replayed { signer, timestamp } => {
+                write!(f, "replayed timestamp {} from {}", timestamp, signer)
+            }
+            FreshnessError::Expired { expires_at, ago, limit } => {
+                write!(f, "expired at {}, {:?} ago, over the {:?} skew limit", expires_at, ago, limit)
+            }
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Timestamp window and the delivered (signer, timestamp) pairs within it
+#[derive(Debug, Clone)]
+pub struct FreshnessGuard {
+    max_future: Duration,
+    max_age: Duration,
+    capacity: usize,
+    /// Ordered by timestamp, so the oldest are forgotten first
+    seen: BTreeSet<(u64, PeerId)>,
+}
+
+impl FreshnessGuard {
+    /// Accept timestamps up to `max_future` ahead and `max_age` behind local
+    /// time, remembering up to `capacity` (at least one) delivered pairs
+    pub fn new(max_future: Duration, max_age: Duration, capacity: usize) -> Self {
+        Self { max_future, max_age, capacity: capacity.max(1), seen: BTreeSet::new() }
+    }
+
+    /// Check that `packet`'s timestamp is within the window around local time
+    pub fn check_time(&self, packet: &DataPacket) -> Result<(), FreshnessError> {
+        self.check_time_at(packet, now_millis())
+    }
+
+    fn check_time_at(&self, packet: &DataPacket, now: u64) -> Result<(), FreshnessError> {
+        let timestamp = packet.timestamp;
+        if timestamp > now {
+            let ahead = Duration::from_millis(timestamp - now);
+            if ahead > self.max_future {
+                return Err(FreshnessError::Future { timestamp, ahead, limit: self.max_future });
+            }
+        } else {
+            let age = Duration::from_millis(now - timestamp);
+            if age > self.max_age {
+                return Err(FreshnessError::TooOld { timestamp, age, limit: self.max_age });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a message expiring at `expires_at`, if ever, has not expired
+    pub fn check_expiry(&self, expires_at: Option<u64>) -> Result<(), FreshnessError> {
+        self.check_expiry_at(expires_at, now_millis())
+    }
+
+    fn check_expiry_at(&self, expires_at: Option<u64>, now: u64) -> Result<(), FreshnessError> {
+        let Some(expires_at) = expires_at.filter(|expires_at| *expires_at < now) else {
+            return Ok(());
+        };
+        let ago = Duration::from_millis(now - expires_at);
+        if ago > self.max_future {
+            return Err(FreshnessError::Expired { expires_at, ago, limit: self.max_future });
+        }
+        Ok(())
+    }
+
+    /// Remember `packet`, verified as signed by `signer`, as delivered,
+    /// unless a packet from that signer with its timestamp already was
+    pub fn record(&mut self, signer: &PeerId, packet: &DataPacket) -> Result<(), FreshnessError> {
+        self.record_at(signer, packet, now_millis())
+    }
+
+    fn record_at(&mut self, signer: &PeerId, packet: &DataPacket, now: u64) -> Result<(), FreshnessError> {
+        let cutoff = now.saturating_sub(self.max_age.as_millis() as u64);
+        while self.seen.first().is_some_and(|(timestamp, _)| *timestamp < cutoff) {
+            self.seen.pop_first();
+        }
+        if !self.seen.insert((packet.timestamp, *signer)) {
+            return Err(FreshnessError::Replayed { signer: *signer, timestamp: packet.timestamp });
+        }
+        if self.seen.len() > self.capacity {
+            self.seen.pop_first();
+        }
+        Ok(())
+    }
+
+    /// Number of pairs remembered
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Whether no pairs are remembered
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    const NOW: u64 = 1_700_000_000_000;
+    const MINUTE: u64 = 60_000;
+
+    fn packet(source: &str, timestamp: u64) -> DataPacket {
+        fixtures::packet().source(source).timestamp(timestamp).build()
+    }
+
+    #[test]
+    fn test_time_window() {
+        let guard = FreshnessGuard::new(Duration::from_secs(60), Duration::from_secs(600), 16);
+        assert_eq!(guard.check_time_at(&packet("bank-a", NOW + MINUTE), NOW), Ok(()));
+        assert_eq!(guard.check_time_at(&packet("bank-a", NOW - 10 * MINUTE), NOW), Ok(()));
+        assert!(matches!(
+            guard.check_time_at(&packet("bank-a", NOW + MINUTE + 1), NOW),
+            Err(FreshnessError::Future { .. })
+        ));
+        assert!(matches!(
+            guard.check_time_at(&packet("bank-a", NOW - 30 * 24 * 60 * MINUTE), NOW),
+            Err(FreshnessError::TooOld { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expiry_allows_skew() {
+        let guard = FreshnessGuard::new(Duration::from_secs(60), Duration::from_secs(600), 16);
+        assert_eq!(guard.check_expiry_at(None, NOW), Ok(()));
+        assert_eq!(guard.check_expiry_at(Some(NOW + MINUTE), NOW), Ok(()));
+        // A receiver whose clock runs a little ahead still takes it
+        assert_eq!(guard.check_expiry_at(Some(NOW - MINUTE), NOW), Ok(()));
+        assert!(matches!(guard.check_expiry_at(Some(NOW - MINUTE - 1), NOW), Err(FreshnessError::Expired { .. })));
+    }
+
+    #[test]
+    fn test_pairs_bounded_by_age_and_capacity() {
+        let mut guard = FreshnessGuard::new(Duration::from_secs(60), Duration::from_secs(600), 3);
+        let (a, b) = (PeerId::random(), PeerId::random());
+        guard.record_at(&a, &packet("bank-a", NOW), NOW).unwrap();
+        guard.record_at(&b, &packet("bank-b", NOW), NOW).unwrap();
+        assert!(matches!(guard.record_at(&a, &packet("bank-a", NOW), NOW), Err(FreshnessError::Replayed { .. })));
+
+        // Past the age limit the pair is forgotten, as the time check rejects it anyway
+        guard.record_at(&a, &packet("bank-a", NOW + 11 * MINUTE), NOW + 11 * MINUTE).unwrap();
+        assert_eq!(guard.len(), 1);
+
+        // Over capacity the oldest pair goes first
+        for offset in 1..=3 {
+            guard.record_at(&a, &packet("bank-a", NOW + 11 * MINUTE + offset), NOW + 11 * MINUTE).unwrap();
+        }
+        assert_eq!(guard.len(), 3);
+        assert_eq!(guard.record_at(&a, &packet("bank-a", NOW + 11 * MINUTE), NOW + 11 * MINUTE), Ok(()));
+    }
+
+    #[test]
+    fn test_pairs_keyed_by_signer_not_claimed_source() {
+        let mut guard = FreshnessGuard::new(Duration::from_secs(60), Duration::from_secs(600), 16);
+        let (honest, impostor) = (PeerId::random(), PeerId::random());
+        guard.record_at(&impostor, &packet("bank-a", NOW), NOW).unwrap();
+        // Claiming bank-a's name first does not get bank-a's own report refused
+        assert_eq!(guard.record_at(&honest, &packet("bank-a", NOW), NOW), Ok(()));
+        assert_eq!(
+            guard.record_at(&honest, &packet("bank-b", NOW), NOW),
+            Err(FreshnessError::Replayed { signer: honest, timestamp: NOW })
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Peer Health
+//!
+//! A bank that stops reporting and a peer that has dropped off the network
+//! look the same from the gossip alone. Connected peers are pinged every
+//! heartbeat interval, and `HealthTracker` keeps when each was last heard
+//! from, by ping or by gossip, and a smoothed round-trip time. A connected
+//! peer silent for longer than the stale threshold is marked `Stale` once,
+//! and is `Alive` again as soon as it is heard from.
+//!
+//! The round-trip estimate is smoothed like TCP's: each sample moves it an
+//! eighth of the way. Times are Unix epoch milliseconds.
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Whether a connected peer has been heard from recently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerStatus {
+    /// Heard from within the stale threshold
+    #[default]
+    Alive,
+    /// Silent for longer than the stale threshold
+    Stale,
+}
+
+/// Liveness and latency of one connected peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerHealth {
+    /// When the peer was last heard from (Unix epoch milliseconds)
+    pub last_seen: u64,
+    /// Smoothed round-trip time, once a ping has been answered
+    pub rtt_ms: Option<u64>,
+    pub status: PeerStatus,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Health of every connected peer
+#[derive(Debug, Clone)]
+pub struct HealthTracker {
+    stale_after: Duration,
+    peers: HashMap<PeerId, PeerHealth>,
+}
+
+impl HealthTracker {
+    /// Mark peers silent for longer than `stale_after` as stale
+    pub fn new(stale_after: Duration) -> Self {
+        Self { stale_after, peers: HashMap::new() }
+    }
+
+    /// Start tracking `peer`, which just connected
+    pub fn connected(&mut self, peer: PeerId) {
+        self.connected_at(peer, now_millis())
+    }
+
+    pub(crate) fn connected_at(&mut self, peer: PeerId, now: u64) {
+        self.peers.insert(peer, PeerHealth { last_seen: now, rtt_ms: None, status: PeerStatus::Alive });
+    }
+
+    /// Stop tracking `peer`, whose last connection closed
+    pub fn disconnected(&mut self, peer: &PeerId) {
+        self.peers.remove(peer);
+    }
+
+    /// Note that `peer` was heard from, returning whether it was stale
+    pub fn heard(&mut self, peer: &PeerId) -> bool {
+        self.heard_at(peer, now_millis())
+    }
+
+    fn heard_at(&mut self, peer: &PeerId, now: u64) -> bool {
+        let Some(health) = self.peers.get_mut(peer) else {
+            return false;
+        };
+        health.last_seen = health.last_seen.max(now);
+        std::mem::replace(&mut health.status, PeerStatus::Alive) == PeerStatus::Stale
+    }
+
+    /// Note that `peer` answered a ping after `rtt`, returning whether it was stale
+    pub fn pinged(&mut self, peer: &PeerId, rtt: Duration) -> bool {
+        self.pinged_at(peer, rtt, now_millis())
+    }
+
+    fn pinged_at(&mut self, peer: &PeerId, rtt: Duration, now: u64) -> bool {
+        let sample = rtt.as_millis() as u64;
+        if let Some(health) = self.peers.get_mut(peer) {
+            health.rtt_ms = Some(match health.rtt_ms {
+                Some(estimate) => (estimate * 7 + sample) / 8,
+                None => sample,
+            });
+        }
+        self.heard_at(peer, now)
+    }
+
+    /// Mark peers silent past the threshold as stale, returning those newly
+    /// stale with how long each has been silent
+    pub fn sweep(&mut self) -> Vec<(PeerId, Duration)> {
+        self.sweep_at(now_millis())
+    }
+
+    pub(crate) fn sweep_at(&mut self, now: u64) -> Vec<(PeerId, Duration)> {
+        let mut stale = Vec::new();
+        for (peer, health) in &mut self.peers {
+            let silent = Duration::from_millis(now.saturating_sub(health.last_seen));
+            if health.status == PeerStatus::Alive && silent > self.stale_after {
+                health.status = PeerStatus::Stale;
+                stale.push((*peer, silent));
+            }
+        }
+        stale
+    }
+
+    /// Health of each connected peer
+    pub fn peers(&self) -> &HashMap<PeerId, PeerHealth> {
+        &self.peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_silent_peer_goes_stale_once() {
+        let mut tracker = HealthTracker::new(Duration::from_secs(60));
+        let (quiet, chatty) = (PeerId::random(), PeerId::random());
+        tracker.connected_at(quiet, NOW);
+        tracker.connected_at(chatty, NOW);
+        assert!(tracker.sweep_at(NOW + 60_000).is_empty());
+
+        tracker.heard_at(&chatty, NOW + 30_000);
+        assert_eq!(tracker.sweep_at(NOW + 60_001), vec![(quiet, Duration::from_millis(60_001))]);
+        assert_eq!(tracker.peers()[&quiet].status, PeerStatus::Stale);
+        assert_eq!(tracker.peers()[&chatty].status, PeerStatus::Alive);
+        assert!(tracker.sweep_at(NOW + 120_000).iter().all(|(peer, _)| *peer == chatty));
+
+        assert!(tracker.pinged_at(&quiet, Duration::from_millis(40), NOW + 130_000));
+        assert_eq!(tracker.peers()[&quiet].status, PeerStatus::Alive);
+        tracker.disconnected(&quiet);
+        assert!(!tracker.heard_at(&quiet, NOW + 140_000));
+        assert!(!tracker.peers().contains_key(&quiet));
+    }
+
+    #[test]
+    fn test_rtt_smoothed() {
+        let mut tracker = HealthTracker::new(Duration::from_secs(60));
+        let peer = PeerId::random();
+        tracker.connected_at(peer, NOW);
+        assert_eq!(tracker.peers()[&peer].rtt_ms, None);
+        tracker.pinged_at(&peer, Duration::from_millis(80), NOW);
+        assert_eq!(tracker.peers()[&peer].rtt_ms, Some(80));
+        tracker.pinged_at(&peer, Duration::from_millis(160), NOW);
+        assert_eq!(tracker.peers()[&peer].rtt_ms, Some(90));
+    }
+}
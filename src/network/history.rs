@@ -0,0 +1,156 @@
+//! Packet History Protocol
+//!
+//! Gossipsub only carries live traffic, so a node that was offline misses
+//! everything published meanwhile. Over the `/olo/history/1` request-response
+//! protocol a node asks a peer for the packets it holds from a timestamp on.
+//!
+//! Requests carry `since`, the earliest timestamp wanted, and `skip`, the
+//! number of matching packets already received, so a requester pages
+//! through the history with repeated requests. Each page holds at most
+//! `limit` packets, never more than `MAX_PAGE_PACKETS` or about
+//! `MAX_PAGE_BYTES`, and says whether more remain. Packets travel in their
+//! wire encoding and the requester checks each one's signature against the
+//! peer ID named as its source, since the server is not that peer.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use libp2p::StreamProtocol;
+
+use crate::network::ingestion::DataPacket;
+use crate::network::wire::{self, WireFormat};
+
+/// Protocol name of the history exchange
+pub const HISTORY_PROTOCOL: StreamProtocol = StreamProtocol::new("/olo/history/1");
+
+/// Most packets in one response
+pub const MAX_PAGE_PACKETS: usize = 256;
+
+/// Encoded size at which a response is cut short
+pub const MAX_PAGE_BYTES: usize = 1 << 20;
+
+/// Request for packets with timestamps from `since` on, oldest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRequest {
+    /// Earliest timestamp wanted (Unix epoch milliseconds)
+    pub since: u64,
+    /// Matching packets to pass over, having come in earlier pages
+    pub skip: u32,
+    /// Most packets wanted in this page
+    pub limit: u32,
+}
+
+/// One page of packets answering a `HistoryRequest`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryResponse {
+    /// Wire-encoded packets, oldest first
+    pub packets: Vec<Vec<u8>>,
+    /// Whether matching packets remain after this page
+    pub more: bool,
+}
+
+/// Errors fetching history from a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    /// The request could not be sent or was not answered
+    Request { reason: String },
+    /// No answer came within the timeout
+    Timeout,
+    /// The peer announced over identify that it does not serve history
+    Unsupported,
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Request { reason } => write!(f, "history request failed: {}", reason),
+            HistoryError::Timeout => write!(f, "history request timed out"),
+            HistoryError::Unsupported => write!(f, "peer does not serve history"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+/// Page of `packets` answering `request`, encoded in `format`
+///
+/// `packets` need not be sorted.
+pub fn serve<'a>(
+    packets: impl IntoIterator<Item = &'a DataPacket>,
+    request: &HistoryRequest,
+    format: WireFormat,
+) -> HistoryResponse {
+    let mut matching: Vec<&DataPacket> = packets
+        .into_iter()
+        .filter(|packet| packet.timestamp >= request.since)
+        .collect();
+    matching.sort_by_key(|packet| packet.timestamp);
+
+    let limit = (request.limit as usize).min(MAX_PAGE_PACKETS);
+    let mut response = HistoryResponse::default();
+    let mut bytes = 0;
+    let mut remaining = matching.into_iter().skip(request.skip as usize).peekable();
+    while let Some(packet) = remaining.peek() {
+        if response.packets.len() == limit {
+            break;
+        }
+        let Ok(encoded) = wire::encode(packet, format) else {
+            remaining.next();
+            continue;
+        };
+        if !response.packets.is_empty() && bytes + encoded.len() > MAX_PAGE_BYTES {
+            break;
+        }
+        bytes += encoded.len();
+        response.packets.push(encoded);
+        remaining.next();
+    }
+    response.more = remaining.peek().is_some();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    fn packet(timestamp: u64) -> DataPacket {
+        fixtures::packet().timestamp(timestamp).signature(vec![0; 64]).build()
+    }
+
+    fn timestamps(response: &HistoryResponse) -> Vec<u64> {
+        response
+            .packets
+            .iter()
+            .map(|bytes| wire::decode(bytes).unwrap().0.timestamp)
+            .collect()
+    }
+
+    #[test]
+    fn test_pages_oldest_first_from_since() {
+        let packets: Vec<_> = [50, 10, 40, 20, 30].into_iter().map(packet).collect();
+        let first = serve(&packets, &HistoryRequest { since: 20, skip: 0, limit: 2 }, WireFormat::Json);
+        assert_eq!(timestamps(&first), vec![20, 30]);
+        assert!(first.more);
+        let second = serve(&packets, &HistoryRequest { since: 20, skip: 2, limit: 2 }, WireFormat::Cbor);
+        assert_eq!(timestamps(&second), vec![40, 50]);
+        assert!(!second.more);
+        let past_end = serve(&packets, &HistoryRequest { since: 20, skip: 4, limit: 2 }, WireFormat::Json);
+        assert!(past_end.packets.is_empty() && !past_end.more);
+    }
+
+    #[test]
+    fn test_page_size_capped() {
+        let packets: Vec<_> = (0..MAX_PAGE_PACKETS as u64 + 10).map(packet).collect();
+        let page = serve(&packets, &HistoryRequest { since: 0, skip: 0, limit: u32::MAX }, WireFormat::Bincode);
+        assert_eq!(page.packets.len(), MAX_PAGE_PACKETS);
+        assert!(page.more);
+
+        let mut bulky = packet(0);
+        bulky.proof = Some(vec![7; MAX_PAGE_BYTES / 3]);
+        let bulky: Vec<_> = (0..4).map(|t| DataPacket { timestamp: t, ..bulky.clone() }).collect();
+        let page = serve(&bulky, &HistoryRequest { since: 0, skip: 0, limit: 10 }, WireFormat::Bincode);
+        assert_eq!(page.packets.len(), 2);
+        assert!(page.more);
+    }
+}
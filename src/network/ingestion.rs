@@ -4,17 +4,83 @@
 //! Enables sovereign nodes to share fragility signals without central authority.
 
 use libp2p::{
-    gossipsub::{self, Gossipsub, GossipsubEvent, MessageAuthenticity, ValidationMode},
-    identity::Keypair,
-    swarm::{SwarmBuilder, SwarmEvent},
-    Multiaddr, PeerId, Swarm,
+    allow_block_list::{self, BlockedPeers},
+    autonat,
+    core::{
+        muxing::StreamMuxerBox,
+        transport::{Boxed, ListenerId, OptionalTransport},
+        upgrade,
+    },
+    futures::{stream, Stream, StreamExt},
+    gossipsub::{self, MessageAuthenticity, ValidationMode},
+    identify,
+    identity::{self, Keypair},
+    kad::{self, store::MemoryStore, QueryId, QueryResult},
+    mdns,
+    multiaddr::Protocol,
+    noise, ping, relay,
+    request_response::{self, ProtocolSupport},
+    swarm::{behaviour::toggle::Toggle, dial_opts::DialOpts, NetworkBehaviour, SwarmEvent},
+    tcp, websocket, yamux, Multiaddr, PeerId, StreamProtocol, Swarm, Transport,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
 
-use crate::core::lagrangian::BankState;
+use crate::core::lagrangian::{compute_fragility, BankState, LagrangianConfig};
+use crate::proofs::envelope::ProofEnvelope;
+use crate::proofs::error::ProofError;
+use crate::proofs::nullifier::NullifierSet;
+use crate::proofs::packet::{packet_identity, verify_packet, PacketVerdict};
+use crate::network::acl::{AccessDenial, AccessList};
+use crate::network::aggregate::{AggregateSnapshot, AggregationState};
+use crate::network::builder::EngineBuilder;
+use crate::network::dedup::SeenCache;
+use crate::network::delta::{DeltaOutcome, StateDelta, StateTracker};
+use crate::network::filter::PacketFilter;
+use crate::network::freshness::{FreshnessError, FreshnessGuard};
+use crate::network::health::{HealthTracker, PeerHealth};
+use crate::network::message::{MessageKind, NetworkMessage};
+use crate::network::metrics::{MetricsSnapshot, NetworkMetrics, RejectReason};
+use crate::network::nat::{self, Reachability, ReachabilityTracker};
+use crate::network::outbound::{self, Outbound, OutboundQueue, OverflowPolicy, PacketSender};
+use crate::network::outbox::{Outbox, OutboxEntry};
+use crate::network::peer_info::{self, Capabilities, PeerInfo};
+use crate::network::psk::TopicCipher;
+use crate::network::retry::{DeferredQueue, RetryPolicy};
+use crate::network::history::{self, HistoryError, HistoryRequest, HistoryResponse, HISTORY_PROTOCOL, MAX_PAGE_PACKETS};
+use crate::network::rate_limit::RateLimiter;
+use crate::network::reputation::{Conduct, PeerReputation};
+use crate::network::store::{FileStore, InMemoryStore, PacketStore, StoreError};
+use crate::network::sync::{self, SyncRequest, SyncResponse, SYNC_PROTOCOL};
+use crate::network::validation::PacketValidator;
+use crate::network::wire::{self, Compression, WireError, WireFormat};
+use crate::proofs::verifier::FragilityVerifier;
+
+/// Leading bytes of a packet's signed encoding
+const PACKET_MAGIC: &[u8; 4] = b"OLDP";
+
+/// How long one bootstrap dial may take before it counts as failed
+const DIAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long one step of a DHT query may take before it counts as failed
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a peer may take to answer one history request
+const HISTORY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest `shutdown` keeps driving connections to send flushed packets
+const FLUSH_GRACE: Duration = Duration::from_millis(250);
+
+/// Relative tolerance of a delta-built state's fragility against its recomputed one
+const FRAGILITY_TOLERANCE: f64 = 1e-6;
 
 /// Financial data packet for P2P network
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,8 +93,441 @@ pub struct DataPacket {
     pub state: BankState,
     /// Fragility score
     pub fragility: f64,
-    /// Signature (verification)
+    /// Publishing node's signature over `signing_bytes`, empty until signed
     pub signature: Vec<u8>,
+    /// Compressed Groth16 fragility proof (see `proofs::prover::serialize_proof`)
+    #[serde(default)]
+    pub proof: Option<Vec<u8>>,
+    /// Reporting period the proof is bound to, if any
+    #[serde(default)]
+    pub period: Option<u64>,
+    /// Signed envelope around `proof`, binding it to the prover's identity
+    #[serde(default)]
+    pub envelope: Option<ProofEnvelope>,
+    /// When the packet stops being worth delivering (Unix epoch
+    /// milliseconds); `None` if it never does
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl DataPacket {
+    /// Check the attached proof against the advertised state and fragility
+    ///
+    /// The proof commits to the state it was made from, so a packet whose
+    /// state differs from the proven one does not verify. Neither does a
+    /// packet without a proof. See `proofs::packet::verify_packet` for why a
+    /// packet failed.
+    pub fn verify_proof(&self, verifier: &FragilityVerifier) -> Result<bool, ProofError> {
+        verify_packet(self, verifier).map(|verdict| verdict.is_valid())
+    }
+
+    /// Canonical encoding of the signed fields: timestamp, source, state,
+    /// fragility, and expiry
+    ///
+    /// Amounts are encoded by their IEEE 754 bits, so any change to them,
+    /// however small, changes the encoding. The expiry is only appended when
+    /// set, so packets without one sign as they did before it existed.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(62 + self.source.len());
+        bytes.extend_from_slice(PACKET_MAGIC);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&(self.source.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(self.source.as_bytes());
+        for value in [
+            self.state.tier1_capital,
+            self.state.total_assets,
+            self.state.liquidity_coverage,
+            self.state.entropy_index,
+            self.fragility,
+        ] {
+            bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+        }
+        if let Some(expires_at) = self.expires_at {
+            bytes.extend_from_slice(&expires_at.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Sign `signing_bytes` with the node's key, replacing any previous signature
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), PacketError> {
+        self.signature = keypair
+            .sign(&self.signing_bytes())
+            .map_err(|e| PacketError::Signing { reason: e.to_string() })?;
+        Ok(())
+    }
+
+    /// Check the packet is signed by `public_key`
+    pub fn verify(&self, public_key: &identity::PublicKey) -> Result<(), PacketError> {
+        if self.signature.is_empty() {
+            return Err(PacketError::Unsigned);
+        }
+        if !public_key.verify(&self.signing_bytes(), &self.signature) {
+            return Err(PacketError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// Errors signing or checking a `DataPacket`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    /// The packet carries no signature
+    Unsigned,
+    /// The signature does not match the packet or the key
+    InvalidSignature,
+    /// The node's key could not sign
+    Signing { reason: String },
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketError::Unsigned => write!(f, "packet is not signed"),
+            PacketError::InvalidSignature => write!(f, "packet signature does not verify"),
+            PacketError::Signing { reason } => write!(f, "signing packet failed: {}", reason),
+        }
+    }
+}
+
+impl Error for PacketError {}
+
+/// Errors loading or creating a node's identity keyfile
+#[derive(Debug)]
+pub enum IdentityError {
+    /// Reading or writing the keyfile failed
+    Io { path: PathBuf, source: io::Error },
+    /// The keyfile does not hold a protobuf-encoded libp2p keypair
+    Corrupt { path: PathBuf, reason: String },
+    /// A new keypair could not be encoded for saving
+    Encode { path: PathBuf, reason: String },
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentityError::Io { path, source } => {
+                write!(f, "identity keyfile I/O failed for {}: {}", path.display(), source)
+            }
+            IdentityError::Corrupt { path, reason } => {
+                write!(f, "identity keyfile {} is corrupt: {}", path.display(), reason)
+            }
+            IdentityError::Encode { path, reason } => {
+                write!(f, "identity for {} could not be encoded: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl Error for IdentityError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IdentityError::Io { source, .. } => Some(source),
+            IdentityError::Corrupt { .. } | IdentityError::Encode { .. } => None,
+        }
+    }
+}
+
+/// Keypair stored at `path`, generated and saved there on first use
+///
+/// The file holds the keypair's libp2p protobuf encoding and is created
+/// readable by its owner only.
+pub fn load_or_create_identity(path: &Path) -> Result<Keypair, IdentityError> {
+    let io_error = |source| IdentityError::Io { path: path.to_path_buf(), source };
+    match fs::read(path) {
+        Ok(bytes) => {
+            return Keypair::from_protobuf_encoding(&bytes).map_err(|e| IdentityError::Corrupt {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            });
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(io_error(e)),
+    }
+
+    let keypair = Keypair::generate_ed25519();
+    let bytes = keypair.to_protobuf_encoding().map_err(|e| IdentityError::Encode {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(io_error)?;
+    file.write_all(&bytes).and_then(|_| file.sync_all()).map_err(io_error)?;
+    Ok(keypair)
+}
+
+/// Public key inlined in `peer`, as it is in every ed25519 peer ID
+fn public_key_of(peer: &PeerId) -> Option<identity::PublicKey> {
+    let hash = peer.as_ref();
+    // Multihash code 0 is the identity hash: the digest is the key itself
+    if hash.code() != 0 {
+        return None;
+    }
+    identity::PublicKey::try_decode_protobuf(hash.digest()).ok()
+}
+
+/// Peer ID of the libp2p identity with ed25519 public key `key`
+fn peer_id_of(key: &[u8; 32]) -> Option<PeerId> {
+    let key = identity::ed25519::PublicKey::try_from_bytes(key).ok()?;
+    Some(PeerId::from(identity::PublicKey::from(key)))
+}
+
+/// Check that `packet`'s proof is bound to `source`, the peer that published it
+///
+/// The envelope's key must be the one behind `source`, and the packet and
+/// envelope must both name `source` as their origin; anything else, including
+/// a packet with no binding envelope, is an `IdentityMismatch`.
+pub fn identity_verdict(packet: &DataPacket, source: Option<&PeerId>) -> PacketVerdict {
+    let bound = packet_identity(packet).and_then(|key| peer_id_of(key.as_bytes()));
+    match (bound, source, &packet.envelope) {
+        (Some(bound), Some(source), Some(envelope))
+            if bound == *source && packet.source == source.to_string() && envelope.prover == packet.source =>
+        {
+            PacketVerdict::Valid
+        }
+        _ => PacketVerdict::IdentityMismatch,
+    }
+}
+
+/// What to do with received packets that fail the proof or replay checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProofPolicy {
+    /// Discard the packet
+    #[default]
+    Drop,
+    /// Deliver the packet anyway and count it against its source
+    Flag,
+}
+
+/// Whether the node answers DHT queries from other peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DhtMode {
+    /// Query the DHT but do not serve records; for nodes peers cannot dial
+    Client,
+    /// Query the DHT and serve records to other peers
+    #[default]
+    Server,
+}
+
+/// Which side of the data flow a node takes part in
+///
+/// Every role forwards gossip as the protocol requires; the role only
+/// decides what the node does with it itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeRole {
+    /// Publish packets and deliver, store, and aggregate those received
+    #[default]
+    Full,
+    /// Publish packets but deliver, store, and aggregate none received,
+    /// as a data vendor does; it keeps no store or aggregate at all
+    PublisherOnly,
+    /// Deliver, store, and aggregate received packets but publish none,
+    /// as an analytics node does
+    ObserverOnly,
+}
+
+impl fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeRole::Full => write!(f, "full"),
+            NodeRole::PublisherOnly => write!(f, "publisher-only"),
+            NodeRole::ObserverOnly => write!(f, "observer-only"),
+        }
+    }
+}
+
+/// A gossipsub topic a node subscribes to from the start
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicConfig {
+    /// Topic name, such as `olo-fragility-eu`
+    pub name: String,
+    /// Whether `bootstrap_dht` advertises this node as a provider of the topic
+    pub advertise: bool,
+    /// Pre-shared keys making the topic private: payloads are sealed with
+    /// the first and opened with any, so keys can be rotated. Empty leaves
+    /// the topic open. A node with a private topic serves no history or sync
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl TopicConfig {
+    /// Advertised open topic called `name`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), advertise: true, keys: vec![] }
+    }
+
+    /// Make the topic private to holders of `keys`, sealing with the first
+    pub fn with_keys(mut self, keys: Vec<Vec<u8>>) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+/// Gossipsub protocol parameters
+///
+/// The defaults suit a node reporting every few minutes; high-frequency
+/// deployments shorten the heartbeat and raise the message size limit.
+/// Inconsistent values, such as mesh bounds out of order, are reported as
+/// `NetworkError::GossipsubConfig` when the engine is built.
+#[derive(Debug, Clone)]
+pub struct GossipsubTuning {
+    /// Time between mesh maintenance rounds
+    pub heartbeat_interval: Duration,
+    /// Peers each topic's mesh aims for
+    pub mesh_n: usize,
+    /// Mesh size below which more peers are grafted
+    pub mesh_n_low: usize,
+    /// Mesh size above which peers are pruned
+    pub mesh_n_high: usize,
+    /// Heartbeats a message is kept for answering peers' requests
+    pub history_length: usize,
+    /// Largest message sent or accepted, in bytes; larger publishes fail
+    pub max_transmit_size: usize,
+    /// Which signature and sequence-number checks received messages get
+    pub validation_mode: ValidationMode,
+}
+
+impl Default for GossipsubTuning {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(10),
+            mesh_n: 6,
+            mesh_n_low: 5,
+            mesh_n_high: 12,
+            history_length: 5,
+            max_transmit_size: 65_536,
+            validation_mode: ValidationMode::Strict,
+        }
+    }
+}
+
+/// Errors building an engine from a `NetworkConfig`
+#[derive(Debug)]
+pub enum NetworkError {
+    /// `listen_addr`, or `ws_listen_addr` with WebSocket enabled, is not a multiaddr
+    InvalidListenAddr { addr: String, reason: String },
+    /// Gossipsub rejected the tuning
+    GossipsubConfig { reason: String },
+    /// `topics` is empty, leaving nowhere to publish
+    NoTopics,
+    /// Gossipsub refused to subscribe to a configured topic
+    SubscriptionFailed { topic: String, reason: String },
+    /// `kad_protocol` is not a protocol name, which must start with `/`
+    InvalidKadProtocol { name: String, reason: String },
+    /// An entry of `relay_addresses` is not a multiaddr ending in a peer ID
+    InvalidRelayAddress { addr: String, reason: String },
+    /// Reading or writing the identity keyfile failed
+    IdentityIo { path: PathBuf, source: io::Error },
+    /// The identity keyfile is corrupt, or a new identity could not be saved to it
+    InvalidIdentity { path: PathBuf, reason: String },
+    /// mDNS could not open its socket
+    Mdns { source: io::Error },
+    /// The transport could not be set up
+    Transport { reason: String },
+    /// The packet store at `store_path` could not be opened
+    Store(StoreError),
+    /// The outbox could not be opened, or a message could not be written to it
+    Outbox(StoreError),
+    /// No peer is known to subscribe to `topic`, so nothing was published
+    NoPeers { topic: String },
+    /// The encoded message of `size` bytes is over gossipsub's `limit`
+    MessageTooLarge { size: usize, limit: usize },
+    /// Gossipsub has already published this exact message
+    Duplicate,
+    /// The packet could not be signed
+    Signing(PacketError),
+    /// The message could not be encoded
+    Encode(WireError),
+    /// Gossipsub refused the message for another reason
+    Publish { reason: String },
+    /// The node's role is `NodeRole::ObserverOnly`, which publishes nothing
+    NotPublisher,
+    /// The message expired at `expires_at` before it could be sent
+    Expired { expires_at: u64 },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::InvalidListenAddr { addr, reason } => write!(f, "invalid listen address {:?}: {}", addr, reason),
+            NetworkError::GossipsubConfig { reason } => write!(f, "invalid gossipsub config: {}", reason),
+            NetworkError::NoTopics => write!(f, "no topics configured"),
+            NetworkError::SubscriptionFailed { topic, reason } => {
+                write!(f, "failed to subscribe to {:?}: {}", topic, reason)
+            }
+            NetworkError::InvalidKadProtocol { name, reason } => {
+                write!(f, "invalid Kademlia protocol name {:?}: {}", name, reason)
+            }
+            NetworkError::InvalidRelayAddress { addr, reason } => {
+                write!(f, "invalid relay address {:?}: {}", addr, reason)
+            }
+            NetworkError::IdentityIo { path, source } => {
+                write!(f, "identity keyfile I/O failed for {}: {}", path.display(), source)
+            }
+            NetworkError::InvalidIdentity { path, reason } => {
+                write!(f, "identity keyfile {} is unusable: {}", path.display(), reason)
+            }
+            NetworkError::Mdns { source } => write!(f, "mDNS failed to start: {}", source),
+            NetworkError::Transport { reason } => write!(f, "transport setup failed: {}", reason),
+            NetworkError::Store(e) => write!(f, "packet store failed to open: {}", e),
+            NetworkError::Outbox(e) => write!(f, "outbox failed: {}", e),
+            NetworkError::NoPeers { topic } => write!(f, "no peers subscribed to {:?}", topic),
+            NetworkError::MessageTooLarge { size, limit } => {
+                write!(f, "message of {} bytes is over the {} byte limit", size, limit)
+            }
+            NetworkError::Duplicate => write!(f, "message was already published"),
+            NetworkError::Signing(e) => write!(f, "packet signing failed: {}", e),
+            NetworkError::Encode(e) => write!(f, "message encoding failed: {}", e),
+            NetworkError::Publish { reason } => write!(f, "publish failed: {}", reason),
+            NetworkError::NotPublisher => write!(f, "an {} node does not publish", NodeRole::ObserverOnly),
+            NetworkError::Expired { expires_at } => write!(f, "message expired at {} before it was sent", expires_at),
+        }
+    }
+}
+
+impl Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetworkError::IdentityIo { source, .. } | NetworkError::Mdns { source } => Some(source),
+            NetworkError::Store(e) | NetworkError::Outbox(e) => Some(e),
+            NetworkError::Signing(e) => Some(e),
+            NetworkError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<IdentityError> for NetworkError {
+    fn from(error: IdentityError) -> Self {
+        match error {
+            IdentityError::Io { path, source } => NetworkError::IdentityIo { path, source },
+            IdentityError::Corrupt { path, reason } | IdentityError::Encode { path, reason } => {
+                NetworkError::InvalidIdentity { path, reason }
+            }
+        }
+    }
+}
+
+impl From<StoreError> for NetworkError {
+    fn from(error: StoreError) -> Self {
+        NetworkError::Store(error)
+    }
+}
+
+impl From<PacketError> for NetworkError {
+    fn from(error: PacketError) -> Self {
+        NetworkError::Signing(error)
+    }
+}
+
+impl From<WireError> for NetworkError {
+    fn from(error: WireError) -> Self {
+        NetworkError::Encode(error)
+    }
 }
 
 /// Network configuration
@@ -36,151 +535,4219 @@ pub struct DataPacket {
 pub struct NetworkConfig {
     /// Listen address
     pub listen_addr: String,
-    /// Bootstrap peers
+    /// Add the WebSocket transport, so the node can listen on and dial
+    /// `/ws` addresses, as browser clients need
+    pub enable_websocket: bool,
+    /// WebSocket listen address, used by `listen_configured` when
+    /// `enable_websocket` is set
+    pub ws_listen_addr: String,
+    /// Whether the node publishes, consumes received packets, or both
+    pub role: NodeRole,
+    /// Bootstrap peers, as multiaddrs dialed by `connect_bootstrap`
     pub bootstrap_peers: Vec<String>,
-    /// Topic for gossipsub
-    pub topic: String,
+    /// Gossipsub topics subscribed to at startup; the first is where
+    /// `publish` and the sender publish
+    pub topics: Vec<TopicConfig>,
+    /// Gossipsub parameters; `None` uses `GossipsubTuning::default()`
+    pub gossipsub: Option<GossipsubTuning>,
+    /// Dial attempts per bootstrap peer before giving up
+    pub bootstrap_attempts: u32,
+    /// Wait after the first failed dial, doubled after each further failure
+    pub bootstrap_backoff: Duration,
+    /// Discover and dial peers on the local network with mDNS
+    pub enable_mdns: bool,
+    /// Only peers mDNS may report and dial; `None` allows any
+    pub discovery_allowlist: Option<Vec<PeerId>>,
+    /// Only peers that may connect and whose gossip is accepted; empty allows any
+    pub allowlist: Vec<PeerId>,
+    /// Peers never allowed to connect or have their gossip accepted
+    pub denylist: Vec<PeerId>,
+    /// Probe whether peers can dial this node with AutoNAT
+    pub enable_autonat: bool,
+    /// Circuit relays to reserve a slot on when the node is not publicly
+    /// reachable, as multiaddrs ending in the relay's `/p2p/` peer ID.
+    /// Without AutoNAT the node is assumed unreachable and reserves at once
+    pub relay_addresses: Vec<String>,
+    /// How often each connected peer is pinged
+    pub ping_interval: Duration,
+    /// How long a connected peer may go unheard before it is reported stale
+    pub stale_after: Duration,
+    /// Kademlia protocol name; only nodes using the same name share a DHT
+    pub kad_protocol: String,
+    /// Whether this node serves DHT records or only queries them
+    pub kad_mode: DhtMode,
+    /// Keyfile holding the node's identity; `None` uses a new identity each run
+    pub identity_path: Option<PathBuf>,
+    /// Delivered reports remembered for duplicate suppression
+    pub dedup_capacity: usize,
+    /// How long a delivered report is remembered
+    pub dedup_ttl: Duration,
+    /// How far ahead of local time a packet may be stamped
+    pub max_future_skew: Duration,
+    /// How far behind local time a packet may be stamped
+    pub max_packet_age: Duration,
+    /// Delivered (source, timestamp) pairs remembered to reject replays
+    pub replay_capacity: usize,
+    /// Encoding of published packets; received packets may use any format
+    pub wire_format: WireFormat,
+    /// When published messages are compressed; `None` never compresses.
+    /// Compressed messages are always read
+    pub compression: Option<Compression>,
+    /// Largest size a received compressed message may expand to
+    pub max_decompressed_size: usize,
+    /// Packets accepted from each source per minute; `None` is unlimited
+    pub max_packets_per_peer_per_minute: Option<u32>,
+    /// Packets a source may send at once before the per-minute rate applies
+    pub rate_limit_burst: u32,
+    /// Sources exempt from rate limiting
+    pub rate_limit_exempt: Vec<PeerId>,
+    /// Reputation score at or below which a source is banned
+    pub ban_threshold: i32,
+    /// How long a banned source stays disconnected and ignored
+    pub ban_duration: Duration,
+    /// Age at which a source's latest packet drops out of the aggregate
+    pub aggregate_staleness: Duration,
+    /// How often to publish the aggregate snapshot; `None`, or any role
+    /// but `Full`, never does
+    pub aggregate_publish_interval: Option<Duration>,
+    /// Log file keeping delivered and published packets across restarts;
+    /// `None` keeps them in memory, and a `PublisherOnly` role keeps none
+    pub store_path: Option<PathBuf>,
+    /// Packets kept when `store_path` is unset
+    pub store_capacity: usize,
+    /// How far back to reconcile stored packets with each newly connected
+    /// peer; `None` never starts a sync, though peers' requests are answered,
+    /// and a `PublisherOnly` role neither starts nor answers one
+    pub sync_horizon: Option<Duration>,
+    /// Most encoded bytes of packets taken from a peer in one sync
+    pub sync_max_bytes: usize,
+    /// Whether messages published while no peer subscribes to their topic
+    /// wait for one; `None` fails such publishes with `NetworkError::NoPeers`.
+    /// Unused with an outbox, which keeps such messages until they are sent
+    pub publish_retry: Option<RetryPolicy>,
+    /// Log file keeping published messages until gossipsub accepts them, so
+    /// they are sent even if the node restarts first; `None` keeps none.
+    /// Pair it with `identity_path`: peers reject packets replayed under
+    /// another identity than the one that signed them
+    pub outbox_path: Option<PathBuf>,
+    /// Packets the sender queue holds before `overflow_policy` applies
+    pub outbound_capacity: usize,
+    /// What a full sender queue does with another packet
+    pub overflow_policy: OverflowPolicy,
 }
 
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
-            listen_addr: \"/ip4/0.0.0.0/tcp/0\".to_string(),
+            listen_addr: "/ip4/0.0.0.0/tcp/0".to_string(),
+            enable_websocket: false,
+            ws_listen_addr: "/ip4/0.0.0.0/tcp/0/ws".to_string(),
+            role: NodeRole::default(),
             bootstrap_peers: vec![],
-            topic: \"olo-fragility\".to_string(),
+            topics: vec![TopicConfig::new("olo-fragility")],
+            gossipsub: None,
+            bootstrap_attempts: 5,
+            bootstrap_backoff: Duration::from_millis(500),
+            enable_mdns: false,
+            discovery_allowlist: None,
+            allowlist: vec![],
+            denylist: vec![],
+            enable_autonat: false,
+            relay_addresses: vec![],
+            ping_interval: Duration::from_secs(15),
+            stale_after: Duration::from_secs(60),
+            kad_protocol: "/olo/kad/1.0.0".to_string(),
+            kad_mode: DhtMode::default(),
+            identity_path: None,
+            dedup_capacity: 4096,
+            dedup_ttl: Duration::from_secs(600),
+            max_future_skew: Duration::from_secs(300),
+            max_packet_age: Duration::from_secs(300),
+            replay_capacity: 16_384,
+            wire_format: WireFormat::default(),
+            compression: None,
+            max_decompressed_size: wire::DEFAULT_DECOMPRESSION_LIMIT,
+            max_packets_per_peer_per_minute: Some(600),
+            rate_limit_burst: 60,
+            rate_limit_exempt: vec![],
+            ban_threshold: -50,
+            ban_duration: Duration::from_secs(3600),
+            aggregate_staleness: Duration::from_secs(900),
+            aggregate_publish_interval: None,
+            store_path: None,
+            store_capacity: 10_000,
+            sync_horizon: None,
+            sync_max_bytes: 1 << 20,
+            publish_retry: None,
+            outbox_path: None,
+            outbound_capacity: 1000,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+/// Result of dialing one bootstrap peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BootstrapOutcome {
+    /// Connected to `peer` on attempt number `attempts`
+    Connected { peer: PeerId, attempts: u32 },
+    /// The entry does not parse as a multiaddr
+    InvalidAddress { reason: String },
+    /// Every attempt failed; `error` is the last failure
+    Unreachable { attempts: u32, error: String },
+}
+
+/// Outcomes of `connect_bootstrap`, in `bootstrap_peers` order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootstrapReport {
+    /// Each configured entry with what became of it
+    pub outcomes: Vec<(String, BootstrapOutcome)>,
+}
+
+impl BootstrapReport {
+    /// Number of bootstrap peers connected to
+    pub fn connected(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, BootstrapOutcome::Connected { .. }))
+            .count()
+    }
+}
+
+/// Something the engine observed on the network
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// A packet gossiped on `topic` that passed the enabled checks
+    PacketReceived { topic: String, packet: DataPacket },
+    /// A message of another kind than `Fragility` or `StateDelta` gossiped on `topic`
+    MessageReceived { topic: String, message: NetworkMessage },
+    /// A `StateDelta` gossiped on `topic` applied to its source's known
+    /// state; `packet` is the new state, unsigned and without a proof
+    DeltaApplied { topic: String, packet: DataPacket },
+    /// A delta from `source` was not made from its known state, so the node
+    /// asked `peer` over sync for the full packets it lacks
+    ResyncRequested { peer: PeerId, source: String },
+    /// A signed packet that could not be decoded or failed validation
+    PacketRejected { reason: String },
+    /// A packet from `peer` was over its rate limit; `dropped` counts the
+    /// packets dropped from it since it was last under the limit
+    RateLimited { peer: PeerId, dropped: u64 },
+    /// `peer`'s reputation fell to the ban threshold; it is disconnected and
+    /// ignored for `duration`
+    PeerBanned { peer: PeerId, duration: Duration },
+    /// `peer` connected or authored a gossip message but is not admitted by
+    /// the allowlist and denylist; its connections are closed
+    PeerRejected { peer: PeerId, reason: AccessDenial },
+    /// The first connection to `PeerId` is up
+    PeerConnected(PeerId),
+    /// `peer` told the node what software it runs, kept as its `peer_info`
+    PeerIdentified { peer: PeerId, agent_version: String },
+    /// The last connection to `PeerId` closed
+    PeerDisconnected(PeerId),
+    /// Connected `peer` has not been heard from, by ping or gossip, for `silent`
+    PeerStale { peer: PeerId, silent: Duration },
+    /// A stale peer was heard from again
+    PeerRecovered(PeerId),
+    /// A sync with `peer` brought in `packets` packets this node was
+    /// missing; `truncated` if the peer held more than the byte budget allowed
+    Synced { peer: PeerId, packets: usize, truncated: bool },
+    /// A listener bound this address
+    ListeningOn(Multiaddr),
+    /// AutoNAT came to a new conclusion about whether peers can dial the node
+    ReachabilityChanged(Reachability),
+    /// A packet from the sender, or the periodic aggregate, could not be published
+    PublishFailed { error: String },
+    /// No peer subscribes to `topic` yet, so `message` waits for one in the
+    /// outbox or under the `publish_retry` policy
+    PublishDeferred { topic: String, message: NetworkMessage },
+    /// A deferred `message` found no peer on `topic` within the retry TTL,
+    /// or passed its own expiry, and was dropped
+    PublishExpired { topic: String, message: NetworkMessage },
+    /// The sender queue was full and `dropped` packets were discarded under
+    /// the configured `OverflowPolicy`
+    OutboundDropped { dropped: u64 },
+    /// mDNS found an allowed `peer` at `addr`, which is being dialed
+    PeerDiscovered { peer: PeerId, addr: Multiaddr },
+    /// mDNS no longer sees `peer` at `addr`
+    PeerExpired { peer: PeerId, addr: Multiaddr },
+    /// `shutdown` ran; the last event the engine produces
+    ShuttingDown,
+}
+
+/// What became of the packets still queued, or deferred for want of peers,
+/// when `shutdown` was called
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Packets published before the timeout
+    pub flushed: usize,
+    /// Packets that failed to publish or were left when the timeout passed
+    pub dropped: usize,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Next tick of `timer`, or never without one
+async fn next_tick(timer: &mut Option<tokio::time::Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
         }
+        None => std::future::pending().await,
+    }
+}
+
+/// Channel of a filtered subscription, with the packets dropped while it was full
+struct Subscription {
+    filter: PacketFilter,
+    tx: mpsc::Sender<DataPacket>,
+    dropped: u64,
+}
+
+/// Gossipsub, Kademlia, ping, identify, and the history protocol, plus the sync
+/// protocol unless the node is a publisher and mDNS when `enable_mdns` is set,
+/// behind a block list of banned peers
+#[derive(NetworkBehaviour)]
+#[behaviour(to_swarm = "NodeEvent")]
+struct NodeBehaviour {
+    blocked: allow_block_list::Behaviour<BlockedPeers>,
+    gossipsub: gossipsub::Behaviour,
+    mdns: Toggle<mdns::tokio::Behaviour>,
+    kademlia: kad::Behaviour<MemoryStore>,
+    ping: ping::Behaviour,
+    identify: identify::Behaviour,
+    history: request_response::cbor::Behaviour<HistoryRequest, HistoryResponse>,
+    sync: Toggle<request_response::cbor::Behaviour<SyncRequest, SyncResponse>>,
+    autonat: Toggle<autonat::Behaviour>,
+    relay: Toggle<relay::client::Behaviour>,
+}
+
+/// Events raised by `NodeBehaviour`
+#[derive(Debug)]
+enum NodeEvent {
+    Gossipsub(Box<gossipsub::Event>),
+    Mdns(mdns::Event),
+    Kademlia(Box<kad::Event>),
+    Ping(ping::Event),
+    Identify(Box<identify::Event>),
+    History(Box<request_response::Event<HistoryRequest, HistoryResponse>>),
+    Sync(Box<request_response::Event<SyncRequest, SyncResponse>>),
+    Autonat(autonat::Event),
+    Relay(relay::client::Event),
+}
+
+impl From<gossipsub::Event> for NodeEvent {
+    fn from(event: gossipsub::Event) -> Self {
+        NodeEvent::Gossipsub(Box::new(event))
+    }
+}
+
+impl From<mdns::Event> for NodeEvent {
+    fn from(event: mdns::Event) -> Self {
+        NodeEvent::Mdns(event)
+    }
+}
+
+impl From<ping::Event> for NodeEvent {
+    fn from(event: ping::Event) -> Self {
+        NodeEvent::Ping(event)
+    }
+}
+
+impl From<request_response::Event<HistoryRequest, HistoryResponse>> for NodeEvent {
+    fn from(event: request_response::Event<HistoryRequest, HistoryResponse>) -> Self {
+        NodeEvent::History(Box::new(event))
+    }
+}
+
+impl From<request_response::Event<SyncRequest, SyncResponse>> for NodeEvent {
+    fn from(event: request_response::Event<SyncRequest, SyncResponse>) -> Self {
+        NodeEvent::Sync(Box::new(event))
+    }
+}
+
+impl From<autonat::Event> for NodeEvent {
+    fn from(event: autonat::Event) -> Self {
+        NodeEvent::Autonat(event)
+    }
+}
+
+impl From<relay::client::Event> for NodeEvent {
+    fn from(event: relay::client::Event) -> Self {
+        NodeEvent::Relay(event)
+    }
+}
+
+impl From<identify::Event> for NodeEvent {
+    fn from(event: identify::Event) -> Self {
+        NodeEvent::Identify(Box::new(event))
     }
 }
 
+impl From<void::Void> for NodeEvent {
+    fn from(event: void::Void) -> Self {
+        void::unreachable(event)
+    }
+}
+
+impl From<kad::Event> for NodeEvent {
+    fn from(event: kad::Event) -> Self {
+        NodeEvent::Kademlia(Box::new(event))
+    }
+}
+
+/// TCP, plus WebSocket over TCP if `websocket` is set and circuits through
+/// relays if given the relay client's transport, all secured with Noise and
+/// multiplexed with yamux alike
+fn build_transport(
+    local_key: &Keypair,
+    websocket: bool,
+    relay: Option<relay::client::Transport>,
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>, NetworkError> {
+    let noise = noise::Config::new(local_key).map_err(|e| NetworkError::Transport { reason: e.to_string() })?;
+    let relay = match relay {
+        Some(relay) => OptionalTransport::some(relay),
+        None => OptionalTransport::none(),
+    };
+    // Each transport only takes addresses of its own form, so the order is free
+    let websocket = if websocket {
+        let tcp = tcp::tokio::Transport::new(tcp::Config::default().nodelay(true));
+        OptionalTransport::some(websocket::WsConfig::new(tcp))
+    } else {
+        OptionalTransport::none()
+    };
+    Ok(relay
+        .or_transport(websocket)
+        .or_transport(tcp::tokio::Transport::new(tcp::Config::default().nodelay(true)))
+        .upgrade(upgrade::Version::V1Lazy)
+        .authenticate(noise)
+        .multiplex(yamux::Config::default())
+        .boxed())
+}
+
 /// P2P network ingestion engine
 pub struct IngestionEngine {
-    swarm: Swarm<Gossipsub>,
-    topic: gossipsub::IdentTopic,
-    data_rx: mpsc::Receiver<DataPacket>,
-    data_tx: mpsc::Sender<DataPacket>,
+    swarm: Swarm<NodeBehaviour>,
+    /// Node identity, which signs published packets
+    local_key: Keypair,
+    config: NetworkConfig,
+    /// Events raised while waiting on a dial or listener, not yet returned
+    inbox: VecDeque<EngineEvent>,
+    /// Topic `publish` and the sender publish to
+    default_topic: gossipsub::IdentTopic,
+    /// Names of the topics subscribed to
+    topics: HashMap<gossipsub::TopicHash, String>,
+    /// Keys of the private topics, whether subscribed to or not
+    ciphers: HashMap<gossipsub::TopicHash, TopicCipher>,
+    /// Peers admitted to connect and gossip
+    acl: AccessList,
+    /// Whether peers can dial the node, as AutoNAT last concluded
+    reachability: ReachabilityTracker,
+    /// When each connected peer was last heard from, and its round-trip time
+    health: HealthTracker,
+    /// What each connected peer announced over identify
+    peer_info: HashMap<PeerId, PeerInfo>,
+    /// Configured circuit relays, by peer ID
+    relays: Vec<(PeerId, Multiaddr)>,
+    /// Listeners on relay circuits, open while the node is unreachable
+    relay_listeners: Vec<ListenerId>,
+    /// Packets queued by senders, awaiting publication
+    outbound: OutboundQueue,
+    /// Messages waiting for a peer on their topic, if `publish_retry` is set
+    deferred: Option<DeferredQueue>,
+    /// Published messages not yet accepted by gossipsub, if `outbox_path` is set
+    outbox: Option<Outbox>,
+    sender: PacketSender,
+    /// Listeners opened by `listen`, closed by `shutdown`
+    listeners: Vec<ListenerId>,
+    /// Set once `shutdown` has run
+    shut_down: bool,
+    verifier: Option<FragilityVerifier>,
+    proof_policy: ProofPolicy,
+    /// Expected reporting period and the nullifiers seen in it
+    replay: Option<(u64, NullifierSet)>,
+    /// Whether proofs must be bound to the peer that published them
+    identity_binding: bool,
+    /// Packets failing proof, identity, or replay checks, by source
+    flagged: HashMap<String, u64>,
+    /// Packets dropped for a missing or invalid signature
+    invalid_signatures: u64,
+    /// Sanity checks every received packet must pass, unless the node is a publisher
+    validator: Option<PacketValidator>,
+    /// Packets that could not be decoded or failed validation or the freshness checks
+    rejected: u64,
+    /// Timestamp window, and the signer and timestamp of packets delivered
+    freshness: FreshnessGuard,
+    /// Reports recently delivered
+    seen: SeenCache,
+    /// Packets dropped as repeats of a recently delivered report
+    duplicates: u64,
+    /// Per-source token buckets, if rate limiting is on
+    rate_limiter: Option<RateLimiter>,
+    /// Packets dropped for exceeding a source's rate limit
+    rate_limited: u64,
+    /// Source scores and bans
+    reputation: PeerReputation,
+    /// Kinds of message delivered; others are ignored
+    message_kinds: HashSet<MessageKind>,
+    /// Filtered subscriptions, in the order made
+    subscriptions: Vec<Subscription>,
+    /// Latest packet from every source, unless the node is a publisher
+    aggregation: Option<AggregationState>,
+    /// Latest state of every source, full or rebuilt from deltas
+    states: StateTracker,
+    /// Ticks when the aggregate is due to be published, once `next_event` starts it
+    aggregate_timer: Option<tokio::time::Interval>,
+    /// Ticks when peers are checked for staleness, once `next_event` starts it
+    health_timer: Option<tokio::time::Interval>,
+    /// Counters and gauges, shared with scrapers
+    metrics: Arc<NetworkMetrics>,
+    /// Delivered and published packets, unless the node is a publisher
+    store: Option<Box<dyn PacketStore>>,
+    /// Packets the store failed to keep, and history requests it failed to serve
+    store_errors: u64,
+    /// Syncs awaiting an answer, with the start of the horizon each asked for
+    syncs: HashMap<request_response::RequestId, u64>,
 }
 
 impl IngestionEngine {
+    /// Set up an engine field by field, starting from the default configuration
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+
     /// Create new ingestion engine
-    pub fn new(config: NetworkConfig) -> Result<Self, Box<dyn Error>> {
-        // Generate keypair
-        let local_key = Keypair::generate_ed25519();
+    ///
+    /// `IngestionEngine::builder` sets the configuration field by field.
+    pub fn new(config: NetworkConfig) -> Result<Self, NetworkError> {
+        let listen_addrs = std::iter::once(&config.listen_addr).chain(config.enable_websocket.then_some(&config.ws_listen_addr));
+        for addr in listen_addrs {
+            if let Err(e) = addr.parse::<Multiaddr>() {
+                return Err(NetworkError::InvalidListenAddr { addr: addr.clone(), reason: e.to_string() });
+            }
+        }
+        // Load or generate keypair
+        let local_key = match &config.identity_path {
+            Some(path) => load_or_create_identity(path)?,
+            None => Keypair::generate_ed25519(),
+        };
         let local_peer_id = PeerId::from(local_key.public());
 
         // Create gossipsub
-        let gossipsub_config = gossipsub::GossipsubConfigBuilder::default()
-            .heartbeat_interval(std::time::Duration::from_secs(10))
-            .validation_mode(ValidationMode::Strict)
+        let tuning = config.gossipsub.clone().unwrap_or_default();
+        let invalid = |reason: &dyn fmt::Display| NetworkError::GossipsubConfig { reason: reason.to_string() };
+        let gossipsub_config = gossipsub::ConfigBuilder::default()
+            .heartbeat_interval(tuning.heartbeat_interval)
+            .mesh_n(tuning.mesh_n)
+            .mesh_n_low(tuning.mesh_n_low)
+            .mesh_n_high(tuning.mesh_n_high)
+            .history_length(tuning.history_length)
+            .max_transmit_size(tuning.max_transmit_size)
+            .validation_mode(tuning.validation_mode)
             .build()
-            .expect(\"Valid gossipsub config\");
+            .map_err(|e| invalid(&e))?;
 
-        let mut gossipsub = Gossipsub::new(
+        let mut gossipsub = gossipsub::Behaviour::new(
             MessageAuthenticity::Signed(local_key.clone()),
             gossipsub_config,
         )
-        .expect(\"Failed to create gossipsub\");
+        .map_err(|e| invalid(&e))?;
+
+        // Subscribe to topics
+        let Some(default_topic) = config.topics.first() else {
+            return Err(NetworkError::NoTopics);
+        };
+        let default_topic = gossipsub::IdentTopic::new(&default_topic.name);
+        let mut topics = HashMap::new();
+        let mut ciphers = HashMap::new();
+        for topic in &config.topics {
+            let ident = gossipsub::IdentTopic::new(&topic.name);
+            gossipsub
+                .subscribe(&ident)
+                .map_err(|e| NetworkError::SubscriptionFailed { topic: topic.name.clone(), reason: e.to_string() })?;
+            let hash = ident.hash();
+            topics.insert(hash.clone(), topic.name.clone());
+            if let Some(cipher) = TopicCipher::new(&topic.name, &topic.keys) {
+                ciphers.insert(hash, cipher);
+            }
+        }
 
-        // Subscribe to topic
-        let topic = gossipsub::IdentTopic::new(&config.topic);
-        gossipsub.subscribe(&topic)?;
+        let mdns = if config.enable_mdns {
+            Some(
+                mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)
+                    .map_err(|source| NetworkError::Mdns { source })?,
+            )
+        } else {
+            None
+        };
+        let mut kad_config = kad::Config::default();
+        let kad_protocol = StreamProtocol::try_from_owned(config.kad_protocol.clone()).map_err(|e| {
+            NetworkError::InvalidKadProtocol { name: config.kad_protocol.clone(), reason: e.to_string() }
+        })?;
+        kad_config.set_protocol_names(vec![kad_protocol]);
+        let mut kademlia = kad::Behaviour::with_config(local_peer_id, MemoryStore::new(local_peer_id), kad_config);
+        kademlia.set_mode(Some(match config.kad_mode {
+            DhtMode::Client => kad::Mode::Client,
+            DhtMode::Server => kad::Mode::Server,
+        }));
+        let relays = config
+            .relay_addresses
+            .iter()
+            .map(|addr| nat::parse_relay_address(addr))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (relay_transport, relay) = if relays.is_empty() {
+            (None, None)
+        } else {
+            let (transport, behaviour) = relay::client::new(local_peer_id);
+            (Some(transport), Some(behaviour))
+        };
+        let autonat = config.enable_autonat.then(|| {
+            let mut autonat = autonat::Behaviour::new(local_peer_id, autonat::Config::default());
+            for (peer, addr) in &relays {
+                autonat.add_server(*peer, Some(addr.clone()));
+            }
+            autonat
+        });
+        // Publishers keep nothing they receive, so need no store, aggregate, validator, or sync
+        let keeps = config.role != NodeRole::PublisherOnly;
+        let behaviour = NodeBehaviour {
+            blocked: allow_block_list::Behaviour::default(),
+            gossipsub,
+            mdns: Toggle::from(mdns),
+            kademlia,
+            ping: ping::Behaviour::new(ping::Config::new().with_interval(config.ping_interval)),
+            identify: identify::Behaviour::new(
+                identify::Config::new(peer_info::PROTOCOL_VERSION.to_string(), local_key.public())
+                    .with_agent_version(peer_info::agent_version(&Capabilities::local(config.role))),
+            ),
+            history: request_response::cbor::Behaviour::new(
+                [(HISTORY_PROTOCOL, ProtocolSupport::Full)],
+                request_response::Config::default(),
+            ),
+            sync: Toggle::from(keeps.then(|| {
+                request_response::cbor::Behaviour::new([(SYNC_PROTOCOL, ProtocolSupport::Full)], request_response::Config::default())
+            })),
+            autonat: Toggle::from(autonat),
+            relay: Toggle::from(relay),
+        };
 
         // Create swarm
-        let swarm = SwarmBuilder::with_tokio_executor(
-            libp2p::Transport::boxed(libp2p::tcp::tokio::Transport::default()),
-            gossipsub,
-            local_peer_id,
-        )
-        .build();
+        let transport = build_transport(&local_key, config.enable_websocket, relay_transport)?;
+        let Ok(builder) = libp2p::SwarmBuilder::with_existing_identity(local_key.clone())
+            .with_tokio()
+            .with_other_transport(|_| transport);
+        let Ok(builder) = builder.with_behaviour(|_| behaviour);
+        let swarm = builder.build();
 
-        // Create channel for data packets
-        let (data_tx, data_rx) = mpsc::channel(1000);
+        // Create queue for data packets
+        let (sender, outbound) = outbound::channel(config.outbound_capacity, config.overflow_policy);
+        // Observers need none of the publishing machinery, and their sender refuses packets
+        let publishes = config.role != NodeRole::ObserverOnly;
+        if !publishes {
+            outbound.close();
+        }
+        let seen = SeenCache::new(config.dedup_capacity, config.dedup_ttl);
+        let store: Option<Box<dyn PacketStore>> = match &config.store_path {
+            _ if !keeps => None,
+            Some(path) => Some(Box::new(FileStore::open(path)?)),
+            None => Some(Box::new(InMemoryStore::new(config.store_capacity))),
+        };
+        let outbox = config
+            .outbox_path
+            .as_deref()
+            .filter(|_| publishes)
+            .map(Outbox::open)
+            .transpose()
+            .map_err(NetworkError::Outbox)?;
+        let rate_limiter = config.max_packets_per_peer_per_minute.map(|per_minute| {
+            RateLimiter::new(per_minute, config.rate_limit_burst, config.rate_limit_exempt.iter().copied())
+        });
 
-        Ok(Self {
+        let mut engine = Self {
             swarm,
-            topic,
-            data_rx,
-            data_tx,
-        })
+            local_key,
+            inbox: VecDeque::new(),
+            default_topic,
+            topics,
+            ciphers,
+            acl: AccessList::new(config.allowlist.iter().copied(), config.denylist.iter().copied()),
+            reachability: ReachabilityTracker::default(),
+            health: HealthTracker::new(config.stale_after),
+            peer_info: HashMap::new(),
+            relays,
+            relay_listeners: Vec::new(),
+            outbound,
+            deferred: config.publish_retry.filter(|_| publishes).map(DeferredQueue::new),
+            outbox,
+            sender,
+            listeners: Vec::new(),
+            shut_down: false,
+            verifier: None,
+            proof_policy: ProofPolicy::default(),
+            replay: None,
+            identity_binding: false,
+            flagged: HashMap::new(),
+            invalid_signatures: 0,
+            validator: keeps.then(PacketValidator::default),
+            rejected: 0,
+            freshness: FreshnessGuard::new(config.max_future_skew, config.max_packet_age, config.replay_capacity),
+            seen,
+            duplicates: 0,
+            rate_limiter,
+            rate_limited: 0,
+            reputation: PeerReputation::new(config.ban_threshold, config.ban_duration),
+            message_kinds: MessageKind::ALL.into_iter().collect(),
+            subscriptions: Vec::new(),
+            aggregation: keeps.then(|| AggregationState::new(config.aggregate_staleness)),
+            states: StateTracker::default(),
+            aggregate_timer: None,
+            health_timer: None,
+            metrics: Arc::new(NetworkMetrics::default()),
+            store,
+            store_errors: 0,
+            syncs: HashMap::new(),
+            config,
+        };
+        if !engine.config.enable_autonat {
+            engine.reserve_relays();
+        }
+        Ok(engine)
     }
 
-    /// Start listening for incoming data
-    pub async fn listen(&mut self, addr: Multiaddr) -> Result<(), Box<dyn Error>> {
-        self.swarm.listen_on(addr)?;
-        Ok(())
+    /// Only accept packets whose fragility proof verifies
+    ///
+    /// Verifier-only nodes build the verifier from an exported key and never
+    /// hold proving parameters.
+    pub fn with_verifier(mut self, verifier: FragilityVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
     }
 
-    /// Publish data packet to network
-    pub async fn publish(&mut self, packet: DataPacket) -> Result<(), Box<dyn Error>> {
-        let data = serde_json::to_vec(&packet)?;
-        self.swarm
-            .behaviour_mut()
-            .publish(self.topic.clone(), data)?;
-        Ok(())
+    /// Choose whether packets failing the proof check are dropped or flagged
+    ///
+    /// Only applies once a verifier is set with `with_verifier` or replay
+    /// protection with `with_replay_protection`.
+    pub fn with_proof_policy(mut self, policy: ProofPolicy) -> Self {
+        self.proof_policy = policy;
+        self
     }
 
-    /// Process network events
-    pub async fn process_events(&mut self) -> Result<Option<DataPacket>, Box<dyn Error>> {
-        loop {
-            tokio::select! {
-                event = self.swarm.select_next_some() => {
-                    match event {
-                        SwarmEvent::Behaviour(GossipsubEvent::Message {
-                            message,
-                            ..
-                        }) => {
-                            // Deserialize data packet
-                            if let Ok(packet) = serde_json::from_slice::<DataPacket>(&message.data) {
-                                return Ok(Some(packet));
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                packet = self.data_rx.recv() => {
-                    if let Some(p) = packet {
-                        self.publish(p).await?;
-                    }
-                }
-            }
-        }
+    /// Only accept packets for reporting `period`, one per source
+    ///
+    /// A packet bound to another period, or a second packet from the same
+    /// source for `period` within `window`, fails like an invalid proof. The
+    /// period is only proven once a verifier is set with `with_verifier`.
+    pub fn with_replay_protection(mut self, period: u64, window: Duration) -> Self {
+        self.replay = Some((period, NullifierSet::new(window)));
+        self
     }
 
-    /// Get sender for publishing data
-    pub fn get_sender(&self) -> mpsc::Sender<DataPacket> {
-        self.data_tx.clone()
+    /// Only accept packets whose proof is bound to the peer that published them
+    ///
+    /// The packet's signed envelope must name the key behind the gossipsub
+    /// message source (see `identity_verdict`), so a proof relayed by another
+    /// peer fails like an invalid proof.
+    pub fn with_identity_binding(mut self) -> Self {
+        self.identity_binding = true;
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Check received packets with `validator` instead of the default rules
+    pub fn with_packet_validator(mut self, validator: PacketValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
 
-    #[tokio::test]
-    async fn test_engine_creation() {
-        let config = NetworkConfig::default();
-        let engine = IngestionEngine::new(config);
-        assert!(engine.is_ok());
+    /// Keep delivered and published packets in `store` instead of the configured one
+    pub fn with_packet_store(mut self, store: impl PacketStore + 'static) -> Self {
+        self.store = Some(Box::new(store));
+        self
     }
 
-    #[test]
-    fn test_data_packet_serialization() {
-        let packet = DataPacket {
-            timestamp: 1234567890,
-            source: \"test-node\".to_string(),
-            state: BankState {
-                assets: 1000.0,
-                liabilities: 900.0,
-                equity: 100.0,
-                leverage: 9.0,
-            },
-            fragility: 15.0,
-            signature: vec![1, 2, 3, 4],
-        };
+    /// Only deliver messages of `kinds`, ignoring the rest as they arrive
+    ///
+    /// Every kind is delivered by default.
+    pub fn with_message_kinds(mut self, kinds: impl IntoIterator<Item = MessageKind>) -> Self {
+        self.message_kinds = kinds.into_iter().collect();
+        self
+    }
 
-        let serialized = serde_json::to_string(&packet);
-        assert!(serialized.is_ok());
+    /// This node's peer ID, derived from its identity key
+    pub fn local_peer_id(&self) -> PeerId {
+        *self.swarm.local_peer_id()
+    }
 
-        let deserialized = serde_json::from_str::<DataPacket>(&serialized.unwrap());
-        assert!(deserialized.is_ok());
+    /// Count of packets that failed the proof, identity, or replay checks, by source
+    pub fn flagged(&self) -> &HashMap<String, u64> {
+        &self.flagged
+    }
+
+    /// Count of packets dropped because they were not signed by the peer that gossiped them
+    pub fn invalid_signatures(&self) -> u64 {
+        self.invalid_signatures
+    }
+
+    /// Count of packets rejected as undecodable or by the packet validator
+    pub fn rejected_packets(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Count of packets dropped as repeats of a recently delivered report
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Count of packets dropped for exceeding their source's rate limit
+    pub fn rate_limited(&self) -> u64 {
+        self.rate_limited
+    }
+
+    /// Count of packets the store failed to keep, history requests it failed
+    /// to serve, and outbox acknowledgements that could not be recorded
+    pub fn store_errors(&self) -> u64 {
+        self.store_errors
+    }
+
+    /// Delivered and published packets, for querying; none on a publisher
+    pub fn packet_store(&self) -> Option<&dyn PacketStore> {
+        self.store.as_deref()
+    }
+
+    /// Delivered and published packets, for pruning; none on a publisher
+    pub fn packet_store_mut(&mut self) -> Option<&mut dyn PacketStore> {
+        match &mut self.store {
+            Some(store) => Some(store.as_mut()),
+            None => None,
+        }
+    }
+
+    /// Reputation score of every source heard from and not banned
+    pub fn peer_scores(&self) -> &HashMap<PeerId, i32> {
+        self.reputation.scores()
+    }
+
+    /// Number of peers in the DHT routing table
+    pub fn routing_table_size(&mut self) -> usize {
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .kbuckets()
+            .map(|bucket| bucket.num_entries())
+            .sum()
+    }
+
+    /// Decode a message gossiped by `source` and apply the checks for its kind
+    ///
+    /// Returns the message if it should be delivered. Messages of a kind not
+    /// chosen with `with_message_kinds` are ignored. Messages other than
+    /// packets are only rate limited, their source already being
+    /// authenticated by gossipsub. Packets go on to `receive_packet`.
+    fn receive(&mut self, data: &[u8], source: Option<&PeerId>) -> Option<NetworkMessage> {
+        if !self.admitted(source) {
+            self.metrics.rejected(RejectReason::Denied);
+            return None;
+        }
+        if source.is_some_and(|peer| self.reputation.is_banned(peer)) {
+            self.metrics.rejected(RejectReason::Banned);
+            return None;
+        }
+        let message = match wire::decode_message_limited(data, self.config.max_decompressed_size) {
+            Ok((message, _)) => message,
+            Err(e) => {
+                let kind = match e {
+                    WireError::TooLarge { .. } => RejectReason::TooLarge,
+                    _ => RejectReason::Undecodable,
+                };
+                self.reject(kind, e.to_string());
+                self.score(source, Conduct::Undecodable);
+                return None;
+            }
+        };
+        if !self.message_kinds.contains(&message.kind()) {
+            return None;
+        }
+        if let Err(e) = self.freshness.check_expiry(message.expires_at()) {
+            tracing::debug!(error = %e, "dropped expired message");
+            self.metrics.rejected(RejectReason::Expired);
+            return None;
+        }
+        match message {
+            NetworkMessage::Fragility(packet) => self.receive_packet(packet, source).map(NetworkMessage::Fragility),
+            // Only the source may say how its state changed
+            NetworkMessage::StateDelta(delta) if delta.source.parse::<PeerId>().ok().as_ref() != source => {
+                self.invalid_signatures += 1;
+                self.metrics.rejected(RejectReason::BadSignature);
+                self.score(source, Conduct::BadSignature);
+                None
+            }
+            message => {
+                if !self.within_rate_limit(source) {
+                    return None;
+                }
+                self.score(source, Conduct::Valid);
+                Some(message)
+            }
+        }
+    }
+
+    /// Apply the signature, validation, duplicate, and proof checks to a packet
+    ///
+    /// Returns the packet if it should be delivered. Packets not signed by
+    /// `source`, or naming another peer as their source, are always dropped,
+    /// whatever the policy. Undecodable packets
+    /// and signed packets failing the validator are rejected with a
+    /// `PacketRejected` event, as are packets stamped outside the freshness
+    /// window and, once past the proof checks, packets with the signer and
+    /// timestamp of one already delivered. Packets over their source's rate
+    /// limit are dropped with a `RateLimited` event. Packets from banned
+    /// sources are ignored before decoding. Repeats of a report already
+    /// delivered within `dedup_ttl` are dropped before the proof checks, so
+    /// they are neither delivered nor flagged again. Packets whose claims
+    /// cannot even be encoded are treated like invalid proofs.
+    fn receive_packet(&mut self, packet: DataPacket, source: Option<&PeerId>) -> Option<DataPacket> {
+        let signer = source.filter(|peer| {
+            packet.source == peer.to_string() && public_key_of(peer).is_some_and(|key| packet.verify(&key).is_ok())
+        });
+        let Some(signer) = signer else {
+            self.invalid_signatures += 1;
+            self.metrics.rejected(RejectReason::BadSignature);
+            self.score(source, Conduct::BadSignature);
+            return None;
+        };
+        if !self.within_rate_limit(source) {
+            return None;
+        }
+        if let Err(e) = self.freshness.check_time(&packet) {
+            let kind = match e {
+                FreshnessError::Future { .. } => RejectReason::FutureTimestamp,
+                _ => RejectReason::StaleTimestamp,
+            };
+            self.reject(kind, e.to_string());
+            self.score(source, Conduct::Invalid);
+            return None;
+        }
+        if let Err(reason) = self.validator.as_ref().map_or(Ok(()), |validator| validator.validate(&packet)) {
+            self.reject(RejectReason::Invalid, reason);
+            self.score(source, Conduct::Invalid);
+            return None;
+        }
+        self.score(source, Conduct::Valid);
+        if self.seen.check(&packet) {
+            self.duplicates += 1;
+            self.metrics.deduplicated();
+            return None;
+        }
+        if !self.admit(&packet, signer) {
+            *self.flagged.entry(packet.source.clone()).or_insert(0) += 1;
+            if self.proof_policy == ProofPolicy::Drop {
+                self.metrics.rejected(RejectReason::ProofFailed);
+                return None;
+            }
+        }
+        if let Err(e) = self.freshness.record(signer, &packet) {
+            self.reject(RejectReason::Replayed, e.to_string());
+            return None;
+        }
+        Some(packet)
+    }
+
+    /// Decrypt `data` gossiped by `source` on `topic` if the topic is private
+    ///
+    /// Payloads no topic key authenticates are rejected before any other
+    /// check and count against `source` like undecodable ones.
+    fn open(&mut self, topic: &gossipsub::TopicHash, data: Vec<u8>, source: Option<&PeerId>) -> Option<Vec<u8>> {
+        let Some(cipher) = self.ciphers.get(topic) else {
+            return Some(data);
+        };
+        match cipher.open(&data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                self.reject(RejectReason::Unauthenticated, e.to_string());
+                self.score(source, Conduct::Undecodable);
+                None
+            }
+        }
+    }
+
+    /// `data` to publish on `topic`, sealed if the topic is private
+    fn seal(&self, topic: &gossipsub::TopicHash, data: Vec<u8>) -> Vec<u8> {
+        match self.ciphers.get(topic) {
+            Some(cipher) => cipher.seal(&data),
+            None => data,
+        }
+    }
+
+    /// Whether the access list admits the author `source`, reporting it if not
+    ///
+    /// Unattributed messages are only admitted without an allowlist.
+    fn admitted(&mut self, source: Option<&PeerId>) -> bool {
+        let Some(peer) = source else {
+            return !self.acl.is_restricted();
+        };
+        match self.acl.check(peer) {
+            Ok(()) => true,
+            Err(reason) => {
+                self.inbox.push_back(EngineEvent::PeerRejected { peer: *peer, reason });
+                false
+            }
+        }
+    }
+
+    /// Replace the allowlist and denylist, closing connections to peers no longer admitted
+    ///
+    /// An empty `allowlist` admits any peer not on `denylist`.
+    pub fn update_acl(&mut self, allowlist: Vec<PeerId>, denylist: Vec<PeerId>) {
+        self.acl = AccessList::new(allowlist.iter().copied(), denylist.iter().copied());
+        self.config.allowlist = allowlist;
+        self.config.denylist = denylist;
+        let connected: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+        for peer in connected {
+            if let Err(reason) = self.acl.check(&peer) {
+                let _ = self.swarm.disconnect_peer_id(peer);
+                self.inbox.push_back(EngineEvent::PeerRejected { peer, reason });
+            }
+        }
+    }
+
+    /// Take a token from `source`'s bucket, reporting it if there was none
+    fn within_rate_limit(&mut self, source: Option<&PeerId>) -> bool {
+        if let (Some(limiter), Some(peer)) = (&mut self.rate_limiter, source) {
+            if let Err(dropped) = limiter.check(peer) {
+                self.rate_limited += 1;
+                self.metrics.rejected(RejectReason::RateLimited);
+                self.inbox.push_back(EngineEvent::RateLimited { peer: *peer, dropped });
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Score `source` for `conduct`, banning it if its score falls too low
+    fn score(&mut self, source: Option<&PeerId>, conduct: Conduct) {
+        let Some(peer) = source else {
+            return;
+        };
+        if self.reputation.record(peer, conduct) {
+            self.swarm.behaviour_mut().blocked.block_peer(*peer);
+            self.inbox.push_back(EngineEvent::PeerBanned {
+                peer: *peer,
+                duration: self.reputation.ban_duration(),
+            });
+        }
+    }
+
+    /// Count a rejected packet and report why
+    fn reject(&mut self, kind: RejectReason, reason: String) {
+        self.rejected += 1;
+        self.metrics.rejected(kind);
+        self.inbox.push_back(EngineEvent::PacketRejected { reason });
+    }
+
+    /// Whether `packet`, signed by `signer`, passes the enabled proof,
+    /// identity, and replay checks
+    ///
+    /// Replays are tracked per signer, so a packet cannot spend another
+    /// peer's report for the period.
+    fn admit(&mut self, packet: &DataPacket, signer: &PeerId) -> bool {
+        if self.identity_binding && !identity_verdict(packet, Some(signer)).is_valid() {
+            return false;
+        }
+        if let Some(verifier) = &self.verifier {
+            if !verify_packet(packet, verifier).is_ok_and(|verdict| verdict.is_valid()) {
+                return false;
+            }
+        }
+        match &mut self.replay {
+            Some((period, nullifiers)) => {
+                packet.period == Some(*period) && nullifiers.check(&signer.to_string(), *period).is_ok()
+            }
+            None => true,
+        }
+    }
+
+    /// Start listening for incoming data, returning the address bound
+    ///
+    /// Port 0 picks a free port, which the returned address carries.
+    pub async fn listen(&mut self, addr: Multiaddr) -> Result<Multiaddr, Box<dyn Error>> {
+        let listener = self.swarm.listen_on(addr)?;
+        self.listeners.push(listener);
+        loop {
+            let event = self.swarm.select_next_some().await;
+            match &event {
+                SwarmEvent::NewListenAddr { listener_id, address } if *listener_id == listener => {
+                    let address = address.clone();
+                    self.on_swarm_event(event);
+                    return Ok(address);
+                }
+                SwarmEvent::ListenerClosed { listener_id, reason, .. } if *listener_id == listener => {
+                    return Err(format!("listener closed: {:?}", reason).into());
+                }
+                _ => self.on_swarm_event(event),
+            }
+        }
+    }
+
+    /// Listen on `listen_addr`, and on `ws_listen_addr` too with WebSocket
+    /// enabled, returning the bound addresses
+    pub async fn listen_configured(&mut self) -> Result<Vec<Multiaddr>, Box<dyn Error>> {
+        let mut addrs = vec![self.config.listen_addr.clone()];
+        if self.config.enable_websocket {
+            addrs.push(self.config.ws_listen_addr.clone());
+        }
+        let mut bound = Vec::new();
+        for addr in addrs {
+            bound.push(self.listen(addr.parse()?).await?);
+        }
+        Ok(bound)
+    }
+
+    /// Addresses peers can reach this node on
+    ///
+    /// Every address the open listeners are bound to, with port 0 resolved
+    /// to the port the OS assigned, followed by any confirmed external
+    /// addresses not among them. A listener on an unspecified IP reports one
+    /// address per interface as the interfaces come up.
+    pub fn listen_addrs(&self) -> Vec<Multiaddr> {
+        let mut addrs: Vec<Multiaddr> = self.swarm.listeners().cloned().collect();
+        for addr in self.swarm.external_addresses() {
+            if !addrs.contains(addr) {
+                addrs.push(addr.clone());
+            }
+        }
+        addrs
+    }
+
+    /// Dial `addr` without waiting for the connection, which `next_event` drives
+    #[cfg(any(test, feature = "test-utils"))]
+    pub(crate) fn dial(&mut self, addr: Multiaddr) -> Result<(), Box<dyn Error>> {
+        self.swarm.dial(addr)?;
+        Ok(())
+    }
+
+    /// Dial every configured bootstrap peer, retrying with exponential backoff
+    ///
+    /// Peers are dialed in order, each up to `bootstrap_attempts` times.
+    /// Entries that are not multiaddrs are reported and skipped. Packets
+    /// gossiped meanwhile are kept for `next_event`.
+    pub async fn connect_bootstrap(&mut self) -> BootstrapReport {
+        let mut report = BootstrapReport::default();
+        for entry in self.config.bootstrap_peers.clone() {
+            let outcome = match entry.parse::<Multiaddr>() {
+                Ok(addr) => self.dial_with_backoff(&addr).await,
+                Err(e) => BootstrapOutcome::InvalidAddress { reason: e.to_string() },
+            };
+            report.outcomes.push((entry, outcome));
+        }
+        report
+    }
+
+    /// Dial `addr` until it connects or the attempts run out
+    async fn dial_with_backoff(&mut self, addr: &Multiaddr) -> BootstrapOutcome {
+        let attempts = self.config.bootstrap_attempts.max(1);
+        let mut backoff = self.config.bootstrap_backoff;
+        let mut error = String::new();
+        for attempt in 1..=attempts {
+            match self.dial_once(addr).await {
+                Ok(peer) => return BootstrapOutcome::Connected { peer, attempts: attempt },
+                Err(e) => error = e,
+            }
+            if attempt < attempts {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+        BootstrapOutcome::Unreachable { attempts, error }
+    }
+
+    /// Dial `addr` once, driving the swarm until that connection is up or fails
+    async fn dial_once(&mut self, addr: &Multiaddr) -> Result<PeerId, String> {
+        let opts = DialOpts::unknown_peer_id().address(addr.clone()).build();
+        let dial = opts.connection_id();
+        self.swarm.dial(opts).map_err(|e| e.to_string())?;
+        let outcome = tokio::time::timeout(DIAL_TIMEOUT, async {
+            loop {
+                let event = self.swarm.select_next_some().await;
+                match &event {
+                    SwarmEvent::ConnectionEstablished { peer_id, connection_id, .. } if *connection_id == dial => {
+                        let peer_id = *peer_id;
+                        self.swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                        self.on_swarm_event(event);
+                        return Ok(peer_id);
+                    }
+                    SwarmEvent::OutgoingConnectionError { connection_id, error, .. } if *connection_id == dial => {
+                        return Err(error.to_string());
+                    }
+                    _ => self.on_swarm_event(event),
+                }
+            }
+        })
+        .await;
+        outcome.unwrap_or_else(|_| Err(format!("no connection within {:?}", DIAL_TIMEOUT)))
+    }
+
+    /// Queue the engine events a swarm event raises
+    ///
+    /// Generic over the connection handler error, which only closed
+    /// connections carry and the engine never inspects.
+    fn on_swarm_event<E>(&mut self, event: SwarmEvent<NodeEvent, E>) {
+        match event {
+            SwarmEvent::Behaviour(event) => self.on_behaviour_event(event),
+            SwarmEvent::ConnectionEstablished { peer_id, num_established, .. } => match self.acl.check(&peer_id) {
+                Err(reason) => {
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    if num_established.get() == 1 {
+                        self.inbox.push_back(EngineEvent::PeerRejected { peer: peer_id, reason });
+                    }
+                }
+                Ok(()) if num_established.get() == 1 => {
+                    self.health.connected(peer_id);
+                    self.inbox.push_back(EngineEvent::PeerConnected(peer_id));
+                    self.start_sync(peer_id);
+                }
+                Ok(()) => {}
+            },
+            // Rejected peers were never reported connected
+            SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } if self.acl.check(&peer_id).is_ok() => {
+                self.health.disconnected(&peer_id);
+                self.peer_info.remove(&peer_id);
+                self.inbox.push_back(EngineEvent::PeerDisconnected(peer_id));
+            }
+            SwarmEvent::NewListenAddr { address, .. } => {
+                self.inbox.push_back(EngineEvent::ListeningOn(address));
+            }
+            _ => {}
+        }
+        self.refresh_peer_gauges();
+    }
+
+    /// Turn a behaviour event into engine events, dialing peers mDNS discovers
+    fn on_behaviour_event(&mut self, event: NodeEvent) {
+        match event {
+            NodeEvent::Gossipsub(event) => match *event {
+                gossipsub::Event::Message { propagation_source, message, .. } => {
+                    self.heard(&propagation_source);
+                    self.metrics.arrived(message.data.len());
+                    // Gossipsub has already forwarded it; a publisher keeps nothing
+                    if self.config.role == NodeRole::PublisherOnly {
+                        return;
+                    }
+                    let Some(data) = self.open(&message.topic, message.data, message.source.as_ref()) else {
+                        return;
+                    };
+                    if let Some(received) = self.receive(&data, message.source.as_ref()) {
+                        let topic = match self.topics.get(&message.topic) {
+                            Some(name) => name.clone(),
+                            // Left the topic while the message was queued
+                            None => message.topic.into_string(),
+                        };
+                        let event = match received {
+                            NetworkMessage::Fragility(packet) => {
+                                self.persist(&packet);
+                                self.aggregate(&packet);
+                                self.states.record(&packet);
+                                self.fan_out(&packet);
+                                EngineEvent::PacketReceived { topic, packet }
+                            }
+                            NetworkMessage::StateDelta(delta) => match self.apply_delta(&delta) {
+                                Some(packet) => EngineEvent::DeltaApplied { topic, packet },
+                                None => return,
+                            },
+                            message => EngineEvent::MessageReceived { topic, message },
+                        };
+                        self.metrics.delivered();
+                        self.inbox.push_back(event);
+                    }
+                }
+                // A peer on the topic can take the messages waiting for one
+                gossipsub::Event::Subscribed { topic, .. } => {
+                    self.retry_deferred(&topic);
+                    self.replay_outbox(&topic);
+                }
+                _ => {}
+            },
+            NodeEvent::History(event) => {
+                // Responses are collected by `request_history`
+                if let request_response::Event::Message {
+                    peer,
+                    message: request_response::Message::Request { request, channel, .. },
+                } = *event
+                {
+                    let response = match self.store.as_ref().map(|store| store.since(request.since)) {
+                        // A publisher keeps no history to serve
+                        None => HistoryResponse::default(),
+                        // Stored packets do not record their topic, so none may be private
+                        Some(Ok(_)) if !self.ciphers.is_empty() => HistoryResponse::default(),
+                        Some(Ok(packets)) => history::serve(&packets, &request, self.format_for(&peer)),
+                        Some(Err(error)) => {
+                            tracing::warn!(%error, "packet store failed to serve history");
+                            self.store_errors += 1;
+                            HistoryResponse::default()
+                        }
+                    };
+                    // Fails only if the requester has gone
+                    let _ = self.swarm.behaviour_mut().history.send_response(channel, response);
+                }
+            }
+            NodeEvent::Ping(ping::Event { peer, result: Ok(rtt), .. }) => {
+                if self.health.pinged(&peer, rtt) {
+                    self.inbox.push_back(EngineEvent::PeerRecovered(peer));
+                }
+            }
+            NodeEvent::Sync(event) => match *event {
+                request_response::Event::Message {
+                    peer,
+                    message: request_response::Message::Request { request, channel, .. },
+                } => {
+                    let response = match self.store.as_ref().map(|store| store.since(request.since)) {
+                        None => SyncResponse::default(),
+                        Some(Ok(_)) if !self.ciphers.is_empty() => SyncResponse::default(),
+                        Some(Ok(packets)) => sync::serve(&packets, &request, self.format_for(&peer)),
+                        Some(Err(error)) => {
+                            tracing::warn!(%error, "packet store failed to serve sync");
+                            self.store_errors += 1;
+                            SyncResponse::default()
+                        }
+                    };
+                    // Fails only if the requester has gone
+                    if let Some(sync) = self.swarm.behaviour_mut().sync.as_mut() {
+                        let _ = sync.send_response(channel, response);
+                    }
+                }
+                request_response::Event::Message {
+                    peer,
+                    message: request_response::Message::Response { request_id, response },
+                } => {
+                    if let Some(since) = self.syncs.remove(&request_id) {
+                        self.apply_sync(peer, since, response);
+                    }
+                }
+                request_response::Event::OutboundFailure { peer, request_id, error }
+                    if self.syncs.remove(&request_id).is_some() =>
+                {
+                    tracing::debug!(%peer, %error, "sync failed");
+                }
+                _ => {}
+            },
+            NodeEvent::Mdns(mdns::Event::Discovered(found)) => {
+                for (peer, addr) in found {
+                    if !self.discoverable(&peer) {
+                        continue;
+                    }
+                    self.swarm.behaviour_mut().kademlia.add_address(&peer, addr.clone());
+                    if !self.swarm.is_connected(&peer) {
+                        // A failed dial surfaces as a swarm event; mDNS will offer the peer again
+                        let _ = self.swarm.dial(DialOpts::peer_id(peer).addresses(vec![addr.clone()]).build());
+                    }
+                    self.inbox.push_back(EngineEvent::PeerDiscovered { peer, addr });
+                }
+            }
+            NodeEvent::Mdns(mdns::Event::Expired(lost)) => {
+                for (peer, addr) in lost {
+                    if self.discoverable(&peer) {
+                        self.inbox.push_back(EngineEvent::PeerExpired { peer, addr });
+                    }
+                }
+            }
+            NodeEvent::Autonat(autonat::Event::StatusChanged { new, .. }) => {
+                if let Some(status) = self.reachability.apply(new) {
+                    self.metrics.set_reachability(status);
+                    match status {
+                        Reachability::Private => self.reserve_relays(),
+                        Reachability::Public => self.release_relays(),
+                        Reachability::Unknown => {}
+                    }
+                    self.inbox.push_back(EngineEvent::ReachabilityChanged(status));
+                }
+            }
+            NodeEvent::Identify(event) => {
+                if let identify::Event::Received { peer_id, info } = *event {
+                    // DHT servers are routable at the addresses they listen on
+                    let kad_server = info.protocols.iter().any(|p| p.as_ref() == self.config.kad_protocol);
+                    if kad_server && self.discoverable(&peer_id) {
+                        for addr in &info.listen_addrs {
+                            self.swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                        }
+                    }
+                    let agent_version = info.agent_version.clone();
+                    self.peer_info.insert(peer_id, PeerInfo::from(info));
+                    self.inbox.push_back(EngineEvent::PeerIdentified { peer: peer_id, agent_version });
+                }
+            }
+            NodeEvent::Relay(relay::client::Event::ReservationReqAccepted { relay_peer_id, .. }) => {
+                // Advertise the circuit so peers learn to reach the node through the relay
+                if let Some((_, addr)) = self.relays.iter().find(|(peer, _)| *peer == relay_peer_id) {
+                    let circuit = addr
+                        .clone()
+                        .with(Protocol::P2pCircuit)
+                        .with(Protocol::P2p(*self.swarm.local_peer_id()));
+                    self.swarm.add_external_address(circuit);
+                }
+            }
+            // Query results are collected by whichever call started the query
+            // A failed ping leaves the peer to go stale
+            NodeEvent::Kademlia(_) | NodeEvent::Ping(_) | NodeEvent::Autonat(_) | NodeEvent::Relay(_) => {}
+        }
+    }
+
+    /// Listen on a circuit through each configured relay, reserving a slot on it
+    ///
+    /// The relayed addresses are reported as `ListeningOn` once the relays accept.
+    fn reserve_relays(&mut self) {
+        if !self.relay_listeners.is_empty() {
+            return;
+        }
+        for (relay, addr) in &self.relays {
+            match self.swarm.listen_on(addr.clone().with(Protocol::P2pCircuit)) {
+                Ok(listener) => self.relay_listeners.push(listener),
+                Err(error) => tracing::warn!(%relay, %error, "relay reservation failed"),
+            }
+        }
+    }
+
+    /// Close the relay circuits once the node is reachable directly
+    fn release_relays(&mut self) {
+        for listener in self.relay_listeners.drain(..) {
+            self.swarm.remove_listener(listener);
+        }
+    }
+
+    /// Note that `peer` was heard from, reporting it if it was stale
+    fn heard(&mut self, peer: &PeerId) {
+        if self.health.heard(peer) {
+            self.inbox.push_back(EngineEvent::PeerRecovered(*peer));
+        }
+    }
+
+    /// Report connected peers newly gone silent past `stale_after`
+    fn sweep_health(&mut self) {
+        self.sweep_health_at(now_millis())
+    }
+
+    fn sweep_health_at(&mut self, now: u64) {
+        for (peer, silent) in self.health.sweep_at(now) {
+            self.inbox.push_back(EngineEvent::PeerStale { peer, silent });
+        }
+    }
+
+    /// Last-seen time, round-trip time, and status of each connected peer
+    pub fn peer_health(&self) -> &HashMap<PeerId, PeerHealth> {
+        self.health.peers()
+    }
+
+    /// Whether peers can dial the node, as AutoNAT last concluded
+    pub fn reachability(&self) -> Reachability {
+        self.reachability.status()
+    }
+
+    /// Join the DHT through the peers already known and advertise the topics
+    ///
+    /// Bootstrapping fills the routing table from connected bootstrap or mDNS
+    /// peers, so call it after `connect_bootstrap`. The node then records
+    /// itself as a provider of each configured topic marked `advertise` so
+    /// `find_peers` on other nodes finds it. Returns the routing table size
+    /// afterwards.
+    pub async fn bootstrap_dht(&mut self) -> Result<usize, Box<dyn Error>> {
+        let query = self.swarm.behaviour_mut().kademlia.bootstrap()?;
+        loop {
+            match self.next_query_step(query).await? {
+                (QueryResult::Bootstrap(Err(e)), _) => return Err(e.into()),
+                (_, true) => break,
+                (_, false) => {}
+            }
+        }
+
+        let advertised: Vec<String> = self
+            .config
+            .topics
+            .iter()
+            .filter(|topic| topic.advertise)
+            .map(|topic| topic.name.clone())
+            .collect();
+        for topic in advertised {
+            let query = self.swarm.behaviour_mut().kademlia.start_providing(kad::RecordKey::new(&topic))?;
+            if let (QueryResult::StartProviding(Err(e)), _) = self.next_query_step(query).await? {
+                return Err(e.into());
+            }
+        }
+        Ok(self.routing_table_size())
+    }
+
+    /// Peers the DHT lists as providers of `topic`, other than this node
+    ///
+    /// A lookup that times out returns the providers found before it did.
+    pub async fn find_peers(&mut self, topic: &str) -> Result<HashSet<PeerId>, Box<dyn Error>> {
+        let query = self.swarm.behaviour_mut().kademlia.get_providers(kad::RecordKey::new(&topic));
+        let mut peers = HashSet::new();
+        loop {
+            let (result, last) = self.next_query_step(query).await?;
+            match result {
+                QueryResult::GetProviders(Ok(kad::GetProvidersOk::FoundProviders { providers, .. })) => {
+                    peers.extend(providers);
+                }
+                QueryResult::GetProviders(Err(_)) => break,
+                _ => {}
+            }
+            if last {
+                break;
+            }
+        }
+        peers.remove(self.swarm.local_peer_id());
+        Ok(peers)
+    }
+
+    /// Drive the swarm until `query` progresses, returning its result and
+    /// whether that was the final step
+    async fn next_query_step(&mut self, query: QueryId) -> Result<(QueryResult, bool), Box<dyn Error>> {
+        let step = tokio::time::timeout(QUERY_TIMEOUT, async {
+            loop {
+                match self.swarm.select_next_some().await {
+                    SwarmEvent::Behaviour(NodeEvent::Kademlia(event)) => {
+                        if let kad::Event::OutboundQueryProgressed { id, result, step, .. } = *event {
+                            if id == query {
+                                return (result, step.last);
+                            }
+                        }
+                    }
+                    event => self.on_swarm_event(event),
+                }
+            }
+        })
+        .await;
+        step.map_err(|_| format!("DHT query made no progress within {:?}", QUERY_TIMEOUT).into())
+    }
+
+    /// Whether the discovery allowlist and the access list admit `peer`
+    fn discoverable(&self, peer: &PeerId) -> bool {
+        self.config
+            .discovery_allowlist
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(peer))
+            && self.acl.check(peer).is_ok()
+    }
+
+    /// Messages waiting for a peer on their topic under the `publish_retry` policy
+    pub fn deferred_publishes(&self) -> usize {
+        self.deferred.as_ref().map_or(0, DeferredQueue::len)
+    }
+
+    /// Messages in the outbox not yet accepted by gossipsub
+    pub fn outbox_depth(&self) -> usize {
+        self.outbox.as_ref().map_or(0, Outbox::depth)
+    }
+
+    /// Rewrite the outbox log without the messages already sent, returning
+    /// how many records were dropped
+    pub fn compact_outbox(&mut self) -> Result<usize, NetworkError> {
+        match &mut self.outbox {
+            Some(outbox) => outbox.compact().map_err(NetworkError::Outbox),
+            None => Ok(0),
+        }
+    }
+
+    /// Live metrics, for a scrape handler running alongside the engine
+    pub fn metrics(&self) -> Arc<NetworkMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Current metrics, with the peer gauges brought up to date
+    pub fn metrics_snapshot(&self) -> MetricsSnapshot {
+        self.refresh_peer_gauges();
+        self.metrics.snapshot()
+    }
+
+    /// Current metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        self.metrics_snapshot().render_prometheus()
+    }
+
+    fn refresh_peer_gauges(&self) {
+        let mesh = self.swarm.behaviour().gossipsub.all_mesh_peers().count();
+        self.metrics.set_peers(self.swarm.network_info().num_peers(), mesh);
+    }
+
+    /// System-wide fragility from the latest packet of every source heard
+    /// from, this node's published packets included; none on a publisher
+    pub fn aggregate_snapshot(&self) -> Option<AggregateSnapshot> {
+        self.aggregation.as_ref().map(AggregationState::snapshot)
+    }
+
+    /// Channel of the delivered packets `filter` matches
+    ///
+    /// Packets are sent after validation and duplicate suppression, besides
+    /// being returned as `PacketReceived` events. Any number of
+    /// subscriptions may be open at once, and a packet matching several is
+    /// sent to each. Dropping the receiver ends the subscription.
+    pub fn subscribe_filtered(&mut self, filter: PacketFilter) -> mpsc::Receiver<DataPacket> {
+        let (tx, rx) = mpsc::channel(filter.capacity());
+        self.subscriptions.push(Subscription { filter, tx, dropped: 0 });
+        rx
+    }
+
+    /// Matching packets dropped because the subscriber's channel was full,
+    /// per filtered subscription in the order made, ended ones included
+    pub fn subscription_drops(&self) -> Vec<u64> {
+        self.subscriptions.iter().map(|subscription| subscription.dropped).collect()
+    }
+
+    /// Send `packet` to every open subscription whose filter matches it
+    fn fan_out(&mut self, packet: &DataPacket) {
+        for subscription in &mut self.subscriptions {
+            if subscription.tx.is_closed() || !subscription.filter.matches(packet) {
+                continue;
+            }
+            if let Err(mpsc::error::TrySendError::Full(_)) = subscription.tx.try_send(packet.clone()) {
+                subscription.dropped += 1;
+            }
+        }
+    }
+
+    /// Keep `packet` in the store, logging and counting a failure rather than returning it
+    fn persist(&mut self, packet: &DataPacket) {
+        let Some(store) = &mut self.store else {
+            return;
+        };
+        if let Err(error) = store.insert(packet) {
+            tracing::warn!(source = %packet.source, %error, "packet store failed to keep packet");
+            self.store_errors += 1;
+        }
+    }
+
+    /// Count `packet` toward the aggregate, if the node keeps one
+    fn aggregate(&mut self, packet: &DataPacket) {
+        if let Some(aggregation) = &mut self.aggregation {
+            aggregation.record(packet);
+        }
+    }
+
+    /// Fetch up to `limit` packets from `peer` with timestamps from `since` on, oldest first
+    ///
+    /// Pages are requested until `limit` is reached or the peer has no more.
+    /// Each packet must be signed by the peer its `source` names; packets
+    /// that are not are dropped and count against `peer` like a bad
+    /// signature on a gossiped packet. A peer whose identify announcement
+    /// leaves out the history protocol is not asked.
+    pub async fn request_history(
+        &mut self,
+        peer: PeerId,
+        since: u64,
+        limit: usize,
+    ) -> Result<Vec<DataPacket>, HistoryError> {
+        if self.peer_info.get(&peer).is_some_and(|info| !info.speaks(&HISTORY_PROTOCOL)) {
+            return Err(HistoryError::Unsupported);
+        }
+        let mut packets = Vec::new();
+        let mut skip = 0u32;
+        while packets.len() < limit {
+            let request = HistoryRequest {
+                since,
+                skip,
+                limit: (limit - packets.len()).min(MAX_PAGE_PACKETS) as u32,
+            };
+            let response = self.history_page(peer, request).await?;
+            skip += response.packets.len() as u32;
+            let more = response.more && !response.packets.is_empty();
+            for bytes in response.packets {
+                if let Some((packet, _)) = self.verify_served(&peer, &bytes) {
+                    packets.push(packet);
+                }
+            }
+            if !more {
+                break;
+            }
+        }
+        packets.truncate(limit);
+        Ok(packets)
+    }
+
+    /// Send `request` to `peer` and drive the swarm until it is answered
+    async fn history_page(&mut self, peer: PeerId, request: HistoryRequest) -> Result<HistoryResponse, HistoryError> {
+        let sent = self.swarm.behaviour_mut().history.send_request(&peer, request);
+        let outcome = tokio::time::timeout(HISTORY_TIMEOUT, async {
+            loop {
+                match self.swarm.select_next_some().await {
+                    SwarmEvent::Behaviour(NodeEvent::History(event)) => match *event {
+                        request_response::Event::Message {
+                            message: request_response::Message::Response { request_id, response },
+                            ..
+                        } if request_id == sent => return Ok(response),
+                        request_response::Event::OutboundFailure { request_id, error, .. } if request_id == sent => {
+                            return Err(HistoryError::Request { reason: error.to_string() });
+                        }
+                        event => self.on_behaviour_event(NodeEvent::History(Box::new(event))),
+                    },
+                    event => self.on_swarm_event(event),
+                }
+            }
+        })
+        .await;
+        outcome.unwrap_or(Err(HistoryError::Timeout))
+    }
+
+    /// Send `peer` a digest of the packets held within the sync horizon
+    fn start_sync(&mut self, peer: PeerId) {
+        let Some(horizon) = self.config.sync_horizon.filter(|_| self.config.role != NodeRole::PublisherOnly) else {
+            return;
+        };
+        let since = now_millis().saturating_sub(horizon.as_millis() as u64);
+        self.request_sync(peer, since);
+    }
+
+    /// Send `peer` a digest of the store from `since` on, returning whether it was sent
+    fn request_sync(&mut self, peer: PeerId, since: u64) -> bool {
+        let Some(store) = &self.store else {
+            return false;
+        };
+        let latest = match store.latest_per_source() {
+            Ok(latest) => sync::digest(latest.values(), since),
+            Err(error) => {
+                tracing::warn!(%error, "packet store failed to digest for sync");
+                self.store_errors += 1;
+                return false;
+            }
+        };
+        let request = SyncRequest { since, latest, max_bytes: self.config.sync_max_bytes as u64 };
+        let Some(sync) = self.swarm.behaviour_mut().sync.as_mut() else {
+            return false;
+        };
+        let sent = sync.send_request(&peer, request);
+        self.syncs.insert(sent, since);
+        true
+    }
+
+    /// Apply a gossiped delta to its source's known state, returning the new state
+    ///
+    /// A source first heard from through a delta has its state looked up in
+    /// the store. A delta no newer than the known state is ignored; one made
+    /// from any other state starts a sync with its source, which holds the
+    /// full packet the delta was made from. The new state counts toward the
+    /// aggregate but is not stored, having no signature of its own.
+    ///
+    /// The new state must pass the freshness window, the validator, and the
+    /// proof checks as a gossiped packet would, and its fragility must be
+    /// the one `compute_fragility` gives its state under the default
+    /// `LagrangianConfig`, there being no signature or proof to vouch for
+    /// it. A delta carries no proof, so once proofs are required it fails
+    /// like an unproven packet.
+    fn apply_delta(&mut self, delta: &StateDelta) -> Option<DataPacket> {
+        if !self.states.knows(&delta.source) {
+            match self.store.as_ref().map(|store| store.latest_per_source()) {
+                Some(Ok(latest)) => {
+                    if let Some(packet) = latest.get(&delta.source) {
+                        self.states.record(packet);
+                    }
+                }
+                Some(Err(error)) => {
+                    tracing::warn!(%error, "packet store failed to look up a delta's base");
+                    self.store_errors += 1;
+                }
+                None => {}
+            }
+        }
+        match self.states.rebuild(delta) {
+            DeltaOutcome::Applied(packet) => {
+                let packet = self.check_rebuilt(*packet)?;
+                self.states.record(&packet);
+                self.aggregate(&packet);
+                Some(packet)
+            }
+            DeltaOutcome::Stale => None,
+            DeltaOutcome::Mismatch => {
+                // `receive` checked the source names the delta's author
+                let peer = delta.source.parse::<PeerId>().ok()?;
+                let since = self.states.timestamp(&delta.source).unwrap_or(0);
+                if self.request_sync(peer, since) {
+                    self.inbox.push_back(EngineEvent::ResyncRequested { peer, source: delta.source.clone() });
+                }
+                None
+            }
+        }
+    }
+
+    /// Apply the freshness, validation, fragility, and proof checks to the
+    /// state a delta rebuilt, returning it if it should be delivered
+    fn check_rebuilt(&mut self, packet: DataPacket) -> Option<DataPacket> {
+        // `receive` checked the source names the delta's author
+        let author = packet.source.parse::<PeerId>().ok()?;
+        if let Err(e) = self.freshness.check_time(&packet) {
+            let kind = match e {
+                FreshnessError::Future { .. } => RejectReason::FutureTimestamp,
+                _ => RejectReason::StaleTimestamp,
+            };
+            self.reject(kind, e.to_string());
+            self.score(Some(&author), Conduct::Invalid);
+            return None;
+        }
+        let expected = compute_fragility(&packet.state, &LagrangianConfig::default());
+        let consistent = (packet.fragility - expected).abs() <= FRAGILITY_TOLERANCE * expected.abs().max(1.0);
+        let checked = match self.validator.as_ref().map_or(Ok(()), |validator| validator.validate(&packet)) {
+            Ok(()) if !consistent => {
+                Err(format!("fragility {} does not match its state's {}", packet.fragility, expected))
+            }
+            checked => checked,
+        };
+        if let Err(reason) = checked {
+            self.reject(RejectReason::Invalid, reason);
+            self.score(Some(&author), Conduct::Invalid);
+            return None;
+        }
+        if !self.admit(&packet, &author) {
+            *self.flagged.entry(packet.source.clone()).or_insert(0) += 1;
+            if self.proof_policy == ProofPolicy::Drop {
+                self.metrics.rejected(RejectReason::ProofFailed);
+                return None;
+            }
+        }
+        Some(packet)
+    }
+
+    /// Take in the packets `peer` sent in answer to a sync from `since`
+    ///
+    /// Packets must be signed by their named source and pass the validator
+    /// and the proof checks like gossiped ones, and must not be stamped
+    /// ahead of the freshness window; history being old, its age limit does
+    /// not apply. Past the byte budget the rest are ignored. Packets already
+    /// delivered are skipped; the others are stored, aggregated, and
+    /// delivered as `PacketReceived` on the default topic.
+    fn apply_sync(&mut self, peer: PeerId, since: u64, response: SyncResponse) {
+        let mut budget = self.config.sync_max_bytes;
+        let mut received = 0;
+        let mut truncated = response.truncated;
+        for bytes in response.packets {
+            let Some(left) = budget.checked_sub(bytes.len()) else {
+                truncated = true;
+                break;
+            };
+            budget = left;
+            let Some((packet, author)) = self.verify_served(&peer, &bytes) else {
+                continue;
+            };
+            let future = matches!(self.freshness.check_time(&packet), Err(FreshnessError::Future { .. }));
+            if packet.timestamp < since
+                || future
+                || self.validator.as_ref().is_some_and(|validator| validator.validate(&packet).is_err())
+            {
+                self.score(Some(&peer), Conduct::Invalid);
+                continue;
+            }
+            if self.seen.check(&packet) {
+                continue;
+            }
+            if !self.admit(&packet, &author) {
+                *self.flagged.entry(packet.source.clone()).or_insert(0) += 1;
+                if self.proof_policy == ProofPolicy::Drop {
+                    continue;
+                }
+            }
+            self.persist(&packet);
+            self.aggregate(&packet);
+            self.states.record(&packet);
+            self.fan_out(&packet);
+            self.inbox.push_back(EngineEvent::PacketReceived {
+                topic: self.default_topic.to_string(),
+                packet,
+            });
+            received += 1;
+        }
+        self.inbox.push_back(EngineEvent::Synced { peer, packets: received, truncated });
+    }
+
+    /// Decode a packet `server` sent and check it is signed by its named
+    /// source, returning it with that source
+    fn verify_served(&mut self, server: &PeerId, bytes: &[u8]) -> Option<(DataPacket, PeerId)> {
+        let Ok((packet, _)) = wire::decode(bytes) else {
+            self.score(Some(server), Conduct::Undecodable);
+            return None;
+        };
+        let author = packet
+            .source
+            .parse::<PeerId>()
+            .ok()
+            .filter(|source| public_key_of(source).is_some_and(|key| packet.verify(&key).is_ok()));
+        let Some(author) = author else {
+            self.invalid_signatures += 1;
+            self.score(Some(server), Conduct::BadSignature);
+            return None;
+        };
+        Some((packet, author))
+    }
+
+    /// Join `topic`, returning whether the node was not already subscribed
+    pub fn subscribe(&mut self, topic: &str) -> Result<bool, Box<dyn Error>> {
+        let hashed = gossipsub::IdentTopic::new(topic);
+        let joined = self.swarm.behaviour_mut().gossipsub.subscribe(&hashed)?;
+        self.topics.insert(hashed.hash(), topic.to_string());
+        Ok(joined)
+    }
+
+    /// Leave `topic`, returning whether the node was subscribed
+    ///
+    /// The node can still publish to a topic it has left.
+    pub fn unsubscribe(&mut self, topic: &str) -> Result<bool, Box<dyn Error>> {
+        let hashed = gossipsub::IdentTopic::new(topic);
+        let left = self.swarm.behaviour_mut().gossipsub.unsubscribe(&hashed)?;
+        self.topics.remove(&hashed.hash());
+        Ok(left)
+    }
+
+    /// Names of the topics subscribed to, sorted
+    pub fn topics(&self) -> Vec<&str> {
+        let mut topics: Vec<&str> = self.topics.values().map(String::as_str).collect();
+        topics.sort_unstable();
+        topics
+    }
+
+    /// Sign a data packet with the node's key and publish it to the first configured topic
+    ///
+    /// The packet's `source` is set to the node's peer id, as peers accept
+    /// the packet from no other. The signed packet is kept in the store even if there are no peers to
+    /// publish it to yet. Without peers it fails with `NetworkError::NoPeers`,
+    /// unless there is an outbox or a `publish_retry` policy: then it waits
+    /// for a peer, is reported as `PublishDeferred`, and `publish` succeeds.
+    /// With an outbox, the message is on disk before any send is attempted.
+    pub async fn publish(&mut self, packet: DataPacket) -> Result<(), NetworkError> {
+        let topic = self.default_topic.clone();
+        self.publish_signed(topic, NetworkMessage::Fragility(packet))
+    }
+
+    /// Sign a data packet with the node's key and publish it to `topic`
+    ///
+    /// As gossipsub allows, the node need not be subscribed to `topic`: the
+    /// packet goes to peers that are, though the node receives nothing
+    /// published there. Like `publish`, it fails when no peer is known to
+    /// subscribe to the topic.
+    pub async fn publish_to(&mut self, topic: &str, packet: DataPacket) -> Result<(), NetworkError> {
+        self.publish_signed(gossipsub::IdentTopic::new(topic), NetworkMessage::Fragility(packet))
+    }
+
+    /// Publish any kind of message to `topic`, as `publish_to` does packets
+    ///
+    /// A `Fragility` packet is signed and stored; other kinds are sent as they
+    /// are, gossipsub's own message signature vouching for their source.
+    pub async fn publish_message(&mut self, topic: &str, message: NetworkMessage) -> Result<(), NetworkError> {
+        self.publish_signed(gossipsub::IdentTopic::new(topic), message)
+    }
+
+    /// Publish `packet` to the first configured topic as a `StateDelta` on
+    /// the last packet stored from this node
+    ///
+    /// The full packet is signed and stored, so peers that missed the base
+    /// can sync it. With no earlier packet from this node in the store, or
+    /// no store, as on a publisher, the packet is published whole, as
+    /// `publish` does.
+    pub async fn publish_delta(&mut self, mut packet: DataPacket) -> Result<(), NetworkError> {
+        if self.config.role == NodeRole::ObserverOnly {
+            return Err(NetworkError::NotPublisher);
+        }
+        packet.source = self.local_peer_id().to_string();
+        let base = match self.store.as_ref().map(|store| store.latest_per_source()) {
+            Some(Ok(mut latest)) => latest.remove(&packet.source),
+            Some(Err(error)) => {
+                tracing::warn!(%error, "packet store failed to look up a delta's base");
+                self.store_errors += 1;
+                None
+            }
+            None => None,
+        };
+        let topic = self.default_topic.clone();
+        let Some(base) = base.filter(|base| base.timestamp < packet.timestamp) else {
+            return self.publish_signed(topic, NetworkMessage::Fragility(packet));
+        };
+        self.attribute(&mut packet)?;
+        self.persist(&packet);
+        self.aggregate(&packet);
+        self.publish_signed(topic, NetworkMessage::StateDelta(StateDelta::between(&base, &packet)))
+    }
+
+    /// Attribute `packet` to this node and sign it with the node's key
+    fn attribute(&self, packet: &mut DataPacket) -> Result<(), NetworkError> {
+        packet.source = self.local_peer_id().to_string();
+        packet.sign(&self.local_key)?;
+        Ok(())
+    }
+
+    fn publish_signed(&mut self, topic: gossipsub::IdentTopic, mut message: NetworkMessage) -> Result<(), NetworkError> {
+        if self.config.role == NodeRole::ObserverOnly {
+            return Err(NetworkError::NotPublisher);
+        }
+        if let Some(expires_at) = message.expires_at().filter(|expires_at| *expires_at <= now_millis()) {
+            return Err(NetworkError::Expired { expires_at });
+        }
+        if let NetworkMessage::Fragility(packet) = &mut message {
+            self.attribute(packet)?;
+        }
+        let data = wire::encode_message_with(&message, self.config.wire_format, self.config.compression)?;
+        let data = self.seal(&topic.hash(), data);
+        if let NetworkMessage::Fragility(packet) = &message {
+            self.persist(packet);
+            self.aggregate(packet);
+        }
+        let entry = match &mut self.outbox {
+            Some(outbox) => Some(outbox.enqueue(&topic.to_string(), &data, message.expires_at()).map_err(NetworkError::Outbox)?),
+            None => None,
+        };
+        let size = data.len();
+        match self.swarm.behaviour_mut().gossipsub.publish(topic.clone(), data.clone()) {
+            Ok(_) => {
+                self.acknowledge(entry);
+                self.metrics.published(size);
+                Ok(())
+            }
+            // The outbox entry is sent once a peer subscribes
+            Err(gossipsub::PublishError::InsufficientPeers) if entry.is_some() => {
+                self.inbox.push_back(EngineEvent::PublishDeferred { topic: topic.to_string(), message });
+                Ok(())
+            }
+            Err(gossipsub::PublishError::InsufficientPeers) => self.defer(topic, data, message),
+            Err(e) => {
+                // Retrying would fail the same way
+                self.acknowledge(entry);
+                Err(self.publish_error(e, &topic, size))
+            }
+        }
+    }
+
+    /// Mark outbox entry `id` as done with, if there is one
+    fn acknowledge(&mut self, id: Option<u64>) {
+        let (Some(outbox), Some(id)) = (&mut self.outbox, id) else {
+            return;
+        };
+        if let Err(error) = outbox.ack(id) {
+            tracing::warn!(id, %error, "outbox failed to record acknowledgement");
+            self.store_errors += 1;
+        }
+    }
+
+    /// Send the outbox entries for `topic`, now that a peer subscribed,
+    /// including those a previous run left unsent
+    fn replay_outbox(&mut self, topic: &gossipsub::TopicHash) {
+        let Some(outbox) = &self.outbox else {
+            return;
+        };
+        let waiting: Vec<OutboxEntry> = outbox.pending().filter(|entry| entry.topic == topic.as_str()).cloned().collect();
+        for entry in waiting {
+            if entry.expires_at.is_some_and(|expires_at| expires_at <= now_millis()) {
+                tracing::debug!(id = entry.id, topic = %entry.topic, "outbox entry expired before a peer joined");
+                self.acknowledge(Some(entry.id));
+                continue;
+            }
+            let size = entry.data.len();
+            let topic = gossipsub::IdentTopic::new(entry.topic);
+            match self.swarm.behaviour_mut().gossipsub.publish(topic.clone(), entry.data) {
+                Ok(_) => self.metrics.published(size),
+                // Sent before the acknowledgement was lost
+                Err(gossipsub::PublishError::Duplicate) => {}
+                Err(gossipsub::PublishError::InsufficientPeers) => continue,
+                Err(e) => {
+                    let error = self.publish_error(e, &topic, size);
+                    self.inbox.push_back(EngineEvent::PublishFailed { error: error.to_string() });
+                }
+            }
+            self.acknowledge(Some(entry.id));
+        }
+    }
+
+    /// Keep a message no peer could take for a retry, if the policy allows
+    fn defer(&mut self, topic: gossipsub::IdentTopic, data: Vec<u8>, message: NetworkMessage) -> Result<(), NetworkError> {
+        let no_peers = NetworkError::NoPeers { topic: topic.to_string() };
+        let Some(deferred) = &mut self.deferred else {
+            return Err(no_peers);
+        };
+        if !deferred.push(topic.clone(), data, message.clone()) {
+            return Err(no_peers);
+        }
+        self.inbox.push_back(EngineEvent::PublishDeferred { topic: topic.to_string(), message });
+        Ok(())
+    }
+
+    /// Publish the messages waiting for a peer on `topic`, now that one subscribed
+    fn retry_deferred(&mut self, topic: &gossipsub::TopicHash) {
+        let Some(waiting) = self.deferred.as_mut().map(|deferred| deferred.take_topic(topic)) else {
+            return;
+        };
+        let mut still_waiting = Vec::new();
+        for entry in waiting {
+            if entry.message.expires_at().is_some_and(|expires_at| expires_at <= now_millis()) {
+                self.inbox.push_back(EngineEvent::PublishExpired { topic: entry.topic.to_string(), message: entry.message });
+                continue;
+            }
+            let size = entry.data.len();
+            match self.swarm.behaviour_mut().gossipsub.publish(entry.topic.clone(), entry.data.clone()) {
+                Ok(_) => self.metrics.published(size),
+                Err(gossipsub::PublishError::InsufficientPeers) => still_waiting.push(entry),
+                Err(e) => {
+                    let error = self.publish_error(e, &entry.topic, size);
+                    self.inbox.push_back(EngineEvent::PublishFailed { error: error.to_string() });
+                }
+            }
+        }
+        if let Some(deferred) = &mut self.deferred {
+            deferred.restore(still_waiting);
+        }
+    }
+
+    /// Drop the deferred messages past the retry TTL, reporting each
+    fn expire_deferred(&mut self) {
+        let Some(deferred) = &mut self.deferred else {
+            return;
+        };
+        for entry in deferred.expire() {
+            self.inbox.push_back(EngineEvent::PublishExpired { topic: entry.topic.to_string(), message: entry.message });
+        }
+    }
+
+    /// Gossipsub's refusal to publish `size` bytes on `topic`, classified
+    fn publish_error(&self, error: gossipsub::PublishError, topic: &gossipsub::IdentTopic, size: usize) -> NetworkError {
+        match error {
+            gossipsub::PublishError::InsufficientPeers => NetworkError::NoPeers { topic: topic.to_string() },
+            gossipsub::PublishError::MessageTooLarge => {
+                let tuning = self.config.gossipsub.clone().unwrap_or_default();
+                NetworkError::MessageTooLarge { size, limit: tuning.max_transmit_size }
+            }
+            gossipsub::PublishError::Duplicate => NetworkError::Duplicate,
+            e => NetworkError::Publish { reason: e.to_string() },
+        }
+    }
+
+    /// Next engine event, publishing packets from the sender while waiting
+    ///
+    /// Cancel-safe: an event is only taken off the swarm or a packet off the
+    /// sender's queue once it can be handled without waiting again, so
+    /// dropping the future loses nothing. After `shutdown`, the remaining
+    /// events are returned and then `ShuttingDown` every time. With
+    /// `aggregate_publish_interval` set, the aggregate snapshot is published
+    /// to the first configured topic each interval while waiting. Peers are
+    /// checked for staleness every `ping_interval`.
+    pub async fn next_event(&mut self) -> EngineEvent {
+        let aggregate_interval = self.config.aggregate_publish_interval.filter(|_| self.config.role == NodeRole::Full);
+        if let (None, Some(period)) = (&self.aggregate_timer, aggregate_interval) {
+            let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            self.aggregate_timer = Some(timer);
+        }
+        if self.health_timer.is_none() {
+            let period = self.config.ping_interval;
+            let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            self.health_timer = Some(timer);
+        }
+        loop {
+            for peer in self.reputation.expire_bans() {
+                self.swarm.behaviour_mut().blocked.unblock_peer(peer);
+            }
+            self.expire_deferred();
+            if let Some(event) = self.inbox.pop_front() {
+                return event;
+            }
+            if self.shut_down {
+                return EngineEvent::ShuttingDown;
+            }
+            tokio::select! {
+                event = self.swarm.select_next_some() => self.on_swarm_event(event),
+                // A finished queue (an observer's, from the start) would resolve at once forever
+                next = self.outbound.next(), if !self.outbound.is_finished() => match next {
+                    Some(Outbound::Packet(p)) => {
+                        if let Err(e) = self.publish(*p).await {
+                            self.inbox.push_back(EngineEvent::PublishFailed { error: e.to_string() });
+                        }
+                    }
+                    Some(Outbound::Dropped(dropped)) => self.outbound_dropped(dropped),
+                    None => {}
+                },
+                _ = next_tick(&mut self.aggregate_timer) => {
+                    let topic = self.default_topic.clone();
+                    // Only full nodes start the timer, and they keep an aggregate
+                    let Some(snapshot) = self.aggregate_snapshot() else {
+                        continue;
+                    };
+                    if let Err(e) = self.publish_signed(topic, NetworkMessage::Aggregate(snapshot)) {
+                        self.inbox.push_back(EngineEvent::PublishFailed { error: e.to_string() });
+                    }
+                }
+                _ = next_tick(&mut self.health_timer) => self.sweep_health(),
+            }
+        }
+    }
+
+    /// Engine events as a stream, for use alongside other work in `tokio::select!`
+    ///
+    /// Polling the stream drives the engine just as `next_event` does. The
+    /// stream ends after yielding `ShuttingDown`.
+    pub fn events(&mut self) -> impl Stream<Item = EngineEvent> + '_ {
+        stream::unfold((self, false), |(engine, ended)| async move {
+            if ended {
+                return None;
+            }
+            let event = engine.next_event().await;
+            let ended = matches!(event, EngineEvent::ShuttingDown);
+            Some((event, (engine, ended)))
+        })
+    }
+
+    /// Stop the engine, publishing the packets still queued by senders
+    ///
+    /// New sends are refused at once. Queued packets are published until
+    /// `timeout` passes and the rest are dropped; the connections are then
+    /// driven briefly so published packets leave the node. Finally the node
+    /// leaves its topics, closes its listeners, and queues `ShuttingDown`.
+    pub async fn shutdown(&mut self, timeout: Duration) -> ShutdownReport {
+        let deadline = Instant::now() + timeout;
+        let mut report = ShutdownReport::default();
+        self.outbound.close();
+        while let Some(next) = self.outbound.try_next() {
+            let packet = match next {
+                Outbound::Packet(packet) => *packet,
+                Outbound::Dropped(dropped) => {
+                    self.outbound_dropped(dropped);
+                    continue;
+                }
+            };
+            if Instant::now() < deadline && self.publish(packet).await.is_ok() {
+                report.flushed += 1;
+            } else {
+                report.dropped += 1;
+            }
+        }
+        // Deferred messages get one last try; a peer may have joined since
+        let waiting = self.deferred.as_mut().map(DeferredQueue::drain).unwrap_or_default();
+        for entry in waiting {
+            let size = entry.data.len();
+            let expired = entry.message.expires_at().is_some_and(|expires_at| expires_at <= now_millis());
+            if !expired && self.swarm.behaviour_mut().gossipsub.publish(entry.topic, entry.data).is_ok() {
+                self.metrics.published(size);
+                report.flushed += 1;
+            } else {
+                report.dropped += 1;
+            }
+        }
+
+        let grace = deadline.saturating_duration_since(Instant::now()).min(FLUSH_GRACE);
+        let _ = tokio::time::timeout(grace, async {
+            loop {
+                let event = self.swarm.select_next_some().await;
+                self.on_swarm_event(event);
+            }
+        })
+        .await;
+
+        for (_, topic) in self.topics.drain() {
+            let _ = self.swarm.behaviour_mut().gossipsub.unsubscribe(&gossipsub::IdentTopic::new(topic));
+        }
+        for listener in self.listeners.drain(..) {
+            self.swarm.remove_listener(listener);
+        }
+        self.shut_down = true;
+        self.inbox.push_back(EngineEvent::ShuttingDown);
+        report
+    }
+
+    /// Process network events until a packet arrives, skipping other events
+    ///
+    /// A packet from the sender that cannot be published is returned as an error.
+    pub async fn process_events(&mut self) -> Result<Option<DataPacket>, Box<dyn Error>> {
+        loop {
+            match self.next_event().await {
+                EngineEvent::PacketReceived { packet, .. } => return Ok(Some(packet)),
+                EngineEvent::PublishFailed { error } => return Err(error.into()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the node publishes, consumes received packets, or both
+    pub fn role(&self) -> NodeRole {
+        self.config.role
+    }
+
+    /// Version and capabilities of the node, with its role, as announced to peers over identify
+    pub fn user_agent(&self) -> String {
+        peer_info::agent_version(&Capabilities::local(self.config.role))
+    }
+
+    /// What `peer` announced about itself over identify, while connected
+    pub fn peer_info(&self, peer: &PeerId) -> Option<&PeerInfo> {
+        self.peer_info.get(peer)
+    }
+
+    /// Format to encode packets served to `peer` in: the configured one if
+    /// the peer advertises decoding it, else the default every node decodes
+    fn format_for(&self, peer: &PeerId) -> WireFormat {
+        match self.peer_info.get(peer) {
+            Some(info) if info.decodes(self.config.wire_format) => self.config.wire_format,
+            _ => WireFormat::default(),
+        }
+    }
+
+    /// Get sender for publishing data
+    ///
+    /// An `ObserverOnly` node's sender refuses every packet as if the
+    /// engine had shut down.
+    pub fn get_sender(&self) -> PacketSender {
+        self.sender.clone()
+    }
+
+    fn outbound_dropped(&mut self, dropped: u64) {
+        self.metrics.outbound_dropped(dropped);
+        self.inbox.push_back(EngineEvent::OutboundDropped { dropped });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::delta::state_hash;
+    use crate::network::health::PeerStatus;
+    use crate::network::message::Severity;
+    use crate::network::store::StoreError;
+    use crate::network::testing::{fixtures, TestMesh};
+    use crate::core::lagrangian::{compute_fragility, LagrangianConfig};
+    use crate::proofs::circuit::reference_fragility;
+    use crate::proofs::circuit::state_commitment;
+    use crate::proofs::encoding::FixedPoint;
+    use crate::proofs::envelope::CircuitId;
+    use crate::proofs::prover::{deserialize_proof, serialize_proof, test_prover};
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+
+    fn verifying_engine(policy: ProofPolicy) -> IngestionEngine {
+        let verifier = FragilityVerifier::from_bytes(&test_prover().export_verifying_key()).unwrap();
+        IngestionEngine::new(NetworkConfig::default())
+            .unwrap()
+            .with_verifier(verifier)
+            .with_proof_policy(policy)
+    }
+
+    /// Key of the node gossiping `proven_packet`s
+    fn node_key() -> Keypair {
+        Keypair::ed25519_from_bytes([7u8; 32]).unwrap()
+    }
+
+    /// Packets arrive as the bytes `publish` gossips with the default config
+    fn gossip(packet: &DataPacket) -> Vec<u8> {
+        wire::encode(packet, WireFormat::Json).unwrap()
+    }
+
+    /// Proven packet signed by `node_key`
+    fn proven_packet(period: Option<u64>) -> DataPacket {
+        let state = BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        let proof = test_prover().prove_for_period(&state, fragility, period.unwrap_or(0)).unwrap();
+        let mut packet = DataPacket {
+            timestamp: now_millis(),
+            source: node_key().public().to_peer_id().to_string(),
+            state,
+            fragility,
+            signature: vec![],
+            proof: Some(serialize_proof(&proof)),
+            period,
+            envelope: None,
+            expires_at: None,
+        };
+        packet.sign(&node_key()).unwrap();
+        packet
+    }
+
+    /// `proven_packet(None)` in an envelope signed by `key`, signed by and
+    /// attributed to the peer behind `key`
+    fn bound_packet(key: &SigningKey) -> (DataPacket, PeerId) {
+        let peer = peer_id_of(key.verifying_key().as_bytes()).unwrap();
+        let mut packet = proven_packet(None);
+        let proof = deserialize_proof(packet.proof.as_ref().unwrap()).unwrap();
+        let mut envelope = ProofEnvelope::new(
+            CircuitId::Fragility,
+            &proof,
+            vec![FixedPoint::to_units(packet.fragility).unwrap()],
+            Some(state_commitment(&packet.state).unwrap()),
+            peer.to_string(),
+        );
+        envelope.sign(key);
+        packet.source = peer.to_string();
+        packet.envelope = Some(envelope);
+        packet.sign(&Keypair::ed25519_from_bytes(key.to_bytes()).unwrap()).unwrap();
+        (packet, peer)
+    }
+
+    #[tokio::test]
+    async fn test_engine_creation() {
+        let config = NetworkConfig::default();
+        let engine = IngestionEngine::new(config);
+        assert!(engine.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_gossipsub_tuning_applied() {
+        let tuning = GossipsubTuning {
+            heartbeat_interval: Duration::from_secs(1),
+            max_transmit_size: 2048,
+            ..GossipsubTuning::default()
+        };
+        let config = NetworkConfig { gossipsub: Some(tuning), ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config).unwrap();
+
+        // The size check comes before gossipsub looks for peers to send to
+        let oversized = DataPacket { proof: Some(vec![0; 4096]), ..plain_packet() };
+        let error = engine.publish(oversized).await.unwrap_err();
+        assert!(matches!(error, NetworkError::MessageTooLarge { limit: 2048, .. }));
+        let fits = engine.publish(plain_packet()).await.unwrap_err();
+        assert!(matches!(fits, NetworkError::NoPeers { .. }));
+
+        let crossed = GossipsubTuning { mesh_n_low: 8, mesh_n: 6, ..GossipsubTuning::default() };
+        let config = NetworkConfig { gossipsub: Some(crossed), ..NetworkConfig::default() };
+        let error = IngestionEngine::new(config).err().unwrap();
+        assert!(matches!(error, NetworkError::GossipsubConfig { .. }));
+    }
+
+    #[test]
+    fn test_data_packet_serialization() {
+        let packet = DataPacket {
+            timestamp: 1234567890,
+            source: "test-node".to_string(),
+            state: BankState {
+                tier1_capital: 100.0,
+                total_assets: 1000.0,
+                liquidity_coverage: 1.2,
+                entropy_index: 2.5,
+            },
+            fragility: 15.0,
+            signature: vec![1, 2, 3, 4],
+            proof: Some(vec![0u8; 192]),
+            period: None,
+            envelope: None,
+            expires_at: None,
+        };
+
+        let serialized = serde_json::to_string(&packet);
+        assert!(serialized.is_ok());
+
+        let deserialized = serde_json::from_str::<DataPacket>(&serialized.unwrap());
+        assert!(deserialized.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_gossip_rejected() {
+        let mut engine = verifying_engine(ProofPolicy::Drop);
+        let node = node_key().public().to_peer_id();
+        let honest = proven_packet(None);
+        // Signed by the node, so only the proof check can catch it
+        let mut tampered = honest.clone();
+        tampered.state.tier1_capital *= 2.0;
+        tampered.sign(&node_key()).unwrap();
+
+        assert!(engine.receive(&gossip(&honest), Some(&node)).is_some());
+        assert_eq!(engine.flagged().get(&node.to_string()), None);
+        assert!(engine.receive(&gossip(&tampered), Some(&node)).is_none());
+        assert_eq!(engine.flagged().get(&node.to_string()), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_flag_policy_delivers_failed_packets() {
+        let mut engine = verifying_engine(ProofPolicy::Flag);
+        let mut unproven = proven_packet(None);
+        unproven.proof = None;
+
+        let delivered = engine.receive(&gossip(&unproven), Some(&node_key().public().to_peer_id()));
+        assert!(delivered.is_some());
+        assert_eq!(engine.flagged().get(&node_key().public().to_peer_id().to_string()), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_replayed_packets_rejected() {
+        let mut engine = verifying_engine(ProofPolicy::Drop).with_replay_protection(96, Duration::from_secs(3600));
+        let node = node_key().public().to_peer_id();
+        let current = proven_packet(Some(96));
+
+        assert!(engine.receive(&gossip(&current), Some(&node)).is_some());
+        // The same report again, and last period's report relabelled
+        assert!(engine.receive(&gossip(&current), Some(&node)).is_none());
+        let relabelled = DataPacket { period: Some(96), ..proven_packet(Some(95)) };
+        assert!(engine.receive(&gossip(&relabelled), Some(&node)).is_none());
+        assert!(engine.receive(&gossip(&proven_packet(Some(95))), Some(&node)).is_none());
+        // The exact repeat is a duplicate, dropped before the proof checks
+        assert_eq!(engine.duplicates(), 1);
+        assert_eq!(engine.flagged().get(&node.to_string()), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn test_relayed_proof_rejected() {
+        let mut engine = verifying_engine(ProofPolicy::Drop).with_identity_binding();
+        let origin_key = SigningKey::generate(&mut OsRng);
+        let (packet, origin) = bound_packet(&origin_key);
+        let relay_key = Keypair::generate_ed25519();
+        let relay = relay_key.public().to_peer_id();
+
+        assert_eq!(identity_verdict(&packet, Some(&origin)), PacketVerdict::Valid);
+        assert!(engine.receive(&gossip(&packet), Some(&origin)).is_some());
+
+        // The same packet republished by another peer, as is and claimed as its own
+        assert_eq!(identity_verdict(&packet, Some(&relay)), PacketVerdict::IdentityMismatch);
+        assert!(engine.receive(&gossip(&packet), Some(&relay)).is_none());
+        let mut claimed = DataPacket { source: relay.to_string(), ..packet.clone() };
+        claimed.sign(&relay_key).unwrap();
+        assert!(engine.receive(&gossip(&claimed), Some(&relay)).is_none());
+        // Unbound packets no longer pass
+        let mut unbound = DataPacket { source: origin.to_string(), ..proven_packet(None) };
+        unbound.sign(&Keypair::ed25519_from_bytes(origin_key.to_bytes()).unwrap()).unwrap();
+        assert!(engine.receive(&gossip(&unbound), Some(&origin)).is_none());
+        assert_eq!(engine.flagged().get(&relay.to_string()), Some(&1));
+        assert_eq!(engine.invalid_signatures(), 1);
+    }
+
+    #[test]
+    fn test_packet_signature_round_trip() {
+        let packet = proven_packet(None);
+        let key = node_key().public();
+        assert_eq!(packet.verify(&key), Ok(()));
+        assert_eq!(packet.verify(&Keypair::generate_ed25519().public()), Err(PacketError::InvalidSignature));
+
+        let tampered = DataPacket { fragility: packet.fragility + 1e-9, ..packet.clone() };
+        assert_eq!(tampered.verify(&key), Err(PacketError::InvalidSignature));
+        let unsigned = DataPacket { signature: vec![], ..packet.clone() };
+        assert_eq!(unsigned.verify(&key), Err(PacketError::Unsigned));
+        // The proof and envelope are not signed; they carry their own checks
+        let reproven = DataPacket { proof: None, ..packet };
+        assert_eq!(reproven.verify(&key), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_gossip_dropped() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap().with_proof_policy(ProofPolicy::Flag);
+        let node = node_key().public().to_peer_id();
+        let packet = proven_packet(None);
+
+        assert!(engine.receive(&gossip(&packet), Some(&node)).is_some());
+        let tampered = DataPacket { fragility: 1.0, ..packet.clone() };
+        assert!(engine.receive(&gossip(&tampered), Some(&node)).is_none());
+        assert!(engine.receive(&gossip(&DataPacket { signature: vec![], ..packet.clone() }), Some(&node)).is_none());
+        // Without a source there is no key to check against
+        assert!(engine.receive(&gossip(&packet), None).is_none());
+        // Signed by the node but claiming to come from another peer
+        let mut impostor = DataPacket { source: Keypair::generate_ed25519().public().to_peer_id().to_string(), ..packet };
+        impostor.sign(&node_key()).unwrap();
+        assert!(engine.receive(&gossip(&impostor), Some(&node)).is_none());
+        assert_eq!(engine.invalid_signatures(), 4);
+        assert!(engine.flagged().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_packets_rejected() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let node = node_key().public().to_peer_id();
+        let mut nan = DataPacket { fragility: f64::NAN, ..plain_packet() };
+        nan.sign(&node_key()).unwrap();
+        let mut stale = DataPacket { timestamp: 1_700_000_000_000, ..plain_packet() };
+        stale.sign(&node_key()).unwrap();
+
+        // JSON carries NaN as null, so the packet does not even decode
+        assert!(engine.receive(&gossip(&nan), Some(&node)).is_none());
+        assert!(engine.receive(&gossip(&stale), Some(&node)).is_none());
+        assert_eq!(engine.rejected_packets(), 2);
+        assert!(matches!(engine.next_event().await, EngineEvent::PacketRejected { reason } if reason.contains("undecodable")));
+        assert!(matches!(engine.next_event().await, EngineEvent::PacketRejected { reason } if reason.contains("timestamp")));
+
+        // Binary formats carry NaN, so the validator sees it
+        let cbor = wire::encode(&nan, WireFormat::Cbor).unwrap();
+        assert!(engine.receive(&cbor, Some(&node)).is_none());
+        assert!(matches!(engine.next_event().await, EngineEvent::PacketRejected { reason } if reason.starts_with("fragility NaN")));
+        let mut unknown = gossip(&stale);
+        unknown[0] = 0xff;
+        assert!(engine.receive(&unknown, Some(&node)).is_none());
+        assert!(matches!(engine.next_event().await, EngineEvent::PacketRejected { reason } if reason.contains("format tag")));
+        assert_eq!(engine.rejected_packets(), 4);
+
+        let mut engine = engine.with_packet_validator(PacketValidator::default().with_rule(|packet| {
+            if packet.fragility > 10.0 {
+                Err("fragility above alert threshold".to_string())
+            } else {
+                Ok(())
+            }
+        }));
+        let mut signed = plain_packet();
+        signed.sign(&node_key()).unwrap();
+        assert!(engine.receive(&gossip(&signed), Some(&node)).is_none());
+        assert_eq!(engine.rejected_packets(), 5);
+        assert_eq!(engine.invalid_signatures(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_role_limits_publishing_and_consuming() {
+        let mut observer = IngestionEngine::builder().role(NodeRole::ObserverOnly).build().unwrap();
+        assert!(matches!(observer.publish(plain_packet()).await, Err(NetworkError::NotPublisher)));
+        assert!(matches!(observer.get_sender().try_send(plain_packet()), Err(outbound::TrySendError::Closed(_))));
+        assert!(observer.packet_store().unwrap().is_empty());
+        assert!(observer.user_agent().contains(" role=observer-only "));
+
+        let mut packet = plain_packet();
+        packet.sign(&node_key()).unwrap();
+        let message = |engine: &IngestionEngine| {
+            NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: node_key().public().to_peer_id(),
+                message_id: gossipsub::MessageId::new(b"1"),
+                message: gossipsub::Message {
+                    source: Some(node_key().public().to_peer_id()),
+                    data: gossip(&packet),
+                    sequence_number: Some(1),
+                    topic: engine.default_topic.hash(),
+                },
+            }))
+        };
+        observer.on_behaviour_event(message(&observer));
+        assert!(matches!(observer.inbox.pop_front(), Some(EngineEvent::PacketReceived { .. })));
+
+        let mut publisher = IngestionEngine::builder().role(NodeRole::PublisherOnly).build().unwrap();
+        publisher.on_behaviour_event(message(&publisher));
+        assert!(publisher.inbox.is_empty());
+        assert!(publisher.packet_store().is_none());
+        assert!(publisher.aggregate_snapshot().is_none());
+        assert!(!publisher.swarm.behaviour().sync.is_enabled());
+        let metrics = publisher.metrics_snapshot();
+        assert!(metrics.bytes_in > 0);
+        assert_eq!(metrics.messages_received, 0);
+        assert!(matches!(publisher.publish(plain_packet()).await, Err(NetworkError::NoPeers { .. })));
+        assert!(publisher.packet_store().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_relayed_duplicates_delivered_once() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let origin = node_key().public().to_peer_id();
+        let mut packet = plain_packet();
+        packet.sign(&node_key()).unwrap();
+
+        // The origin's report reaches us through two relaying peers
+        for (sequence, relay) in [(1u64, Keypair::generate_ed25519()), (2, Keypair::generate_ed25519())] {
+            let message = gossipsub::Message {
+                source: Some(origin),
+                data: gossip(&packet),
+                sequence_number: Some(sequence),
+                topic: engine.default_topic.hash(),
+            };
+            engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: relay.public().to_peer_id(),
+                message_id: gossipsub::MessageId::new(&sequence.to_be_bytes()),
+                message,
+            })));
+        }
+
+        let delivered: Vec<_> = engine.inbox.drain(..).collect();
+        assert_eq!(delivered.len(), 1);
+        assert!(matches!(&delivered[0], EngineEvent::PacketReceived { packet: received, .. } if received.signing_bytes() == packet.signing_bytes()));
+        assert_eq!(engine.duplicates(), 1);
+
+        // A new report from the same source still gets through
+        let mut next = DataPacket { timestamp: packet.timestamp + 1, ..packet.clone() };
+        next.sign(&node_key()).unwrap();
+        assert!(engine.receive(&gossip(&next), Some(&origin)).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_chatty_peer_rate_limited() {
+        let config = NetworkConfig {
+            max_packets_per_peer_per_minute: Some(60),
+            rate_limit_burst: 5,
+            ..NetworkConfig::default()
+        };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        let chatty = node_key();
+        let quiet = Keypair::generate_ed25519();
+        let packet_from = |key: &Keypair, timestamp: u64| {
+            let source = key.public().to_peer_id().to_string();
+            let mut packet = DataPacket { timestamp, source, ..plain_packet() };
+            packet.sign(key).unwrap();
+            gossip(&packet)
+        };
+        let start = now_millis();
+
+        let delivered = (0..8)
+            .filter(|i| engine.receive(&packet_from(&chatty, start + i), Some(&chatty.public().to_peer_id())).is_some())
+            .count();
+        assert_eq!(delivered, 5);
+        assert_eq!(engine.rate_limited(), 3);
+        let limited: Vec<_> = engine
+            .inbox
+            .drain(..)
+            .filter_map(|event| match event {
+                EngineEvent::RateLimited { peer, dropped } => Some((peer, dropped)),
+                _ => None,
+            })
+            .collect();
+        let chatty_id = chatty.public().to_peer_id();
+        assert_eq!(limited, vec![(chatty_id, 1), (chatty_id, 2), (chatty_id, 3)]);
+
+        // Another peer's packets still flow
+        for i in 0..5 {
+            assert!(engine.receive(&packet_from(&quiet, start + 100 + i), Some(&quiet.public().to_peer_id())).is_some());
+        }
+        assert_eq!(engine.rate_limited(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_misbehaving_peer_banned() {
+        let config = NetworkConfig { ban_threshold: -30, ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        let node = node_key().public().to_peer_id();
+        let mut good = plain_packet();
+        good.sign(&node_key()).unwrap();
+        assert!(engine.receive(&gossip(&good), Some(&node)).is_some());
+        assert_eq!(engine.peer_scores().get(&node), Some(&1));
+
+        // Signed by someone else, so each costs 10
+        for timestamp in 1..=4 {
+            let mut forged = DataPacket { timestamp: good.timestamp + timestamp, ..plain_packet() };
+            forged.sign(&Keypair::generate_ed25519()).unwrap();
+            assert!(engine.receive(&gossip(&forged), Some(&node)).is_none());
+        }
+        assert_eq!(engine.invalid_signatures(), 4);
+        assert!(engine.peer_scores().get(&node).is_none());
+        let banned: Vec<_> = engine
+            .inbox
+            .drain(..)
+            .filter(|event| matches!(event, EngineEvent::PeerBanned { peer, .. } if *peer == node))
+            .collect();
+        assert_eq!(banned.len(), 1);
+
+        // Even good packets are ignored now, without being counted
+        let mut later = DataPacket { timestamp: good.timestamp + 10, ..good.clone() };
+        later.sign(&node_key()).unwrap();
+        assert!(engine.receive(&gossip(&later), Some(&node)).is_none());
+        assert_eq!(engine.invalid_signatures(), 4);
+        assert!(engine.inbox.is_empty());
+        // Other peers are unaffected
+        let other = Keypair::generate_ed25519();
+        let other_id = other.public().to_peer_id();
+        // A distinct timestamp, or it would repeat `good` and be dropped as a duplicate
+        let mut theirs = DataPacket { timestamp: good.timestamp + 20, source: other_id.to_string(), ..plain_packet() };
+        theirs.sign(&other).unwrap();
+        assert!(engine.receive(&gossip(&theirs), Some(&other_id)).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_history_fetched_from_peer() {
+        let mut publisher = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let publisher_id = publisher.local_peer_id();
+        let addr = publisher.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let start = now_millis();
+        let published: Vec<_> = (0..3)
+            .map(|i| DataPacket { timestamp: start + i, source: publisher_id.to_string(), ..plain_packet() })
+            .collect();
+        for packet in &published {
+            // No peers yet, so gossiping fails, but the packet is kept
+            assert!(publisher.publish(packet.clone()).await.is_err());
+        }
+        // A packet the publisher holds but its named source never signed
+        let mut forged = DataPacket { timestamp: start + 3, source: publisher_id.to_string(), ..plain_packet() };
+        forged.sign(&Keypair::generate_ed25519()).unwrap();
+        publisher.persist(&forged);
+        tokio::spawn(async move {
+            loop {
+                let _ = publisher.next_event().await;
+            }
+        });
+
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut requester = IngestionEngine::new(config).unwrap();
+        assert_eq!(requester.connect_bootstrap().await.connected(), 1);
+
+        let fetched = requester.request_history(publisher_id, start, 10).await.unwrap();
+        assert_eq!(fetched.len(), 3);
+        for (fetched, published) in fetched.iter().zip(&published) {
+            assert_eq!(fetched.signing_bytes(), published.signing_bytes());
+        }
+        assert_eq!(requester.invalid_signatures(), 1);
+
+        let later = requester.request_history(publisher_id, start + 1, 1).await.unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].timestamp, start + 1);
+    }
+
+    #[test]
+    fn test_private_topic_readable_only_with_key() {
+        let (old, new) = (b"consortium 2025".to_vec(), b"consortium 2026".to_vec());
+        let private = |keys: Vec<Vec<u8>>| NetworkConfig {
+            topics: vec![TopicConfig::new("olo-fragility").with_keys(keys)],
+            ..NetworkConfig::default()
+        };
+        let mut rotated = IngestionEngine::new(private(vec![new, old.clone()])).unwrap();
+        let mut lagging = IngestionEngine::new(private(vec![old])).unwrap();
+        let mut outsider = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let topic = rotated.default_topic.hash();
+        let packet = |timestamp| {
+            let mut packet = DataPacket { timestamp, ..plain_packet() };
+            packet.sign(&node_key()).unwrap();
+            gossip(&packet)
+        };
+        fn delivered(engine: &mut IngestionEngine, data: Vec<u8>) -> bool {
+            let message = gossipsub::Message {
+                source: Some(node_key().public().to_peer_id()),
+                data,
+                sequence_number: Some(1),
+                topic: engine.default_topic.hash(),
+            };
+            engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: node_key().public().to_peer_id(),
+                message_id: gossipsub::MessageId::new(b"1"),
+                message,
+            })));
+            engine.inbox.drain(..).any(|event| matches!(event, EngineEvent::PacketReceived { .. }))
+        }
+        let start = now_millis();
+
+        // Sealed with the new key: only the rotated node reads it
+        let sealed = rotated.seal(&topic, packet(start));
+        assert!(!delivered(&mut outsider, sealed.clone()));
+        assert_eq!(outsider.metrics_snapshot().rejected(RejectReason::Undecodable), 1);
+        assert!(!delivered(&mut lagging, sealed.clone()));
+        assert_eq!(lagging.metrics_snapshot().rejected(RejectReason::Unauthenticated), 1);
+        assert!(delivered(&mut rotated, sealed));
+
+        // Mid-rotation, traffic sealed with the old key is still read
+        assert!(delivered(&mut rotated, lagging.seal(&topic, packet(start + 1))));
+
+        // Unsealed or forged payloads are refused
+        assert!(!delivered(&mut rotated, packet(start + 2)));
+        let guessed = TopicCipher::new("olo-fragility", &[b"guess".to_vec()]).unwrap();
+        assert!(!delivered(&mut rotated, guessed.seal(&packet(start + 3))));
+        assert_eq!(rotated.metrics_snapshot().rejected(RejectReason::Unauthenticated), 2);
+    }
+
+    #[test]
+    fn test_silent_peer_reported_stale() {
+        let config = NetworkConfig { stale_after: Duration::from_secs(60), ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        let peer = PeerId::random();
+        let connected = now_millis();
+        engine.health.connected_at(peer, connected);
+
+        // The mock clock stands still past the threshold, then moves beyond it
+        engine.sweep_health_at(connected + 60_000);
+        assert!(engine.inbox.is_empty());
+        engine.sweep_health_at(connected + 60_001);
+        assert!(matches!(
+            engine.inbox.pop_front(),
+            Some(EngineEvent::PeerStale { peer: p, silent }) if p == peer && silent == Duration::from_millis(60_001)
+        ));
+        assert_eq!(engine.peer_health()[&peer].status, PeerStatus::Stale);
+        engine.sweep_health_at(connected + 120_000);
+        assert!(engine.inbox.is_empty());
+
+        engine.on_behaviour_event(NodeEvent::Ping(ping::Event {
+            peer,
+            connection: libp2p::swarm::ConnectionId::new_unchecked(0),
+            result: Ok(Duration::from_millis(25)),
+        }));
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::PeerRecovered(p)) if p == peer));
+        let health = engine.peer_health()[&peer];
+        assert_eq!((health.status, health.rtt_ms), (PeerStatus::Alive, Some(25)));
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_engines_converge_on_connect() {
+        let config = NetworkConfig { sync_horizon: Some(Duration::from_secs(3600)), ..NetworkConfig::default() };
+        let mut first = IngestionEngine::new(config.clone()).unwrap();
+        let mut second = IngestionEngine::new(config).unwrap();
+        let addr = first.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+
+        // Partitioned: each publishes without peers, keeping the packets
+        let start = now_millis();
+        for i in 0..2 {
+            let packet = DataPacket { timestamp: start + i, source: first.local_peer_id().to_string(), ..plain_packet() };
+            assert!(first.publish(packet).await.is_err());
+        }
+        for i in 0..3 {
+            let packet = DataPacket { timestamp: start + i, source: second.local_peer_id().to_string(), ..plain_packet() };
+            assert!(second.publish(packet).await.is_err());
+        }
+        // A packet the second node heard from a third before the partition healed
+        let third = Keypair::generate_ed25519();
+        let mut heard = DataPacket { timestamp: start, source: third.public().to_peer_id().to_string(), ..plain_packet() };
+        heard.sign(&third).unwrap();
+        second.persist(&heard);
+
+        second.swarm.dial(addr).unwrap();
+        let (mut into_first, mut into_second) = (None, None);
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while into_first.is_none() || into_second.is_none() {
+                tokio::select! {
+                    event = first.next_event() => if let EngineEvent::Synced { packets, .. } = event {
+                        into_first = Some(packets);
+                    },
+                    event = second.next_event() => if let EngineEvent::Synced { packets, .. } = event {
+                        into_second = Some(packets);
+                    },
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!((into_first, into_second), (Some(4), Some(2)));
+
+        let view = |engine: &IngestionEngine| -> HashMap<String, u64> {
+            let latest = engine.packet_store().unwrap().latest_per_source().unwrap();
+            latest.into_iter().map(|(source, packet)| (source, packet.timestamp)).collect()
+        };
+        assert_eq!(view(&first).len(), 3);
+        assert_eq!(view(&first), view(&second));
+    }
+
+    #[tokio::test]
+    async fn test_peers_exchange_identify() {
+        let mut full = IngestionEngine::builder().wire_format(WireFormat::Cbor).build().unwrap();
+        let addr = full.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let mut observer = IngestionEngine::builder().role(NodeRole::ObserverOnly).build().unwrap();
+        let (full_id, observer_id) = (full.local_peer_id(), observer.local_peer_id());
+        observer.swarm.dial(addr).unwrap();
+
+        let (mut heard_full, mut heard_observer) = (None, None);
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while heard_full.is_none() || heard_observer.is_none() {
+                tokio::select! {
+                    event = full.next_event() => if let EngineEvent::PeerIdentified { peer, agent_version } = event {
+                        heard_observer = Some((peer, agent_version));
+                    },
+                    event = observer.next_event() => if let EngineEvent::PeerIdentified { peer, agent_version } = event {
+                        heard_full = Some((peer, agent_version));
+                    },
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(heard_full, Some((full_id, full.user_agent())));
+        assert_eq!(heard_observer, Some((observer_id, observer.user_agent())));
+
+        let info = observer.peer_info(&full_id).unwrap();
+        assert_eq!(info.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(info.protocol_version, peer_info::PROTOCOL_VERSION);
+        assert_eq!(info.capabilities, Some(Capabilities::local(NodeRole::Full)));
+        assert!(info.speaks(&HISTORY_PROTOCOL) && info.speaks(&SYNC_PROTOCOL));
+        let info = full.peer_info(&observer_id).unwrap();
+        assert_eq!(info.capabilities.as_ref().map(|caps| caps.role), Some(NodeRole::ObserverOnly));
+        assert_eq!(full.format_for(&observer_id), WireFormat::Cbor);
+        assert_eq!(full.format_for(&PeerId::random()), WireFormat::Json);
+    }
+
+    #[tokio::test]
+    async fn test_publish_deferred_until_peer_joins() {
+        let mut listener = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let mut publisher = IngestionEngine::builder().publish_retry(RetryPolicy::default()).build().unwrap();
+
+        let packet = DataPacket { timestamp: now_millis(), source: publisher.local_peer_id().to_string(), ..plain_packet() };
+        publisher.publish(packet.clone()).await.unwrap();
+        assert!(matches!(
+            publisher.inbox.pop_front(),
+            Some(EngineEvent::PublishDeferred { message: NetworkMessage::Fragility(_), .. })
+        ));
+        assert_eq!(publisher.deferred_publishes(), 1);
+
+        publisher.swarm.dial(addr).unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                tokio::select! {
+                    event = listener.next_event() => if let EngineEvent::PacketReceived { packet, .. } = event {
+                        return packet;
+                    },
+                    event = publisher.next_event() => assert!(!matches!(event, EngineEvent::PublishExpired { .. })),
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!((received.source, received.timestamp), (packet.source, packet.timestamp));
+        assert_eq!(publisher.metrics_snapshot().messages_published, 1);
+        assert_eq!(publisher.deferred_publishes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_outbox_replayed_once_after_restart() {
+        let path = std::env::temp_dir().join(format!("olo-engine-outbox-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        // Replayed packets carry the first run's signature, so the identity must persist too
+        let key_path = std::env::temp_dir().join(format!("olo-engine-outbox-{}.key", std::process::id()));
+        let _ = fs::remove_file(&key_path);
+        let mut engine = IngestionEngine::builder().outbox_path(&path).identity_path(&key_path).build().unwrap();
+        let packet = DataPacket { timestamp: now_millis(), source: engine.local_peer_id().to_string(), ..plain_packet() };
+        engine.publish(packet.clone()).await.unwrap();
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::PublishDeferred { .. })));
+        assert_eq!(engine.outbox_depth(), 1);
+        // Stopped before any peer could take it
+        drop(engine);
+
+        let mut listener = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let mut restarted = IngestionEngine::builder().outbox_path(&path).identity_path(&key_path).build().unwrap();
+        assert_eq!(restarted.outbox_depth(), 1);
+        restarted.swarm.dial(addr).unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                tokio::select! {
+                    event = listener.next_event() => if let EngineEvent::PacketReceived { packet, .. } = event {
+                        return packet;
+                    },
+                    _ = restarted.next_event() => {}
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!((received.source, received.timestamp), (packet.source, packet.timestamp));
+        assert_eq!(restarted.outbox_depth(), 0);
+        assert_eq!(restarted.metrics_snapshot().messages_published, 1);
+        assert_eq!(restarted.compact_outbox().unwrap(), 2);
+        drop(restarted);
+
+        // Acknowledged, so not replayed again
+        let again = IngestionEngine::builder().outbox_path(&path).build().unwrap();
+        assert_eq!(again.outbox_depth(), 0);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&key_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_packet_dropped_on_receipt() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let node = node_key().public().to_peer_id();
+        let skew = engine.config.max_future_skew.as_millis() as u64;
+        let signed = |timestamp: u64, expires_at: u64| {
+            let mut packet = DataPacket { timestamp, expires_at: Some(expires_at), ..plain_packet() };
+            packet.sign(&node_key()).unwrap();
+            gossip(&packet)
+        };
+        let now = now_millis();
+        assert!(engine.receive(&signed(now, now - skew - 1_000), Some(&node)).is_none());
+        assert_eq!(engine.metrics_snapshot().rejected(RejectReason::Expired), 1);
+        assert!(engine.inbox.is_empty());
+        // Within the allowed clock skew it still arrives in time
+        let packet = engine.receive(&signed(now + 1, now - 1_000), Some(&node));
+        assert!(matches!(packet, Some(NetworkMessage::Fragility(packet)) if packet.expires_at == Some(now - 1_000)));
+    }
+
+    #[tokio::test]
+    async fn test_packet_expiring_before_send_not_transmitted() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let sender = engine.get_sender();
+        sender.try_send(DataPacket { expires_at: Some(now_millis() + 50), ..plain_packet() }).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let error = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let EngineEvent::PublishFailed { error } = engine.next_event().await {
+                    return error;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(error.starts_with("message expired at "), "{}", error);
+        // Refused before it was signed and stored
+        assert!(engine.packet_store().unwrap().is_empty());
+
+        let path = std::env::temp_dir().join(format!("olo-engine-outbox-expiry-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut engine = IngestionEngine::builder().outbox_path(&path).build().unwrap();
+        let alert = NetworkMessage::Alert {
+            severity: Severity::Warning,
+            source: engine.local_peer_id().to_string(),
+            text: "liquidity thinning".to_string(),
+            fragility: None,
+            expires_at: Some(now_millis() + 50),
+        };
+        engine.publish_message("olo-fragility", alert).await.unwrap();
+        assert_eq!(engine.outbox_depth(), 1);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // A peer joins the topic after the alert expired
+        engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Subscribed {
+            peer_id: node_key().public().to_peer_id(),
+            topic: engine.default_topic.hash(),
+        })));
+        assert_eq!(engine.outbox_depth(), 0);
+        assert_eq!(engine.metrics_snapshot().messages_published, 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_published_packets_stored_across_restart() {
+        let path = std::env::temp_dir().join(format!("olo-engine-store-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = NetworkConfig { store_path: Some(path.clone()), ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config.clone()).unwrap();
+        let source = engine.local_peer_id().to_string();
+        let start = now_millis();
+        for i in 0..3 {
+            let packet = DataPacket { timestamp: start + i, source: source.clone(), ..plain_packet() };
+            assert!(engine.publish(packet).await.is_err());
+        }
+        drop(engine);
+
+        let mut restarted = IngestionEngine::new(config).unwrap();
+        let stored = restarted.packet_store().unwrap().by_source(&source, start..start + 3).unwrap();
+        assert_eq!(stored.iter().map(|packet| packet.timestamp).collect::<Vec<_>>(), vec![start, start + 1, start + 2]);
+        assert_eq!(restarted.packet_store().unwrap().latest_per_source().unwrap()[&source].timestamp, start + 2);
+        assert_eq!(restarted.packet_store_mut().unwrap().prune_older_than(start + 2).unwrap(), 2);
+        assert_eq!(restarted.store_errors(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_message_kinds_delivered_and_filtered() {
+        let origin = node_key().public().to_peer_id();
+        let alert = NetworkMessage::Alert {
+            severity: Severity::Critical,
+            source: origin.to_string(),
+            text: "fragility above 70".to_string(),
+            fragility: Some(71.0),
+            expires_at: None,
+        };
+        let mut packet = plain_packet();
+        packet.sign(&node_key()).unwrap();
+        let gossiped = |engine: &mut IngestionEngine, sequence: u64, data: Vec<u8>| {
+            let message = gossipsub::Message {
+                source: Some(origin),
+                data,
+                sequence_number: Some(sequence),
+                topic: engine.default_topic.hash(),
+            };
+            engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: origin,
+                message_id: gossipsub::MessageId::new(&sequence.to_be_bytes()),
+                message,
+            })));
+        };
+
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        gossiped(&mut engine, 1, wire::encode_message(&alert, WireFormat::Bincode).unwrap());
+        gossiped(&mut engine, 2, gossip(&packet));
+        assert!(matches!(
+            engine.inbox.pop_front(),
+            Some(EngineEvent::MessageReceived { message: NetworkMessage::Alert { severity: Severity::Critical, .. }, .. })
+        ));
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::PacketReceived { .. })));
+        // Only packets are stored
+        assert_eq!(engine.packet_store().unwrap().len(), 1);
+
+        let mut alerts_only = IngestionEngine::new(NetworkConfig::default())
+            .unwrap()
+            .with_message_kinds([MessageKind::Alert]);
+        gossiped(&mut alerts_only, 1, gossip(&packet));
+        gossiped(&mut alerts_only, 2, wire::encode_message(&alert, WireFormat::Json).unwrap());
+        let delivered: Vec<_> = alerts_only.inbox.drain(..).collect();
+        assert_eq!(delivered.len(), 1);
+        assert!(matches!(&delivered[0], EngineEvent::MessageReceived { message, .. } if message.kind() == MessageKind::Alert));
+    }
+
+    #[tokio::test]
+    async fn test_synced_packets_from_the_future_rejected() {
+        // A validator lenient about time, so only the freshness window judges it
+        let lenient = PacketValidator::default().with_max_clock_skew(Duration::from_secs(365 * 24 * 3600));
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap().with_packet_validator(lenient);
+        let server = Keypair::generate_ed25519().public().to_peer_id();
+        let hour = 3_600_000;
+        let signed = |timestamp| {
+            let mut packet = DataPacket { timestamp, ..plain_packet() };
+            packet.sign(&node_key()).unwrap();
+            gossip(&packet)
+        };
+        // History a month old is what sync is for; a report stamped hours ahead is not
+        let packets = vec![signed(now_millis() - 30 * 24 * hour), signed(now_millis() + 3 * hour)];
+        let response = SyncResponse { packets, truncated: false };
+        engine.apply_sync(server, 0, response);
+
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::PacketReceived { .. })));
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::Synced { packets: 1, .. })));
+        assert_eq!(engine.packet_store().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deltas_rebuild_state_and_resync_on_mismatch() {
+        let origin = node_key().public().to_peer_id();
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let gossiped = |engine: &mut IngestionEngine, sequence: u64, message: &NetworkMessage| {
+            let message = gossipsub::Message {
+                source: Some(origin),
+                data: wire::encode_message(message, WireFormat::Json).unwrap(),
+                sequence_number: Some(sequence),
+                topic: engine.default_topic.hash(),
+            };
+            engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: origin,
+                message_id: gossipsub::MessageId::new(&sequence.to_be_bytes()),
+                message,
+            })));
+        };
+        let mut snapshot = plain_packet();
+        snapshot.source = origin.to_string();
+        snapshot.timestamp -= 3_000;
+        snapshot.sign(&node_key()).unwrap();
+        let mut first = snapshot.clone();
+        first.timestamp += 1_000;
+        first.state.tier1_capital = 7_500.0;
+        first.fragility = compute_fragility(&first.state, &LagrangianConfig::default());
+        let mut second = first.clone();
+        second.timestamp += 1_000;
+        second.state.liquidity_coverage = 0.9;
+        second.fragility = compute_fragility(&second.state, &LagrangianConfig::default());
+        let deltas = [StateDelta::between(&snapshot, &first), StateDelta::between(&first, &second)];
+
+        gossiped(&mut engine, 1, &NetworkMessage::Fragility(snapshot.clone()));
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::PacketReceived { .. })));
+        for (sequence, delta) in (2..).zip(&deltas) {
+            gossiped(&mut engine, sequence, &NetworkMessage::StateDelta(delta.clone()));
+        }
+        // A repeat changes nothing
+        gossiped(&mut engine, 4, &NetworkMessage::StateDelta(deltas[0].clone()));
+        let rebuilt: Vec<DataPacket> = engine
+            .inbox
+            .drain(..)
+            .map(|event| match event {
+                EngineEvent::DeltaApplied { packet, .. } => packet,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(rebuilt.len(), 2);
+        assert_eq!((rebuilt[0].timestamp, rebuilt[0].state.tier1_capital), (first.timestamp, 7_500.0));
+        assert_eq!(state_hash(&rebuilt[1].state), state_hash(&second.state));
+        assert_eq!(rebuilt[1].fragility, second.fragility);
+        assert_eq!(engine.packet_store().unwrap().len(), 1);
+
+        // A delta made from a state this node never saw
+        let mut skipped = second.clone();
+        skipped.state.entropy_index = 1.5;
+        let mut third = skipped.clone();
+        third.timestamp += 2_000;
+        third.state.total_assets = 90_000.0;
+        gossiped(&mut engine, 5, &NetworkMessage::StateDelta(StateDelta::between(&skipped, &third)));
+        assert!(matches!(
+            engine.inbox.pop_front(),
+            Some(EngineEvent::ResyncRequested { peer, source }) if peer == origin && source == origin.to_string()
+        ));
+        assert_eq!(engine.syncs.values().collect::<Vec<_>>(), [&second.timestamp]);
+        assert!(engine.inbox.is_empty());
+
+        // Nobody else may speak for the source
+        let mut forged = StateDelta::between(&second, &third);
+        forged.source = Keypair::generate_ed25519().public().to_peer_id().to_string();
+        gossiped(&mut engine, 6, &NetworkMessage::StateDelta(forged));
+        assert!(engine.inbox.is_empty());
+        assert_eq!(engine.metrics_snapshot().rejected(RejectReason::BadSignature), 1);
+
+        // A rebuilt state must pass the checks a full packet would
+        let mut unfounded = second.clone();
+        unfounded.timestamp += 1_000;
+        unfounded.fragility = 99.0;
+        gossiped(&mut engine, 7, &NetworkMessage::StateDelta(StateDelta::between(&second, &unfounded)));
+        assert!(matches!(
+            engine.inbox.pop_front(),
+            Some(EngineEvent::PacketRejected { reason }) if reason.contains("does not match")
+        ));
+        let mut ahead = second.clone();
+        ahead.timestamp += 3_600_000;
+        gossiped(&mut engine, 8, &NetworkMessage::StateDelta(StateDelta::between(&second, &ahead)));
+        assert!(matches!(
+            engine.inbox.pop_front(),
+            Some(EngineEvent::PacketRejected { reason }) if reason.contains("timestamp")
+        ));
+        // Neither became the base for later deltas
+        assert_eq!(engine.states.timestamp(&origin.to_string()), Some(second.timestamp));
+    }
+
+    #[tokio::test]
+    async fn test_deltas_refused_when_proofs_required() {
+        let origin = node_key().public().to_peer_id();
+        let mut engine = verifying_engine(ProofPolicy::Drop);
+        let base = proven_packet(None);
+        let mut next = base.clone();
+        next.timestamp += 1;
+        next.state.tier1_capital = 7_500.0;
+        next.fragility = compute_fragility(&next.state, &LagrangianConfig::default());
+
+        assert!(engine.receive(&gossip(&base), Some(&origin)).is_some());
+        engine.states.record(&base);
+        let delta = NetworkMessage::StateDelta(StateDelta::between(&base, &next));
+        let data = wire::encode_message(&delta, WireFormat::Json).unwrap();
+        let Some(NetworkMessage::StateDelta(delta)) = engine.receive(&data, Some(&origin)) else {
+            panic!("delta from its source not received");
+        };
+        // No proof comes with the rebuilt state, so it is dropped and its source flagged
+        assert!(engine.apply_delta(&delta).is_none());
+        assert_eq!(engine.flagged().get(&origin.to_string()), Some(&1));
+        assert_eq!(engine.metrics_snapshot().rejected(RejectReason::ProofFailed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscriptions_get_only_matches() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let banks: Vec<_> = (1..=3).map(|n| Keypair::ed25519_from_bytes([n; 32]).unwrap()).collect();
+        let ids: Vec<_> = banks.iter().map(|bank| bank.public().to_peer_id().to_string()).collect();
+        let mut alerting = engine.subscribe_filtered(
+            PacketFilter::default().with_min_fragility(70.0).with_sources([ids[0].clone(), ids[1].clone()]),
+        );
+        let mut calm = engine.subscribe_filtered(PacketFilter::default().with_max_fragility(30.0).with_capacity(1));
+        let start = now_millis();
+        let reports = [(0, 82.0), (2, 91.0), (1, 20.0), (1, 75.0), (2, 10.0)];
+        for (i, (bank, fragility)) in reports.into_iter().enumerate() {
+            let origin = banks[bank].public().to_peer_id();
+            let mut packet = DataPacket { timestamp: start + i as u64, source: ids[bank].clone(), fragility, ..plain_packet() };
+            packet.sign(&banks[bank]).unwrap();
+            let message = gossipsub::Message {
+                source: Some(origin),
+                data: gossip(&packet),
+                sequence_number: Some(i as u64),
+                topic: engine.default_topic.hash(),
+            };
+            engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: origin,
+                message_id: gossipsub::MessageId::new(&i.to_be_bytes()),
+                message,
+            })));
+        }
+
+        // Every packet is still delivered as an event
+        assert_eq!(engine.inbox.len(), reports.len());
+        let received = |rx: &mut mpsc::Receiver<DataPacket>| {
+            std::iter::from_fn(|| rx.try_recv().ok()).map(|p| (p.source, p.fragility)).collect::<Vec<_>>()
+        };
+        assert_eq!(received(&mut alerting), [(ids[0].clone(), 82.0), (ids[1].clone(), 75.0)]);
+        // The second calm packet found the one-packet channel full
+        assert_eq!(received(&mut calm), [(ids[1].clone(), 20.0)]);
+        assert_eq!(engine.subscription_drops(), [0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_tracks_received_and_published() {
+        let config = NetworkConfig { aggregate_publish_interval: Some(Duration::from_millis(50)), ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        let start = now_millis();
+        for (i, fragility) in [80.0, 40.0].into_iter().enumerate() {
+            let bank = Keypair::ed25519_from_bytes([i as u8 + 1; 32]).unwrap();
+            let origin = bank.public().to_peer_id();
+            let mut packet = DataPacket { timestamp: start + i as u64, source: origin.to_string(), fragility, ..plain_packet() };
+            packet.sign(&bank).unwrap();
+            let message = gossipsub::Message {
+                source: Some(origin),
+                data: gossip(&packet),
+                sequence_number: Some(i as u64),
+                topic: engine.default_topic.hash(),
+            };
+            engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+                propagation_source: origin,
+                message_id: gossipsub::MessageId::new(&i.to_be_bytes()),
+                message,
+            })));
+        }
+        let own = DataPacket { source: engine.local_peer_id().to_string(), fragility: 60.0, ..plain_packet() };
+        assert!(engine.publish(own).await.is_err());
+
+        let snapshot = engine.aggregate_snapshot().unwrap();
+        assert_eq!(snapshot.sources, 3);
+        // Equal assets, so a plain mean
+        assert!((snapshot.weighted_fragility.unwrap() - 60.0).abs() < 1e-9);
+        assert_eq!(snapshot.high_fragility_sources, 1);
+
+        // The periodic publish has no peers to reach
+        engine.inbox.clear();
+        let event = tokio::time::timeout(Duration::from_secs(5), engine.next_event()).await.unwrap();
+        assert!(matches!(event, EngineEvent::PublishFailed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_follow_traffic() {
+        let mut listener = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut publisher = IngestionEngine::new(config).unwrap();
+        let published = publisher.metrics();
+        connect_bootstrap_to(&mut publisher, &mut listener).await;
+        let sender = publisher.get_sender();
+        tokio::spawn(async move {
+            loop {
+                // Publishing fails until the listener's subscription arrives
+                let _ = publisher.next_event().await;
+            }
+        });
+
+        let start = now_millis();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            for i in 0.. {
+                sender.send(DataPacket { timestamp: start + i, ..plain_packet() }).await.unwrap();
+                let next = tokio::time::timeout(Duration::from_millis(500), listener.process_events()).await;
+                if let Ok(Ok(Some(_))) = next {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(listener.receive(b"\x00\x02not json", None).is_none());
+
+        let sent = published.snapshot();
+        let received = listener.metrics_snapshot();
+        assert!(sent.messages_published >= 1);
+        assert!(sent.bytes_out > 0);
+        assert_eq!(received.messages_received, 1);
+        assert!(received.bytes_in > 0 && received.bytes_in <= sent.bytes_out);
+        assert_eq!(received.rejected(RejectReason::Undecodable), 1);
+        assert_eq!(received.connected_peers, 1);
+        let text = listener.render_prometheus();
+        assert!(text.contains("olo_messages_received_total 1\n"));
+        assert!(text.contains("olo_connected_peers 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_oldest() {
+        let mut listener = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig {
+            bootstrap_peers: vec![addr.to_string()],
+            outbound_capacity: 4,
+            overflow_policy: OverflowPolicy::DropOldest,
+            ..NetworkConfig::default()
+        };
+        let mut publisher = IngestionEngine::new(config).unwrap();
+        connect_bootstrap_to(&mut publisher, &mut listener).await;
+        let start = now_millis();
+
+        // Publish probes until one gets through, so the mesh is up
+        tokio::time::timeout(Duration::from_secs(30), async {
+            for i in 0.. {
+                let _ = publisher.publish(DataPacket { timestamp: start + i, ..plain_packet() }).await;
+                let arrived = tokio::time::timeout(Duration::from_millis(500), async {
+                    loop {
+                        tokio::select! {
+                            _ = publisher.next_event() => {}
+                            event = listener.next_event() => {
+                                if let EngineEvent::PacketReceived { .. } = event {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                })
+                .await;
+                if arrived.is_ok() {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        // Queue ten packets while the publisher is not being driven
+        let batch = start + 100_000;
+        let sender = publisher.get_sender();
+        for i in 0..10 {
+            sender.try_send(DataPacket { timestamp: batch + i, ..plain_packet() }).unwrap();
+        }
+        assert_eq!(sender.len(), 4);
+
+        let mut received = Vec::new();
+        let mut dropped = 0;
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while received.len() < 4 {
+                tokio::select! {
+                    event = publisher.next_event() => {
+                        if let EngineEvent::OutboundDropped { dropped: count } = event {
+                            dropped += count;
+                        }
+                    }
+                    event = listener.next_event() => {
+                        if let EngineEvent::PacketReceived { packet, .. } = event {
+                            if packet.timestamp >= batch {
+                                received.push(packet.timestamp - batch);
+                            }
+                        }
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        // Gossipsub may deliver them in any order; `outbound` tests the queue's own order
+        received.sort_unstable();
+        assert_eq!(received, [6, 7, 8, 9]);
+        assert_eq!(dropped, 6);
+        assert_eq!(publisher.metrics_snapshot().outbound_dropped, 6);
+    }
+
+    #[tokio::test]
+    async fn test_packet_exchanged_over_websocket() {
+        let mut listener = IngestionEngine::builder()
+            .listen_addr("/ip4/127.0.0.1/tcp/0")
+            .enable_websocket(true)
+            .ws_listen_addr("/ip4/127.0.0.1/tcp/0/ws")
+            .build()
+            .unwrap();
+        let bound = listener.listen_configured().await.unwrap();
+        assert_eq!(bound.len(), 2);
+        let ws = bound[1].clone();
+        assert!(ws.iter().any(|protocol| matches!(protocol, Protocol::Ws(_))));
+        assert!(!bound[0].iter().any(|protocol| matches!(protocol, Protocol::Ws(_))));
+        assert!(bound.iter().all(|addr| listener.listen_addrs().contains(addr)));
+
+        let mut publisher =
+            IngestionEngine::builder().enable_websocket(true).bootstrap_peers([ws.to_string()]).build().unwrap();
+        assert_eq!(connect_bootstrap_to(&mut publisher, &mut listener).await.connected(), 1);
+
+        let start = now_millis();
+        let received = tokio::time::timeout(Duration::from_secs(30), async {
+            for i in 0.. {
+                let _ = publisher.publish(DataPacket { timestamp: start + i, ..plain_packet() }).await;
+                let arrived = tokio::time::timeout(Duration::from_millis(500), async {
+                    loop {
+                        tokio::select! {
+                            _ = publisher.next_event() => {}
+                            event = listener.next_event() => {
+                                if let EngineEvent::PacketReceived { packet, .. } = event {
+                                    return packet;
+                                }
+                            }
+                        }
+                    }
+                })
+                .await;
+                if let Ok(packet) = arrived {
+                    return packet;
+                }
+            }
+            unreachable!()
+        })
+        .await
+        .unwrap();
+        assert!(received.timestamp >= start);
+
+        // Without the transport, `/ws` addresses are refused
+        let mut plain = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        assert!(plain.listen("/ip4/127.0.0.1/tcp/0/ws".parse().unwrap()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_priority_packet_overtakes_queued_reports() {
+        let config = NetworkConfig { max_packets_per_peer_per_minute: None, ..NetworkConfig::default() };
+        let mut listener = IngestionEngine::new(config).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut publisher = IngestionEngine::new(config).unwrap();
+        connect_bootstrap_to(&mut publisher, &mut listener).await;
+        let start = now_millis();
+
+        // Publish probes until one gets through, so the mesh is up
+        tokio::time::timeout(Duration::from_secs(30), async {
+            for i in 0.. {
+                let _ = publisher.publish(DataPacket { timestamp: start + i, ..plain_packet() }).await;
+                let arrived = tokio::time::timeout(Duration::from_millis(500), async {
+                    loop {
+                        tokio::select! {
+                            _ = publisher.next_event() => {}
+                            event = listener.next_event() => {
+                                if let EngineEvent::PacketReceived { .. } = event {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                })
+                .await;
+                if arrived.is_ok() {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        // A hundred routine reports, then an alert, queued while the publisher is not being driven
+        let batch = start + 100_000;
+        let sender = publisher.get_sender();
+        for i in 0..100 {
+            sender.try_send(DataPacket { timestamp: batch + i, ..plain_packet() }).unwrap();
+        }
+        let alert = DataPacket { timestamp: batch + 100, ..plain_packet() };
+        sender.try_send_priority(alert).unwrap();
+        // Gossipsub may reorder a burst on the wire, so check the alert leaves the queue first
+        let Some(Outbound::Packet(first)) = publisher.outbound.try_next() else {
+            panic!("the alert should be queued");
+        };
+        assert_eq!(first.timestamp, batch + 100);
+        publisher.publish(*first).await.unwrap();
+
+        let mut received = Vec::new();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while received.len() < 101 {
+                tokio::select! {
+                    _ = publisher.next_event() => {}
+                    event = listener.next_event() => {
+                        if let EngineEvent::PacketReceived { packet, .. } = event {
+                            if packet.timestamp >= batch {
+                                received.push(packet.timestamp - batch);
+                            }
+                        }
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        received.sort_unstable();
+        assert_eq!(received, (0..=100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_peer_outside_allowlist_never_delivers() {
+        let member = PeerId::random();
+        let config = NetworkConfig { allowlist: vec![member], ..NetworkConfig::default() };
+        let mut listener = IngestionEngine::new(config).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut outsider = IngestionEngine::new(config).unwrap();
+        let outsider_id = outsider.local_peer_id();
+        connect_bootstrap_to(&mut outsider, &mut listener).await;
+        let sender = outsider.get_sender();
+        tokio::spawn(async move {
+            loop {
+                let _ = outsider.next_event().await;
+            }
+        });
+
+        let mut rejected = None;
+        let mut delivered = 0;
+        let start = now_millis();
+        let _ = tokio::time::timeout(Duration::from_secs(3), async {
+            for i in 0.. {
+                let _ = sender.try_send(DataPacket { timestamp: start + i, ..plain_packet() });
+                if let Ok(event) = tokio::time::timeout(Duration::from_millis(100), listener.next_event()).await {
+                    match event {
+                        EngineEvent::PeerRejected { peer, reason } => rejected = Some((peer, reason)),
+                        EngineEvent::PacketReceived { .. } => delivered += 1,
+                        _ => {}
+                    }
+                }
+            }
+        })
+        .await;
+        assert_eq!(rejected, Some((outsider_id, AccessDenial::NotAllowlisted)));
+        assert_eq!(delivered, 0);
+
+        // Gossip authored by the outsider is refused even when relayed by a member
+        let denied = listener.metrics_snapshot().rejected(RejectReason::Denied);
+        let message = gossipsub::Message {
+            source: Some(outsider_id),
+            data: gossip(&plain_packet()),
+            sequence_number: Some(1),
+            topic: listener.default_topic.hash(),
+        };
+        listener.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+            propagation_source: member,
+            message_id: gossipsub::MessageId::new(b"relayed"),
+            message,
+        })));
+        assert!(matches!(
+            listener.inbox.pop_back(),
+            Some(EngineEvent::PeerRejected { peer, reason: AccessDenial::NotAllowlisted }) if peer == outsider_id
+        ));
+        assert_eq!(listener.metrics_snapshot().rejected(RejectReason::Denied), denied + 1);
+    }
+
+    #[tokio::test]
+    async fn test_denylisting_connected_peer_cuts_it_off() {
+        let mut listener = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut publisher = IngestionEngine::new(config).unwrap();
+        let publisher_id = publisher.local_peer_id();
+        assert_eq!(connect_bootstrap_to(&mut publisher, &mut listener).await.connected(), 1);
+        let sender = publisher.get_sender();
+        tokio::spawn(async move {
+            loop {
+                let _ = publisher.next_event().await;
+            }
+        });
+
+        let start = now_millis();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            for i in 0.. {
+                sender.send(DataPacket { timestamp: start + i, ..plain_packet() }).await.unwrap();
+                let next = tokio::time::timeout(Duration::from_millis(500), listener.process_events()).await;
+                if let Ok(Ok(Some(_))) = next {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        listener.update_acl(vec![], vec![publisher_id]);
+        loop {
+            if let EngineEvent::PeerRejected { peer, reason } = listener.next_event().await {
+                assert_eq!((peer, reason), (publisher_id, AccessDenial::Denylisted));
+                break;
+            }
+        }
+        let _ = tokio::time::timeout(Duration::from_secs(2), async {
+            for i in 0.. {
+                let _ = sender.try_send(DataPacket { timestamp: start + 1_000 + i, ..plain_packet() });
+                if let Ok(EngineEvent::PacketReceived { packet, .. }) =
+                    tokio::time::timeout(Duration::from_millis(100), listener.next_event()).await
+                {
+                    assert!(packet.timestamp < start + 1_000, "denylisted peer delivered a packet");
+                }
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_stale_and_replayed_packets_rejected() {
+        // Remember one report, so a replay gets past duplicate suppression
+        let config = NetworkConfig { dedup_capacity: 1, ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        let node = node_key().public().to_peer_id();
+        let signed = |packet: DataPacket| {
+            let mut packet = packet;
+            packet.sign(&node_key()).unwrap();
+            gossip(&packet)
+        };
+        let now = now_millis();
+        let hour = 3_600_000;
+
+        let future = signed(DataPacket { timestamp: now + 3 * hour, ..plain_packet() });
+        assert!(engine.receive(&future, Some(&node)).is_none());
+        let ancient = signed(DataPacket { timestamp: now - 30 * 24 * hour, ..plain_packet() });
+        assert!(engine.receive(&ancient, Some(&node)).is_none());
+
+        let original = signed(DataPacket { timestamp: now, ..plain_packet() });
+        assert!(engine.receive(&original, Some(&node)).is_some());
+        assert!(engine.receive(&signed(DataPacket { timestamp: now + 1, ..plain_packet() }), Some(&node)).is_some());
+        // The exact report again, and a different one claiming the same moment
+        assert!(engine.receive(&original, Some(&node)).is_none());
+        let contradicting = signed(DataPacket { timestamp: now, fragility: 99.0, ..plain_packet() });
+        assert!(engine.receive(&contradicting, Some(&node)).is_none());
+
+        let reasons: Vec<String> = engine
+            .inbox
+            .drain(..)
+            .filter_map(|event| match event {
+                EngineEvent::PacketRejected { reason } => Some(reason),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reasons.len(), 4);
+        assert!(reasons[0].starts_with("future timestamp"));
+        assert!(reasons[1].starts_with("stale timestamp"));
+        assert!(reasons[2..].iter().all(|reason| reason.starts_with("replayed timestamp")));
+        let metrics = engine.metrics_snapshot();
+        assert_eq!(metrics.rejected(RejectReason::FutureTimestamp), 1);
+        assert_eq!(metrics.rejected(RejectReason::StaleTimestamp), 1);
+        assert_eq!(metrics.rejected(RejectReason::Replayed), 2);
+        assert_eq!(engine.duplicates(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_packets_read_and_bombs_refused() {
+        let config = NetworkConfig { max_decompressed_size: 1 << 20, ..NetworkConfig::default() };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        let node = node_key().public().to_peer_id();
+        let mut large = DataPacket { proof: Some((0..300_000).map(|i| (i % 251) as u8).collect()), ..plain_packet() };
+        large.sign(&node_key()).unwrap();
+        let message = NetworkMessage::Fragility(large.clone());
+        let compressed = wire::encode_message_with(&message, WireFormat::Cbor, Some(Compression::default())).unwrap();
+        assert!(compressed.len() < 64 * 1024);
+        let delivered = engine.receive(&compressed, Some(&node)).unwrap();
+        assert!(matches!(delivered, NetworkMessage::Fragility(packet) if packet.proof == large.proof));
+
+        let mut bomb = vec![WireFormat::Json.tag() | wire::COMPRESSED_FLAG, wire::SCHEMA_VERSION];
+        bomb.extend_from_slice(&zstd::bulk::compress(&vec![b' '; 2 << 20], 19).unwrap());
+        assert!(engine.receive(&bomb, Some(&node)).is_none());
+        assert_eq!(engine.metrics_snapshot().rejected(RejectReason::TooLarge), 1);
+        assert_eq!(engine.rejected_packets(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_nat_traversal_composed_per_config() {
+        let engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        assert!(!engine.swarm.behaviour().autonat.is_enabled());
+        assert!(!engine.swarm.behaviour().relay.is_enabled());
+        assert_eq!(engine.reachability(), Reachability::Unknown);
+
+        let relay = format!("/ip4/127.0.0.1/tcp/1/p2p/{}", PeerId::random());
+        let config = NetworkConfig {
+            enable_autonat: true,
+            relay_addresses: vec![relay.clone()],
+            ..NetworkConfig::default()
+        };
+        let mut engine = IngestionEngine::new(config).unwrap();
+        assert!(engine.swarm.behaviour().autonat.is_enabled());
+        assert!(engine.swarm.behaviour().relay.is_enabled());
+        // Reachability is not known yet, so no slot is reserved
+        assert!(engine.relay_listeners.is_empty());
+
+        // Without AutoNAT the node reserves at once
+        let config = NetworkConfig { relay_addresses: vec![relay.clone()], ..NetworkConfig::default() };
+        assert_eq!(IngestionEngine::new(config).unwrap().relay_listeners.len(), 1);
+
+        let unnamed = NetworkConfig { relay_addresses: vec!["/ip4/127.0.0.1/tcp/1".to_string()], ..NetworkConfig::default() };
+        let error = IngestionEngine::new(unnamed).err().unwrap();
+        assert!(matches!(error, NetworkError::InvalidRelayAddress { .. }));
+
+        // AutoNAT concludes the node is private, then public
+        let status = |old, new| NodeEvent::Autonat(autonat::Event::StatusChanged { old, new });
+        engine.on_behaviour_event(status(autonat::NatStatus::Unknown, autonat::NatStatus::Private));
+        assert!(matches!(engine.inbox.pop_back(), Some(EngineEvent::ReachabilityChanged(Reachability::Private))));
+        assert_eq!(engine.relay_listeners.len(), 1);
+        assert_eq!(engine.metrics_snapshot().reachability, Reachability::Private);
+
+        let public: Multiaddr = "/ip4/198.51.100.4/tcp/4001".parse().unwrap();
+        engine.on_behaviour_event(status(autonat::NatStatus::Private, autonat::NatStatus::Public(public)));
+        assert!(matches!(engine.inbox.pop_back(), Some(EngineEvent::ReachabilityChanged(Reachability::Public))));
+        assert!(engine.relay_listeners.is_empty());
+        assert_eq!(engine.reachability(), Reachability::Public);
+        assert!(engine.render_prometheus().contains("olo_reachability{status=\"public\"} 1\n"));
+    }
+
+    /// Store that fails every operation
+    struct BrokenStore;
+
+    impl PacketStore for BrokenStore {
+        fn insert(&mut self, _: &DataPacket) -> Result<(), StoreError> {
+            Err(StoreError::Io { path: PathBuf::from("broken"), source: io::ErrorKind::Other.into() })
+        }
+        fn by_source(&self, _: &str, _: std::ops::Range<u64>) -> Result<Vec<DataPacket>, StoreError> {
+            Ok(vec![])
+        }
+        fn since(&self, _: u64) -> Result<Vec<DataPacket>, StoreError> {
+            Ok(vec![])
+        }
+        fn latest_per_source(&self) -> Result<HashMap<String, DataPacket>, StoreError> {
+            Ok(HashMap::new())
+        }
+        fn prune_older_than(&mut self, _: u64) -> Result<usize, StoreError> {
+            Ok(0)
+        }
+        fn len(&self) -> usize {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_failures_counted_not_fatal() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap().with_packet_store(BrokenStore);
+        let origin = node_key().public().to_peer_id();
+        let mut packet = plain_packet();
+        packet.sign(&node_key()).unwrap();
+        let message = gossipsub::Message {
+            source: Some(origin),
+            data: gossip(&packet),
+            sequence_number: Some(1),
+            topic: engine.default_topic.hash(),
+        };
+        engine.on_behaviour_event(NodeEvent::Gossipsub(Box::new(gossipsub::Event::Message {
+            propagation_source: origin,
+            message_id: gossipsub::MessageId::new(&[1]),
+            message,
+        })));
+
+        // Still delivered, just not kept
+        assert!(matches!(engine.inbox.pop_front(), Some(EngineEvent::PacketReceived { .. })));
+        assert_eq!(engine.store_errors(), 1);
+        assert!(engine.publish(plain_packet()).await.is_err());
+        assert_eq!(engine.store_errors(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_connects_and_gossips() {
+        // Proving blocks the runtime, so do it before any connection is open
+        let packet = proven_packet(None);
+        let mut seed = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let packet = DataPacket { source: seed.local_peer_id().to_string(), ..packet };
+        let addr = seed.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let sender = seed.get_sender();
+        tokio::spawn(async move {
+            loop {
+                // Publishing fails until the node's subscription arrives
+                let _ = seed.process_events().await;
+            }
+        });
+
+        let config = NetworkConfig {
+            bootstrap_peers: vec![addr.to_string(), "not a multiaddr".to_string()],
+            ..NetworkConfig::default()
+        };
+        let mut node = IngestionEngine::new(config).unwrap();
+        let report = node.connect_bootstrap().await;
+        assert_eq!(report.connected(), 1);
+        assert!(matches!(report.outcomes[0].1, BootstrapOutcome::Connected { attempts: 1, .. }));
+        assert!(matches!(report.outcomes[1].1, BootstrapOutcome::InvalidAddress { .. }));
+
+        let received = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                sender.send(packet.clone()).await.unwrap();
+                let next = tokio::time::timeout(Duration::from_millis(500), node.process_events()).await;
+                if let Ok(Ok(Some(received))) = next {
+                    return received;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(received.signing_bytes(), packet.signing_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_packets_tagged_with_topic() {
+        let (eu, us, alerts) = ("olo-fragility-eu", "olo-fragility-us", "olo-alerts");
+        let config = NetworkConfig {
+            topics: vec![TopicConfig::new(eu), TopicConfig::new(alerts)],
+            ..NetworkConfig::default()
+        };
+        let mut listener = IngestionEngine::new(config).unwrap();
+        assert_eq!(listener.topics(), [alerts, eu]);
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut publisher = IngestionEngine::new(config).unwrap();
+        connect_bootstrap_to(&mut publisher, &mut listener).await;
+        // Distinct timestamps so no report is a duplicate of another
+        let start = now_millis();
+        let reports = [(eu, start), (us, start + 1), (alerts, start + 2)];
+        tokio::spawn(async move {
+            loop {
+                for (topic, timestamp) in reports {
+                    // Fails until the listener's subscription arrives
+                    let _ = publisher.publish_to(topic, DataPacket { timestamp, ..plain_packet() }).await;
+                }
+                let _ = tokio::time::timeout(Duration::from_millis(200), publisher.next_event()).await;
+            }
+        });
+
+        let mut received = HashMap::new();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while received.len() < 3 {
+                if let EngineEvent::PacketReceived { topic, packet } = listener.next_event().await {
+                    received.insert(topic, packet.timestamp);
+                    if received.len() == 2 {
+                        assert!(listener.subscribe(us).unwrap());
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        // Nothing on the US topic arrived before subscribing to it
+        assert_eq!(received, reports.iter().map(|(topic, t)| (topic.to_string(), *t)).collect());
+        assert!(listener.unsubscribe(alerts).unwrap());
+        assert!(!listener.unsubscribe(alerts).unwrap());
+        assert_eq!(listener.topics(), [eu, us]);
+    }
+
+    /// Unsigned, unproven packet, as a node without a prover gossips
+    fn plain_packet() -> DataPacket {
+        fixtures::packet()
+            .source(node_key().public().to_peer_id())
+            .timestamp(now_millis())
+            .tier1_capital(8_002.5)
+            .computed_fragility()
+            .build()
+    }
+
+    /// `node.connect_bootstrap()`, driving `listener` so it completes the handshakes
+    async fn connect_bootstrap_to(node: &mut IngestionEngine, listener: &mut IngestionEngine) -> BootstrapReport {
+        tokio::select! {
+            report = node.connect_bootstrap() => report,
+            _ = async {
+                loop {
+                    let _ = listener.next_event().await;
+                }
+            } => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_orders_listen_connect_packet() {
+        let packet = plain_packet();
+        let mut listener = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = listener.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut publisher = IngestionEngine::new(config).unwrap();
+        let publisher_id = publisher.local_peer_id();
+        let sender = publisher.get_sender();
+        tokio::spawn(async move {
+            publisher.connect_bootstrap().await;
+            loop {
+                // Publishing fails until the listener's subscription arrives
+                let _ = publisher.next_event().await;
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                if sender.send(packet.clone()).await.is_err() {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+        });
+
+        let mut seen = Vec::new();
+        let events = listener.events();
+        tokio::pin!(events);
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while let Some(event) = events.next().await {
+                match event {
+                    EngineEvent::ListeningOn(_) => seen.push("listening"),
+                    EngineEvent::PeerConnected(peer) if peer == publisher_id => seen.push("connected"),
+                    EngineEvent::PacketReceived { .. } => {
+                        seen.push("packet");
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(seen, ["listening", "connected", "packet"]);
+    }
+
+    #[tokio::test]
+    async fn test_mixed_wire_formats_interoperate() {
+        // The publisher writes CBOR, the listener JSON
+        let mut mesh = TestMesh::with_config(2, |i| NetworkConfig {
+            wire_format: if i == 0 { WireFormat::Cbor } else { WireFormat::Json },
+            ..NetworkConfig::default()
+        })
+        .await
+        .unwrap();
+        let packet = DataPacket { source: mesh.peer_id(0).to_string(), ..plain_packet() };
+        mesh.publish_from(0, packet.clone()).await.unwrap();
+        let received = mesh.collect_on(1, Duration::from_secs(2)).await;
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].signing_bytes(), packet.signing_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_cancel_safe_in_select() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        {
+            let events = engine.events();
+            tokio::pin!(events);
+            tokio::select! {
+                event = events.next() => panic!("unexpected event {:?}", event),
+                _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+            }
+        }
+        // No peers, so the publish fails, and the abandoned poll must not swallow that
+        engine.get_sender().send(plain_packet()).await.unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(5), engine.next_event()).await.unwrap();
+        assert!(matches!(event, EngineEvent::PublishFailed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_listen_reports_assigned_port() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        assert!(engine.listen_addrs().is_empty());
+        let bound = engine.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+
+        let port = |addr: &Multiaddr| {
+            addr.iter().find_map(|protocol| match protocol {
+                libp2p::multiaddr::Protocol::Tcp(port) => Some(port),
+                _ => None,
+            })
+        };
+        assert_ne!(port(&bound), Some(0));
+        assert!(port(&bound).is_some());
+        assert_eq!(engine.listen_addrs(), vec![bound.clone()]);
+
+        engine.swarm.add_external_address(bound.clone());
+        let external: Multiaddr = "/ip4/203.0.113.7/tcp/4001".parse().unwrap();
+        engine.swarm.add_external_address(external.clone());
+        assert_eq!(engine.listen_addrs(), vec![bound, external]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_accounts_for_queued_packets() {
+        let mut seed = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = seed.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        tokio::spawn(async move {
+            loop {
+                let _ = seed.next_event().await;
+            }
+        });
+        let config = NetworkConfig { bootstrap_peers: vec![addr.to_string()], ..NetworkConfig::default() };
+        let mut node = IngestionEngine::new(config).unwrap();
+        node.connect_bootstrap().await;
+        // Wait for the seed's subscription, without which every publish fails
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while !node.swarm.behaviour().gossipsub.all_peers().any(|(_, topics)| !topics.is_empty()) {
+                let _ = tokio::time::timeout(Duration::from_millis(100), node.next_event()).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let sender = node.get_sender();
+        for timestamp in 0..5 {
+            sender.send(DataPacket { timestamp, ..plain_packet() }).await.unwrap();
+        }
+        let report = node.shutdown(Duration::from_secs(5)).await;
+        assert_eq!(report, ShutdownReport { flushed: 5, dropped: 0 });
+        assert!(sender.send(plain_packet()).await.is_err());
+
+        let events: Vec<_> = node.events().collect().await;
+        assert!(matches!(events.last(), Some(EngineEvent::ShuttingDown)));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_without_peers_drops_queued_packets() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        engine.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        let sender = engine.get_sender();
+        for timestamp in 0..3 {
+            sender.send(DataPacket { timestamp, ..plain_packet() }).await.unwrap();
+        }
+
+        let report = engine.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(report, ShutdownReport { flushed: 0, dropped: 3 });
+        assert!(engine.listeners.is_empty());
+        assert!(matches!(engine.next_event().await, EngineEvent::ListeningOn(_)));
+        assert!(matches!(engine.next_event().await, EngineEvent::ShuttingDown));
+        assert!(matches!(engine.next_event().await, EngineEvent::ShuttingDown));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_bootstrap_peer_retried() {
+        let config = NetworkConfig {
+            bootstrap_peers: vec!["/ip4/127.0.0.1/tcp/1".to_string()],
+            bootstrap_attempts: 3,
+            bootstrap_backoff: Duration::from_millis(20),
+            ..NetworkConfig::default()
+        };
+        let mut node = IngestionEngine::new(config).unwrap();
+        let started = std::time::Instant::now();
+        let report = node.connect_bootstrap().await;
+        assert!(matches!(report.outcomes[0].1, BootstrapOutcome::Unreachable { attempts: 3, .. }));
+        assert_eq!(report.connected(), 0);
+        // Backoff of 20 ms, then 40 ms
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn test_discovery_respects_allowlist() {
+        let allowed = Keypair::generate_ed25519().public().to_peer_id();
+        let stranger = Keypair::generate_ed25519().public().to_peer_id();
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/1".parse().unwrap();
+        let config = NetworkConfig {
+            discovery_allowlist: Some(vec![allowed]),
+            ..NetworkConfig::default()
+        };
+        let mut engine = IngestionEngine::new(config).unwrap();
+
+        engine.on_behaviour_event(NodeEvent::Mdns(mdns::Event::Discovered(vec![
+            (stranger, addr.clone()),
+            (allowed, addr.clone()),
+        ])));
+        engine.on_behaviour_event(NodeEvent::Mdns(mdns::Event::Expired(vec![(stranger, addr.clone())])));
+        let event = engine.next_event().await;
+        assert!(matches!(event, EngineEvent::PeerDiscovered { peer, .. } if peer == allowed));
+        assert!(engine.inbox.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dht_composed_per_config() {
+        let mut engine = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        assert_eq!(engine.routing_table_size(), 0);
+        // Nothing to bootstrap from yet
+        assert!(engine.bootstrap_dht().await.is_err());
+
+        let client = NetworkConfig { kad_mode: DhtMode::Client, ..NetworkConfig::default() };
+        assert!(IngestionEngine::new(client).is_ok());
+        let unnamed = NetworkConfig { kad_protocol: "olo-kad".to_string(), ..NetworkConfig::default() };
+        assert!(IngestionEngine::new(unnamed).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dht_finds_topic_providers() {
+        let mut seed = IngestionEngine::new(NetworkConfig::default()).unwrap();
+        let addr = seed.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        tokio::spawn(async move {
+            loop {
+                let _ = seed.next_event().await;
+            }
+        });
+        let config = NetworkConfig {
+            bootstrap_peers: vec![addr.to_string()],
+            ..NetworkConfig::default()
+        };
+
+        // Listening gives the seed an address to hand out for the provider
+        let mut provider = IngestionEngine::new(config.clone()).unwrap();
+        provider.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).await.unwrap();
+        provider.connect_bootstrap().await;
+        assert!(provider.bootstrap_dht().await.unwrap() >= 1);
+        let provider_id = provider.local_peer_id();
+        tokio::spawn(async move {
+            loop {
+                let _ = provider.next_event().await;
+            }
+        });
+
+        let mut seeker = IngestionEngine::new(NetworkConfig { kad_mode: DhtMode::Client, ..config }).unwrap();
+        seeker.connect_bootstrap().await;
+        seeker.bootstrap_dht().await.unwrap();
+        let peers = seeker.find_peers("olo-fragility").await.unwrap();
+        assert!(peers.contains(&provider_id));
+        assert!(!peers.contains(&seeker.local_peer_id()));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs multicast on the local network"]
+    async fn test_mdns_engines_discover_each_other() {
+        let config = NetworkConfig {
+            enable_mdns: true,
+            ..NetworkConfig::default()
+        };
+        let mut first = IngestionEngine::new(config.clone()).unwrap();
+        let mut second = IngestionEngine::new(config).unwrap();
+        first.listen("/ip4/0.0.0.0/tcp/0".parse().unwrap()).await.unwrap();
+        second.listen("/ip4/0.0.0.0/tcp/0".parse().unwrap()).await.unwrap();
+        let second_id = second.local_peer_id();
+        tokio::spawn(async move {
+            loop {
+                let _ = second.next_event().await;
+            }
+        });
+
+        let discovered = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let EngineEvent::PeerDiscovered { peer, .. } = first.next_event().await {
+                    if peer == second_id {
+                        return peer;
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(discovered, second_id);
+    }
+
+    #[tokio::test]
+    async fn test_identity_persists_across_engines() {
+        let path = std::env::temp_dir().join(format!("olo-identity-{}.key", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = NetworkConfig { identity_path: Some(path.clone()), ..NetworkConfig::default() };
+
+        let first = IngestionEngine::new(config.clone()).unwrap().local_peer_id();
+        let second = IngestionEngine::new(config.clone()).unwrap().local_peer_id();
+        assert_eq!(first, second);
+        assert_ne!(first, IngestionEngine::new(NetworkConfig::default()).unwrap().local_peer_id());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        fs::write(&path, b"not a keypair").unwrap();
+        let error = IngestionEngine::new(config).err().unwrap().to_string();
+        assert!(error.contains(&path.display().to_string()));
+        fs::remove_file(&path).unwrap();
     }
 }
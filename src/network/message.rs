@@ -0,0 +1,264 @@
+//! Network Messages
+//!
+//! Besides point-in-time fragility packets, nodes gossip simulation
+//! summaries, alerts, standalone proof envelopes, their aggregate view of
+//! the network, and state deltas. Every message on the
+//! wire is a `NetworkMessage`, serialized with its variant name as the tag
+//! (`{"alert": {...}}` in JSON). Messages written before the tag existed
+//! carry a bare `DataPacket`; `wire::decode_message` reads those as
+//! `Fragility` messages.
+//!
+//! Packets, alerts, and state deltas may carry an expiry. Past it, receivers drop them and
+//! senders no longer send them, so an alert stops propagating once it is
+//! moot while a daily report stays valid all day.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::network::aggregate::AggregateSnapshot;
+use crate::network::delta::StateDelta;
+use crate::network::ingestion::DataPacket;
+use crate::proofs::envelope::ProofEnvelope;
+use crate::simulation::meta::SimulationMeta;
+use crate::simulation::monte_carlo::SimulationResult;
+
+/// How urgent an alert is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Headline statistics of a Monte Carlo run, with its provenance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationSummary {
+    /// Peer ID of the node that ran the simulation
+    pub source: String,
+    /// Inputs and provenance of the run
+    pub meta: SimulationMeta,
+    /// Mean fragility
+    pub mean: f64,
+    /// Standard deviation
+    pub std_dev: f64,
+    /// 95% Value-at-Risk
+    pub var_95: f64,
+    /// 99% Value-at-Risk
+    pub var_99: f64,
+    /// Maximum fragility observed
+    pub max_fragility: f64,
+}
+
+impl SimulationSummary {
+    /// Summary of `result` as run by `source`; `None` for derived results without metadata
+    pub fn from_result(source: impl Into<String>, result: &SimulationResult) -> Option<Self> {
+        Some(Self {
+            source: source.into(),
+            meta: result.meta.clone()?,
+            mean: result.mean,
+            std_dev: result.std_dev,
+            var_95: result.var_95,
+            var_99: result.var_99,
+            max_fragility: result.max_fragility,
+        })
+    }
+}
+
+/// Kind of a `NetworkMessage`, for choosing which kinds to receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Fragility,
+    SimulationSummary,
+    Alert,
+    ProofEnvelope,
+    Aggregate,
+    StateDelta,
+}
+
+impl MessageKind {
+    /// Every kind
+    pub const ALL: [MessageKind; 6] = [
+        MessageKind::Fragility,
+        MessageKind::SimulationSummary,
+        MessageKind::Alert,
+        MessageKind::ProofEnvelope,
+        MessageKind::Aggregate,
+        MessageKind::StateDelta,
+    ];
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageKind::Fragility => write!(f, "fragility"),
+            MessageKind::SimulationSummary => write!(f, "simulation summary"),
+            MessageKind::Alert => write!(f, "alert"),
+            MessageKind::ProofEnvelope => write!(f, "proof envelope"),
+            MessageKind::Aggregate => write!(f, "aggregate"),
+            MessageKind::StateDelta => write!(f, "state delta"),
+        }
+    }
+}
+
+/// Anything a node gossips
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkMessage {
+    /// A bank's state and fragility at a point in time
+    Fragility(DataPacket),
+    /// Results of a Monte Carlo run
+    SimulationSummary(SimulationSummary),
+    /// A notification for operators
+    Alert {
+        severity: Severity,
+        /// Peer ID of the node raising the alert
+        source: String,
+        text: String,
+        /// Fragility that prompted the alert, if any
+        fragility: Option<f64>,
+        /// When the alert stops being worth delivering (Unix epoch
+        /// milliseconds); `None` if it never does
+        #[serde(default)]
+        expires_at: Option<u64>,
+    },
+    /// A proof published on its own, outside a fragility packet
+    ProofEnvelope(ProofEnvelope),
+    /// A node's snapshot of system-wide fragility
+    ///
+    /// Receiving nodes deliver it without folding it into their own
+    /// aggregate, which would count the same packets twice.
+    Aggregate(AggregateSnapshot),
+    /// The fields of a bank's state that changed since an earlier state
+    StateDelta(StateDelta),
+}
+
+impl NetworkMessage {
+    /// Which kind of message this is
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            NetworkMessage::Fragility(_) => MessageKind::Fragility,
+            NetworkMessage::SimulationSummary(_) => MessageKind::SimulationSummary,
+            NetworkMessage::Alert { .. } => MessageKind::Alert,
+            NetworkMessage::ProofEnvelope(_) => MessageKind::ProofEnvelope,
+            NetworkMessage::Aggregate(_) => MessageKind::Aggregate,
+            NetworkMessage::StateDelta(_) => MessageKind::StateDelta,
+        }
+    }
+
+    /// When the message stops being worth delivering, for the kinds that say
+    pub fn expires_at(&self) -> Option<u64> {
+        match self {
+            NetworkMessage::Fragility(packet) => packet.expires_at,
+            NetworkMessage::Alert { expires_at, .. } => *expires_at,
+            NetworkMessage::StateDelta(delta) => delta.expires_at,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::BankState;
+    use crate::network::wire::{self, WireFormat};
+    use crate::proofs::envelope::{BackendId, CircuitId};
+    use std::time::Duration;
+
+    fn messages() -> Vec<NetworkMessage> {
+        let packet = DataPacket {
+            timestamp: 1_700_000_000_000,
+            source: "12D3KooWnode".to_string(),
+            state: BankState {
+                tier1_capital: 10_000.0,
+                total_assets: 100_000.0,
+                liquidity_coverage: 1.2,
+                entropy_index: 2.0,
+            },
+            fragility: 21.5,
+            signature: vec![1; 64],
+            proof: None,
+            period: None,
+            envelope: None,
+            expires_at: None,
+        };
+        let meta = SimulationMeta {
+            fingerprint: "ab".repeat(32),
+            seeds: vec![42],
+            num_simulations: 10_000,
+            sampler: "pseudo_random".to_string(),
+            distributions: vec!["normal".to_string()],
+            crate_version: "0.1.0".to_string(),
+            duration: Duration::from_millis(1_250),
+            degraded_to_streaming: false,
+        };
+        vec![
+            NetworkMessage::Fragility(packet),
+            NetworkMessage::SimulationSummary(SimulationSummary {
+                source: "12D3KooWnode".to_string(),
+                meta,
+                mean: 24.1,
+                std_dev: 3.2,
+                var_95: 29.8,
+                var_99: 33.0,
+                max_fragility: 41.7,
+            }),
+            NetworkMessage::Alert {
+                severity: Severity::Critical,
+                source: "12D3KooWnode".to_string(),
+                text: "fragility above 70".to_string(),
+                fragility: Some(72.4),
+                expires_at: Some(1_700_000_300_000),
+            },
+            NetworkMessage::ProofEnvelope(ProofEnvelope::wrap(
+                BackendId::Bellman,
+                CircuitId::Fragility,
+                vec![7; 192],
+                vec![12_345_678],
+                None,
+                "12D3KooWnode",
+            )),
+            NetworkMessage::Aggregate(AggregateSnapshot {
+                taken_at: 1_700_000_000_000,
+                sources: 12,
+                total_assets: 4.2e12,
+                weighted_fragility: Some(38.5),
+                high_fragility_sources: 2,
+                hourly_trend: None,
+            }),
+            NetworkMessage::StateDelta(StateDelta {
+                source: "12D3KooWnode".to_string(),
+                timestamp: 1_700_000_060_000,
+                base: [3; 32],
+                tier1_capital: Some(9_500.0),
+                total_assets: None,
+                liquidity_coverage: Some(1.1),
+                entropy_index: None,
+                fragility: Some(23.0),
+                expires_at: None,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_every_kind_round_trips_every_format() {
+        for format in [WireFormat::Json, WireFormat::Cbor, WireFormat::Bincode] {
+            for message in messages() {
+                let bytes = wire::encode_message(&message, format).unwrap();
+                let (decoded, used) = wire::decode_message(&bytes).unwrap();
+                assert_eq!(used, format);
+                assert_eq!(decoded.kind(), message.kind());
+                // Byte-identical re-encoding shows nothing was lost
+                assert_eq!(wire::encode_message(&decoded, format).unwrap(), bytes, "{} in {}", message.kind(), format);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_tagged_by_variant() {
+        let alert = &messages()[2];
+        let json: serde_json::Value = serde_json::to_value(alert).unwrap();
+        assert_eq!(json["alert"]["severity"], "critical");
+        assert_eq!(json["alert"]["fragility"], 72.4);
+    }
+}
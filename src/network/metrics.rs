@@ -0,0 +1,274 @@
+//! Engine Metrics
+//!
+//! Counters and gauges describing what a node's engine is doing, updated as
+//! it runs. `NetworkMetrics` is shared behind an `Arc`, so a scrape handler
+//! on another task or thread can read it while the engine runs. A
+//! `MetricsSnapshot` is a plain copy of the values, serializable as JSON or
+//! rendered in the Prometheus text exposition format:
+//!
+//! | Metric                            | Type    | Meaning                                   |
+//! |-----------------------------------|---------|-------------------------------------------|
+//! | `olo_messages_published_total`    | counter | messages published to gossip              |
+//! | `olo_messages_received_total`     | counter | received messages delivered               |
+//! | `olo_packets_rejected_total`      | counter | received messages dropped, by `reason`    |
+//! | `olo_packets_deduplicated_total`  | counter | packets dropped as repeats                |
+//! | `olo_bytes_in_total`              | counter | bytes of gossip messages received         |
+//! | `olo_bytes_out_total`             | counter | bytes of gossip messages published        |
+//! | `olo_outbound_dropped_total`      | counter | queued packets dropped by overflow policy |
+//! | `olo_connected_peers`             | gauge   | peers with an open connection             |
+//! | `olo_mesh_peers`                  | gauge   | peers in the gossip mesh of any topic     |
+//! | `olo_reachability`                | gauge   | 1 for the current `status`, else 0        |
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::network::nat::Reachability;
+
+/// Why a received message was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectReason {
+    /// Its source is not admitted by the allowlist and denylist
+    Denied,
+    /// Its source is banned
+    Banned,
+    /// It was on a private topic and no topic key authenticated it
+    Unauthenticated,
+    /// It could not be decoded
+    Undecodable,
+    /// Its compressed body expanded past the decompression limit
+    TooLarge,
+    /// It was unsigned or not signed by its source
+    BadSignature,
+    /// Its source was over its rate limit
+    RateLimited,
+    /// It was stamped too far ahead of local time
+    FutureTimestamp,
+    /// It was stamped too far behind local time
+    StaleTimestamp,
+    /// Its source and timestamp repeat those of a delivered packet
+    Replayed,
+    /// It failed the packet validator
+    Invalid,
+    /// It failed the proof, identity, or replay checks under `ProofPolicy::Drop`
+    ProofFailed,
+    /// It arrived past its expiry
+    Expired,
+}
+
+impl RejectReason {
+    /// Every reason
+    pub const ALL: [RejectReason; 13] = [
+        RejectReason::Denied,
+        RejectReason::Banned,
+        RejectReason::Unauthenticated,
+        RejectReason::Undecodable,
+        RejectReason::TooLarge,
+        RejectReason::BadSignature,
+        RejectReason::RateLimited,
+        RejectReason::FutureTimestamp,
+        RejectReason::StaleTimestamp,
+        RejectReason::Replayed,
+        RejectReason::Invalid,
+        RejectReason::ProofFailed,
+        RejectReason::Expired,
+    ];
+
+    /// Value of the `reason` label
+    pub fn label(self) -> &'static str {
+        match self {
+            RejectReason::Denied => "denied",
+            RejectReason::Banned => "banned",
+            RejectReason::Unauthenticated => "unauthenticated",
+            RejectReason::Undecodable => "undecodable",
+            RejectReason::TooLarge => "too_large",
+            RejectReason::BadSignature => "bad_signature",
+            RejectReason::RateLimited => "rate_limited",
+            RejectReason::FutureTimestamp => "future_timestamp",
+            RejectReason::StaleTimestamp => "stale_timestamp",
+            RejectReason::Replayed => "replayed",
+            RejectReason::Invalid => "invalid",
+            RejectReason::ProofFailed => "proof_failed",
+            RejectReason::Expired => "expired",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Live engine counters and gauges
+#[derive(Debug, Default)]
+pub struct NetworkMetrics {
+    messages_published: AtomicU64,
+    messages_received: AtomicU64,
+    rejected: [AtomicU64; RejectReason::ALL.len()],
+    deduplicated: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    outbound_dropped: AtomicU64,
+    connected_peers: AtomicU64,
+    mesh_peers: AtomicU64,
+    /// Index into `Reachability::ALL`, which starts with `Unknown`
+    reachability: AtomicU8,
+}
+
+impl NetworkMetrics {
+    pub(crate) fn published(&self, bytes: usize) {
+        self.messages_published.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn arrived(&self, bytes: usize) {
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn delivered(&self) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn rejected(&self, reason: RejectReason) {
+        self.rejected[reason.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn deduplicated(&self) {
+        self.deduplicated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn outbound_dropped(&self, packets: u64) {
+        self.outbound_dropped.fetch_add(packets, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_peers(&self, connected: usize, mesh: usize) {
+        self.connected_peers.store(connected as u64, Ordering::Relaxed);
+        self.mesh_peers.store(mesh as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_reachability(&self, status: Reachability) {
+        let index = Reachability::ALL.iter().position(|s| *s == status).unwrap_or_default();
+        self.reachability.store(index as u8, Ordering::Relaxed);
+    }
+
+    /// Current values
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            messages_published: self.messages_published.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            packets_rejected: RejectReason::ALL
+                .iter()
+                .map(|reason| (reason.label().to_string(), self.rejected[reason.index()].load(Ordering::Relaxed)))
+                .collect(),
+            packets_deduplicated: self.deduplicated.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            outbound_dropped: self.outbound_dropped.load(Ordering::Relaxed),
+            connected_peers: self.connected_peers.load(Ordering::Relaxed),
+            mesh_peers: self.mesh_peers.load(Ordering::Relaxed),
+            reachability: Reachability::ALL[self.reachability.load(Ordering::Relaxed) as usize],
+        }
+    }
+
+    /// Current values in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        self.snapshot().render_prometheus()
+    }
+}
+
+/// Engine metrics at one moment
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub messages_published: u64,
+    pub messages_received: u64,
+    /// Dropped received messages, by `RejectReason::label`
+    pub packets_rejected: BTreeMap<String, u64>,
+    pub packets_deduplicated: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Packets dropped from a full sender queue
+    pub outbound_dropped: u64,
+    pub connected_peers: u64,
+    pub mesh_peers: u64,
+    /// Whether peers can dial the node
+    pub reachability: Reachability,
+}
+
+impl MetricsSnapshot {
+    /// Rejections for `reason`
+    pub fn rejected(&self, reason: RejectReason) -> u64 {
+        self.packets_rejected.get(reason.label()).copied().unwrap_or(0)
+    }
+
+    /// The values in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, u64)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+        let plain = |value: u64| [(String::new(), value)];
+        metric("olo_messages_published_total", "counter", "Messages published to gossip.", &plain(self.messages_published));
+        metric("olo_messages_received_total", "counter", "Received messages delivered.", &plain(self.messages_received));
+        let rejected: Vec<(String, u64)> = self
+            .packets_rejected
+            .iter()
+            .map(|(reason, count)| (format!("{{reason=\"{}\"}}", reason), *count))
+            .collect();
+        metric("olo_packets_rejected_total", "counter", "Received messages dropped, by reason.", &rejected);
+        metric("olo_packets_deduplicated_total", "counter", "Packets dropped as repeats.", &plain(self.packets_deduplicated));
+        metric("olo_bytes_in_total", "counter", "Bytes of gossip messages received.", &plain(self.bytes_in));
+        metric("olo_bytes_out_total", "counter", "Bytes of gossip messages published.", &plain(self.bytes_out));
+        metric("olo_outbound_dropped_total", "counter", "Queued packets dropped by the overflow policy.", &plain(self.outbound_dropped));
+        metric("olo_connected_peers", "gauge", "Peers with an open connection.", &plain(self.connected_peers));
+        metric("olo_mesh_peers", "gauge", "Peers in the gossip mesh of any topic.", &plain(self.mesh_peers));
+        let reachability: Vec<(String, u64)> = Reachability::ALL
+            .iter()
+            .map(|status| (format!("{{status=\"{}\"}}", status), u64::from(*status == self.reachability)))
+            .collect();
+        metric("olo_reachability", "gauge", "Whether peers can dial the node, by status.", &reachability);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_exposition() {
+        let metrics = NetworkMetrics::default();
+        metrics.published(120);
+        metrics.published(80);
+        metrics.arrived(64);
+        metrics.delivered();
+        metrics.rejected(RejectReason::Invalid);
+        metrics.rejected(RejectReason::Invalid);
+        metrics.deduplicated();
+        metrics.set_peers(3, 2);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.messages_published, 2);
+        assert_eq!(snapshot.bytes_out, 200);
+        assert_eq!(snapshot.rejected(RejectReason::Invalid), 2);
+        assert_eq!(snapshot.rejected(RejectReason::Banned), 0);
+        assert_eq!(snapshot.packets_rejected.len(), RejectReason::ALL.len());
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<MetricsSnapshot>(&json).unwrap(), snapshot);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("# TYPE olo_messages_published_total counter\nolo_messages_published_total 2\n"));
+        assert!(text.contains("olo_packets_rejected_total{reason=\"invalid\"} 2\n"));
+        assert!(text.contains("olo_packets_rejected_total{reason=\"banned\"} 0\n"));
+        assert!(text.contains("# TYPE olo_mesh_peers gauge\nolo_mesh_peers 2\n"));
+        // Every sample line is `name{labels} value`
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (name, value) = line.rsplit_once(' ').unwrap();
+            assert!(name.starts_with("olo_"));
+            value.parse::<u64>().unwrap();
+        }
+    }
+}
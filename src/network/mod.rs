@@ -0,0 +1,66 @@
+//! # Network Module
+//!
+//! P2P layer for OLO Core.
+//! Contains the libp2p ingestion engine and its builder, the message kinds
+//! it gossips and their wire encodings, the sanity checks applied to packets
+//! it receives, duplicate suppression, per-peer rate limiting, peer
+//! reputation, the local packet store, the history and anti-entropy sync
+//! protocols, subscription filters, the network-wide fragility aggregate,
+//! engine metrics, the outbound queue, peer access control, timestamp
+//! freshness checks, NAT traversal, peer health tracking, private topics,
+//! retrying publishes made before any peer joined, the durable outbox, the
+//! capabilities peers announce over identify, state deltas, and, with the
+//! `test-utils` feature, in-process test meshes.
+
+pub mod ingestion;
+pub mod builder;
+pub mod validation;
+pub mod dedup;
+pub mod message;
+pub mod wire;
+pub mod rate_limit;
+pub mod reputation;
+pub mod store;
+pub mod history;
+pub mod filter;
+pub mod aggregate;
+pub mod metrics;
+pub mod outbound;
+pub mod acl;
+pub mod freshness;
+pub mod nat;
+pub mod sync;
+pub mod health;
+pub mod psk;
+pub mod retry;
+pub mod outbox;
+pub mod peer_info;
+pub mod delta;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+
+// Re-export key types
+pub use ingestion::{DataPacket, EngineEvent, GossipsubTuning, IngestionEngine, NetworkConfig, NetworkError, NodeRole, TopicConfig};
+pub use builder::EngineBuilder;
+pub use validation::{PacketRule, PacketValidator};
+pub use dedup::{SeenCache, packet_digest};
+pub use message::{MessageKind, NetworkMessage, Severity, SimulationSummary};
+pub use wire::{Compression, SCHEMA_VERSION, WireError, WireFormat};
+pub use rate_limit::RateLimiter;
+pub use reputation::{Conduct, PeerReputation};
+pub use store::{FileStore, InMemoryStore, PacketStore, StoreError};
+pub use history::{HistoryError, HistoryRequest, HistoryResponse};
+pub use sync::{SyncRequest, SyncResponse};
+pub use filter::{PacketFilter, PacketPredicate};
+pub use aggregate::{AggregateSnapshot, AggregationState};
+pub use metrics::{MetricsSnapshot, NetworkMetrics, RejectReason};
+pub use outbound::{OverflowPolicy, PacketSender, SendError, TrySendError};
+pub use acl::{AccessDenial, AccessList};
+pub use freshness::{FreshnessError, FreshnessGuard};
+pub use nat::{Reachability, ReachabilityTracker};
+pub use health::{HealthTracker, PeerHealth, PeerStatus};
+pub use psk::{OpenError, TopicCipher};
+pub use retry::RetryPolicy;
+pub use outbox::{Outbox, OutboxEntry};
+pub use peer_info::{Capabilities, PeerInfo};
+pub use delta::{StateDelta, state_hash};
@@ -0,0 +1,140 @@
+//! NAT Traversal
+//!
+//! A node behind NAT can dial out but never receives inbound dials, so it
+//! publishes without anyone being able to reach it. AutoNAT asks connected
+//! peers to dial the node back and concludes whether it is publicly
+//! reachable. A node found to be private reserves a slot on each configured
+//! circuit relay and advertises the relayed address instead, so peers reach
+//! it through the relay.
+//!
+//! Relay addresses must end in the relay's peer ID, as in
+//! `/ip4/203.0.113.7/tcp/4001/p2p/12D3KooW...`, since the reservation is
+//! made with that peer.
+
+use libp2p::autonat::NatStatus;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::network::ingestion::NetworkError;
+
+/// Whether peers can dial the node directly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reachability {
+    /// Not yet probed, or the probes were inconclusive
+    #[default]
+    Unknown,
+    /// Peers dialed the node back on a public address
+    Public,
+    /// Peers could not dial the node back
+    Private,
+}
+
+impl Reachability {
+    /// Every status
+    pub const ALL: [Reachability; 3] = [Reachability::Unknown, Reachability::Public, Reachability::Private];
+}
+
+impl fmt::Display for Reachability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reachability::Unknown => write!(f, "unknown"),
+            Reachability::Public => write!(f, "public"),
+            Reachability::Private => write!(f, "private"),
+        }
+    }
+}
+
+/// Relay's peer ID and address, from an entry of `NetworkConfig::relay_addresses`
+pub fn parse_relay_address(addr: &str) -> Result<(PeerId, Multiaddr), NetworkError> {
+    let invalid = |reason: &str| NetworkError::InvalidRelayAddress { addr: addr.to_string(), reason: reason.to_string() };
+    let parsed: Multiaddr = addr.parse().map_err(|e: libp2p::multiaddr::Error| invalid(&e.to_string()))?;
+    match parsed.iter().last() {
+        Some(Protocol::P2p(peer)) => Ok((peer, parsed)),
+        _ => Err(invalid("does not end in the relay's /p2p/ peer ID")),
+    }
+}
+
+/// The node's reachability as AutoNAT last concluded it
+#[derive(Debug, Clone, Default)]
+pub struct ReachabilityTracker {
+    status: Reachability,
+    public_address: Option<Multiaddr>,
+}
+
+impl ReachabilityTracker {
+    /// Current status
+    pub fn status(&self) -> Reachability {
+        self.status
+    }
+
+    /// Address peers dialed the node back on, while it is public
+    pub fn public_address(&self) -> Option<&Multiaddr> {
+        self.public_address.as_ref()
+    }
+
+    /// Take AutoNAT's latest conclusion, returning the status if it changed
+    ///
+    /// A public node confirmed on another address stays public, without a change.
+    pub fn apply(&mut self, nat: NatStatus) -> Option<Reachability> {
+        let status = match nat {
+            NatStatus::Public(address) => {
+                self.public_address = Some(address);
+                Reachability::Public
+            }
+            NatStatus::Private => Reachability::Private,
+            NatStatus::Unknown => Reachability::Unknown,
+        };
+        if status != Reachability::Public {
+            self.public_address = None;
+        }
+        if status == self.status {
+            return None;
+        }
+        self.status = status;
+        Some(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_addresses_parsed() {
+        let relay = PeerId::random();
+        let addr = format!("/ip4/203.0.113.7/tcp/4001/p2p/{}", relay);
+        let (peer, parsed) = parse_relay_address(&addr).unwrap();
+        assert_eq!(peer, relay);
+        assert_eq!(parsed.to_string(), addr);
+
+        for bad in ["/ip4/203.0.113.7/tcp/4001", "relay.example.org:4001", ""] {
+            assert!(
+                matches!(parse_relay_address(bad), Err(NetworkError::InvalidRelayAddress { ref addr, .. }) if addr == bad),
+                "{:?} accepted",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_status_changes_reported_once() {
+        let mut tracker = ReachabilityTracker::default();
+        assert_eq!(tracker.status(), Reachability::Unknown);
+        assert_eq!(tracker.apply(NatStatus::Unknown), None);
+
+        let first: Multiaddr = "/ip4/198.51.100.4/tcp/4001".parse().unwrap();
+        let second: Multiaddr = "/ip4/198.51.100.4/tcp/4002".parse().unwrap();
+        assert_eq!(tracker.apply(NatStatus::Public(first.clone())), Some(Reachability::Public));
+        assert_eq!(tracker.public_address(), Some(&first));
+        assert_eq!(tracker.apply(NatStatus::Public(second.clone())), None);
+        assert_eq!(tracker.public_address(), Some(&second));
+
+        assert_eq!(tracker.apply(NatStatus::Private), Some(Reachability::Private));
+        assert_eq!(tracker.public_address(), None);
+        assert_eq!(tracker.apply(NatStatus::Private), None);
+        assert_eq!(tracker.apply(NatStatus::Unknown), Some(Reachability::Unknown));
+    }
+}
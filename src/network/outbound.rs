@@ -0,0 +1,408 @@
+//! Outbound Queue
+//!
+//! Packets handed to a `PacketSender` wait in a bounded queue until the
+//! engine publishes them. When publishing falls behind and the queue fills,
+//! the `OverflowPolicy` decides what gives: the sender waits, or a packet is
+//! dropped. Dropped packets are counted in the engine metrics and reported
+//! as `EngineEvent::OutboundDropped`, so a publisher outpacing the network
+//! is visible instead of silently stalled.
+//!
+//! Packets sent with `send_priority`, such as those behind an alert, wait in
+//! a lane of their own that the engine drains first, so they never queue
+//! behind routine reports during a stress event. Each lane holds up to the
+//! capacity. To keep a flood of priority packets from starving routine
+//! traffic, one normal packet is taken after every `PRIORITY_RUN` priority
+//! packets while any are waiting.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::Notify;
+
+use crate::network::ingestion::DataPacket;
+
+/// Priority packets taken in a row before a waiting normal packet goes next
+pub const PRIORITY_RUN: usize = 8;
+
+/// What a full queue does with another packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// `send` waits for room and `try_send` fails with `TrySendError::Full`
+    #[default]
+    Block,
+    /// The packet being sent is dropped
+    DropNewest,
+    /// The longest-queued packet is dropped to make room
+    DropOldest,
+}
+
+/// The engine has shut down, so `packet` was not queued
+#[derive(Debug)]
+pub struct SendError(pub DataPacket);
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "engine has shut down")
+    }
+}
+
+impl Error for SendError {}
+
+/// Why `try_send` did not queue a packet, which it hands back
+#[derive(Debug)]
+pub enum TrySendError {
+    /// The queue is full under `OverflowPolicy::Block`
+    Full(Box<DataPacket>),
+    /// The engine has shut down
+    Closed(Box<DataPacket>),
+}
+
+impl fmt::Display for TrySendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "outbound queue is full"),
+            TrySendError::Closed(_) => write!(f, "engine has shut down"),
+        }
+    }
+}
+
+impl Error for TrySendError {}
+
+/// Which lane a packet waits in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lane {
+    Normal,
+    Priority,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    packets: VecDeque<DataPacket>,
+    /// Packets sent with `send_priority`, taken before `packets`
+    priority: VecDeque<DataPacket>,
+    /// Priority packets taken in a row while normal ones waited
+    priority_run: usize,
+    /// Packets dropped since the engine last took the count
+    dropped: u64,
+    closed: bool,
+}
+
+impl State {
+    fn lane(&mut self, lane: Lane) -> &mut VecDeque<DataPacket> {
+        match lane {
+            Lane::Normal => &mut self.packets,
+            Lane::Priority => &mut self.priority,
+        }
+    }
+
+    /// Next packet to publish, priority first unless normal traffic is due
+    fn pop(&mut self) -> Option<DataPacket> {
+        let normal_due = self.priority_run >= PRIORITY_RUN && !self.packets.is_empty();
+        if normal_due || self.priority.is_empty() {
+            self.priority_run = 0;
+            return self.packets.pop_front();
+        }
+        if !self.packets.is_empty() {
+            self.priority_run += 1;
+        }
+        self.priority.pop_front()
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    policy: OverflowPolicy,
+    state: Mutex<State>,
+    /// Wakes the engine when a packet is queued or dropped
+    queued: Notify,
+    /// Wakes a blocked sender when room is made or the queue closes
+    room: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state stays consistent even if a holder panicked
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Handle for queueing packets to be signed and published by the engine
+#[derive(Debug, Clone)]
+pub struct PacketSender {
+    shared: Arc<Shared>,
+}
+
+impl PacketSender {
+    /// Queue `packet`, waiting for room only under `OverflowPolicy::Block`
+    ///
+    /// Under the drop policies a full queue never makes the caller wait; the
+    /// dropped packet is reported by the engine and this still returns `Ok`.
+    pub async fn send(&self, packet: DataPacket) -> Result<(), SendError> {
+        self.send_in(Lane::Normal, packet).await
+    }
+
+    /// Queue `packet` ahead of those queued with `send`, as `send` does
+    pub async fn send_priority(&self, packet: DataPacket) -> Result<(), SendError> {
+        self.send_in(Lane::Priority, packet).await
+    }
+
+    async fn send_in(&self, lane: Lane, mut packet: DataPacket) -> Result<(), SendError> {
+        loop {
+            let room = self.shared.room.notified();
+            tokio::pin!(room);
+            // Register before checking, so room made in between wakes us
+            room.as_mut().enable();
+            match self.try_send_in(lane, packet) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(packet)) => return Err(SendError(*packet)),
+                Err(TrySendError::Full(returned)) => packet = *returned,
+            }
+            room.await;
+        }
+    }
+
+    /// Queue `packet` without waiting
+    ///
+    /// Only fails with `Full` under `OverflowPolicy::Block`; the drop
+    /// policies make room or drop `packet` instead.
+    pub fn try_send(&self, packet: DataPacket) -> Result<(), TrySendError> {
+        self.try_send_in(Lane::Normal, packet)
+    }
+
+    /// Queue `packet` ahead of those queued with `send`, as `try_send` does
+    pub fn try_send_priority(&self, packet: DataPacket) -> Result<(), TrySendError> {
+        self.try_send_in(Lane::Priority, packet)
+    }
+
+    fn try_send_in(&self, lane: Lane, packet: DataPacket) -> Result<(), TrySendError> {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return Err(TrySendError::Closed(Box::new(packet)));
+        }
+        if state.lane(lane).len() >= self.shared.capacity {
+            match self.shared.policy {
+                OverflowPolicy::Block => return Err(TrySendError::Full(Box::new(packet))),
+                OverflowPolicy::DropNewest => {
+                    state.dropped += 1;
+                    drop(state);
+                    self.shared.queued.notify_one();
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    state.lane(lane).pop_front();
+                    state.dropped += 1;
+                }
+            }
+        }
+        state.lane(lane).push_back(packet);
+        drop(state);
+        self.shared.queued.notify_one();
+        Ok(())
+    }
+
+    /// Packets waiting to be published, in either lane
+    pub fn len(&self) -> usize {
+        let state = self.shared.lock();
+        state.packets.len() + state.priority.len()
+    }
+
+    /// Whether no packets are waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Packets each lane holds before the overflow policy applies
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// What a full queue does with another packet
+    pub fn policy(&self) -> OverflowPolicy {
+        self.shared.policy
+    }
+
+    /// Whether the engine has shut down and refuses new packets
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().closed
+    }
+}
+
+/// What the engine takes off the queue next
+#[derive(Debug)]
+pub(crate) enum Outbound {
+    Packet(Box<DataPacket>),
+    /// Packets dropped since the count was last taken
+    Dropped(u64),
+}
+
+/// Engine's end of the queue
+#[derive(Debug)]
+pub(crate) struct OutboundQueue {
+    shared: Arc<Shared>,
+}
+
+/// Queue holding up to `capacity` (at least one) packets
+pub(crate) fn channel(capacity: usize, policy: OverflowPolicy) -> (PacketSender, OutboundQueue) {
+    let shared = Arc::new(Shared {
+        capacity: capacity.max(1),
+        policy,
+        state: Mutex::new(State::default()),
+        queued: Notify::new(),
+        room: Notify::new(),
+    });
+    (PacketSender { shared: Arc::clone(&shared) }, OutboundQueue { shared })
+}
+
+impl OutboundQueue {
+    /// Next packet or drop count, waiting for one; `None` once closed and empty
+    ///
+    /// Cancel-safe: the queue only changes when this returns.
+    pub(crate) async fn next(&self) -> Option<Outbound> {
+        loop {
+            let queued = self.shared.queued.notified();
+            tokio::pin!(queued);
+            queued.as_mut().enable();
+            if let Some(next) = self.try_next() {
+                return Some(next);
+            }
+            if self.shared.lock().closed {
+                return None;
+            }
+            queued.await;
+        }
+    }
+
+    /// Next packet or drop count, if there is one now
+    pub(crate) fn try_next(&self) -> Option<Outbound> {
+        let mut state = self.shared.lock();
+        if state.dropped > 0 {
+            return Some(Outbound::Dropped(std::mem::take(&mut state.dropped)));
+        }
+        let packet = state.pop()?;
+        drop(state);
+        self.shared.room.notify_one();
+        Some(Outbound::Packet(Box::new(packet)))
+    }
+
+    /// Whether `next` returns `None` at once: closed with nothing left to take
+    pub(crate) fn is_finished(&self) -> bool {
+        let state = self.shared.lock();
+        state.closed && state.dropped == 0 && state.packets.is_empty() && state.priority.is_empty()
+    }
+
+    /// Refuse new packets; those queued can still be taken
+    pub(crate) fn close(&self) {
+        self.shared.lock().closed = true;
+        self.shared.room.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    fn packet(timestamp: u64) -> DataPacket {
+        fixtures::packet().source("bank-a").timestamp(timestamp).fragility(20.0).build()
+    }
+
+    fn drain(queue: &OutboundQueue) -> (Vec<u64>, u64) {
+        let (mut timestamps, mut dropped) = (Vec::new(), 0);
+        while let Some(next) = queue.try_next() {
+            match next {
+                Outbound::Packet(packet) => timestamps.push(packet.timestamp),
+                Outbound::Dropped(count) => dropped += count,
+            }
+        }
+        (timestamps, dropped)
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let (sender, queue) = channel(3, OverflowPolicy::DropNewest);
+        for t in 0..5 {
+            sender.try_send(packet(t)).unwrap();
+        }
+        assert_eq!(drain(&queue), (vec![0, 1, 2], 2));
+
+        let (sender, queue) = channel(3, OverflowPolicy::DropOldest);
+        for t in 0..5 {
+            sender.try_send(packet(t)).unwrap();
+        }
+        assert_eq!(drain(&queue), (vec![2, 3, 4], 2));
+
+        let (sender, queue) = channel(1, OverflowPolicy::Block);
+        sender.try_send(packet(0)).unwrap();
+        assert!(matches!(sender.try_send(packet(1)), Err(TrySendError::Full(p)) if p.timestamp == 1));
+        queue.close();
+        assert!(matches!(sender.try_send(packet(2)), Err(TrySendError::Closed(_))));
+        assert!(!queue.is_finished());
+        assert_eq!(drain(&queue), (vec![0], 0));
+        assert!(queue.is_finished());
+    }
+
+    #[test]
+    fn test_packets_leave_in_send_order() {
+        let (sender, queue) = channel(100, OverflowPolicy::Block);
+        for t in 0..10 {
+            sender.try_send(packet(t)).unwrap();
+        }
+        for t in 0..3 {
+            assert!(matches!(queue.try_next(), Some(Outbound::Packet(p)) if p.timestamp == t));
+        }
+        for t in 10..13 {
+            sender.try_send(packet(t)).unwrap();
+        }
+        assert_eq!(drain(&queue), ((3..13).collect(), 0));
+    }
+
+    #[test]
+    fn test_priority_lane_drained_first_without_starving_normal() {
+        let (sender, queue) = channel(100, OverflowPolicy::Block);
+        for t in 0..3 {
+            sender.try_send(packet(t)).unwrap();
+        }
+        sender.try_send_priority(packet(100)).unwrap();
+        assert_eq!(sender.len(), 4);
+        assert_eq!(drain(&queue), (vec![100, 0, 1, 2], 0));
+
+        for t in 0..2 {
+            sender.try_send(packet(t)).unwrap();
+        }
+        for t in 0..20 {
+            sender.try_send_priority(packet(100 + t)).unwrap();
+        }
+        let (order, _) = drain(&queue);
+        let normal: Vec<usize> = (0..order.len()).filter(|i| order[*i] < 100).collect();
+        assert_eq!(normal, vec![PRIORITY_RUN, 2 * PRIORITY_RUN + 1]);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_send_resumes_when_room_made() {
+        let (sender, queue) = channel(1, OverflowPolicy::Block);
+        sender.send(packet(0)).await.unwrap();
+        let blocked = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.send(packet(1)).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!blocked.is_finished());
+
+        assert!(matches!(queue.next().await, Some(Outbound::Packet(p)) if p.timestamp == 0));
+        blocked.await.unwrap().unwrap();
+        assert!(matches!(queue.next().await, Some(Outbound::Packet(p)) if p.timestamp == 1));
+
+        let closed = tokio::spawn({
+            let sender = sender.clone();
+            async move {
+                sender.send(packet(2)).await.unwrap();
+                sender.send(packet(3)).await
+            }
+        });
+        tokio::task::yield_now().await;
+        queue.close();
+        assert!(matches!(closed.await.unwrap(), Err(SendError(p)) if p.timestamp == 3));
+    }
+}
@@ -0,0 +1,221 @@
+//! Durable Outbox
+//!
+//! A packet queued for publishing lives only in memory until gossipsub
+//! takes it, so a crash loses it, which regulatory reporting cannot accept.
+//! An `Outbox` writes each message to an append-only log, synced to disk,
+//! before the engine tries to send it, and records an acknowledgement once
+//! gossipsub accepts it. Entries a previous run left unacknowledged are
+//! sent again after a restart.
+//!
+//! Records use the packet store's framing: a little-endian `u32` length
+//! followed by a bincode body, with a record torn by a crash truncated away
+//! on open. Only enqueues are synced; an acknowledgement lost in a crash
+//! means its message is sent twice, and receivers drop the repeat. The log
+//! grows with every message until `compact` rewrites it with just the
+//! unacknowledged entries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::network::store::StoreError;
+
+/// Size of a record's length prefix
+const LENGTH_BYTES: usize = 4;
+
+/// A message waiting for gossipsub to accept it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// Position in the order messages were enqueued
+    pub id: u64,
+    pub topic: String,
+    /// The message as gossiped: encoded, and sealed if the topic is private
+    pub data: Vec<u8>,
+    /// When the message stops being worth sending (Unix epoch milliseconds)
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Record {
+    Enqueued(OutboxEntry),
+    Acked(u64),
+}
+
+/// Messages to publish, kept in a log file until acknowledged
+#[derive(Debug)]
+pub struct Outbox {
+    path: PathBuf,
+    log: File,
+    /// Unacknowledged entries, by ID
+    pending: BTreeMap<u64, OutboxEntry>,
+    next_id: u64,
+    /// Records in the log, acknowledged ones included
+    records: usize,
+}
+
+impl Outbox {
+    /// Open the log at `path`, creating it if missing
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        let io_error = |source| StoreError::Io { path: path.to_path_buf(), source };
+        let log = OpenOptions::new().read(true).append(true).create(true).open(path).map_err(io_error)?;
+        let mut outbox = Self { path: path.to_path_buf(), log, pending: BTreeMap::new(), next_id: 0, records: 0 };
+        for record in outbox.scan()? {
+            match record {
+                Record::Enqueued(entry) => {
+                    outbox.next_id = outbox.next_id.max(entry.id + 1);
+                    outbox.pending.insert(entry.id, entry);
+                }
+                Record::Acked(id) => {
+                    outbox.pending.remove(&id);
+                }
+            }
+            outbox.records += 1;
+        }
+        Ok(outbox)
+    }
+
+    fn io_error(&self, source: io::Error) -> StoreError {
+        StoreError::Io { path: self.path.clone(), source }
+    }
+
+    /// Records in the log, truncating a torn final record
+    fn scan(&self) -> Result<Vec<Record>, StoreError> {
+        let bytes = fs::read(&self.path).map_err(|e| self.io_error(e))?;
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let Some(header) = bytes.get(offset..offset + LENGTH_BYTES) else { break };
+            let length = u32::from_le_bytes(header.try_into().expect("four bytes")) as usize;
+            let Some(body) = bytes.get(offset + LENGTH_BYTES..offset + LENGTH_BYTES + length) else { break };
+            let record = bincode::deserialize(body).map_err(|e| StoreError::Corrupt {
+                path: self.path.clone(),
+                offset: offset as u64,
+                reason: e.to_string(),
+            })?;
+            records.push(record);
+            offset += LENGTH_BYTES + length;
+        }
+        if offset < bytes.len() {
+            self.log.set_len(offset as u64).map_err(|e| self.io_error(e))?;
+        }
+        Ok(records)
+    }
+
+    /// Framed bytes of `record`
+    fn frame(record: &Record) -> Vec<u8> {
+        let body = bincode::serialize(record).expect("outbox records are always serializable");
+        let mut bytes = (body.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    fn append(&mut self, record: &Record) -> Result<(), StoreError> {
+        self.log.write_all(&Self::frame(record)).map_err(|e| self.io_error(e))?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Keep `data` for `topic`, expiring at `expires_at` if ever, on disk
+    /// until acknowledged, returning its ID
+    pub fn enqueue(&mut self, topic: &str, data: &[u8], expires_at: Option<u64>) -> Result<u64, StoreError> {
+        let entry = OutboxEntry { id: self.next_id, topic: topic.to_string(), data: data.to_vec(), expires_at };
+        self.append(&Record::Enqueued(entry.clone()))?;
+        self.log.sync_data().map_err(|e| self.io_error(e))?;
+        let id = entry.id;
+        self.next_id += 1;
+        self.pending.insert(id, entry);
+        Ok(id)
+    }
+
+    /// Note that gossipsub accepted entry `id`, or that it is given up on
+    pub fn ack(&mut self, id: u64) -> Result<(), StoreError> {
+        if self.pending.remove(&id).is_some() {
+            self.append(&Record::Acked(id))?;
+        }
+        Ok(())
+    }
+
+    /// Unacknowledged entries, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &OutboxEntry> {
+        self.pending.values()
+    }
+
+    /// Number of unacknowledged entries
+    pub fn depth(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Rewrite the log with only the unacknowledged entries, returning how
+    /// many records were dropped
+    pub fn compact(&mut self) -> Result<usize, StoreError> {
+        let dropped = self.records - self.pending.len();
+        if dropped == 0 {
+            return Ok(0);
+        }
+        let staging = self.path.with_extension("compact");
+        let mut bytes = Vec::new();
+        for entry in self.pending.values() {
+            bytes.extend(Self::frame(&Record::Enqueued(entry.clone())));
+        }
+        let mut file = File::create(&staging).map_err(|e| self.io_error(e))?;
+        file.write_all(&bytes).map_err(|e| self.io_error(e))?;
+        file.sync_all().map_err(|e| self.io_error(e))?;
+        fs::rename(&staging, &self.path).map_err(|e| self.io_error(e))?;
+        self.log = OpenOptions::new().read(true).append(true).open(&self.path).map_err(|e| self.io_error(e))?;
+        self.records = self.pending.len();
+        Ok(dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("olo-outbox-{}-{}.log", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn pending(outbox: &Outbox) -> Vec<(u64, Vec<u8>)> {
+        outbox.pending().map(|entry| (entry.id, entry.data.clone())).collect()
+    }
+
+    #[test]
+    fn test_unacked_entries_survive_reopen_and_compaction() {
+        let path = log_path("reopen");
+        let mut outbox = Outbox::open(&path).unwrap();
+        for data in [[1], [2], [3]] {
+            outbox.enqueue("olo-fragility", &data, None).unwrap();
+        }
+        outbox.ack(1).unwrap();
+        outbox.ack(1).unwrap();
+        drop(outbox);
+
+        // A crash partway through appending a record
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(&[200, 0, 0, 0, 1]).unwrap();
+        drop(log);
+
+        let mut reopened = Outbox::open(&path).unwrap();
+        assert_eq!(pending(&reopened), vec![(0, vec![1]), (2, vec![3])]);
+        assert_eq!(reopened.enqueue("olo-alerts", &[4], Some(1_700_000_300_000)).unwrap(), 3);
+        reopened.ack(0).unwrap();
+        assert_eq!(reopened.depth(), 2);
+
+        let size = fs::metadata(&path).unwrap().len();
+        assert_eq!(reopened.compact().unwrap(), 4);
+        assert_eq!(reopened.compact().unwrap(), 0);
+        assert!(fs::metadata(&path).unwrap().len() < size);
+        reopened.ack(2).unwrap();
+        drop(reopened);
+
+        let compacted = Outbox::open(&path).unwrap();
+        assert_eq!(pending(&compacted), vec![(3, vec![4])]);
+        let entry = compacted.pending().next().unwrap();
+        assert_eq!((entry.topic.as_str(), entry.expires_at), ("olo-alerts", Some(1_700_000_300_000)));
+        fs::remove_file(&path).unwrap();
+    }
+}
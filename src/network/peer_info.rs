@@ -0,0 +1,189 @@
+//! Peer Identification
+//!
+//! A mesh mixing software versions is hard to debug when nodes cannot see
+//! what their peers run. Every node answers the libp2p identify protocol
+//! with its agent string: the crate version followed by its capabilities,
+//! as space-separated `key=value` fields:
+//!
+//! ```text
+//! olo/0.1.0 role=full wire=json,cbor,bincode compression=zstd circuit=1
+//! ```
+//!
+//! | Field         | Meaning                                              |
+//! |---------------|------------------------------------------------------|
+//! | `role`        | `NodeRole` of the node                               |
+//! | `wire`        | `WireFormat`s the node decodes                       |
+//! | `compression` | `zstd` if it decompresses message bodies             |
+//! | `circuit`     | `VERIFYING_KEY_VERSION` of the proofs it checks      |
+//!
+//! Unknown fields and values are skipped, so newer nodes can advertise
+//! more without confusing older ones. A peer whose agent string does not
+//! parse has no known capabilities, and gets only default features.
+
+use libp2p::{identify, Multiaddr, StreamProtocol};
+
+use crate::network::ingestion::NodeRole;
+use crate::network::wire::WireFormat;
+use crate::proofs::verifier::VERIFYING_KEY_VERSION;
+
+/// Protocol family announced over identify
+pub const PROTOCOL_VERSION: &str = "/olo/1";
+
+/// Product name leading the agent string
+const AGENT_PRODUCT: &str = "olo";
+
+/// What a node supports beyond the defaults every node shares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub role: NodeRole,
+    /// Formats the node decodes
+    pub wire_formats: Vec<WireFormat>,
+    /// Whether the node decompresses zstd message bodies
+    pub compression: bool,
+    /// `VERIFYING_KEY_VERSION` of the proofs the node checks
+    pub circuit_version: Option<u32>,
+}
+
+impl Capabilities {
+    /// Capabilities of this build, running as `role`
+    pub fn local(role: NodeRole) -> Self {
+        Self {
+            role,
+            wire_formats: vec![WireFormat::Json, WireFormat::Cbor, WireFormat::Bincode],
+            compression: true,
+            circuit_version: Some(VERIFYING_KEY_VERSION),
+        }
+    }
+
+    /// Whether the node decodes `format`
+    pub fn decodes(&self, format: WireFormat) -> bool {
+        format == WireFormat::default() || self.wire_formats.contains(&format)
+    }
+}
+
+fn format_name(format: WireFormat) -> &'static str {
+    match format {
+        WireFormat::Json => "json",
+        WireFormat::Cbor => "cbor",
+        WireFormat::Bincode => "bincode",
+    }
+}
+
+fn parse_format(name: &str) -> Option<WireFormat> {
+    match name {
+        "json" => Some(WireFormat::Json),
+        "cbor" => Some(WireFormat::Cbor),
+        "bincode" => Some(WireFormat::Bincode),
+        _ => None,
+    }
+}
+
+fn parse_role(name: &str) -> Option<NodeRole> {
+    [NodeRole::Full, NodeRole::PublisherOnly, NodeRole::ObserverOnly]
+        .into_iter()
+        .find(|role| role.to_string() == name)
+}
+
+/// Agent string announcing this build's version and `capabilities`
+pub fn agent_version(capabilities: &Capabilities) -> String {
+    let formats: Vec<&str> = capabilities.wire_formats.iter().map(|format| format_name(*format)).collect();
+    let mut agent = format!(
+        "{}/{} role={} wire={}",
+        AGENT_PRODUCT,
+        env!("CARGO_PKG_VERSION"),
+        capabilities.role,
+        formats.join(",")
+    );
+    if capabilities.compression {
+        agent.push_str(" compression=zstd");
+    }
+    if let Some(version) = capabilities.circuit_version {
+        agent.push_str(&format!(" circuit={}", version));
+    }
+    agent
+}
+
+/// Crate version and capabilities in an agent string, if it is one of ours
+pub fn parse_agent(agent: &str) -> Option<(String, Capabilities)> {
+    let mut fields = agent.split_whitespace();
+    let version = fields.next()?.strip_prefix(AGENT_PRODUCT)?.strip_prefix('/')?.to_string();
+    let mut capabilities =
+        Capabilities { role: NodeRole::Full, wire_formats: Vec::new(), compression: false, circuit_version: None };
+    for (key, value) in fields.filter_map(|field| field.split_once('=')) {
+        match key {
+            "role" => capabilities.role = parse_role(value).unwrap_or_default(),
+            "wire" => capabilities.wire_formats = value.split(',').filter_map(parse_format).collect(),
+            "compression" => capabilities.compression = value.split(',').any(|codec| codec == "zstd"),
+            "circuit" => capabilities.circuit_version = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some((version, capabilities))
+}
+
+/// What a connected peer said about itself over identify
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// Agent string as announced
+    pub agent_version: String,
+    /// Crate version, if the agent string is one of ours
+    pub version: Option<String>,
+    /// Capabilities, if the agent string is one of ours
+    pub capabilities: Option<Capabilities>,
+    /// Protocol family, `PROTOCOL_VERSION` for this build
+    pub protocol_version: String,
+    /// Stream protocols the peer speaks
+    pub protocols: Vec<StreamProtocol>,
+    /// Addresses the peer listens on
+    pub listen_addrs: Vec<Multiaddr>,
+}
+
+impl PeerInfo {
+    /// Whether the peer speaks `protocol`
+    pub fn speaks(&self, protocol: &StreamProtocol) -> bool {
+        self.protocols.contains(protocol)
+    }
+
+    /// Whether the peer is known to decode `format`
+    pub fn decodes(&self, format: WireFormat) -> bool {
+        format == WireFormat::default() || self.capabilities.as_ref().is_some_and(|caps| caps.decodes(format))
+    }
+}
+
+impl From<identify::Info> for PeerInfo {
+    fn from(info: identify::Info) -> Self {
+        let parsed = parse_agent(&info.agent_version);
+        Self {
+            version: parsed.as_ref().map(|(version, _)| version.clone()),
+            capabilities: parsed.map(|(_, capabilities)| capabilities),
+            agent_version: info.agent_version,
+            protocol_version: info.protocol_version,
+            protocols: info.protocols,
+            listen_addrs: info.listen_addrs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_string_round_trips_and_tolerates_unknowns() {
+        let local = Capabilities::local(NodeRole::PublisherOnly);
+        let agent = agent_version(&local);
+        assert!(agent.starts_with(&format!("olo/{} role=publisher-only wire=json,cbor,bincode", env!("CARGO_PKG_VERSION"))));
+        assert_eq!(parse_agent(&agent), Some((env!("CARGO_PKG_VERSION").to_string(), local)));
+
+        let (version, newer) = parse_agent("olo/9.0.0 role=archive wire=cbor,capnp shards=4").unwrap();
+        assert_eq!(version, "9.0.0");
+        assert_eq!(newer.role, NodeRole::Full);
+        assert_eq!(newer.wire_formats, vec![WireFormat::Cbor]);
+        assert!(newer.decodes(WireFormat::Json) && !newer.decodes(WireFormat::Bincode));
+        assert!(!newer.compression);
+        assert_eq!(newer.circuit_version, None);
+
+        assert_eq!(parse_agent("rust-libp2p/0.44.0"), None);
+        assert_eq!(parse_agent("olo-core/0.1.0"), None);
+    }
+}
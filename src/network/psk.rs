@@ -0,0 +1,134 @@
+//! Private Topics
+//!
+//! A consortium that does not want to run a PKI can still keep its gossip
+//! unreadable and unforgeable by outsiders: members share a key for a
+//! topic, and every payload on it is sealed with XChaCha20-Poly1305 under a
+//! key derived from that pre-shared key and the topic name, so the same
+//! secret never encrypts two topics alike. A sealed payload is a random
+//! 24-byte nonce followed by the ciphertext and its tag.
+//!
+//! Keys are rotated without a flag day by configuring several: payloads
+//! are sealed with the first and opened with whichever fits. Members first
+//! add the new key after the old one, then move it to the front once all
+//! of them accept it, and finally drop the old key.
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+use std::fmt;
+
+/// Salt binding derived keys to this protocol
+const KEY_DOMAIN: &[u8] = b"olo-core/topic-key/v1";
+
+/// Length of the nonce in front of each sealed payload
+const NONCE_LEN: usize = 24;
+
+/// Why a sealed payload could not be opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenError {
+    /// Too short to hold a nonce and tag
+    Truncated,
+    /// No configured key authenticates it
+    Unauthenticated,
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::Truncated => write!(f, "sealed payload is truncated"),
+            OpenError::Unauthenticated => write!(f, "payload is not sealed with a topic key"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
+
+/// Keys of one private topic, derived from its pre-shared keys
+#[derive(Clone)]
+pub struct TopicCipher {
+    /// Sealing key first, then the others accepted
+    keys: Vec<XChaCha20Poly1305>,
+}
+
+impl fmt::Debug for TopicCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopicCipher").field("keys", &self.keys.len()).finish()
+    }
+}
+
+impl TopicCipher {
+    /// Cipher for `topic` sealing with the first of `psks` and opening with
+    /// any; `None` if there are none, leaving the topic open
+    pub fn new(topic: &str, psks: &[Vec<u8>]) -> Option<Self> {
+        if psks.is_empty() {
+            return None;
+        }
+        let keys = psks
+            .iter()
+            .map(|psk| {
+                let mut key = [0u8; 32];
+                Hkdf::<Sha256>::new(Some(KEY_DOMAIN), psk)
+                    .expand(topic.as_bytes(), &mut key)
+                    .expect("32 bytes is a valid HKDF-SHA256 output length");
+                XChaCha20Poly1305::new(&key.into())
+            })
+            .collect();
+        Some(Self { keys })
+    }
+
+    /// Encrypt and authenticate `payload` with the sealing key
+    pub fn seal(&self, payload: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self.keys[0]
+            .encrypt(XNonce::from_slice(&nonce), payload)
+            .expect("XChaCha20-Poly1305 encrypts payloads of any practical size");
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        sealed
+    }
+
+    /// Check and decrypt `sealed` with whichever configured key fits
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, OpenError> {
+        if sealed.len() < NONCE_LEN + 16 {
+            return Err(OpenError::Truncated);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.keys
+            .iter()
+            .find_map(|key| key.decrypt(XNonce::from_slice(nonce), ciphertext).ok())
+            .ok_or(OpenError::Unauthenticated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_only_for_key_holders() {
+        let (old, new) = (b"consortium 2025".to_vec(), b"consortium 2026".to_vec());
+        let rotated = TopicCipher::new("olo-fragility", &[new.clone(), old.clone()]).unwrap();
+        let lagging = TopicCipher::new("olo-fragility", std::slice::from_ref(&old)).unwrap();
+        let outsider = TopicCipher::new("olo-fragility", &[b"guess".to_vec()]).unwrap();
+        assert!(TopicCipher::new("olo-fragility", &[]).is_none());
+
+        let sealed = lagging.seal(b"report");
+        assert_ne!(&sealed[NONCE_LEN..], b"report");
+        assert_eq!(rotated.open(&sealed).unwrap(), b"report");
+        assert_eq!(outsider.open(&sealed), Err(OpenError::Unauthenticated));
+        assert_eq!(lagging.open(&rotated.seal(b"report")), Err(OpenError::Unauthenticated));
+
+        // The same key seals another topic differently
+        let elsewhere = TopicCipher::new("olo-alerts", &[old]).unwrap();
+        assert_eq!(elsewhere.open(&sealed), Err(OpenError::Unauthenticated));
+
+        let mut tampered = rotated.seal(b"report");
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(rotated.open(&tampered), Err(OpenError::Unauthenticated));
+        assert_eq!(rotated.open(&sealed[..NONCE_LEN]), Err(OpenError::Truncated));
+    }
+}
@@ -0,0 +1,150 @@
+//! Per-Peer Rate Limiting
+//!
+//! One chatty peer can flood the topic and crowd out everyone else's
+//! packets. `RateLimiter` gives each source peer a token bucket: a packet
+//! takes a token, buckets hold at most `burst` tokens, and they refill at
+//! the configured rate. Packets arriving to an empty bucket are dropped.
+//!
+//! A bucket left idle long enough to refill completely is indistinguishable
+//! from a new one, so such buckets are swept away periodically and memory
+//! stays bounded by the peers recently heard from.
+
+use libp2p::PeerId;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// How often idle buckets are swept away
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    /// Packets dropped since the bucket last had a token
+    dropped: u64,
+}
+
+/// Token buckets limiting the packets accepted from each peer
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Tokens added per second
+    rate: f64,
+    burst: f64,
+    exempt: HashSet<PeerId>,
+    buckets: HashMap<PeerId, Bucket>,
+    swept: Instant,
+}
+
+impl RateLimiter {
+    /// Accept `per_minute` packets a minute from each peer, in bursts of up to
+    /// `burst` (at least one), except from `exempt` peers
+    pub fn new(per_minute: u32, burst: u32, exempt: impl IntoIterator<Item = PeerId>) -> Self {
+        Self {
+            rate: per_minute as f64 / 60.0,
+            burst: burst.max(1) as f64,
+            exempt: exempt.into_iter().collect(),
+            buckets: HashMap::new(),
+            swept: Instant::now(),
+        }
+    }
+
+    /// Take a token for a packet from `peer`
+    ///
+    /// Returns `Err` with the packets dropped from `peer` since it last had a
+    /// token, this one included, when its bucket is empty.
+    pub fn check(&mut self, peer: &PeerId) -> Result<(), u64> {
+        self.check_at(peer, Instant::now())
+    }
+
+    fn check_at(&mut self, peer: &PeerId, now: Instant) -> Result<(), u64> {
+        if self.exempt.contains(peer) {
+            return Ok(());
+        }
+        if now.saturating_duration_since(self.swept) >= SWEEP_INTERVAL {
+            self.sweep(now);
+        }
+
+        let (rate, burst) = (self.rate, self.burst);
+        let bucket = self.buckets.entry(*peer).or_insert(Bucket { tokens: burst, refilled: now, dropped: 0 });
+        let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.refilled = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.dropped = 0;
+            Ok(())
+        } else {
+            bucket.dropped += 1;
+            Err(bucket.dropped)
+        }
+    }
+
+    /// Forget buckets that would have refilled completely by `now`
+    fn sweep(&mut self, now: Instant) {
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+            bucket.tokens + elapsed * rate < burst
+        });
+        self.swept = now;
+    }
+
+    /// Number of peers with a bucket
+    pub fn tracked_peers(&self) -> usize {
+        self.buckets.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    fn peer() -> PeerId {
+        Keypair::generate_ed25519().public().to_peer_id()
+    }
+
+    #[test]
+    fn test_burst_then_refill() {
+        let mut limiter = RateLimiter::new(60, 3, []);
+        let chatty = peer();
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(&chatty, start), Ok(()));
+        }
+        assert_eq!(limiter.check_at(&chatty, start), Err(1));
+        assert_eq!(limiter.check_at(&chatty, start), Err(2));
+        // One token a second
+        assert_eq!(limiter.check_at(&chatty, start + Duration::from_secs(1)), Ok(()));
+        assert_eq!(limiter.check_at(&chatty, start + Duration::from_secs(1)), Err(1));
+        // Another peer has its own bucket
+        assert_eq!(limiter.check_at(&peer(), start), Ok(()));
+    }
+
+    #[test]
+    fn test_exempt_peers_unlimited() {
+        let trusted = peer();
+        let mut limiter = RateLimiter::new(1, 1, [trusted]);
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(limiter.check_at(&trusted, now), Ok(()));
+        }
+        assert_eq!(limiter.tracked_peers(), 0);
+    }
+
+    #[test]
+    fn test_idle_buckets_swept() {
+        let mut limiter = RateLimiter::new(60, 5, []);
+        let (idle, busy) = (peer(), peer());
+        let start = limiter.swept;
+        limiter.check_at(&idle, start).unwrap();
+        for _ in 0..5 {
+            limiter.check_at(&busy, start + Duration::from_secs(56)).unwrap();
+        }
+        // Sweeping at 60 s: the idle bucket is full again, the busy one is not
+        limiter.check_at(&peer(), start + SWEEP_INTERVAL).unwrap();
+        assert_eq!(limiter.tracked_peers(), 2);
+        assert!(!limiter.buckets.contains_key(&idle));
+        assert!(limiter.buckets.contains_key(&busy));
+    }
+}
@@ -0,0 +1,181 @@
+//! Peer Reputation
+//!
+//! Rate limiting caps how much a peer can send; reputation deals with peers
+//! whose packets keep failing. Each source peer has a score that drops with
+//! every undecodable, badly signed, or invalid packet and creeps back up
+//! with valid ones. A peer whose score falls to the ban threshold is banned
+//! for a fixed time, after which it starts again from zero.
+//!
+//! | Conduct                  | Score change  |
+//! |--------------------------|---------------|
+//! | valid packet             | +1, up to +20 |
+//! | failed validation        | -5            |
+//! | undecodable packet       | -10           |
+//! | missing or bad signature | -10           |
+
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Highest score a peer can build up with valid packets
+const MAX_SCORE: i32 = 20;
+
+/// What a peer's packet showed about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conduct {
+    /// The packet passed decoding, signature, and validation
+    Valid,
+    /// The packet decoded and was signed but failed validation
+    Invalid,
+    /// The packet could not be decoded
+    Undecodable,
+    /// The packet was unsigned or not signed by its source
+    BadSignature,
+}
+
+impl Conduct {
+    /// Change to the peer's score
+    pub fn score_change(self) -> i32 {
+        match self {
+            Conduct::Valid => 1,
+            Conduct::Invalid => -5,
+            Conduct::Undecodable => -10,
+            Conduct::BadSignature => -10,
+        }
+    }
+}
+
+/// Scores of source peers, and the peers banned for low scores
+#[derive(Debug, Clone)]
+pub struct PeerReputation {
+    threshold: i32,
+    ban_duration: Duration,
+    scores: HashMap<PeerId, i32>,
+    /// Banned peers, with when each ban ends
+    banned: HashMap<PeerId, Instant>,
+}
+
+impl PeerReputation {
+    /// Ban peers for `ban_duration` once their score is at or below `threshold`
+    pub fn new(threshold: i32, ban_duration: Duration) -> Self {
+        Self {
+            threshold,
+            ban_duration,
+            scores: HashMap::new(),
+            banned: HashMap::new(),
+        }
+    }
+
+    /// Score `peer` for `conduct`, returning whether that got it banned
+    ///
+    /// Conduct of a peer already banned is not scored.
+    pub fn record(&mut self, peer: &PeerId, conduct: Conduct) -> bool {
+        self.record_at(peer, conduct, Instant::now())
+    }
+
+    fn record_at(&mut self, peer: &PeerId, conduct: Conduct, now: Instant) -> bool {
+        if self.is_banned_at(peer, now) {
+            return false;
+        }
+        let score = self.scores.entry(*peer).or_insert(0);
+        *score = (*score + conduct.score_change()).min(MAX_SCORE);
+        if *score > self.threshold {
+            return false;
+        }
+        self.scores.remove(peer);
+        self.banned.insert(*peer, now + self.ban_duration);
+        true
+    }
+
+    /// Whether `peer` is currently banned
+    pub fn is_banned(&self, peer: &PeerId) -> bool {
+        self.is_banned_at(peer, Instant::now())
+    }
+
+    fn is_banned_at(&self, peer: &PeerId, now: Instant) -> bool {
+        self.banned.get(peer).is_some_and(|until| now < *until)
+    }
+
+    /// Lift the bans that have run their course, returning the peers unbanned
+    pub fn expire_bans(&mut self) -> Vec<PeerId> {
+        self.expire_bans_at(Instant::now())
+    }
+
+    fn expire_bans_at(&mut self, now: Instant) -> Vec<PeerId> {
+        let expired: Vec<PeerId> = self
+            .banned
+            .iter()
+            .filter(|(_, until)| now >= **until)
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in &expired {
+            self.banned.remove(peer);
+        }
+        expired
+    }
+
+    /// Current score of every scored peer; banned peers have none
+    pub fn scores(&self) -> &HashMap<PeerId, i32> {
+        &self.scores
+    }
+
+    /// How long bans last
+    pub fn ban_duration(&self) -> Duration {
+        self.ban_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    fn peer() -> PeerId {
+        Keypair::generate_ed25519().public().to_peer_id()
+    }
+
+    #[test]
+    fn test_repeated_failures_ban() {
+        let mut reputation = PeerReputation::new(-30, Duration::from_secs(60));
+        let peer = peer();
+        let now = Instant::now();
+        assert!(!reputation.record_at(&peer, Conduct::BadSignature, now));
+        assert!(!reputation.record_at(&peer, Conduct::Undecodable, now));
+        assert_eq!(reputation.scores()[&peer], -20);
+        assert!(!reputation.record_at(&peer, Conduct::Invalid, now));
+        assert!(reputation.record_at(&peer, Conduct::Invalid, now));
+        assert!(reputation.is_banned_at(&peer, now));
+        assert!(!reputation.scores().contains_key(&peer));
+        // Nothing more is scored while banned
+        assert!(!reputation.record_at(&peer, Conduct::BadSignature, now));
+    }
+
+    #[test]
+    fn test_valid_packets_recover_score_up_to_cap() {
+        let mut reputation = PeerReputation::new(-30, Duration::from_secs(60));
+        let peer = peer();
+        let now = Instant::now();
+        reputation.record_at(&peer, Conduct::Undecodable, now);
+        for _ in 0..10 {
+            reputation.record_at(&peer, Conduct::Valid, now);
+        }
+        assert_eq!(reputation.scores()[&peer], 0);
+        for _ in 0..100 {
+            reputation.record_at(&peer, Conduct::Valid, now);
+        }
+        assert_eq!(reputation.scores()[&peer], MAX_SCORE);
+    }
+
+    #[test]
+    fn test_bans_expire() {
+        let mut reputation = PeerReputation::new(-10, Duration::from_secs(60));
+        let peer = peer();
+        let start = Instant::now();
+        assert!(reputation.record_at(&peer, Conduct::BadSignature, start));
+        assert!(reputation.expire_bans_at(start + Duration::from_secs(59)).is_empty());
+        assert_eq!(reputation.expire_bans_at(start + Duration::from_secs(60)), vec![peer]);
+        assert!(!reputation.is_banned_at(&peer, start + Duration::from_secs(60)));
+        assert!(!reputation.record_at(&peer, Conduct::Invalid, start + Duration::from_secs(60)));
+        assert_eq!(reputation.scores()[&peer], -5);
+    }
+}
@@ -0,0 +1,142 @@
+//! Deferred Publishing
+//!
+//! Gossipsub refuses to publish to a topic no known peer subscribes to, as
+//! happens at startup or while a node is cut off. Under a `RetryPolicy` the
+//! engine keeps such messages instead, already signed and encoded, and
+//! publishes them again when a peer subscribes to their topic. Messages
+//! still waiting after the policy's TTL are given up on; the TTL should stay
+//! below the packet age peers accept, or retried packets arrive stale.
+
+use libp2p::gossipsub::{IdentTopic, TopicHash};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::network::message::NetworkMessage;
+
+/// How messages published while no peer subscribes to their topic are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How long a message waits for a peer before it is given up on
+    pub ttl: Duration,
+    /// Most messages waiting at once; publishing fails while this many are
+    pub capacity: usize,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { ttl: Duration::from_secs(120), capacity: 1000 }
+    }
+}
+
+/// A message waiting for a peer on its topic
+#[derive(Debug, Clone)]
+pub(crate) struct Deferred {
+    pub(crate) topic: IdentTopic,
+    /// Encoded, and sealed if the topic is private, ready to publish
+    pub(crate) data: Vec<u8>,
+    pub(crate) message: NetworkMessage,
+    deferred_at: Instant,
+}
+
+/// Messages waiting for peers, oldest first
+#[derive(Debug)]
+pub(crate) struct DeferredQueue {
+    policy: RetryPolicy,
+    entries: VecDeque<Deferred>,
+}
+
+impl DeferredQueue {
+    pub(crate) fn new(policy: RetryPolicy) -> Self {
+        Self { policy, entries: VecDeque::new() }
+    }
+
+    /// Keep `message` for a retry, unless the queue is full
+    pub(crate) fn push(&mut self, topic: IdentTopic, data: Vec<u8>, message: NetworkMessage) -> bool {
+        self.push_at(topic, data, message, Instant::now())
+    }
+
+    fn push_at(&mut self, topic: IdentTopic, data: Vec<u8>, message: NetworkMessage, now: Instant) -> bool {
+        if self.entries.len() >= self.policy.capacity {
+            return false;
+        }
+        self.entries.push_back(Deferred { topic, data, message, deferred_at: now });
+        true
+    }
+
+    /// Take the messages waiting on `topic`, to be retried
+    pub(crate) fn take_topic(&mut self, topic: &TopicHash) -> Vec<Deferred> {
+        let (taken, kept): (Vec<Deferred>, Vec<Deferred>) =
+            self.entries.drain(..).partition(|entry| entry.topic.hash() == *topic);
+        self.entries = kept.into();
+        taken
+    }
+
+    /// Put back messages whose retry failed, keeping their original deferral time
+    pub(crate) fn restore(&mut self, entries: Vec<Deferred>) {
+        self.entries.extend(entries);
+        self.entries.make_contiguous().sort_by_key(|entry| entry.deferred_at);
+    }
+
+    /// Remove and return the messages waiting longer than the TTL
+    pub(crate) fn expire(&mut self) -> Vec<Deferred> {
+        self.expire_at(Instant::now())
+    }
+
+    fn expire_at(&mut self, now: Instant) -> Vec<Deferred> {
+        let mut expired = Vec::new();
+        while self
+            .entries
+            .front()
+            .is_some_and(|entry| now.saturating_duration_since(entry.deferred_at) > self.policy.ttl)
+        {
+            expired.extend(self.entries.pop_front());
+        }
+        expired
+    }
+
+    /// Remove and return every waiting message
+    pub(crate) fn drain(&mut self) -> Vec<Deferred> {
+        self.entries.drain(..).collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::message::Severity;
+
+    fn message() -> NetworkMessage {
+        NetworkMessage::Alert {
+            severity: Severity::Warning,
+            source: "12D3KooWnode".to_string(),
+            text: "liquidity below threshold".to_string(),
+            fragility: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_deferred_until_topic_retried_or_expired() {
+        let mut queue = DeferredQueue::new(RetryPolicy { ttl: Duration::from_secs(60), capacity: 3 });
+        let (eu, alerts) = (IdentTopic::new("olo-fragility-eu"), IdentTopic::new("olo-alerts"));
+        let start = Instant::now();
+        assert!(queue.push_at(eu.clone(), vec![1], message(), start));
+        assert!(queue.push_at(alerts.clone(), vec![2], message(), start + Duration::from_secs(10)));
+        assert!(queue.push_at(eu.clone(), vec![3], message(), start + Duration::from_secs(20)));
+        assert!(!queue.push_at(eu.clone(), vec![4], message(), start + Duration::from_secs(30)));
+
+        let retried = queue.take_topic(&eu.hash());
+        assert_eq!(retried.iter().map(|entry| entry.data[0]).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(queue.len(), 1);
+        queue.restore(retried);
+
+        let expired = queue.expire_at(start + Duration::from_secs(75));
+        assert_eq!(expired.iter().map(|entry| entry.data[0]).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.drain().len(), 1);
+    }
+}
@@ -0,0 +1,414 @@
+//! Local Packet Store
+//!
+//! Delivered packets are handed to the consumer and otherwise forgotten, so
+//! nothing can answer "what did this bank report last week?". A
+//! `PacketStore` keeps them for querying by source and time, and backs the
+//! history protocol.
+//!
+//! `InMemoryStore` keeps a bounded number of packets and loses them on
+//! restart. `FileStore` appends each packet to a log file and keeps only an
+//! index in memory, rebuilt by scanning the log when the store is opened.
+//! Each log record is a little-endian `u32` length followed by the packet in
+//! its bincode wire encoding, so the wire header versions the records too.
+//! A record cut short by a crash mid-append is truncated away on open.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::network::ingestion::DataPacket;
+use crate::network::wire::{self, WireFormat};
+
+/// Errors reading or writing a packet store
+#[derive(Debug)]
+pub enum StoreError {
+    /// Reading or writing the log failed
+    Io { path: PathBuf, source: io::Error },
+    /// A complete record in the log does not hold a packet
+    Corrupt { path: PathBuf, offset: u64, reason: String },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io { path, source } => write!(f, "packet store I/O failed for {}: {}", path.display(), source),
+            StoreError::Corrupt { path, offset, reason } => {
+                write!(f, "packet store {} is corrupt at byte {}: {}", path.display(), offset, reason)
+            }
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Io { source, .. } => Some(source),
+            StoreError::Corrupt { .. } => None,
+        }
+    }
+}
+
+/// Packets kept for querying by source and timestamp
+///
+/// Timestamps are Unix epoch milliseconds, and results come oldest first.
+pub trait PacketStore: Send {
+    /// Keep `packet`
+    fn insert(&mut self, packet: &DataPacket) -> Result<(), StoreError>;
+
+    /// Packets from `source` with timestamps in `range`
+    fn by_source(&self, source: &str, range: Range<u64>) -> Result<Vec<DataPacket>, StoreError>;
+
+    /// Packets from any source with timestamps from `since` on
+    fn since(&self, since: u64) -> Result<Vec<DataPacket>, StoreError>;
+
+    /// Most recent packet from each source, by source
+    fn latest_per_source(&self) -> Result<HashMap<String, DataPacket>, StoreError>;
+
+    /// Forget packets with timestamps before `timestamp`, returning how many
+    fn prune_older_than(&mut self, timestamp: u64) -> Result<usize, StoreError>;
+
+    /// Number of packets kept
+    fn len(&self) -> usize;
+
+    /// Whether no packets are kept
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Packets held in memory, oldest inserted forgotten beyond capacity
+#[derive(Debug, Clone)]
+pub struct InMemoryStore {
+    capacity: usize,
+    packets: VecDeque<DataPacket>,
+}
+
+impl InMemoryStore {
+    /// Keep up to `capacity` packets; zero keeps none
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, packets: VecDeque::new() }
+    }
+}
+
+/// `packets` sorted oldest first
+fn oldest_first(mut packets: Vec<DataPacket>) -> Vec<DataPacket> {
+    packets.sort_by_key(|packet| packet.timestamp);
+    packets
+}
+
+impl PacketStore for InMemoryStore {
+    fn insert(&mut self, packet: &DataPacket) -> Result<(), StoreError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        if self.packets.len() == self.capacity {
+            self.packets.pop_front();
+        }
+        self.packets.push_back(packet.clone());
+        Ok(())
+    }
+
+    fn by_source(&self, source: &str, range: Range<u64>) -> Result<Vec<DataPacket>, StoreError> {
+        Ok(oldest_first(
+            self.packets
+                .iter()
+                .filter(|packet| packet.source == source && range.contains(&packet.timestamp))
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    fn since(&self, since: u64) -> Result<Vec<DataPacket>, StoreError> {
+        Ok(oldest_first(self.packets.iter().filter(|packet| packet.timestamp >= since).cloned().collect()))
+    }
+
+    fn latest_per_source(&self) -> Result<HashMap<String, DataPacket>, StoreError> {
+        let mut latest: HashMap<String, DataPacket> = HashMap::new();
+        for packet in &self.packets {
+            match latest.get(&packet.source) {
+                Some(kept) if kept.timestamp > packet.timestamp => {}
+                _ => {
+                    latest.insert(packet.source.clone(), packet.clone());
+                }
+            }
+        }
+        Ok(latest)
+    }
+
+    fn prune_older_than(&mut self, timestamp: u64) -> Result<usize, StoreError> {
+        let before = self.packets.len();
+        self.packets.retain(|packet| packet.timestamp >= timestamp);
+        Ok(before - self.packets.len())
+    }
+
+    fn len(&self) -> usize {
+        self.packets.len()
+    }
+}
+
+/// Size of a record's length prefix
+const LENGTH_BYTES: usize = 4;
+
+/// Packets in an append-only log file, indexed in memory
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    log: File,
+    /// Offsets of each source's records, by timestamp
+    index: HashMap<String, BTreeMap<u64, Vec<u64>>>,
+    len: usize,
+}
+
+impl FileStore {
+    /// Open the log at `path`, creating it if missing
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        let io_error = |source| StoreError::Io { path: path.to_path_buf(), source };
+        let log = OpenOptions::new().read(true).append(true).create(true).open(path).map_err(io_error)?;
+        let mut store = Self { path: path.to_path_buf(), log, index: HashMap::new(), len: 0 };
+        store.rebuild_index()?;
+        Ok(store)
+    }
+
+    fn io_error(&self, source: io::Error) -> StoreError {
+        StoreError::Io { path: self.path.clone(), source }
+    }
+
+    /// Body of the record for `packet`
+    fn encode(&self, packet: &DataPacket) -> Result<Vec<u8>, StoreError> {
+        wire::encode(packet, WireFormat::Bincode).map_err(|e| self.io_error(io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+
+    /// Records in the log as `(offset, packet)`, truncating a torn final record
+    fn scan(&self) -> Result<Vec<(u64, DataPacket)>, StoreError> {
+        let bytes = fs::read(&self.path).map_err(|e| self.io_error(e))?;
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let Some(header) = bytes.get(offset..offset + LENGTH_BYTES) else { break };
+            let length = u32::from_le_bytes(header.try_into().expect("four bytes")) as usize;
+            let Some(body) = bytes.get(offset + LENGTH_BYTES..offset + LENGTH_BYTES + length) else { break };
+            let (packet, _) = wire::decode(body).map_err(|e| StoreError::Corrupt {
+                path: self.path.clone(),
+                offset: offset as u64,
+                reason: e.to_string(),
+            })?;
+            records.push((offset as u64, packet));
+            offset += LENGTH_BYTES + length;
+        }
+        if offset < bytes.len() {
+            self.log.set_len(offset as u64).map_err(|e| self.io_error(e))?;
+        }
+        Ok(records)
+    }
+
+    fn rebuild_index(&mut self) -> Result<(), StoreError> {
+        let records = self.scan()?;
+        self.index.clear();
+        self.len = records.len();
+        for (offset, packet) in records {
+            self.index_record(&packet, offset);
+        }
+        Ok(())
+    }
+
+    fn index_record(&mut self, packet: &DataPacket, offset: u64) {
+        self.index
+            .entry(packet.source.clone())
+            .or_default()
+            .entry(packet.timestamp)
+            .or_default()
+            .push(offset);
+    }
+
+    /// Packets of the records at `offsets`
+    fn read(&self, offsets: impl IntoIterator<Item = u64>) -> Result<Vec<DataPacket>, StoreError> {
+        let mut log = File::open(&self.path).map_err(|e| self.io_error(e))?;
+        let mut packets = Vec::new();
+        for offset in offsets {
+            let mut header = [0; LENGTH_BYTES];
+            log.seek(SeekFrom::Start(offset)).map_err(|e| self.io_error(e))?;
+            log.read_exact(&mut header).map_err(|e| self.io_error(e))?;
+            let mut body = vec![0; u32::from_le_bytes(header) as usize];
+            log.read_exact(&mut body).map_err(|e| self.io_error(e))?;
+            let (packet, _) = wire::decode(&body).map_err(|e| StoreError::Corrupt {
+                path: self.path.clone(),
+                offset,
+                reason: e.to_string(),
+            })?;
+            packets.push(packet);
+        }
+        Ok(packets)
+    }
+}
+
+impl PacketStore for FileStore {
+    fn insert(&mut self, packet: &DataPacket) -> Result<(), StoreError> {
+        let body = self.encode(packet)?;
+        let offset = self.log.seek(SeekFrom::End(0)).map_err(|e| self.io_error(e))?;
+        let mut record = (body.len() as u32).to_le_bytes().to_vec();
+        record.extend_from_slice(&body);
+        self.log.write_all(&record).map_err(|e| self.io_error(e))?;
+        self.index_record(packet, offset);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn by_source(&self, source: &str, range: Range<u64>) -> Result<Vec<DataPacket>, StoreError> {
+        let Some(records) = self.index.get(source) else {
+            return Ok(Vec::new());
+        };
+        self.read(records.range(range).flat_map(|(_, offsets)| offsets.iter().copied()))
+    }
+
+    fn since(&self, since: u64) -> Result<Vec<DataPacket>, StoreError> {
+        let mut offsets: Vec<u64> = self
+            .index
+            .values()
+            .flat_map(|records| records.range(since..).flat_map(|(_, offsets)| offsets.iter().copied()))
+            .collect();
+        offsets.sort_unstable();
+        Ok(oldest_first(self.read(offsets)?))
+    }
+
+    fn latest_per_source(&self) -> Result<HashMap<String, DataPacket>, StoreError> {
+        let offsets = self
+            .index
+            .values()
+            .filter_map(|records| records.values().next_back().and_then(|offsets| offsets.last().copied()));
+        Ok(self.read(offsets)?.into_iter().map(|packet| (packet.source.clone(), packet)).collect())
+    }
+
+    /// Rewrites the log without the pruned records
+    fn prune_older_than(&mut self, timestamp: u64) -> Result<usize, StoreError> {
+        let records = self.scan()?;
+        let before = records.len();
+        let kept: Vec<DataPacket> = records
+            .into_iter()
+            .map(|(_, packet)| packet)
+            .filter(|packet| packet.timestamp >= timestamp)
+            .collect();
+        if kept.len() == before {
+            return Ok(0);
+        }
+
+        let staging = self.path.with_extension("prune");
+        let mut bytes = Vec::new();
+        for packet in &kept {
+            let body = self.encode(packet)?;
+            bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&body);
+        }
+        fs::write(&staging, &bytes).map_err(|e| self.io_error(e))?;
+        fs::rename(&staging, &self.path).map_err(|e| self.io_error(e))?;
+        self.log = OpenOptions::new().read(true).append(true).open(&self.path).map_err(|e| self.io_error(e))?;
+        self.rebuild_index()?;
+        Ok(before - kept.len())
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    fn packet(source: &str, timestamp: u64) -> DataPacket {
+        fixtures::packet().source(source).timestamp(timestamp).signature(vec![timestamp as u8; 64]).build()
+    }
+
+    fn timestamps(packets: &[DataPacket]) -> Vec<u64> {
+        packets.iter().map(|packet| packet.timestamp).collect()
+    }
+
+    fn log_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("olo-store-{}-{}.log", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Fill `store` with three reports each from two banks
+    fn fill(store: &mut dyn PacketStore) {
+        let reports = [("bank-a", 30), ("bank-b", 10), ("bank-a", 10), ("bank-b", 20), ("bank-a", 20), ("bank-b", 40)];
+        for (source, timestamp) in reports {
+            store.insert(&packet(source, timestamp)).unwrap();
+        }
+    }
+
+    fn assert_queries(store: &dyn PacketStore) {
+        assert_eq!(store.len(), 6);
+        assert_eq!(timestamps(&store.by_source("bank-a", 0..u64::MAX).unwrap()), vec![10, 20, 30]);
+        assert_eq!(timestamps(&store.by_source("bank-b", 15..40).unwrap()), vec![20]);
+        assert!(store.by_source("bank-c", 0..u64::MAX).unwrap().is_empty());
+        assert_eq!(timestamps(&store.since(20).unwrap()), vec![20, 20, 30, 40]);
+        let latest = store.latest_per_source().unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest["bank-a"].timestamp, 30);
+        assert_eq!(latest["bank-b"].timestamp, 40);
+    }
+
+    #[test]
+    fn test_memory_store_queries_and_capacity() {
+        let mut store = InMemoryStore::new(6);
+        fill(&mut store);
+        assert_queries(&store);
+        assert_eq!(store.prune_older_than(20).unwrap(), 2);
+        assert_eq!(timestamps(&store.since(0).unwrap()), vec![20, 20, 30, 40]);
+
+        let mut small = InMemoryStore::new(2);
+        fill(&mut small);
+        assert_eq!(timestamps(&small.since(0).unwrap()), vec![20, 40]);
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let path = log_path("reopen");
+        let mut store = FileStore::open(&path).unwrap();
+        fill(&mut store);
+        assert_queries(&store);
+        drop(store);
+
+        let reopened = FileStore::open(&path).unwrap();
+        assert_queries(&reopened);
+        let stored = reopened.by_source("bank-b", 40..41).unwrap();
+        assert_eq!(stored[0].signing_bytes(), packet("bank-b", 40).signing_bytes());
+        assert_eq!(stored[0].signature, vec![40; 64]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_prunes_and_recovers_torn_tail() {
+        let path = log_path("prune");
+        let mut store = FileStore::open(&path).unwrap();
+        fill(&mut store);
+        assert_eq!(store.prune_older_than(20).unwrap(), 2);
+        assert_eq!(store.prune_older_than(20).unwrap(), 0);
+        store.insert(&packet("bank-a", 50)).unwrap();
+        drop(store);
+
+        // A crash partway through appending a record
+        let intact = fs::metadata(&path).unwrap().len();
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(&[200, 0, 0, 0, 1, 2, 3]).unwrap();
+        drop(log);
+
+        let mut reopened = FileStore::open(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), intact);
+        assert_eq!(timestamps(&reopened.since(0).unwrap()), vec![20, 20, 30, 40, 50]);
+        reopened.insert(&packet("bank-b", 60)).unwrap();
+        assert_eq!(FileStore::open(&path).unwrap().len(), 6);
+
+        // A complete record that is not a packet is reported, not skipped
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(&[3, 0, 0, 0, 9, 9, 9]).unwrap();
+        drop(log);
+        assert!(matches!(FileStore::open(&path), Err(StoreError::Corrupt { .. })));
+        fs::remove_file(&path).unwrap();
+    }
+}
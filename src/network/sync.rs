@@ -0,0 +1,154 @@
+//! Anti-Entropy Sync
+//!
+//! Gossip only reaches the peers connected when a packet is published, so
+//! two nodes that were partitioned for a while each miss what the other
+//! heard meanwhile. When a peer connects, each node sends it a digest over
+//! the `/olo/sync/1` request-response protocol: the latest timestamp it
+//! holds from every source within the sync horizon. The peer answers with
+//! the packets it holds within the horizon that are newer than the digest
+//! says, so both sides converge on the same latest packet per source.
+//!
+//! A session is bounded in bytes: the requester names its budget, the
+//! server never sends more than that or `MAX_SYNC_BYTES`, and the requester
+//! ignores anything past its budget. Each source's newest missing packet
+//! is sent before any older ones, so a truncated session still brings the
+//! latest-per-source view up to date first. As with history, packets
+//! travel in their wire encoding and each one's signature is checked
+//! against the peer ID named as its source.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+use libp2p::StreamProtocol;
+
+use crate::network::ingestion::DataPacket;
+use crate::network::wire::{self, WireFormat};
+
+/// Protocol name of the sync exchange
+pub const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/olo/sync/1");
+
+/// Most encoded bytes a server sends in one session, whatever the budget asked for
+pub const MAX_SYNC_BYTES: usize = 4 << 20;
+
+/// What the requester holds, so the server can send what it lacks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncRequest {
+    /// Start of the horizon: earliest timestamp wanted (Unix epoch milliseconds)
+    pub since: u64,
+    /// Latest timestamp held from each source within the horizon
+    pub latest: BTreeMap<String, u64>,
+    /// Most encoded bytes wanted in the response
+    pub max_bytes: u64,
+}
+
+/// Packets the requester was missing
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncResponse {
+    /// Wire-encoded packets, each source's newest first
+    pub packets: Vec<Vec<u8>>,
+    /// Whether missing packets were left out to stay within the budget
+    pub truncated: bool,
+}
+
+/// Digest of `packets` from `since` on: the latest timestamp from each source
+pub fn digest<'a>(packets: impl IntoIterator<Item = &'a DataPacket>, since: u64) -> BTreeMap<String, u64> {
+    let mut latest = BTreeMap::new();
+    for packet in packets.into_iter().filter(|packet| packet.timestamp >= since) {
+        let timestamp = latest.entry(packet.source.clone()).or_insert(packet.timestamp);
+        *timestamp = (*timestamp).max(packet.timestamp);
+    }
+    latest
+}
+
+/// Packets among `packets` that `request` shows missing, encoded in `format`
+///
+/// `packets` need not be sorted.
+pub fn serve<'a>(
+    packets: impl IntoIterator<Item = &'a DataPacket>,
+    request: &SyncRequest,
+    format: WireFormat,
+) -> SyncResponse {
+    let mut missing: Vec<&DataPacket> = packets
+        .into_iter()
+        .filter(|packet| packet.timestamp >= request.since)
+        .filter(|packet| request.latest.get(&packet.source).is_none_or(|latest| packet.timestamp > *latest))
+        .collect();
+    missing.sort_by_key(|packet| Reverse(packet.timestamp));
+    // Each source's newest packet, then the rest, newest first
+    let mut sources = HashSet::new();
+    let (newest, older): (Vec<&DataPacket>, Vec<&DataPacket>) =
+        missing.into_iter().partition(|packet| sources.insert(packet.source.as_str()));
+
+    let budget = usize::try_from(request.max_bytes).unwrap_or(usize::MAX).min(MAX_SYNC_BYTES);
+    let mut response = SyncResponse::default();
+    let mut bytes = 0;
+    for packet in newest.into_iter().chain(older) {
+        let Ok(encoded) = wire::encode(packet, format) else {
+            continue;
+        };
+        if bytes + encoded.len() > budget {
+            response.truncated = true;
+            break;
+        }
+        bytes += encoded.len();
+        response.packets.push(encoded);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    fn packet(source: &str, timestamp: u64) -> DataPacket {
+        fixtures::packet().source(source).timestamp(timestamp).signature(vec![0; 64]).build()
+    }
+
+    fn sent(response: &SyncResponse) -> Vec<(String, u64)> {
+        response
+            .packets
+            .iter()
+            .map(|bytes| wire::decode(bytes).unwrap().0)
+            .map(|packet| (packet.source, packet.timestamp))
+            .collect()
+    }
+
+    #[test]
+    fn test_only_missing_packets_within_horizon_sent() {
+        let held = [packet("bank-a", 5), packet("bank-a", 20), packet("bank-a", 30), packet("bank-b", 25)];
+        assert_eq!(digest(&held, 10), BTreeMap::from([("bank-a".to_string(), 30), ("bank-b".to_string(), 25)]));
+
+        let packets = [
+            packet("bank-a", 5),
+            packet("bank-a", 20),
+            packet("bank-a", 40),
+            packet("bank-a", 50),
+            packet("bank-b", 25),
+            packet("bank-c", 15),
+        ];
+        let request = SyncRequest { since: 10, latest: digest(&held, 10), max_bytes: u64::MAX };
+        let response = serve(&packets, &request, WireFormat::Json);
+        assert_eq!(
+            sent(&response),
+            vec![("bank-a".to_string(), 50), ("bank-c".to_string(), 15), ("bank-a".to_string(), 40)]
+        );
+        assert!(!response.truncated);
+    }
+
+    #[test]
+    fn test_session_bounded_in_bytes() {
+        let packets: Vec<_> = (0..10).map(|t| packet("bank-a", 100 + t)).chain([packet("bank-b", 1)]).collect();
+        let size = wire::encode(&packets[0], WireFormat::Cbor).unwrap().len();
+        let request = SyncRequest { since: 0, latest: BTreeMap::new(), max_bytes: (size * 3) as u64 };
+        let response = serve(&packets, &request, WireFormat::Cbor);
+        assert!(response.truncated);
+        assert!(response.packets.iter().map(Vec::len).sum::<usize>() <= size * 3);
+        // Each source's newest packet goes first
+        assert_eq!(
+            sent(&response)[..2],
+            [("bank-a".to_string(), 109), ("bank-b".to_string(), 1)]
+        );
+    }
+}
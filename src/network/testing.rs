@@ -0,0 +1,259 @@
+//! In-Process Test Meshes
+//!
+//! Testing gossip takes several engines listening, connected to each other,
+//! and driven together. `TestMesh` runs `n` engines in the calling task on
+//! ephemeral loopback ports, dials every pair, and waits until each engine
+//! has the others in its gossip mesh. Engines only make progress while the
+//! mesh drives them, in `collect_on`; the packets each delivers meanwhile
+//! are kept until collected, and other events are discarded.
+//!
+//! Built for the crate's own tests, and for other crates with the
+//! `test-utils` feature. The crate's tests also share the packet builder in
+//! `fixtures`.
+
+use libp2p::futures::future::select_all;
+use libp2p::PeerId;
+use std::error::Error;
+use std::time::Duration;
+
+use crate::network::ingestion::{DataPacket, EngineEvent, GossipsubTuning, IngestionEngine, NetworkConfig, NetworkError};
+
+/// Longest `TestMesh::new` waits for every engine's gossip mesh to fill
+pub const MESH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the mesh is checked while it forms, as heartbeats raise no events
+const MESH_POLL: Duration = Duration::from_millis(100);
+
+/// Engines in one process, each connected to all the others
+pub struct TestMesh {
+    engines: Vec<IngestionEngine>,
+    /// Packets each engine delivered, not yet collected
+    received: Vec<Vec<DataPacket>>,
+    /// Peers each engine was partitioned from
+    denied: Vec<Vec<PeerId>>,
+}
+
+impl TestMesh {
+    /// Mesh of `n` engines with the default configuration
+    pub async fn new(n: usize) -> Result<Self, Box<dyn Error>> {
+        Self::with_config(n, |_| NetworkConfig::default()).await
+    }
+
+    /// Mesh of `n` engines, engine `i` configured by `config(i)`
+    ///
+    /// Engines listen on loopback whatever their `listen_addr`. Fails if an
+    /// engine cannot be built or bound, or the mesh is not up within
+    /// `MESH_TIMEOUT`: every engine meshed with all the others, or with the
+    /// default `mesh_n_low` of them in larger meshes.
+    pub async fn with_config(n: usize, config: impl Fn(usize) -> NetworkConfig) -> Result<Self, Box<dyn Error>> {
+        let mut engines = Vec::with_capacity(n);
+        let mut addrs = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut engine = IngestionEngine::new(config(i))?;
+            addrs.push(engine.listen("/ip4/127.0.0.1/tcp/0".parse()?).await?);
+            engines.push(engine);
+        }
+        for (i, engine) in engines.iter_mut().enumerate() {
+            for addr in &addrs[..i] {
+                engine.dial(addr.clone())?;
+            }
+        }
+        let mut mesh = Self { engines, received: vec![Vec::new(); n], denied: vec![Vec::new(); n] };
+        let wanted = n.saturating_sub(1).min(GossipsubTuning::default().mesh_n_low) as u64;
+        tokio::time::timeout(MESH_TIMEOUT, async {
+            while mesh.engines.iter().any(|engine| engine.metrics_snapshot().mesh_peers < wanted) {
+                let _ = tokio::time::timeout(MESH_POLL, mesh.step()).await;
+            }
+        })
+        .await
+        .map_err(|_| format!("gossip mesh of {} engines not up within {:?}", n, MESH_TIMEOUT))?;
+        Ok(mesh)
+    }
+
+    /// Number of engines
+    pub fn len(&self) -> usize {
+        self.engines.len()
+    }
+
+    /// Whether the mesh has no engines
+    pub fn is_empty(&self) -> bool {
+        self.engines.is_empty()
+    }
+
+    pub fn engine(&self, i: usize) -> &IngestionEngine {
+        &self.engines[i]
+    }
+
+    pub fn engine_mut(&mut self, i: usize) -> &mut IngestionEngine {
+        &mut self.engines[i]
+    }
+
+    /// Peer ID of engine `i`
+    pub fn peer_id(&self, i: usize) -> PeerId {
+        self.engines[i].local_peer_id()
+    }
+
+    /// Sign `packet` with engine `i`'s key and publish it, as `IngestionEngine::publish` does
+    pub async fn publish_from(&mut self, i: usize, packet: DataPacket) -> Result<(), NetworkError> {
+        self.engines[i].publish(packet).await
+    }
+
+    /// Drive every engine for `timeout`, then take the packets engine `j` has delivered
+    pub async fn collect_on(&mut self, j: usize, timeout: Duration) -> Vec<DataPacket> {
+        let _ = tokio::time::timeout(timeout, async {
+            loop {
+                self.step().await;
+            }
+        })
+        .await;
+        std::mem::take(&mut self.received[j])
+    }
+
+    /// Cut engines `a` and `b` off from each other
+    ///
+    /// Their connections are closed, and each denies the other from then
+    /// on: connections, and messages it authored even when relayed by a
+    /// third engine.
+    pub fn partition(&mut self, a: usize, b: usize) {
+        for (from, to) in [(a, b), (b, a)] {
+            let peer = self.peer_id(to);
+            self.denied[from].push(peer);
+            self.engines[from].update_acl(Vec::new(), self.denied[from].clone());
+        }
+    }
+
+    /// Handle the next event of whichever engine has one first
+    async fn step(&mut self) {
+        if self.engines.is_empty() {
+            return std::future::pending().await;
+        }
+        let (event, i, _) = select_all(self.engines.iter_mut().map(|engine| Box::pin(engine.next_event()))).await;
+        if let EngineEvent::PacketReceived { packet, .. } = event {
+            self.received[i].push(packet);
+        }
+    }
+}
+
+/// Packets for the crate's tests
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::core::lagrangian::{compute_fragility, BankState, LagrangianConfig};
+    use crate::network::ingestion::DataPacket;
+
+    /// Builds a test packet, starting from an unsigned, unproven report
+    pub(crate) struct PacketBuilder(DataPacket);
+
+    /// Report from `12D3KooWnode` stamped 2023-11-14, fragility 21.5
+    pub(crate) fn packet() -> PacketBuilder {
+        PacketBuilder(DataPacket {
+            timestamp: 1_700_000_000_000,
+            source: "12D3KooWnode".to_string(),
+            state: BankState {
+                tier1_capital: 10_000.0,
+                total_assets: 100_000.0,
+                liquidity_coverage: 1.2,
+                entropy_index: 2.0,
+            },
+            fragility: 21.5,
+            signature: vec![],
+            proof: None,
+            period: None,
+            envelope: None,
+            expires_at: None,
+        })
+    }
+
+    impl PacketBuilder {
+        pub(crate) fn source(mut self, source: impl ToString) -> Self {
+            self.0.source = source.to_string();
+            self
+        }
+
+        pub(crate) fn timestamp(mut self, timestamp: u64) -> Self {
+            self.0.timestamp = timestamp;
+            self
+        }
+
+        pub(crate) fn tier1_capital(mut self, tier1_capital: f64) -> Self {
+            self.0.state.tier1_capital = tier1_capital;
+            self
+        }
+
+        pub(crate) fn total_assets(mut self, total_assets: f64) -> Self {
+            self.0.state.total_assets = total_assets;
+            self
+        }
+
+        pub(crate) fn entropy_index(mut self, entropy_index: f64) -> Self {
+            self.0.state.entropy_index = entropy_index;
+            self
+        }
+
+        pub(crate) fn fragility(mut self, fragility: f64) -> Self {
+            self.0.fragility = fragility;
+            self
+        }
+
+        /// The fragility `compute_fragility` gives the state, by default
+        pub(crate) fn computed_fragility(mut self) -> Self {
+            self.0.fragility = compute_fragility(&self.0.state, &LagrangianConfig::default());
+            self
+        }
+
+        pub(crate) fn signature(mut self, signature: Vec<u8>) -> Self {
+            self.0.signature = signature;
+            self
+        }
+
+        pub(crate) fn proof(mut self, proof: Vec<u8>) -> Self {
+            self.0.proof = Some(proof);
+            self
+        }
+
+        pub(crate) fn period(mut self, period: u64) -> Self {
+            self.0.period = Some(period);
+            self
+        }
+
+        pub(crate) fn build(self) -> DataPacket {
+            self.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::packet;
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn packet_from(source: PeerId) -> DataPacket {
+        packet()
+            .source(source)
+            .timestamp(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64)
+            .tier1_capital(8_002.5)
+            .computed_fragility()
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_gossip_reaches_every_node_but_partitioned_ones() {
+        let mut mesh = TestMesh::new(3).await.unwrap();
+        let first = packet_from(mesh.peer_id(0));
+        mesh.publish_from(0, first.clone()).await.unwrap();
+        for j in [1, 2] {
+            let received = mesh.collect_on(j, Duration::from_secs(2)).await;
+            assert_eq!(received.len(), 1, "node {}", j);
+            assert_eq!(received[0].signing_bytes(), first.signing_bytes());
+        }
+        assert!(mesh.collect_on(0, Duration::ZERO).await.is_empty());
+
+        mesh.partition(0, 2);
+        let second = packet_from(mesh.peer_id(0));
+        mesh.publish_from(0, second.clone()).await.unwrap();
+        let received = mesh.collect_on(1, Duration::from_secs(2)).await;
+        assert_eq!(received.iter().map(|packet| packet.timestamp).collect::<Vec<_>>(), [second.timestamp]);
+        // Not even through node 1
+        assert!(mesh.collect_on(2, Duration::from_secs(1)).await.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+//! Packet Validation
+//!
+//! Sanity checks on received packets before they are delivered. A packet can
+//! be correctly signed and still carry a NaN fragility or a negative balance
+//! sheet, whether from a buggy peer or a malicious one; `PacketValidator`
+//! rejects those with a reason instead of passing them downstream.
+//!
+//! The default rules require:
+//!
+//! - a finite fragility in [0, 100], the range `compute_fragility` produces
+//! - finite, non-negative state fields, with positive total assets and
+//!   liquidity coverage since fragility divides by them
+//! - a timestamp within the clock skew window of local time
+//! - a non-empty source
+//!
+//! Further rules are added as closures with `PacketValidator::with_rule`.
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::network::ingestion::DataPacket;
+
+/// Highest fragility score `compute_fragility` produces
+const MAX_FRAGILITY: f64 = 100.0;
+
+/// Extra check on a received packet, returning why it fails
+pub type PacketRule = Box<dyn Fn(&DataPacket) -> Result<(), String> + Send + Sync>;
+
+/// Checks received packets against sane value ranges
+pub struct PacketValidator {
+    max_clock_skew: Duration,
+    log_rejections: bool,
+    rules: Vec<PacketRule>,
+}
+
+impl Default for PacketValidator {
+    fn default() -> Self {
+        Self {
+            max_clock_skew: Duration::from_secs(300),
+            log_rejections: false,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for PacketValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketValidator")
+            .field("max_clock_skew", &self.max_clock_skew)
+            .field("log_rejections", &self.log_rejections)
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+impl PacketValidator {
+    /// Accept timestamps up to `skew` before or after local time
+    pub fn with_max_clock_skew(mut self, skew: Duration) -> Self {
+        self.max_clock_skew = skew;
+        self
+    }
+
+    /// Log each rejection as a warning
+    pub fn with_logging(mut self, enabled: bool) -> Self {
+        self.log_rejections = enabled;
+        self
+    }
+
+    /// Also require `rule` to pass, after the default rules
+    pub fn with_rule<F>(mut self, rule: F) -> Self
+    where
+        F: Fn(&DataPacket) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Check `packet` against every rule, returning the first failure
+    pub fn validate(&self, packet: &DataPacket) -> Result<(), String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let result = self.validate_at(packet, now);
+        if let Err(reason) = &result {
+            if self.log_rejections {
+                tracing::warn!(source = %packet.source, %reason, "rejected packet");
+            }
+        }
+        result
+    }
+
+    /// `validate` with local time `now`, in Unix epoch milliseconds
+    fn validate_at(&self, packet: &DataPacket, now: u64) -> Result<(), String> {
+        if packet.source.trim().is_empty() {
+            return Err("empty source".to_string());
+        }
+        if !(packet.fragility.is_finite() && (0.0..=MAX_FRAGILITY).contains(&packet.fragility)) {
+            return Err(format!("fragility {} outside [0, {}]", packet.fragility, MAX_FRAGILITY));
+        }
+
+        let state = &packet.state;
+        for (name, value) in [
+            ("tier1_capital", state.tier1_capital),
+            ("total_assets", state.total_assets),
+            ("liquidity_coverage", state.liquidity_coverage),
+            ("entropy_index", state.entropy_index),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{} {} is not a finite non-negative amount", name, value));
+            }
+        }
+        if state.total_assets == 0.0 || state.liquidity_coverage == 0.0 {
+            return Err("total_assets and liquidity_coverage must be positive".to_string());
+        }
+
+        let skew = self.max_clock_skew.as_millis() as u64;
+        if packet.timestamp.abs_diff(now) > skew {
+            return Err(format!(
+                "timestamp {} is more than {:?} from local time {}",
+                packet.timestamp, self.max_clock_skew, now
+            ));
+        }
+
+        self.rules.iter().try_for_each(|rule| rule(packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn packet() -> DataPacket {
+        fixtures::packet().timestamp(NOW).build()
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let validator = PacketValidator::default();
+        assert_eq!(validator.validate_at(&packet(), NOW), Ok(()));
+
+        let nan = DataPacket { fragility: f64::NAN, ..packet() };
+        assert!(validator.validate_at(&nan, NOW).unwrap_err().contains("fragility"));
+        let over = DataPacket { fragility: 100.5, ..packet() };
+        assert!(validator.validate_at(&over, NOW).is_err());
+
+        let mut negative = packet();
+        negative.state.tier1_capital = -1.0;
+        assert!(validator.validate_at(&negative, NOW).unwrap_err().contains("tier1_capital"));
+        let mut illiquid = packet();
+        illiquid.state.liquidity_coverage = 0.0;
+        assert!(validator.validate_at(&illiquid, NOW).is_err());
+
+        let anonymous = DataPacket { source: " ".to_string(), ..packet() };
+        assert_eq!(validator.validate_at(&anonymous, NOW), Err("empty source".to_string()));
+    }
+
+    #[test]
+    fn test_clock_skew_window() {
+        let validator = PacketValidator::default().with_max_clock_skew(Duration::from_secs(60));
+        assert!(validator.validate_at(&packet(), NOW + 60_000).is_ok());
+        assert!(validator.validate_at(&packet(), NOW - 60_000).is_ok());
+        assert!(validator.validate_at(&packet(), NOW + 60_001).is_err());
+        assert!(validator.validate_at(&packet(), NOW - 60_001).is_err());
+    }
+
+    #[test]
+    fn test_custom_rules_run_after_defaults() {
+        let validator = PacketValidator::default().with_rule(|packet| {
+            if packet.fragility > 50.0 {
+                Err("fragility above alert threshold".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(validator.validate_at(&packet(), NOW).is_ok());
+        let alarming = DataPacket { fragility: 75.0, ..packet() };
+        assert_eq!(
+            validator.validate_at(&alarming, NOW),
+            Err("fragility above alert threshold".to_string())
+        );
+        let nan = DataPacket { fragility: f64::NAN, ..packet() };
+        assert!(validator.validate_at(&nan, NOW).unwrap_err().starts_with("fragility NaN"));
+    }
+}
@@ -0,0 +1,377 @@
+//! Packet Wire Encoding
+//!
+//! Messages travel as a two-byte header followed by the serialized
+//! `NetworkMessage`:
+//!
+//! | Byte | Meaning                                        |
+//! |------|------------------------------------------------|
+//! | 0    | `WireFormat` tag: 0 JSON, 1 CBOR, 2 bincode,   |
+//! |      | plus `COMPRESSED_FLAG` if the body is zstd     |
+//! | 1    | message schema version, `SCHEMA_VERSION`       |
+//!
+//! Schema version 1 predates `NetworkMessage`: its body is a bare
+//! `DataPacket` without a kind tag, and is still read, as a `Fragility`
+//! message, so packets from older nodes and older stores stay readable.
+//!
+//! Each node publishes in its configured format, and every node decodes any
+//! supported format and version, whatever it publishes in. JSON is the
+//! default for readability; CBOR and bincode are a fraction of the size and
+//! cheaper to decode at high message rates. JSON cannot carry NaN or
+//! infinite amounts, which decode as undecodable rather than reaching the
+//! packet validator.
+//!
+//! Bodies larger than a `Compression` threshold, such as packets carrying
+//! proofs and portfolio detail, may be zstd-compressed; receivers
+//! decompress them transparently. Uncompressed messages are unchanged, so
+//! nodes that never compress stay readable by older ones. Decompression
+//! stops at a size limit, so a small message cannot expand into gigabytes.
+
+use std::fmt;
+use std::io::Read;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::network::ingestion::DataPacket;
+use crate::network::message::{MessageKind, NetworkMessage};
+
+/// Schema version of messages written by this build
+pub const SCHEMA_VERSION: u8 = 2;
+
+/// Schema version whose body is a bare `DataPacket`
+const LEGACY_SCHEMA_VERSION: u8 = 1;
+
+/// Bit set in the format tag byte when the body is zstd-compressed
+pub const COMPRESSED_FLAG: u8 = 0x80;
+
+/// Largest body `decode_message` decompresses to
+pub const DEFAULT_DECOMPRESSION_LIMIT: usize = 8 << 20;
+
+/// When published message bodies are zstd-compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// Bodies larger than this many bytes are compressed
+    pub threshold: usize,
+    /// zstd level, from 1 (fastest) to 22 (smallest)
+    pub level: i32,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self { threshold: 16 * 1024, level: 3 }
+    }
+}
+
+/// Serialization used for published packets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    /// Self-describing text
+    #[default]
+    Json,
+    /// Self-describing binary (RFC 8949)
+    Cbor,
+    /// Compact positional binary
+    Bincode,
+}
+
+impl WireFormat {
+    /// Header byte identifying the format
+    pub fn tag(self) -> u8 {
+        match self {
+            WireFormat::Json => 0,
+            WireFormat::Cbor => 1,
+            WireFormat::Bincode => 2,
+        }
+    }
+
+    /// Format for a header byte
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(WireFormat::Json),
+            1 => Some(WireFormat::Cbor),
+            2 => Some(WireFormat::Bincode),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireFormat::Json => write!(f, "JSON"),
+            WireFormat::Cbor => write!(f, "CBOR"),
+            WireFormat::Bincode => write!(f, "bincode"),
+        }
+    }
+}
+
+/// Errors encoding or decoding a packet on the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    /// The message is shorter than the header
+    Truncated,
+    /// The format tag is not one this build knows
+    UnknownFormat(u8),
+    /// The schema version is not one this build reads
+    UnsupportedVersion(u8),
+    /// The packet could not be serialized
+    Encode { format: WireFormat, reason: String },
+    /// The body does not hold a message in the tagged format
+    Decode { format: WireFormat, reason: String },
+    /// A packet was expected but the message is of another kind
+    NotAPacket(MessageKind),
+    /// The compressed body expands past `limit` bytes
+    TooLarge { limit: usize },
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Truncated => write!(f, "message shorter than the packet header"),
+            WireError::UnknownFormat(tag) => write!(f, "unknown wire format tag {}", tag),
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "unsupported message schema version {} (reading {})", version, SCHEMA_VERSION)
+            }
+            WireError::Encode { format, reason } => write!(f, "encoding {} packet failed: {}", format, reason),
+            WireError::Decode { format, reason } => write!(f, "undecodable {} packet: {}", format, reason),
+            WireError::NotAPacket(kind) => write!(f, "expected a fragility packet, got a {} message", kind),
+            WireError::TooLarge { limit } => write!(f, "compressed message expands past {} bytes", limit),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+/// `message` in `format`, behind the wire header
+pub fn encode_message(message: &NetworkMessage, format: WireFormat) -> Result<Vec<u8>, WireError> {
+    encode_message_with(message, format, None)
+}
+
+/// `message` in `format`, compressed if `compression` applies to its size
+///
+/// The body is only sent compressed when that makes it smaller.
+pub fn encode_message_with(
+    message: &NetworkMessage,
+    format: WireFormat,
+    compression: Option<Compression>,
+) -> Result<Vec<u8>, WireError> {
+    let mut bytes = vec![format.tag(), SCHEMA_VERSION];
+    serialize_into(&mut bytes, message, format)?;
+    let Some(compression) = compression.filter(|compression| bytes.len() - 2 > compression.threshold) else {
+        return Ok(bytes);
+    };
+    let compressed = zstd::bulk::compress(&bytes[2..], compression.level).map_err(|e| WireError::Encode {
+        format,
+        reason: format!("zstd: {}", e),
+    })?;
+    if compressed.len() >= bytes.len() - 2 {
+        return Ok(bytes);
+    }
+    let mut framed = vec![format.tag() | COMPRESSED_FLAG, SCHEMA_VERSION];
+    framed.extend_from_slice(&compressed);
+    Ok(framed)
+}
+
+/// Message written by `encode_message`, or a legacy bare packet, with the format it used
+///
+/// Compressed bodies may expand to `DEFAULT_DECOMPRESSION_LIMIT`.
+pub fn decode_message(bytes: &[u8]) -> Result<(NetworkMessage, WireFormat), WireError> {
+    decode_message_limited(bytes, DEFAULT_DECOMPRESSION_LIMIT)
+}
+
+/// `decode_message`, rejecting compressed bodies that expand past `limit` bytes
+pub fn decode_message_limited(bytes: &[u8], limit: usize) -> Result<(NetworkMessage, WireFormat), WireError> {
+    let [tag, version, body @ ..] = bytes else {
+        return Err(WireError::Truncated);
+    };
+    let format = WireFormat::from_tag(*tag & !COMPRESSED_FLAG).ok_or(WireError::UnknownFormat(*tag))?;
+    let expanded;
+    let body = if *tag & COMPRESSED_FLAG != 0 {
+        expanded = decompress(body, limit, format)?;
+        &expanded[..]
+    } else {
+        body
+    };
+    let message = match *version {
+        SCHEMA_VERSION => deserialize(body, format)?,
+        LEGACY_SCHEMA_VERSION => NetworkMessage::Fragility(deserialize(body, format)?),
+        version => return Err(WireError::UnsupportedVersion(version)),
+    };
+    Ok((message, format))
+}
+
+/// zstd `body` expanded, reading no more than one byte past `limit`
+fn decompress(body: &[u8], limit: usize, format: WireFormat) -> Result<Vec<u8>, WireError> {
+    let decode_error = |e: std::io::Error| WireError::Decode { format, reason: format!("zstd: {}", e) };
+    let decoder = zstd::stream::read::Decoder::new(body).map_err(decode_error)?;
+    let mut expanded = Vec::new();
+    decoder.take(limit as u64 + 1).read_to_end(&mut expanded).map_err(decode_error)?;
+    if expanded.len() > limit {
+        return Err(WireError::TooLarge { limit });
+    }
+    Ok(expanded)
+}
+
+/// `packet` as a `Fragility` message in `format`, behind the wire header
+pub fn encode(packet: &DataPacket, format: WireFormat) -> Result<Vec<u8>, WireError> {
+    encode_message(&NetworkMessage::Fragility(packet.clone()), format)
+}
+
+/// Packet from a `Fragility` message, with the format it used
+pub fn decode(bytes: &[u8]) -> Result<(DataPacket, WireFormat), WireError> {
+    match decode_message(bytes)? {
+        (NetworkMessage::Fragility(packet), format) => Ok((packet, format)),
+        (message, _) => Err(WireError::NotAPacket(message.kind())),
+    }
+}
+
+fn serialize_into<T: Serialize>(bytes: &mut Vec<u8>, value: &T, format: WireFormat) -> Result<(), WireError> {
+    let encode_error = |reason: String| WireError::Encode { format, reason };
+    match format {
+        WireFormat::Json => serde_json::to_writer(bytes, value).map_err(|e| encode_error(e.to_string())),
+        WireFormat::Cbor => ciborium::into_writer(value, bytes).map_err(|e| encode_error(e.to_string())),
+        WireFormat::Bincode => bincode::serialize_into(bytes, value).map_err(|e| encode_error(e.to_string())),
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(body: &[u8], format: WireFormat) -> Result<T, WireError> {
+    let decode_error = |reason: String| WireError::Decode { format, reason };
+    match format {
+        WireFormat::Json => serde_json::from_slice(body).map_err(|e| decode_error(e.to_string())),
+        WireFormat::Cbor => ciborium::from_reader(body).map_err(|e| decode_error(e.to_string())),
+        WireFormat::Bincode => bincode::deserialize(body).map_err(|e| decode_error(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::testing::fixtures;
+
+    const FORMATS: [WireFormat; 3] = [WireFormat::Json, WireFormat::Cbor, WireFormat::Bincode];
+
+    fn packet() -> DataPacket {
+        fixtures::packet()
+            .source("12D3KooWD3eckifWpRn9wQpMG9R9hX3sD158z7EqHWmweQAJU5SA")
+            .tier1_capital(8_002.5)
+            .fragility(18.479_262_672_811_06)
+            .signature((0..64).map(|i| (i * 37) as u8).collect())
+            .proof((0..192).map(|i| (i * 11) as u8).collect())
+            .period(96)
+            .build()
+    }
+
+    #[test]
+    fn test_round_trip_every_format() {
+        let packet = packet();
+        for format in FORMATS {
+            let bytes = encode(&packet, format).unwrap();
+            assert_eq!(&bytes[..2], &[format.tag(), SCHEMA_VERSION]);
+            let (decoded, used) = decode(&bytes).unwrap();
+            assert_eq!(used, format);
+            assert_eq!(decoded.signing_bytes(), packet.signing_bytes());
+            assert_eq!(decoded.signature, packet.signature);
+            assert_eq!(decoded.proof, packet.proof);
+            assert_eq!(decoded.period, packet.period);
+        }
+    }
+
+    #[test]
+    fn test_binary_formats_smaller_than_json() {
+        let packet = packet();
+        let json = encode(&packet, WireFormat::Json).unwrap().len();
+        let cbor = encode(&packet, WireFormat::Cbor).unwrap().len();
+        let bincode = encode(&packet, WireFormat::Bincode).unwrap().len();
+        // Byte arrays dominate, and JSON spells each byte out as a number
+        assert!(cbor * 4 < json * 3, "CBOR {} bytes vs JSON {}", cbor, json);
+        assert!(bincode < cbor, "bincode {} bytes vs CBOR {}", bincode, cbor);
+    }
+
+    #[test]
+    fn test_binary_formats_carry_nan() {
+        let nan = DataPacket { fragility: f64::NAN, ..packet() };
+        for format in [WireFormat::Cbor, WireFormat::Bincode] {
+            let (decoded, _) = decode(&encode(&nan, format).unwrap()).unwrap();
+            assert!(decoded.fragility.is_nan());
+        }
+        let json = encode(&nan, WireFormat::Json).unwrap();
+        assert!(matches!(decode(&json), Err(WireError::Decode { format: WireFormat::Json, .. })));
+    }
+
+    #[test]
+    fn test_legacy_bare_packets_read_as_fragility() {
+        let packet = packet();
+        for format in FORMATS {
+            let mut legacy = vec![format.tag(), LEGACY_SCHEMA_VERSION];
+            serialize_into(&mut legacy, &packet, format).unwrap();
+            let (decoded, used) = decode(&legacy).unwrap();
+            assert_eq!(used, format);
+            assert_eq!(decoded.signing_bytes(), packet.signing_bytes());
+            assert_eq!(decoded.proof, packet.proof);
+        }
+    }
+
+    #[test]
+    fn test_other_kinds_are_not_packets() {
+        let alert = NetworkMessage::Alert {
+            severity: crate::network::message::Severity::Warning,
+            source: packet().source,
+            text: "liquidity thinning".to_string(),
+            fragility: None,
+            expires_at: None,
+        };
+        let bytes = encode_message(&alert, WireFormat::Cbor).unwrap();
+        assert_eq!(decode(&bytes).unwrap_err(), WireError::NotAPacket(MessageKind::Alert));
+        assert_eq!(decode_message(&bytes).unwrap().0.kind(), MessageKind::Alert);
+    }
+
+    #[test]
+    fn test_large_bodies_compressed_transparently() {
+        // A few hundred KB of proof bytes, repetitive as serialized curve points are not
+        let large = DataPacket { proof: Some((0..400_000).map(|i| (i % 251) as u8).collect()), ..packet() };
+        let compression = Some(Compression::default());
+        for format in FORMATS {
+            let plain = encode(&large, format).unwrap();
+            let message = NetworkMessage::Fragility(large.clone());
+            let compressed = encode_message_with(&message, format, compression).unwrap();
+            assert_eq!(compressed[0], format.tag() | COMPRESSED_FLAG);
+            assert!(compressed.len() * 10 < plain.len(), "{} compressed to {} of {}", format, compressed.len(), plain.len());
+            let (decoded, used) = decode(&compressed).unwrap();
+            assert_eq!(used, format);
+            assert_eq!(decoded.proof, large.proof);
+            assert_eq!(decoded.signing_bytes(), large.signing_bytes());
+
+            // Small bodies stay as they were
+            let small = encode_message_with(&NetworkMessage::Fragility(packet()), format, compression).unwrap();
+            assert_eq!(small, encode(&packet(), format).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decompression_bomb_rejected() {
+        // 4 MiB of zeros compresses to a few hundred bytes
+        let bomb = zstd::bulk::compress(&vec![0u8; 4 << 20], 19).unwrap();
+        assert!(bomb.len() < 1024);
+        let mut bytes = vec![WireFormat::Json.tag() | COMPRESSED_FLAG, SCHEMA_VERSION];
+        bytes.extend_from_slice(&bomb);
+        assert_eq!(decode_message_limited(&bytes, 1 << 20).unwrap_err(), WireError::TooLarge { limit: 1 << 20 });
+        // Under a large enough limit it is merely not JSON
+        assert!(matches!(decode_message_limited(&bytes, 8 << 20), Err(WireError::Decode { .. })));
+        // Not zstd at all
+        let garbage = [WireFormat::Cbor.tag() | COMPRESSED_FLAG, SCHEMA_VERSION, 1, 2, 3];
+        assert!(matches!(decode_message(&garbage), Err(WireError::Decode { format: WireFormat::Cbor, .. })));
+    }
+
+    #[test]
+    fn test_bad_headers_rejected() {
+        let mut bytes = encode(&packet(), WireFormat::Cbor).unwrap();
+        assert_eq!(decode(&bytes[..1]).unwrap_err(), WireError::Truncated);
+        bytes[1] = SCHEMA_VERSION + 1;
+        assert_eq!(decode(&bytes).unwrap_err(), WireError::UnsupportedVersion(SCHEMA_VERSION + 1));
+        bytes[0] = 9;
+        assert_eq!(decode(&bytes).unwrap_err(), WireError::UnknownFormat(9));
+        // A body in another format than its tag says
+        bytes[0] = WireFormat::Bincode.tag();
+        bytes[1] = SCHEMA_VERSION;
+        assert!(matches!(decode(&bytes), Err(WireError::Decode { format: WireFormat::Bincode, .. })));
+    }
+}
@@ -0,0 +1,393 @@
+//! Arkworks Backend
+//!
+//! `ArkworksProver` proves `FragilityCircuit` with arkworks Groth16 over
+//! BN254, the curve on-chain verifiers support. The circuit is synthesized
+//! over `Bn254Scalar`, an `ff` field with the BN254 scalar modulus, and each
+//! variable and constraint is forwarded into an arkworks constraint system.
+//! Gadgets, fixed-point codec, and witness are therefore shared with the
+//! bellman backend, and both accept exactly the same states.
+//!
+//! The state commitment is the same MiMC chain evaluated in the BN254 field,
+//! so it differs from `state_commitment`. Proofs and keys are serialized in
+//! arkworks' compressed form. Only compiled with the `backend-arkworks`
+//! feature.
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField as _;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{self, ConstraintSynthesizer, ConstraintSystemRef};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use bellman::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use ff::PrimeField;
+use rand::rngs::OsRng;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::proofs::backend::ProvingBackend;
+use crate::proofs::circuit::{commit_units, FragilityCircuit};
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::envelope::{BackendId, CircuitId, ProofEnvelope};
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::{mimc_constants, CircuitField};
+
+/// BN254 scalar field as an `ff` field, for synthesizing the gadgets
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617"]
+#[PrimeFieldGenerator = "5"]
+#[PrimeFieldReprEndianness = "little"]
+pub struct Bn254Scalar([u64; 4]);
+
+impl CircuitField for Bn254Scalar {
+    fn mimc_constants() -> &'static [Self] {
+        static CONSTANTS: OnceLock<Vec<Bn254Scalar>> = OnceLock::new();
+        CONSTANTS.get_or_init(mimc_constants)
+    }
+}
+
+/// The same element as an arkworks field element
+pub(crate) fn to_ark(scalar: Bn254Scalar) -> Fr {
+    Fr::from_le_bytes_mod_order(scalar.to_repr().as_ref())
+}
+
+fn from_ark_error(e: r1cs::SynthesisError) -> SynthesisError {
+    match e {
+        r1cs::SynthesisError::AssignmentMissing => SynthesisError::AssignmentMissing,
+        r1cs::SynthesisError::DivisionByZero => SynthesisError::DivisionByZero,
+        r1cs::SynthesisError::PolynomialDegreeTooLarge => SynthesisError::PolynomialDegreeTooLarge,
+        r1cs::SynthesisError::UnexpectedIdentity => SynthesisError::UnexpectedIdentity,
+        _ => SynthesisError::Unsatisfiable,
+    }
+}
+
+fn to_ark_error(e: SynthesisError) -> r1cs::SynthesisError {
+    match e {
+        SynthesisError::AssignmentMissing => r1cs::SynthesisError::AssignmentMissing,
+        SynthesisError::DivisionByZero => r1cs::SynthesisError::DivisionByZero,
+        SynthesisError::PolynomialDegreeTooLarge => r1cs::SynthesisError::PolynomialDegreeTooLarge,
+        SynthesisError::UnexpectedIdentity => r1cs::SynthesisError::UnexpectedIdentity,
+        _ => r1cs::SynthesisError::Unsatisfiable,
+    }
+}
+
+fn invalid_proof(e: impl std::fmt::Display) -> ProofError {
+    ProofError::InvalidProof { reason: e.to_string() }
+}
+
+/// Bellman constraint system forwarding into an arkworks one
+struct Bridge {
+    cs: ConstraintSystemRef<Fr>,
+    /// Instance variables by bellman input index; input 0 is the constant one
+    inputs: Vec<r1cs::Variable>,
+    aux: Vec<r1cs::Variable>,
+    /// First failure from `enforce`, which cannot return one
+    error: Option<r1cs::SynthesisError>,
+}
+
+impl Bridge {
+    fn variable(&self, var: Variable) -> r1cs::Variable {
+        match var.get_unchecked() {
+            Index::Input(i) => self.inputs[i],
+            Index::Aux(i) => self.aux[i],
+        }
+    }
+
+    fn lc(&self, lc: LinearCombination<Bn254Scalar>) -> r1cs::LinearCombination<Fr> {
+        let mut terms = r1cs::LinearCombination(
+            lc.as_ref().iter().map(|&(var, coeff)| (to_ark(coeff), self.variable(var))).collect(),
+        );
+        terms.compactify();
+        terms
+    }
+}
+
+impl ConstraintSystem<Bn254Scalar> for Bridge {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Bn254Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let var = self
+            .cs
+            .new_witness_variable(|| f().map(to_ark).map_err(to_ark_error))
+            .map_err(from_ark_error)?;
+        self.aux.push(var);
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Bn254Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let var = self
+            .cs
+            .new_input_variable(|| f().map(to_ark).map_err(to_ark_error))
+            .map_err(from_ark_error)?;
+        self.inputs.push(var);
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Bn254Scalar>) -> LinearCombination<Bn254Scalar>,
+        LB: FnOnce(LinearCombination<Bn254Scalar>) -> LinearCombination<Bn254Scalar>,
+        LC: FnOnce(LinearCombination<Bn254Scalar>) -> LinearCombination<Bn254Scalar>,
+    {
+        let a = self.lc(a(LinearCombination::zero()));
+        let b = self.lc(b(LinearCombination::zero()));
+        let c = self.lc(c(LinearCombination::zero()));
+        if let Err(e) = self.cs.enforce_constraint(a, b, c) {
+            self.error.get_or_insert(e);
+        }
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Bellman circuit over `Bn254Scalar`, synthesized through a `Bridge`
+struct Bridged<C>(C);
+
+impl<C: Circuit<Bn254Scalar>> ConstraintSynthesizer<Fr> for Bridged<C> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), r1cs::SynthesisError> {
+        let mut bridge = Bridge {
+            cs,
+            inputs: vec![r1cs::Variable::One],
+            aux: Vec::new(),
+            error: None,
+        };
+        self.0.synthesize(&mut bridge).map_err(to_ark_error)?;
+        bridge.error.map_or(Ok(()), Err)
+    }
+}
+
+/// `state_commitment` of `state` in the BN254 field
+fn state_commitment(state: &BankState) -> Result<Bn254Scalar, ProofError> {
+    Ok(commit_units([
+        FixedPoint::to_units(state.tier1_capital)?,
+        FixedPoint::to_units(state.total_assets)?,
+        FixedPoint::to_units(state.liquidity_coverage)?,
+        FixedPoint::to_units(state.entropy_index)?,
+    ]))
+}
+
+/// Fragility prover on arkworks Groth16 over BN254
+pub struct ArkworksProver {
+    config: LagrangianConfig,
+    pk: ProvingKey<Bn254>,
+    pvk: PreparedVerifyingKey<Bn254>,
+}
+
+impl ArkworksProver {
+    /// Prove as with `ProvingBackend::prove`, bound to reporting `period`
+    ///
+    /// Period 0 is the unbound proof.
+    pub fn prove_for_period(
+        &self,
+        state: &BankState,
+        fragility_score: f64,
+        period: u64,
+    ) -> Result<Proof<Bn254>, ProofError> {
+        let circuit = FragilityCircuit {
+            period: Some(Bn254Scalar::from(period)),
+            ..FragilityCircuit::generate_witness(state, fragility_score, self.config.clone())?
+        };
+        Groth16::<Bn254>::prove(&self.pk, Bridged(circuit), &mut OsRng).map_err(|e| from_ark_error(e).into())
+    }
+
+    /// Unprepared verifying key
+    pub(crate) fn groth16_vk(&self) -> &VerifyingKey<Bn254> {
+        &self.pk.vk
+    }
+
+    /// Compressed verifying key, for deploying an on-chain verifier
+    pub fn export_verifying_key(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.pk
+            .vk
+            .serialize_compressed(&mut bytes)
+            .expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Compressed proof bytes, as carried in envelopes
+    pub fn serialize_proof(proof: &Proof<Bn254>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Decode bytes written by `serialize_proof`
+    pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bn254>, ProofError> {
+        Proof::deserialize_compressed(bytes).map_err(invalid_proof)
+    }
+}
+
+impl ProvingBackend for ArkworksProver {
+    type Proof = Proof<Bn254>;
+    type VerifyingKey = PreparedVerifyingKey<Bn254>;
+    const BACKEND: BackendId = BackendId::Arkworks;
+
+    fn setup() -> Result<Self, ProofError> {
+        let config = LagrangianConfig::default();
+        let blank = FragilityCircuit::<Bn254Scalar>::blank(config.clone());
+        let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(Bridged(blank), &mut OsRng).map_err(from_ark_error)?;
+        let pvk = Groth16::<Bn254>::process_vk(&vk).map_err(from_ark_error)?;
+        Ok(Self { config, pk, pvk })
+    }
+
+    fn verifying_key(&self) -> &PreparedVerifyingKey<Bn254> {
+        &self.pvk
+    }
+
+    fn prove(&self, state: &BankState, fragility_score: f64) -> Result<Proof<Bn254>, ProofError> {
+        self.prove_for_period(state, fragility_score, 0)
+    }
+
+    fn verify(
+        vk: &PreparedVerifyingKey<Bn254>,
+        proof: &Proof<Bn254>,
+        state: &BankState,
+        fragility_score: f64,
+    ) -> Result<bool, ProofError> {
+        let inputs = [
+            Fr::from(FixedPoint::to_units(fragility_score)?),
+            to_ark(state_commitment(state)?),
+            Fr::from(0u64),
+        ];
+        Groth16::<Bn254>::verify_with_processed_vk(vk, &inputs, proof).map_err(|e| from_ark_error(e).into())
+    }
+
+    fn prove_enveloped(
+        &self,
+        state: &BankState,
+        fragility_score: f64,
+        prover_id: &str,
+        period: Option<u64>,
+    ) -> Result<ProofEnvelope, ProofError> {
+        if period == Some(0) {
+            return Err(ProofError::PublicInputMismatch {
+                reason: "period 0 is reserved for unbound proofs".to_string(),
+            });
+        }
+        let started = Instant::now();
+        let proof = self.prove_for_period(state, fragility_score, period.unwrap_or(0))?;
+        let proving_ms = started.elapsed().as_millis() as u64;
+
+        let mut envelope = ProofEnvelope::wrap(
+            BackendId::Arkworks,
+            CircuitId::Fragility,
+            Self::serialize_proof(&proof),
+            vec![FixedPoint::to_units(fragility_score)?],
+            Some(state_commitment(state)?.to_repr().0),
+            prover_id,
+        );
+        envelope.period = period;
+        envelope.proving_ms = Some(proving_ms);
+        Ok(envelope)
+    }
+
+    fn verify_envelope(
+        vk: &PreparedVerifyingKey<Bn254>,
+        envelope: &ProofEnvelope,
+        expected_period: Option<u64>,
+    ) -> Result<bool, ProofError> {
+        envelope.check_backend(BackendId::Arkworks)?;
+        envelope.check_version()?;
+        if envelope.circuit != CircuitId::Fragility {
+            return Err(ProofError::PublicInputMismatch {
+                reason: format!("{:?} proofs have no BN254 circuit", envelope.circuit),
+            });
+        }
+        envelope.check_identity()?;
+        if envelope.period != expected_period {
+            return Err(ProofError::PeriodMismatch {
+                expected: expected_period,
+                found: envelope.period,
+            });
+        }
+        let inputs: Vec<Fr> = envelope
+            .public_scalars_in::<Bn254Scalar>()?
+            .into_iter()
+            .map(to_ark)
+            .collect();
+        let proof = Self::deserialize_proof(&envelope.proof)?;
+        Groth16::<Bn254>::verify_with_processed_vk(vk, &inputs, &proof).map_err(|e| from_ark_error(e).into())
+    }
+}
+
+/// BN254 prover shared by tests, set up once
+#[cfg(test)]
+pub(crate) fn test_ark_prover() -> &'static ArkworksProver {
+    static PROVER: OnceLock<ArkworksProver> = OnceLock::new();
+    PROVER.get_or_init(|| ArkworksProver::setup().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proofs::backend::round_trip;
+    use crate::proofs::prover::test_prover;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    #[test]
+    fn test_both_backends_prove_the_same_state() {
+        let bellman = round_trip(test_prover());
+        let arkworks = round_trip(test_ark_prover());
+        assert_eq!(bellman.public_inputs, arkworks.public_inputs);
+        // Same MiMC chain, different field
+        assert_ne!(bellman.commitment, arkworks.commitment);
+
+        // Neither backend accepts the other's envelopes
+        assert!(matches!(
+            ArkworksProver::verify_envelope(test_ark_prover().verifying_key(), &bellman, Some(96)),
+            Err(ProofError::BackendMismatch { expected: BackendId::Arkworks, found: BackendId::Bellman })
+        ));
+        assert!(matches!(
+            test_prover().verify_envelope(&arkworks, Some(96)),
+            Err(ProofError::BackendMismatch { expected: BackendId::Bellman, found: BackendId::Arkworks })
+        ));
+        let decoded = ProofEnvelope::from_bytes(&arkworks.to_bytes()).unwrap();
+        assert!(ArkworksProver::verify_envelope(test_ark_prover().verifying_key(), &decoded, Some(96)).unwrap());
+    }
+
+    #[test]
+    fn test_bridged_circuit_matches_bellman_shape() {
+        let state = BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let fragility = crate::proofs::circuit::reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        let circuit = FragilityCircuit::<Bn254Scalar>::generate_witness(&state, fragility, LagrangianConfig::default()).unwrap();
+
+        let mut bellman = TestConstraintSystem::new();
+        circuit.clone().synthesize(&mut bellman).unwrap();
+        assert!(bellman.is_satisfied());
+
+        let ark = r1cs::ConstraintSystem::<Fr>::new_ref();
+        Bridged(circuit).generate_constraints(ark.clone()).unwrap();
+        assert!(ark.is_satisfied().unwrap());
+        assert_eq!(ark.num_constraints(), bellman.num_constraints());
+        assert_eq!(ark.num_instance_variables(), bellman.num_inputs());
+    }
+}
@@ -0,0 +1,168 @@
+//! Proving Backends
+//!
+//! `ProvingBackend` abstracts setup, proving, and verification of fragility
+//! proofs over each backend's own proof and key types. `FragilityProver`,
+//! bellman Groth16 over BLS12-381, is the default. With the
+//! `backend-arkworks` feature, `ArkworksProver` proves the same
+//! `FragilityCircuit` with arkworks Groth16 over BN254, the curve on-chain
+//! verifiers support.
+//!
+//! Both backends synthesize the circuit from the same fixed-point witness,
+//! so they accept exactly the same states and scores. Their proofs, keys,
+//! and commitments are not interchangeable: envelopes record the
+//! `BackendId`, and each backend refuses envelopes from the other with
+//! `ProofError::BackendMismatch`.
+
+use bellman::groth16::Proof;
+use bls12_381::Bls12;
+
+use crate::core::lagrangian::BankState;
+use crate::proofs::envelope::{BackendId, ProofEnvelope};
+use crate::proofs::error::ProofError;
+use crate::proofs::prover::FragilityProver;
+use crate::proofs::verifier::FragilityVerifier;
+
+/// Groth16 implementation that can prove and verify fragility scores
+pub trait ProvingBackend: Sized {
+    /// Proof made by the backend
+    type Proof;
+    /// Key checking the backend's proofs
+    type VerifyingKey;
+    /// Backend recorded in the envelopes this backend makes
+    const BACKEND: BackendId;
+
+    /// Generate fresh fragility parameters (trusted setup)
+    fn setup() -> Result<Self, ProofError>;
+
+    /// Key for verifying this backend's proofs
+    fn verifying_key(&self) -> &Self::VerifyingKey;
+
+    /// Prove `fragility_score` is the circuit score of `state`
+    ///
+    /// Fails with `ProofError::InvalidWitness` for an invalid state or a
+    /// score other than `reference_fragility(state)`.
+    fn prove(&self, state: &BankState, fragility_score: f64) -> Result<Self::Proof, ProofError>;
+
+    /// Check a proof of `fragility_score` for the published `state`
+    fn verify(
+        vk: &Self::VerifyingKey,
+        proof: &Self::Proof,
+        state: &BankState,
+        fragility_score: f64,
+    ) -> Result<bool, ProofError>;
+
+    /// Prove as with `prove` and wrap the proof in an unsigned envelope
+    ///
+    /// `period` is the reporting period the proof is bound to, if any.
+    fn prove_enveloped(
+        &self,
+        state: &BankState,
+        fragility_score: f64,
+        prover_id: &str,
+        period: Option<u64>,
+    ) -> Result<ProofEnvelope, ProofError>;
+
+    /// Check an envelope made by this backend against its own public inputs
+    fn verify_envelope(
+        vk: &Self::VerifyingKey,
+        envelope: &ProofEnvelope,
+        expected_period: Option<u64>,
+    ) -> Result<bool, ProofError>;
+}
+
+impl ProvingBackend for FragilityProver {
+    type Proof = Proof<Bls12>;
+    type VerifyingKey = FragilityVerifier;
+    const BACKEND: BackendId = BackendId::Bellman;
+
+    fn setup() -> Result<Self, ProofError> {
+        FragilityProver::try_setup()
+    }
+
+    fn verifying_key(&self) -> &FragilityVerifier {
+        self.verifier()
+    }
+
+    fn prove(&self, state: &BankState, fragility_score: f64) -> Result<Proof<Bls12>, ProofError> {
+        FragilityProver::prove(self, state, fragility_score)
+    }
+
+    fn verify(
+        vk: &FragilityVerifier,
+        proof: &Proof<Bls12>,
+        state: &BankState,
+        fragility_score: f64,
+    ) -> Result<bool, ProofError> {
+        vk.verify(proof, state, fragility_score)
+    }
+
+    fn prove_enveloped(
+        &self,
+        state: &BankState,
+        fragility_score: f64,
+        prover_id: &str,
+        period: Option<u64>,
+    ) -> Result<ProofEnvelope, ProofError> {
+        FragilityProver::prove_enveloped(self, state, fragility_score, prover_id, period)
+    }
+
+    fn verify_envelope(
+        vk: &FragilityVerifier,
+        envelope: &ProofEnvelope,
+        expected_period: Option<u64>,
+    ) -> Result<bool, ProofError> {
+        vk.verify_envelope(envelope, expected_period)
+    }
+}
+
+/// Prove and verify a fixed state through the trait alone
+///
+/// Shared by the tests of every backend; returns an envelope bound to
+/// period 96.
+#[cfg(test)]
+pub(crate) fn round_trip<B: ProvingBackend>(backend: &B) -> ProofEnvelope {
+    use crate::core::lagrangian::LagrangianConfig;
+    use crate::proofs::circuit::reference_fragility;
+
+    let state = BankState {
+        tier1_capital: 8_002.5,
+        total_assets: 100_000.0,
+        liquidity_coverage: 1.2,
+        entropy_index: 2.0,
+    };
+    let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+    let vk = backend.verifying_key();
+
+    let proof = backend.prove(&state, fragility).unwrap();
+    assert!(B::verify(vk, &proof, &state, fragility).unwrap());
+    assert!(!B::verify(vk, &proof, &state, fragility + 0.5).unwrap());
+    let moved = BankState { total_assets: 100_001.0, ..state.clone() };
+    assert!(!B::verify(vk, &proof, &moved, fragility).unwrap());
+
+    let envelope = backend.prove_enveloped(&state, fragility, "12D3KooWbackend", Some(96)).unwrap();
+    assert_eq!(envelope.backend, B::BACKEND);
+    assert!(B::verify_envelope(vk, &envelope, Some(96)).unwrap());
+    assert!(matches!(
+        B::verify_envelope(vk, &envelope, Some(97)),
+        Err(ProofError::PeriodMismatch { .. })
+    ));
+    envelope
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proofs::prover::test_prover;
+
+    #[test]
+    fn test_bellman_backend_round_trip() {
+        let envelope = round_trip(test_prover());
+
+        // Relabelled as another backend's proof, it is refused before decoding
+        let relabelled = ProofEnvelope { backend: BackendId::Arkworks, ..envelope };
+        assert!(matches!(
+            test_prover().verify_envelope(&relabelled, Some(96)),
+            Err(ProofError::BackendMismatch { expected: BackendId::Bellman, found: BackendId::Arkworks })
+        ));
+    }
+}
@@ -0,0 +1,279 @@
+//! Balance Sheet Circuit
+//!
+//! Groth16 circuit attesting that a committed bank state balances, `assets =
+//! liabilities + equity`, with all three amounts non-negative. Far cheaper
+//! than the fragility proof, it lets gossip peers reject fabricated states
+//! with negative equity before anything heavier runs.
+//!
+//! Equity is the state's Tier 1 capital and assets its total assets;
+//! liabilities are the difference, the one figure `BankState` does not
+//! carry. All three are private and range-checked to 64 bits, so none can be
+//! a "negative" field element. The full state is hashed as in
+//! `FragilityCircuit`, and `state_commitment` and the reporting period are
+//! the only public inputs.
+
+use bellman::groth16::{
+    create_random_proof, generate_random_parameters, prepare_verifying_key, Parameters, PreparedVerifyingKey, Proof,
+};
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError};
+use bls12_381::{Bls12, Scalar};
+use rand::rngs::OsRng;
+
+use crate::core::lagrangian::BankState;
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::{mimc_compress, mimc_hash, range_check, scalar_from_i128};
+use crate::proofs::verifier::check_proof;
+
+/// Bits covering 64-bit encoded amounts
+const AMOUNT_BITS: usize = 64;
+
+/// Private witness for the balance sheet circuit
+///
+/// Amounts are signed so the circuit, not the encoding, is what rejects a
+/// negative one; `new` only builds balanced, non-negative witnesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceSheetWitness {
+    assets: i128,
+    liabilities: i128,
+    equity: i128,
+    liquidity_coverage: i128,
+    entropy_index: i128,
+}
+
+impl BalanceSheetWitness {
+    /// Encode `state`; errors name the field, or report equity above assets
+    pub fn new(state: &BankState) -> Result<Self, ProofError> {
+        let encode = |name: &str, value: f64| {
+            FixedPoint::to_units(value).map(i128::from).map_err(|e| ProofError::InvalidWitness {
+                reason: format!("{}: {}", name, e),
+            })
+        };
+        let equity = encode("tier1_capital", state.tier1_capital)?;
+        let assets = encode("total_assets", state.total_assets)?;
+        if equity > assets {
+            return Err(ProofError::InvalidWitness {
+                reason: format!(
+                    "tier1_capital {} exceeds total_assets {}, leaving negative liabilities",
+                    state.tier1_capital, state.total_assets
+                ),
+            });
+        }
+        Ok(Self {
+            assets,
+            liabilities: assets - equity,
+            equity,
+            liquidity_coverage: encode("liquidity_coverage", state.liquidity_coverage)?,
+            entropy_index: encode("entropy_index", state.entropy_index)?,
+        })
+    }
+
+    /// Liabilities implied by the state, in fixed-point units
+    pub fn liabilities_units(&self) -> u64 {
+        self.liabilities as u64
+    }
+
+    /// State in `state_commitment` order
+    fn committed(&self) -> [Scalar; 4] {
+        [self.equity, self.assets, self.liquidity_coverage, self.entropy_index].map(scalar_from_i128::<Scalar>)
+    }
+}
+
+/// Circuit proving a committed state balances with non-negative amounts
+#[derive(Clone)]
+pub struct BalanceSheetCircuit {
+    /// Private: Balance sheet and remaining state (`None` during setup)
+    pub witness: Option<BalanceSheetWitness>,
+    /// Public: `state_commitment` of the witnessed state
+    pub commitment: Option<Scalar>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<Scalar>,
+}
+
+impl BalanceSheetCircuit {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank() -> Self {
+        Self {
+            witness: None,
+            commitment: None,
+            period: None,
+        }
+    }
+
+    /// Assign the witness and commitment of `state`, unbound to a period
+    pub fn generate_witness(state: &BankState) -> Result<Self, ProofError> {
+        let witness = BalanceSheetWitness::new(state)?;
+        Ok(Self {
+            witness: Some(witness),
+            commitment: Some(mimc_hash(&witness.committed())),
+            period: Some(Scalar::zero()),
+        })
+    }
+}
+
+impl Circuit<Scalar> for BalanceSheetCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        let w = self.witness;
+
+        // Allocate public inputs
+        let commitment = cs.alloc_input(
+            || "commitment",
+            || self.commitment.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let values = w.map(|w| w.committed());
+        let mut state = Vec::with_capacity(4);
+        for (i, name) in ["equity", "assets", "liquidity_coverage", "entropy_index"].into_iter().enumerate() {
+            state.push(cs.alloc(|| name, || values.map(|v| v[i]).ok_or(SynthesisError::AssignmentMissing))?);
+        }
+        let (equity, assets) = (state[0], state[1]);
+        let liabilities_value = w.map(|w| scalar_from_i128::<Scalar>(w.liabilities));
+        let liabilities = cs.alloc(
+            || "liabilities",
+            || liabilities_value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+
+        // Assets = liabilities + equity, each in [0, 2^64)
+        cs.enforce(
+            || "balance sheet identity",
+            |lc| lc + liabilities + equity,
+            |lc| lc + CS::one(),
+            |lc| lc + assets,
+        );
+        for (name, var, value) in [
+            ("assets", assets, values.map(|v| v[1])),
+            ("liabilities", liabilities, liabilities_value),
+            ("equity", equity, values.map(|v| v[0])),
+        ] {
+            range_check(
+                cs.namespace(|| format!("{} range", name)),
+                LinearCombination::zero() + var,
+                value,
+                AMOUNT_BITS,
+            )?;
+        }
+
+        // Chain the state through the MiMC compression
+        let mut digest = LinearCombination::zero();
+        let mut digest_value = w.map(|_| Scalar::zero());
+        for (i, var) in state.into_iter().enumerate() {
+            (digest, digest_value) = mimc_compress(
+                cs.namespace(|| format!("commitment {}", i)),
+                &digest,
+                digest_value,
+                &(LinearCombination::zero() + var),
+                values.map(|v| v[i]),
+            )?;
+        }
+        cs.enforce(|| "commitment matches", |_| digest, |lc| lc + CS::one(), |lc| lc + commitment);
+        Ok(())
+    }
+}
+
+/// Prover and verifier for `BalanceSheetCircuit`
+///
+/// Kept apart from `FragilityProver` so peers that only screen gossip for
+/// fabricated states need not hold the fragility parameters.
+pub struct BalanceSheetProver {
+    params: Parameters<Bls12>,
+    pvk: PreparedVerifyingKey<Bls12>,
+}
+
+impl BalanceSheetProver {
+    /// Generate fresh parameters (trusted setup)
+    pub fn setup() -> Result<Self, ProofError> {
+        let params = generate_random_parameters::<Bls12, _, _>(BalanceSheetCircuit::blank(), &mut OsRng)?;
+        let pvk = prepare_verifying_key(&params.vk);
+        Ok(Self { params, pvk })
+    }
+
+    /// Prove `state` balances with non-negative assets, liabilities, and
+    /// equity
+    ///
+    /// The proof reveals only `state_commitment(state)`, which the prover
+    /// publishes alongside it. Fails with `ProofError::InvalidWitness` for a
+    /// state that cannot be encoded or whose capital exceeds its assets.
+    pub fn prove_balance_sheet(&self, state: &BankState) -> Result<Proof<Bls12>, ProofError> {
+        let circuit = BalanceSheetCircuit::generate_witness(state)?;
+        Ok(create_random_proof(circuit, &self.params, &mut OsRng)?)
+    }
+
+    /// Verify a proof from `prove_balance_sheet` for the published
+    /// `commitment`
+    pub fn verify_balance_sheet(&self, proof: &Proof<Bls12>, commitment: Scalar) -> Result<bool, ProofError> {
+        check_proof(&self.pvk, proof, &[commitment, Scalar::zero()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proofs::circuit::state_commitment;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    fn state() -> BankState {
+        BankState {
+            tier1_capital: 8_400.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    fn satisfied(witness: BalanceSheetWitness) -> bool {
+        let circuit = BalanceSheetCircuit {
+            witness: Some(witness),
+            commitment: Some(mimc_hash(&witness.committed())),
+            period: Some(Scalar::zero()),
+        };
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_consistent_state_proves_and_verifies() {
+        let witness = BalanceSheetWitness::new(&state()).unwrap();
+        assert_eq!(witness.liabilities_units(), 91_600 * FixedPoint::SCALE);
+        assert!(satisfied(witness));
+
+        let prover = BalanceSheetProver::setup().unwrap();
+        let proof = prover.prove_balance_sheet(&state()).unwrap();
+        let commitment = state_commitment(&state()).unwrap();
+        assert!(prover.verify_balance_sheet(&proof, commitment).unwrap());
+        let other = BankState { tier1_capital: 9_000.0, ..state() };
+        assert!(!prover.verify_balance_sheet(&proof, state_commitment(&other).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_inconsistent_balance_sheet_rejected() {
+        let witness = BalanceSheetWitness::new(&state()).unwrap();
+        // Liabilities that do not close the gap
+        assert!(!satisfied(BalanceSheetWitness { liabilities: witness.liabilities - 1, ..witness }));
+        assert!(!satisfied(BalanceSheetWitness { liabilities: witness.liabilities + 1, ..witness }));
+
+        let err = BalanceSheetWitness::new(&BankState { tier1_capital: 120_000.0, ..state() }).unwrap_err();
+        assert!(err.to_string().contains("exceeds total_assets"), "{}", err);
+    }
+
+    #[test]
+    fn test_negative_equity_witness_rejected() {
+        // Balances only because equity is a "negative" field element
+        let witness = BalanceSheetWitness::new(&state()).unwrap();
+        let negative = BalanceSheetWitness {
+            equity: -5_000 * FixedPoint::SCALE as i128,
+            liabilities: witness.assets + 5_000 * FixedPoint::SCALE as i128,
+            ..witness
+        };
+        assert!(!satisfied(negative));
+
+        let err = BalanceSheetWitness::new(&BankState { tier1_capital: -5_000.0, ..state() }).unwrap_err();
+        assert!(err.to_string().contains("tier1_capital"), "{}", err);
+    }
+}
@@ -0,0 +1,358 @@
+//! Proof Cache
+//!
+//! Reuses fragility proof envelopes for states that have not changed since
+//! they were last proven, so unchanged banks are not re-proven every
+//! reporting interval.
+//!
+//! Entries are keyed by `cache_key`, a SHA-256 over the circuit version and
+//! the fixed-point encoding of the state and claimed fragility. The cache is
+//! tied to one set of parameters through `FragilityProver::params_fingerprint`;
+//! proving with different parameters clears it.
+//!
+//! With persistence enabled, the cache is rewritten after every new proof.
+//! All integers are big-endian:
+//!
+//! | Field       | Encoding                                          |
+//! |-------------|---------------------------------------------------|
+//! | magic       | `OLPC`                                            |
+//! | version     | `u32` `VERIFYING_KEY_VERSION`                     |
+//! | params hash | 32 bytes                                          |
+//! | count       | `u32`                                             |
+//! | entries     | 32-byte key, `u32` length, `ProofEnvelope` bytes  |
+//!
+//! Entries are written least recently used first.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::lagrangian::BankState;
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::envelope::ProofEnvelope;
+use crate::proofs::error::ProofError;
+use crate::proofs::prover::FragilityProver;
+use crate::proofs::verifier::VERIFYING_KEY_VERSION;
+
+/// Leading bytes of a persisted proof cache
+const CACHE_MAGIC: &[u8; 4] = b"OLPC";
+
+/// Cache key for proving `state` with `fragility_score`
+///
+/// SHA-256 of `VERIFYING_KEY_VERSION` followed by the fixed-point units of
+/// tier 1 capital, total assets, liquidity coverage, entropy index, and the
+/// fragility score.
+pub fn cache_key(state: &BankState, fragility_score: f64) -> Result<[u8; 32], ProofError> {
+    let mut hasher = Sha256::new();
+    hasher.update(VERIFYING_KEY_VERSION.to_be_bytes());
+    for value in [
+        state.tier1_capital,
+        state.total_assets,
+        state.liquidity_coverage,
+        state.entropy_index,
+        fragility_score,
+    ] {
+        hasher.update(FixedPoint::to_units(value)?.to_be_bytes());
+    }
+    Ok(hasher.finalize().into())
+}
+
+struct Entry {
+    envelope: ProofEnvelope,
+    last_used: u64,
+}
+
+/// Least-recently-used cache of fragility proof envelopes
+pub struct ProofCache {
+    capacity: usize,
+    prover_id: String,
+    path: Option<PathBuf>,
+    /// Parameters the entries were proven with
+    params_fingerprint: Option<[u8; 32]>,
+    entries: HashMap<[u8; 32], Entry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl ProofCache {
+    /// In-memory cache holding up to `capacity` envelopes (at least one)
+    ///
+    /// `prover_id` is recorded in envelopes proven through the cache.
+    pub fn new(capacity: usize, prover_id: impl Into<String>) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            prover_id: prover_id.into(),
+            path: None,
+            params_fingerprint: None,
+            entries: HashMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Persist the cache at `path`, loading any entries already there
+    ///
+    /// Files from another circuit version, or that cannot be decoded, are
+    /// discarded: the cache only saves work and never holds the only copy
+    /// of anything.
+    pub fn with_persistence(mut self, path: impl AsRef<Path>) -> Result<Self, ProofError> {
+        let path = path.as_ref();
+        match fs::read(path) {
+            Ok(bytes) => {
+                if let Some((params_fingerprint, entries)) = decode(&bytes) {
+                    self.params_fingerprint = Some(params_fingerprint);
+                    for (key, envelope) in entries {
+                        self.insert(key, envelope);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(ProofError::ParameterIo(e)),
+        }
+        self.path = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Cached envelope for `state`, or a new proof from `prover`
+    ///
+    /// Proofs are not bound to a reporting period.
+    ///
+    /// Cached entries are only reused for the parameters of `prover`; a
+    /// prover with other parameters clears the cache first. New proofs are
+    /// persisted before returning when persistence is enabled.
+    pub fn get_or_prove(
+        &mut self,
+        state: &BankState,
+        fragility_score: f64,
+        prover: &FragilityProver,
+    ) -> Result<ProofEnvelope, ProofError> {
+        self.get_or_prove_with(state, fragility_score, prover.params_fingerprint(), |prover_id| {
+            prover.prove_enveloped(state, fragility_score, prover_id, None)
+        })
+    }
+
+    fn get_or_prove_with<F>(
+        &mut self,
+        state: &BankState,
+        fragility_score: f64,
+        params_fingerprint: [u8; 32],
+        prove: F,
+    ) -> Result<ProofEnvelope, ProofError>
+    where
+        F: FnOnce(&str) -> Result<ProofEnvelope, ProofError>,
+    {
+        if self.params_fingerprint != Some(params_fingerprint) {
+            self.entries.clear();
+            self.params_fingerprint = Some(params_fingerprint);
+        }
+
+        let key = cache_key(state, fragility_score)?;
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.last_used = self.tick;
+            self.hits += 1;
+            return Ok(entry.envelope.clone());
+        }
+
+        self.misses += 1;
+        let envelope = prove(&self.prover_id)?;
+        self.insert(key, envelope.clone());
+        self.persist()?;
+        Ok(envelope)
+    }
+
+    /// Add an entry, evicting the least recently used one when full
+    fn insert(&mut self, key: [u8; 32], envelope: ProofEnvelope) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, Entry { envelope, last_used: self.tick });
+    }
+
+    /// Write the cache to its file, if persistence is enabled
+    pub fn persist(&self) -> Result<(), ProofError> {
+        let (Some(path), Some(params_fingerprint)) = (&self.path, &self.params_fingerprint) else {
+            return Ok(());
+        };
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|(_, entry)| entry.last_used);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(CACHE_MAGIC);
+        bytes.extend_from_slice(&VERIFYING_KEY_VERSION.to_be_bytes());
+        bytes.extend_from_slice(params_fingerprint);
+        bytes.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for (key, entry) in entries {
+            let envelope = entry.envelope.to_bytes();
+            bytes.extend_from_slice(key);
+            bytes.extend_from_slice(&(envelope.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&envelope);
+        }
+
+        // Write to a sibling, then rename, so readers never see a partial file
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, &bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Number of cached envelopes
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache holds no envelopes
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that required a new proof
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+/// Cache key and envelope, as persisted
+type PersistedEntry = ([u8; 32], ProofEnvelope);
+
+/// Params hash and entries of a persisted cache, oldest first, or `None` if unusable
+fn decode(bytes: &[u8]) -> Option<([u8; 32], Vec<PersistedEntry>)> {
+    fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        if bytes.len() < n {
+            return None;
+        }
+        let (head, rest) = bytes.split_at(n);
+        *bytes = rest;
+        Some(head)
+    }
+    let mut rest = bytes;
+    let u32_be = |rest: &mut &[u8]| take(rest, 4).map(|b| u32::from_be_bytes(b.try_into().unwrap()));
+
+    if take(&mut rest, 4)? != CACHE_MAGIC || u32_be(&mut rest)? != VERIFYING_KEY_VERSION {
+        return None;
+    }
+    let params_fingerprint = take(&mut rest, 32)?.try_into().ok()?;
+    let count = u32_be(&mut rest)?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let key = take(&mut rest, 32)?.try_into().ok()?;
+        let len = u32_be(&mut rest)? as usize;
+        let envelope = ProofEnvelope::from_bytes(take(&mut rest, len)?).ok()?;
+        entries.push((key, envelope));
+    }
+    rest.is_empty().then_some((params_fingerprint, entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::LagrangianConfig;
+    use crate::proofs::circuit::reference_fragility;
+    use crate::proofs::prover::test_prover;
+    use std::cell::Cell;
+
+    fn state(tier1_capital: f64) -> (BankState, f64) {
+        let state = BankState {
+            tier1_capital,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        (state, fragility)
+    }
+
+    /// Prover closure that counts its invocations
+    fn counted<'a>(
+        proofs: &'a Cell<u32>,
+        state: &'a BankState,
+        fragility: f64,
+    ) -> impl FnOnce(&str) -> Result<ProofEnvelope, ProofError> + 'a {
+        move |prover_id| {
+            proofs.set(proofs.get() + 1);
+            test_prover().prove_enveloped(state, fragility, prover_id, None)
+        }
+    }
+
+    #[test]
+    fn test_identical_state_is_not_reproven() {
+        let prover = test_prover();
+        let proofs = Cell::new(0);
+        let mut cache = ProofCache::new(4, "12D3KooWcache");
+        let params = prover.params_fingerprint();
+        let (unchanged, f) = state(8_002.5);
+
+        let first = cache.get_or_prove_with(&unchanged, f, params, counted(&proofs, &unchanged, f)).unwrap();
+        let second = cache.get_or_prove_with(&unchanged, f, params, counted(&proofs, &unchanged, f)).unwrap();
+        assert_eq!(proofs.get(), 1);
+        assert_eq!(first, second);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert!(prover.verify_envelope(&second, None).unwrap());
+
+        let (modified, g) = state(9_000.0);
+        cache.get_or_prove_with(&modified, g, params, counted(&proofs, &modified, g)).unwrap();
+        assert_eq!(proofs.get(), 2);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[test]
+    fn test_eviction_and_params_invalidation() {
+        let (s, f) = state(8_002.5);
+        let envelope = test_prover().prove_enveloped(&s, f, "12D3KooWcache", None).unwrap();
+        let fake = |_: &str| Ok(envelope.clone());
+        let mut cache = ProofCache::new(2, "12D3KooWcache");
+        let params = [7u8; 32];
+        let (a, b, c) = (state(1_000.0), state(2_000.0), state(3_000.0));
+
+        cache.get_or_prove_with(&a.0, a.1, params, fake).unwrap();
+        cache.get_or_prove_with(&b.0, b.1, params, fake).unwrap();
+        cache.get_or_prove_with(&a.0, a.1, params, fake).unwrap();
+        // b is least recently used
+        cache.get_or_prove_with(&c.0, c.1, params, fake).unwrap();
+        cache.get_or_prove_with(&a.0, a.1, params, fake).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (2, 3));
+        cache.get_or_prove_with(&b.0, b.1, params, fake).unwrap();
+        assert_eq!(cache.misses(), 4);
+
+        // New parameters drop every entry
+        cache.get_or_prove_with(&a.0, a.1, [8u8; 32], fake).unwrap();
+        assert_eq!((cache.misses(), cache.len()), (5, 1));
+    }
+
+    #[test]
+    fn test_persisted_cache_round_trip() {
+        let prover = test_prover();
+        let path = std::env::temp_dir().join(format!("olo-proof-cache-{}.bin", std::process::id()));
+        let (s, f) = state(8_002.5);
+
+        let mut cache = ProofCache::new(4, "12D3KooWcache").with_persistence(&path).unwrap();
+        let envelope = cache.get_or_prove(&s, f, prover).unwrap();
+
+        let mut reloaded = ProofCache::new(4, "12D3KooWcache").with_persistence(&path).unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded.get_or_prove(&s, f, prover).unwrap(), envelope);
+        assert_eq!((reloaded.hits(), reloaded.misses()), (1, 0));
+
+        // Corrupt files start empty instead of failing
+        fs::write(&path, b"OLPC\0\0").unwrap();
+        assert!(ProofCache::new(4, "12D3KooWcache").with_persistence(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,358 @@
+//! Trusted Setup Ceremony
+//!
+//! Sequential multi-party contributions to Groth16 parameters, in the style
+//! of the phase-2 ceremony of Bowe, Gabizon, and Miers. Each contributor
+//! draws a secret factor `s`, multiplies `delta` by it, and divides the `h`
+//! and `l` queries by it. The result is published with a `ContributionHash`:
+//! a digest identifying the contribution and a Schnorr proof that the
+//! contributor knows `s`. Anyone holding the parameters before and after can
+//! check a contribution with `verify_contribution`; a `Transcript` records
+//! the whole chain.
+//!
+//! # Security model
+//!
+//! After contributions `s₁ … sₙ` the final `delta` is `δ₀·s₁·…·sₙ`, and
+//! forging a proof requires knowing it. As long as one contributor drew its
+//! factor honestly and destroyed it, nobody knows `delta`, including whoever
+//! ran the setup; the Schnorr proofs stop a later contributor from choosing
+//! `delta` outright to cancel the earlier ones.
+//!
+//! Only `delta` is randomized. The other setup secrets (`tau`, `alpha`,
+//! `beta`, `gamma`) are fixed by the initial parameters, and anyone knowing
+//! them can forge proofs regardless of this ceremony. Parameters from
+//! `generate_random_parameters` are therefore only as trustworthy as the
+//! machine that made them; production parameters must start from a
+//! multi-party phase-1 (powers of tau) that this module does not perform.
+//! Contributions are per circuit, so each circuit's parameters need their
+//! own chain.
+
+use bellman::groth16::{Parameters, VerifyingKey};
+use bls12_381::{pairing, Bls12, G1Affine, G1Projective, Scalar};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::proofs::error::ProofError;
+
+/// Domain separator for factors, challenges, and digests
+const CEREMONY_DOMAIN: &[u8] = b"olo-core/ceremony/v1";
+
+/// Public record of one contribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContributionHash {
+    /// SHA-256 over the `delta` change and the proof of knowledge
+    digest: [u8; 32],
+    /// Schnorr commitment `k·δ_before`
+    commitment: G1Affine,
+    /// Schnorr response `k + c·s`
+    response: Scalar,
+}
+
+impl ContributionHash {
+    /// Digest identifying the contribution
+    pub fn digest(&self) -> [u8; 32] {
+        self.digest
+    }
+}
+
+impl fmt::Display for ContributionHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.digest))
+    }
+}
+
+/// Parameters after one contribution, with its public record
+pub struct Contribution {
+    /// Randomized parameters, to pass to the next contributor
+    pub params: Parameters<Bls12>,
+    /// Record for `verify_contribution` and the transcript
+    pub hash: ContributionHash,
+}
+
+impl Contribution {
+    /// Contribute to `previous` with the contributor's `entropy`
+    ///
+    /// `entropy` is mixed with OS randomness, so weak entropy cannot make the
+    /// factor predictable. The factor is dropped on return; it must not be
+    /// derivable from anything kept.
+    pub fn new(previous: &Parameters<Bls12>, entropy: &[u8]) -> Self {
+        let s = secret_scalar(entropy);
+        let s_inv = s.invert().unwrap();
+
+        let delta_g1 = G1Affine::from(previous.vk.delta_g1 * s);
+        let delta_g2 = (previous.vk.delta_g2 * s).into();
+        let divide = |points: &[G1Affine]| {
+            let scaled: Vec<G1Projective> = points.iter().map(|p| p * s_inv).collect();
+            let mut affine = vec![G1Affine::identity(); scaled.len()];
+            G1Projective::batch_normalize(&scaled, &mut affine);
+            Arc::new(affine)
+        };
+        let params = Parameters {
+            vk: VerifyingKey {
+                delta_g1,
+                delta_g2,
+                ..previous.vk.clone()
+            },
+            h: divide(&previous.h),
+            l: divide(&previous.l),
+            a: previous.a.clone(),
+            b_g1: previous.b_g1.clone(),
+            b_g2: previous.b_g2.clone(),
+        };
+
+        // Schnorr proof of knowledge of `s` with `δ_after = s·δ_before`
+        let k = secret_scalar(b"nonce");
+        let commitment = G1Affine::from(previous.vk.delta_g1 * k);
+        let challenge = challenge(&previous.vk, &params.vk, &commitment);
+        let response = k + challenge * s;
+        let hash = ContributionHash {
+            digest: digest(&previous.vk, &params.vk, &commitment, &response),
+            commitment,
+            response,
+        };
+        Self { params, hash }
+    }
+}
+
+/// Check that `after` is `before` with the contribution recorded in `hash`
+///
+/// Everything but `delta`, `h`, and `l` must be unchanged, `delta` must move
+/// by the factor the Schnorr proof attests to in both groups, and `h` and `l`
+/// must be divided by that factor. The queries are compared through one
+/// random linear combination, so the cost is a few pairings plus one scalar
+/// multiplication per point.
+pub fn verify_contribution(
+    before: &Parameters<Bls12>,
+    after: &Parameters<Bls12>,
+    hash: &ContributionHash,
+) -> Result<(), ProofError> {
+    let invalid = |reason: &str| Err(ProofError::InvalidContribution { reason: reason.to_string() });
+    let (vb, va) = (&before.vk, &after.vk);
+
+    if va.alpha_g1 != vb.alpha_g1
+        || va.beta_g1 != vb.beta_g1
+        || va.beta_g2 != vb.beta_g2
+        || va.gamma_g2 != vb.gamma_g2
+        || va.ic != vb.ic
+        || after.a != before.a
+        || after.b_g1 != before.b_g1
+        || after.b_g2 != before.b_g2
+    {
+        return invalid("parameters other than delta, h, and l changed");
+    }
+    if after.h.len() != before.h.len() || after.l.len() != before.l.len() {
+        return invalid("h or l query length changed");
+    }
+    if bool::from(va.delta_g1.is_identity()) {
+        return invalid("delta is the identity");
+    }
+    if digest(vb, va, &hash.commitment, &hash.response) != hash.digest {
+        return invalid("digest does not match the contribution");
+    }
+
+    let c = challenge(vb, va, &hash.commitment);
+    if vb.delta_g1 * hash.response != G1Projective::from(hash.commitment) + va.delta_g1 * c {
+        return invalid("proof of knowledge of the delta factor does not verify");
+    }
+    if pairing(&va.delta_g1, &vb.delta_g2) != pairing(&vb.delta_g1, &va.delta_g2) {
+        return invalid("delta changed inconsistently between G1 and G2");
+    }
+
+    // Σ rᵢ·afterᵢ · δ_after must equal Σ rᵢ·beforeᵢ · δ_before
+    let mut combined_before = G1Projective::identity();
+    let mut combined_after = G1Projective::identity();
+    let pairs = before.h.iter().zip(after.h.iter()).chain(before.l.iter().zip(after.l.iter()));
+    for (b, a) in pairs {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let r = Scalar::from_raw([
+            u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            u64::from_le_bytes(bytes[8..].try_into().unwrap()),
+            0,
+            0,
+        ]);
+        combined_before += b * r;
+        combined_after += a * r;
+    }
+    if pairing(&combined_after.into(), &va.delta_g2) != pairing(&combined_before.into(), &vb.delta_g2) {
+        return invalid("h and l were not divided by the delta factor");
+    }
+    Ok(())
+}
+
+/// Ordered record of a ceremony's contributions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    contributions: Vec<ContributionHash>,
+}
+
+impl Transcript {
+    /// Empty transcript
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Contribute to `params` and record the contribution
+    pub fn contribute(&mut self, params: &Parameters<Bls12>, entropy: &[u8]) -> Parameters<Bls12> {
+        let Contribution { params, hash } = Contribution::new(params, entropy);
+        self.contributions.push(hash);
+        params
+    }
+
+    /// Record a contribution made elsewhere
+    pub fn push(&mut self, hash: ContributionHash) {
+        self.contributions.push(hash);
+    }
+
+    /// Contributions in order
+    pub fn hashes(&self) -> &[ContributionHash] {
+        &self.contributions
+    }
+
+    /// Check every link from `initial` through `stages`
+    ///
+    /// `stages[i]` holds the parameters after contribution `i`. Errors name
+    /// the first link that fails.
+    pub fn verify(&self, initial: &Parameters<Bls12>, stages: &[Parameters<Bls12>]) -> Result<(), ProofError> {
+        if stages.len() != self.contributions.len() {
+            return Err(ProofError::InvalidContribution {
+                reason: format!("{} stages for {} contributions", stages.len(), self.contributions.len()),
+            });
+        }
+        let befores = std::iter::once(initial).chain(stages.iter());
+        for (i, ((before, after), hash)) in befores.zip(stages).zip(&self.contributions).enumerate() {
+            verify_contribution(before, after, hash).map_err(|e| match e {
+                ProofError::InvalidContribution { reason } => ProofError::InvalidContribution {
+                    reason: format!("contribution {}: {}", i, reason),
+                },
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Non-zero scalar from `entropy` and OS randomness
+fn secret_scalar(entropy: &[u8]) -> Scalar {
+    let mut os = [0u8; 32];
+    OsRng.fill_bytes(&mut os);
+    for counter in 0u32.. {
+        let hash = Sha512::new()
+            .chain_update(CEREMONY_DOMAIN)
+            .chain_update(entropy)
+            .chain_update(os)
+            .chain_update(counter.to_be_bytes())
+            .finalize();
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&hash);
+        let scalar = Scalar::from_bytes_wide(&wide);
+        if scalar != Scalar::zero() {
+            return scalar;
+        }
+    }
+    unreachable!("a zero scalar is drawn with negligible probability")
+}
+
+/// Fiat-Shamir challenge binding both deltas and the commitment
+fn challenge(before: &VerifyingKey<Bls12>, after: &VerifyingKey<Bls12>, commitment: &G1Affine) -> Scalar {
+    let hash = Sha512::new()
+        .chain_update(CEREMONY_DOMAIN)
+        .chain_update(before.delta_g1.to_compressed())
+        .chain_update(after.delta_g1.to_compressed())
+        .chain_update(commitment.to_compressed())
+        .finalize();
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hash);
+    Scalar::from_bytes_wide(&wide)
+}
+
+fn digest(
+    before: &VerifyingKey<Bls12>,
+    after: &VerifyingKey<Bls12>,
+    commitment: &G1Affine,
+    response: &Scalar,
+) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(CEREMONY_DOMAIN)
+        .chain_update(before.delta_g1.to_compressed())
+        .chain_update(before.delta_g2.to_compressed())
+        .chain_update(after.delta_g1.to_compressed())
+        .chain_update(after.delta_g2.to_compressed())
+        .chain_update(commitment.to_compressed())
+        .chain_update(response.to_bytes())
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::groth16::{create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof};
+    use crate::proofs::encoding::FixedPoint;
+    use crate::proofs::lcr_circuit::{LcrCircuit, LcrInputs, LcrWitness};
+
+    fn lcr_params() -> Parameters<Bls12> {
+        generate_random_parameters::<Bls12, _, _>(LcrCircuit::blank(), &mut OsRng).unwrap()
+    }
+
+    fn proves(params: &Parameters<Bls12>, vk: &VerifyingKey<Bls12>) -> bool {
+        let multiplier = FixedPoint::encode(1.0).unwrap();
+        let circuit = LcrCircuit {
+            witness: Some(LcrWitness::new(&LcrInputs { hqla: 130.0, net_outflows: 100.0 }).unwrap()),
+            multiplier: Some(multiplier),
+            period: Some(Scalar::zero()),
+        };
+        let proof = create_random_proof(circuit, params, &mut OsRng).unwrap();
+        verify_proof(&prepare_verifying_key(vk), &proof, &[multiplier, Scalar::zero()]).is_ok()
+    }
+
+    #[test]
+    fn test_three_party_chain() {
+        let initial = lcr_params();
+        let mut transcript = Transcript::new();
+        let mut stages = Vec::new();
+        for entropy in [&b"alice"[..], b"bob", b"carol"] {
+            let previous = stages.last().unwrap_or(&initial);
+            let next = transcript.contribute(previous, entropy);
+            stages.push(next);
+        }
+
+        assert_eq!(transcript.hashes().len(), 3);
+        for (i, hash) in transcript.hashes().iter().enumerate() {
+            let before = if i == 0 { &initial } else { &stages[i - 1] };
+            verify_contribution(before, &stages[i], hash).unwrap();
+        }
+        transcript.verify(&initial, &stages).unwrap();
+
+        let last = &stages[2];
+        assert_ne!(last.vk.delta_g1, initial.vk.delta_g1);
+        assert!(proves(last, &last.vk));
+        // Proofs from the original parameters no longer verify
+        assert!(!proves(&initial, &last.vk));
+    }
+
+    #[test]
+    fn test_broken_links_rejected() {
+        let initial = lcr_params();
+        let Contribution { params: first, hash } = Contribution::new(&initial, b"alice");
+        let Contribution { params: second, hash: second_hash } = Contribution::new(&first, b"bob");
+
+        // Skipping a link, or reusing a record for other parameters
+        assert!(verify_contribution(&initial, &second, &second_hash).is_err());
+        assert!(verify_contribution(&first, &second, &hash).is_err());
+
+        // A delta change without the matching h and l update
+        let mut lazy = second.clone();
+        lazy.h = first.h.clone();
+        let err = verify_contribution(&first, &lazy, &second_hash).unwrap_err();
+        assert!(err.to_string().contains("not divided"), "{}", err);
+
+        let mut transcript = Transcript::new();
+        transcript.push(hash);
+        transcript.push(second_hash);
+        let err = transcript.verify(&initial, &[first, lazy]).unwrap_err();
+        assert!(err.to_string().contains("contribution 1"), "{}", err);
+    }
+}
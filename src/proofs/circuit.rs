@@ -0,0 +1,842 @@
+//! Fragility Circuit
+//!
+//! Groth16 circuits enforcing a fixed-point approximation of
+//! `compute_fragility`, and a native reference implementation of the same
+//! integer arithmetic so prover inputs match the circuit exactly.
+//! `FragilityCircuit` publishes the score along with a commitment to the bank
+//! state; `ThresholdCircuit` only shows the score is below a public bound.
+//!
+//! # Approximation
+//!
+//! All amounts use the `FixedPoint` encoding (scale S = 10^6). With
+//! capital C, assets A, liquidity coverage L and entropy H:
+//!
+//! - Capital distance `g = C - m·A` is computed exactly in units of 1/S².
+//! - The barrier `λ` is 1000 for `g <= 0`; on `(0, 8]` it interpolates
+//!   `α·exp(-g)` linearly between knots 0.25 apart; above 8 it is 0. The
+//!   interpolation overshoots by at most `α/128` and the cut-off drops
+//!   `α·e^-8`, so λ is within 0.016 of the exact barrier for `α = 2`.
+//! - The entropy term `1.5·H` is exact; the liquidity term `10/L` is floored
+//!   to a multiple of 1/S.
+//! - The score `100·raw/(raw + 50)` is floored to a multiple of 1/S.
+//!
+//! Scores differ from `compute_fragility` by well under 0.05 points. The
+//! interpolation segment is chosen with a one-hot selector, and every
+//! division is checked through a remainder constrained by `range_check`.
+//!
+//! # Input ranges
+//!
+//! Each private input is range-checked to 64 bits and the score to
+//! `[0, 100]`, so no field element outside the fixed-point encoding (a
+//! "negative" asset total, say) can stand in for an amount. This adds 316
+//! constraints (692 to 1008) and raises single-core release proving time
+//! from about 133 ms to 149 ms.
+//!
+//! # State commitment
+//!
+//! `FragilityCircuit` hashes the four encoded inputs with `mimc_hash` and
+//! exposes the digest as a second public input. Verifiers recompute it from
+//! the published state with `state_commitment`, so a proof only verifies for
+//! the exact state it was made from.
+//!
+//! # Reporting period
+//!
+//! Every circuit takes a reporting period as its last public input, 0 when
+//! the proof is not bound to one. No constraint reads it: bellman adds a
+//! constraint per public input, which is enough to make a proof fail for any
+//! other period, so last quarter's proof cannot be passed off as this one's.
+
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+use bls12_381::Scalar;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::proofs::encoding::{EncodingError, FixedPoint};
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::{mimc_compress, mimc_hash, range_check, scalar_from_i128, scalar_from_u128, CircuitField};
+use crate::proofs::prover::validate_witness;
+
+/// Fixed-point scale as a wide integer
+const S: i128 = FixedPoint::SCALE as i128;
+
+/// Barrier knot spacing in units of 1/S² (0.25)
+const KNOT_WIDTH: i128 = S * S / 4;
+
+/// Number of interpolated barrier segments, covering `g` in (0, 8]
+const BARRIER_SEGMENTS: usize = 32;
+
+/// Barrier value for an insolvent state (`g <= 0`)
+const INSOLVENT_LAMBDA: f64 = 1000.0;
+
+/// Span of the open-ended segments; exceeds any `|g|` from 64-bit inputs
+const OPEN_SPAN: i128 = 1 << 86;
+
+/// Bits covering a segment offset and its distance to the segment end
+const OFFSET_BITS: usize = 87;
+
+/// Bits covering the barrier remainder (`KNOT_WIDTH < 2^38`)
+const KNOT_BITS: usize = 38;
+
+/// Bits covering 64-bit encoded amounts and the quotients derived from them
+const AMOUNT_BITS: usize = 64;
+
+/// Bits covering a score in `[0, 100·S]` (`100·S < 2^27`)
+const FRAGILITY_BITS: usize = 27;
+
+/// Bits covering the score denominator `raw2 + 100·S`
+const SCORE_BITS: usize = 68;
+
+/// Bits covering an encoded threshold minus a score
+const THRESHOLD_BITS: usize = 64;
+
+/// One piece of the barrier approximation
+///
+/// Covers `g` in `[lo, lo + width)` (units of 1/S²), where `λ·S` runs from
+/// `value` by `slope` per `KNOT_WIDTH`.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    lo: i128,
+    width: i128,
+    value: i128,
+    slope: i128,
+}
+
+/// Barrier segments: insolvent, the interpolated knots, then the far tail
+fn barrier_segments(config: &LagrangianConfig) -> Vec<Segment> {
+    let knot = |k: usize| (config.lambda_sensitivity * (-(k as f64) * 0.25).exp() * S as f64).round() as i128;
+
+    let mut segments = vec![Segment {
+        lo: -OPEN_SPAN,
+        width: OPEN_SPAN + 1,
+        value: (INSOLVENT_LAMBDA * S as f64) as i128,
+        slope: 0,
+    }];
+    // The smallest positive distance is one unit, so solvent segments start at 1
+    for k in 0..BARRIER_SEGMENTS {
+        segments.push(Segment {
+            lo: 1 + k as i128 * KNOT_WIDTH,
+            width: KNOT_WIDTH,
+            value: knot(k),
+            slope: knot(k + 1) - knot(k),
+        });
+    }
+    segments.push(Segment {
+        lo: 1 + BARRIER_SEGMENTS as i128 * KNOT_WIDTH,
+        width: OPEN_SPAN,
+        value: 0,
+        slope: 0,
+    });
+    segments
+}
+
+/// Regulatory minimum capital ratio in fixed-point units
+fn min_capital_units(config: &LagrangianConfig) -> i128 {
+    (config.regulatory_min_capital * S as f64).round() as i128
+}
+
+/// Private witness for the fragility circuit
+///
+/// Holds the encoded inputs and every intermediate the circuit checks, all
+/// in fixed-point units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragilityWitness {
+    pub(crate) tier1_capital: u64,
+    pub(crate) total_assets: u64,
+    pub(crate) liquidity_coverage: u64,
+    pub(crate) entropy_index: u64,
+    /// Index into the barrier segments
+    pub(crate) segment: usize,
+    /// Capital distance minus the segment start
+    pub(crate) offset: i128,
+    pub(crate) lambda: i128,
+    pub(crate) lambda_rem: i128,
+    pub(crate) liquidity_stress: u128,
+    pub(crate) liquidity_rem: u128,
+    pub(crate) fragility: u128,
+    pub(crate) fragility_rem: u128,
+}
+
+impl FragilityWitness {
+    /// Compute the witness for `state` with the circuit's integer arithmetic
+    ///
+    /// Fails if an amount cannot be encoded, or the liquidity coverage
+    /// encodes to zero.
+    pub fn new(state: &BankState, config: &LagrangianConfig) -> Result<Self, EncodingError> {
+        let tier1_capital = FixedPoint::to_units(state.tier1_capital)?;
+        let total_assets = FixedPoint::to_units(state.total_assets)?;
+        let liquidity_coverage = FixedPoint::to_units(state.liquidity_coverage)?;
+        let entropy_index = FixedPoint::to_units(state.entropy_index)?;
+        if liquidity_coverage == 0 {
+            return Err(EncodingError::ZeroDivisor { value: state.liquidity_coverage });
+        }
+        Ok(Self::from_units([tier1_capital, total_assets, liquidity_coverage, entropy_index], config))
+    }
+
+    /// Compute the witness for an already encoded state
+    ///
+    /// `units` holds capital, assets, liquidity coverage, and entropy in that
+    /// order; the liquidity coverage must be nonzero.
+    pub(crate) fn from_units(units: [u64; 4], config: &LagrangianConfig) -> Self {
+        let [tier1_capital, total_assets, liquidity_coverage, entropy_index] = units;
+        let segments = barrier_segments(config);
+        let distance = tier1_capital as i128 * S - total_assets as i128 * min_capital_units(config);
+        let segment = if distance <= 0 {
+            0
+        } else {
+            (1 + ((distance - 1) / KNOT_WIDTH) as usize).min(BARRIER_SEGMENTS + 1)
+        };
+        let Segment { lo, value, slope, .. } = segments[segment];
+        let offset = distance - lo;
+
+        // λ·S·KNOT_WIDTH, then floored to λ·S
+        let scaled_lambda = value * KNOT_WIDTH + slope * offset;
+        let (lambda, lambda_rem) = (scaled_lambda / KNOT_WIDTH, scaled_lambda % KNOT_WIDTH);
+
+        let numerator = 10 * (S * S) as u128;
+        let lcr = liquidity_coverage as u128;
+        let (liquidity_stress, liquidity_rem) = (numerator / lcr, numerator % lcr);
+
+        let raw2 = raw2(lambda as u128, entropy_index, liquidity_stress);
+        let (fragility, fragility_rem) = score_division(raw2);
+
+        Self {
+            tier1_capital,
+            total_assets,
+            liquidity_coverage,
+            entropy_index,
+            segment,
+            offset,
+            lambda,
+            lambda_rem,
+            liquidity_stress,
+            liquidity_rem,
+            fragility,
+            fragility_rem,
+        }
+    }
+
+    /// Encoded capital, assets, liquidity coverage, and entropy
+    pub(crate) fn units(&self) -> [u64; 4] {
+        [self.tier1_capital, self.total_assets, self.liquidity_coverage, self.entropy_index]
+    }
+
+    /// Fragility score the circuit computes, in fixed-point units
+    pub fn fragility_units(&self) -> u64 {
+        self.fragility as u64
+    }
+
+    /// Fragility score the circuit computes
+    pub fn fragility(&self) -> f64 {
+        self.fragility as f64 / FixedPoint::SCALE as f64
+    }
+}
+
+/// Twice the raw stress score in fixed-point units: `2λ + 3H + 2·(10/L)`
+fn raw2(lambda: u128, entropy_index: u64, liquidity_stress: u128) -> u128 {
+    2 * lambda + 3 * entropy_index as u128 + 2 * liquidity_stress
+}
+
+/// Floored `100·S·raw2 / (raw2 + 100·S)` and its remainder
+fn score_division(raw2: u128) -> (u128, u128) {
+    let hundred = 100 * S as u128;
+    let numerator = hundred * raw2;
+    let denominator = raw2 + hundred;
+    (numerator / denominator, numerator % denominator)
+}
+
+/// Fragility score under the circuit's approximation of `compute_fragility`
+///
+/// This is the value a proof for `state` must be verified against.
+pub fn reference_fragility(state: &BankState, config: &LagrangianConfig) -> Result<f64, EncodingError> {
+    FragilityWitness::new(state, config).map(|w| w.fragility())
+}
+
+/// Commitment a fragility proof for `state` is bound to
+///
+/// MiMC digest of the encoded capital, assets, liquidity coverage, and
+/// entropy, in that order.
+pub fn state_commitment(state: &BankState) -> Result<Scalar, EncodingError> {
+    let inputs = [
+        FixedPoint::encode(state.tier1_capital)?,
+        FixedPoint::encode(state.total_assets)?,
+        FixedPoint::encode(state.liquidity_coverage)?,
+        FixedPoint::encode(state.entropy_index)?,
+    ];
+    Ok(mimc_hash(&inputs))
+}
+
+/// `state_commitment` of an encoded state, in any circuit field
+pub(crate) fn commit_units<S: CircuitField>(units: [u64; 4]) -> S {
+    mimc_hash(&units.map(S::from))
+}
+
+/// Fragility computation circuit for ZK-SNARK
+///
+/// The bank state is private; the public inputs are the fragility score, the
+/// state commitment, and the reporting period. Proofs use the BLS12-381
+/// `Scalar`; other fields serve alternative proving backends.
+#[derive(Clone)]
+pub struct FragilityCircuit<S = Scalar> {
+    /// Barrier and capital constants baked into the constraints
+    pub config: LagrangianConfig,
+    /// Private: Witness for the bank state (`None` during setup)
+    pub witness: Option<FragilityWitness>,
+    /// Public: Fragility score output
+    pub fragility: Option<S>,
+    /// Public: `state_commitment` of the witnessed state
+    pub commitment: Option<S>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<S>,
+}
+
+impl<S: CircuitField> FragilityCircuit<S> {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank(config: LagrangianConfig) -> Self {
+        Self {
+            config,
+            witness: None,
+            fragility: None,
+            commitment: None,
+            period: None,
+        }
+    }
+
+    /// Fully assigned circuit proving `fragility_score` for `state`
+    ///
+    /// The state is checked with `validate_witness`, and `fragility_score`
+    /// must equal `reference_fragility(state)` to six decimals; either failure
+    /// is reported as `ProofError::InvalidWitness`. `FragilityProver::prove`
+    /// proves exactly this circuit, and `check_constraints` checks it without
+    /// proving.
+    pub fn generate_witness(
+        state: &BankState,
+        fragility_score: f64,
+        config: LagrangianConfig,
+    ) -> Result<Self, ProofError> {
+        validate_witness(state)?;
+        let witness = FragilityWitness::new(state, &config)?;
+        let claimed = FixedPoint::to_units(fragility_score)?;
+        if claimed != witness.fragility_units() {
+            return Err(ProofError::InvalidWitness {
+                reason: format!(
+                    "fragility {} != circuit fragility {} by {}",
+                    fragility_score,
+                    witness.fragility(),
+                    (fragility_score - witness.fragility()).abs()
+                ),
+            });
+        }
+
+        Ok(Self {
+            config,
+            commitment: Some(commit_units(witness.units())),
+            witness: Some(witness),
+            fragility: Some(S::from(claimed)),
+            period: Some(S::ZERO),
+        })
+    }
+}
+
+impl<S: CircuitField> Circuit<S> for FragilityCircuit<S> {
+    fn synthesize<CS: ConstraintSystem<S>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        self.synthesize_with(cs, AMOUNT_BITS)
+    }
+}
+
+impl<S: CircuitField> FragilityCircuit<S> {
+    /// Synthesize with amounts range-checked to `amount_bits` rather than 64
+    ///
+    /// Other widths only size hypothetical layouts for `estimate_circuit`.
+    pub(crate) fn synthesize_with<CS: ConstraintSystem<S>>(
+        self,
+        cs: &mut CS,
+        amount_bits: usize,
+    ) -> Result<(), SynthesisError> {
+        let w = self.witness.as_ref();
+
+        // Allocate public outputs
+        let fragility = cs.alloc_input(
+            || "fragility",
+            || self.fragility.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        let commitment = cs.alloc_input(
+            || "commitment",
+            || self.commitment.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let inputs = enforce_fragility(cs, &self.config, w, fragility, amount_bits)?;
+
+        // Chain the inputs through the MiMC compression
+        let values = w.map(FragilityWitness::units);
+        let mut digest = LinearCombination::zero();
+        let mut digest_value = w.map(|_| S::ZERO);
+        for (i, input) in inputs.into_iter().enumerate() {
+            (digest, digest_value) = mimc_compress(
+                cs.namespace(|| format!("commitment {}", i)),
+                &digest,
+                digest_value,
+                &(LinearCombination::zero() + input),
+                values.map(|v| S::from(v[i])),
+            )?;
+        }
+        cs.enforce(|| "commitment matches", |_| digest, |lc| lc + CS::one(), |lc| lc + commitment);
+        Ok(())
+    }
+}
+
+/// Circuit proving the fragility score is below a public bound
+///
+/// The score is a private witness constrained to the same computation as
+/// `FragilityCircuit`; the threshold and reporting period are the only public
+/// inputs.
+#[derive(Clone)]
+pub struct ThresholdCircuit {
+    /// Barrier and capital constants baked into the constraints
+    pub config: LagrangianConfig,
+    /// Private: Witness for the bank state (`None` during setup)
+    pub witness: Option<FragilityWitness>,
+    /// Public: Exclusive upper bound on the fragility score
+    pub threshold: Option<Scalar>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<Scalar>,
+}
+
+impl ThresholdCircuit {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank(config: LagrangianConfig) -> Self {
+        Self {
+            config,
+            witness: None,
+            threshold: None,
+            period: None,
+        }
+    }
+}
+
+impl Circuit<Scalar> for ThresholdCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        self.synthesize_with(cs, AMOUNT_BITS)
+    }
+}
+
+impl ThresholdCircuit {
+    /// Synthesize with amounts range-checked to `amount_bits`; see
+    /// `FragilityCircuit::synthesize_with`
+    pub(crate) fn synthesize_with<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        amount_bits: usize,
+    ) -> Result<(), SynthesisError> {
+        let w = self.witness.as_ref();
+
+        // Allocate public bound and private score
+        let threshold = cs.alloc_input(
+            || "threshold",
+            || self.threshold.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+        let score = cs.alloc(
+            || "score",
+            || w.map(|w| scalar_from_u128(w.fragility)).ok_or(SynthesisError::AssignmentMissing),
+        )?;
+
+        enforce_fragility(cs, &self.config, w, score, amount_bits)?;
+
+        // score < threshold  <=>  threshold - 1 - score fits in THRESHOLD_BITS
+        range_check(
+            cs.namespace(|| "below threshold"),
+            LinearCombination::zero() + threshold - CS::one() - score,
+            self.threshold
+                .zip(w)
+                .map(|(t, w)| t - Scalar::one() - scalar_from_u128::<Scalar>(w.fragility)),
+            THRESHOLD_BITS - AMOUNT_BITS + amount_bits,
+        )
+    }
+}
+
+/// Constrain `fragility` to the score of the witnessed bank state
+///
+/// Allocates the state as private inputs; shared by every circuit that
+/// makes a statement about a fragility score. Returns the capital, assets,
+/// liquidity coverage, and entropy variables. Amounts are range-checked to
+/// `amount_bits`, `AMOUNT_BITS` outside of estimates.
+pub(crate) fn enforce_fragility<S: CircuitField, CS: ConstraintSystem<S>>(
+    cs: &mut CS,
+    config: &LagrangianConfig,
+    w: Option<&FragilityWitness>,
+    fragility: Variable,
+    amount_bits: usize,
+) -> Result<[Variable; 4], SynthesisError> {
+    // Widths derived from the amount width keep their headroom over it
+    let offset_bits = OFFSET_BITS - AMOUNT_BITS + amount_bits;
+    let score_bits = SCORE_BITS - AMOUNT_BITS + amount_bits;
+    let segments = barrier_segments(config);
+    let scale = S::from(FixedPoint::SCALE);
+
+    // Helper: allocate a private value derived from the witness
+    macro_rules! witness {
+        ($name:expr, $value:expr) => {
+            cs.alloc(|| $name, || w.map($value).ok_or(SynthesisError::AssignmentMissing))?
+        };
+    }
+
+    // Allocate private inputs
+    let tier1_capital = witness!("tier1_capital", |w| S::from(w.tier1_capital));
+    let total_assets = witness!("total_assets", |w| S::from(w.total_assets));
+    let liquidity_coverage = witness!("liquidity_coverage", |w| S::from(w.liquidity_coverage));
+    let entropy_index = witness!("entropy_index", |w| S::from(w.entropy_index));
+
+    // Inputs must be genuine 64-bit encodings; a field element such as -A
+    // would otherwise pass as a negative amount
+    let inputs: [(&str, Variable, Option<u64>); 4] = [
+        ("tier1_capital", tier1_capital, w.map(|w| w.tier1_capital)),
+        ("total_assets", total_assets, w.map(|w| w.total_assets)),
+        ("liquidity_coverage", liquidity_coverage, w.map(|w| w.liquidity_coverage)),
+        ("entropy_index", entropy_index, w.map(|w| w.entropy_index)),
+    ];
+    for (name, var, units) in inputs {
+        range_check(
+            cs.namespace(|| format!("{} range", name)),
+            LinearCombination::zero() + var,
+            units.map(S::from),
+            amount_bits,
+        )?;
+    }
+
+    // Score lies in [0, 100]
+    let max_score = S::from(100 * FixedPoint::SCALE);
+    let score_units = w.map(|w| scalar_from_u128(w.fragility));
+    range_check(
+        cs.namespace(|| "fragility range"),
+        LinearCombination::zero() + fragility,
+        score_units,
+        FRAGILITY_BITS,
+    )?;
+    range_check(
+        cs.namespace(|| "fragility headroom"),
+        LinearCombination::zero() + (max_score, CS::one()) - fragility,
+        score_units.map(|f| max_score - f),
+        FRAGILITY_BITS,
+    )?;
+
+    // Barrier segment selector: boolean and one-hot
+    let mut selected = LinearCombination::zero();
+    let (mut lo, mut width, mut value, mut slope) = (
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+    );
+    for (k, segment) in segments.iter().enumerate() {
+        let bit = witness!(format!("segment {}", k), |w| {
+            if w.segment == k { S::ONE } else { S::ZERO }
+        });
+        cs.enforce(
+            || format!("segment {} boolean", k),
+            |lc| lc + bit,
+            |lc| lc + CS::one() - bit,
+            |lc| lc,
+        );
+        selected = selected + bit;
+        lo = lo + (scalar_from_i128(segment.lo), bit);
+        width = width + (scalar_from_i128(segment.width), bit);
+        value = value + (scalar_from_i128(segment.value), bit);
+        slope = slope + (scalar_from_i128(segment.slope), bit);
+    }
+    cs.enforce(|| "one segment", |_| selected, |lc| lc + CS::one(), |lc| lc + CS::one());
+
+    // Capital distance g = C·S - m·A, offset into the selected segment
+    let offset = witness!("offset", |w| scalar_from_i128(w.offset));
+    let min_capital = scalar_from_i128(min_capital_units(config));
+    cs.enforce(
+        || "segment offset",
+        |lc| lc + (scale, tier1_capital) - (min_capital, total_assets) - &lo,
+        |lc| lc + CS::one(),
+        |lc| lc + offset,
+    );
+    range_check(
+        cs.namespace(|| "offset lower"),
+        LinearCombination::zero() + offset,
+        w.map(|w| scalar_from_i128(w.offset)),
+        offset_bits,
+    )?;
+    range_check(
+        cs.namespace(|| "offset upper"),
+        width.clone() - CS::one() - offset,
+        w.map(|w| scalar_from_i128(segments[w.segment].width - 1 - w.offset)),
+        offset_bits,
+    )?;
+
+    // Barrier: λ·S·KNOT_WIDTH = value·KNOT_WIDTH + slope·offset
+    let slope_offset = witness!("slope offset", |w| {
+        scalar_from_i128(segments[w.segment].slope * w.offset)
+    });
+    cs.enforce(|| "slope times offset", |_| slope, |lc| lc + offset, |lc| lc + slope_offset);
+    let lambda = witness!("lambda", |w| scalar_from_i128(w.lambda));
+    let lambda_rem = witness!("lambda remainder", |w| scalar_from_i128(w.lambda_rem));
+    let knot_width = scalar_from_i128(KNOT_WIDTH);
+    cs.enforce(
+        || "lambda division",
+        |lc| lc + (knot_width, lambda) + lambda_rem,
+        |lc| lc + CS::one(),
+        |lc| lc + (knot_width, &value) + slope_offset,
+    );
+    range_check(
+        cs.namespace(|| "lambda remainder lower"),
+        LinearCombination::zero() + lambda_rem,
+        w.map(|w| scalar_from_i128(w.lambda_rem)),
+        KNOT_BITS,
+    )?;
+    range_check(
+        cs.namespace(|| "lambda remainder upper"),
+        LinearCombination::zero() + (knot_width, CS::one()) - CS::one() - lambda_rem,
+        w.map(|w| scalar_from_i128(KNOT_WIDTH - 1 - w.lambda_rem)),
+        KNOT_BITS,
+    )?;
+    range_check(
+        cs.namespace(|| "lambda range"),
+        LinearCombination::zero() + lambda,
+        w.map(|w| scalar_from_i128(w.lambda)),
+        amount_bits,
+    )?;
+
+    // Liquidity stress: Q·L + r = 10·S², 0 <= r < L
+    let liquidity_stress = witness!("liquidity stress", |w| scalar_from_u128(w.liquidity_stress));
+    let liquidity_rem = witness!("liquidity remainder", |w| scalar_from_u128(w.liquidity_rem));
+    let ten_s2 = scalar_from_i128(10 * S * S);
+    cs.enforce(
+        || "liquidity division",
+        |lc| lc + liquidity_stress,
+        |lc| lc + liquidity_coverage,
+        |lc| lc + (ten_s2, CS::one()) - liquidity_rem,
+    );
+    range_check(
+        cs.namespace(|| "liquidity remainder lower"),
+        LinearCombination::zero() + liquidity_rem,
+        w.map(|w| scalar_from_u128(w.liquidity_rem)),
+        amount_bits,
+    )?;
+    range_check(
+        cs.namespace(|| "liquidity remainder upper"),
+        LinearCombination::zero() + liquidity_coverage - CS::one() - liquidity_rem,
+        w.map(|w| scalar_from_u128(w.liquidity_coverage as u128 - 1 - w.liquidity_rem)),
+        amount_bits,
+    )?;
+    range_check(
+        cs.namespace(|| "liquidity stress range"),
+        LinearCombination::zero() + liquidity_stress,
+        w.map(|w| scalar_from_u128(w.liquidity_stress)),
+        amount_bits,
+    )?;
+
+    // Score: F·(raw2 + 100·S) + r = 100·S·raw2, 0 <= r < raw2 + 100·S
+    let two = S::from(2u64);
+    let raw2 = LinearCombination::zero()
+        + (two, lambda)
+        + (S::from(3u64), entropy_index)
+        + (two, liquidity_stress);
+    let hundred = S::from(100 * FixedPoint::SCALE);
+    let denominator = raw2.clone() + (hundred, CS::one());
+    let fragility_rem = witness!("fragility remainder", |w| scalar_from_u128(w.fragility_rem));
+    let raw2_value = w.map(raw2_units);
+    cs.enforce(
+        || "score division",
+        |lc| lc + fragility,
+        |lc| lc + &denominator,
+        |lc| lc + (hundred, &raw2) - fragility_rem,
+    );
+    range_check(
+        cs.namespace(|| "fragility remainder lower"),
+        LinearCombination::zero() + fragility_rem,
+        w.map(|w| scalar_from_u128(w.fragility_rem)),
+        score_bits,
+    )?;
+    range_check(
+        cs.namespace(|| "fragility remainder upper"),
+        denominator - CS::one() - fragility_rem,
+        raw2_value
+            .zip(w)
+            .map(|(raw2, w)| scalar_from_u128(raw2 + 100 * S as u128 - 1 - w.fragility_rem)),
+        score_bits,
+    )?;
+
+    Ok([tier1_capital, total_assets, liquidity_coverage, entropy_index])
+}
+
+/// `raw2` recomputed from a witness
+fn raw2_units(w: &FragilityWitness) -> u128 {
+    raw2(w.lambda as u128, w.entropy_index, w.liquidity_stress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::compute_fragility;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    fn state(tier1_capital: f64, liquidity_coverage: f64, entropy_index: f64) -> BankState {
+        BankState {
+            tier1_capital,
+            total_assets: 100_000.0,
+            liquidity_coverage,
+            entropy_index,
+        }
+    }
+
+    fn satisfied(circuit: FragilityCircuit) -> bool {
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    fn satisfied_threshold(circuit: ThresholdCircuit) -> bool {
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_reference_tracks_compute_fragility() {
+        let config = LagrangianConfig::default();
+        // Capital from insolvent, through the barrier knots, to far above it
+        let capitals = [5_000.0, 8_000.0, 8_000.000001, 8_000.1, 8_000.6, 8_002.5, 8_007.9, 8_050.0, 12_000.0];
+        for &capital in &capitals {
+            for &(lcr, entropy) in &[(1.2, 2.0), (0.35, 0.0), (4.0, 9.5)] {
+                let s = state(capital, lcr, entropy);
+                let exact = compute_fragility(&s, &config);
+                let approx = reference_fragility(&s, &config).unwrap();
+                assert!(
+                    (exact - approx).abs() < 0.05,
+                    "capital {} lcr {}: exact {} approx {}",
+                    capital,
+                    lcr,
+                    exact,
+                    approx
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_witness_satisfies_circuit() {
+        let config = LagrangianConfig::default();
+        for &capital in &[5_000.0, 8_000.0, 8_002.5, 8_009.0] {
+            let s = state(capital, 1.2, 2.0);
+            let witness = FragilityWitness::new(&s, &config).unwrap();
+            let circuit = FragilityCircuit {
+                config: config.clone(),
+                fragility: Some(Scalar::from(witness.fragility_units())),
+                witness: Some(witness),
+                commitment: Some(state_commitment(&s).unwrap()),
+                period: Some(Scalar::zero()),
+            };
+            assert!(satisfied(circuit), "capital {}", capital);
+        }
+    }
+
+    #[test]
+    fn test_tampered_score_unsatisfied() {
+        let config = LagrangianConfig::default();
+        let s = state(8_002.5, 1.2, 2.0);
+        let witness = FragilityWitness::new(&s, &config).unwrap();
+        let circuit = FragilityCircuit {
+            config,
+            fragility: Some(Scalar::from(witness.fragility_units() - 1)),
+            witness: Some(witness),
+            commitment: Some(state_commitment(&s).unwrap()),
+            period: Some(Scalar::zero()),
+        };
+        assert!(!satisfied(circuit));
+    }
+
+    #[test]
+    fn test_commitment_binds_state() {
+        let config = LagrangianConfig::default();
+        let s = state(8_002.5, 1.2, 2.0);
+        let witness = FragilityWitness::new(&s, &config).unwrap();
+        let with_commitment = |commitment: Scalar| FragilityCircuit {
+            config: config.clone(),
+            fragility: Some(Scalar::from(witness.fragility_units())),
+            witness: Some(witness.clone()),
+            commitment: Some(commitment),
+            period: Some(Scalar::zero()),
+        };
+
+        assert!(satisfied(with_commitment(state_commitment(&s).unwrap())));
+        let altered = [
+            BankState { tier1_capital: 8_002.6, ..s.clone() },
+            BankState { total_assets: 99_999.0, ..s.clone() },
+            BankState { liquidity_coverage: 1.3, ..s.clone() },
+            BankState { entropy_index: 2.1, ..s.clone() },
+        ];
+        for other in &altered {
+            assert!(!satisfied(with_commitment(state_commitment(other).unwrap())), "{:?}", other);
+        }
+    }
+
+    #[test]
+    fn test_threshold_comparison() {
+        let config = LagrangianConfig::default();
+        let witness = FragilityWitness::new(&state(10_000.0, 1.2, 2.0), &config).unwrap();
+        let score = witness.fragility_units();
+        let bounded = |threshold: u64| {
+            satisfied_threshold(ThresholdCircuit {
+                config: config.clone(),
+                witness: Some(witness.clone()),
+                threshold: Some(Scalar::from(threshold)),
+                period: Some(Scalar::zero()),
+            })
+        };
+
+        assert!(bounded(score + 1));
+        assert!(bounded(100 * FixedPoint::SCALE));
+        assert!(!bounded(score));
+        assert!(!bounded(score - 1));
+    }
+
+    #[test]
+    fn test_negative_assets_rejected() {
+        // Assets of -50_000 against capital 4_000 give the same capital
+        // distance as capital 8_000 with no assets
+        let config = LagrangianConfig::default();
+        let mut honest = state(8_000.0, 1.2, 2.0);
+        honest.total_assets = 0.0;
+        let witness = FragilityWitness::new(&honest, &config).unwrap();
+        let circuit = ThresholdCircuit {
+            config,
+            witness: Some(witness),
+            threshold: Some(Scalar::from(100 * FixedPoint::SCALE)),
+            period: Some(Scalar::zero()),
+        };
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+
+        let capital = 4_000 * FixedPoint::SCALE;
+        cs.set("tier1_capital", Scalar::from(capital));
+        for i in 0..AMOUNT_BITS {
+            let bit = if (capital >> i) & 1 == 1 { Scalar::one() } else { Scalar::zero() };
+            cs.set(&format!("tier1_capital range/bit {}", i), bit);
+        }
+        cs.set("total_assets", -Scalar::from(50_000 * FixedPoint::SCALE));
+
+        // Only the range check stands between the forged state and a proof
+        assert_eq!(cs.which_is_unsatisfied(), Some("total_assets range/packing"));
+    }
+
+    #[test]
+    fn test_zero_liquidity_rejected() {
+        let result = FragilityWitness::new(&state(10_000.0, 0.0, 2.0), &LagrangianConfig::default());
+        assert!(matches!(result, Err(EncodingError::ZeroDivisor { .. })));
+    }
+}
@@ -0,0 +1,120 @@
+//! Fixed-Point Encoding
+//!
+//! Maps non-negative `f64` amounts onto BLS12-381 scalars for use as circuit
+//! witnesses and public inputs.
+//!
+//! Every value is scaled by `FixedPoint::SCALE` (10^6) and rounded to the
+//! nearest integer, so amounts keep six decimal places. Scaled values must fit
+//! in a `u64`, which bounds encodable amounts at about 1.8 × 10^13. Products of
+//! two encoded values carry the scale twice, so multiplicative constraints
+//! multiply the other side by `SCALE` once more.
+
+use bls12_381::Scalar;
+use std::fmt;
+
+/// Fixed-point codec between `f64` and `Scalar`
+pub struct FixedPoint;
+
+impl FixedPoint {
+    /// Encoded units per 1.0
+    pub const SCALE: u64 = 1_000_000;
+
+    /// Largest encodable value
+    pub const MAX: f64 = u64::MAX as f64 / Self::SCALE as f64;
+
+    /// Scaled integer representation of `value`
+    pub fn to_units(value: f64) -> Result<u64, EncodingError> {
+        if !value.is_finite() {
+            return Err(EncodingError::NotFinite { value });
+        }
+        if value < 0.0 {
+            return Err(EncodingError::Negative { value });
+        }
+        let scaled = (value * Self::SCALE as f64).round();
+        // u64::MAX is not representable as f64; the cast rounds it up to 2^64
+        if scaled >= u64::MAX as f64 {
+            return Err(EncodingError::Overflow { value });
+        }
+        Ok(scaled as u64)
+    }
+
+    /// Encode `value` as a field element
+    pub fn encode(value: f64) -> Result<Scalar, EncodingError> {
+        Self::to_units(value).map(Scalar::from)
+    }
+
+    /// Decode a field element produced by `encode`
+    ///
+    /// Elements beyond the `u64` range (never produced by `encode`) decode
+    /// to their approximate integer value divided by the scale.
+    pub fn decode(scalar: Scalar) -> f64 {
+        let bytes = scalar.to_bytes();
+        let units = bytes
+            .chunks(8)
+            .rev()
+            .fold(0.0, |acc, limb| acc * 2f64.powi(64) + u64::from_le_bytes(limb.try_into().unwrap()) as f64);
+        units / Self::SCALE as f64
+    }
+}
+
+/// Values that cannot be represented in the fixed-point encoding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncodingError {
+    /// Negative amounts would wrap around the field modulus
+    Negative { value: f64 },
+    /// NaN or infinite input
+    NotFinite { value: f64 },
+    /// Scaled value does not fit in 64 bits
+    Overflow { value: f64 },
+    /// Value encodes to zero where the circuit divides by it
+    ZeroDivisor { value: f64 },
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::Negative { value } => write!(f, "cannot encode negative value {}", value),
+            EncodingError::NotFinite { value } => write!(f, "cannot encode non-finite value {}", value),
+            EncodingError::Overflow { value } => {
+                write!(f, "value {} exceeds the encodable maximum {:e}", value, FixedPoint::MAX)
+            }
+            EncodingError::ZeroDivisor { value } => {
+                write!(f, "divisor {} encodes to zero at scale {}", value, FixedPoint::SCALE)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_grid() {
+        let values = [0.0, 1e-6, 0.5, 1.0, 9.25, 100.123456, 1_000.5, 123_456_789.012345, 1e13];
+        for &value in &values {
+            let decoded = FixedPoint::decode(FixedPoint::encode(value).unwrap());
+            assert!(
+                (decoded - value).abs() <= 0.5e-6 * value.max(1.0),
+                "{} decoded as {}",
+                value,
+                decoded
+            );
+        }
+
+        // Sub-unit amounts round to the nearest millionth
+        assert_eq!(FixedPoint::to_units(0.0000004).unwrap(), 0);
+        assert_eq!(FixedPoint::to_units(2.5000006).unwrap(), 2_500_001);
+    }
+
+    #[test]
+    fn test_rejects_unrepresentable_values() {
+        assert!(matches!(FixedPoint::encode(-0.01), Err(EncodingError::Negative { .. })));
+        assert!(matches!(FixedPoint::encode(f64::NAN), Err(EncodingError::NotFinite { .. })));
+        assert!(matches!(FixedPoint::encode(f64::INFINITY), Err(EncodingError::NotFinite { .. })));
+        assert!(matches!(FixedPoint::encode(2e13), Err(EncodingError::Overflow { .. })));
+        assert!(FixedPoint::encode(FixedPoint::MAX * 0.999).is_ok());
+    }
+}
@@ -0,0 +1,434 @@
+//! Entropy Circuit
+//!
+//! Groth16 circuit proving a portfolio's Shannon entropy is at least a public
+//! bound without revealing the holdings, and a native reference of the same
+//! integer arithmetic.
+//!
+//! # Approximation
+//!
+//! Weights are normalized as in `calculate_entropy` and encoded in units of
+//! 1/S (S = 10^6), with rounding residue assigned so they sum to exactly S.
+//! Up to `MAX_POSITIONS` weights are used; unused slots hold zero.
+//!
+//! Each term `-w·log2 w` is interpolated by chords between knots spaced a
+//! quarter octave apart (`S·2^(-t/4)`), with knot values floored. The curve
+//! is concave, so every chord lies below it and the circuit entropy is a
+//! lower bound on the entropy of the encoded weights, short by at most about
+//! 0.007 bits.
+
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+use bls12_381::Scalar;
+
+use crate::core::entropy::{EntropyConfig, Position};
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::{range_check, scalar_from_i128};
+
+/// Fixed-point scale as a wide integer
+const S: i128 = FixedPoint::SCALE as i128;
+
+/// Positions per proof; portfolios with fewer are padded with zero weights
+pub const MAX_POSITIONS: usize = 16;
+
+/// Quarter octaves below 1 covered by knots; smaller weights share the first chord
+const KNOT_STEPS: i32 = 72;
+
+/// Bits covering a chord span (the widest is `S - S·2^-0.25 < 2^18`)
+const SPAN_BITS: usize = 18;
+
+/// Bits covering one term (`-w·log2 w <= 0.531`)
+const TERM_BITS: usize = 20;
+
+/// Bits covering the entropy minus the bound (`log2 16 · S < 2^23`)
+const ENTROPY_BITS: usize = 23;
+
+/// One chord of the term approximation
+///
+/// Covers weights in `[lo, lo + span)`; the term runs from `value` by `delta`
+/// across `width` units.
+#[derive(Debug, Clone, Copy)]
+struct Chord {
+    lo: i128,
+    width: i128,
+    span: i128,
+    value: i128,
+    delta: i128,
+}
+
+/// Floored `-w·log2 w` in fixed-point units for a weight of `units`/S
+fn term_units(units: i128) -> i128 {
+    if units == 0 {
+        return 0;
+    }
+    let units = units as f64;
+    (-units * (units / S as f64).log2()).floor() as i128
+}
+
+/// Chords from zero weight to a weight of one
+fn chords() -> Vec<Chord> {
+    let mut knots = vec![0];
+    for t in (0..=KNOT_STEPS).rev() {
+        let knot = (S as f64 * 2f64.powf(-t as f64 / 4.0)).round() as i128;
+        if knot > *knots.last().unwrap() {
+            knots.push(knot);
+        }
+    }
+
+    let last = knots.len() - 2;
+    knots
+        .windows(2)
+        .enumerate()
+        .map(|(k, pair)| {
+            let width = pair[1] - pair[0];
+            Chord {
+                lo: pair[0],
+                width,
+                // The last chord also covers a weight of exactly one
+                span: if k == last { width + 1 } else { width },
+                value: term_units(pair[0]),
+                delta: term_units(pair[1]) - term_units(pair[0]),
+            }
+        })
+        .collect()
+}
+
+/// One position's weight and its interpolated term
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    weight: u64,
+    chord: usize,
+    offset: i128,
+    term: i128,
+    term_rem: i128,
+}
+
+impl Term {
+    fn new(weight: u64, chords: &[Chord]) -> Self {
+        let w = weight as i128;
+        let chord = chords.iter().position(|c| w < c.lo + c.span).expect("weight at most S");
+        let Chord { lo, width, value, delta, .. } = chords[chord];
+        let offset = w - lo;
+        let numerator = value * width + delta * offset;
+        Self {
+            weight,
+            chord,
+            offset,
+            term: numerator / width,
+            term_rem: numerator % width,
+        }
+    }
+}
+
+/// Private witness for the entropy circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntropyWitness {
+    terms: Vec<Term>,
+}
+
+impl EntropyWitness {
+    /// Encode `positions` and interpolate their terms
+    ///
+    /// Weights below `EntropyConfig::default().min_weight` are dropped, as in
+    /// `calculate_entropy`. Fails if a weight is negative or not finite, no
+    /// weight remains, or more than `MAX_POSITIONS` remain.
+    pub fn new(positions: &[Position]) -> Result<Self, ProofError> {
+        let invalid = |reason: String| ProofError::InvalidWitness { reason };
+        if let Some(p) = positions.iter().find(|p| !p.weight.is_finite() || p.weight < 0.0) {
+            return Err(invalid(format!("position {} has weight {}", p.asset, p.weight)));
+        }
+
+        let min_weight = EntropyConfig::default().min_weight;
+        let weights: Vec<f64> = positions.iter().map(|p| p.weight).filter(|&w| w >= min_weight).collect();
+        if weights.is_empty() {
+            return Err(invalid("portfolio has no weighted positions".to_string()));
+        }
+        if weights.len() > MAX_POSITIONS {
+            return Err(invalid(format!(
+                "{} positions exceed the circuit maximum of {}",
+                weights.len(),
+                MAX_POSITIONS
+            )));
+        }
+
+        // Floor each share, then hand the residue to the largest remainders
+        let sum: f64 = weights.iter().sum();
+        let exact: Vec<f64> = weights.iter().map(|w| w / sum * S as f64).collect();
+        let mut units: Vec<u64> = exact.iter().map(|x| x.floor() as u64).collect();
+        let mut by_remainder: Vec<usize> = (0..units.len()).collect();
+        by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+        let residue = FixedPoint::SCALE.saturating_sub(units.iter().sum());
+        for &i in by_remainder.iter().cycle().take(residue as usize) {
+            units[i] += 1;
+        }
+        units.resize(MAX_POSITIONS, 0);
+
+        let chords = chords();
+        Ok(Self {
+            terms: units.into_iter().map(|w| Term::new(w, &chords)).collect(),
+        })
+    }
+
+    /// Entropy the circuit computes, in fixed-point units
+    pub fn entropy_units(&self) -> u64 {
+        self.terms.iter().map(|t| t.term as u64).sum()
+    }
+
+    /// Entropy the circuit computes, in bits
+    pub fn entropy(&self) -> f64 {
+        self.entropy_units() as f64 / FixedPoint::SCALE as f64
+    }
+}
+
+/// Entropy under the circuit's approximation of `calculate_entropy`
+///
+/// A proof for `positions` can attest to any bound up to this value.
+pub fn reference_entropy(positions: &[Position]) -> Result<f64, ProofError> {
+    EntropyWitness::new(positions).map(|w| w.entropy())
+}
+
+/// Circuit proving portfolio entropy is at least a public bound
+///
+/// Weights are private; the bound and reporting period are the only public
+/// inputs.
+#[derive(Clone)]
+pub struct EntropyCircuit {
+    /// Private: Encoded weights and terms (`None` during setup)
+    pub witness: Option<EntropyWitness>,
+    /// Public: Lower bound on the entropy
+    pub bound: Option<Scalar>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<Scalar>,
+}
+
+impl EntropyCircuit {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank() -> Self {
+        Self {
+            witness: None,
+            bound: None,
+            period: None,
+        }
+    }
+}
+
+impl Circuit<Scalar> for EntropyCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        self.synthesize_with(cs, MAX_POSITIONS)
+    }
+}
+
+impl EntropyCircuit {
+    /// Synthesize with `positions` weight slots instead of `MAX_POSITIONS`
+    ///
+    /// Only `MAX_POSITIONS` matches the deployed parameters; other counts are
+    /// for sizing. A witness with a different slot count is unsatisfiable.
+    pub(crate) fn synthesize_with<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        positions: usize,
+    ) -> Result<(), SynthesisError> {
+        let chords = chords();
+        let w = self.witness.as_ref();
+        if w.is_some_and(|w| w.terms.len() != positions) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // Allocate public bound
+        let bound = cs.alloc_input(
+            || "bound",
+            || self.bound.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let mut total_weight = LinearCombination::zero();
+        let mut entropy = LinearCombination::zero();
+        for i in 0..positions {
+            let term = w.map(|w| &w.terms[i]);
+            let (weight, term) = enforce_term(cs.namespace(|| format!("position {}", i)), &chords, term)?;
+            total_weight = total_weight + weight;
+            entropy = entropy + term;
+        }
+
+        cs.enforce(
+            || "weights sum to scale",
+            |_| total_weight,
+            |lc| lc + CS::one(),
+            |lc| lc + (Scalar::from(FixedPoint::SCALE), CS::one()),
+        );
+
+        // entropy >= bound  <=>  entropy - bound fits in ENTROPY_BITS
+        let entropy_value = w.map(|w| Scalar::from(w.entropy_units()));
+        range_check(
+            cs.namespace(|| "at least bound"),
+            entropy - bound,
+            entropy_value.zip(self.bound).map(|(e, b)| e - b),
+            ENTROPY_BITS,
+        )
+    }
+}
+
+/// Allocate one weight and constrain its interpolated term
+///
+/// Returns the weight and term variables.
+fn enforce_term<CS: ConstraintSystem<Scalar>>(
+    mut cs: CS,
+    chords: &[Chord],
+    t: Option<&Term>,
+) -> Result<(Variable, Variable), SynthesisError> {
+    // Helper: allocate a private value derived from the term
+    macro_rules! witness {
+        ($name:expr, $value:expr) => {
+            cs.alloc(|| $name, || t.map($value).ok_or(SynthesisError::AssignmentMissing))?
+        };
+    }
+
+    let weight = witness!("weight", |t| Scalar::from(t.weight));
+
+    // Chord selector: boolean and one-hot
+    let mut selected = LinearCombination::zero();
+    let (mut lo, mut width, mut span, mut delta, mut scaled_value) = (
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+        LinearCombination::zero(),
+    );
+    for (k, chord) in chords.iter().enumerate() {
+        let bit = witness!(format!("chord {}", k), |t| {
+            if t.chord == k { Scalar::one() } else { Scalar::zero() }
+        });
+        cs.enforce(
+            || format!("chord {} boolean", k),
+            |lc| lc + bit,
+            |lc| lc + CS::one() - bit,
+            |lc| lc,
+        );
+        selected = selected + bit;
+        lo = lo + (scalar_from_i128(chord.lo), bit);
+        width = width + (scalar_from_i128(chord.width), bit);
+        span = span + (scalar_from_i128(chord.span), bit);
+        delta = delta + (scalar_from_i128(chord.delta), bit);
+        scaled_value = scaled_value + (scalar_from_i128(chord.value * chord.width), bit);
+    }
+    cs.enforce(|| "one chord", |_| selected, |lc| lc + CS::one(), |lc| lc + CS::one());
+
+    // Weight lies in the selected chord, which also bounds it to [0, S]
+    let offset = witness!("offset", |t| scalar_from_i128(t.offset));
+    cs.enforce(
+        || "chord offset",
+        |lc| lc + weight - &lo,
+        |lc| lc + CS::one(),
+        |lc| lc + offset,
+    );
+    range_check(
+        cs.namespace(|| "offset lower"),
+        LinearCombination::zero() + offset,
+        t.map(|t| scalar_from_i128(t.offset)),
+        SPAN_BITS,
+    )?;
+    range_check(
+        cs.namespace(|| "offset upper"),
+        span - CS::one() - offset,
+        t.map(|t| scalar_from_i128(chords[t.chord].span - 1 - t.offset)),
+        SPAN_BITS,
+    )?;
+
+    // Term: T·width + r = value·width + delta·offset, 0 <= r < width
+    let delta_offset = witness!("delta offset", |t| scalar_from_i128(chords[t.chord].delta * t.offset));
+    cs.enforce(|| "delta times offset", |_| delta, |lc| lc + offset, |lc| lc + delta_offset);
+    let term = witness!("term", |t| scalar_from_i128(t.term));
+    let term_rem = witness!("term remainder", |t| scalar_from_i128(t.term_rem));
+    cs.enforce(
+        || "term division",
+        |lc| lc + term,
+        |lc| lc + &width,
+        |lc| lc + &scaled_value + delta_offset - term_rem,
+    );
+    range_check(
+        cs.namespace(|| "term remainder lower"),
+        LinearCombination::zero() + term_rem,
+        t.map(|t| scalar_from_i128(t.term_rem)),
+        SPAN_BITS,
+    )?;
+    range_check(
+        cs.namespace(|| "term remainder upper"),
+        width - CS::one() - term_rem,
+        t.map(|t| scalar_from_i128(chords[t.chord].width - 1 - t.term_rem)),
+        SPAN_BITS,
+    )?;
+    range_check(
+        cs.namespace(|| "term range"),
+        LinearCombination::zero() + term,
+        t.map(|t| scalar_from_i128(t.term)),
+        TERM_BITS,
+    )?;
+
+    Ok((weight, term))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entropy::calculate_entropy;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    fn portfolio(weights: &[f64]) -> Vec<Position> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, &weight)| Position { asset: format!("A{}", i), weight })
+            .collect()
+    }
+
+    fn satisfied(positions: &[Position], bound: f64) -> bool {
+        let circuit = EntropyCircuit {
+            witness: Some(EntropyWitness::new(positions).unwrap()),
+            bound: Some(FixedPoint::encode(bound).unwrap()),
+            period: Some(Scalar::zero()),
+        };
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_reference_is_tight_lower_bound() {
+        let portfolios = [
+            vec![1.0],
+            vec![0.25; 4],
+            vec![1.0; 16],
+            vec![0.97, 0.01, 0.01, 0.01],
+            vec![0.5, 0.3, 0.15, 0.04, 0.009, 0.001],
+            vec![3.0, 1.0, 0.000002],
+        ];
+        for weights in &portfolios {
+            let positions = portfolio(weights);
+            let exact = calculate_entropy(&positions, &EntropyConfig::default());
+            let approx = reference_entropy(&positions).unwrap();
+            assert!(approx <= exact + 1e-5 && exact - approx < 0.01, "{:?}: exact {} approx {}", weights, exact, approx);
+        }
+    }
+
+    #[test]
+    fn test_bound_comparison() {
+        let positions = portfolio(&[0.3, 0.3, 0.2, 0.1, 0.1]);
+        let entropy = reference_entropy(&positions).unwrap();
+
+        assert!(satisfied(&positions, 2.0));
+        assert!(satisfied(&positions, entropy));
+        assert!(!satisfied(&positions, entropy + 1e-6));
+    }
+
+    #[test]
+    fn test_unprovable_portfolios_rejected() {
+        assert!(EntropyWitness::new(&[]).is_err());
+        assert!(EntropyWitness::new(&portfolio(&[0.5, -0.1])).is_err());
+        assert!(EntropyWitness::new(&portfolio(&[1.0; MAX_POSITIONS + 1])).is_err());
+        assert!(EntropyWitness::new(&portfolio(&[1.0; MAX_POSITIONS])).is_ok());
+    }
+}
@@ -0,0 +1,546 @@
+//! Proof Envelopes
+//!
+//! Self-describing wrapper for proofs in transit: which circuit and version
+//! produced them, their public inputs, when and by whom they were made, and
+//! an optional ed25519 signature.
+//!
+//! Signing also records the signer's public key, binding the envelope to the
+//! prover's libp2p identity: the key behind an ed25519 peer ID. Verifiers
+//! reject a bound envelope whose signature does not check under that key, and
+//! the ingestion layer compares the key with the peer that gossiped it.
+//!
+//! The proving backend is recorded too, since a BN254 proof and a BLS12-381
+//! proof of the same circuit are not interchangeable.
+//!
+//! Envelopes derive serde for JSON transport and also have a canonical byte
+//! encoding, which is what gets signed. All integers are big-endian:
+//!
+//! | Field          | Encoding                                   |
+//! |----------------|--------------------------------------------|
+//! | magic          | `OLPE`                                     |
+//! | circuit        | `u8` (`CircuitId::tag`)                    |
+//! | backend        | `u8` (`BackendId::tag`)                    |
+//! | version        | `u32`                                      |
+//! | created_at     | `u64` Unix epoch milliseconds              |
+//! | proving_ms     | `u8` flag, `u64` if present                |
+//! | prover         | `u16` length, UTF-8 bytes                  |
+//! | prover key     | `u8` flag, 32 bytes if present             |
+//! | public inputs  | `u8` count, `u64` fixed-point units each   |
+//! | commitment     | `u8` flag, 32 scalar bytes if present      |
+//! | period         | `u8` flag, `u64` if present                |
+//! | fingerprint    | `u8` flag, 32 bytes if present             |
+//! | proof          | `u16` length, compressed proof bytes       |
+//! | signature      | `u8` flag, 64 bytes if present (not signed)|
+
+use bellman::groth16::Proof;
+use bls12_381::{Bls12, Scalar};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::proofs::error::ProofError;
+use crate::proofs::prover::serialize_proof;
+use crate::proofs::verifier::VERIFYING_KEY_VERSION;
+
+/// Leading bytes of an encoded envelope
+const ENVELOPE_MAGIC: &[u8; 4] = b"OLPE";
+
+/// Circuit a proof was made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CircuitId {
+    /// `FragilityCircuit`: score and state commitment
+    Fragility,
+    /// `ThresholdCircuit`: score below a bound
+    Threshold,
+    /// `EntropyCircuit`: entropy at least a bound
+    Entropy,
+    /// `GroupSolvencyCircuit`: minimum ratio and group commitment
+    GroupSolvency,
+    /// `LcrCircuit`: liquidity coverage at least a multiplier
+    Lcr,
+}
+
+impl CircuitId {
+    /// Byte identifying the circuit in the canonical encoding
+    pub fn tag(self) -> u8 {
+        match self {
+            CircuitId::Fragility => 0,
+            CircuitId::Threshold => 1,
+            CircuitId::Entropy => 2,
+            CircuitId::GroupSolvency => 3,
+            CircuitId::Lcr => 4,
+        }
+    }
+
+    /// Circuit for a canonical tag
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(CircuitId::Fragility),
+            1 => Some(CircuitId::Threshold),
+            2 => Some(CircuitId::Entropy),
+            3 => Some(CircuitId::GroupSolvency),
+            4 => Some(CircuitId::Lcr),
+            _ => None,
+        }
+    }
+}
+
+/// Proving backend a proof was made with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BackendId {
+    /// bellman Groth16 over BLS12-381, `FragilityProver`
+    #[default]
+    Bellman,
+    /// arkworks Groth16 over BN254, `ArkworksProver`
+    Arkworks,
+}
+
+impl BackendId {
+    /// Byte identifying the backend in the canonical encoding
+    pub fn tag(self) -> u8 {
+        match self {
+            BackendId::Bellman => 0,
+            BackendId::Arkworks => 1,
+        }
+    }
+
+    /// Backend for a canonical tag
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(BackendId::Bellman),
+            1 => Some(BackendId::Arkworks),
+            _ => None,
+        }
+    }
+}
+
+/// Proof with the context needed to verify and attribute it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofEnvelope {
+    /// Compressed Groth16 proof (see `serialize_proof`)
+    pub proof: Vec<u8>,
+    /// Circuit the proof was made for
+    pub circuit: CircuitId,
+    /// Backend the proof was made with
+    #[serde(default)]
+    pub backend: BackendId,
+    /// `VERIFYING_KEY_VERSION` of the prover
+    pub circuit_version: u32,
+    /// Fixed-point public inputs: the score, threshold, entropy bound, or ratio
+    pub public_inputs: Vec<u64>,
+    /// `state_commitment` or `group_commitment` bytes, for the circuits that have one
+    pub commitment: Option<[u8; 32]>,
+    /// Reporting period the proof is bound to; `None` for unbound proofs
+    #[serde(default)]
+    pub period: Option<u64>,
+    /// `params_fingerprint` of the parameters the proof was made with
+    #[serde(default)]
+    pub params_fingerprint: Option<[u8; 32]>,
+    /// Timestamp (Unix epoch milliseconds)
+    pub created_at: u64,
+    /// Wall-clock time spent proving, in milliseconds
+    #[serde(default)]
+    pub proving_ms: Option<u64>,
+    /// Peer ID of the proving node
+    pub prover: String,
+    /// ed25519 public key behind `prover`, recorded by `sign`
+    #[serde(default)]
+    pub prover_key: Option<[u8; 32]>,
+    /// ed25519 signature over `signing_bytes`
+    pub signature: Option<Vec<u8>>,
+}
+
+impl ProofEnvelope {
+    /// Wrap `proof` at the current circuit version, timestamped now
+    pub fn new(
+        circuit: CircuitId,
+        proof: &Proof<Bls12>,
+        public_inputs: Vec<u64>,
+        commitment: Option<Scalar>,
+        prover: impl Into<String>,
+    ) -> Self {
+        Self::wrap(
+            BackendId::Bellman,
+            circuit,
+            serialize_proof(proof),
+            public_inputs,
+            commitment.map(|c| c.to_bytes()),
+            prover,
+        )
+    }
+
+    /// Wrap proof bytes from `backend`, as with `new`
+    pub(crate) fn wrap(
+        backend: BackendId,
+        circuit: CircuitId,
+        proof: Vec<u8>,
+        public_inputs: Vec<u64>,
+        commitment: Option<[u8; 32]>,
+        prover: impl Into<String>,
+    ) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            proof,
+            circuit,
+            backend,
+            circuit_version: VERIFYING_KEY_VERSION,
+            public_inputs,
+            commitment,
+            period: None,
+            params_fingerprint: None,
+            created_at,
+            proving_ms: None,
+            prover: prover.into(),
+            prover_key: None,
+            signature: None,
+        }
+    }
+
+    /// Fail unless the envelope was made for this build's circuits
+    ///
+    /// Cheap enough to run before any decoding or pairing work.
+    pub fn check_version(&self) -> Result<(), ProofError> {
+        if self.circuit_version != VERIFYING_KEY_VERSION {
+            return Err(ProofError::KeyVersionMismatch {
+                expected: VERIFYING_KEY_VERSION,
+                found: self.circuit_version,
+            });
+        }
+        Ok(())
+    }
+
+    /// Fail with `ProofError::BackendMismatch` unless made with `expected`
+    pub fn check_backend(&self, expected: BackendId) -> Result<(), ProofError> {
+        if self.backend != expected {
+            return Err(ProofError::BackendMismatch {
+                expected,
+                found: self.backend,
+            });
+        }
+        Ok(())
+    }
+
+    /// Public inputs as field elements, in circuit order
+    ///
+    /// Fragility and group solvency proofs need exactly one input and a
+    /// commitment; the others one input and no commitment. The period comes
+    /// last, 0 when unbound; `Some(0)` is rejected as ambiguous.
+    pub fn public_scalars(&self) -> Result<Vec<Scalar>, ProofError> {
+        self.public_scalars_in()
+    }
+
+    /// `public_scalars` in the field of another backend's curve
+    pub(crate) fn public_scalars_in<S: PrimeField>(&self) -> Result<Vec<S>, ProofError> {
+        let invalid = |reason: String| ProofError::PublicInputMismatch { reason };
+        if self.public_inputs.len() != 1 {
+            return Err(invalid(format!(
+                "{:?} proof needs 1 public input, got {}",
+                self.circuit,
+                self.public_inputs.len()
+            )));
+        }
+
+        let mut scalars = vec![S::from(self.public_inputs[0])];
+        let committed = matches!(self.circuit, CircuitId::Fragility | CircuitId::GroupSolvency);
+        match (committed, self.commitment) {
+            (true, Some(bytes)) => {
+                let mut repr = S::Repr::default();
+                repr.as_mut().copy_from_slice(&bytes);
+                let commitment = Option::<S>::from(S::from_repr(repr))
+                    .ok_or_else(|| invalid("commitment is not a canonical scalar".to_string()))?;
+                scalars.push(commitment);
+            }
+            (true, None) => return Err(invalid(format!("{:?} proof has no commitment", self.circuit))),
+            (false, Some(_)) => return Err(invalid(format!("{:?} proof carries a commitment", self.circuit))),
+            (false, None) => {}
+        }
+        match self.period {
+            Some(0) => return Err(invalid("period 0 is reserved for unbound proofs".to_string())),
+            period => scalars.push(S::from(period.unwrap_or(0))),
+        }
+        Ok(scalars)
+    }
+
+    /// Canonical encoding of everything but the signature
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.prover.len() + self.proof.len());
+        bytes.extend_from_slice(ENVELOPE_MAGIC);
+        bytes.push(self.circuit.tag());
+        bytes.push(self.backend.tag());
+        bytes.extend_from_slice(&self.circuit_version.to_be_bytes());
+        bytes.extend_from_slice(&self.created_at.to_be_bytes());
+        match self.proving_ms {
+            Some(ms) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&ms.to_be_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(self.prover.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(self.prover.as_bytes());
+        match &self.prover_key {
+            Some(key) => {
+                bytes.push(1);
+                bytes.extend_from_slice(key);
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(self.public_inputs.len() as u8);
+        for input in &self.public_inputs {
+            bytes.extend_from_slice(&input.to_be_bytes());
+        }
+        match &self.commitment {
+            Some(commitment) => {
+                bytes.push(1);
+                bytes.extend_from_slice(commitment);
+            }
+            None => bytes.push(0),
+        }
+        match self.period {
+            Some(period) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&period.to_be_bytes());
+            }
+            None => bytes.push(0),
+        }
+        match &self.params_fingerprint {
+            Some(fingerprint) => {
+                bytes.push(1);
+                bytes.extend_from_slice(fingerprint);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(self.proof.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.proof);
+        bytes
+    }
+
+    /// Canonical encoding including the signature
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.signing_bytes();
+        match &self.signature {
+            Some(signature) => {
+                bytes.push(1);
+                bytes.extend_from_slice(signature);
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    /// Decode an envelope written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        let mut r = Reader { bytes };
+        if r.take(4)? != ENVELOPE_MAGIC {
+            return Err(envelope_error("not a proof envelope"));
+        }
+        let tag = r.u8()?;
+        let circuit = CircuitId::from_tag(tag).ok_or_else(|| envelope_error(format!("unknown circuit {}", tag)))?;
+        let tag = r.u8()?;
+        let backend = BackendId::from_tag(tag).ok_or_else(|| envelope_error(format!("unknown backend {}", tag)))?;
+        let circuit_version = u32::from_be_bytes(r.array()?);
+        let created_at = u64::from_be_bytes(r.array()?);
+        let proving_ms = match r.u8()? {
+            0 => None,
+            _ => Some(u64::from_be_bytes(r.array()?)),
+        };
+        let prover_len = u16::from_be_bytes(r.array()?) as usize;
+        let prover = String::from_utf8(r.take(prover_len)?.to_vec())
+            .map_err(|_| envelope_error("prover id is not UTF-8"))?;
+        let prover_key = match r.u8()? {
+            0 => None,
+            _ => Some(r.array()?),
+        };
+        let count = r.u8()?;
+        let public_inputs = (0..count)
+            .map(|_| r.array().map(u64::from_be_bytes))
+            .collect::<Result<_, _>>()?;
+        let commitment = match r.u8()? {
+            0 => None,
+            _ => Some(r.array()?),
+        };
+        let period = match r.u8()? {
+            0 => None,
+            _ => Some(u64::from_be_bytes(r.array()?)),
+        };
+        let params_fingerprint = match r.u8()? {
+            0 => None,
+            _ => Some(r.array()?),
+        };
+        let proof_len = u16::from_be_bytes(r.array()?) as usize;
+        let proof = r.take(proof_len)?.to_vec();
+        let signature = match r.u8()? {
+            0 => None,
+            _ => Some(r.take(Signature::BYTE_SIZE)?.to_vec()),
+        };
+        if !r.bytes.is_empty() {
+            return Err(envelope_error(format!("{} trailing bytes", r.bytes.len())));
+        }
+
+        Ok(Self {
+            proof,
+            circuit,
+            backend,
+            circuit_version,
+            public_inputs,
+            commitment,
+            period,
+            params_fingerprint,
+            created_at,
+            proving_ms,
+            prover,
+            prover_key,
+            signature,
+        })
+    }
+
+    /// Bind the envelope to `key` and sign the canonical encoding
+    ///
+    /// Records the public key in `prover_key`, then replaces any previous
+    /// signature.
+    pub fn sign(&mut self, key: &SigningKey) {
+        self.prover_key = Some(key.verifying_key().to_bytes());
+        self.signature = Some(key.sign(&self.signing_bytes()).to_bytes().to_vec());
+    }
+
+    /// Whether the envelope carries a valid signature by `key`
+    pub fn verify_signature(&self, key: &VerifyingKey) -> bool {
+        let Some(bytes) = &self.signature else {
+            return false;
+        };
+        match Signature::from_slice(bytes) {
+            Ok(signature) => key.verify(&self.signing_bytes(), &signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Key the envelope is bound to, if it carries a valid signature by it
+    pub fn bound_identity(&self) -> Option<VerifyingKey> {
+        let key = VerifyingKey::from_bytes(self.prover_key.as_ref()?).ok()?;
+        self.verify_signature(&key).then_some(key)
+    }
+
+    /// Fail with `ProofError::IdentityMismatch` if the envelope names a
+    /// `prover_key` it is not signed by
+    ///
+    /// Envelopes with no `prover_key` are unbound and pass.
+    pub fn check_identity(&self) -> Result<(), ProofError> {
+        if self.prover_key.is_some() && self.bound_identity().is_none() {
+            return Err(ProofError::IdentityMismatch {
+                prover: self.prover.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn envelope_error(reason: impl Into<String>) -> ProofError {
+    ProofError::InvalidEnvelope { reason: reason.into() }
+}
+
+/// Cursor over canonical envelope bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ProofError> {
+        if self.bytes.len() < n {
+            return Err(envelope_error("truncated envelope"));
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ProofError> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, ProofError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proofs::prover::PROOF_BYTES;
+    use rand::rngs::OsRng;
+
+    /// Envelope around placeholder proof bytes
+    fn envelope() -> ProofEnvelope {
+        ProofEnvelope {
+            proof: vec![7u8; PROOF_BYTES],
+            circuit: CircuitId::Fragility,
+            backend: BackendId::Bellman,
+            circuit_version: VERIFYING_KEY_VERSION,
+            public_inputs: vec![12_345_678],
+            commitment: Some(Scalar::from(42u64).to_bytes()),
+            period: Some(96),
+            params_fingerprint: Some([3u8; 32]),
+            created_at: 1_700_000_000_000,
+            proving_ms: Some(212),
+            prover: "12D3KooWtest".to_string(),
+            prover_key: None,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_canonical_encoding_round_trips() {
+        let mut env = envelope();
+        assert_eq!(ProofEnvelope::from_bytes(&env.to_bytes()).unwrap(), env);
+
+        env.sign(&SigningKey::generate(&mut OsRng));
+        let bytes = env.to_bytes();
+        assert_eq!(ProofEnvelope::from_bytes(&bytes).unwrap(), env);
+        let json = serde_json::to_string(&env).unwrap();
+        assert_eq!(serde_json::from_str::<ProofEnvelope>(&json).unwrap(), env);
+
+        assert!(ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        for offset in [4, 5] {
+            let mut unknown = bytes.clone();
+            unknown[offset] = 9;
+            assert!(matches!(ProofEnvelope::from_bytes(&unknown), Err(ProofError::InvalidEnvelope { .. })));
+        }
+    }
+
+    #[test]
+    fn test_signature_covers_every_field() {
+        let key = SigningKey::generate(&mut OsRng);
+        let mut env = envelope();
+        assert!(!env.verify_signature(&key.verifying_key()));
+        env.sign(&key);
+        assert!(env.verify_signature(&key.verifying_key()));
+        assert!(!env.verify_signature(&SigningKey::generate(&mut OsRng).verifying_key()));
+        assert_eq!(env.bound_identity(), Some(key.verifying_key()));
+
+        let tampered = [
+            ProofEnvelope { public_inputs: vec![12_345_679], ..env.clone() },
+            ProofEnvelope { backend: BackendId::Arkworks, ..env.clone() },
+            ProofEnvelope { created_at: env.created_at + 1, ..env.clone() },
+            ProofEnvelope { proving_ms: None, ..env.clone() },
+            ProofEnvelope { period: Some(97), ..env.clone() },
+            ProofEnvelope { params_fingerprint: None, ..env.clone() },
+            ProofEnvelope { prover: "12D3KooWother".to_string(), ..env.clone() },
+            ProofEnvelope { prover_key: None, ..env.clone() },
+            ProofEnvelope { circuit_version: VERIFYING_KEY_VERSION + 1, ..env.clone() },
+        ];
+        for t in &tampered {
+            assert!(!t.verify_signature(&key.verifying_key()));
+        }
+
+        // Claiming the proof for another key without its signature
+        let other = SigningKey::generate(&mut OsRng).verifying_key();
+        let rebound = ProofEnvelope { prover_key: Some(other.to_bytes()), ..env.clone() };
+        assert!(rebound.bound_identity().is_none());
+        assert!(matches!(rebound.check_identity(), Err(ProofError::IdentityMismatch { .. })));
+        assert!(env.check_identity().is_ok());
+        assert!(envelope().check_identity().is_ok());
+    }
+}
@@ -0,0 +1,143 @@
+//! Proof Error Types
+//!
+//! Failures surfaced by setup, proving, verification, and parameter or proof
+//! (de)serialization. Variants separate caller mistakes (bad witness or
+//! inputs) from damaged artifacts (corrupt parameters or proof bytes).
+
+use bellman::{SynthesisError, VerificationError};
+use std::fmt;
+use std::io;
+
+use crate::proofs::encoding::EncodingError;
+use crate::proofs::envelope::{BackendId, CircuitId};
+
+/// Errors produced by the proof system
+#[derive(Debug)]
+pub enum ProofError {
+    /// Constraint synthesis failed during setup or proving
+    Synthesis(SynthesisError),
+    /// The bank state cannot back the requested statement
+    InvalidWitness { reason: String },
+    /// An amount or public value has no fixed-point encoding
+    Encoding(EncodingError),
+    /// Reading or writing parameters or keys failed
+    ParameterIo(io::Error),
+    /// Parameter bytes could not be decoded (truncated, corrupt, or not Groth16
+    /// parameters for this curve)
+    InvalidParameters { reason: String },
+    /// Proof bytes could not be decoded
+    InvalidProof { reason: String },
+    /// Public inputs do not fit the circuit they are checked against
+    PublicInputMismatch { reason: String },
+    /// Proof envelope bytes could not be decoded
+    InvalidEnvelope { reason: String },
+    /// Proof is bound to another reporting period than the one expected
+    PeriodMismatch { expected: Option<u64>, found: Option<u64> },
+    /// A proof from this prover for this period was already accepted
+    Replayed { prover: String, period: u64 },
+    /// Envelope is not signed by the identity it is bound to
+    IdentityMismatch { prover: String },
+    /// A trusted setup contribution does not connect the parameters it claims to
+    InvalidContribution { reason: String },
+    /// Parameters differ from the ones expected, by `params_fingerprint`
+    ParameterMismatch { expected: [u8; 32], actual: [u8; 32] },
+    /// Proofs for this circuit version have been retired
+    DeprecatedCircuit { circuit: CircuitId, version: u32 },
+    /// Exported verifying key was made for another circuit version
+    KeyVersionMismatch { expected: u32, found: u32 },
+    /// Proof was made with another proving backend than the verifier's
+    BackendMismatch { expected: BackendId, found: BackendId },
+    /// A background proving task panicked or was cancelled
+    ProvingTask { reason: String },
+    /// The verifier could not evaluate the proof
+    ///
+    /// A proof that evaluates but does not check out is reported as
+    /// `Ok(false)`, not as this error.
+    VerificationFailed(VerificationError),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Synthesis(source) => write!(f, "circuit synthesis failed: {}", source),
+            ProofError::InvalidWitness { reason } => write!(f, "invalid witness: {}", reason),
+            ProofError::Encoding(source) => write!(f, "encoding failed: {}", source),
+            ProofError::ParameterIo(source) => write!(f, "proof parameter I/O failed: {}", source),
+            ProofError::InvalidParameters { reason } => {
+                write!(f, "invalid proving parameters: {}", reason)
+            }
+            ProofError::InvalidProof { reason } => write!(f, "invalid proof encoding: {}", reason),
+            ProofError::PublicInputMismatch { reason } => {
+                write!(f, "public inputs do not match the circuit: {}", reason)
+            }
+            ProofError::InvalidEnvelope { reason } => write!(f, "invalid proof envelope: {}", reason),
+            ProofError::PeriodMismatch { expected, found } => write!(
+                f,
+                "proof is for {} but {} was expected",
+                period_name(*found),
+                period_name(*expected)
+            ),
+            ProofError::Replayed { prover, period } => {
+                write!(f, "proof from {} for period {} was already seen", prover, period)
+            }
+            ProofError::IdentityMismatch { prover } => {
+                write!(f, "proof is not signed by the identity of {}", prover)
+            }
+            ProofError::InvalidContribution { reason } => write!(f, "invalid setup contribution: {}", reason),
+            ProofError::ParameterMismatch { expected, actual } => write!(
+                f,
+                "parameter fingerprint {} does not match expected {}",
+                hex::encode(actual),
+                hex::encode(expected)
+            ),
+            ProofError::DeprecatedCircuit { circuit, version } => {
+                write!(f, "{:?} circuit version {} is deprecated", circuit, version)
+            }
+            ProofError::KeyVersionMismatch { expected, found } => write!(
+                f,
+                "verifying key version {} does not match circuit version {}",
+                found, expected
+            ),
+            ProofError::BackendMismatch { expected, found } => {
+                write!(f, "proof was made with the {:?} backend, not {:?}", found, expected)
+            }
+            ProofError::ProvingTask { reason } => write!(f, "proving task failed: {}", reason),
+            ProofError::VerificationFailed(source) => write!(f, "verification failed: {}", source),
+        }
+    }
+}
+
+/// `"period N"`, or `"no period"` for unbound proofs
+fn period_name(period: Option<u64>) -> String {
+    period.map_or_else(|| "no period".to_string(), |p| format!("period {}", p))
+}
+
+impl std::error::Error for ProofError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProofError::Synthesis(source) => Some(source),
+            ProofError::Encoding(source) => Some(source),
+            ProofError::ParameterIo(source) => Some(source),
+            ProofError::VerificationFailed(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ProofError {
+    fn from(source: io::Error) -> Self {
+        ProofError::ParameterIo(source)
+    }
+}
+
+impl From<SynthesisError> for ProofError {
+    fn from(source: SynthesisError) -> Self {
+        ProofError::Synthesis(source)
+    }
+}
+
+impl From<EncodingError> for ProofError {
+    fn from(source: EncodingError) -> Self {
+        ProofError::Encoding(source)
+    }
+}
@@ -0,0 +1,216 @@
+//! Circuit Cost Estimates
+//!
+//! Sizes a circuit layout before committing to a setup. `estimate_circuit`
+//! synthesizes the blank circuit into a counting constraint system, so it
+//! never generates parameters, and scales setup and proving times measured
+//! once per process on a small calibration circuit.
+//!
+//! Time estimates extrapolate linearly from roughly 2,000 constraints. The
+//! multi-exponentiations dominate and scale close to linearly, but the FFTs
+//! do not, so treat the durations as a guide for choosing precision rather
+//! than a benchmark.
+
+use bellman::groth16::{create_random_proof, generate_random_parameters};
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError};
+use bls12_381::{Bls12, Scalar};
+use rand::rngs::OsRng;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::core::lagrangian::LagrangianConfig;
+use crate::proofs::circuit::{FragilityCircuit, ThresholdCircuit};
+use crate::proofs::entropy_circuit::{EntropyCircuit, MAX_POSITIONS};
+use crate::proofs::envelope::CircuitId;
+use crate::proofs::gadgets::range_check;
+use crate::proofs::group_circuit::GroupSolvencyCircuit;
+use crate::proofs::lcr_circuit::LcrCircuit;
+use crate::proofs::stats::Counter;
+
+/// Range checks in the calibration circuit
+const CALIBRATION_CHECKS: usize = 8;
+
+/// Width of each calibration range check
+const CALIBRATION_BITS: usize = 250;
+
+/// Layout to estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CircuitConfig {
+    /// Circuit to size
+    pub circuit: CircuitId,
+    /// Width of amount range checks; widths derived from it keep their
+    /// headroom over it. Ignored by the entropy circuit.
+    pub precision_bits: usize,
+    /// Weight slots in the entropy circuit; ignored by the others
+    pub max_positions: usize,
+}
+
+impl CircuitConfig {
+    /// Deployed layout of `circuit`
+    pub fn new(circuit: CircuitId) -> Self {
+        Self {
+            circuit,
+            precision_bits: 64,
+            max_positions: MAX_POSITIONS,
+        }
+    }
+
+    /// Range-check amounts to `precision_bits`
+    pub fn with_precision_bits(mut self, precision_bits: usize) -> Self {
+        self.precision_bits = precision_bits;
+        self
+    }
+
+    /// Give the entropy circuit `max_positions` weight slots
+    pub fn with_max_positions(mut self, max_positions: usize) -> Self {
+        self.max_positions = max_positions;
+        self
+    }
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self::new(CircuitId::Fragility)
+    }
+}
+
+/// Size and cost of a circuit layout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CircuitEstimate {
+    /// Circuit sized
+    pub circuit: CircuitId,
+    /// R1CS constraints
+    pub constraints: usize,
+    /// Private variables
+    pub variables: usize,
+    /// Public inputs, not counting the constant one
+    pub inputs: usize,
+    /// Extrapolated parameter generation time
+    pub setup: Duration,
+    /// Extrapolated time for one proof
+    pub proving: Duration,
+}
+
+/// Count constraints and variables of `config` and extrapolate its costs
+///
+/// The first call in a process runs the calibration setup and proof, which
+/// takes a moment. Panics if `precision_bits` pushes a range check to the
+/// field size.
+pub fn estimate_circuit(config: &CircuitConfig) -> CircuitEstimate {
+    let mut counter = Counter::default();
+    let bits = config.precision_bits;
+    // Counting never evaluates assignments, so synthesis cannot fail on them
+    match config.circuit {
+        CircuitId::Fragility => FragilityCircuit::blank(LagrangianConfig::default()).synthesize_with(&mut counter, bits),
+        CircuitId::Threshold => ThresholdCircuit::blank(LagrangianConfig::default()).synthesize_with(&mut counter, bits),
+        CircuitId::Entropy => EntropyCircuit::blank().synthesize_with(&mut counter, config.max_positions),
+        CircuitId::GroupSolvency => GroupSolvencyCircuit::blank().synthesize_with(&mut counter, bits),
+        CircuitId::Lcr => LcrCircuit::blank().synthesize_with(&mut counter, bits),
+    }
+    .ok();
+
+    let (setup, proving) = calibration();
+    let scale = counter.constraints as u32;
+    CircuitEstimate {
+        circuit: config.circuit,
+        constraints: counter.constraints,
+        variables: counter.aux,
+        inputs: counter.inputs,
+        setup: setup * scale,
+        proving: proving * scale,
+    }
+}
+
+/// Setup and proving time per constraint, measured once
+fn calibration() -> (Duration, Duration) {
+    static PER_CONSTRAINT: OnceLock<(Duration, Duration)> = OnceLock::new();
+    *PER_CONSTRAINT.get_or_init(|| {
+        let mut counter = Counter::default();
+        Calibration::blank().synthesize(&mut counter).ok();
+        let constraints = counter.constraints as u32;
+
+        let start = Instant::now();
+        let params = generate_random_parameters::<Bls12, _, _>(Calibration::blank(), &mut OsRng)
+            .expect("calibration setup");
+        let setup = start.elapsed();
+        let start = Instant::now();
+        create_random_proof(Calibration::assigned(), &params, &mut OsRng).expect("calibration proof");
+        let proving = start.elapsed();
+        (setup / constraints, proving / constraints)
+    })
+}
+
+/// Range checks standing in for a circuit of known size
+struct Calibration {
+    values: Option<[u64; CALIBRATION_CHECKS]>,
+}
+
+impl Calibration {
+    fn blank() -> Self {
+        Self { values: None }
+    }
+
+    fn assigned() -> Self {
+        Self {
+            values: Some(std::array::from_fn(|i| u64::MAX - i as u64)),
+        }
+    }
+}
+
+impl Circuit<Scalar> for Calibration {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        for i in 0..CALIBRATION_CHECKS {
+            let value = self.values.map(|v| Scalar::from(v[i]));
+            let var = cs.alloc(
+                || format!("value {}", i),
+                || value.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+            range_check(
+                cs.namespace(|| format!("value {} range", i)),
+                LinearCombination::zero() + var,
+                value,
+                CALIBRATION_BITS,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints(config: CircuitConfig) -> usize {
+        estimate_circuit(&config).constraints
+    }
+
+    #[test]
+    fn test_constraints_grow_linearly_with_precision() {
+        let config = CircuitConfig::default();
+        let [c32, c64, c128] = [32, 64, 128].map(|bits| constraints(config.with_precision_bits(bits)));
+        // Every range check tracks the amount width, so doubling it adds
+        // twice what the previous doubling did
+        assert!(c32 < c64 && c64 < c128);
+        assert_eq!(c128 - c64, 2 * (c64 - c32));
+
+        let mut deployed = Counter::default();
+        FragilityCircuit::blank(LagrangianConfig::default()).synthesize(&mut deployed).ok();
+        assert_eq!(c64, deployed.constraints);
+    }
+
+    #[test]
+    fn test_estimate_reports_counts_and_costs() {
+        let estimate = estimate_circuit(&CircuitConfig::new(CircuitId::Lcr));
+        assert_eq!(estimate.circuit, CircuitId::Lcr);
+        assert_eq!(estimate.inputs, 2);
+        assert!(estimate.constraints > 0 && estimate.variables > 0);
+        assert!(estimate.setup > Duration::ZERO && estimate.proving > Duration::ZERO);
+
+        let wider = estimate_circuit(&CircuitConfig::new(CircuitId::Lcr).with_precision_bits(128));
+        assert!(wider.constraints > estimate.constraints);
+        assert!(wider.proving >= estimate.proving);
+
+        let entropy = CircuitConfig::new(CircuitId::Entropy);
+        assert!(constraints(entropy.with_max_positions(2 * MAX_POSITIONS)) > constraints(entropy));
+    }
+}
@@ -0,0 +1,275 @@
+//! EVM Export
+//!
+//! snarkjs-style JSON for BN254 verifying keys and proofs, so third parties
+//! can check fragility proofs from `ArkworksProver` in a smart contract. EVM
+//! pairing precompiles only support BN254, so proofs from the default
+//! BLS12-381 backend cannot be exported.
+//!
+//! Points are affine and uncompressed, every coordinate a decimal string,
+//! with snarkjs' projective `z` appended: `"1"` for G1 and `["1", "0"]` for
+//! G2, or all-zero-but-`y` for the point at infinity. G2 coordinates are
+//! `[c0, c1]` pairs, as in snarkjs' `verification_key.json`; calldata for the
+//! `ecPairing` precompile takes each pair as `c1, c0`.
+//!
+//! `import_vk_evm_json` and `import_proof_evm_json` read the same layout
+//! back, rejecting points that are off the curve or outside the prime-order
+//! subgroup.
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::PrimeField;
+use ark_groth16::{Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::proofs::arkworks::{to_ark, ArkworksProver, Bn254Scalar};
+use crate::proofs::envelope::{BackendId, ProofEnvelope};
+use crate::proofs::error::ProofError;
+
+/// Affine G1 point: `[x, y, z]`
+type G1Json = [String; 3];
+
+/// Affine G2 point: `[[x.c0, x.c1], [y.c0, y.c1], [z.c0, z.c1]]`
+type G2Json = [[String; 2]; 3];
+
+/// `verification_key.json` as written by snarkjs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvmVerifyingKey {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: G1Json,
+    pub vk_beta_2: G2Json,
+    pub vk_gamma_2: G2Json,
+    pub vk_delta_2: G2Json,
+    #[serde(rename = "IC")]
+    pub ic: Vec<G1Json>,
+}
+
+/// `proof.json` as written by snarkjs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvmProofPoints {
+    pub pi_a: G1Json,
+    pub pi_b: G2Json,
+    pub pi_c: G1Json,
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// Proof with its public signals, as returned by snarkjs `fullProve`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvmProof {
+    pub proof: EvmProofPoints,
+    #[serde(rename = "publicSignals")]
+    pub public_signals: Vec<String>,
+}
+
+/// Protocol name snarkjs records
+const PROTOCOL: &str = "groth16";
+
+/// snarkjs' name for BN254
+const CURVE: &str = "bn128";
+
+fn decimal<F: PrimeField>(value: &F) -> String {
+    value.into_bigint().to_string()
+}
+
+fn g1_json(point: &G1Affine) -> G1Json {
+    if point.infinity {
+        return ["0".into(), "1".into(), "0".into()];
+    }
+    [decimal(&point.x), decimal(&point.y), "1".into()]
+}
+
+fn g2_json(point: &G2Affine) -> G2Json {
+    let pair = |c: &Fq2| [decimal(&c.c0), decimal(&c.c1)];
+    if point.infinity {
+        return [["0".into(), "0".into()], ["1".into(), "0".into()], ["0".into(), "0".into()]];
+    }
+    [pair(&point.x), pair(&point.y), ["1".into(), "0".into()]]
+}
+
+/// Parse a canonical decimal field element
+fn parse<F: PrimeField + FromStr>(value: &str) -> Result<F, String> {
+    let parsed = F::from_str(value).map_err(|_| format!("{:?} is not a decimal field element", value))?;
+    // `from_str` reduces; anything but the canonical form is a different encoding
+    if decimal(&parsed) != value {
+        return Err(format!("{:?} is not a canonical field element", value));
+    }
+    Ok(parsed)
+}
+
+fn parse_g1(json: &G1Json) -> Result<G1Affine, String> {
+    if json[2] == "0" {
+        return Ok(G1Affine::identity());
+    }
+    if json[2] != "1" {
+        return Err(format!("G1 point is not affine (z = {})", json[2]));
+    }
+    let point = G1Affine::new_unchecked(parse::<Fq>(&json[0])?, parse::<Fq>(&json[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G1 point is not in the BN254 group".to_string());
+    }
+    Ok(point)
+}
+
+fn parse_g2(json: &G2Json) -> Result<G2Affine, String> {
+    let pair = |c: &[String; 2]| -> Result<Fq2, String> { Ok(Fq2::new(parse::<Fq>(&c[0])?, parse::<Fq>(&c[1])?)) };
+    match [json[2][0].as_str(), json[2][1].as_str()] {
+        ["0", "0"] => return Ok(G2Affine::identity()),
+        ["1", "0"] => {}
+        z => return Err(format!("G2 point is not affine (z = {:?})", z)),
+    }
+    let point = G2Affine::new_unchecked(pair(&json[0])?, pair(&json[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G2 point is not in the BN254 group".to_string());
+    }
+    Ok(point)
+}
+
+fn check_header(protocol: &str, curve: &str) -> Result<(), String> {
+    if protocol != PROTOCOL || curve != CURVE {
+        return Err(format!("expected {} over {}, got {} over {}", PROTOCOL, CURVE, protocol, curve));
+    }
+    Ok(())
+}
+
+impl ArkworksProver {
+    /// Verifying key as snarkjs `verification_key.json`
+    pub fn export_vk_evm_json(&self) -> Result<String, ProofError> {
+        let vk = self.groth16_vk();
+        let json = EvmVerifyingKey {
+            protocol: PROTOCOL.to_string(),
+            curve: CURVE.to_string(),
+            n_public: vk.gamma_abc_g1.len() - 1,
+            vk_alpha_1: g1_json(&vk.alpha_g1),
+            vk_beta_2: g2_json(&vk.beta_g2),
+            vk_gamma_2: g2_json(&vk.gamma_g2),
+            vk_delta_2: g2_json(&vk.delta_g2),
+            ic: vk.gamma_abc_g1.iter().map(g1_json).collect(),
+        };
+        serde_json::to_string_pretty(&json).map_err(|e| ProofError::InvalidParameters { reason: e.to_string() })
+    }
+}
+
+/// Proof and public inputs of a BN254 envelope as snarkjs JSON
+///
+/// Fails with `ProofError::BackendMismatch` for envelopes from any other
+/// backend. Public signals are in circuit order, period last.
+pub fn export_proof_evm_json(envelope: &ProofEnvelope) -> Result<String, ProofError> {
+    envelope.check_backend(BackendId::Arkworks)?;
+    let proof = ArkworksProver::deserialize_proof(&envelope.proof)?;
+    let public_signals = envelope
+        .public_scalars_in::<Bn254Scalar>()?
+        .into_iter()
+        .map(|s| decimal(&to_ark(s)))
+        .collect();
+    let json = EvmProof {
+        proof: EvmProofPoints {
+            pi_a: g1_json(&proof.a),
+            pi_b: g2_json(&proof.b),
+            pi_c: g1_json(&proof.c),
+            protocol: PROTOCOL.to_string(),
+            curve: CURVE.to_string(),
+        },
+        public_signals,
+    };
+    serde_json::to_string_pretty(&json).map_err(|e| ProofError::InvalidProof { reason: e.to_string() })
+}
+
+/// Verifying key from `export_vk_evm_json` output
+pub fn import_vk_evm_json(json: &str) -> Result<VerifyingKey<Bn254>, ProofError> {
+    let invalid = |reason: String| ProofError::InvalidParameters { reason };
+    let vk: EvmVerifyingKey = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    check_header(&vk.protocol, &vk.curve).map_err(invalid)?;
+    if vk.ic.len() != vk.n_public + 1 {
+        return Err(invalid(format!("{} IC points for {} public inputs", vk.ic.len(), vk.n_public)));
+    }
+    Ok(VerifyingKey {
+        alpha_g1: parse_g1(&vk.vk_alpha_1).map_err(invalid)?,
+        beta_g2: parse_g2(&vk.vk_beta_2).map_err(invalid)?,
+        gamma_g2: parse_g2(&vk.vk_gamma_2).map_err(invalid)?,
+        delta_g2: parse_g2(&vk.vk_delta_2).map_err(invalid)?,
+        gamma_abc_g1: vk.ic.iter().map(parse_g1).collect::<Result<_, _>>().map_err(invalid)?,
+    })
+}
+
+/// Proof and public inputs from `export_proof_evm_json` output
+pub fn import_proof_evm_json(json: &str) -> Result<(Proof<Bn254>, Vec<Fr>), ProofError> {
+    let invalid = |reason: String| ProofError::InvalidProof { reason };
+    let EvmProof { proof, public_signals } = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    check_header(&proof.protocol, &proof.curve).map_err(invalid)?;
+    let inputs = public_signals
+        .iter()
+        .map(|s| parse::<Fr>(s))
+        .collect::<Result<_, _>>()
+        .map_err(invalid)?;
+    let proof = Proof {
+        a: parse_g1(&proof.pi_a).map_err(invalid)?,
+        b: parse_g2(&proof.pi_b).map_err(invalid)?,
+        c: parse_g1(&proof.pi_c).map_err(invalid)?,
+    };
+    Ok((proof, inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::proofs::arkworks::test_ark_prover;
+    use crate::proofs::backend::ProvingBackend;
+    use crate::proofs::circuit::reference_fragility;
+    use ark_groth16::Groth16;
+    use ark_snark::SNARK;
+
+    fn envelope() -> ProofEnvelope {
+        let state = BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        test_ark_prover().prove_enveloped(&state, fragility, "12D3KooWevm", Some(96)).unwrap()
+    }
+
+    #[test]
+    fn test_json_round_trips_and_verifies() {
+        let vk_json = test_ark_prover().export_vk_evm_json().unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&vk_json).unwrap();
+        assert_eq!(parsed["nPublic"], 3);
+        assert_eq!(parsed["vk_alpha_1"][2], "1");
+        let vk = import_vk_evm_json(&vk_json).unwrap();
+        assert_eq!(&vk, test_ark_prover().groth16_vk());
+
+        let envelope = envelope();
+        let (proof, inputs) = import_proof_evm_json(&export_proof_evm_json(&envelope).unwrap()).unwrap();
+        assert_eq!(proof, ArkworksProver::deserialize_proof(&envelope.proof).unwrap());
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(decimal(&inputs[2]), "96");
+        assert!(Groth16::<Bn254>::verify(&vk, &inputs, &proof).unwrap());
+
+        let mut wrong = inputs.clone();
+        wrong[0] += Fr::from(1u64);
+        assert!(!Groth16::<Bn254>::verify(&vk, &wrong, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_json_rejected() {
+        let envelope = envelope();
+        let json = export_proof_evm_json(&envelope).unwrap();
+        let mut value: EvmProof = serde_json::from_str(&json).unwrap();
+
+        // Off the curve
+        value.proof.pi_a[1] = "2".to_string();
+        let off_curve = serde_json::to_string(&value).unwrap();
+        assert!(matches!(import_proof_evm_json(&off_curve), Err(ProofError::InvalidProof { .. })));
+        // Non-canonical signal
+        let mut padded: EvmProof = serde_json::from_str(&json).unwrap();
+        padded.public_signals[2] = "096".to_string();
+        assert!(import_proof_evm_json(&serde_json::to_string(&padded).unwrap()).is_err());
+
+        let bellman = ProofEnvelope { backend: BackendId::Bellman, ..envelope };
+        assert!(matches!(export_proof_evm_json(&bellman), Err(ProofError::BackendMismatch { .. })));
+    }
+}
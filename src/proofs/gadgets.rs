@@ -0,0 +1,237 @@
+//! Circuit Gadgets
+//!
+//! Reusable constraint fragments for the fragility circuits.
+//!
+//! `mimc_compress` is a MiMC permutation with exponent 5 (coprime to the
+//! BLS12-381 scalar field order minus one) and `MIMC_ROUNDS` rounds, keyed and
+//! fed forward in Miyaguchi–Preneel mode. Round constants are SHA-512 digests
+//! of a fixed tag, so nothing about them is chosen by hand. Each compression
+//! costs three constraints per round.
+//!
+//! The gadgets are generic over `CircuitField` so the fragility circuit can
+//! also be synthesized over the BN254 scalar field, where exponent 5 is a
+//! permutation as well. Everything else uses the BLS12-381 `Scalar`.
+
+use bellman::{ConstraintSystem, LinearCombination, SynthesisError};
+use bls12_381::Scalar;
+use ff::PrimeField;
+use sha2::{Digest, Sha512};
+use std::sync::OnceLock;
+
+/// MiMC rounds: `ceil(log5 p)` for the 255-bit scalar field
+pub const MIMC_ROUNDS: usize = 110;
+
+/// Scalar field the gadgets can be synthesized over
+///
+/// `Repr` must be little-endian, which is how `range_check` reads bits.
+pub trait CircuitField: PrimeField {
+    /// MiMC round constants reduced into this field, computed once
+    fn mimc_constants() -> &'static [Self];
+}
+
+impl CircuitField for Scalar {
+    fn mimc_constants() -> &'static [Self] {
+        static CONSTANTS: OnceLock<Vec<Scalar>> = OnceLock::new();
+        CONSTANTS.get_or_init(mimc_constants)
+    }
+}
+
+/// Field element for a signed integer
+pub(crate) fn scalar_from_i128<S: PrimeField>(value: i128) -> S {
+    let scalar = S::from_u128(value.unsigned_abs());
+    if value < 0 {
+        -scalar
+    } else {
+        scalar
+    }
+}
+
+/// Field element for an unsigned integer
+pub(crate) fn scalar_from_u128<S: PrimeField>(value: u128) -> S {
+    S::from_u128(value)
+}
+
+/// Constrain `value` to `[0, 2^bits)` by decomposing it into boolean bits
+///
+/// Costs `bits + 1` constraints. `assignment` is the prover's value of the
+/// combination; a value outside the range cannot be decomposed, so the
+/// resulting proof does not verify.
+pub fn range_check<S: CircuitField, CS: ConstraintSystem<S>>(
+    mut cs: CS,
+    value: LinearCombination<S>,
+    assignment: Option<S>,
+    bits: usize,
+) -> Result<(), SynthesisError> {
+    assert!(bits < S::CAPACITY as usize, "range check must stay below the field size");
+    let bytes = assignment.map(|v| v.to_repr());
+
+    let mut packed = LinearCombination::zero();
+    let mut coeff = S::ONE;
+    for i in 0..bits {
+        let bit_value = bytes.as_ref().map(|b| (b.as_ref()[i / 8] >> (i % 8)) & 1 == 1);
+        let bit = cs.alloc(
+            || format!("bit {}", i),
+            || {
+                bit_value
+                    .map(|b| if b { S::ONE } else { S::ZERO })
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+        )?;
+        // bit * (1 - bit) = 0
+        cs.enforce(
+            || format!("bit {} boolean", i),
+            |lc| lc + bit,
+            |lc| lc + CS::one() - bit,
+            |lc| lc,
+        );
+        packed = packed + (coeff, bit);
+        coeff = coeff.double();
+    }
+
+    cs.enforce(|| "packing", |_| packed, |lc| lc + CS::one(), |_| value);
+    Ok(())
+}
+
+/// MiMC round constants for `CircuitField::mimc_constants`
+///
+/// Each digest is read as a little-endian 512-bit integer and reduced into
+/// the field.
+pub(crate) fn mimc_constants<S: PrimeField>() -> Vec<S> {
+    let byte = S::from(256);
+    (0..MIMC_ROUNDS)
+        .map(|round| {
+            let digest = Sha512::new()
+                .chain_update(b"olo-core mimc5")
+                .chain_update((round as u64).to_le_bytes())
+                .finalize();
+            digest.iter().rev().fold(S::ZERO, |acc, &b| acc * byte + S::from(u64::from(b)))
+        })
+        .collect()
+}
+
+/// Native `mimc_compress`: `E_key(msg) + key + msg`
+pub fn mimc_compress_native<S: CircuitField>(key: S, msg: S) -> S {
+    let mut x = msg;
+    for c in S::mimc_constants() {
+        let t = x + key + c;
+        let t2 = t.square();
+        x = t2.square() * t;
+    }
+    x + key + key + msg
+}
+
+/// Chain `mimc_compress_native` over `inputs`, starting from a zero key
+pub fn mimc_hash<S: CircuitField>(inputs: &[S]) -> S {
+    inputs
+        .iter()
+        .fold(S::ZERO, |h, &m| mimc_compress_native(h, m))
+}
+
+/// Constrain one Miyaguchi–Preneel compression of `msg` under `key`
+///
+/// Returns the output combination and its value; matches
+/// `mimc_compress_native`.
+pub fn mimc_compress<S: CircuitField, CS: ConstraintSystem<S>>(
+    mut cs: CS,
+    key: &LinearCombination<S>,
+    key_value: Option<S>,
+    msg: &LinearCombination<S>,
+    msg_value: Option<S>,
+) -> Result<(LinearCombination<S>, Option<S>), SynthesisError> {
+    let mut x = msg.clone();
+    let mut x_value = msg_value;
+    for (round, &c) in S::mimc_constants().iter().enumerate() {
+        // t = x + key + c; t2 = t², t4 = t2², x = t4·t
+        let t = x + key + (c, CS::one());
+        let t_value = x_value.zip(key_value).map(|(x, k)| x + k + c);
+
+        let t2_value = t_value.map(|t| t.square());
+        let t2 = cs.alloc(|| format!("round {} t2", round), || t2_value.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| format!("round {} square", round), |lc| lc + &t, |lc| lc + &t, |lc| lc + t2);
+
+        let t4_value = t2_value.map(|t2| t2.square());
+        let t4 = cs.alloc(|| format!("round {} t4", round), || t4_value.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| format!("round {} fourth", round), |lc| lc + t2, |lc| lc + t2, |lc| lc + t4);
+
+        x_value = t4_value.zip(t_value).map(|(t4, t)| t4 * t);
+        let next = cs.alloc(|| format!("round {} out", round), || x_value.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| format!("round {} fifth", round), |lc| lc + t4, |lc| lc + &t, |lc| lc + next);
+        x = LinearCombination::zero() + next;
+    }
+
+    let two = S::from(2);
+    let out = x + (two, key) + msg;
+    let out_value = x_value.zip(key_value).zip(msg_value).map(|((x, k), m)| x + k + k + m);
+    Ok((out, out_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::gadgets::test::TestConstraintSystem;
+    use bellman::Circuit;
+
+    /// Allocates one witness and range-checks it
+    struct RangeCircuit {
+        value: Scalar,
+        bits: usize,
+    }
+
+    impl Circuit<Scalar> for RangeCircuit {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let v = cs.alloc(|| "value", || Ok(self.value))?;
+            range_check(cs.namespace(|| "range"), LinearCombination::zero() + v, Some(self.value), self.bits)
+        }
+    }
+
+    fn satisfied(value: Scalar, bits: usize) -> bool {
+        let mut cs = TestConstraintSystem::new();
+        RangeCircuit { value, bits }.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_range_check_bounds() {
+        assert!(satisfied(Scalar::zero(), 8));
+        assert!(satisfied(Scalar::from(255u64), 8));
+        assert!(!satisfied(Scalar::from(256u64), 8));
+        // A wrapped-around negative value is a huge field element
+        assert!(!satisfied(-Scalar::one(), 64));
+    }
+
+    #[test]
+    fn test_mimc_gadget_matches_native() {
+        let (key, msg) = (Scalar::from(7u64), Scalar::from(1_000_000u64));
+        let mut cs = TestConstraintSystem::new();
+        let k = cs.alloc(|| "key", || Ok(key)).unwrap();
+        let m = cs.alloc(|| "msg", || Ok(msg)).unwrap();
+        let (out, value) = mimc_compress(
+            cs.namespace(|| "mimc"),
+            &(LinearCombination::zero() + k),
+            Some(key),
+            &(LinearCombination::zero() + m),
+            Some(msg),
+        )
+        .unwrap();
+        let o = cs.alloc(|| "out", || Ok(value.unwrap())).unwrap();
+        cs.enforce(|| "out matches", |_| out, |lc| lc + TestConstraintSystem::<Scalar>::one(), |lc| lc + o);
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 3 * MIMC_ROUNDS + 1);
+        assert_eq!(value.unwrap(), mimc_compress_native(key, msg));
+        assert_ne!(mimc_hash(&[key, msg]), mimc_hash(&[msg, key]));
+
+        // The generic reduction matches the curve's own wide reduction
+        let digest = Sha512::new()
+            .chain_update(b"olo-core mimc5")
+            .chain_update(0u64.to_le_bytes())
+            .finalize();
+        assert_eq!(Scalar::mimc_constants()[0], Scalar::from_bytes_wide(&digest.into()));
+    }
+
+    #[test]
+    fn test_signed_scalars() {
+        assert_eq!(scalar_from_i128::<Scalar>(-5) + Scalar::from(5u64), Scalar::zero());
+        assert_eq!(scalar_from_u128::<Scalar>(1 << 64), Scalar::from(u64::MAX) + Scalar::one());
+    }
+}
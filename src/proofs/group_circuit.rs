@@ -0,0 +1,294 @@
+//! Group Solvency Circuit
+//!
+//! Groth16 circuit proving a banking group meets the capital minimum on a
+//! consolidated basis, from private subsidiary figures.
+//!
+//! Up to `MAX_SUBSIDIARIES` capital/asset pairs are private inputs, padded
+//! with zeros. The circuit enforces `S·ΣC >= ratio·ΣA` in fixed-point units,
+//! so individual subsidiaries may fall below the minimum as long as the group
+//! does not. The public inputs are the ratio and `group_commitment`, a MiMC
+//! digest of the subsidiary count and each pair packed as `C + 2^64·A`, which
+//! ties the proof to one set of subsidiary figures without revealing them.
+//! The reporting period is a third public input.
+
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError};
+use bls12_381::Scalar;
+
+use crate::core::lagrangian::BankState;
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::{mimc_compress, mimc_hash, range_check, scalar_from_u128};
+
+/// Subsidiaries per proof; smaller groups are padded with zero pairs
+pub const MAX_SUBSIDIARIES: usize = 8;
+
+/// Bits covering 64-bit encoded amounts
+const AMOUNT_BITS: usize = 64;
+
+/// Bits covering the capital surplus `S·ΣC - ratio·ΣA` (`S·ΣC < 2^87`)
+const SURPLUS_BITS: usize = 88;
+
+/// Private witness for the group solvency circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupWitness {
+    count: u64,
+    /// Encoded (capital, assets) per slot, zero-padded
+    pairs: Vec<(u64, u64)>,
+}
+
+impl GroupWitness {
+    /// Encode each subsidiary's capital and assets
+    ///
+    /// Fails if the group is empty, exceeds `MAX_SUBSIDIARIES`, or an amount
+    /// cannot be encoded; errors name the subsidiary index.
+    pub fn new(states: &[BankState]) -> Result<Self, ProofError> {
+        let invalid = |reason: String| ProofError::InvalidWitness { reason };
+        if states.is_empty() {
+            return Err(invalid("group has no subsidiaries".to_string()));
+        }
+        if states.len() > MAX_SUBSIDIARIES {
+            return Err(invalid(format!(
+                "{} subsidiaries exceed the circuit maximum of {}",
+                states.len(),
+                MAX_SUBSIDIARIES
+            )));
+        }
+
+        let mut pairs = Vec::with_capacity(MAX_SUBSIDIARIES);
+        for (i, state) in states.iter().enumerate() {
+            let encode = |name: &str, value: f64| {
+                FixedPoint::to_units(value).map_err(|e| invalid(format!("subsidiary {} {}: {}", i, name, e)))
+            };
+            pairs.push((encode("tier1_capital", state.tier1_capital)?, encode("total_assets", state.total_assets)?));
+        }
+        pairs.resize(MAX_SUBSIDIARIES, (0, 0));
+
+        Ok(Self {
+            count: states.len() as u64,
+            pairs,
+        })
+    }
+
+    /// Consolidated capital and assets in fixed-point units
+    pub fn totals(&self) -> (u128, u128) {
+        self.pairs
+            .iter()
+            .fold((0, 0), |(c, a), &(ci, ai)| (c + ci as u128, a + ai as u128))
+    }
+
+    /// Whether the group meets `min_ratio` (fixed-point units) in aggregate
+    pub fn is_solvent(&self, min_ratio: u64) -> bool {
+        let (capital, assets) = self.totals();
+        FixedPoint::SCALE as u128 * capital >= min_ratio as u128 * assets
+    }
+
+    /// Digest binding a proof to these figures; see `group_commitment`
+    pub fn commitment(&self) -> Scalar {
+        let mut inputs = vec![Scalar::from(self.count)];
+        inputs.extend(self.pairs.iter().map(|&(c, a)| pack(c, a)));
+        mimc_hash(&inputs)
+    }
+}
+
+/// `capital + 2^64·assets`, unique for range-checked 64-bit halves
+fn pack(capital: u64, assets: u64) -> Scalar {
+    scalar_from_u128(capital as u128 | ((assets as u128) << 64))
+}
+
+/// Commitment a group solvency proof for `states` is bound to
+pub fn group_commitment(states: &[BankState]) -> Result<Scalar, ProofError> {
+    GroupWitness::new(states).map(|w| w.commitment())
+}
+
+/// Circuit proving consolidated capital meets a public minimum ratio
+///
+/// Subsidiary figures are private; the public inputs are the ratio, the group
+/// commitment, and the reporting period.
+#[derive(Clone)]
+pub struct GroupSolvencyCircuit {
+    /// Private: Subsidiary figures (`None` during setup)
+    pub witness: Option<GroupWitness>,
+    /// Public: Minimum capital-to-assets ratio
+    pub min_ratio: Option<Scalar>,
+    /// Public: `group_commitment` of the subsidiaries
+    pub commitment: Option<Scalar>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<Scalar>,
+}
+
+impl GroupSolvencyCircuit {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank() -> Self {
+        Self {
+            witness: None,
+            min_ratio: None,
+            commitment: None,
+            period: None,
+        }
+    }
+}
+
+impl Circuit<Scalar> for GroupSolvencyCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        self.synthesize_with(cs, AMOUNT_BITS)
+    }
+}
+
+impl GroupSolvencyCircuit {
+    /// Synthesize with amounts range-checked to `amount_bits`; see
+    /// `FragilityCircuit::synthesize_with`
+    pub(crate) fn synthesize_with<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        amount_bits: usize,
+    ) -> Result<(), SynthesisError> {
+        let w = self.witness.as_ref();
+
+        // Allocate public inputs
+        let min_ratio = cs.alloc_input(
+            || "min_ratio",
+            || self.min_ratio.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        let commitment = cs.alloc_input(
+            || "commitment",
+            || self.commitment.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+
+        // Helper: allocate a private value derived from the witness
+        macro_rules! witness {
+            ($name:expr, $value:expr) => {
+                cs.alloc(|| $name, || w.map($value).ok_or(SynthesisError::AssignmentMissing))?
+            };
+        }
+
+        // Commitment chain starts from the subsidiary count
+        let count = witness!("count", |w| Scalar::from(w.count));
+        let (mut digest, mut digest_value) = mimc_compress(
+            cs.namespace(|| "commitment count"),
+            &LinearCombination::zero(),
+            Some(Scalar::zero()),
+            &(LinearCombination::zero() + count),
+            w.map(|w| Scalar::from(w.count)),
+        )?;
+
+        let two_64 = scalar_from_u128(1 << 64);
+        let mut total_capital = LinearCombination::zero();
+        let mut total_assets = LinearCombination::zero();
+        for i in 0..MAX_SUBSIDIARIES {
+            let pair = w.map(|w| w.pairs[i]);
+            let capital = witness!(format!("capital {}", i), |w| Scalar::from(w.pairs[i].0));
+            let assets = witness!(format!("assets {}", i), |w| Scalar::from(w.pairs[i].1));
+            range_check(
+                cs.namespace(|| format!("capital {} range", i)),
+                LinearCombination::zero() + capital,
+                pair.map(|(c, _)| Scalar::from(c)),
+                amount_bits,
+            )?;
+            range_check(
+                cs.namespace(|| format!("assets {} range", i)),
+                LinearCombination::zero() + assets,
+                pair.map(|(_, a)| Scalar::from(a)),
+                amount_bits,
+            )?;
+            total_capital = total_capital + capital;
+            total_assets = total_assets + assets;
+
+            (digest, digest_value) = mimc_compress(
+                cs.namespace(|| format!("commitment {}", i)),
+                &digest,
+                digest_value,
+                &(LinearCombination::zero() + capital + (two_64, assets)),
+                pair.map(|(c, a)| pack(c, a)),
+            )?;
+        }
+        cs.enforce(|| "commitment matches", |_| digest, |lc| lc + CS::one(), |lc| lc + commitment);
+
+        // S·ΣC - ratio·ΣA must be a small non-negative number
+        let assets_value = w.map(|w| scalar_from_u128::<Scalar>(w.totals().1));
+        let required_value = self.min_ratio.zip(assets_value).map(|(r, a)| r * a);
+        let required = cs.alloc(
+            || "required capital",
+            || required_value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        cs.enforce(
+            || "ratio times assets",
+            |lc| lc + min_ratio,
+            |_| total_assets,
+            |lc| lc + required,
+        );
+        let scale = Scalar::from(FixedPoint::SCALE);
+        range_check(
+            cs.namespace(|| "capital surplus"),
+            LinearCombination::zero() + (scale, &total_capital) - required,
+            w.zip(required_value)
+                .map(|(w, required)| scale * scalar_from_u128::<Scalar>(w.totals().0) - required),
+            SURPLUS_BITS - AMOUNT_BITS + amount_bits,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    fn subsidiary(tier1_capital: f64, total_assets: f64) -> BankState {
+        BankState {
+            tier1_capital,
+            total_assets,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    fn satisfied(states: &[BankState], min_ratio: f64, commitment: Scalar) -> bool {
+        let circuit = GroupSolvencyCircuit {
+            witness: Some(GroupWitness::new(states).unwrap()),
+            min_ratio: Some(FixedPoint::encode(min_ratio).unwrap()),
+            commitment: Some(commitment),
+            period: Some(Scalar::zero()),
+        };
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_group_passes_only_in_aggregate() {
+        // 15% and 2% capitalized; 10.7% consolidated
+        let group = [subsidiary(15_000.0, 100_000.0), subsidiary(1_000.0, 50_000.0)];
+        let weak = [group[1].clone()];
+
+        assert!(GroupWitness::new(&group).unwrap().is_solvent(80_000));
+        assert!(!GroupWitness::new(&weak).unwrap().is_solvent(80_000));
+        assert!(satisfied(&group, 0.08, group_commitment(&group).unwrap()));
+        assert!(!satisfied(&weak, 0.08, group_commitment(&weak).unwrap()));
+        assert!(!satisfied(&group, 0.11, group_commitment(&group).unwrap()));
+    }
+
+    #[test]
+    fn test_commitment_binds_figures() {
+        let group = [subsidiary(15_000.0, 100_000.0), subsidiary(1_000.0, 50_000.0)];
+        let swapped = [group[1].clone(), group[0].clone()];
+        let padded = [group[0].clone(), group[1].clone(), subsidiary(0.0, 0.0)];
+
+        let commitment = group_commitment(&group).unwrap();
+        assert_ne!(commitment, group_commitment(&swapped).unwrap());
+        assert_ne!(commitment, group_commitment(&padded).unwrap());
+        assert!(!satisfied(&group, 0.08, group_commitment(&swapped).unwrap()));
+    }
+
+    #[test]
+    fn test_unprovable_groups_rejected() {
+        assert!(GroupWitness::new(&[]).is_err());
+        assert!(GroupWitness::new(&vec![subsidiary(1.0, 1.0); MAX_SUBSIDIARIES + 1]).is_err());
+        let err = GroupWitness::new(&[subsidiary(1.0, 1.0), subsidiary(-1.0, 1.0)]).unwrap_err();
+        assert!(err.to_string().contains("subsidiary 1 tier1_capital"), "{}", err);
+    }
+}
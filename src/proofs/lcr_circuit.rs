@@ -0,0 +1,195 @@
+//! LCR Compliance Circuit
+//!
+//! Groth16 circuit attesting that a bank's liquidity coverage ratio meets a
+//! public multiplier, `HQLA >= k·outflows`, without revealing either amount.
+//!
+//! HQLA and net stressed outflows are private fixed-point inputs, each
+//! range-checked to 64 bits. The multiplier `k` and the reporting period are
+//! the only public inputs; `k = 1.0` is the regulatory minimum. The comparison is `S·HQLA - k·O >= 0`
+//! in fixed-point units, so an LCR exactly at `k` passes.
+
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError};
+use bls12_381::Scalar;
+
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::range_check;
+
+/// Bits covering 64-bit encoded amounts
+const AMOUNT_BITS: usize = 64;
+
+/// Bits covering the liquidity surplus `S·HQLA - k·O` (`S·HQLA < 2^84`)
+const SURPLUS_BITS: usize = 85;
+
+/// Liquidity figures behind an LCR attestation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LcrInputs {
+    /// Stock of high-quality liquid assets
+    pub hqla: f64,
+    /// Net cash outflows over the 30-day stress period
+    pub net_outflows: f64,
+}
+
+/// Private witness for the LCR circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LcrWitness {
+    hqla: u64,
+    net_outflows: u64,
+}
+
+impl LcrWitness {
+    /// Encode both amounts; errors name the field
+    pub fn new(inputs: &LcrInputs) -> Result<Self, ProofError> {
+        let encode = |name: &str, value: f64| {
+            FixedPoint::to_units(value).map_err(|e| ProofError::InvalidWitness {
+                reason: format!("{}: {}", name, e),
+            })
+        };
+        Ok(Self {
+            hqla: encode("hqla", inputs.hqla)?,
+            net_outflows: encode("net_outflows", inputs.net_outflows)?,
+        })
+    }
+
+    /// Whether `HQLA >= k·outflows` for `k` in fixed-point units
+    pub fn meets(&self, multiplier: u64) -> bool {
+        FixedPoint::SCALE as u128 * self.hqla as u128 >= multiplier as u128 * self.net_outflows as u128
+    }
+}
+
+/// Circuit proving `HQLA >= k·outflows` for a public `k`
+#[derive(Clone)]
+pub struct LcrCircuit {
+    /// Private: HQLA and outflows (`None` during setup)
+    pub witness: Option<LcrWitness>,
+    /// Public: Multiplier `k`
+    pub multiplier: Option<Scalar>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<Scalar>,
+}
+
+impl LcrCircuit {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank() -> Self {
+        Self {
+            witness: None,
+            multiplier: None,
+            period: None,
+        }
+    }
+}
+
+impl Circuit<Scalar> for LcrCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        self.synthesize_with(cs, AMOUNT_BITS)
+    }
+}
+
+impl LcrCircuit {
+    /// Synthesize with amounts range-checked to `amount_bits`; see
+    /// `FragilityCircuit::synthesize_with`
+    pub(crate) fn synthesize_with<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        amount_bits: usize,
+    ) -> Result<(), SynthesisError> {
+        let w = self.witness;
+
+        // Allocate public inputs
+        let multiplier = cs.alloc_input(
+            || "multiplier",
+            || self.multiplier.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let hqla_value = w.map(|w| Scalar::from(w.hqla));
+        let outflows_value = w.map(|w| Scalar::from(w.net_outflows));
+        let hqla = cs.alloc(|| "hqla", || hqla_value.ok_or(SynthesisError::AssignmentMissing))?;
+        let outflows = cs.alloc(
+            || "net_outflows",
+            || outflows_value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        range_check(
+            cs.namespace(|| "hqla range"),
+            LinearCombination::zero() + hqla,
+            hqla_value,
+            amount_bits,
+        )?;
+        range_check(
+            cs.namespace(|| "net_outflows range"),
+            LinearCombination::zero() + outflows,
+            outflows_value,
+            amount_bits,
+        )?;
+
+        // S·HQLA - k·O must be a small non-negative number
+        let required_value = self.multiplier.zip(outflows_value).map(|(k, o)| k * o);
+        let required = cs.alloc(
+            || "required hqla",
+            || required_value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        cs.enforce(
+            || "multiplier times outflows",
+            |lc| lc + multiplier,
+            |lc| lc + outflows,
+            |lc| lc + required,
+        );
+        let scale = Scalar::from(FixedPoint::SCALE);
+        range_check(
+            cs.namespace(|| "liquidity surplus"),
+            LinearCombination::zero() + (scale, hqla) - required,
+            hqla_value.zip(required_value).map(|(h, required)| scale * h - required),
+            SURPLUS_BITS - AMOUNT_BITS + amount_bits,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    fn satisfied(hqla: f64, net_outflows: f64, k: f64) -> bool {
+        let circuit = LcrCircuit {
+            witness: Some(LcrWitness::new(&LcrInputs { hqla, net_outflows }).unwrap()),
+            multiplier: Some(FixedPoint::encode(k).unwrap()),
+            period: Some(Scalar::zero()),
+        };
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_lcr_comparison() {
+        assert!(satisfied(130_000.0, 100_000.0, 1.0));
+        assert!(satisfied(130_000.0, 100_000.0, 1.25));
+        assert!(!satisfied(90_000.0, 100_000.0, 1.0));
+        assert!(!satisfied(130_000.0, 100_000.0, 1.5));
+        // No outflows meets any multiplier
+        assert!(satisfied(0.0, 0.0, 2.0));
+    }
+
+    #[test]
+    fn test_exact_equality_boundary() {
+        assert!(satisfied(100_000.0, 100_000.0, 1.0));
+        assert!(!satisfied(99_999.999999, 100_000.0, 1.0));
+        assert!(satisfied(150_000.0, 100_000.0, 1.5));
+        assert!(!satisfied(150_000.0, 100_000.0, 1.500001));
+
+        let witness = LcrWitness::new(&LcrInputs { hqla: 100_000.0, net_outflows: 100_000.0 }).unwrap();
+        assert!(witness.meets(1_000_000));
+        assert!(!witness.meets(1_000_001));
+    }
+
+    #[test]
+    fn test_negative_amounts_rejected() {
+        let err = LcrWitness::new(&LcrInputs { hqla: 1.0, net_outflows: -1.0 }).unwrap_err();
+        assert!(err.to_string().contains("net_outflows"), "{}", err);
+    }
+}
@@ -0,0 +1,62 @@
+//! # Proofs Module
+//!
+//! Zero-knowledge proof layer for OLO Core.
+//! Contains the Groth16 fragility, entropy, group solvency, LCR, balance sheet, and Monte Carlo VaR circuits, prover, standalone verifier,
+//! fixed-point encoding, typed public inputs, proof envelopes, packet proof checks, replay nullifiers, proof caching, prover statistics, setup ceremonies, a versioned circuit registry, constraint checking, circuit cost estimates, pluggable proving backends (arkworks over BN254 behind `backend-arkworks`), EVM-compatible key and proof export, and proof error types.
+
+pub mod prover;
+pub mod verifier;
+pub mod circuit;
+pub mod entropy_circuit;
+pub mod group_circuit;
+pub mod lcr_circuit;
+pub mod balance_circuit;
+pub mod var_circuit;
+pub mod gadgets;
+pub mod error;
+pub mod encoding;
+pub mod envelope;
+pub mod public_inputs;
+pub mod packet;
+pub mod nullifier;
+pub mod cache;
+pub mod stats;
+pub mod ceremony;
+pub mod registry;
+pub mod constraints;
+pub mod estimate;
+pub mod backend;
+#[cfg(feature = "backend-arkworks")]
+pub mod arkworks;
+#[cfg(feature = "backend-arkworks")]
+pub mod evm;
+
+// Re-export key types
+pub use circuit::{FragilityCircuit, FragilityWitness, ThresholdCircuit, reference_fragility, state_commitment};
+pub use entropy_circuit::{EntropyCircuit, EntropyWitness, MAX_POSITIONS, reference_entropy};
+pub use group_circuit::{GroupSolvencyCircuit, GroupWitness, MAX_SUBSIDIARIES, group_commitment};
+pub use lcr_circuit::{LcrCircuit, LcrInputs, LcrWitness};
+pub use balance_circuit::{BalanceSheetCircuit, BalanceSheetProver, BalanceSheetWitness};
+pub use var_circuit::{VAR_PATHS, VarCircuit, VarProver, VarWitness, reference_var, shock_root, var_rank};
+pub use prover::{
+    BatchProveConfig, FragilityProver, deserialize_proof, proof_from_hex, proof_to_hex,
+    serialize_proof, validate_witness,
+};
+pub use verifier::{BatchOutcome, FragilityVerifier, VERIFYING_KEY_VERSION};
+pub use error::ProofError;
+pub use encoding::{EncodingError, FixedPoint};
+pub use envelope::{BackendId, CircuitId, ProofEnvelope};
+pub use public_inputs::PublicInputs;
+pub use packet::{PacketVerdict, packet_identity, verify_packet};
+pub use nullifier::{NullifierSet, nullifier};
+pub use constraints::{ConstraintViolation, check_constraints};
+pub use cache::{ProofCache, cache_key};
+pub use stats::{CircuitStats, ProverStats, StatsSnapshot};
+pub use ceremony::{Contribution, ContributionHash, Transcript, verify_contribution};
+pub use registry::{CircuitRegistry, PERIOD_VERSION, key_file_name};
+pub use estimate::{CircuitConfig, CircuitEstimate, estimate_circuit};
+pub use backend::ProvingBackend;
+#[cfg(feature = "backend-arkworks")]
+pub use arkworks::{ArkworksProver, Bn254Scalar};
+#[cfg(feature = "backend-arkworks")]
+pub use evm::{export_proof_evm_json, import_proof_evm_json, import_vk_evm_json, EvmProof, EvmProofPoints, EvmVerifyingKey};
@@ -0,0 +1,104 @@
+//! Replay Nullifiers
+//!
+//! A node reports once per reporting period, so the nullifier
+//! `SHA-256(prover ‖ period)` identifies a report without looking at its
+//! proof. `NullifierSet` remembers the nullifiers it has accepted for a
+//! configurable window and rejects a second proof from the same prover for
+//! the same period with `ProofError::Replayed`.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::proofs::error::ProofError;
+
+/// Domain separator for nullifier hashing
+const NULLIFIER_DOMAIN: &[u8] = b"olo-core/nullifier/v1";
+
+/// Nullifier of the report `prover_id` makes for `period`
+pub fn nullifier(prover_id: &str, period: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(NULLIFIER_DOMAIN);
+    hasher.update((prover_id.len() as u16).to_be_bytes());
+    hasher.update(prover_id.as_bytes());
+    hasher.update(period.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Nullifiers seen within a sliding window
+#[derive(Debug, Clone)]
+pub struct NullifierSet {
+    window: Duration,
+    seen: HashMap<[u8; 32], Instant>,
+}
+
+impl NullifierSet {
+    /// Remember each nullifier for `window` after it is first seen
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Record the report from `prover_id` for `period`
+    ///
+    /// Fails with `ProofError::Replayed` if the same report was recorded
+    /// within the window.
+    pub fn check(&mut self, prover_id: &str, period: u64) -> Result<(), ProofError> {
+        self.check_at(prover_id, period, Instant::now())
+    }
+
+    fn check_at(&mut self, prover_id: &str, period: u64, now: Instant) -> Result<(), ProofError> {
+        let window = self.window;
+        self.seen.retain(|_, seen| now.saturating_duration_since(*seen) < window);
+
+        let key = nullifier(prover_id, period);
+        if self.seen.contains_key(&key) {
+            return Err(ProofError::Replayed {
+                prover: prover_id.to_string(),
+                period,
+            });
+        }
+        self.seen.insert(key, now);
+        Ok(())
+    }
+
+    /// Nullifiers currently remembered
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Whether no nullifier is remembered
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_in_same_period_rejected() {
+        let mut set = NullifierSet::new(Duration::from_secs(3600));
+        set.check("12D3KooWa", 96).unwrap();
+        set.check("12D3KooWa", 97).unwrap();
+        set.check("12D3KooWb", 96).unwrap();
+
+        let err = set.check("12D3KooWa", 96).unwrap_err();
+        assert!(matches!(&err, ProofError::Replayed { prover, period: 96 } if prover == "12D3KooWa"));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_nullifiers_expire_after_window() {
+        let mut set = NullifierSet::new(Duration::from_secs(60));
+        let start = Instant::now();
+        set.check_at("12D3KooWa", 96, start).unwrap();
+        assert!(set.check_at("12D3KooWa", 96, start + Duration::from_secs(59)).is_err());
+        set.check_at("12D3KooWa", 96, start + Duration::from_secs(60)).unwrap();
+        assert_eq!(set.len(), 1);
+        assert_ne!(nullifier("12D3KooWa", 96), nullifier("12D3KooWa9", 6));
+    }
+}
@@ -0,0 +1,183 @@
+//! Packet Proof Verification
+//!
+//! Checks the fragility proof carried by a gossiped `DataPacket` against the
+//! packet's own claims: its fragility score, encoded with the fixed-point
+//! codec, the `state_commitment` of its bank state, and its reporting period.
+//! A packet may also carry a signed `ProofEnvelope` around its proof, which
+//! binds the proof to the prover's identity key (see `packet_identity`).
+
+use ed25519_dalek::VerifyingKey;
+use std::fmt;
+
+use crate::network::ingestion::DataPacket;
+use crate::proofs::circuit::state_commitment;
+use crate::proofs::envelope::CircuitId;
+use crate::proofs::error::ProofError;
+use crate::proofs::prover::deserialize_proof;
+use crate::proofs::public_inputs::PublicInputs;
+use crate::proofs::verifier::FragilityVerifier;
+
+/// Outcome of checking a packet's proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketVerdict {
+    /// The proof verifies for the packet's state and fragility
+    Valid,
+    /// The proof is undecodable or does not verify
+    InvalidProof,
+    /// The packet carries no proof
+    MissingProof,
+    /// The proof is not bound to the identity presenting it
+    IdentityMismatch,
+}
+
+impl PacketVerdict {
+    /// Whether the packet's proof verified
+    pub fn is_valid(&self) -> bool {
+        matches!(self, PacketVerdict::Valid)
+    }
+}
+
+impl fmt::Display for PacketVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketVerdict::Valid => write!(f, "valid proof"),
+            PacketVerdict::InvalidProof => write!(f, "invalid proof"),
+            PacketVerdict::MissingProof => write!(f, "missing proof"),
+            PacketVerdict::IdentityMismatch => write!(f, "identity mismatch"),
+        }
+    }
+}
+
+/// Key the proof in `packet` is bound to
+///
+/// `Some` only if the packet's envelope wraps the packet's own proof and is
+/// signed by the envelope's `prover_key`.
+pub fn packet_identity(packet: &DataPacket) -> Option<VerifyingKey> {
+    let envelope = packet.envelope.as_ref()?;
+    if packet.proof.as_ref() != Some(&envelope.proof) {
+        return None;
+    }
+    envelope.bound_identity()
+}
+
+/// Verify the proof in `packet` against its fragility, state, and period
+///
+/// Corrupt proof bytes are a bad packet, not a local failure, so they are
+/// reported as `InvalidProof`. A packet with an envelope that does not bind
+/// its proof (see `packet_identity`) is an `IdentityMismatch`. Errors are
+/// left for claims that have no fixed-point encoding and for verifier
+/// failures.
+pub fn verify_packet(packet: &DataPacket, verifier: &FragilityVerifier) -> Result<PacketVerdict, ProofError> {
+    let Some(bytes) = &packet.proof else {
+        return Ok(PacketVerdict::MissingProof);
+    };
+    if packet.envelope.is_some() && packet_identity(packet).is_none() {
+        return Ok(PacketVerdict::IdentityMismatch);
+    }
+    let proof = match deserialize_proof(bytes) {
+        Ok(proof) => proof,
+        Err(ProofError::InvalidProof { .. }) => return Ok(PacketVerdict::InvalidProof),
+        Err(e) => return Err(e),
+    };
+    let inputs = PublicInputs::new(CircuitId::Fragility)
+        .fragility(packet.fragility)
+        .commitment(state_commitment(&packet.state)?)
+        .period(packet.period.unwrap_or(0));
+    let verdict = match verifier.verify_inputs(&proof, &inputs)? {
+        true => PacketVerdict::Valid,
+        false => PacketVerdict::InvalidProof,
+    };
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::proofs::circuit::reference_fragility;
+    use crate::proofs::encoding::FixedPoint;
+    use crate::proofs::envelope::ProofEnvelope;
+    use crate::proofs::prover::{serialize_proof, test_prover};
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+
+    fn proven_packet() -> DataPacket {
+        let state = BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        let proof = test_prover().prove(&state, fragility).unwrap();
+        DataPacket {
+            timestamp: 1_700_000_000_000,
+            source: "12D3KooWpacket".to_string(),
+            state,
+            fragility,
+            signature: vec![],
+            proof: Some(serialize_proof(&proof)),
+            period: None,
+            envelope: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_packet_verdicts() {
+        let verifier = test_prover().verifier();
+        let packet = proven_packet();
+        assert_eq!(verify_packet(&packet, verifier).unwrap(), PacketVerdict::Valid);
+
+        let mut inflated = packet.clone();
+        inflated.fragility += 1.0;
+        assert_eq!(verify_packet(&inflated, verifier).unwrap(), PacketVerdict::InvalidProof);
+
+        let mut restated = packet.clone();
+        restated.state.tier1_capital += 1.0;
+        assert_eq!(verify_packet(&restated, verifier).unwrap(), PacketVerdict::InvalidProof);
+
+        let mut relabelled = packet.clone();
+        relabelled.period = Some(96);
+        assert_eq!(verify_packet(&relabelled, verifier).unwrap(), PacketVerdict::InvalidProof);
+
+        let mut unproven = packet;
+        unproven.proof = None;
+        assert_eq!(verify_packet(&unproven, verifier).unwrap(), PacketVerdict::MissingProof);
+    }
+
+    #[test]
+    fn test_envelope_must_bind_the_packet_proof() {
+        let verifier = test_prover().verifier();
+        let key = SigningKey::generate(&mut OsRng);
+        let mut packet = proven_packet();
+        let proof = deserialize_proof(packet.proof.as_ref().unwrap()).unwrap();
+        let units = FixedPoint::to_units(packet.fragility).unwrap();
+        let mut envelope = ProofEnvelope::new(CircuitId::Fragility, &proof, vec![units], None, "12D3KooWpacket");
+        envelope.sign(&key);
+        packet.envelope = Some(envelope.clone());
+        assert_eq!(packet_identity(&packet), Some(key.verifying_key()));
+        assert_eq!(verify_packet(&packet, verifier).unwrap(), PacketVerdict::Valid);
+
+        // Another proof under the same envelope, or the envelope claimed for another key
+        let mut swapped = packet.clone();
+        swapped.proof = proven_packet().proof;
+        assert_eq!(verify_packet(&swapped, verifier).unwrap(), PacketVerdict::IdentityMismatch);
+        envelope.prover_key = Some(SigningKey::generate(&mut OsRng).verifying_key().to_bytes());
+        packet.envelope = Some(envelope);
+        assert_eq!(packet_identity(&packet), None);
+        assert_eq!(verify_packet(&packet, verifier).unwrap(), PacketVerdict::IdentityMismatch);
+    }
+
+    #[test]
+    fn test_corrupt_proof_bytes_are_invalid() {
+        let verifier = test_prover().verifier();
+        let mut packet = proven_packet();
+        packet.proof = Some(vec![0u8; 12]);
+        assert_eq!(verify_packet(&packet, verifier).unwrap(), PacketVerdict::InvalidProof);
+
+        packet.fragility = f64::NAN;
+        packet.proof = proven_packet().proof;
+        assert!(verify_packet(&packet, verifier).is_err());
+    }
+}
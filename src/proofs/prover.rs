@@ -1,154 +1,690 @@
 //! Zero-Knowledge Proof Generation
 //!
 //! Cryptographic proof system for verifiable financial computations.
-//! Generates ZK-SNARK proofs that fragility calculations are correct without revealing data.
-
-use bellman::{
-    groth16::{
-        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
-        Parameters, Proof,
-    },
-    Circuit, ConstraintSystem, SynthesisError,
-};
+//! Generates ZK-SNARK proofs that fragility and entropy calculations are correct without revealing data.
+
+use bellman::groth16::{create_random_proof, generate_random_parameters, Parameters, Proof};
+use bellman::Circuit;
 use bls12_381::{Bls12, Scalar};
 use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+use crate::core::entropy::Position;
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::proofs::circuit::{state_commitment, FragilityCircuit, FragilityWitness, ThresholdCircuit};
+use crate::proofs::entropy_circuit::{EntropyCircuit, EntropyWitness};
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::envelope::{CircuitId, ProofEnvelope};
+use crate::proofs::error::ProofError;
+use crate::proofs::group_circuit::{GroupSolvencyCircuit, GroupWitness};
+use crate::proofs::public_inputs::PublicInputs;
+use crate::proofs::stats::ProverStats;
+use crate::proofs::lcr_circuit::{LcrCircuit, LcrInputs, LcrWitness};
+use crate::proofs::verifier::{BatchOutcome, FragilityVerifier};
+
+/// Size of a compressed Groth16 proof over BLS12-381 (G1 + G2 + G1)
+pub const PROOF_BYTES: usize = 192;
+
+/// `prove_async` proofs running at once unless configured otherwise
+///
+/// bellman already spreads each proof over every core, so more only
+/// interleaves them.
+pub const DEFAULT_MAX_CONCURRENT_PROOFS: usize = 1;
 
-use crate::core::lagrangian::BankState;
-
-/// Fragility computation circuit for ZK-SNARK
-#[derive(Clone)]
-pub struct FragilityCircuit {
-    /// Private: Bank assets
-    pub assets: Option<Scalar>,
-    /// Private: Bank liabilities
-    pub liabilities: Option<Scalar>,
-    /// Private: Bank equity
-    pub equity: Option<Scalar>,
-    /// Private: Leverage ratio
-    pub leverage: Option<Scalar>,
-    /// Public: Fragility score output
-    pub fragility: Option<Scalar>,
+/// Encode a proof in the compressed Groth16 format
+pub fn serialize_proof(proof: &Proof<Bls12>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(PROOF_BYTES);
+    proof.write(&mut bytes).expect("writing to a Vec cannot fail");
+    bytes
 }
 
-impl Circuit<Scalar> for FragilityCircuit {
-    fn synthesize<CS: ConstraintSystem<Scalar>>(
-        self,
-        cs: &mut CS,
-    ) -> Result<(), SynthesisError> {
-        // Allocate private inputs
-        let assets = cs.alloc(
-            || \"assets\",
-            || self.assets.ok_or(SynthesisError::AssignmentMissing),
-        )?;
-
-        let liabilities = cs.alloc(
-            || \"liabilities\",
-            || self.liabilities.ok_or(SynthesisError::AssignmentMissing),
-        )?;
-
-        let equity = cs.alloc(
-            || \"equity\",
-            || self.equity.ok_or(SynthesisError::AssignmentMissing),
-        )?;
-
-        let leverage = cs.alloc(
-            || \"leverage\",
-            || self.leverage.ok_or(SynthesisError::AssignmentMissing),
-        )?;
-
-        // Allocate public output
-        let fragility = cs.alloc_input(
-            || \"fragility\",
-            || self.fragility.ok_or(SynthesisError::AssignmentMissing),
-        )?;
-
-        // Constraint: assets = liabilities + equity (balance sheet identity)
-        cs.enforce(
-            || \"balance_sheet\",
-            |lc| lc + liabilities + equity,
-            |lc| lc + CS::one(),
-            |lc| lc + assets,
-        );
+/// Decode a proof written by `serialize_proof`
+///
+/// Truncated input, trailing bytes, and points off the curve are rejected
+/// with `ProofError::InvalidProof`.
+pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bls12>, ProofError> {
+    if bytes.len() != PROOF_BYTES {
+        return Err(ProofError::InvalidProof {
+            reason: format!("expected {} bytes, got {}", PROOF_BYTES, bytes.len()),
+        });
+    }
+    Proof::read(bytes).map_err(|e| ProofError::InvalidProof { reason: e.to_string() })
+}
 
-        // Constraint: leverage = liabilities / equity
-        cs.enforce(
-            || \"leverage_ratio\",
-            |lc| lc + leverage,
-            |lc| lc + equity,
-            |lc| lc + liabilities,
-        );
+/// Hex encoding of `serialize_proof`
+pub fn proof_to_hex(proof: &Proof<Bls12>) -> String {
+    hex::encode(serialize_proof(proof))
+}
 
-        // Simplified fragility constraint (actual implementation would be more complex)
-        // fragility ≈ leverage * volatility_factor
-        cs.enforce(
-            || \"fragility_calculation\",
-            |lc| lc + leverage,
-            |lc| lc + CS::one(),
-            |lc| lc + fragility,
-        );
+/// Decode a proof from `proof_to_hex` output
+pub fn proof_from_hex(encoded: &str) -> Result<Proof<Bls12>, ProofError> {
+    let bytes = hex::decode(encoded.trim())
+        .map_err(|e| ProofError::InvalidProof { reason: e.to_string() })?;
+    deserialize_proof(&bytes)
+}
 
-        Ok(())
+/// Check that `state` can be used as a circuit witness
+///
+/// Every field must be finite, non-negative, and within the fixed-point
+/// range, and the liquidity coverage must not round to zero since the
+/// liquidity term divides by it. Errors name the offending field.
+pub fn validate_witness(state: &BankState) -> Result<(), ProofError> {
+    let fields = [
+        ("tier1_capital", state.tier1_capital),
+        ("total_assets", state.total_assets),
+        ("liquidity_coverage", state.liquidity_coverage),
+        ("entropy_index", state.entropy_index),
+    ];
+    for (name, value) in fields {
+        if let Err(e) = FixedPoint::to_units(value) {
+            return Err(ProofError::InvalidWitness {
+                reason: format!("{}: {}", name, e),
+            });
+        }
+    }
+
+    if FixedPoint::to_units(state.liquidity_coverage)? == 0 {
+        return Err(ProofError::InvalidWitness {
+            reason: format!(
+                "liquidity_coverage {} rounds to zero at scale {}",
+                state.liquidity_coverage,
+                FixedPoint::SCALE
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Options for `FragilityProver::prove_batch_with`
+#[derive(Debug, Clone)]
+pub struct BatchProveConfig {
+    /// States proven per parallel chunk; bounds peak memory
+    pub chunk_size: usize,
+    /// Peer ID recorded in every envelope
+    pub prover_id: String,
+    /// Reporting period every proof is bound to, if any
+    pub period: Option<u64>,
+}
+
+impl Default for BatchProveConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 64,
+            prover_id: String::new(),
+            period: None,
+        }
     }
 }
 
 /// ZK-SNARK prover for fragility calculations
+///
+/// The circuits are built for `LagrangianConfig::default()`; proofs attest to
+/// `reference_fragility` under that configuration.
 pub struct FragilityProver {
     params: Parameters<Bls12>,
+    threshold_params: Parameters<Bls12>,
+    entropy_params: Parameters<Bls12>,
+    group_params: Parameters<Bls12>,
+    lcr_params: Parameters<Bls12>,
+    verifier: FragilityVerifier,
+    stats: Option<ProverStats>,
+    proof_slots: Arc<Semaphore>,
 }
 
 impl FragilityProver {
     /// Generate proving parameters (trusted setup - do this once)
+    ///
+    /// # Panics
+    /// If parameter generation fails; see `try_setup`.
     pub fn setup() -> Self {
-        let circuit = FragilityCircuit {
-            assets: None,
-            liabilities: None,
-            equity: None,
-            leverage: None,
-            fragility: None,
+        Self::try_setup().expect("Parameter generation failed")
+    }
+
+    /// Generate proving parameters, reporting synthesis failures
+    pub fn try_setup() -> Result<Self, ProofError> {
+        Self::setup_with(&mut OsRng)
+    }
+
+    /// Generate parameters reproducibly from `seed`
+    ///
+    /// # Security
+    /// **Never use this in production.** The setup randomness ("toxic
+    /// waste") is derived from `seed`, so anyone who knows or guesses the
+    /// seed can forge proofs of any statement for these parameters. It exists
+    /// so CI and examples get identical parameters without a fresh setup, and
+    /// is only compiled in tests or with the `insecure-test-setup` feature.
+    #[cfg(any(test, feature = "insecure-test-setup"))]
+    pub fn setup_deterministic(seed: u64) -> Self {
+        use rand::SeedableRng;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(seed);
+        Self::setup_with(&mut rng).expect("Parameter generation failed")
+    }
+
+    /// Generate parameters for every circuit from `rng`
+    fn setup_with<R: RngCore>(rng: &mut R) -> Result<Self, ProofError> {
+        let circuit = FragilityCircuit::blank(LagrangianConfig::default());
+        let threshold_circuit = ThresholdCircuit::blank(LagrangianConfig::default());
+
+        let params = generate_random_parameters::<Bls12, _, _>(circuit, &mut *rng)?;
+        let threshold_params = generate_random_parameters::<Bls12, _, _>(threshold_circuit, &mut *rng)?;
+        let entropy_params = generate_random_parameters::<Bls12, _, _>(EntropyCircuit::blank(), &mut *rng)?;
+        let group_params = generate_random_parameters::<Bls12, _, _>(GroupSolvencyCircuit::blank(), &mut *rng)?;
+        let lcr_params = generate_random_parameters::<Bls12, _, _>(LcrCircuit::blank(), &mut *rng)?;
+
+        let mut bytes = Vec::new();
+        for p in [&params, &threshold_params, &entropy_params, &group_params, &lcr_params] {
+            p.write(&mut bytes)?;
+        }
+        let fingerprint = Sha256::digest(&bytes).into();
+        Ok(Self::from_params(params, threshold_params, entropy_params, group_params, lcr_params, fingerprint))
+    }
+
+    /// Assemble from parameters and the fingerprint of their `save_params`
+    /// encoding, deriving the verifier
+    fn from_params(
+        params: Parameters<Bls12>,
+        threshold_params: Parameters<Bls12>,
+        entropy_params: Parameters<Bls12>,
+        group_params: Parameters<Bls12>,
+        lcr_params: Parameters<Bls12>,
+        fingerprint: [u8; 32],
+    ) -> Self {
+        let verifier = FragilityVerifier::new(
+            params.vk.clone(),
+            threshold_params.vk.clone(),
+            entropy_params.vk.clone(),
+            group_params.vk.clone(),
+            lcr_params.vk.clone(),
+            fingerprint,
+        );
+        Self {
+            params,
+            threshold_params,
+            entropy_params,
+            group_params,
+            lcr_params,
+            verifier,
+            stats: None,
+            proof_slots: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_PROOFS)),
+        }
+    }
+
+    /// Write the proving parameters (including the verifying keys)
+    ///
+    /// Score, threshold, entropy, and group circuit parameters are written back to back.
+    pub fn save_params<W: Write>(&self, mut w: W) -> Result<(), ProofError> {
+        self.params.write(&mut w)?;
+        self.threshold_params.write(&mut w)?;
+        self.entropy_params.write(&mut w)?;
+        self.group_params.write(&mut w)?;
+        self.lcr_params.write(&mut w)?;
+        Ok(())
+    }
+
+    /// Read proving parameters written by `save_params`
+    ///
+    /// With an `expected` fingerprint, parameters that hash differently are
+    /// rejected with `ProofError::ParameterMismatch` before decoding. Curve
+    /// points are checked on load, so truncated or corrupted files are
+    /// otherwise rejected with `ProofError::InvalidParameters`.
+    pub fn load_params<R: Read>(mut r: R, expected: Option<[u8; 32]>) -> Result<Self, ProofError> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        let fingerprint: [u8; 32] = Sha256::digest(&bytes).into();
+        if let Some(expected) = expected.filter(|expected| *expected != fingerprint) {
+            return Err(ProofError::ParameterMismatch {
+                expected,
+                actual: fingerprint,
+            });
+        }
+
+        let mut r = bytes.as_slice();
+        let read = |r: &mut &[u8]| {
+            Parameters::read(r, true).map_err(|e| match e.kind() {
+                io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                    ProofError::InvalidParameters { reason: e.to_string() }
+                }
+                _ => ProofError::ParameterIo(e),
+            })
         };
+        let params = read(&mut r)?;
+        let threshold_params = read(&mut r)?;
+        let entropy_params = read(&mut r)?;
+        let group_params = read(&mut r)?;
+        let lcr_params = read(&mut r)?;
+        if !r.is_empty() {
+            return Err(ProofError::InvalidParameters {
+                reason: format!("{} trailing bytes", r.len()),
+            });
+        }
+        Ok(Self::from_params(params, threshold_params, entropy_params, group_params, lcr_params, fingerprint))
+    }
 
-        let mut rng = OsRng;
-        let params = generate_random_parameters::<Bls12, _, _>(circuit, &mut rng)
-            .expect(\"Parameter generation failed\");
+    /// Load parameters from `path`, or run setup and persist them there
+    ///
+    /// Proofs made by any process sharing the file verify against each other.
+    pub fn load_or_setup(path: impl AsRef<Path>) -> Result<Self, ProofError> {
+        let path = path.as_ref();
+        match File::open(path) {
+            Ok(file) => Self::load_params(BufReader::new(file), None),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let prover = Self::try_setup()?;
 
-        Self { params }
+                // Write to a sibling, then rename, so readers never see a partial file
+                let mut tmp = path.as_os_str().to_owned();
+                tmp.push(".tmp");
+                let mut bytes = Vec::new();
+                prover.save_params(&mut bytes)?;
+                fs::write(&tmp, &bytes)?;
+                fs::rename(&tmp, path)?;
+
+                Ok(prover)
+            }
+            Err(e) => Err(ProofError::ParameterIo(e)),
+        }
+    }
+
+    /// Record timing and size statistics for every proof, verification, and batch
+    ///
+    /// Off by default; see `stats`.
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(ProverStats::default());
+        self
+    }
+
+    /// Run at most `limit` `prove_async` proofs at once (at least one)
+    ///
+    /// Defaults to `DEFAULT_MAX_CONCURRENT_PROOFS`. Synchronous proving is not
+    /// limited.
+    pub fn with_max_concurrent_proofs(mut self, limit: usize) -> Self {
+        self.proof_slots = Arc::new(Semaphore::new(limit.max(1)));
+        self
+    }
+
+    /// Statistics collected since `with_stats`, if enabled
+    pub fn stats(&self) -> Option<&ProverStats> {
+        self.stats.as_ref()
+    }
+
+    /// `create_random_proof`, recorded under `id` when stats are enabled
+    fn create<C: Circuit<Scalar> + Clone>(
+        &self,
+        id: CircuitId,
+        circuit: C,
+        params: &Parameters<Bls12>,
+    ) -> Result<Proof<Bls12>, ProofError> {
+        let Some(stats) = &self.stats else {
+            return Ok(create_random_proof(circuit, params, &mut OsRng)?);
+        };
+        let shape = stats.shape(id, &circuit);
+        let started = Instant::now();
+        let proof = create_random_proof(circuit, params, &mut OsRng);
+        stats.record_proof(id, shape, started.elapsed(), proof.is_ok());
+        Ok(proof?)
+    }
+
+    /// Run a verification, recorded when stats are enabled
+    fn timed<T>(&self, verify: impl FnOnce() -> Result<T, ProofError>) -> Result<T, ProofError> {
+        let started = Instant::now();
+        let result = verify();
+        if let Some(stats) = &self.stats {
+            stats.record_verification(started.elapsed());
+        }
+        result
     }
 
     /// Generate proof for a bank state fragility calculation
+    ///
+    /// Proves the circuit from `FragilityCircuit::generate_witness`, so the
+    /// same checks apply: an invalid state or a `fragility_score` other than
+    /// `reference_fragility(state)` is reported as
+    /// `ProofError::InvalidWitness` before proving.
     pub fn prove(
         &self,
         state: &BankState,
         fragility_score: f64,
-    ) -> Result<Proof<Bls12>, String> {
-        // Convert f64 to Scalar (simplified - real implementation needs proper encoding)
+    ) -> Result<Proof<Bls12>, ProofError> {
+        self.prove_for_period(state, fragility_score, 0)
+    }
+
+    /// Prove as with `prove`, bound to reporting `period`
+    ///
+    /// The proof only verifies with `PublicInputs::period(period)`; period 0
+    /// is the unbound proof `prove` makes.
+    pub fn prove_for_period(
+        &self,
+        state: &BankState,
+        fragility_score: f64,
+        period: u64,
+    ) -> Result<Proof<Bls12>, ProofError> {
         let circuit = FragilityCircuit {
-            assets: Some(Scalar::from(state.assets as u64)),
-            liabilities: Some(Scalar::from(state.liabilities as u64)),
-            equity: Some(Scalar::from(state.equity as u64)),
-            leverage: Some(Scalar::from((state.leverage * 100.0) as u64)),
-            fragility: Some(Scalar::from((fragility_score * 1000.0) as u64)),
+            period: Some(Scalar::from(period)),
+            ..FragilityCircuit::generate_witness(state, fragility_score, LagrangianConfig::default())?
+        };
+        self.create(CircuitId::Fragility, circuit, &self.params)
+    }
+
+    /// Verify a fragility proof for the published `state`
+    pub fn verify(&self, proof: &Proof<Bls12>, state: &BankState, fragility_score: f64) -> Result<bool, ProofError> {
+        self.timed(|| self.verifier.verify(proof, state, fragility_score))
+    }
+
+    /// Prove `state` as with `prove` and wrap the proof in an envelope
+    ///
+    /// `prover_id` is the proving node's peer ID, and `period` the reporting
+    /// period the proof is bound to, if any; period 0 is reserved for unbound
+    /// proofs. The envelope is unsigned; see `ProofEnvelope::sign`.
+    pub fn prove_enveloped(
+        &self,
+        state: &BankState,
+        fragility_score: f64,
+        prover_id: &str,
+        period: Option<u64>,
+    ) -> Result<ProofEnvelope, ProofError> {
+        if period == Some(0) {
+            return Err(ProofError::PublicInputMismatch {
+                reason: "period 0 is reserved for unbound proofs".to_string(),
+            });
+        }
+        let started = Instant::now();
+        let proof = self.prove_for_period(state, fragility_score, period.unwrap_or(0))?;
+        let proving_ms = started.elapsed().as_millis() as u64;
+
+        let mut envelope = ProofEnvelope::new(
+            CircuitId::Fragility,
+            &proof,
+            vec![FixedPoint::to_units(fragility_score)?],
+            Some(state_commitment(state)?),
+            prover_id,
+        );
+        envelope.period = period;
+        envelope.params_fingerprint = Some(self.params_fingerprint());
+        envelope.proving_ms = Some(proving_ms);
+        Ok(envelope)
+    }
+
+    /// Prove as with `prove_enveloped` on a blocking thread, off the async
+    /// runtime
+    ///
+    /// Waits for one of the `with_max_concurrent_proofs` slots first. Dropping
+    /// the future before its proof starts cancels it; a proof already running
+    /// finishes on its thread and is discarded, keeping its slot until then.
+    /// Must be polled within a tokio runtime.
+    pub fn prove_async(
+        self: &Arc<Self>,
+        state: BankState,
+        fragility_score: f64,
+        prover_id: String,
+        period: Option<u64>,
+    ) -> impl Future<Output = Result<ProofEnvelope, ProofError>> + Send + 'static {
+        let prover = Arc::clone(self);
+        async move {
+            let slot = Arc::clone(&prover.proof_slots)
+                .acquire_owned()
+                .await
+                .expect("proof slots are never closed");
+            let cancelled = CancelOnDrop::default();
+            let skip = Arc::clone(&cancelled.0);
+            let task = tokio::task::spawn_blocking(move || {
+                let _slot = slot;
+                if skip.load(Ordering::Acquire) {
+                    return Err(ProofError::ProvingTask { reason: "cancelled".to_string() });
+                }
+                prover.prove_enveloped(&state, fragility_score, &prover_id, period)
+            });
+            task.await.map_err(|e| ProofError::ProvingTask { reason: e.to_string() })?
+        }
+    }
+
+    /// Prove many states in parallel with default options
+    ///
+    /// See `prove_batch_with`.
+    pub fn prove_batch(&self, states: &[(BankState, f64)]) -> Vec<Result<ProofEnvelope, ProofError>> {
+        self.prove_batch_with(states, &BatchProveConfig::default())
+    }
+
+    /// Prove many `(state, fragility)` pairs in parallel
+    ///
+    /// States are proven `chunk_size` at a time, one scoped thread each, all
+    /// sharing these parameters, so only one chunk of proving work is in
+    /// memory at once. Plain threads rather than the rayon pool: bellman
+    /// spreads each proof's multiexps over rayon itself and panics if
+    /// `create_proof` is called from a pool worker. Results are in input
+    /// order; a failed state does not stop the rest.
+    pub fn prove_batch_with(
+        &self,
+        states: &[(BankState, f64)],
+        config: &BatchProveConfig,
+    ) -> Vec<Result<ProofEnvelope, ProofError>> {
+        let started = Instant::now();
+        let mut envelopes = Vec::with_capacity(states.len());
+        for chunk in states.chunks(config.chunk_size.max(1)) {
+            thread::scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|(state, fragility)| {
+                        scope.spawn(move || {
+                            self.prove_enveloped(state, *fragility, &config.prover_id, config.period)
+                        })
+                    })
+                    .collect();
+                envelopes.extend(handles.into_iter().map(|h| h.join().expect("proving thread panicked")));
+            });
+        }
+        if let Some(stats) = &self.stats {
+            stats.record_batch(states.len(), started.elapsed());
+        }
+        envelopes
+    }
+
+    /// Verify an envelope; see `FragilityVerifier::verify_envelope`
+    pub fn verify_envelope(&self, envelope: &ProofEnvelope, expected_period: Option<u64>) -> Result<bool, ProofError> {
+        self.timed(|| self.verifier.verify_envelope(envelope, expected_period))
+    }
+
+    /// Verify many fragility proofs; see `FragilityVerifier::verify_batch`
+    pub fn verify_batch(&self, items: &[(Proof<Bls12>, PublicInputs)]) -> Result<BatchOutcome, ProofError> {
+        self.timed(|| self.verifier.verify_batch(items))
+    }
+
+    /// Prove the fragility of `state` is strictly below `threshold`
+    ///
+    /// The proof reveals the threshold and nothing else about the score. Fails
+    /// without proving if the state's `reference_fragility` is not below it.
+    pub fn prove_below(&self, state: &BankState, threshold: f64) -> Result<Proof<Bls12>, ProofError> {
+        validate_witness(state)?;
+        let witness = FragilityWitness::new(state, &LagrangianConfig::default())?;
+        let bound = FixedPoint::to_units(threshold)?;
+        if witness.fragility_units() >= bound {
+            return Err(ProofError::InvalidWitness {
+                reason: format!("fragility {} is not below threshold {}", witness.fragility(), threshold),
+            });
+        }
+
+        let circuit = ThresholdCircuit {
+            config: LagrangianConfig::default(),
+            witness: Some(witness),
+            threshold: Some(Scalar::from(bound)),
+            period: Some(Scalar::zero()),
+        };
+        self.create(CircuitId::Threshold, circuit, &self.threshold_params)
+    }
+
+    /// Verify a proof from `prove_below` against `threshold`
+    pub fn verify_below(&self, proof: &Proof<Bls12>, threshold: f64) -> Result<bool, ProofError> {
+        self.timed(|| self.verifier.verify_below(proof, threshold))
+    }
+
+    /// Prove the entropy of `positions` is at least `bound` bits
+    ///
+    /// The proof reveals the bound and nothing about the holdings. Fails
+    /// without proving if `reference_entropy(positions)` is below it.
+    pub fn prove_entropy_at_least(&self, positions: &[Position], bound: f64) -> Result<Proof<Bls12>, ProofError> {
+        let witness = EntropyWitness::new(positions)?;
+        let encoded = FixedPoint::to_units(bound)?;
+        if witness.entropy_units() < encoded {
+            return Err(ProofError::InvalidWitness {
+                reason: format!("entropy {} is below bound {}", witness.entropy(), bound),
+            });
+        }
+
+        let circuit = EntropyCircuit {
+            witness: Some(witness),
+            bound: Some(Scalar::from(encoded)),
+            period: Some(Scalar::zero()),
+        };
+        self.create(CircuitId::Entropy, circuit, &self.entropy_params)
+    }
+
+    /// Prove a banking group meets `min_ratio` on a consolidated basis
+    ///
+    /// Only the tier 1 capital and total assets of each subsidiary are used.
+    /// The proof reveals the ratio and `group_commitment(states)`, which the
+    /// prover publishes alongside it. Fails without proving if the group
+    /// falls short in aggregate.
+    pub fn prove_group_solvency(&self, states: &[BankState], min_ratio: f64) -> Result<Proof<Bls12>, ProofError> {
+        let witness = GroupWitness::new(states)?;
+        let ratio = FixedPoint::to_units(min_ratio)?;
+        if !witness.is_solvent(ratio) {
+            let (capital, assets) = witness.totals();
+            return Err(ProofError::InvalidWitness {
+                reason: format!(
+                    "group capital {} is below {} of assets {}",
+                    capital as f64 / FixedPoint::SCALE as f64,
+                    min_ratio,
+                    assets as f64 / FixedPoint::SCALE as f64
+                ),
+            });
+        }
+
+        let circuit = GroupSolvencyCircuit {
+            min_ratio: Some(Scalar::from(ratio)),
+            commitment: Some(witness.commitment()),
+            witness: Some(witness),
+            period: Some(Scalar::zero()),
+        };
+        self.create(CircuitId::GroupSolvency, circuit, &self.group_params)
+    }
+
+    /// Verify a proof from `prove_group_solvency`
+    pub fn verify_group_solvency(
+        &self,
+        proof: &Proof<Bls12>,
+        commitment: Scalar,
+        min_ratio: f64,
+    ) -> Result<bool, ProofError> {
+        self.timed(|| self.verifier.verify_group_solvency(proof, commitment, min_ratio))
+    }
+
+    /// Prove a bank's liquidity coverage ratio is at least `k`
+    ///
+    /// The proof reveals `k` and nothing about HQLA or outflows; `k = 1.0`
+    /// attests regulatory LCR compliance. Fails without proving if
+    /// `HQLA < k·outflows`.
+    pub fn prove_lcr_at_least(&self, inputs: &LcrInputs, k: f64) -> Result<Proof<Bls12>, ProofError> {
+        let witness = LcrWitness::new(inputs)?;
+        let multiplier = FixedPoint::to_units(k)?;
+        if !witness.meets(multiplier) {
+            return Err(ProofError::InvalidWitness {
+                reason: format!(
+                    "hqla {} is below {} times net outflows {}",
+                    inputs.hqla, k, inputs.net_outflows
+                ),
+            });
+        }
+
+        let circuit = LcrCircuit {
+            witness: Some(witness),
+            multiplier: Some(Scalar::from(multiplier)),
+            period: Some(Scalar::zero()),
         };
+        self.create(CircuitId::Lcr, circuit, &self.lcr_params)
+    }
+
+    /// Verify a proof from `prove_lcr_at_least` against `k`
+    pub fn verify_lcr_at_least(&self, proof: &Proof<Bls12>, k: f64) -> Result<bool, ProofError> {
+        self.timed(|| self.verifier.verify_lcr_at_least(proof, k))
+    }
+
+    /// Verify a proof from `prove_entropy_at_least` against `bound`
+    pub fn verify_entropy_at_least(&self, proof: &Proof<Bls12>, bound: f64) -> Result<bool, ProofError> {
+        self.timed(|| self.verifier.verify_entropy_at_least(proof, bound))
+    }
+
+    /// Verifier sharing this prover's keys
+    pub fn verifier(&self) -> &FragilityVerifier {
+        &self.verifier
+    }
+
+    /// Versioned verifying keys for `FragilityVerifier::from_bytes`
+    ///
+    /// Contains no proving material; safe to publish to verifier-only nodes.
+    pub fn export_verifying_key(&self) -> Vec<u8> {
+        self.verifier.to_bytes()
+    }
 
-        let mut rng = OsRng;
-        create_random_proof(circuit, &self.params, &mut rng)
-            .map_err(|e| format!(\"Proof generation failed: {:?}\", e))
+    /// SHA-256 of the `save_params` encoding
+    ///
+    /// Identifies these parameters: it changes with every setup. Envelopes
+    /// carry it so verifiers with other parameters can say so.
+    pub fn params_fingerprint(&self) -> [u8; 32] {
+        self.verifier.params_fingerprint()
     }
+}
 
-    /// Verify a fragility proof
-    pub fn verify(&self, proof: &Proof<Bls12>, fragility_score: f64) -> Result<bool, String> {
-        let pvk = prepare_verifying_key(&self.params.vk);
-        
-        // Public input: fragility score
-        let public_input = vec![Scalar::from((fragility_score * 1000.0) as u64)];
+/// Flag set when a `prove_async` future is dropped, so a proof still
+/// queued for a blocking thread is skipped
+#[derive(Default)]
+struct CancelOnDrop(Arc<AtomicBool>);
 
-        verify_proof(&pvk, proof, &public_input)
-            .map_err(|e| format!(\"Verification failed: {:?}\", e))
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
     }
 }
 
+/// Prover shared by the test suite, from a fixed-seed setup
+#[cfg(test)]
+pub(crate) fn test_prover() -> &'static FragilityProver {
+    static PROVER: std::sync::OnceLock<FragilityProver> = std::sync::OnceLock::new();
+    PROVER.get_or_init(|| FragilityProver::setup_deterministic(0x01_0c0de))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::proofs::circuit::reference_fragility;
+    use crate::proofs::encoding::EncodingError;
+    use crate::proofs::envelope::BackendId;
+    use crate::proofs::group_circuit::group_commitment;
+    use crate::proofs::verifier::VERIFYING_KEY_VERSION;
+    use ed25519_dalek::SigningKey;
+
+    fn near_barrier() -> BankState {
+        BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    /// Proof for `near_barrier` and the score it attests to
+    fn proven(prover: &FragilityProver) -> (Proof<Bls12>, f64) {
+        let fragility = reference_fragility(&near_barrier(), &LagrangianConfig::default()).unwrap();
+        (prover.prove(&near_barrier(), fragility).unwrap(), fragility)
+    }
 
     #[test]
     fn test_prover_setup() {
@@ -156,39 +692,516 @@ mod tests {
         assert!(prover.params.vk.alpha_g1.is_identity().unwrap_u8() == 0);
     }
 
+    #[test]
+    fn test_deterministic_setup_reproducible() {
+        let first = FragilityProver::setup_deterministic(42).export_verifying_key();
+        let second = FragilityProver::setup_deterministic(42).export_verifying_key();
+        assert_eq!(first, second);
+        assert_ne!(first, test_prover().export_verifying_key());
+    }
+
+    #[test]
+    fn test_loaded_params_prove_and_verify() {
+        let mut bytes = Vec::new();
+        test_prover().save_params(&mut bytes).unwrap();
+
+        let prover = FragilityProver::load_params(bytes.as_slice(), None).unwrap();
+        let verifier = FragilityProver::load_params(bytes.as_slice(), Some(test_prover().params_fingerprint())).unwrap();
+        assert_eq!(verifier.params_fingerprint(), test_prover().params_fingerprint());
+
+        let (proof, fragility) = proven(&prover);
+
+        assert!(verifier.verify(&proof, &near_barrier(), fragility).unwrap());
+    }
+
+    #[test]
+    fn test_serialized_proof_round_trips() {
+        let prover = test_prover();
+        let (proof, fragility) = proven(prover);
+
+        let bytes = serialize_proof(&proof);
+        assert_eq!(bytes.len(), PROOF_BYTES);
+        assert!(prover.verify(&deserialize_proof(&bytes).unwrap(), &near_barrier(), fragility).unwrap());
+
+        let from_hex = proof_from_hex(&proof_to_hex(&proof)).unwrap();
+        assert!(prover.verify(&from_hex, &near_barrier(), fragility).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_proof_bytes_rejected() {
+        let prover = test_prover();
+        let bytes = serialize_proof(&proven(prover).0);
+
+        for len in [0, 1, 48, PROOF_BYTES - 1] {
+            assert!(matches!(deserialize_proof(&bytes[..len]), Err(ProofError::InvalidProof { .. })));
+        }
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(deserialize_proof(&padded).is_err());
+        assert!(deserialize_proof(&[0xff; PROOF_BYTES]).is_err());
+        assert!(proof_from_hex("not hex").is_err());
+    }
+
+    #[test]
+    fn test_fractional_amounts_prove_and_verify() {
+        let prover = test_prover();
+
+        let state = BankState {
+            tier1_capital: 8_003.137_5,
+            total_assets: 100_000.25,
+            liquidity_coverage: 1.234_567,
+            entropy_index: 2.5,
+        };
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        let proof = prover.prove(&state, fragility).unwrap();
+
+        assert!(prover.verify(&proof, &state, fragility).unwrap());
+        assert!(prover.verify(&proof, &state, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_params_rejected() {
+        let mut bytes = Vec::new();
+        test_prover().save_params(&mut bytes).unwrap();
+
+        let truncated = FragilityProver::load_params(&bytes[..bytes.len() / 2], None);
+        assert!(matches!(truncated, Err(ProofError::InvalidParameters { .. })));
+
+        let mut flipped = bytes.clone();
+        flipped[100] ^= 0xff;
+        let corrupted = FragilityProver::load_params(flipped.as_slice(), None);
+        assert!(matches!(corrupted, Err(ProofError::InvalidParameters { .. })));
+    }
+
+    #[test]
+    fn test_corrupt_param_file_fingerprint_mismatch() {
+        let prover = test_prover();
+        let path = std::env::temp_dir().join(format!("olo-params-pinned-{}.bin", std::process::id()));
+        prover.save_params(File::create(&path).unwrap()).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        fs::write(&path, &bytes).unwrap();
+
+        let loaded = FragilityProver::load_params(File::open(&path).unwrap(), Some(prover.params_fingerprint()));
+        fs::remove_file(&path).unwrap();
+        match loaded {
+            Err(ProofError::ParameterMismatch { expected, actual }) => {
+                assert_eq!(expected, prover.params_fingerprint());
+                assert_eq!(actual, <[u8; 32]>::from(Sha256::digest(&bytes)));
+            }
+            other => panic!("expected a fingerprint mismatch, got {:?}", other.err()),
+        }
+
+        // Envelopes name the parameters they were made with
+        let fragility = reference_fragility(&near_barrier(), &LagrangianConfig::default()).unwrap();
+        let envelope = prover.prove_enveloped(&near_barrier(), fragility, "12D3KooWtest", None).unwrap();
+        assert_eq!(envelope.params_fingerprint, Some(prover.params_fingerprint()));
+        let foreign = ProofEnvelope { params_fingerprint: Some([9u8; 32]), ..envelope };
+        assert!(matches!(
+            prover.verify_envelope(&foreign, None),
+            Err(ProofError::ParameterMismatch { expected, .. }) if expected == [9u8; 32]
+        ));
+    }
+
+    #[test]
+    fn test_load_or_setup_persists() {
+        let path = std::env::temp_dir().join(format!("olo-params-{}.bin", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        let first = FragilityProver::load_or_setup(&path).unwrap();
+        assert!(path.exists());
+        let second = FragilityProver::load_or_setup(&path).unwrap();
+        assert!(first.params.vk == second.params.vk);
+
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_proof_generation() {
-        let prover = FragilityProver::setup();
-        
+        let prover = test_prover();
+
         let state = BankState {
-            assets: 1000.0,
-            liabilities: 900.0,
-            equity: 100.0,
-            leverage: 9.0,
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
         };
 
-        let fragility = 15.0;
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
         let proof = prover.prove(&state, fragility);
-        
+
         assert!(proof.is_ok());
     }
 
+    #[test]
+    fn test_altered_state_fails_commitment() {
+        let prover = test_prover();
+        let (proof, fragility) = proven(prover);
+        assert!(prover.verify(&proof, &near_barrier(), fragility).unwrap());
+
+        // Each field changed by the smallest encodable step after proving
+        let altered = [
+            BankState { tier1_capital: 8_002.500_001, ..near_barrier() },
+            BankState { total_assets: 100_000.000_001, ..near_barrier() },
+            BankState { liquidity_coverage: 1.200_001, ..near_barrier() },
+            BankState { entropy_index: 2.000_001, ..near_barrier() },
+        ];
+        for state in &altered {
+            assert!(!prover.verify(&proof, state, fragility).unwrap(), "{:?}", state);
+        }
+    }
+
     #[test]
     fn test_proof_verification() {
-        let prover = FragilityProver::setup();
-        
-        let state = BankState {
-            assets: 1000.0,
-            liabilities: 900.0,
-            equity: 100.0,
-            leverage: 9.0,
-        };
+        let prover = test_prover();
+        let (proof, fragility) = proven(prover);
 
-        let fragility = 15.0;
-        let proof = prover.prove(&state, fragility).unwrap();
-        
-        let verified = prover.verify(&proof, fragility);
+        let verified = prover.verify(&proof, &near_barrier(), fragility);
         assert!(verified.is_ok());
         assert!(verified.unwrap());
     }
+
+    /// State scoring `entropy`-dependent fragility with a negligible barrier
+    fn scored(entropy_index: f64) -> BankState {
+        BankState {
+            tier1_capital: 20_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.0,
+            entropy_index,
+        }
+    }
+
+    #[test]
+    fn test_threshold_proofs() {
+        let prover = test_prover();
+        let config = LagrangianConfig::default();
+        let (below, above) = (scored(7.0), scored(8.4));
+        assert!((reference_fragility(&below, &config).unwrap() - 29.0).abs() < 0.5);
+        assert!((reference_fragility(&above, &config).unwrap() - 31.0).abs() < 0.5);
+
+        let proof = prover.prove_below(&below, 30.0).unwrap();
+        assert!(prover.verify_below(&proof, 30.0).unwrap());
+        assert!(!prover.verify_below(&proof, 29.0).unwrap());
+
+        // Refused up front, and a forced proof does not verify
+        assert!(prover.prove_below(&above, 30.0).is_err());
+        let forced = ThresholdCircuit {
+            config: config.clone(),
+            witness: Some(FragilityWitness::new(&above, &config).unwrap()),
+            threshold: Some(FixedPoint::encode(30.0).unwrap()),
+            period: Some(Scalar::zero()),
+        };
+        let forced = create_random_proof(forced, &prover.threshold_params, &mut OsRng).unwrap();
+        assert!(!prover.verify_below(&forced, 30.0).unwrap());
+    }
+
+    #[test]
+    fn test_threshold_proof_hides_score() {
+        let prover = test_prover();
+
+        // Proofs for different scores under the same bound have the same
+        // shape and check against the same public input only
+        let low = serialize_proof(&prover.prove_below(&scored(1.0), 30.0).unwrap());
+        let high = serialize_proof(&prover.prove_below(&scored(7.0), 30.0).unwrap());
+        assert_eq!(low.len(), high.len());
+        for bytes in [&low, &high] {
+            let proof = deserialize_proof(bytes).unwrap();
+            assert!(prover.verify_below(&proof, 30.0).unwrap());
+            assert!(!prover.verify(&proof, &scored(1.0), 29.0).unwrap());
+        }
+
+        // Proving is randomized: the same statement yields different bytes
+        let again = serialize_proof(&prover.prove_below(&scored(7.0), 30.0).unwrap());
+        assert_ne!(again, high);
+    }
+
+    /// Proofs for a few states with their public inputs
+    fn batch_items(prover: &FragilityProver) -> Vec<(Proof<Bls12>, PublicInputs)> {
+        [1.0, 3.0, 5.0, 7.0]
+            .iter()
+            .map(|&entropy| {
+                let state = scored(entropy);
+                let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+                let proof = prover.prove(&state, fragility).unwrap();
+                let inputs = PublicInputs::new(CircuitId::Fragility)
+                    .fragility(fragility)
+                    .commitment(state_commitment(&state).unwrap());
+                (proof, inputs)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_verification() {
+        let prover = test_prover();
+        let mut items = batch_items(prover);
+        assert_eq!(prover.verify_batch(&items).unwrap(), BatchOutcome::AllValid);
+        assert!(prover.verify_batch(&[]).unwrap().is_valid());
+
+        // One proof checked against the wrong score
+        items[2].1 = items[2].1.clone().fragility(50.0);
+        assert_eq!(prover.verify_batch(&items).unwrap(), BatchOutcome::Failed(vec![2]));
+
+        // Inputs for another circuit are a caller error
+        items[0].1 = PublicInputs::new(CircuitId::Threshold).threshold(30.0);
+        assert!(matches!(prover.verify_batch(&items), Err(ProofError::PublicInputMismatch { .. })));
+    }
+
+    fn portfolio(weights: &[f64]) -> Vec<Position> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, &weight)| Position { asset: format!("A{}", i), weight })
+            .collect()
+    }
+
+    #[test]
+    fn test_entropy_proofs() {
+        let prover = test_prover();
+        let diversified = portfolio(&[0.2, 0.15, 0.15, 0.1, 0.1, 0.1, 0.1, 0.1]);
+        let concentrated = portfolio(&[0.97, 0.01, 0.01, 0.01]);
+
+        let proof = prover.prove_entropy_at_least(&diversified, 2.0).unwrap();
+        assert!(prover.verify_entropy_at_least(&proof, 2.0).unwrap());
+        assert!(!prover.verify_entropy_at_least(&proof, 2.5).unwrap());
+        assert!(prover.verifier().verify_entropy_at_least(&proof, 2.0).unwrap());
+
+        // Refused up front, and a forced proof does not verify
+        let err = prover.prove_entropy_at_least(&concentrated, 2.0).unwrap_err();
+        assert!(err.to_string().contains("below bound 2"), "{}", err);
+        let forced = EntropyCircuit {
+            witness: Some(EntropyWitness::new(&concentrated).unwrap()),
+            bound: Some(FixedPoint::encode(2.0).unwrap()),
+            period: Some(Scalar::zero()),
+        };
+        let forced = create_random_proof(forced, &prover.entropy_params, &mut OsRng).unwrap();
+        assert!(!prover.verify_entropy_at_least(&forced, 2.0).unwrap());
+    }
+
+    #[test]
+    fn test_enveloped_proofs() {
+        let prover = test_prover();
+        let fragility = reference_fragility(&near_barrier(), &LagrangianConfig::default()).unwrap();
+        let envelope = prover.prove_enveloped(&near_barrier(), fragility, "12D3KooWtest", None).unwrap();
+        assert_eq!(envelope.commitment, Some(state_commitment(&near_barrier()).unwrap().to_bytes()));
+        assert!(prover.verify_envelope(&envelope, None).unwrap());
+
+        let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert!(prover.verifier().verify_envelope(&decoded, None).unwrap());
+
+        // Another state's commitment does not verify
+        let other = state_commitment(&scored(1.0)).unwrap().to_bytes();
+        let moved = ProofEnvelope { commitment: Some(other), ..envelope.clone() };
+        assert!(!prover.verify_envelope(&moved, None).unwrap());
+
+        // A signed envelope is bound to its signer; claiming it for another key fails
+        let mut signed = envelope;
+        signed.sign(&SigningKey::generate(&mut OsRng));
+        assert!(prover.verify_envelope(&signed, None).unwrap());
+        let claimed = SigningKey::generate(&mut OsRng).verifying_key().to_bytes();
+        let relayed = ProofEnvelope { prover_key: Some(claimed), ..signed };
+        assert!(matches!(prover.verify_envelope(&relayed, None), Err(ProofError::IdentityMismatch { .. })));
+    }
+
+    #[test]
+    fn test_envelope_replayed_into_wrong_period_rejected() {
+        let prover = test_prover();
+        let fragility = reference_fragility(&near_barrier(), &LagrangianConfig::default()).unwrap();
+        let envelope = prover.prove_enveloped(&near_barrier(), fragility, "12D3KooWtest", Some(96)).unwrap();
+        assert!(prover.verify_envelope(&envelope, Some(96)).unwrap());
+        assert!(matches!(
+            prover.verify_envelope(&envelope, Some(97)),
+            Err(ProofError::PeriodMismatch { expected: Some(97), found: Some(96) })
+        ));
+        assert!(matches!(prover.verify_envelope(&envelope, None), Err(ProofError::PeriodMismatch { .. })));
+
+        // Relabelling the envelope does not move the proof
+        let relabelled = ProofEnvelope { period: Some(97), ..envelope.clone() };
+        assert!(!prover.verify_envelope(&relabelled, Some(97)).unwrap());
+        assert!(prover.prove_enveloped(&near_barrier(), fragility, "12D3KooWtest", Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_batch_proving_preserves_order() {
+        let prover = test_prover();
+        let mut states: Vec<(BankState, f64)> = [1.0, 3.0, 5.0, 7.0, 9.0]
+            .iter()
+            .map(|&entropy| {
+                let state = scored(entropy);
+                let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+                (state, fragility)
+            })
+            .collect();
+        // A score the state does not produce fails on its own
+        states[3].1 += 1.0;
+
+        let config = BatchProveConfig {
+            chunk_size: 2,
+            prover_id: "12D3KooWbatch".to_string(),
+            period: Some(96),
+        };
+        let results = prover.prove_batch_with(&states, &config);
+        assert_eq!(results.len(), states.len());
+        for (i, ((state, fragility), result)) in states.iter().zip(&results).enumerate() {
+            if i == 3 {
+                assert!(matches!(result, Err(ProofError::InvalidWitness { .. })));
+                continue;
+            }
+            let envelope = result.as_ref().unwrap();
+            assert_eq!(envelope.public_inputs, vec![FixedPoint::to_units(*fragility).unwrap()]);
+            assert_eq!(envelope.commitment, Some(state_commitment(state).unwrap().to_bytes()));
+            assert_eq!(envelope.prover, "12D3KooWbatch");
+            assert!(envelope.proving_ms.is_some());
+            assert!(prover.verify_envelope(envelope, Some(96)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_unknown_envelope_version_rejected_first() {
+        let prover = test_prover();
+
+        // Garbage proof bytes would fail decoding; the version check fires first
+        let envelope = ProofEnvelope {
+            proof: vec![0xff; 3],
+            circuit: CircuitId::Threshold,
+            backend: BackendId::Bellman,
+            circuit_version: VERIFYING_KEY_VERSION + 1,
+            public_inputs: vec![],
+            commitment: None,
+            period: None,
+            params_fingerprint: None,
+            created_at: 0,
+            proving_ms: None,
+            prover: String::new(),
+            prover_key: None,
+            signature: None,
+        };
+        assert!(matches!(
+            prover.verify_envelope(&envelope, None),
+            Err(ProofError::KeyVersionMismatch { found, .. }) if found == VERIFYING_KEY_VERSION + 1
+        ));
+
+        let current = ProofEnvelope { circuit_version: VERIFYING_KEY_VERSION, ..envelope };
+        assert!(matches!(prover.verify_envelope(&current, None), Err(ProofError::PublicInputMismatch { .. })));
+    }
+
+    #[test]
+    fn test_group_solvency_proofs() {
+        let prover = test_prover();
+        let subsidiary = |tier1_capital, total_assets| BankState { tier1_capital, total_assets, ..near_barrier() };
+
+        // One weak subsidiary, carried by the other
+        let group = [subsidiary(15_000.0, 100_000.0), subsidiary(1_000.0, 50_000.0)];
+        let commitment = group_commitment(&group).unwrap();
+        let proof = prover.prove_group_solvency(&group, 0.08).unwrap();
+        assert!(prover.verify_group_solvency(&proof, commitment, 0.08).unwrap());
+        assert!(!prover.verify_group_solvency(&proof, commitment, 0.1).unwrap());
+        let other = group_commitment(&group[..1]).unwrap();
+        assert!(!prover.verify_group_solvency(&proof, other, 0.08).unwrap());
+
+        // Short in aggregate: refused up front
+        let failing = [subsidiary(5_000.0, 100_000.0), subsidiary(2_000.0, 50_000.0)];
+        let err = prover.prove_group_solvency(&failing, 0.08).unwrap_err();
+        assert!(err.to_string().contains("group capital 7000 is below 0.08"), "{}", err);
+    }
+
+    #[test]
+    fn test_lcr_proofs() {
+        let prover = test_prover();
+        let compliant = LcrInputs { hqla: 130_000.0, net_outflows: 100_000.0 };
+        let proof = prover.prove_lcr_at_least(&compliant, 1.0).unwrap();
+        assert!(prover.verify_lcr_at_least(&proof, 1.0).unwrap());
+        assert!(!prover.verify_lcr_at_least(&proof, 1.3).unwrap());
+
+        // Exactly at the minimum
+        let boundary = LcrInputs { hqla: 100_000.0, net_outflows: 100_000.0 };
+        let proof = prover.prove_lcr_at_least(&boundary, 1.0).unwrap();
+        assert!(prover.verify_lcr_at_least(&proof, 1.0).unwrap());
+
+        let short = LcrInputs { hqla: 90_000.0, net_outflows: 100_000.0 };
+        let err = prover.prove_lcr_at_least(&short, 1.0).unwrap_err();
+        assert!(err.to_string().contains("hqla 90000 is below 1 times net outflows 100000"), "{}", err);
+    }
+
+    #[test]
+    fn test_errors_are_typed() {
+        let prover = test_prover();
+
+        assert!(matches!(
+            prover.verify(&proven(prover).0, &near_barrier(), f64::NAN),
+            Err(ProofError::Encoding(EncodingError::NotFinite { .. }))
+        ));
+
+        let err = prover.prove_below(&scored(8.4), 30.0).unwrap_err();
+        assert!(matches!(err, ProofError::InvalidWitness { .. }));
+        assert!(err.to_string().contains("not below threshold 30"));
+
+        // A directory is found but cannot be read as parameters
+        let err = FragilityProver::load_or_setup(std::env::temp_dir()).err().unwrap();
+        assert!(matches!(err, ProofError::ParameterIo(_)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_tampered_fragility_fails() {
+        let prover = test_prover();
+        let (proof, fragility) = proven(prover);
+
+        // Verifying against another score, off by one fixed-point unit
+        assert!(!prover.verify(&proof, &near_barrier(), fragility + 1e-6).unwrap());
+
+        // Proving a score the state does not produce is refused up front
+        let err = prover.prove(&near_barrier(), fragility - 1.0).unwrap_err();
+        assert!(matches!(err, ProofError::InvalidWitness { .. }));
+        assert!(err.to_string().contains(&format!("circuit fragility {} by", fragility)));
+    }
+
+    #[test]
+    fn test_validate_witness_names_field() {
+        assert!(validate_witness(&near_barrier()).is_ok());
+
+        let cases = [
+            (BankState { total_assets: -3.2, ..near_barrier() }, "total_assets: cannot encode negative value -3.2"),
+            (BankState { entropy_index: f64::NAN, ..near_barrier() }, "entropy_index: cannot encode non-finite"),
+            (BankState { tier1_capital: 1e20, ..near_barrier() }, "tier1_capital: value 100000000000000000000 exceeds"),
+            (BankState { liquidity_coverage: 1e-7, ..near_barrier() }, "liquidity_coverage 0.0000001 rounds to zero"),
+        ];
+        for (state, message) in cases {
+            let err = validate_witness(&state).unwrap_err();
+            assert!(matches!(err, ProofError::InvalidWitness { .. }));
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+
+        // prove runs the same check
+        let prover = test_prover();
+        let zero_lcr = BankState { liquidity_coverage: 0.0, ..near_barrier() };
+        let err = prover.prove(&zero_lcr, 10.0).unwrap_err();
+        assert!(err.to_string().contains("liquidity_coverage"));
+    }
+
+    #[tokio::test]
+    async fn test_async_proofs_respect_concurrency_limit() {
+        let prover = Arc::new(FragilityProver::setup_deterministic(7).with_max_concurrent_proofs(1));
+        let fragility = reference_fragility(&near_barrier(), &LagrangianConfig::default()).unwrap();
+        let prove = |period| prover.prove_async(near_barrier(), fragility, "12D3KooWasync".to_string(), Some(period));
+
+        let started = Instant::now();
+        let (first, second) = tokio::join!(prove(1), prove(2));
+        let (first, second) = (first.unwrap(), second.unwrap());
+        // With one slot the second proof cannot start before the first ends
+        let proving = first.proving_ms.unwrap() + second.proving_ms.unwrap();
+        assert!(started.elapsed().as_millis() as u64 >= proving);
+        assert!(prover.verifier().verify_envelope(&first, Some(1)).unwrap());
+        assert!(prover.verifier().verify_envelope(&second, Some(2)).unwrap());
+
+        // A proof waiting for the slot is cancelled by dropping it
+        let running = tokio::spawn(prove(3));
+        tokio::task::yield_now().await;
+        assert_eq!(prover.proof_slots.available_permits(), 0);
+        let queued = tokio::time::timeout(std::time::Duration::from_millis(20), prove(4)).await;
+        assert!(queued.is_err());
+        assert_eq!(running.await.unwrap().unwrap().period, Some(3));
+        assert_eq!(prover.proof_slots.available_permits(), 1);
+    }
 }
@@ -0,0 +1,241 @@
+//! Public Inputs
+//!
+//! Typed builder for the public inputs a proof is verified against. Setters
+//! take plain values and apply the fixed-point codec; `to_scalars` lays them
+//! out in the order the circuit allocates them:
+//!
+//! | Circuit         | Inputs                        |
+//! |-----------------|-------------------------------|
+//! | `Fragility`     | fragility, commitment, period |
+//! | `Threshold`     | threshold, period             |
+//! | `Entropy`       | bound, period                 |
+//! | `GroupSolvency` | min_ratio, commitment, period |
+//! | `Lcr`           | multiplier, period            |
+//!
+//! The reporting period defaults to 0, meaning the proof is not bound to one.
+//!
+//! Inputs that cannot belong to the circuit, such as a missing commitment or
+//! a score outside `[0, 100]`, fail with `ProofError::PublicInputMismatch`
+//! instead of silently producing a proof that does not verify.
+
+use bls12_381::Scalar;
+
+use crate::proofs::encoding::{EncodingError, FixedPoint};
+use crate::proofs::envelope::CircuitId;
+use crate::proofs::error::ProofError;
+
+/// Fixed-point input slot; each circuit takes exactly one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Fragility,
+    Threshold,
+    Bound,
+    MinRatio,
+    Multiplier,
+}
+
+impl Slot {
+    fn of(circuit: CircuitId) -> Self {
+        match circuit {
+            CircuitId::Fragility => Slot::Fragility,
+            CircuitId::Threshold => Slot::Threshold,
+            CircuitId::Entropy => Slot::Bound,
+            CircuitId::GroupSolvency => Slot::MinRatio,
+            CircuitId::Lcr => Slot::Multiplier,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Slot::Fragility => "fragility",
+            Slot::Threshold => "threshold",
+            Slot::Bound => "bound",
+            Slot::MinRatio => "min_ratio",
+            Slot::Multiplier => "multiplier",
+        }
+    }
+}
+
+/// Whether the circuit's second public input is a commitment
+fn committed(circuit: CircuitId) -> bool {
+    matches!(circuit, CircuitId::Fragility | CircuitId::GroupSolvency)
+}
+
+/// Public inputs for one circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicInputs {
+    circuit: CircuitId,
+    /// Fixed-point values as set, encoding failures kept for `to_scalars`
+    values: Vec<(Slot, Result<u64, EncodingError>)>,
+    commitment: Option<Scalar>,
+    period: u64,
+}
+
+impl PublicInputs {
+    /// Empty inputs for `circuit`
+    pub fn new(circuit: CircuitId) -> Self {
+        Self {
+            circuit,
+            values: Vec::new(),
+            commitment: None,
+            period: 0,
+        }
+    }
+
+    fn set(mut self, slot: Slot, value: f64) -> Self {
+        self.values.retain(|(s, _)| *s != slot);
+        self.values.push((slot, FixedPoint::to_units(value)));
+        self
+    }
+
+    /// Fragility score, for `CircuitId::Fragility`
+    pub fn fragility(self, score: f64) -> Self {
+        self.set(Slot::Fragility, score)
+    }
+
+    /// Score bound, for `CircuitId::Threshold`
+    pub fn threshold(self, threshold: f64) -> Self {
+        self.set(Slot::Threshold, threshold)
+    }
+
+    /// Entropy bound, for `CircuitId::Entropy`
+    pub fn bound(self, bound: f64) -> Self {
+        self.set(Slot::Bound, bound)
+    }
+
+    /// Capital ratio, for `CircuitId::GroupSolvency`
+    pub fn min_ratio(self, ratio: f64) -> Self {
+        self.set(Slot::MinRatio, ratio)
+    }
+
+    /// LCR multiplier, for `CircuitId::Lcr`
+    pub fn multiplier(self, k: f64) -> Self {
+        self.set(Slot::Multiplier, k)
+    }
+
+    /// State or group commitment, for the circuits that take one
+    pub fn commitment(mut self, commitment: Scalar) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    /// Reporting period the proof is bound to
+    pub fn period(mut self, period: u64) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Circuit these inputs are for
+    pub fn circuit(&self) -> CircuitId {
+        self.circuit
+    }
+
+    /// Field elements in circuit order
+    ///
+    /// Values with no fixed-point encoding fail with `ProofError::Encoding`;
+    /// inputs the circuit does not take, or does not get, fail with
+    /// `ProofError::PublicInputMismatch`.
+    pub fn to_scalars(&self) -> Result<Vec<Scalar>, ProofError> {
+        let mismatch = |reason: String| ProofError::PublicInputMismatch { reason };
+        let expected = Slot::of(self.circuit);
+
+        let mut units = None;
+        for (slot, value) in &self.values {
+            if *slot != expected {
+                return Err(mismatch(format!("{:?} proofs take no {}", self.circuit, slot.name())));
+            }
+            units = Some((*value)?);
+        }
+        let units =
+            units.ok_or_else(|| mismatch(format!("{:?} proofs need a {}", self.circuit, expected.name())))?;
+        if expected == Slot::Fragility && units > 100 * FixedPoint::SCALE {
+            return Err(mismatch(format!(
+                "fragility {} is outside [0, 100]",
+                units as f64 / FixedPoint::SCALE as f64
+            )));
+        }
+
+        let mut scalars = vec![Scalar::from(units)];
+        match (committed(self.circuit), self.commitment) {
+            (true, Some(commitment)) => scalars.push(commitment),
+            (true, None) => return Err(mismatch(format!("{:?} proofs need a commitment", self.circuit))),
+            (false, Some(_)) => return Err(mismatch(format!("{:?} proofs take no commitment", self.circuit))),
+            (false, None) => {}
+        }
+        scalars.push(Scalar::from(self.period));
+        Ok(scalars)
+    }
+
+    /// Check raw field elements against the layout of `circuit`
+    ///
+    /// For inputs that arrive already encoded. The fixed-point slot must be a
+    /// 64-bit encoding (and a score at most 100), so a value encoded at the
+    /// wrong scale is reported here rather than as a failed proof.
+    pub fn from_scalars(circuit: CircuitId, scalars: &[Scalar]) -> Result<Self, ProofError> {
+        let mismatch = |reason: String| ProofError::PublicInputMismatch { reason };
+        let count = 2 + committed(circuit) as usize;
+        if scalars.len() != count {
+            return Err(mismatch(format!(
+                "{:?} proofs take {} public inputs, got {}",
+                circuit,
+                count,
+                scalars.len()
+            )));
+        }
+
+        let units = small(&scalars[0])
+            .ok_or_else(|| mismatch(format!("{} is not a fixed-point encoding", Slot::of(circuit).name())))?;
+        let period = small(&scalars[count - 1]).ok_or_else(|| mismatch("period is not a u64".to_string()))?;
+        let inputs = Self {
+            circuit,
+            values: vec![(Slot::of(circuit), Ok(units))],
+            commitment: committed(circuit).then(|| scalars[1]),
+            period,
+        };
+        inputs.to_scalars()?;
+        Ok(inputs)
+    }
+}
+
+/// `scalar` as a `u64`, if it is one
+fn small(scalar: &Scalar) -> Option<u64> {
+    let bytes = scalar.to_bytes();
+    bytes[8..]
+        .iter()
+        .all(|&b| b == 0)
+        .then(|| u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_ordering() {
+        let commitment = Scalar::from(7u64);
+        let inputs = PublicInputs::new(CircuitId::Fragility).commitment(commitment).fragility(42.5);
+        assert_eq!(inputs.to_scalars().unwrap(), vec![Scalar::from(42_500_000u64), commitment, Scalar::zero()]);
+
+        let group = PublicInputs::new(CircuitId::GroupSolvency).period(2024).min_ratio(0.08).commitment(commitment);
+        let expected = vec![Scalar::from(80_000u64), commitment, Scalar::from(2024u64)];
+        assert_eq!(group.to_scalars().unwrap(), expected);
+        assert_eq!(PublicInputs::from_scalars(CircuitId::GroupSolvency, &group.to_scalars().unwrap()).unwrap(), group);
+    }
+
+    #[test]
+    fn test_mismatched_inputs_rejected() {
+        let mismatch = |inputs: PublicInputs| {
+            matches!(inputs.to_scalars(), Err(ProofError::PublicInputMismatch { .. }))
+        };
+        assert!(mismatch(PublicInputs::new(CircuitId::Fragility).fragility(42.5)));
+        assert!(mismatch(PublicInputs::new(CircuitId::Threshold).fragility(42.5)));
+        assert!(mismatch(PublicInputs::new(CircuitId::Threshold).threshold(30.0).commitment(Scalar::one())));
+        // A score multiplied by 1000 once too often
+        assert!(mismatch(PublicInputs::new(CircuitId::Fragility).fragility(42_500.0).commitment(Scalar::one())));
+
+        assert!(matches!(
+            PublicInputs::new(CircuitId::Entropy).bound(f64::NAN).to_scalars(),
+            Err(ProofError::Encoding(EncodingError::NotFinite { .. }))
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+//! Circuit Registry
+//!
+//! Verifying keys for earlier circuit versions, so envelopes made before a
+//! circuit changed still verify against the parameters they were made with.
+//! Keys are registered directly or loaded on first use from a directory
+//! holding one bellman-encoded key per `(circuit, version)`, named by
+//! `key_file_name`. Versions on the deprecation list are refused outright.
+//!
+//! A `FragilityVerifier` built `with_registry` keeps using its own keys for
+//! the current `VERIFYING_KEY_VERSION` and consults the registry for the rest.
+
+use bellman::groth16::{prepare_verifying_key, PreparedVerifyingKey, VerifyingKey};
+use bls12_381::Bls12;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::proofs::envelope::CircuitId;
+use crate::proofs::error::ProofError;
+use crate::proofs::verifier::VERIFYING_KEY_VERSION;
+
+/// Prepared keys by circuit and version
+type KeyMap = HashMap<(CircuitId, u32), Arc<PreparedVerifyingKey<Bls12>>>;
+
+/// First circuit version with a reporting period public input
+pub const PERIOD_VERSION: u32 = 7;
+
+/// File holding the key for `circuit` at `version`, e.g. `lcr-v7.vk`
+pub fn key_file_name(circuit: CircuitId, version: u32) -> String {
+    let name = match circuit {
+        CircuitId::Fragility => "fragility",
+        CircuitId::Threshold => "threshold",
+        CircuitId::Entropy => "entropy",
+        CircuitId::GroupSolvency => "group_solvency",
+        CircuitId::Lcr => "lcr",
+    };
+    format!("{}-v{}.vk", name, version)
+}
+
+/// Verifying keys by circuit and version
+#[derive(Default)]
+pub struct CircuitRegistry {
+    directory: Option<PathBuf>,
+    keys: Mutex<KeyMap>,
+    deprecated: HashSet<(CircuitId, u32)>,
+}
+
+impl CircuitRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load keys that are not registered from `directory` on first use
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Register the key for `circuit` at `version`
+    pub fn with_key(self, circuit: CircuitId, version: u32, vk: &VerifyingKey<Bls12>) -> Self {
+        self.lock().insert((circuit, version), Arc::new(prepare_verifying_key(vk)));
+        self
+    }
+
+    /// Refuse proofs for `circuit` at `version`
+    ///
+    /// Applies to the current version too, when the registry is attached to
+    /// a verifier.
+    pub fn with_deprecated(mut self, circuit: CircuitId, version: u32) -> Self {
+        self.deprecated.insert((circuit, version));
+        self
+    }
+
+    /// Fail with `ProofError::DeprecatedCircuit` if the version is retired
+    pub fn check_deprecated(&self, circuit: CircuitId, version: u32) -> Result<(), ProofError> {
+        if self.deprecated.contains(&(circuit, version)) {
+            return Err(ProofError::DeprecatedCircuit { circuit, version });
+        }
+        Ok(())
+    }
+
+    /// Key for `circuit` at `version`, loading it if needed
+    ///
+    /// Retired versions fail with `ProofError::DeprecatedCircuit`; versions
+    /// with no registered key and no key file with
+    /// `ProofError::KeyVersionMismatch`.
+    pub fn key(&self, circuit: CircuitId, version: u32) -> Result<Arc<PreparedVerifyingKey<Bls12>>, ProofError> {
+        self.check_deprecated(circuit, version)?;
+        if let Some(pvk) = self.lock().get(&(circuit, version)) {
+            return Ok(pvk.clone());
+        }
+
+        let unknown = || ProofError::KeyVersionMismatch {
+            expected: VERIFYING_KEY_VERSION,
+            found: version,
+        };
+        let directory = self.directory.as_ref().ok_or_else(unknown)?;
+        let file = match File::open(directory.join(key_file_name(circuit, version))) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(unknown()),
+            Err(e) => return Err(ProofError::ParameterIo(e)),
+        };
+        let vk = VerifyingKey::read(BufReader::new(file)).map_err(|e| ProofError::InvalidParameters {
+            reason: format!("{}: {}", key_file_name(circuit, version), e),
+        })?;
+        let pvk = Arc::new(prepare_verifying_key(&vk));
+        self.lock().insert((circuit, version), pvk.clone());
+        Ok(pvk)
+    }
+
+    /// Keys registered or loaded so far
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no key is registered or loaded yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, KeyMap> {
+        self.keys.lock().expect("registry lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proofs::encoding::FixedPoint;
+    use crate::proofs::envelope::ProofEnvelope;
+    use crate::proofs::lcr_circuit::{LcrCircuit, LcrInputs, LcrWitness};
+    use crate::proofs::prover::test_prover;
+    use crate::proofs::verifier::FragilityVerifier;
+    use bellman::groth16::{create_random_proof, generate_random_parameters, Parameters, Proof};
+    use bls12_381::Scalar;
+    use rand::rngs::OsRng;
+
+    /// Parameters standing in for an earlier LCR circuit version
+    fn old_params() -> Parameters<Bls12> {
+        generate_random_parameters::<Bls12, _, _>(LcrCircuit::blank(), &mut OsRng).unwrap()
+    }
+
+    const INPUTS: LcrInputs = LcrInputs { hqla: 130.0, net_outflows: 100.0 };
+
+    /// LCR proof of `k = 1` made with `params`
+    fn old_proof(params: &Parameters<Bls12>) -> Proof<Bls12> {
+        let circuit = LcrCircuit {
+            witness: Some(LcrWitness::new(&INPUTS).unwrap()),
+            multiplier: Some(FixedPoint::encode(1.0).unwrap()),
+            period: Some(Scalar::zero()),
+        };
+        create_random_proof(circuit, params, &mut OsRng).unwrap()
+    }
+
+    /// Envelope for an LCR proof of `k = 1`, labelled `version`
+    fn envelope(proof: &Proof<Bls12>, version: u32) -> ProofEnvelope {
+        let multiplier = FixedPoint::to_units(1.0).unwrap();
+        let mut envelope = ProofEnvelope::new(CircuitId::Lcr, proof, vec![multiplier], None, "12D3KooWlcr");
+        envelope.circuit_version = version;
+        envelope
+    }
+
+    fn verifier_with(registry: CircuitRegistry) -> FragilityVerifier {
+        FragilityVerifier::from_bytes(&test_prover().export_verifying_key())
+            .unwrap()
+            .with_registry(registry)
+    }
+
+    #[test]
+    fn test_old_version_verifies_against_its_key() {
+        let old = old_params();
+        let version = VERIFYING_KEY_VERSION - 1;
+        let dir = std::env::temp_dir().join(format!("olo-registry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        old.vk.write(File::create(dir.join(key_file_name(CircuitId::Lcr, version))).unwrap()).unwrap();
+
+        let registry = CircuitRegistry::new().with_directory(&dir);
+        assert!(registry.is_empty());
+        registry.key(CircuitId::Lcr, version).unwrap();
+        assert_eq!(registry.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Loaded once; the file is no longer needed
+        let verifier = verifier_with(registry);
+        assert!(verifier.verify_envelope(&envelope(&old_proof(&old), version), None).unwrap());
+        // Current envelopes still use the verifier's own keys
+        let proof = test_prover().prove_lcr_at_least(&INPUTS, 1.0).unwrap();
+        let current = envelope(&proof, VERIFYING_KEY_VERSION);
+        assert!(verifier.verify_envelope(&current, None).unwrap());
+        // The old key does not vouch for current-version proofs
+        let relabelled = ProofEnvelope { circuit_version: version, ..current };
+        assert!(!verifier.verify_envelope(&relabelled, None).unwrap());
+    }
+
+    #[test]
+    fn test_deprecated_and_unknown_versions_rejected() {
+        let old = old_params();
+        let version = VERIFYING_KEY_VERSION - 1;
+        let registry = CircuitRegistry::new()
+            .with_key(CircuitId::Lcr, version, &old.vk)
+            .with_key(CircuitId::Lcr, version - 1, &old.vk)
+            .with_deprecated(CircuitId::Lcr, version - 1);
+        let verifier = verifier_with(registry);
+        let proof = old_proof(&old);
+
+        assert!(verifier.verify_envelope(&envelope(&proof, version), None).unwrap());
+        assert!(matches!(
+            verifier.verify_envelope(&envelope(&proof, version - 1), None),
+            Err(ProofError::DeprecatedCircuit { circuit: CircuitId::Lcr, version: v }) if v == version - 1
+        ));
+        assert!(matches!(
+            verifier.verify_envelope(&envelope(&proof, version - 2), None),
+            Err(ProofError::KeyVersionMismatch { .. })
+        ));
+    }
+}
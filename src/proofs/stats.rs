@@ -0,0 +1,277 @@
+//! Prover Statistics
+//!
+//! Opt-in timing and size counters for capacity planning. A prover built
+//! with `FragilityProver::with_stats` records every proving call by circuit,
+//! with its duration, constraint count, and witness size, as well as every
+//! verification and batch. `ProverStats::snapshot` copies the counters out
+//! as a serializable `StatsSnapshot`.
+
+use bellman::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use bls12_381::Scalar;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::proofs::envelope::CircuitId;
+
+/// Proving counters for one circuit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    /// Circuit these counters are for
+    pub circuit: CircuitId,
+    /// Proving calls, including failed ones
+    pub proofs: u64,
+    /// Proving calls that returned an error
+    pub failures: u64,
+    /// Time spent proving, in microseconds
+    pub total_micros: u64,
+    /// Fastest proving call, in microseconds
+    pub min_micros: u64,
+    /// Slowest proving call, in microseconds
+    pub max_micros: u64,
+    /// Constraints in the circuit
+    pub constraints: usize,
+    /// Private variables assigned per proof
+    pub witness_size: usize,
+}
+
+impl CircuitStats {
+    /// Mean proving time in microseconds, 0 before the first proof
+    pub fn mean_micros(&self) -> u64 {
+        self.total_micros.checked_div(self.proofs).unwrap_or(0)
+    }
+}
+
+/// Counters at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    /// Proving counters, one entry per circuit proven, in `CircuitId::tag` order
+    pub circuits: Vec<CircuitStats>,
+    /// Largest witness of any recorded proof
+    pub peak_witness_size: usize,
+    /// Verification calls
+    pub verifications: u64,
+    /// Time spent verifying, in microseconds
+    pub verify_micros: u64,
+    /// Calls to `prove_batch_with`
+    pub batches: u64,
+    /// States submitted across all batches
+    pub batched_states: u64,
+    /// Wall-clock time spent in batches, in microseconds
+    pub batch_micros: u64,
+}
+
+impl StatsSnapshot {
+    /// Counters for `circuit`, if it was proven
+    pub fn circuit(&self, circuit: CircuitId) -> Option<&CircuitStats> {
+        self.circuits.iter().find(|c| c.circuit == circuit)
+    }
+}
+
+/// Constraint and witness counts of a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Shape {
+    constraints: usize,
+    witness_size: usize,
+}
+
+/// Thread-safe collector behind `FragilityProver::with_stats`
+#[derive(Debug, Default)]
+pub struct ProverStats {
+    counters: Mutex<StatsSnapshot>,
+    /// Shapes are fixed per circuit, so they are counted once and survive resets
+    shapes: Mutex<Vec<(CircuitId, Shape)>>,
+}
+
+impl ProverStats {
+    /// Copy of the current counters
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.counters.lock().expect("stats lock poisoned").clone()
+    }
+
+    /// Zero every counter
+    pub fn reset(&self) {
+        *self.counters.lock().expect("stats lock poisoned") = StatsSnapshot::default();
+    }
+
+    /// Shape of `circuit`, counted on first use
+    pub(crate) fn shape<C: Circuit<Scalar> + Clone>(&self, id: CircuitId, circuit: &C) -> Shape {
+        let mut shapes = self.shapes.lock().expect("stats lock poisoned");
+        if let Some((_, shape)) = shapes.iter().find(|(c, _)| *c == id) {
+            return *shape;
+        }
+        let mut counter = Counter::default();
+        // Counting never evaluates assignments, so synthesis cannot fail on them
+        circuit.clone().synthesize(&mut counter).ok();
+        let shape = Shape {
+            constraints: counter.constraints,
+            witness_size: counter.aux,
+        };
+        shapes.push((id, shape));
+        shape
+    }
+
+    pub(crate) fn record_proof(&self, id: CircuitId, shape: Shape, elapsed: Duration, ok: bool) {
+        let micros = elapsed.as_micros() as u64;
+        let mut counters = self.counters.lock().expect("stats lock poisoned");
+        let index = match counters.circuits.iter().position(|c| c.circuit == id) {
+            Some(index) => index,
+            None => {
+                let index = counters.circuits.partition_point(|c| c.circuit.tag() < id.tag());
+                counters.circuits.insert(
+                    index,
+                    CircuitStats {
+                        circuit: id,
+                        proofs: 0,
+                        failures: 0,
+                        total_micros: 0,
+                        min_micros: u64::MAX,
+                        max_micros: 0,
+                        constraints: shape.constraints,
+                        witness_size: shape.witness_size,
+                    },
+                );
+                index
+            }
+        };
+        let stats = &mut counters.circuits[index];
+        stats.proofs += 1;
+        stats.failures += u64::from(!ok);
+        stats.total_micros += micros;
+        stats.min_micros = stats.min_micros.min(micros);
+        stats.max_micros = stats.max_micros.max(micros);
+        counters.peak_witness_size = counters.peak_witness_size.max(shape.witness_size);
+    }
+
+    pub(crate) fn record_verification(&self, elapsed: Duration) {
+        let mut counters = self.counters.lock().expect("stats lock poisoned");
+        counters.verifications += 1;
+        counters.verify_micros += elapsed.as_micros() as u64;
+    }
+
+    pub(crate) fn record_batch(&self, states: usize, elapsed: Duration) {
+        let mut counters = self.counters.lock().expect("stats lock poisoned");
+        counters.batches += 1;
+        counters.batched_states += states as u64;
+        counters.batch_micros += elapsed.as_micros() as u64;
+    }
+}
+
+/// Constraint system that only counts variables and constraints
+#[derive(Default)]
+pub(crate) struct Counter {
+    pub(crate) inputs: usize,
+    pub(crate) aux: usize,
+    pub(crate) constraints: usize,
+}
+
+impl ConstraintSystem<Scalar> for Counter {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux += 1;
+        Ok(Variable::new_unchecked(Index::Aux(self.aux - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Input 0 is the constant one
+        self.inputs += 1;
+        Ok(Variable::new_unchecked(Index::Input(self.inputs)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::proofs::circuit::reference_fragility;
+    use crate::proofs::prover::{test_prover, FragilityProver};
+
+    #[test]
+    fn test_counters_increment_per_call() {
+        let mut bytes = Vec::new();
+        test_prover().save_params(&mut bytes).unwrap();
+        let prover = FragilityProver::load_params(bytes.as_slice(), None).unwrap().with_stats();
+        let state = BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let fragility = reference_fragility(&state, &LagrangianConfig::default()).unwrap();
+        let stats = prover.stats().unwrap();
+
+        let proof = prover.prove(&state, fragility).unwrap();
+        assert_eq!(stats.snapshot().circuit(CircuitId::Fragility).unwrap().proofs, 1);
+        assert!(prover.verify(&proof, &state, fragility).unwrap());
+        prover.prove_below(&state, 50.0).unwrap();
+        prover.prove_batch(&[(state.clone(), fragility), (state, fragility + 1.0)]);
+
+        let snapshot = stats.snapshot();
+        let fragility_stats = snapshot.circuit(CircuitId::Fragility).unwrap();
+        // The mismatched score is refused before proving, so it is not timed
+        assert_eq!((fragility_stats.proofs, fragility_stats.failures), (2, 0));
+        assert!(fragility_stats.constraints > 0 && fragility_stats.min_micros <= fragility_stats.max_micros);
+        assert_eq!(snapshot.circuit(CircuitId::Threshold).unwrap().proofs, 1);
+        assert_eq!(snapshot.circuits.iter().map(|c| c.circuit).collect::<Vec<_>>(), [CircuitId::Fragility, CircuitId::Threshold]);
+        assert_eq!(snapshot.peak_witness_size, snapshot.circuits.iter().map(|c| c.witness_size).max().unwrap());
+        assert_eq!((snapshot.verifications, snapshot.batches, snapshot.batched_states), (1, 1, 2));
+
+        stats.reset();
+        assert_eq!(stats.snapshot(), StatsSnapshot::default());
+        assert!(test_prover().stats().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_through_serde() {
+        let stats = ProverStats::default();
+        let shape = Shape {
+            constraints: 1_200,
+            witness_size: 1_150,
+        };
+        stats.record_proof(CircuitId::Lcr, shape, Duration::from_millis(40), true);
+        stats.record_proof(CircuitId::Entropy, shape, Duration::from_millis(90), false);
+        stats.record_verification(Duration::from_micros(800));
+        stats.record_batch(8, Duration::from_millis(300));
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.circuits[0].circuit, CircuitId::Entropy);
+        assert_eq!(snapshot.circuit(CircuitId::Entropy).unwrap().failures, 1);
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<StatsSnapshot>(&json).unwrap(), snapshot);
+    }
+}
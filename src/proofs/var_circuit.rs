@@ -0,0 +1,744 @@
+//! Monte Carlo VaR Circuit
+//!
+//! Groth16 circuit proving a published VaR is an order statistic of the
+//! fragility scores a committed set of shocks produces from a bank state, so
+//! the figure cannot have been picked from a favourable run.
+//!
+//! The shock draws and the base state are private. Each path's shock is
+//! hashed into a leaf, and the circuit rebuilds the MiMC Merkle root over all
+//! leaves (`shock_root`) and the base state's `state_commitment`, both public.
+//! For every path it applies the shock to the base state, recomputes the
+//! fragility score with the `FragilityCircuit` arithmetic, and sorts the
+//! scores with Batcher's odd-even merge network. The score at a public rank,
+//! `floor(quantile·paths)` as in `run_simulation`, is the public VaR. The
+//! reporting period is the last public input.
+//!
+//! # Shocks
+//!
+//! Paths follow `MultiplicativeShock` in fixed-point units: capital and
+//! assets are scaled by `max(0, 1 + 0.01·d)` and floored to whole units;
+//! liquidity coverage and entropy move by `0.01·d`, rounded. A leaf is the
+//! MiMC digest of the two factors packed as `capital + 2^64·assets`, then the
+//! two offsets packed the same way. The circuit does not model the LCR floor
+//! or the entropy clamp; a path that would reach either is refused when the
+//! witness is built.
+//!
+//! # Path count
+//!
+//! The number of paths is fixed when parameters are generated and must be a
+//! power of two. Each path costs about 2,500 constraints (its score, leaf,
+//! share of the Merkle tree, and comparators), so `VAR_PATHS` paths come to
+//! roughly 160,000, and a full 10,000-path run is out of reach. A proof only
+//! covers the committed paths it was made from: publishing a VaR over a
+//! larger run needs a committed subsample of `VAR_PATHS` paths.
+
+use bellman::groth16::{
+    create_random_proof, generate_random_parameters, prepare_verifying_key, Parameters, PreparedVerifyingKey, Proof,
+};
+use bellman::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+use bls12_381::{Bls12, Scalar};
+use rand::rngs::OsRng;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::proofs::circuit::{enforce_fragility, state_commitment, FragilityWitness};
+use crate::proofs::encoding::FixedPoint;
+use crate::proofs::error::ProofError;
+use crate::proofs::gadgets::{
+    mimc_compress, mimc_compress_native, mimc_hash, range_check, scalar_from_i128, scalar_from_u128,
+};
+use crate::proofs::prover::validate_witness;
+use crate::proofs::verifier::check_proof;
+use crate::simulation::shock::{
+    BALANCE_SHEET_SCALE, ENTROPY_INDEX_MAX, ENTROPY_SCALE, LCR_FLOOR, LCR_SCALE, MultiplicativeShock, ShockModel,
+};
+use crate::simulation::shock_set::ShockSet;
+
+/// Paths per proof in production parameters
+pub const VAR_PATHS: usize = 64;
+
+/// Bits covering 64-bit encoded amounts and shock factors
+const AMOUNT_BITS: usize = 64;
+
+/// Bits covering a remainder below the fixed-point scale (`S < 2^20`)
+const SCALE_BITS: usize = 20;
+
+/// Bits covering the gap between two scores in `[0, 100·S]`
+const FRAGILITY_BITS: usize = 27;
+
+/// Shock of one path in the form the circuit applies it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PathShock {
+    /// Capital and asset multipliers in fixed-point units
+    factors: [u64; 2],
+    /// Liquidity coverage and entropy moves in fixed-point units
+    offsets: [i64; 2],
+}
+
+impl PathShock {
+    /// Encode the `[capital, assets, lcr, entropy]` draws of one path
+    fn new(draws: &[f64]) -> Result<Self, ProofError> {
+        let factor = |d: f64| FixedPoint::to_units((1.0 + BALANCE_SHEET_SCALE * d).max(0.0));
+        let offset = |scale: f64, d: f64| (scale * d * FixedPoint::SCALE as f64).round() as i64;
+        Ok(Self {
+            factors: [factor(draws[0])?, factor(draws[1])?],
+            offsets: [offset(LCR_SCALE, draws[2]), offset(ENTROPY_SCALE, draws[3])],
+        })
+    }
+
+    /// Merkle leaf: `mimc_hash` of the packed factors and packed offsets
+    fn leaf(&self) -> Scalar {
+        mimc_hash(&[pack_factors(self.factors), pack_offsets(self.offsets)])
+    }
+}
+
+/// `capital + 2^64·assets`, unique for range-checked 64-bit halves
+fn pack_factors([capital, assets]: [u64; 2]) -> Scalar {
+    scalar_from_u128(capital as u128 | ((assets as u128) << 64))
+}
+
+/// `lcr + 2^64·entropy` over the field
+///
+/// Unambiguous because the shocked LCR and entropy are range-checked.
+fn pack_offsets([lcr, entropy]: [i64; 2]) -> Scalar {
+    scalar_from_i128::<Scalar>(lcr as i128) + two_pow_64() * scalar_from_i128::<Scalar>(entropy as i128)
+}
+
+fn two_pow_64() -> Scalar {
+    Scalar::from_raw([0, 1, 0, 0])
+}
+
+/// Encoded shocks of every path in `shock_set`
+fn path_shocks(shock_set: &ShockSet) -> Result<Vec<PathShock>, ProofError> {
+    let paths = shock_set.len();
+    if paths < 2 || !paths.is_power_of_two() {
+        return Err(ProofError::InvalidWitness {
+            reason: format!("{} paths is not a power of two of at least 2", paths),
+        });
+    }
+    shock_set.draws().chunks(MultiplicativeShock.dimension()).map(PathShock::new).collect()
+}
+
+/// Merkle root over the leaves, pairing neighbours with `mimc_compress_native`
+fn merkle_root(mut nodes: Vec<Scalar>) -> Scalar {
+    while nodes.len() > 1 {
+        nodes = nodes.chunks(2).map(|pair| mimc_compress_native(pair[0], pair[1])).collect();
+    }
+    nodes[0]
+}
+
+/// Root a VaR proof over `shock_set` is bound to
+///
+/// Fails if the set does not hold a power-of-two number of paths, or a draw
+/// cannot be encoded.
+pub fn shock_root(shock_set: &ShockSet) -> Result<Scalar, ProofError> {
+    Ok(merkle_root(path_shocks(shock_set)?.iter().map(PathShock::leaf).collect()))
+}
+
+/// Rank of the `quantile` order statistic among `paths` ascending scores
+///
+/// Matches the index `run_simulation` reads `var_95` and `var_99` from.
+pub fn var_rank(paths: usize, quantile: f64) -> Result<usize, ProofError> {
+    if !(0.0..=1.0).contains(&quantile) {
+        return Err(ProofError::PublicInputMismatch {
+            reason: format!("quantile {} outside [0, 1]", quantile),
+        });
+    }
+    Ok(((quantile * paths as f64) as usize).min(paths - 1))
+}
+
+/// Shock, shocked-state score, and division remainders of one path
+#[derive(Debug, Clone, PartialEq)]
+struct PathWitness {
+    shock: PathShock,
+    /// Remainders of flooring the shocked capital and assets
+    remainders: [u64; 2],
+    score: FragilityWitness,
+}
+
+/// Private witness for the VaR circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarWitness {
+    /// Encoded capital, assets, liquidity coverage, and entropy
+    base: [u64; 4],
+    paths: Vec<PathWitness>,
+    rank: usize,
+}
+
+impl VarWitness {
+    /// Apply every path of `shock_set` to `base_state` with the circuit's arithmetic
+    ///
+    /// Fails with `ProofError::InvalidWitness` if the path count is not a
+    /// power of two, or a path leaves the circuit's range: a shocked amount
+    /// overflowing 64 bits, or reaching the LCR floor or the entropy clamp.
+    pub fn new(
+        shock_set: &ShockSet,
+        base_state: &BankState,
+        quantile: f64,
+        config: &LagrangianConfig,
+    ) -> Result<Self, ProofError> {
+        validate_witness(base_state)?;
+        let base = [
+            FixedPoint::to_units(base_state.tier1_capital)?,
+            FixedPoint::to_units(base_state.total_assets)?,
+            FixedPoint::to_units(base_state.liquidity_coverage)?,
+            FixedPoint::to_units(base_state.entropy_index)?,
+        ];
+        let shocks = path_shocks(shock_set)?;
+        let rank = var_rank(shocks.len(), quantile)?;
+        let lcr_floor = FixedPoint::to_units(LCR_FLOOR)? as i128;
+        let entropy_max = FixedPoint::to_units(ENTROPY_INDEX_MAX)? as i128;
+        let scale = FixedPoint::SCALE as u128;
+
+        let mut paths = Vec::with_capacity(shocks.len());
+        for (i, shock) in shocks.into_iter().enumerate() {
+            let invalid = |what: &str| ProofError::InvalidWitness {
+                reason: format!("path {} {}", i, what),
+            };
+            let scaled = |amount: u64, factor: u64| {
+                let product = amount as u128 * factor as u128;
+                let floored = u64::try_from(product / scale).map_err(|_| invalid("overflows the encoding"))?;
+                Ok::<_, ProofError>((floored, (product % scale) as u64))
+            };
+            let (capital, capital_rem) = scaled(base[0], shock.factors[0])?;
+            let (assets, assets_rem) = scaled(base[1], shock.factors[1])?;
+            let lcr = base[2] as i128 + shock.offsets[0] as i128;
+            let entropy = base[3] as i128 + shock.offsets[1] as i128;
+            if lcr < lcr_floor {
+                return Err(invalid("reaches the LCR floor"));
+            }
+            if !(0..=entropy_max).contains(&entropy) {
+                return Err(invalid("reaches the entropy clamp"));
+            }
+
+            paths.push(PathWitness {
+                shock,
+                remainders: [capital_rem, assets_rem],
+                score: FragilityWitness::from_units([capital, assets, lcr as u64, entropy as u64], config),
+            });
+        }
+
+        Ok(Self { base, paths, rank })
+    }
+
+    /// Path scores in ascending order, in fixed-point units
+    fn sorted_scores(&self) -> Vec<u64> {
+        let mut scores: Vec<u64> = self.paths.iter().map(|p| p.score.fragility_units()).collect();
+        scores.sort_unstable();
+        scores
+    }
+
+    /// VaR the circuit computes, in fixed-point units
+    pub fn var_units(&self) -> u64 {
+        self.sorted_scores()[self.rank]
+    }
+
+    /// VaR the circuit computes
+    pub fn var(&self) -> f64 {
+        self.var_units() as f64 / FixedPoint::SCALE as f64
+    }
+
+    /// `shock_root` of the paths
+    pub fn shock_root(&self) -> Scalar {
+        merkle_root(self.paths.iter().map(|p| p.shock.leaf()).collect())
+    }
+}
+
+/// VaR of `base_state` over `shock_set` under the circuit's arithmetic
+///
+/// This is the value a proof must be verified against.
+pub fn reference_var(shock_set: &ShockSet, base_state: &BankState, quantile: f64) -> Result<f64, ProofError> {
+    VarWitness::new(shock_set, base_state, quantile, &LagrangianConfig::default()).map(|w| w.var())
+}
+
+/// Comparators of Batcher's odd-even merge sort for `n` (a power of two) inputs
+///
+/// Each `(i, j)` with `i < j` leaves the smaller value at `i`.
+fn sorting_network(n: usize) -> Vec<(usize, usize)> {
+    let mut comparators = Vec::new();
+    let mut p = 1;
+    while p < n {
+        let mut k = p;
+        while k >= 1 {
+            let mut j = k % p;
+            while j + k < n {
+                for i in 0..k.min(n - j - k) {
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        comparators.push((i + j, i + j + k));
+                    }
+                }
+                j += 2 * k;
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+    comparators
+}
+
+/// Circuit proving a VaR is the order statistic of committed shock paths
+///
+/// The shocks and base state are private; the public inputs are the VaR, its
+/// rank, the shock root, the state commitment, and the reporting period.
+#[derive(Clone)]
+pub struct VarCircuit {
+    /// Barrier and capital constants baked into the constraints
+    pub config: LagrangianConfig,
+    /// Paths per proof, a power of two
+    pub paths: usize,
+    /// Private: Shocks and base state (`None` during setup)
+    pub witness: Option<VarWitness>,
+    /// Public: VaR output
+    pub var: Option<Scalar>,
+    /// Public: Rank of the VaR among the ascending scores
+    pub rank: Option<Scalar>,
+    /// Public: `shock_root` of the paths
+    pub shock_root: Option<Scalar>,
+    /// Public: `state_commitment` of the base state
+    pub commitment: Option<Scalar>,
+    /// Public: Reporting period the proof is bound to, 0 for none
+    pub period: Option<Scalar>,
+}
+
+impl VarCircuit {
+    /// Circuit shape without assignments, for parameter generation
+    pub fn blank(config: LagrangianConfig, paths: usize) -> Self {
+        Self {
+            config,
+            paths,
+            witness: None,
+            var: None,
+            rank: None,
+            shock_root: None,
+            commitment: None,
+            period: None,
+        }
+    }
+
+    /// Fully assigned circuit proving the `quantile` VaR of `base_state` over `shock_set`
+    pub fn generate_witness(
+        shock_set: &ShockSet,
+        base_state: &BankState,
+        quantile: f64,
+        config: LagrangianConfig,
+    ) -> Result<Self, ProofError> {
+        let witness = VarWitness::new(shock_set, base_state, quantile, &config)?;
+        Ok(Self {
+            paths: witness.paths.len(),
+            var: Some(Scalar::from(witness.var_units())),
+            rank: Some(Scalar::from(witness.rank as u64)),
+            shock_root: Some(witness.shock_root()),
+            commitment: Some(state_commitment(base_state)?),
+            period: Some(Scalar::zero()),
+            witness: Some(witness),
+            config,
+        })
+    }
+}
+
+/// A score combination and its value in fixed-point units
+type Score = (LinearCombination<Scalar>, Option<u128>);
+
+/// A Merkle node combination and its value
+type Node = (LinearCombination<Scalar>, Option<Scalar>);
+
+impl Circuit<Scalar> for VarCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        let w = self.witness.as_ref();
+        if w.is_some_and(|w| w.paths.len() != self.paths) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // Allocate public inputs
+        let var = cs.alloc_input(|| "var", || self.var.ok_or(SynthesisError::AssignmentMissing))?;
+        let rank = cs.alloc_input(|| "rank", || self.rank.ok_or(SynthesisError::AssignmentMissing))?;
+        let root = cs.alloc_input(
+            || "shock root",
+            || self.shock_root.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        let commitment = cs.alloc_input(
+            || "commitment",
+            || self.commitment.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        // Reporting period (0 when unbound); bellman's input constraints
+        // bind it to the proof without any of our own
+        cs.alloc_input(|| "period", || self.period.ok_or(SynthesisError::AssignmentMissing))?;
+
+        // Base state, range-checked and hashed into the commitment
+        let mut base = Vec::with_capacity(4);
+        let mut digest = LinearCombination::zero();
+        let mut digest_value = w.map(|_| Scalar::zero());
+        for (i, name) in ["tier1_capital", "total_assets", "liquidity_coverage", "entropy_index"].into_iter().enumerate() {
+            let units = w.map(|w| Scalar::from(w.base[i]));
+            let v = cs.alloc(|| format!("base {}", name), || units.ok_or(SynthesisError::AssignmentMissing))?;
+            range_check(cs.namespace(|| format!("base {} range", name)), LinearCombination::zero() + v, units, AMOUNT_BITS)?;
+            (digest, digest_value) = mimc_compress(
+                cs.namespace(|| format!("commitment {}", i)),
+                &digest,
+                digest_value,
+                &(LinearCombination::zero() + v),
+                units,
+            )?;
+            base.push(v);
+        }
+        cs.enforce(|| "commitment matches", |_| digest, |lc| lc + CS::one(), |lc| lc + commitment);
+
+        // Shock each path and score it
+        let mut leaves = Vec::with_capacity(self.paths);
+        let mut scores: Vec<Score> = Vec::with_capacity(self.paths);
+        for i in 0..self.paths {
+            let mut cs = cs.namespace(|| format!("path {}", i));
+            let p = w.map(|w| &w.paths[i]);
+            let (leaf, score) = enforce_path(&mut cs, &self.config, &base, p)?;
+            leaves.push(leaf);
+            scores.push(score);
+        }
+
+        // Merkle root over the leaves
+        let mut level = 0;
+        while leaves.len() > 1 {
+            let mut parents = Vec::with_capacity(leaves.len() / 2);
+            for (k, pair) in leaves.chunks(2).enumerate() {
+                let (left, right) = (&pair[0], &pair[1]);
+                parents.push(mimc_compress(
+                    cs.namespace(|| format!("node {} {}", level, k)),
+                    &left.0,
+                    left.1,
+                    &right.0,
+                    right.1,
+                )?);
+            }
+            leaves = parents;
+            level += 1;
+        }
+        let (digest, _) = leaves.pop().expect("at least one path");
+        cs.enforce(|| "shock root matches", |_| digest, |lc| lc + CS::one(), |lc| lc + root);
+
+        // Sort the scores
+        for (k, (i, j)) in sorting_network(self.paths).into_iter().enumerate() {
+            let (lo, hi) = compare_swap(cs.namespace(|| format!("comparator {}", k)), &scores[i], &scores[j])?;
+            scores[i] = lo;
+            scores[j] = hi;
+        }
+
+        // Select the score at the public rank with a one-hot selector
+        let mut selected = LinearCombination::zero();
+        let mut position = LinearCombination::zero();
+        for (k, (score, _)) in scores.iter().enumerate() {
+            let bit = cs.alloc(
+                || format!("rank {}", k),
+                || {
+                    w.map(|w| if w.rank == k { Scalar::one() } else { Scalar::zero() })
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?;
+            cs.enforce(
+                || format!("rank {} boolean", k),
+                |lc| lc + bit,
+                |lc| lc + CS::one() - bit,
+                |lc| lc,
+            );
+            cs.enforce(
+                || format!("rank {} selects var", k),
+                |lc| lc + bit,
+                |lc| lc + score - var,
+                |lc| lc,
+            );
+            selected = selected + bit;
+            position = position + (Scalar::from(k as u64), bit);
+        }
+        cs.enforce(|| "one rank", |_| selected, |lc| lc + CS::one(), |lc| lc + CS::one());
+        cs.enforce(|| "rank matches", |_| position, |lc| lc + CS::one(), |lc| lc + rank);
+        Ok(())
+    }
+}
+
+/// Constrain one path: its shock, shocked state, score, and leaf
+///
+/// Returns the leaf and the score.
+fn enforce_path<CS: ConstraintSystem<Scalar>>(
+    cs: &mut CS,
+    config: &LagrangianConfig,
+    base: &[Variable],
+    p: Option<&PathWitness>,
+) -> Result<(Node, Score), SynthesisError> {
+    // Helper: allocate a private value derived from the path witness
+    macro_rules! witness {
+        ($name:expr, $value:expr) => {
+            cs.alloc(|| $name, || p.map($value).ok_or(SynthesisError::AssignmentMissing))?
+        };
+    }
+
+    let score_value = p.map(|p| p.score.fragility);
+    let score = witness!("fragility", |p| scalar_from_u128(p.score.fragility));
+    let [capital, assets, lcr, entropy] = enforce_fragility(&mut cs.namespace(|| "score"), config, p.map(|p| &p.score), score, AMOUNT_BITS)?;
+
+    // Shocked amount·S + r = base·factor, 0 <= r < S
+    let scale = Scalar::from(FixedPoint::SCALE);
+    let mut factors = Vec::with_capacity(2);
+    for (k, (name, shocked)) in [("capital", capital), ("assets", assets)].into_iter().enumerate() {
+        let factor_units = p.map(|p| Scalar::from(p.shock.factors[k]));
+        let factor = witness!(format!("{} factor", name), |p| Scalar::from(p.shock.factors[k]));
+        range_check(
+            cs.namespace(|| format!("{} factor range", name)),
+            LinearCombination::zero() + factor,
+            factor_units,
+            AMOUNT_BITS,
+        )?;
+        let rem = witness!(format!("{} remainder", name), |p| Scalar::from(p.remainders[k]));
+        cs.enforce(
+            || format!("{} shock", name),
+            |lc| lc + base[k],
+            |lc| lc + factor,
+            |lc| lc + (scale, shocked) + rem,
+        );
+        range_check(
+            cs.namespace(|| format!("{} remainder lower", name)),
+            LinearCombination::zero() + rem,
+            p.map(|p| Scalar::from(p.remainders[k])),
+            SCALE_BITS,
+        )?;
+        range_check(
+            cs.namespace(|| format!("{} remainder upper", name)),
+            LinearCombination::zero() + (scale, CS::one()) - CS::one() - rem,
+            p.map(|p| Scalar::from(FixedPoint::SCALE - 1 - p.remainders[k])),
+            SCALE_BITS,
+        )?;
+        factors.push(factor);
+    }
+
+    // Shocked LCR and entropy = base + offset; their range checks in
+    // `enforce_fragility` keep them non-negative
+    let mut offsets = Vec::with_capacity(2);
+    for (k, (name, shocked)) in [("lcr", lcr), ("entropy", entropy)].into_iter().enumerate() {
+        let offset = witness!(format!("{} offset", name), |p| scalar_from_i128(p.shock.offsets[k] as i128));
+        cs.enforce(
+            || format!("{} shock", name),
+            |lc| lc + base[k + 2] + offset,
+            |lc| lc + CS::one(),
+            |lc| lc + shocked,
+        );
+        offsets.push(offset);
+    }
+
+    // Leaf: factors, then offsets, each packed into one element
+    let two_64 = two_pow_64();
+    let (leaf, leaf_value) = mimc_compress(
+        cs.namespace(|| "leaf factors"),
+        &LinearCombination::zero(),
+        p.map(|_| Scalar::zero()),
+        &(LinearCombination::zero() + factors[0] + (two_64, factors[1])),
+        p.map(|p| pack_factors(p.shock.factors)),
+    )?;
+    let leaf = mimc_compress(
+        cs.namespace(|| "leaf offsets"),
+        &leaf,
+        leaf_value,
+        &(LinearCombination::zero() + offsets[0] + (two_64, offsets[1])),
+        p.map(|p| pack_offsets(p.shock.offsets)),
+    )?;
+
+    Ok((leaf, (LinearCombination::zero() + score, score_value)))
+}
+
+/// Constrain `(min, max)` of two scores
+///
+/// A boolean `swap` is set when `a > b`; `t = swap·(a - b)` moves the gap
+/// across. The range check on `b - a + 2t - swap` reads `b - a >= 0` without
+/// a swap and `a - b - 1 >= 0` with one, so the order is enforced.
+fn compare_swap<CS: ConstraintSystem<Scalar>>(
+    mut cs: CS,
+    a: &Score,
+    b: &Score,
+) -> Result<(Score, Score), SynthesisError> {
+    let values = a.1.zip(b.1);
+    let swap_value = values.map(|(a, b)| a > b);
+    let swap = cs.alloc(
+        || "swap",
+        || {
+            swap_value
+                .map(|s| if s { Scalar::one() } else { Scalar::zero() })
+                .ok_or(SynthesisError::AssignmentMissing)
+        },
+    )?;
+    cs.enforce(|| "swap boolean", |lc| lc + swap, |lc| lc + CS::one() - swap, |lc| lc);
+
+    let gap = values.map(|(a, b)| a.saturating_sub(b));
+    let t = cs.alloc(
+        || "moved gap",
+        || gap.map(scalar_from_u128).ok_or(SynthesisError::AssignmentMissing),
+    )?;
+    cs.enforce(|| "gap", |lc| lc + swap, |lc| lc + &a.0 - &b.0, |lc| lc + t);
+
+    let two = Scalar::from(2u64);
+    range_check(
+        cs.namespace(|| "order"),
+        b.0.clone() - &a.0 + (two, t) - swap,
+        values.map(|(a, b)| if a > b { scalar_from_u128(a - b - 1) } else { scalar_from_u128(b - a) }),
+        FRAGILITY_BITS,
+    )?;
+
+    let lo = (a.0.clone() - t, values.map(|(a, b)| a.min(b)));
+    let hi = (b.0.clone() + t, values.map(|(a, b)| a.max(b)));
+    Ok((lo, hi))
+}
+
+/// Prover and verifier for `VarCircuit` at a fixed path count
+///
+/// Kept apart from `FragilityProver`: parameters for `VAR_PATHS` paths take
+/// far longer to generate than the other circuits together, so only nodes
+/// publishing a VaR pay for them.
+pub struct VarProver {
+    paths: usize,
+    params: Parameters<Bls12>,
+    pvk: PreparedVerifyingKey<Bls12>,
+}
+
+impl VarProver {
+    /// Generate parameters for `VAR_PATHS` paths
+    pub fn setup() -> Result<Self, ProofError> {
+        Self::setup_for(VAR_PATHS)
+    }
+
+    /// Generate parameters for `paths` paths, a power of two of at least 2
+    pub fn setup_for(paths: usize) -> Result<Self, ProofError> {
+        if paths < 2 || !paths.is_power_of_two() {
+            return Err(ProofError::InvalidParameters {
+                reason: format!("{} paths is not a power of two of at least 2", paths),
+            });
+        }
+        let circuit = VarCircuit::blank(LagrangianConfig::default(), paths);
+        let params = generate_random_parameters::<Bls12, _, _>(circuit, &mut OsRng)?;
+        let pvk = prepare_verifying_key(&params.vk);
+        Ok(Self { paths, params, pvk })
+    }
+
+    /// Paths per proof
+    pub fn paths(&self) -> usize {
+        self.paths
+    }
+
+    /// Prove the `quantile` VaR of `base_state` over `shock_set`
+    ///
+    /// The set must hold exactly `paths()` paths. The proof reveals
+    /// `reference_var`, `shock_root(shock_set)`, and
+    /// `state_commitment(base_state)`, which the prover publishes alongside
+    /// it.
+    pub fn prove_var(&self, shock_set: &ShockSet, base_state: &BankState, quantile: f64) -> Result<Proof<Bls12>, ProofError> {
+        if shock_set.len() != self.paths {
+            return Err(ProofError::InvalidWitness {
+                reason: format!("{} paths, parameters are for {}", shock_set.len(), self.paths),
+            });
+        }
+        let circuit = VarCircuit::generate_witness(shock_set, base_state, quantile, LagrangianConfig::default())?;
+        Ok(create_random_proof(circuit, &self.params, &mut OsRng)?)
+    }
+
+    /// Verify a proof from `prove_var`
+    pub fn verify_var(
+        &self,
+        proof: &Proof<Bls12>,
+        var: f64,
+        quantile: f64,
+        shock_root: Scalar,
+        commitment: Scalar,
+    ) -> Result<bool, ProofError> {
+        let inputs = [
+            FixedPoint::encode(var)?,
+            Scalar::from(var_rank(self.paths, quantile)? as u64),
+            shock_root,
+            commitment,
+            Scalar::zero(),
+        ];
+        check_proof(&self.pvk, proof, &inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::MonteCarloConfig;
+    use crate::simulation::shock_set::generate_shocks;
+    use bellman::gadgets::test::TestConstraintSystem;
+
+    fn base_state() -> BankState {
+        BankState {
+            tier1_capital: 8_400.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    fn shocks(paths: usize) -> ShockSet {
+        generate_shocks(&MonteCarloConfig {
+            num_simulations: paths,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_sorting_network_sorts() {
+        assert_eq!(sorting_network(VAR_PATHS).len(), 543);
+        // 0-1 principle: sorting every 0/1 input proves the network
+        let network = sorting_network(8);
+        for mask in 0u32..256 {
+            let mut values: Vec<u32> = (0..8).map(|i| (mask >> i) & 1).collect();
+            for &(i, j) in &network {
+                if values[i] > values[j] {
+                    values.swap(i, j);
+                }
+            }
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "mask {:08b}", mask);
+        }
+    }
+
+    #[test]
+    fn test_circuit_matches_reference_and_rejects_inflated_score() {
+        let (set, state) = (shocks(8), base_state());
+        let circuit = VarCircuit::generate_witness(&set, &state, 0.99, LagrangianConfig::default()).unwrap();
+        let witness = circuit.witness.clone().unwrap();
+        assert_eq!(witness.var(), reference_var(&set, &state, 0.99).unwrap());
+        assert_eq!(witness.shock_root(), shock_root(&set).unwrap());
+
+        let mut cs = TestConstraintSystem::new();
+        circuit.clone().synthesize(&mut cs).unwrap();
+        assert!(cs.is_satisfied(), "{:?}", cs.which_is_unsatisfied());
+
+        // Inflate the worst path's score and claim the inflated VaR
+        let mut tampered = circuit;
+        let w = tampered.witness.as_mut().unwrap();
+        let worst = (0..8).max_by_key(|&i| w.paths[i].score.fragility).unwrap();
+        w.paths[worst].score.fragility += FixedPoint::SCALE as u128;
+        tampered.var = Some(Scalar::from(w.var_units()));
+        let mut cs = TestConstraintSystem::new();
+        tampered.synthesize(&mut cs).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_var_proof_verifies_and_inflated_proof_rejected() {
+        let prover = VarProver::setup_for(4).unwrap();
+        let (set, state) = (shocks(4), base_state());
+        let var = reference_var(&set, &state, 0.99).unwrap();
+        let (root, commitment) = (shock_root(&set).unwrap(), state_commitment(&state).unwrap());
+
+        let proof = prover.prove_var(&set, &state, 0.99).unwrap();
+        assert!(prover.verify_var(&proof, var, 0.99, root, commitment).unwrap());
+        assert!(!prover.verify_var(&proof, var + 1.0, 0.99, root, commitment).unwrap());
+        assert!(!prover.verify_var(&proof, var, 0.25, root, commitment).unwrap());
+
+        // A proof over an inflated score does not verify against its claim
+        let mut circuit = VarCircuit::generate_witness(&set, &state, 0.99, LagrangianConfig::default()).unwrap();
+        let w = circuit.witness.as_mut().unwrap();
+        let worst = (0..4).max_by_key(|&i| w.paths[i].score.fragility).unwrap();
+        w.paths[worst].score.fragility += FixedPoint::SCALE as u128;
+        circuit.var = Some(Scalar::from(w.var_units()));
+        let forged = create_random_proof(circuit, &prover.params, &mut OsRng).unwrap();
+        assert!(!prover.verify_var(&forged, var + 1.0, 0.99, root, commitment).unwrap());
+
+        assert!(matches!(prover.prove_var(&shocks(8), &state, 0.99), Err(ProofError::InvalidWitness { .. })));
+    }
+}
@@ -0,0 +1,414 @@
+//! Proof Verification
+//!
+//! Verifier-only counterpart to `FragilityProver`. Holds just the verifying
+//! keys for the fragility, threshold, entropy, group solvency, and LCR circuits, which are small and public, so nodes that check gossiped proofs
+//! never need the proving parameters.
+//!
+//! Exported keys start with a magic tag and `VERIFYING_KEY_VERSION`. The
+//! version changes whenever the circuits change, since keys from another
+//! circuit layout would reject every proof. The fingerprint of the proving
+//! parameters the keys came from follows, so envelopes made with other
+//! parameters are rejected by name rather than by a failed pairing.
+
+use bellman::{
+    groth16::{batch, prepare_verifying_key, verify_proof, PreparedVerifyingKey, Proof, VerifyingKey},
+    VerificationError,
+};
+use bls12_381::{Bls12, Scalar};
+use rand::rngs::OsRng;
+use rayon::prelude::*;
+use std::io::{Read, Write};
+
+use crate::core::lagrangian::BankState;
+use crate::proofs::circuit::state_commitment;
+use crate::proofs::envelope::{BackendId, CircuitId, ProofEnvelope};
+use crate::proofs::error::ProofError;
+use crate::proofs::prover::deserialize_proof;
+use crate::proofs::public_inputs::PublicInputs;
+use crate::proofs::registry::{CircuitRegistry, PERIOD_VERSION};
+
+/// Leading bytes of an exported verifying key
+const KEY_MAGIC: &[u8; 4] = b"OLVK";
+
+/// Layout version of exported verifying keys and the circuits behind them
+pub const VERIFYING_KEY_VERSION: u32 = 8;
+
+/// Result of verifying many proofs together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOutcome {
+    /// Every proof verified
+    AllValid,
+    /// Indices of the proofs that failed, in ascending order
+    Failed(Vec<usize>),
+}
+
+impl BatchOutcome {
+    /// Whether every proof verified
+    pub fn is_valid(&self) -> bool {
+        matches!(self, BatchOutcome::AllValid)
+    }
+}
+
+/// Verifier for fragility, threshold, entropy, group solvency, and LCR proofs
+pub struct FragilityVerifier {
+    vk: VerifyingKey<Bls12>,
+    threshold_vk: VerifyingKey<Bls12>,
+    entropy_vk: VerifyingKey<Bls12>,
+    group_vk: VerifyingKey<Bls12>,
+    lcr_vk: VerifyingKey<Bls12>,
+    pvk: PreparedVerifyingKey<Bls12>,
+    threshold_pvk: PreparedVerifyingKey<Bls12>,
+    entropy_pvk: PreparedVerifyingKey<Bls12>,
+    group_pvk: PreparedVerifyingKey<Bls12>,
+    lcr_pvk: PreparedVerifyingKey<Bls12>,
+    /// `FragilityProver::params_fingerprint` of the source parameters
+    fingerprint: [u8; 32],
+    /// Keys for other circuit versions
+    registry: Option<CircuitRegistry>,
+}
+
+impl FragilityVerifier {
+    /// Build from the score, threshold, entropy, group, and LCR circuit verifying
+    /// keys and the fingerprint of the parameters they came from
+    pub(crate) fn new(
+        vk: VerifyingKey<Bls12>,
+        threshold_vk: VerifyingKey<Bls12>,
+        entropy_vk: VerifyingKey<Bls12>,
+        group_vk: VerifyingKey<Bls12>,
+        lcr_vk: VerifyingKey<Bls12>,
+        fingerprint: [u8; 32],
+    ) -> Self {
+        Self {
+            pvk: prepare_verifying_key(&vk),
+            threshold_pvk: prepare_verifying_key(&threshold_vk),
+            entropy_pvk: prepare_verifying_key(&entropy_vk),
+            group_pvk: prepare_verifying_key(&group_vk),
+            lcr_pvk: prepare_verifying_key(&lcr_vk),
+            vk,
+            threshold_vk,
+            entropy_vk,
+            group_vk,
+            lcr_vk,
+            fingerprint,
+            registry: None,
+        }
+    }
+
+    /// Verify envelopes from other circuit versions with keys from `registry`
+    ///
+    /// The registry's deprecation list applies to every envelope.
+    pub fn with_registry(mut self, registry: CircuitRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Fingerprint of the proving parameters these keys came from
+    pub fn params_fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    /// Serialize the verifying keys with the version header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(KEY_MAGIC);
+        bytes.extend_from_slice(&VERIFYING_KEY_VERSION.to_be_bytes());
+        bytes.extend_from_slice(&self.fingerprint);
+        self.vk.write(&mut bytes).expect("writing to a Vec cannot fail");
+        self.threshold_vk.write(&mut bytes).expect("writing to a Vec cannot fail");
+        self.entropy_vk.write(&mut bytes).expect("writing to a Vec cannot fail");
+        self.group_vk.write(&mut bytes).expect("writing to a Vec cannot fail");
+        self.lcr_vk.write(&mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Read keys written by `to_bytes` or `FragilityProver::export_verifying_key`
+    ///
+    /// Keys exported for another circuit version are rejected with
+    /// `ProofError::KeyVersionMismatch`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        let invalid = |reason: String| ProofError::InvalidParameters { reason };
+        if bytes.len() < 8 || &bytes[..4] != KEY_MAGIC {
+            return Err(invalid("not an exported verifying key".to_string()));
+        }
+        let found = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if found != VERIFYING_KEY_VERSION {
+            return Err(ProofError::KeyVersionMismatch {
+                expected: VERIFYING_KEY_VERSION,
+                found,
+            });
+        }
+
+        let Some((fingerprint, mut rest)) = bytes[8..].split_first_chunk::<32>() else {
+            return Err(invalid("truncated verifying key".to_string()));
+        };
+        let vk = VerifyingKey::read(&mut rest).map_err(|e| invalid(e.to_string()))?;
+        let threshold_vk = VerifyingKey::read(&mut rest).map_err(|e| invalid(e.to_string()))?;
+        let entropy_vk = VerifyingKey::read(&mut rest).map_err(|e| invalid(e.to_string()))?;
+        let group_vk = VerifyingKey::read(&mut rest).map_err(|e| invalid(e.to_string()))?;
+        let lcr_vk = VerifyingKey::read(&mut rest).map_err(|e| invalid(e.to_string()))?;
+        if !rest.is_empty() {
+            return Err(invalid(format!("{} trailing bytes", rest.len())));
+        }
+        Ok(Self::new(vk, threshold_vk, entropy_vk, group_vk, lcr_vk, *fingerprint))
+    }
+
+    /// Write the exported key to `w`
+    pub fn write<W: Write>(&self, mut w: W) -> Result<(), ProofError> {
+        w.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Read an exported key from `r`
+    pub fn read<R: Read>(mut r: R) -> Result<Self, ProofError> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Verify a proof against typed public inputs
+    ///
+    /// The verifying key is chosen by `inputs.circuit()`.
+    pub fn verify_inputs(&self, proof: &Proof<Bls12>, inputs: &PublicInputs) -> Result<bool, ProofError> {
+        check_proof(self.pvk_for(inputs.circuit()), proof, &inputs.to_scalars()?)
+    }
+
+    /// Verify a fragility proof for the published `state`
+    ///
+    /// The proof only verifies if it was made from exactly this state, since
+    /// its commitment is recomputed here with `state_commitment`.
+    pub fn verify(&self, proof: &Proof<Bls12>, state: &BankState, fragility_score: f64) -> Result<bool, ProofError> {
+        let inputs = PublicInputs::new(CircuitId::Fragility)
+            .fragility(fragility_score)
+            .commitment(state_commitment(state)?);
+        self.verify_inputs(proof, &inputs)
+    }
+
+    /// Verify a proof from `FragilityProver::prove_below` against `threshold`
+    pub fn verify_below(&self, proof: &Proof<Bls12>, threshold: f64) -> Result<bool, ProofError> {
+        self.verify_inputs(proof, &PublicInputs::new(CircuitId::Threshold).threshold(threshold))
+    }
+
+    /// Verify a proof from `FragilityProver::prove_entropy_at_least` against `bound`
+    pub fn verify_entropy_at_least(&self, proof: &Proof<Bls12>, bound: f64) -> Result<bool, ProofError> {
+        self.verify_inputs(proof, &PublicInputs::new(CircuitId::Entropy).bound(bound))
+    }
+
+    /// Verify a proof from `FragilityProver::prove_lcr_at_least` against `k`
+    pub fn verify_lcr_at_least(&self, proof: &Proof<Bls12>, k: f64) -> Result<bool, ProofError> {
+        self.verify_inputs(proof, &PublicInputs::new(CircuitId::Lcr).multiplier(k))
+    }
+
+    /// Verify a proof from `FragilityProver::prove_group_solvency`
+    ///
+    /// `commitment` is the `group_commitment` the prover published.
+    pub fn verify_group_solvency(
+        &self,
+        proof: &Proof<Bls12>,
+        commitment: Scalar,
+        min_ratio: f64,
+    ) -> Result<bool, ProofError> {
+        let inputs = PublicInputs::new(CircuitId::GroupSolvency)
+            .min_ratio(min_ratio)
+            .commitment(commitment);
+        self.verify_inputs(proof, &inputs)
+    }
+
+    /// Verify the proof in `envelope` against its own public inputs
+    ///
+    /// Envelopes from another proving backend fail with
+    /// `ProofError::BackendMismatch`. The circuit version is checked next, so
+    /// envelopes from another version fail with `ProofError::KeyVersionMismatch` before any decoding
+    /// or pairing, unless a registry from `with_registry` holds their key.
+    /// Current-version envelopes made with other parameters fail with
+    /// `ProofError::ParameterMismatch`. An envelope bound to another reporting period than
+    /// `expected_period` (`None` for unbound proofs) fails with
+    /// `ProofError::PeriodMismatch`, so a proof cannot be replayed into a
+    /// later period. An envelope bound to a `prover_key` it is not signed by
+    /// fails with `ProofError::IdentityMismatch`; unbound envelopes are not
+    /// signature-checked here (see `ProofEnvelope::verify_signature`).
+    pub fn verify_envelope(&self, envelope: &ProofEnvelope, expected_period: Option<u64>) -> Result<bool, ProofError> {
+        envelope.check_backend(BackendId::Bellman)?;
+        let (circuit, version) = (envelope.circuit, envelope.circuit_version);
+        let registered = match &self.registry {
+            Some(registry) if version != VERIFYING_KEY_VERSION => Some(registry.key(circuit, version)?),
+            Some(registry) => {
+                registry.check_deprecated(circuit, version)?;
+                None
+            }
+            None => {
+                envelope.check_version()?;
+                None
+            }
+        };
+        if let (None, Some(expected)) = (&registered, envelope.params_fingerprint) {
+            if expected != self.fingerprint {
+                return Err(ProofError::ParameterMismatch {
+                    expected,
+                    actual: self.fingerprint,
+                });
+            }
+        }
+        envelope.check_identity()?;
+        if envelope.period != expected_period {
+            return Err(ProofError::PeriodMismatch {
+                expected: expected_period,
+                found: envelope.period,
+            });
+        }
+        let mut public_inputs = envelope.public_scalars()?;
+        if version < PERIOD_VERSION {
+            // Older circuits take no period input, so their proofs are unbound
+            if envelope.period.is_some() {
+                return Err(ProofError::PublicInputMismatch {
+                    reason: format!("version {} proofs cannot be bound to a period", version),
+                });
+            }
+            public_inputs.pop();
+        }
+        let proof = deserialize_proof(&envelope.proof)?;
+        let pvk = registered.as_deref().unwrap_or_else(|| self.pvk_for(circuit));
+        check_proof(pvk, &proof, &public_inputs)
+    }
+
+    /// Verify many fragility proofs with their public inputs
+    ///
+    /// Every item must have `CircuitId::Fragility` inputs.
+    ///
+    /// All pairing checks are combined under random weights into a single
+    /// check, which costs about one multi-pairing plus a few multi-scalar
+    /// multiplications instead of one pairing product per proof. If the batch
+    /// fails, proofs are rechecked individually in parallel to report which
+    /// ones are invalid.
+    pub fn verify_batch(&self, items: &[(Proof<Bls12>, PublicInputs)]) -> Result<BatchOutcome, ProofError> {
+        let mut scalars = Vec::with_capacity(items.len());
+        for (index, (_, inputs)) in items.iter().enumerate() {
+            if inputs.circuit() != CircuitId::Fragility {
+                return Err(ProofError::PublicInputMismatch {
+                    reason: format!("item {} has {:?} inputs, expected Fragility", index, inputs.circuit()),
+                });
+            }
+            scalars.push(inputs.to_scalars()?);
+        }
+        if items.is_empty() {
+            return Ok(BatchOutcome::AllValid);
+        }
+
+        let mut verifier = batch::Verifier::new();
+        for ((proof, _), inputs) in items.iter().zip(&scalars) {
+            verifier.queue((proof, inputs.as_slice()));
+        }
+        if verifier.verify(OsRng, &self.vk).is_ok() {
+            return Ok(BatchOutcome::AllValid);
+        }
+
+        let failed = items
+            .par_iter()
+            .zip(&scalars)
+            .enumerate()
+            .filter(|(_, ((proof, _), inputs))| verify_proof(&self.pvk, proof, inputs).is_err())
+            .map(|(index, _)| index)
+            .collect();
+        Ok(BatchOutcome::Failed(failed))
+    }
+
+    /// Prepared key for `circuit`
+    fn pvk_for(&self, circuit: CircuitId) -> &PreparedVerifyingKey<Bls12> {
+        match circuit {
+            CircuitId::Fragility => &self.pvk,
+            CircuitId::Threshold => &self.threshold_pvk,
+            CircuitId::Entropy => &self.entropy_pvk,
+            CircuitId::GroupSolvency => &self.group_pvk,
+            CircuitId::Lcr => &self.lcr_pvk,
+        }
+    }
+}
+
+/// Check `proof` against its public inputs
+pub(crate) fn check_proof(
+    pvk: &PreparedVerifyingKey<Bls12>,
+    proof: &Proof<Bls12>,
+    public_inputs: &[Scalar],
+) -> Result<bool, ProofError> {
+    // bellman reports a proof that does not check out as an error
+    match verify_proof(pvk, proof, public_inputs) {
+        Ok(()) => Ok(true),
+        Err(VerificationError::InvalidProof) => Ok(false),
+        Err(e) => Err(ProofError::VerificationFailed(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::proofs::circuit::reference_fragility;
+    use crate::proofs::encoding::FixedPoint;
+    use crate::proofs::prover::test_prover;
+
+    fn state() -> BankState {
+        BankState {
+            tier1_capital: 8_002.5,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_exported_key_verifies_without_params() {
+        let prover = test_prover();
+        let fragility = reference_fragility(&state(), &LagrangianConfig::default()).unwrap();
+        let proof = prover.prove(&state(), fragility).unwrap();
+        let below = prover.prove_below(&state(), 50.0).unwrap();
+        let exported = prover.export_verifying_key();
+
+        let verifier = FragilityVerifier::from_bytes(&exported).unwrap();
+        assert!(verifier.verify(&proof, &state(), fragility).unwrap());
+        assert!(!verifier.verify(&proof, &state(), fragility + 1.0).unwrap());
+        assert!(verifier.verify_below(&below, 50.0).unwrap());
+        assert_eq!(verifier.to_bytes(), exported);
+        assert_eq!(verifier.params_fingerprint(), prover.params_fingerprint());
+    }
+
+    #[test]
+    fn test_key_version_checked() {
+        let exported = test_prover().export_verifying_key();
+
+        let mut future = exported.clone();
+        future[4..8].copy_from_slice(&(VERIFYING_KEY_VERSION + 1).to_be_bytes());
+        assert!(matches!(
+            FragilityVerifier::from_bytes(&future),
+            Err(ProofError::KeyVersionMismatch { found, .. }) if found == VERIFYING_KEY_VERSION + 1
+        ));
+
+        assert!(matches!(
+            FragilityVerifier::from_bytes(&exported[..exported.len() - 1]),
+            Err(ProofError::InvalidParameters { .. })
+        ));
+        assert!(matches!(
+            FragilityVerifier::from_bytes(b"not a key"),
+            Err(ProofError::InvalidParameters { .. })
+        ));
+    }
+
+    #[test]
+    fn test_typed_inputs_catch_mis_scaling() {
+        let verifier = test_prover().verifier();
+        let fragility = reference_fragility(&state(), &LagrangianConfig::default()).unwrap();
+        let proof = test_prover().prove(&state(), fragility).unwrap();
+        let commitment = state_commitment(&state()).unwrap();
+
+        let inputs = PublicInputs::new(CircuitId::Fragility).fragility(fragility).commitment(commitment);
+        assert!(verifier.verify_inputs(&proof, &inputs).unwrap());
+
+        // Scaled by 10^9 instead of 10^6: caught before any pairing
+        let mis_scaled = [Scalar::from((fragility * 1e9).round() as u64), commitment, Scalar::zero()];
+        assert!(matches!(
+            PublicInputs::from_scalars(CircuitId::Fragility, &mis_scaled),
+            Err(ProofError::PublicInputMismatch { .. })
+        ));
+        let correct = [Scalar::from(FixedPoint::to_units(fragility).unwrap()), commitment, Scalar::zero()];
+        let decoded = PublicInputs::from_scalars(CircuitId::Fragility, &correct).unwrap();
+        assert!(verifier.verify_inputs(&proof, &decoded).unwrap());
+    }
+}
@@ -0,0 +1,484 @@
+//! Checkpointed Monte Carlo Runs
+//!
+//! Long simulations periodically persist their progress to disk so that a run
+//! interrupted mid-way (e.g. a reclaimed spot instance) can resume and produce
+//! exactly the same result as an uninterrupted run.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::simulation::error::SimulationError;
+use crate::simulation::meta::{sha256_hex, SimulationMeta};
+use crate::simulation::monte_carlo::{
+    check_memory_budget, finalize, install_pool, simulate_paths, MonteCarloConfig, SimulationResult,
+};
+use crate::simulation::sampler::{validate_sampling, ShockStream};
+use crate::simulation::shock::{MultiplicativeShock, ShockModel};
+use crate::simulation::variance::VarianceReduction;
+
+/// Leading bytes identifying a checkpoint file
+const CHECKPOINT_MAGIC: &[u8; 8] = b"OLOCKPT\0";
+
+/// Checkpoint layout version
+const CHECKPOINT_VERSION: u32 = 2;
+
+/// Checkpoint configuration
+#[derive(Debug, Clone)]
+pub struct CheckpointConfig {
+    /// Checkpoint file location
+    pub path: PathBuf,
+    /// Paths simulated between checkpoint writes
+    pub interval: usize,
+    /// Suspend once this many paths have completed (None = run to completion)
+    pub halt_after: Option<usize>,
+}
+
+impl CheckpointConfig {
+    /// Checkpoint to `path` every 10,000 paths, running to completion
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: 10_000,
+            halt_after: None,
+        }
+    }
+}
+
+/// Outcome of a checkpointed run
+#[derive(Debug, Clone)]
+pub enum CheckpointOutcome {
+    /// All paths completed
+    Completed(Box<SimulationResult>),
+    /// Run stopped at `halt_after`; resume with `resume_from_checkpoint`
+    Suspended { completed: usize, total: usize },
+}
+
+/// On-disk checkpoint contents
+#[derive(Debug, Serialize, Deserialize)]
+struct Checkpoint {
+    version: u32,
+    fingerprint: String,
+    seed: u64,
+    interval: usize,
+    mc_config: MonteCarloConfig,
+    completed: usize,
+    fragilities: Vec<f64>,
+}
+
+/// Run Monte Carlo simulation, writing a checkpoint every `interval` paths
+///
+/// Produces the same fragilities as `run_simulation` for the same inputs.
+pub fn run_simulation_checkpointed(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    checkpoint: CheckpointConfig,
+) -> Result<CheckpointOutcome, SimulationError> {
+    run_simulation_checkpointed_with_model(base_state, lag_config, mc_config, &MultiplicativeShock, checkpoint)
+}
+
+/// Checkpointed `run_simulation_with_model`
+///
+/// Runs on the same pool and sampler as `try_run_simulation_with_model`.
+/// Only stored-sample runs can be checkpointed: streaming mode (including an
+/// automatic degrade under `max_memory_bytes`) and export are rejected.
+pub fn run_simulation_checkpointed_with_model(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    model: &dyn ShockModel,
+    checkpoint: CheckpointConfig,
+) -> Result<CheckpointOutcome, SimulationError> {
+    let started = Instant::now();
+    check_supported(mc_config, model)?;
+
+    let mut state = Checkpoint {
+        version: CHECKPOINT_VERSION,
+        fingerprint: fingerprint(base_state, lag_config, mc_config, model),
+        seed: mc_config.seed,
+        interval: checkpoint.interval.max(1),
+        mc_config: mc_config.clone(),
+        completed: 0,
+        fragilities: Vec::with_capacity(mc_config.num_simulations),
+    };
+
+    let total = mc_config.num_simulations;
+    let stop_at = checkpoint.halt_after.map_or(total, |n| n.min(total));
+    install_pool(mc_config, || {
+        advance(&mut state, base_state, lag_config, model, &checkpoint.path, stop_at)?;
+
+        if state.completed < total {
+            return Ok(CheckpointOutcome::Suspended {
+                completed: state.completed,
+                total,
+            });
+        }
+
+        let result = finish(state, base_state, lag_config, model, started)?;
+        Ok(CheckpointOutcome::Completed(Box::new(result)))
+    })
+}
+
+/// Resume a checkpointed run and continue to completion
+///
+/// `base_state` and `lag_config` must be the ones the checkpoint was started
+/// with; the Monte Carlo configuration is restored from the file itself.
+pub fn resume_from_checkpoint(
+    path: impl AsRef<Path>,
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+) -> Result<SimulationResult, SimulationError> {
+    resume_from_checkpoint_with_model(path, base_state, lag_config, &MultiplicativeShock)
+}
+
+/// Resume a run started with `run_simulation_checkpointed_with_model`
+///
+/// `model` must be the shock model the checkpoint was started with.
+pub fn resume_from_checkpoint_with_model(
+    path: impl AsRef<Path>,
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    model: &dyn ShockModel,
+) -> Result<SimulationResult, SimulationError> {
+    let started = Instant::now();
+    let path = path.as_ref();
+    let mut state = read_checkpoint(path)?;
+
+    let expected = fingerprint(base_state, lag_config, &state.mc_config, model);
+    if expected != state.fingerprint {
+        return Err(SimulationError::CheckpointMismatch {
+            path: path.to_path_buf(),
+            expected,
+            found: state.fingerprint,
+        });
+    }
+    check_supported(&state.mc_config, model)?;
+
+    let mc_config = state.mc_config.clone();
+    install_pool(&mc_config, || {
+        advance(&mut state, base_state, lag_config, model, path, mc_config.num_simulations)?;
+        finish(state, base_state, lag_config, model, started)
+    })
+}
+
+/// Reject configurations a checkpointed run cannot reproduce
+///
+/// Checkpoints persist every path's fragility, so streaming mode and the
+/// memory-budget degrade to it have nothing to resume from, and an export
+/// file cannot be continued across processes.
+fn check_supported(mc_config: &MonteCarloConfig, model: &dyn ShockModel) -> Result<(), SimulationError> {
+    validate_sampling(mc_config, model.dimension())?;
+
+    let unsupported = |reason: &str| {
+        Err(SimulationError::InvalidConfig {
+            reason: format!("checkpointed runs {}", reason),
+        })
+    };
+    if !mc_config.store_samples {
+        return unsupported("require stored samples");
+    }
+    if mc_config.export.is_some() {
+        return unsupported("cannot export paths");
+    }
+    if check_memory_budget(mc_config, model.dimension())? {
+        return unsupported("cannot degrade to streaming under the memory budget");
+    }
+    Ok(())
+}
+
+/// Simulate paths until `stop_at` have completed, checkpointing each chunk
+fn advance(
+    state: &mut Checkpoint,
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    model: &dyn ShockModel,
+    path: &Path,
+    stop_at: usize,
+) -> Result<(), SimulationError> {
+    let mc_config = state.mc_config.clone();
+    let mut stream = ShockStream::new(&mc_config, model.dimension())?;
+
+    // Replay the draws of completed paths so the RNG stream lines up.
+    // Sampling is cheap relative to fragility evaluation.
+    let mut skipped = 0;
+    while skipped < state.completed {
+        let n = state.interval.min(state.completed - skipped);
+        stream.draw(n);
+        skipped += n;
+    }
+
+    while state.completed < stop_at {
+        let n = state.interval.min(stop_at - state.completed);
+        let shocks = stream.draw(n);
+        state.fragilities.extend(simulate_paths(base_state, lag_config, model, &shocks));
+        state.completed += n;
+        write_checkpoint(path, state)?;
+    }
+
+    Ok(())
+}
+
+/// Summarize a completed run exactly as `run_simulation` would
+///
+/// The recorded duration covers only this process's share of the run.
+fn finish(
+    state: Checkpoint,
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    model: &dyn ShockModel,
+    started: Instant,
+) -> Result<SimulationResult, SimulationError> {
+    // Shocks are not persisted; regenerate them only if the control variate needs them
+    let shocks = match state.mc_config.variance_reduction {
+        VarianceReduction::None => Vec::new(),
+        VarianceReduction::ControlVariate => ShockStream::new(&state.mc_config, model.dimension())?
+            .draw(state.mc_config.num_simulations),
+    };
+
+    let mut result = finalize(base_state, lag_config, &state.mc_config, model, &shocks, state.fragilities);
+    result.meta = Some(SimulationMeta::new(
+        base_state,
+        lag_config,
+        &state.mc_config,
+        model,
+        started.elapsed(),
+    ));
+    Ok(result)
+}
+
+/// SHA-256 over the canonical encoding of the run inputs, hex encoded
+///
+/// Covers the shock model's draw labels so resuming under another model fails.
+fn fingerprint(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    model: &dyn ShockModel,
+) -> String {
+    let bytes = bincode::serialize(&(base_state, lag_config, mc_config, model.draw_labels()))
+        .expect("run inputs are always serializable");
+    sha256_hex(&bytes)
+}
+
+/// Atomically replace the checkpoint file (write to a sibling, then rename)
+fn write_checkpoint(path: &Path, state: &Checkpoint) -> Result<(), SimulationError> {
+    let io_err = |source| SimulationError::CheckpointIo {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let mut bytes = CHECKPOINT_MAGIC.to_vec();
+    bytes.extend(bincode::serialize(state).expect("checkpoint is always serializable"));
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, &bytes).map_err(io_err)?;
+    fs::rename(&tmp, path).map_err(io_err)
+}
+
+/// Read and structurally validate a checkpoint file
+fn read_checkpoint(path: &Path) -> Result<Checkpoint, SimulationError> {
+    let corrupt = |reason: String| SimulationError::CheckpointCorrupt {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let bytes = fs::read(path).map_err(|source| SimulationError::CheckpointIo {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let body = bytes
+        .strip_prefix(CHECKPOINT_MAGIC.as_slice())
+        .ok_or_else(|| corrupt("missing checkpoint header".to_string()))?;
+
+    let state: Checkpoint = bincode::deserialize(body)
+        .map_err(|e| corrupt(format!("undecodable body: {}", e)))?;
+
+    if state.version != CHECKPOINT_VERSION {
+        return Err(corrupt(format!(
+            "unsupported version {} (expected {})",
+            state.version, CHECKPOINT_VERSION
+        )));
+    }
+    if state.fragilities.len() != state.completed
+        || state.completed > state.mc_config.num_simulations
+    {
+        return Err(corrupt(format!(
+            "{} completed paths but {} stored samples of {} total",
+            state.completed,
+            state.fragilities.len(),
+            state.mc_config.num_simulations
+        )));
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::export::{ExportConfig, ExportFormat};
+    use crate::simulation::monte_carlo::{run_simulation, try_run_simulation_with_model};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("olo-{}-{}.ckpt", name, std::process::id()))
+    }
+
+    fn base_state() -> BankState {
+        BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_resume_matches_uninterrupted_run() {
+        let path = temp_path("resume");
+        let base_state = base_state();
+        let lag_config = LagrangianConfig::default();
+        let mc_config = MonteCarloConfig {
+            num_simulations: 1000,
+            ..Default::default()
+        };
+
+        let checkpoint = CheckpointConfig {
+            interval: 128,
+            halt_after: Some(500),
+            ..CheckpointConfig::new(&path)
+        };
+        let outcome =
+            run_simulation_checkpointed(&base_state, &lag_config, &mc_config, checkpoint).unwrap();
+        assert!(matches!(
+            outcome,
+            CheckpointOutcome::Suspended { completed: 500, total: 1000 }
+        ));
+
+        let resumed = resume_from_checkpoint(&path, &base_state, &lag_config).unwrap();
+        let uninterrupted = run_simulation(&base_state, &lag_config, &mc_config);
+
+        assert_eq!(resumed.fragilities, uninterrupted.fragilities);
+        assert_eq!(resumed.mean, uninterrupted.mean);
+        assert_eq!(resumed.var_99, uninterrupted.var_99);
+
+        fs::remove_file(&path).ok();
+    }
+
+    /// Two-draw model so the stream dimension differs from the default
+    struct CapitalRun;
+
+    impl ShockModel for CapitalRun {
+        fn shock(&self, base: &BankState, draws: &[f64]) -> BankState {
+            BankState {
+                tier1_capital: base.tier1_capital * (1.0 - 0.01 * draws[0].abs()),
+                liquidity_coverage: (base.liquidity_coverage - 0.01 * draws[1]).max(0.01),
+                ..base.clone()
+            }
+        }
+
+        fn dimension(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_custom_model_on_dedicated_pool_matches_plain_run() {
+        let path = temp_path("model");
+        let base_state = base_state();
+        let lag_config = LagrangianConfig::default();
+        let mc_config = MonteCarloConfig {
+            num_simulations: 600,
+            num_threads: 2,
+            ..Default::default()
+        };
+
+        let checkpoint = CheckpointConfig {
+            interval: 100,
+            halt_after: Some(250),
+            ..CheckpointConfig::new(&path)
+        };
+        run_simulation_checkpointed_with_model(&base_state, &lag_config, &mc_config, &CapitalRun, checkpoint)
+            .unwrap();
+
+        // Resuming under a different model is caught by the fingerprint
+        let err = resume_from_checkpoint(&path, &base_state, &lag_config).unwrap_err();
+        assert!(matches!(err, SimulationError::CheckpointMismatch { .. }));
+
+        let resumed = resume_from_checkpoint_with_model(&path, &base_state, &lag_config, &CapitalRun).unwrap();
+        let plain = try_run_simulation_with_model(&base_state, &lag_config, &mc_config, &CapitalRun).unwrap();
+
+        assert_eq!(resumed.fragilities, plain.fragilities);
+        assert_eq!(resumed.mean, plain.mean);
+
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_unsupported_options_are_rejected() {
+        let path = temp_path("unsupported");
+        let lag_config = LagrangianConfig::default();
+        let rejected = [
+            MonteCarloConfig { store_samples: false, ..Default::default() },
+            MonteCarloConfig {
+                export: Some(ExportConfig::new(temp_path("export"), ExportFormat::Csv)),
+                ..Default::default()
+            },
+            MonteCarloConfig {
+                num_simulations: 100_000,
+                max_memory_bytes: Some(3_000_000),
+                auto_degrade: true,
+                ..Default::default()
+            },
+        ];
+
+        for mc_config in &rejected {
+            let checkpoint = CheckpointConfig::new(&path);
+            let err = run_simulation_checkpointed(&base_state(), &lag_config, mc_config, checkpoint).unwrap_err();
+            assert!(matches!(err, SimulationError::InvalidConfig { .. }), "{}", err);
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_mismatched_state_is_rejected() {
+        let path = temp_path("mismatch");
+        let lag_config = LagrangianConfig::default();
+        let mc_config = MonteCarloConfig {
+            num_simulations: 100,
+            ..Default::default()
+        };
+
+        let checkpoint = CheckpointConfig {
+            halt_after: Some(50),
+            ..CheckpointConfig::new(&path)
+        };
+        run_simulation_checkpointed(&base_state(), &lag_config, &mc_config, checkpoint).unwrap();
+
+        let other_state = BankState {
+            tier1_capital: 9_000.0,
+            ..base_state()
+        };
+        let err = resume_from_checkpoint(&path, &other_state, &lag_config).unwrap_err();
+        assert!(matches!(err, SimulationError::CheckpointMismatch { .. }));
+
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_corrupt_checkpoint_is_rejected() {
+        let path = temp_path("corrupt");
+        fs::write(&path, b"OLOCKPT\0garbage").unwrap();
+
+        let err = resume_from_checkpoint(&path, &base_state(), &LagrangianConfig::default())
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::CheckpointCorrupt { .. }));
+        assert!(err.to_string().contains("corrupt"));
+
+        fs::remove_file(&path).ok();
+    }
+}
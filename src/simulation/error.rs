@@ -0,0 +1,80 @@
+//! Simulation Error Types
+//!
+//! Failures surfaced by the fallible simulation entry points (`try_run_simulation`,
+//! path simulation, checkpointing, resumption) and by merging results.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors produced by the simulation engine
+#[derive(Debug)]
+pub enum SimulationError {
+    /// Configuration parameters are out of range
+    InvalidConfig { reason: String },
+    /// Reading or writing a checkpoint file failed
+    CheckpointIo { path: PathBuf, source: io::Error },
+    /// Checkpoint file could not be decoded
+    CheckpointCorrupt { path: PathBuf, reason: String },
+    /// Checkpoint was written for a different base state or configuration
+    CheckpointMismatch { path: PathBuf, expected: String, found: String },
+    /// Writing the per-path export failed; the run was aborted
+    Export { path: PathBuf, source: io::Error },
+    /// Results from different configurations cannot be combined
+    FingerprintMismatch { expected: String, found: String },
+    /// Both results contain paths drawn from the same seed
+    DuplicateSeed { seed: u64 },
+    /// Estimated memory footprint exceeds `max_memory_bytes`
+    MemoryBudgetExceeded { needed: usize, budget: usize },
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::InvalidConfig { reason } => {
+                write!(f, "invalid simulation config: {}", reason)
+            }
+            SimulationError::CheckpointIo { path, source } => {
+                write!(f, "checkpoint I/O failed for {}: {}", path.display(), source)
+            }
+            SimulationError::CheckpointCorrupt { path, reason } => {
+                write!(f, "checkpoint {} is corrupt: {}", path.display(), reason)
+            }
+            SimulationError::CheckpointMismatch { path, expected, found } => write!(
+                f,
+                "checkpoint {} does not match this run (expected fingerprint {}, found {})",
+                path.display(),
+                expected,
+                found
+            ),
+            SimulationError::Export { path, source } => {
+                write!(f, "export to {} failed: {}", path.display(), source)
+            }
+            SimulationError::FingerprintMismatch { expected, found } => write!(
+                f,
+                "results come from different configurations (fingerprint {} vs {})",
+                expected, found
+            ),
+            SimulationError::DuplicateSeed { seed } => write!(
+                f,
+                "both results include seed {}, so merging would count its paths twice",
+                seed
+            ),
+            SimulationError::MemoryBudgetExceeded { needed, budget } => write!(
+                f,
+                "run needs an estimated {} bytes, over the {} byte memory budget",
+                needed, budget
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimulationError::CheckpointIo { source, .. } => Some(source),
+            SimulationError::Export { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
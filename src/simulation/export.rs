@@ -0,0 +1,221 @@
+//! Per-Path Result Export
+//!
+//! Streams each path's shock draws and resulting fragility to disk while the
+//! simulation runs, so outcomes can be regressed on the shocks that produced
+//! them. Rows travel over a bounded channel to a dedicated writer thread; the
+//! parallel workers never touch the file themselves.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+/// Export file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    NdJson,
+}
+
+/// Per-path export configuration
+///
+/// Honoured by `try_run_simulation`; checkpointed runs do not export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// Output file (created or truncated)
+    pub path: PathBuf,
+    /// Output format
+    pub format: ExportFormat,
+    /// Rows buffered between the workers and the writer thread
+    pub channel_capacity: usize,
+    /// Write each path's shock draws before its fragility
+    #[serde(default = "include_draws_default")]
+    pub include_draws: bool,
+}
+
+fn include_draws_default() -> bool {
+    true
+}
+
+impl ExportConfig {
+    /// Export to `path` in `format` with a 4,096-row channel, draws included
+    pub fn new(path: impl Into<PathBuf>, format: ExportFormat) -> Self {
+        Self {
+            path: path.into(),
+            format,
+            channel_capacity: 4096,
+            include_draws: true,
+        }
+    }
+
+    /// Whether to write the shock draws, or only each path's fragility
+    pub fn with_draws(mut self, include_draws: bool) -> Self {
+        self.include_draws = include_draws;
+        self
+    }
+}
+
+/// One exported path: index, shock draws, and fragility
+pub(crate) struct ExportRow {
+    pub path_index: usize,
+    pub draws: Vec<f64>,
+    pub fragility: f64,
+}
+
+/// Spawn the writer thread; it returns the number of rows written
+pub(crate) fn spawn_writer(
+    config: &ExportConfig,
+    labels: Vec<String>,
+) -> io::Result<(mpsc::SyncSender<ExportRow>, JoinHandle<io::Result<usize>>)> {
+    let file = File::create(&config.path)?;
+    let (tx, rx) = mpsc::sync_channel(config.channel_capacity.max(1));
+    let format = config.format;
+    // Without draws, rows have no columns between index and fragility
+    let labels = if config.include_draws { labels } else { Vec::new() };
+
+    let handle = thread::spawn(move || write_rows(BufWriter::new(file), format, &labels, rx));
+    Ok((tx, handle))
+}
+
+/// Drain the channel into `out`; dropping `rx` on error unblocks the workers
+fn write_rows<W: Write>(
+    mut out: W,
+    format: ExportFormat,
+    labels: &[String],
+    rx: Receiver<ExportRow>,
+) -> io::Result<usize> {
+    if format == ExportFormat::Csv {
+        let columns: Vec<&str> =
+            std::iter::once("path_index").chain(labels.iter().map(String::as_str)).chain(["fragility"]).collect();
+        writeln!(out, "{}", columns.join(","))?;
+    }
+
+    let mut written = 0;
+    for row in rx {
+        match format {
+            ExportFormat::Csv => {
+                write!(out, "{}", row.path_index)?;
+                for d in row.draws.iter().take(labels.len()) {
+                    write!(out, ",{}", d)?;
+                }
+                writeln!(out, ",{}", row.fragility)?;
+            }
+            ExportFormat::NdJson => {
+                let mut record = serde_json::Map::new();
+                record.insert("path_index".to_string(), row.path_index.into());
+                for (label, d) in labels.iter().zip(&row.draws) {
+                    record.insert(label.clone(), (*d).into());
+                }
+                record.insert("fragility".to_string(), row.fragility.into());
+                serde_json::to_writer(&mut out, &record)?;
+                writeln!(out)?;
+            }
+        }
+        written += 1;
+    }
+
+    out.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig, compute_fragility};
+    use crate::simulation::error::SimulationError;
+    use crate::simulation::monte_carlo::{try_run_simulation, MonteCarloConfig};
+    use crate::simulation::shock::{MultiplicativeShock, ShockModel};
+
+    fn base_state() -> BankState {
+        BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    fn run_export(format: ExportFormat, name: &str) -> (PathBuf, usize) {
+        let path = std::env::temp_dir().join(format!("olo-export-{}-{}", name, std::process::id()));
+        let mc_config = MonteCarloConfig {
+            num_simulations: 300,
+            export: Some(ExportConfig::new(&path, format)),
+            ..Default::default()
+        };
+        let result = try_run_simulation(&base_state(), &LagrangianConfig::default(), &mc_config).unwrap();
+        (path, result.fragilities.len())
+    }
+
+    #[test]
+    fn test_csv_export_rows_recompute() {
+        let (path, paths) = run_export(ExportFormat::Csv, "csv");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let mut lines = contents.lines();
+
+        assert_eq!(
+            lines.next().unwrap(),
+            "path_index,shock_capital,shock_assets,shock_lcr,shock_entropy,fragility"
+        );
+        let rows: Vec<Vec<f64>> = lines
+            .map(|l| l.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), paths);
+
+        for row in rows.iter().take(20) {
+            let shocked = MultiplicativeShock.shock(&base_state(), &row[1..5]);
+            let fragility = compute_fragility(&shocked, &LagrangianConfig::default());
+            assert_eq!(fragility, row[5]);
+        }
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_csv_export_without_draws() {
+        let path = std::env::temp_dir().join(format!("olo-export-nodraws-{}", std::process::id()));
+        let mc_config = MonteCarloConfig {
+            num_simulations: 50,
+            export: Some(ExportConfig::new(&path, ExportFormat::Csv).with_draws(false)),
+            ..Default::default()
+        };
+        let result = try_run_simulation(&base_state(), &LagrangianConfig::default(), &mc_config).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let mut lines = contents.lines();
+
+        assert_eq!(lines.next().unwrap(), "path_index,fragility");
+        let first: Vec<f64> = lines.next().unwrap().split(',').map(|v| v.parse().unwrap()).collect();
+        assert_eq!(first, [0.0, result.fragilities[0]]);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_ndjson_export_row_count() {
+        let (path, paths) = run_export(ExportFormat::NdJson, "ndjson");
+        let contents = std::fs::read_to_string(&path).unwrap();
+
+        let rows: Vec<serde_json::Value> =
+            contents.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(rows.len(), paths);
+        assert!(rows.iter().all(|r| r["fragility"].is_f64()));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_unwritable_export_aborts() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 10,
+            export: Some(ExportConfig::new("/nonexistent-dir/out.csv", ExportFormat::Csv)),
+            ..Default::default()
+        };
+
+        let err = try_run_simulation(&base_state(), &LagrangianConfig::default(), &mc_config)
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::Export { .. }));
+    }
+}
@@ -0,0 +1,205 @@
+//! Result Histograms
+//!
+//! Bins a simulated fragility distribution for plotting or terminal display.
+//!
+//! Bins are half-open `[lo, hi)`: a value exactly on an interior edge is counted
+//! in the bin to its right. Data-derived binnings (`EqualWidth`, `EqualCount`)
+//! end at the sample maximum, so their last bin is closed `[lo, hi]`; with
+//! `Explicit` edges a value equal to the last edge counts as overflow.
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::monte_carlo::SimulationResult;
+
+/// How to place bin edges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Binning {
+    /// `n` bins of equal width between the sample minimum and maximum
+    EqualWidth(usize),
+    /// Up to `n` bins holding roughly equal numbers of paths
+    ///
+    /// Edges are sample quantiles; tied quantiles are merged, so heavily
+    /// repeated values (e.g. the insolvency cap) can yield fewer bins.
+    EqualCount(usize),
+    /// Fixed, strictly increasing edges; values outside go to the overflow bins
+    Explicit(Vec<f64>),
+}
+
+/// Binned fragility distribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    /// Bin edges; bin `i` spans `edges[i]..edges[i + 1]`
+    pub edges: Vec<f64>,
+    /// Paths per bin
+    pub counts: Vec<usize>,
+    /// Paths below the first edge
+    pub underflow: usize,
+    /// Paths at or above the last edge (below it for a closed last bin)
+    pub overflow: usize,
+}
+
+impl Histogram {
+    /// Total number of binned paths, including the overflow bins
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum::<usize>() + self.underflow + self.overflow
+    }
+
+    /// Probability density per bin, normalized by the total path count
+    pub fn densities(&self) -> Vec<f64> {
+        let total = self.total() as f64;
+        self.counts
+            .iter()
+            .zip(self.edges.windows(2))
+            .map(|(&count, edge)| count as f64 / (total * (edge[1] - edge[0])))
+            .collect()
+    }
+
+    /// Render as one line per bin with a bar scaled to `width` characters
+    pub fn render_ascii(&self, width: usize) -> String {
+        let peak = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut out = String::new();
+
+        if self.underflow > 0 {
+            out.push_str(&format!("{:>17} {:>8}\n", format!("< {:.2}", self.edges[0]), self.underflow));
+        }
+        for (count, edge) in self.counts.iter().zip(self.edges.windows(2)) {
+            let bar = "#".repeat(count * width / peak);
+            out.push_str(&format!("[{:>7.2}, {:>7.2}) {:>8} {}\n", edge[0], edge[1], count, bar));
+        }
+        if self.overflow > 0 {
+            let last = self.edges[self.edges.len() - 1];
+            out.push_str(&format!("{:>17} {:>8}\n", format!(">= {:.2}", last), self.overflow));
+        }
+        out
+    }
+}
+
+impl SimulationResult {
+    /// Bin the path fragilities
+    ///
+    /// # Panics
+    /// If the result has no stored samples (streaming mode), a data-derived
+    /// binning asks for zero bins, or explicit edges are fewer than two or not
+    /// strictly increasing.
+    pub fn histogram(&self, bins: Binning) -> Histogram {
+        let mut sorted = self.fragilities.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+
+        let (edges, closed) = match bins {
+            Binning::EqualWidth(n) => {
+                assert!(n > 0, "histogram needs at least one bin");
+                // A constant sample gets unit-width bins
+                let width = if max > min { (max - min) / n as f64 } else { 1.0 / n as f64 };
+                let mut edges: Vec<f64> = (0..n).map(|i| min + width * i as f64).collect();
+                edges.push(if max > min { max } else { min + 1.0 });
+                (edges, true)
+            }
+            Binning::EqualCount(n) => {
+                assert!(n > 0, "histogram needs at least one bin");
+                let mut edges: Vec<f64> = (0..n).map(|i| sorted[i * sorted.len() / n]).collect();
+                edges.push(if max > min { max } else { min + 1.0 });
+                edges.dedup();
+                (edges, true)
+            }
+            Binning::Explicit(edges) => {
+                assert!(edges.len() >= 2, "explicit binning needs at least two edges");
+                assert!(
+                    edges.windows(2).all(|e| e[0] < e[1]),
+                    "explicit bin edges must be strictly increasing"
+                );
+                (edges, false)
+            }
+        };
+
+        let mut histogram = Histogram {
+            counts: vec![0; edges.len() - 1],
+            edges,
+            underflow: 0,
+            overflow: 0,
+        };
+        let last = histogram.edges[histogram.edges.len() - 1];
+
+        for &value in &sorted {
+            // Number of edges <= value: 0 is underflow, all edges is overflow
+            let above = histogram.edges.partition_point(|&e| e <= value);
+            if above == 0 {
+                histogram.underflow += 1;
+            } else if above < histogram.edges.len() {
+                histogram.counts[above - 1] += 1;
+            } else if closed && value == last {
+                histogram.counts[above - 2] += 1;
+            } else {
+                histogram.overflow += 1;
+            }
+        }
+
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::simulation::monte_carlo::{run_simulation, summarize, MonteCarloConfig};
+
+    fn simulated() -> SimulationResult {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let mc_config = MonteCarloConfig {
+            num_simulations: 2_000,
+            ..Default::default()
+        };
+        run_simulation(&base_state, &LagrangianConfig::default(), &mc_config)
+    }
+
+    #[test]
+    fn test_counts_sum_to_path_count() {
+        let result = simulated();
+
+        for bins in [Binning::EqualWidth(20), Binning::EqualCount(10)] {
+            let histogram = result.histogram(bins);
+            assert_eq!(histogram.counts.iter().sum::<usize>(), 2_000);
+            assert_eq!(histogram.underflow + histogram.overflow, 0);
+
+            let mass: f64 = histogram
+                .densities()
+                .iter()
+                .zip(histogram.edges.windows(2))
+                .map(|(d, e)| d * (e[1] - e[0]))
+                .sum();
+            assert!((mass - 1.0).abs() < 1e-9);
+        }
+
+        let equal_count = result.histogram(Binning::EqualCount(10));
+        assert!(equal_count.counts.iter().all(|&c| c == 200));
+    }
+
+    #[test]
+    fn test_explicit_edges_overflow_bins() {
+        let result = summarize(vec![-1.0, 0.0, 5.0, 10.0, 10.0, 19.9, 20.0, 30.0]);
+        let histogram = result.histogram(Binning::Explicit(vec![0.0, 10.0, 20.0]));
+
+        assert_eq!(histogram.underflow, 1);
+        assert_eq!(histogram.counts, vec![2, 3]);
+        assert_eq!(histogram.overflow, 2);
+        assert_eq!(histogram.total(), 8);
+    }
+
+    #[test]
+    fn test_ascii_render_has_row_per_bin() {
+        let result = summarize(vec![-1.0, 1.0, 2.0, 2.5, 3.0]);
+        let rendered = result
+            .histogram(Binning::Explicit(vec![0.0, 2.0, 4.0]))
+            .render_ascii(10);
+
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].ends_with("##########"));
+    }
+}
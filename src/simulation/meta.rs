@@ -0,0 +1,311 @@
+//! Result Metadata
+//!
+//! Records which inputs produced a `SimulationResult`, so saved results can be
+//! traced back to their configuration and only compatible runs are combined.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::simulation::error::SimulationError;
+use crate::simulation::monte_carlo::{summarize, MonteCarloConfig, SimulationResult};
+use crate::simulation::sampler::{Sampler, ShockDistribution};
+use crate::simulation::shock::ShockModel;
+use crate::simulation::streaming::QuantileSketch;
+
+/// Provenance of a simulation result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationMeta {
+    /// SHA-256 of the base state and both configs, hex encoded
+    ///
+    /// Seed, path count, thread count, export and memory-budget settings are
+    /// left out: they do not change the distribution being estimated, so runs
+    /// differing only in those can be merged. Seed and path count are recorded
+    /// separately below.
+    pub fingerprint: String,
+    /// Seeds of every run combined into this result
+    pub seeds: Vec<u64>,
+    /// Number of simulated paths
+    pub num_simulations: usize,
+    /// Shock sampler identifier
+    pub sampler: String,
+    /// Distribution identifier per shock draw
+    pub distributions: Vec<String>,
+    /// `olo-core` version that produced the result
+    pub crate_version: String,
+    /// Wall-clock time spent simulating
+    pub duration: Duration,
+    /// Run switched to streaming mode to fit `max_memory_bytes`
+    pub degraded_to_streaming: bool,
+}
+
+impl SimulationMeta {
+    /// Describe a run of `model` with the given inputs
+    pub(crate) fn new(
+        base_state: &BankState,
+        lag_config: &LagrangianConfig,
+        mc_config: &MonteCarloConfig,
+        model: &dyn ShockModel,
+        duration: Duration,
+    ) -> Self {
+        let sampler = match &mc_config.sampler {
+            Sampler::PseudoRandom => "pseudo_random".to_string(),
+            Sampler::LatinHypercube => "latin_hypercube".to_string(),
+            Sampler::Bootstrap { observations, block_size } => match block_size {
+                Some(block) => format!("bootstrap(n={}, block={})", observations.len(), block),
+                None => format!("bootstrap(n={})", observations.len()),
+            },
+        };
+
+        let default = ShockDistribution::Normal { location: 0.0, scale: mc_config.shock_size };
+        let distributions = match mc_config.sampler {
+            Sampler::Bootstrap { .. } => vec!["empirical".to_string(); model.dimension()],
+            _ => (0..model.dimension())
+                .map(|k| describe(mc_config.shock_distributions.get(k).unwrap_or(&default)))
+                .collect(),
+        };
+
+        Self {
+            fingerprint: fingerprint(base_state, lag_config, mc_config),
+            seeds: vec![mc_config.seed],
+            num_simulations: mc_config.num_simulations,
+            sampler,
+            distributions,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            duration,
+            degraded_to_streaming: false,
+        }
+    }
+}
+
+/// Short identifier for a shock distribution
+fn describe(distribution: &ShockDistribution) -> String {
+    match *distribution {
+        ShockDistribution::Normal { location, scale } => format!("normal({}, {})", location, scale),
+        ShockDistribution::SkewNormal { location, scale, shape } => {
+            format!("skew_normal({}, {}, {})", location, scale, shape)
+        }
+        ShockDistribution::StudentT { location, scale, df } => format!("student_t({}, {}, {})", location, scale, df),
+    }
+}
+
+/// Fingerprint of the inputs that determine the fragility distribution
+pub fn fingerprint(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+) -> String {
+    let canonical = MonteCarloConfig {
+        seed: 0,
+        num_simulations: 0,
+        num_threads: 0,
+        export: None,
+        max_memory_bytes: None,
+        auto_degrade: false,
+        ..mc_config.clone()
+    };
+    let bytes = bincode::serialize(&(base_state, lag_config, &canonical))
+        .expect("run inputs are always serializable");
+    sha256_hex(&bytes)
+}
+
+/// Hex-encoded SHA-256 digest
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl SimulationResult {
+    /// Combine with an independent run of the same configuration
+    ///
+    /// Both results need metadata with matching fingerprints and disjoint
+    /// seeds, since equal seeds draw the same paths. Stored samples are
+    /// concatenated and streaming sketches merged. Variance-reduced means are
+    /// combined by path-weighted averaging.
+    pub fn merge(&self, other: &SimulationResult) -> Result<SimulationResult, SimulationError> {
+        let (Some(meta), Some(other_meta)) = (&self.meta, &other.meta) else {
+            return Err(SimulationError::InvalidConfig {
+                reason: "only results with metadata can be merged".to_string(),
+            });
+        };
+        if meta.fingerprint != other_meta.fingerprint {
+            return Err(SimulationError::FingerprintMismatch {
+                expected: meta.fingerprint.clone(),
+                found: other_meta.fingerprint.clone(),
+            });
+        }
+        if let Some(&seed) = other_meta.seeds.iter().find(|s| meta.seeds.contains(s)) {
+            return Err(SimulationError::DuplicateSeed { seed });
+        }
+
+        let (n_a, n_b) = (meta.num_simulations as f64, other_meta.num_simulations as f64);
+        let n = n_a + n_b;
+
+        let mut merged = match (&self.sketch, &other.sketch) {
+            (Some(a), Some(b)) if self.fragilities.is_empty() && other.fragilities.is_empty() => {
+                let mut sketch: QuantileSketch = a.clone();
+                sketch.merge(b);
+
+                // Pooled population variance of the two samples
+                let mean = (n_a * self.mean + n_b * other.mean) / n;
+                let m2 = n_a * self.std_dev.powi(2)
+                    + n_b * other.std_dev.powi(2)
+                    + (self.mean - other.mean).powi(2) * n_a * n_b / n;
+                let std_dev = (m2 / n).sqrt();
+
+                SimulationResult {
+                    fragilities: Vec::new(),
+                    mean,
+                    std_dev,
+                    std_error: std_dev / n.sqrt(),
+                    variance_reduction_ratio: None,
+                    var_95: sketch.quantile(0.95),
+                    var_99: sketch.quantile(0.99),
+                    max_fragility: self.max_fragility.max(other.max_fragility),
+                    shock_hash: None,
+                    sketch: Some(sketch),
+                    meta: None,
+                }
+            }
+            _ => summarize(self.fragilities.iter().chain(&other.fragilities).copied().collect()),
+        };
+
+        if let (Some(_), Some(_)) = (self.variance_reduction_ratio, other.variance_reduction_ratio) {
+            merged.mean = (n_a * self.mean + n_b * other.mean) / n;
+            merged.std_error =
+                ((n_a * self.std_error).powi(2) + (n_b * other.std_error).powi(2)).sqrt() / n;
+            merged.variance_reduction_ratio =
+                Some(merged.std_dev.powi(2) / n / merged.std_error.powi(2));
+        }
+
+        merged.meta = Some(SimulationMeta {
+            seeds: meta.seeds.iter().chain(&other_meta.seeds).copied().collect(),
+            num_simulations: meta.num_simulations + other_meta.num_simulations,
+            duration: meta.duration + other_meta.duration,
+            ..meta.clone()
+        });
+        Ok(merged)
+    }
+
+    /// Merge `other` into `self` in place
+    pub fn extend(&mut self, other: &SimulationResult) -> Result<(), SimulationError> {
+        *self = self.merge(other)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::run_simulation;
+    use crate::simulation::variance::VarianceReduction;
+
+    fn base_state() -> BankState {
+        BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_config_fields() {
+        let base = base_state();
+        let lag = LagrangianConfig::default();
+        let mc = MonteCarloConfig::default();
+        let reference = fingerprint(&base, &lag, &mc);
+
+        // Stable across calls and across run-size settings
+        assert_eq!(fingerprint(&base, &lag, &mc), reference);
+        assert_eq!(
+            fingerprint(&base, &lag, &MonteCarloConfig { seed: 7, num_simulations: 5, ..mc.clone() }),
+            reference
+        );
+
+        let changed = [
+            fingerprint(&BankState { liquidity_coverage: 1.3, ..base.clone() }, &lag, &mc),
+            fingerprint(&base, &LagrangianConfig { lambda_sensitivity: 2.5, ..lag.clone() }, &mc),
+            fingerprint(&base, &LagrangianConfig { regulatory_min_capital: 0.1, ..lag.clone() }, &mc),
+            fingerprint(&base, &lag, &MonteCarloConfig { shock_size: 2.5, ..mc.clone() }),
+            fingerprint(&base, &lag, &MonteCarloConfig { sampler: Sampler::LatinHypercube, ..mc.clone() }),
+            fingerprint(
+                &base,
+                &lag,
+                &MonteCarloConfig { variance_reduction: VarianceReduction::ControlVariate, ..mc.clone() },
+            ),
+            fingerprint(&base, &lag, &MonteCarloConfig { store_samples: false, ..mc.clone() }),
+        ];
+        for (i, other) in changed.iter().enumerate() {
+            assert_ne!(*other, reference, "field change {} kept the fingerprint", i);
+        }
+    }
+
+    #[test]
+    fn test_run_records_meta() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 200,
+            ..Default::default()
+        };
+        let lag = LagrangianConfig::default();
+
+        let first = run_simulation(&base_state(), &lag, &mc_config);
+        let second = run_simulation(&base_state(), &lag, &mc_config);
+        let meta = first.meta.as_ref().unwrap();
+
+        assert_eq!(meta.fingerprint, second.meta.unwrap().fingerprint);
+        assert_eq!(meta.seeds, vec![42]);
+        assert_eq!(meta.num_simulations, 200);
+        assert_eq!(meta.sampler, "pseudo_random");
+        assert_eq!(meta.distributions, vec!["normal(0, 2)"; 4]);
+        assert_eq!(meta.crate_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_merge_checks_fingerprint() {
+        let lag = LagrangianConfig::default();
+        let run = |seed, shock_size| {
+            let mc_config = MonteCarloConfig {
+                num_simulations: 300,
+                seed,
+                shock_size,
+                ..Default::default()
+            };
+            run_simulation(&base_state(), &lag, &mc_config)
+        };
+
+        let mut combined = run(1, 2.0);
+        combined.extend(&run(2, 2.0)).unwrap();
+        assert_eq!(combined.fragilities.len(), 600);
+        assert_eq!(combined.meta.as_ref().unwrap().seeds, vec![1, 2]);
+        assert_eq!(combined.meta.as_ref().unwrap().num_simulations, 600);
+
+        let err = combined.merge(&run(3, 3.0)).unwrap_err();
+        assert!(matches!(err, SimulationError::FingerprintMismatch { .. }));
+    }
+
+    #[test]
+    fn test_merge_rejects_duplicate_seeds() {
+        let lag = LagrangianConfig::default();
+        let run = |seed| {
+            let mc_config = MonteCarloConfig {
+                num_simulations: 100,
+                seed,
+                ..Default::default()
+            };
+            run_simulation(&base_state(), &lag, &mc_config)
+        };
+
+        let mut combined = run(1);
+        let err = combined.merge(&run(1)).unwrap_err();
+        assert!(matches!(err, SimulationError::DuplicateSeed { seed: 1 }));
+
+        combined.extend(&run(2)).unwrap();
+        let err = combined.extend(&run(2)).unwrap_err();
+        assert!(matches!(err, SimulationError::DuplicateSeed { seed: 2 }));
+        assert_eq!(combined.meta.as_ref().unwrap().seeds, vec![1, 2]);
+    }
+}
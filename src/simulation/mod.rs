@@ -0,0 +1,35 @@
+//! # Simulation Module
+//!
+//! Monte Carlo stress testing engine for OLO Core.
+//! Contains the parallel one-shot (stored or streaming), multi-period, and
+//! multi-bank simulators, pluggable shock models, and disk checkpointing for
+//! long runs.
+
+pub mod monte_carlo;
+pub mod checkpoint;
+pub mod error;
+pub mod shock;
+pub mod paths;
+pub mod system;
+pub mod sampler;
+pub mod variance;
+pub mod export;
+pub mod histogram;
+pub mod shock_set;
+pub mod streaming;
+pub mod meta;
+
+// Re-export key types
+pub use monte_carlo::{MonteCarloConfig, SimulationResult, estimate_memory_bytes, exceedance_curve, run_simulation, run_simulation_with_model, threshold_for_exceedance, try_run_simulation};
+pub use checkpoint::{CheckpointConfig, CheckpointOutcome, run_simulation_checkpointed, run_simulation_checkpointed_with_model, resume_from_checkpoint, resume_from_checkpoint_with_model};
+pub use error::SimulationError;
+pub use shock::{MultiplicativeShock, ShockModel};
+pub use paths::{GarchVolatility, HorizonVar, PathConfig, PathSimulationResult, VolatilityModel, horizon_var, run_path_simulation};
+pub use system::{Dependence, SystemSimulationResult, run_system_simulation};
+pub use sampler::{Sampler, ShockDistribution, StateShock, load_shock_history};
+pub use variance::VarianceReduction;
+pub use export::{ExportConfig, ExportFormat};
+pub use histogram::{Binning, Histogram};
+pub use shock_set::{ShockSet, generate_shocks, run_simulation_with_shocks};
+pub use streaming::QuantileSketch;
+pub use meta::SimulationMeta;
@@ -4,16 +4,22 @@
 //! Simulates thousands of scenarios to compute Value-at-Risk and tail risk.
 
 use rayon::prelude::*;
-use rand::distributions::Distribution;
-use rand::rngs::StdRng;
-use rand::SeedableRng;
-use rand_distr::Normal;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::SyncSender;
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::core::lagrangian::{BankState, LagrangianConfig, compute_fragility};
+use crate::simulation::error::SimulationError;
+use crate::simulation::export::{spawn_writer, ExportConfig, ExportRow};
+use crate::simulation::meta::SimulationMeta;
+use crate::simulation::sampler::{validate_sampling, Sampler, ShockDistribution, ShockStream};
+use crate::simulation::shock::{MultiplicativeShock, ShockModel};
+use crate::simulation::streaming::{QuantileSketch, StreamingSummary, SKETCH_BINS};
+use crate::simulation::variance::{apply_control_variate, VarianceReduction};
 
 /// Monte Carlo configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonteCarloConfig {
     /// Number of simulation paths
     pub num_simulations: usize,
@@ -21,8 +27,24 @@ pub struct MonteCarloConfig {
     pub seed: u64,
     /// Shock magnitude (standard deviations)
     pub shock_size: f64,
-    /// Parallel threads (0 = auto)
+    /// Parallel threads (0 = rayon's global pool)
     pub num_threads: usize,
+    /// Source of shock draws
+    pub sampler: Sampler,
+    /// Per-draw distributions for pseudo-random sampling, in the shock model's
+    /// draw order (empty = N(0, shock_size) for every draw)
+    pub shock_distributions: Vec<ShockDistribution>,
+    /// Variance-reduction technique for the mean estimate
+    pub variance_reduction: VarianceReduction,
+    /// Stream per-path shocks and outcomes to a file
+    pub export: Option<ExportConfig>,
+    /// Keep every path's fragility (false = streaming mode: running moments
+    /// and a quantile sketch only, in bounded memory)
+    pub store_samples: bool,
+    /// Refuse runs whose estimated memory footprint exceeds this many bytes
+    pub max_memory_bytes: Option<usize>,
+    /// Switch to streaming mode instead of failing when over the memory budget
+    pub auto_degrade: bool,
 }
 
 impl Default for MonteCarloConfig {
@@ -32,10 +54,20 @@ impl Default for MonteCarloConfig {
             seed: 42,
             shock_size: 2.0,
             num_threads: 0,
+            sampler: Sampler::PseudoRandom,
+            shock_distributions: Vec::new(),
+            variance_reduction: VarianceReduction::None,
+            export: None,
+            store_samples: true,
+            max_memory_bytes: None,
+            auto_degrade: false,
         }
     }
 }
 
+/// Paths simulated per batch in streaming mode
+const STREAM_CHUNK: usize = 65_536;
+
 /// Simulation result
 #[derive(Debug, Clone)]
 pub struct SimulationResult {
@@ -45,54 +77,270 @@ pub struct SimulationResult {
     pub mean: f64,
     /// Standard deviation
     pub std_dev: f64,
+    /// Standard error of the mean estimate
+    pub std_error: f64,
+    /// Plain variance over variance-reduced variance (None = no reduction applied)
+    pub variance_reduction_ratio: Option<f64>,
     /// 95% Value-at-Risk
     pub var_95: f64,
     /// 99% Value-at-Risk
     pub var_99: f64,
     /// Maximum fragility observed
     pub max_fragility: f64,
+    /// SHA-256 of the shock draws (only for runs on a `ShockSet`)
+    pub shock_hash: Option<String>,
+    /// Quantile sketch standing in for `fragilities` (streaming mode only)
+    pub sketch: Option<QuantileSketch>,
+    /// Inputs and provenance of the run (None for derived summaries)
+    pub meta: Option<SimulationMeta>,
 }
 
 /// Run Monte Carlo simulation
 ///
 /// Applies random shocks to bank state and computes fragility distribution
+///
+/// # Panics
+/// If the sampling configuration is invalid, or `mc_config.export` is set and
+/// writing the export fails; use `try_run_simulation` to handle those errors.
 pub fn run_simulation(
     base_state: &BankState,
     lag_config: &LagrangianConfig,
     mc_config: &MonteCarloConfig,
 ) -> SimulationResult {
-    let lag_config = Arc::new(lag_config.clone());
-    
-    // Generate all random shocks upfront
-    let mut rng = StdRng::seed_from_u64(mc_config.seed);
-    let normal = Normal::new(0.0, mc_config.shock_size).unwrap();
-    
-    let shocks: Vec<(f64, f64, f64, f64)> = (0..mc_config.num_simulations)
-        .map(|_| {
-            (
-                normal.sample(&mut rng),
-                normal.sample(&mut rng),
-                normal.sample(&mut rng),
-                normal.sample(&mut rng),
-            )
+    run_simulation_with_model(base_state, lag_config, mc_config, &MultiplicativeShock)
+}
+
+/// Run Monte Carlo simulation with a custom shock model
+///
+/// Draws `model.dimension()` shocks per path from the configured sampler and
+/// lets the model map them onto the shocked bank state.
+///
+/// # Panics
+/// Under the same conditions as `run_simulation`.
+pub fn run_simulation_with_model(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    model: &dyn ShockModel,
+) -> SimulationResult {
+    try_run_simulation_with_model(base_state, lag_config, mc_config, model)
+        .unwrap_or_else(|e| panic!("simulation failed: {}", e))
+}
+
+/// Fallible `run_simulation`
+pub fn try_run_simulation(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+) -> Result<SimulationResult, SimulationError> {
+    try_run_simulation_with_model(base_state, lag_config, mc_config, &MultiplicativeShock)
+}
+
+/// Fallible `run_simulation_with_model`
+///
+/// With `num_threads` set, paths run on a dedicated pool of that many threads.
+pub fn try_run_simulation_with_model(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    model: &dyn ShockModel,
+) -> Result<SimulationResult, SimulationError> {
+    install_pool(mc_config, || simulate_on_current_pool(base_state, lag_config, mc_config, model))
+}
+
+/// Run `op` on a dedicated pool of `num_threads` threads (0 = the current pool)
+pub(crate) fn install_pool<T, F>(mc_config: &MonteCarloConfig, op: F) -> Result<T, SimulationError>
+where
+    T: Send,
+    F: FnOnce() -> Result<T, SimulationError> + Send,
+{
+    if mc_config.num_threads == 0 {
+        return op();
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(mc_config.num_threads)
+        .build()
+        .map_err(|e| SimulationError::InvalidConfig {
+            reason: format!("cannot start {} simulation threads: {}", mc_config.num_threads, e),
+        })?;
+    pool.install(op)
+}
+
+fn simulate_on_current_pool(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    model: &dyn ShockModel,
+) -> Result<SimulationResult, SimulationError> {
+    let started = Instant::now();
+    validate_sampling(mc_config, model.dimension())?;
+
+    let degraded = check_memory_budget(mc_config, model.dimension())?;
+    let streaming_config;
+    let mc_config = if degraded {
+        streaming_config = MonteCarloConfig { store_samples: false, ..mc_config.clone() };
+        &streaming_config
+    } else {
+        mc_config
+    };
+    if !mc_config.store_samples && mc_config.variance_reduction != VarianceReduction::None {
+        return Err(SimulationError::InvalidConfig {
+            reason: "variance reduction requires stored samples".to_string(),
+        });
+    }
+
+    let (tx, writer) = match &mc_config.export {
+        Some(export) => {
+            let (tx, writer) = spawn_writer(export, model.draw_labels()).map_err(|source| {
+                SimulationError::Export { path: export.path.clone(), source }
+            })?;
+            (Some(tx), Some(writer))
+        }
+        None => (None, None),
+    };
+
+    let mut stream = ShockStream::new(mc_config, model.dimension())?;
+    let run = |shocks: &[f64], first_index: usize| match &tx {
+        Some(tx) => simulate_paths_exporting(base_state, lag_config, model, shocks, first_index, tx),
+        None => Ok(simulate_paths(base_state, lag_config, model, shocks)),
+    };
+
+    let outcome = if mc_config.store_samples {
+        // Generate all random shocks upfront
+        let shocks = stream.draw(mc_config.num_simulations);
+        run(&shocks, 0).map(|fragilities| {
+            finalize(base_state, lag_config, mc_config, model, &shocks, fragilities)
         })
-        .collect();
-    
-    // Parallel simulation
-    let fragilities: Vec<f64> = shocks
-        .par_iter()
-        .map(|(shock_assets, shock_liab, shock_equity, shock_lev)| {
-            let shocked_state = BankState {
-                assets: (base_state.assets * (1.0 + shock_assets * 0.01)).max(0.0),
-                liabilities: (base_state.liabilities * (1.0 + shock_liab * 0.01)).max(0.0),
-                equity: (base_state.equity * (1.0 + shock_equity * 0.01)).max(0.0),
-                leverage: (base_state.leverage * (1.0 + shock_lev * 0.01)).max(0.0),
-            };
-            compute_fragility(&shocked_state, &lag_config)
+    } else {
+        let mut summary = StreamingSummary::default();
+        let mut completed = 0;
+        let mut outcome = Ok(());
+        while completed < mc_config.num_simulations && outcome.is_ok() {
+            let count = STREAM_CHUNK.min(mc_config.num_simulations - completed);
+            outcome = run(&stream.draw(count), completed)
+                .map(|fragilities| fragilities.into_iter().for_each(|f| summary.push(f)));
+            completed += count;
+        }
+        outcome.map(|_| summary.into_result())
+    };
+
+    // Close the channel so the writer can finish, then surface its error
+    drop(tx);
+    if let (Some(writer), Some(export)) = (writer, &mc_config.export) {
+        writer
+            .join()
+            .expect("export writer thread panicked")
+            .map_err(|source| SimulationError::Export { path: export.path.clone(), source })?;
+    }
+
+    let mut result = outcome.expect("export channel closed without a write error");
+    let mut meta = SimulationMeta::new(base_state, lag_config, mc_config, model, started.elapsed());
+    meta.degraded_to_streaming = degraded;
+    result.meta = Some(meta);
+    Ok(result)
+}
+
+/// Estimated peak memory of a run, in bytes
+///
+/// Stored mode holds every shock vector plus the fragilities and their sorted
+/// copy; streaming mode holds one batch and the sketch. Latin hypercube designs
+/// are always held in full, and export adds its channel buffer.
+pub fn estimate_memory_bytes(mc_config: &MonteCarloConfig, dimension: usize) -> usize {
+    const F64: usize = std::mem::size_of::<f64>();
+    let paths = mc_config.num_simulations;
+
+    let mut bytes = if mc_config.store_samples {
+        paths * (dimension + 2) * F64
+    } else {
+        STREAM_CHUNK.min(paths) * (dimension + 1) * F64 + SKETCH_BINS * std::mem::size_of::<u64>()
+    };
+    if let Sampler::LatinHypercube = mc_config.sampler {
+        bytes += paths * dimension * F64;
+    }
+    if let Some(export) = &mc_config.export {
+        let row = std::mem::size_of::<ExportRow>() + dimension * F64;
+        bytes += export.channel_capacity * row;
+    }
+    bytes
+}
+
+/// Enforce `max_memory_bytes`; `Ok(true)` means the run must stream instead
+pub(crate) fn check_memory_budget(mc_config: &MonteCarloConfig, dimension: usize) -> Result<bool, SimulationError> {
+    let Some(budget) = mc_config.max_memory_bytes else {
+        return Ok(false);
+    };
+
+    let needed = estimate_memory_bytes(mc_config, dimension);
+    if needed <= budget {
+        return Ok(false);
+    }
+
+    // Streaming cannot apply variance reduction, so there is nothing to degrade to
+    if mc_config.auto_degrade && mc_config.store_samples && mc_config.variance_reduction == VarianceReduction::None {
+        let streaming = MonteCarloConfig { store_samples: false, ..mc_config.clone() };
+        if estimate_memory_bytes(&streaming, dimension) <= budget {
+            return Ok(true);
+        }
+    }
+    Err(SimulationError::MemoryBudgetExceeded { needed, budget })
+}
+
+/// Compute fragility for each shock vector in parallel, preserving order
+pub(crate) fn simulate_paths(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    model: &dyn ShockModel,
+    shocks: &[f64],
+) -> Vec<f64> {
+    let lag_config = Arc::new(lag_config.clone());
+
+    shocks
+        .par_chunks(model.dimension())
+        .map(|draws| compute_fragility(&model.shock(base_state, draws), &lag_config))
+        .collect()
+}
+
+/// `simulate_paths`, sending every row to the export writer thread
+///
+/// Fails once the writer has hung up after a write error, which stops the
+/// remaining workers.
+fn simulate_paths_exporting(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    model: &dyn ShockModel,
+    shocks: &[f64],
+    first_index: usize,
+    tx: &SyncSender<ExportRow>,
+) -> Result<Vec<f64>, ()> {
+    shocks
+        .par_chunks(model.dimension())
+        .enumerate()
+        .map_with(tx.clone(), |tx, (i, draws)| {
+            let fragility = compute_fragility(&model.shock(base_state, draws), lag_config);
+            let row = ExportRow { path_index: first_index + i, draws: draws.to_vec(), fragility };
+            tx.send(row).map(|_| fragility).map_err(|_| ())
         })
-        .collect();
-    
-    // Compute statistics
+        .collect()
+}
+
+/// Summarize path fragilities and apply the configured variance reduction
+pub(crate) fn finalize(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    model: &dyn ShockModel,
+    shocks: &[f64],
+    fragilities: Vec<f64>,
+) -> SimulationResult {
+    let mut result = summarize(fragilities);
+    if mc_config.variance_reduction == VarianceReduction::ControlVariate {
+        apply_control_variate(&mut result, base_state, lag_config, mc_config, model, shocks);
+    }
+    result
+}
+
+/// Compute summary statistics over the full set of path fragilities
+pub(crate) fn summarize(fragilities: Vec<f64>) -> SimulationResult {
     let mut sorted = fragilities.clone();
     sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
     
@@ -106,34 +354,77 @@ pub fn run_simulation(
     let var_99_idx = (0.99 * fragilities.len() as f64) as usize;
     
     SimulationResult {
+        std_error: std_dev / (fragilities.len() as f64).sqrt(),
         fragilities,
         mean,
         std_dev,
+        variance_reduction_ratio: None,
         var_95: sorted[var_95_idx.min(sorted.len() - 1)],
         var_99: sorted[var_99_idx.min(sorted.len() - 1)],
         max_fragility: sorted[sorted.len() - 1],
+        shock_hash: None,
+        sketch: None,
+        meta: None,
     }
 }
 
 /// Calculate tail risk metrics
+///
+/// Share of paths with fragility strictly above `threshold`.
 pub fn calculate_tail_risk(result: &SimulationResult, threshold: f64) -> f64 {
-    let exceedances = result.fragilities.iter()
-        .filter(|&&f| f > threshold)
-        .count();
-    exceedances as f64 / result.fragilities.len() as f64
+    exceedance_curve(result, &[threshold])[0].1
+}
+
+/// Exceedance probability at each threshold, as `(threshold, probability)`
+///
+/// Sorts the stored samples once and binary-searches each threshold. Streaming
+/// results are read off the quantile sketch instead (see `streaming` for its
+/// approximation error).
+pub fn exceedance_curve(result: &SimulationResult, thresholds: &[f64]) -> Vec<(f64, f64)> {
+    if let Some(sketch) = result.sketch.as_ref().filter(|_| result.fragilities.is_empty()) {
+        return thresholds.iter().map(|&t| (t, sketch.exceedance(t))).collect();
+    }
+
+    let sorted = sorted_fragilities(result);
+    let n = sorted.len() as f64;
+    thresholds
+        .iter()
+        .map(|&t| (t, (sorted.len() - sorted.partition_point(|&f| f <= t)) as f64 / n))
+        .collect()
+}
+
+/// Smallest sampled fragility whose exceedance probability is at most `prob`
+///
+/// Inverse of `exceedance_curve`: at most `floor(prob * n)` paths lie strictly
+/// above the returned threshold.
+pub fn threshold_for_exceedance(result: &SimulationResult, prob: f64) -> f64 {
+    if let Some(sketch) = result.sketch.as_ref().filter(|_| result.fragilities.is_empty()) {
+        return sketch.threshold_for_exceedance(prob);
+    }
+
+    let sorted = sorted_fragilities(result);
+    let allowed = ((prob.clamp(0.0, 1.0) * sorted.len() as f64) as usize).min(sorted.len() - 1);
+    sorted[sorted.len() - 1 - allowed]
+}
+
+fn sorted_fragilities(result: &SimulationResult) -> Vec<f64> {
+    let mut sorted = result.fragilities.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_monte_carlo_basic() {
         let base_state = BankState {
-            assets: 1000.0,
-            liabilities: 900.0,
-            equity: 100.0,
-            leverage: 9.0,
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
         };
         
         let lag_config = LagrangianConfig::default();
@@ -151,13 +442,122 @@ mod tests {
         assert!(result.max_fragility >= result.var_99);
     }
     
+    #[test]
+    fn test_negative_capital_skew_raises_var() {
+        // Asset-value losses land on tier 1 capital (draw 0); a 4.9% loss
+        // breaches the minimum, between the symmetric and skewed 1% quantiles
+        let base_state = BankState {
+            tier1_capital: 8_420.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let quiet = ShockDistribution::Normal { location: 0.0, scale: 0.1 };
+        let symmetric = MonteCarloConfig {
+            num_simulations: 20_000,
+            shock_distributions: vec![
+                ShockDistribution::Normal { location: 0.0, scale: 2.0 },
+                quiet,
+                quiet,
+                quiet,
+            ],
+            ..Default::default()
+        };
+        let skewed = MonteCarloConfig {
+            shock_distributions: vec![
+                ShockDistribution::SkewNormal { location: 0.0, scale: 2.0, shape: -5.0 },
+                quiet,
+                quiet,
+                quiet,
+            ],
+            ..symmetric.clone()
+        };
+
+        let lag_config = LagrangianConfig::default();
+        let symmetric = run_simulation(&base_state, &lag_config, &symmetric);
+        let skewed = run_simulation(&base_state, &lag_config, &skewed);
+        assert!(skewed.var_99 > symmetric.var_99 + 10.0);
+    }
+
+    #[test]
+    fn test_exceedance_curve_monotone_and_invertible() {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let mc_config = MonteCarloConfig {
+            num_simulations: 5_000,
+            ..Default::default()
+        };
+        let result = run_simulation(&base_state, &LagrangianConfig::default(), &mc_config);
+
+        let thresholds: Vec<f64> = (0..=100).map(|i| 17.0 + i as f64 * 0.05).collect();
+        let curve = exceedance_curve(&result, &thresholds);
+        assert!(curve.windows(2).all(|w| w[1].1 <= w[0].1));
+        assert_eq!(curve[0].1, calculate_tail_risk(&result, thresholds[0]));
+
+        for prob in [0.5, 0.1, 0.05, 0.01] {
+            let threshold = threshold_for_exceedance(&result, prob);
+            assert!(calculate_tail_risk(&result, threshold) <= prob);
+            assert!(calculate_tail_risk(&result, threshold - 1e-9) > prob);
+        }
+    }
+
+    #[test]
+    fn test_memory_budget_hard_error() {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let mc_config = MonteCarloConfig {
+            num_simulations: 1_000,
+            max_memory_bytes: Some(1_024),
+            ..Default::default()
+        };
+
+        let err = try_run_simulation(&base_state, &LagrangianConfig::default(), &mc_config).unwrap_err();
+        match err {
+            SimulationError::MemoryBudgetExceeded { needed, budget } => {
+                assert_eq!(needed, 1_000 * 6 * 8);
+                assert_eq!(budget, 1_024);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_memory_budget_auto_degrade() {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        // Stored needs 4.8 MB, streaming about 2.7 MB
+        let mc_config = MonteCarloConfig {
+            num_simulations: 100_000,
+            max_memory_bytes: Some(3_000_000),
+            auto_degrade: true,
+            ..Default::default()
+        };
+
+        let result = try_run_simulation(&base_state, &LagrangianConfig::default(), &mc_config).unwrap();
+        assert!(result.fragilities.is_empty());
+        assert!(result.sketch.is_some());
+        assert!(result.meta.unwrap().degraded_to_streaming);
+    }
+
     #[test]
     fn test_tail_risk() {
         let base_state = BankState {
-            assets: 1000.0,
-            liabilities: 950.0,
-            equity: 50.0,
-            leverage: 19.0,
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 0.9,
+            entropy_index: 3.0,
         };
         
         let lag_config = LagrangianConfig::default();
@@ -173,4 +573,47 @@ mod tests {
         // Approximately 50% should exceed mean in normal distribution
         assert!(tail_risk > 0.4 && tail_risk < 0.6);
     }
+
+    /// Deposit-run model: liquidity collapses quadratically in the draw
+    struct DepositRun {
+        calls: AtomicUsize,
+    }
+
+    impl ShockModel for DepositRun {
+        fn shock(&self, base: &BankState, draws: &[f64]) -> BankState {
+            assert_eq!(draws.len(), self.dimension());
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            BankState {
+                liquidity_coverage: (base.liquidity_coverage - 0.01 * draws[0] * draws[0]).max(0.01),
+                tier1_capital: base.tier1_capital * (1.0 - 0.01 * draws[1].abs()),
+                ..base.clone()
+            }
+        }
+
+        fn dimension(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_custom_shock_model() {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+
+        let lag_config = LagrangianConfig::default();
+        let mc_config = MonteCarloConfig {
+            num_simulations: 500,
+            ..Default::default()
+        };
+        let model = DepositRun { calls: AtomicUsize::new(0) };
+
+        let result = run_simulation_with_model(&base_state, &lag_config, &mc_config, &model);
+
+        assert_eq!(model.calls.load(Ordering::Relaxed), 500);
+        assert_eq!(result.fragilities.len(), 500);
+    }
 }
@@ -0,0 +1,550 @@
+//! Multi-Period Path Simulation
+//!
+//! Evolves a bank state through a sequence of shock steps (e.g. quarters) rather
+//! than a single one-shot perturbation. Each step feeds the previous step's
+//! shocked state back into the shock model, and shock volatility may itself
+//! evolve along the path.
+
+use rayon::prelude::*;
+use rand::distributions::Distribution;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::StandardNormal;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig, capital_adequacy_ratio, compute_fragility};
+use crate::simulation::error::SimulationError;
+use crate::simulation::monte_carlo::{summarize, MonteCarloConfig, SimulationResult};
+use crate::simulation::shock::{MultiplicativeShock, ShockModel};
+
+/// GARCH(1,1) conditional variance dynamics
+///
+/// σ²_t = ω + α * ε²_{t-1} + β * σ²_{t-1}
+///
+/// The realized shock ε²_{t-1} is the mean squared draw of the previous step.
+#[derive(Debug, Clone, Copy)]
+pub struct GarchVolatility {
+    /// Constant term ω (> 0)
+    pub omega: f64,
+    /// Reaction to the last realized shock α (>= 0)
+    pub alpha: f64,
+    /// Persistence of the last variance β (>= 0)
+    pub beta: f64,
+    /// Variance of the first step (> 0)
+    pub initial_var: f64,
+}
+
+impl GarchVolatility {
+    /// Check positivity and stationarity (α + β < 1)
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let invalid = |reason: String| Err(SimulationError::InvalidConfig { reason });
+
+        if self.omega.is_nan() || self.omega <= 0.0 {
+            return invalid(format!("GARCH omega must be > 0, got {}", self.omega));
+        }
+        if self.alpha.is_nan() || self.beta.is_nan() || self.alpha < 0.0 || self.beta < 0.0 {
+            return invalid(format!(
+                "GARCH alpha and beta must be >= 0, got {} and {}",
+                self.alpha, self.beta
+            ));
+        }
+        if self.alpha + self.beta >= 1.0 {
+            return invalid(format!(
+                "GARCH is not stationary: alpha + beta = {} >= 1",
+                self.alpha + self.beta
+            ));
+        }
+        if self.initial_var.is_nan() || self.initial_var <= 0.0 {
+            return invalid(format!("GARCH initial_var must be > 0, got {}", self.initial_var));
+        }
+        Ok(())
+    }
+
+    /// Long-run variance ω / (1 - α - β)
+    pub fn unconditional_variance(&self) -> f64 {
+        self.omega / (1.0 - self.alpha - self.beta)
+    }
+}
+
+/// Shock volatility along a path
+#[derive(Debug, Clone, Copy, Default)]
+pub enum VolatilityModel {
+    /// `shock_size` standard deviation at every step
+    #[default]
+    Constant,
+    /// GARCH(1,1) variance clustering
+    Garch(GarchVolatility),
+}
+
+/// Path simulation configuration
+#[derive(Debug, Clone)]
+pub struct PathConfig {
+    /// Shock steps per path
+    pub steps: usize,
+    /// Volatility dynamics across steps
+    pub volatility: VolatilityModel,
+    /// Keep every path's per-step fragility and volatility series
+    pub store_paths: bool,
+}
+
+impl Default for PathConfig {
+    fn default() -> Self {
+        Self {
+            steps: 4,
+            volatility: VolatilityModel::Constant,
+            store_paths: false,
+        }
+    }
+}
+
+/// Path simulation result
+#[derive(Debug, Clone)]
+pub struct PathSimulationResult {
+    /// Fragility distribution at the final step
+    pub terminal: SimulationResult,
+    /// Per-path fragility after each step (only with `store_paths`)
+    pub paths: Option<Vec<Vec<f64>>>,
+    /// Per-path shock standard deviation at each step (only with `store_paths`)
+    pub volatilities: Option<Vec<Vec<f64>>>,
+    /// Distribution of each path's maximum fragility over all steps
+    pub peak_fragility: SimulationResult,
+    /// Each path's minimum capital adequacy ratio over all steps
+    pub min_capital_ratios: Vec<f64>,
+    /// Share of paths whose CAR fell below the regulatory minimum at any step
+    pub intraperiod_breach_probability: f64,
+    /// Share of paths below the regulatory minimum at the final step
+    pub terminal_breach_probability: f64,
+}
+
+/// VaR at one horizon: simulated versus square-root-of-time scaled
+///
+/// The scaled figure stretches the 1-step VaR's excess over the unshocked
+/// fragility by √horizon; the gap to the simulated figure is the scaling error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HorizonVar {
+    /// Number of shock steps
+    pub horizon: usize,
+    /// 95% VaR of the simulated horizon-step fragility
+    pub simulated_var_95: f64,
+    /// 99% VaR of the simulated horizon-step fragility
+    pub simulated_var_99: f64,
+    /// 95% VaR scaled from the 1-step simulation
+    pub scaled_var_95: f64,
+    /// 99% VaR scaled from the 1-step simulation
+    pub scaled_var_99: f64,
+}
+
+/// One simulated path
+struct PathOutcome {
+    fragilities: Vec<f64>,
+    volatilities: Vec<f64>,
+    min_capital_ratio: f64,
+    terminal_capital_ratio: f64,
+}
+
+/// Run multi-period path simulation with the default shock model
+pub fn run_path_simulation(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    path_config: &PathConfig,
+) -> Result<PathSimulationResult, SimulationError> {
+    run_path_simulation_with_model(
+        base_state,
+        lag_config,
+        mc_config,
+        path_config,
+        &MultiplicativeShock,
+    )
+}
+
+/// Run multi-period path simulation with a custom shock model
+///
+/// Each path `i` draws from its own RNG seeded from `mc_config.seed` and `i`,
+/// so results do not depend on thread scheduling.
+pub fn run_path_simulation_with_model(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    path_config: &PathConfig,
+    model: &dyn ShockModel,
+) -> Result<PathSimulationResult, SimulationError> {
+    if path_config.steps == 0 {
+        return Err(SimulationError::InvalidConfig {
+            reason: "path simulation needs at least one step".to_string(),
+        });
+    }
+    if let VolatilityModel::Garch(garch) = &path_config.volatility {
+        garch.validate()?;
+    }
+
+    let outcomes: Vec<PathOutcome> = (0..mc_config.num_simulations)
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = path_rng(mc_config.seed, i);
+            simulate_path(base_state, lag_config, mc_config, path_config, model, &mut rng)
+        })
+        .collect();
+
+    let terminal = summarize(
+        outcomes
+            .iter()
+            .map(|o| *o.fragilities.last().unwrap())
+            .collect(),
+    );
+    let peak_fragility = summarize(
+        outcomes
+            .iter()
+            .map(|o| o.fragilities.iter().copied().fold(f64::MIN, f64::max))
+            .collect(),
+    );
+
+    let minimum = lag_config.regulatory_min_capital;
+    let share = |breached: usize| breached as f64 / outcomes.len() as f64;
+    let min_capital_ratios: Vec<f64> = outcomes.iter().map(|o| o.min_capital_ratio).collect();
+    let intraperiod_breach_probability =
+        share(min_capital_ratios.iter().filter(|&&car| car < minimum).count());
+    let terminal_breach_probability =
+        share(outcomes.iter().filter(|o| o.terminal_capital_ratio < minimum).count());
+
+    let (paths, volatilities) = if path_config.store_paths {
+        let (paths, volatilities) = outcomes
+            .into_iter()
+            .map(|o| (o.fragilities, o.volatilities))
+            .unzip();
+        (Some(paths), Some(volatilities))
+    } else {
+        (None, None)
+    };
+
+    Ok(PathSimulationResult {
+        terminal,
+        paths,
+        volatilities,
+        peak_fragility,
+        min_capital_ratios,
+        intraperiod_breach_probability,
+        terminal_breach_probability,
+    })
+}
+
+/// Compare simulated and √t-scaled VaR at each horizon (in steps)
+///
+/// # Panics
+/// If `horizons` is empty or contains 0.
+pub fn horizon_var(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    horizons: &[usize],
+) -> Vec<HorizonVar> {
+    horizon_var_with_model(base_state, lag_config, mc_config, horizons, &MultiplicativeShock)
+}
+
+/// `horizon_var` with a custom shock model
+///
+/// Every horizon is read off the same simulated paths (common random numbers),
+/// so differences between horizons are not masked by sampling noise.
+pub fn horizon_var_with_model(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    horizons: &[usize],
+    model: &dyn ShockModel,
+) -> Vec<HorizonVar> {
+    assert!(!horizons.contains(&0), "VaR horizons must be at least one step");
+    let path_config = PathConfig {
+        steps: *horizons.iter().max().expect("at least one VaR horizon is required"),
+        ..Default::default()
+    };
+
+    let outcomes: Vec<PathOutcome> = (0..mc_config.num_simulations)
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = path_rng(mc_config.seed, i);
+            simulate_path(base_state, lag_config, mc_config, &path_config, model, &mut rng)
+        })
+        .collect();
+    let at_step = |h: usize| summarize(outcomes.iter().map(|o| o.fragilities[h - 1]).collect());
+
+    let unshocked = compute_fragility(base_state, lag_config);
+    let one_step = at_step(1);
+
+    horizons
+        .iter()
+        .map(|&horizon| {
+            let simulated = at_step(horizon);
+            let scale = (horizon as f64).sqrt();
+            HorizonVar {
+                horizon,
+                simulated_var_95: simulated.var_95,
+                simulated_var_99: simulated.var_99,
+                scaled_var_95: unshocked + (one_step.var_95 - unshocked) * scale,
+                scaled_var_99: unshocked + (one_step.var_99 - unshocked) * scale,
+            }
+        })
+        .collect()
+}
+
+/// Independent, reproducible RNG for path `index`
+pub(crate) fn path_rng(seed: u64, index: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Evolve one path through all steps
+fn simulate_path(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    path_config: &PathConfig,
+    model: &dyn ShockModel,
+    rng: &mut StdRng,
+) -> PathOutcome {
+    let mut state = base_state.clone();
+    let mut draws = vec![0.0; model.dimension()];
+    let mut fragilities = Vec::with_capacity(path_config.steps);
+    let mut volatilities = Vec::with_capacity(path_config.steps);
+    let mut min_capital_ratio = f64::INFINITY;
+
+    let mut variance = match &path_config.volatility {
+        VolatilityModel::Constant => mc_config.shock_size.powi(2),
+        VolatilityModel::Garch(garch) => garch.initial_var,
+    };
+
+    for _ in 0..path_config.steps {
+        let sigma = variance.sqrt();
+        for d in draws.iter_mut() {
+            let z: f64 = StandardNormal.sample(rng);
+            *d = sigma * z;
+        }
+
+        state = model.shock(&state, &draws);
+        fragilities.push(compute_fragility(&state, lag_config));
+        volatilities.push(sigma);
+        min_capital_ratio = min_capital_ratio.min(capital_adequacy_ratio(&state));
+
+        if let VolatilityModel::Garch(garch) = &path_config.volatility {
+            let realized = draws.iter().map(|d| d * d).sum::<f64>() / draws.len() as f64;
+            variance = garch.omega + garch.alpha * realized + garch.beta * variance;
+        }
+    }
+
+    PathOutcome {
+        fragilities,
+        volatilities,
+        min_capital_ratio,
+        terminal_capital_ratio: capital_adequacy_ratio(&state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn base_state() -> BankState {
+        BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    /// Records the first draw of every step; leaves the state untouched
+    struct Recorder {
+        draws: Mutex<Vec<f64>>,
+    }
+
+    impl ShockModel for Recorder {
+        fn shock(&self, base: &BankState, draws: &[f64]) -> BankState {
+            self.draws.lock().unwrap().push(draws[0]);
+            base.clone()
+        }
+
+        fn dimension(&self) -> usize {
+            4
+        }
+    }
+
+    #[test]
+    fn test_garch_rejects_non_stationary() {
+        let garch = GarchVolatility { omega: 0.1, alpha: 0.3, beta: 0.7, initial_var: 1.0 };
+        let path_config = PathConfig {
+            volatility: VolatilityModel::Garch(garch),
+            ..Default::default()
+        };
+
+        let result = run_path_simulation(
+            &base_state(),
+            &LagrangianConfig::default(),
+            &MonteCarloConfig::default(),
+            &path_config,
+        );
+        assert!(matches!(result, Err(SimulationError::InvalidConfig { .. })));
+    }
+
+    #[test]
+    fn test_garch_unconditional_variance() {
+        let garch = GarchVolatility { omega: 0.2, alpha: 0.1, beta: 0.7, initial_var: 1.0 };
+        let path_config = PathConfig {
+            steps: 50_000,
+            volatility: VolatilityModel::Garch(garch),
+            store_paths: true,
+        };
+        let mc_config = MonteCarloConfig {
+            num_simulations: 4,
+            ..Default::default()
+        };
+        let model = Recorder { draws: Mutex::new(Vec::new()) };
+
+        let result = run_path_simulation_with_model(
+            &base_state(),
+            &LagrangianConfig::default(),
+            &mc_config,
+            &path_config,
+            &model,
+        )
+        .unwrap();
+
+        let draws = model.draws.into_inner().unwrap();
+        let variance = draws.iter().map(|d| d * d).sum::<f64>() / draws.len() as f64;
+        let expected = garch.unconditional_variance();
+        assert!(
+            (variance - expected).abs() / expected < 0.05,
+            "sample variance {} vs unconditional {}",
+            variance,
+            expected
+        );
+
+        let volatilities = result.volatilities.unwrap();
+        assert_eq!(volatilities.len(), 4);
+        assert!(volatilities.iter().all(|v| v.len() == 50_000));
+    }
+
+    /// Small additive entropy shocks: fragility is locally linear in the draws
+    struct LinearEntropy;
+
+    impl ShockModel for LinearEntropy {
+        fn shock(&self, base: &BankState, draws: &[f64]) -> BankState {
+            BankState {
+                entropy_index: base.entropy_index + 1e-3 * draws[0],
+                ..base.clone()
+            }
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_horizon_var_sqrt_time_holds_for_linear_model() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 20_000,
+            ..Default::default()
+        };
+
+        let vars = horizon_var_with_model(
+            &base_state(),
+            &LagrangianConfig::default(),
+            &mc_config,
+            &[1, 10],
+            &LinearEntropy,
+        );
+
+        assert_eq!(vars[0].simulated_var_99, vars[0].scaled_var_99);
+        let ten_day = vars[1];
+        let unshocked = compute_fragility(&base_state(), &LagrangianConfig::default());
+        let simulated_excess = ten_day.simulated_var_99 - unshocked;
+        let scaled_excess = ten_day.scaled_var_99 - unshocked;
+        assert!(
+            (simulated_excess - scaled_excess).abs() / scaled_excess < 0.05,
+            "simulated {} vs scaled {}",
+            ten_day.simulated_var_99,
+            ten_day.scaled_var_99
+        );
+    }
+
+    #[test]
+    fn test_horizon_var_diverges_near_insolvency() {
+        // Capital only 200 above the regulatory minimum
+        let state = BankState {
+            tier1_capital: 8_200.0,
+            ..base_state()
+        };
+        let mc_config = MonteCarloConfig {
+            num_simulations: 5_000,
+            ..Default::default()
+        };
+
+        let vars = horizon_var(&state, &LagrangianConfig::default(), &mc_config, &[1, 10]);
+
+        let ten_day = vars[1];
+        assert!(ten_day.simulated_var_99 <= 100.0);
+        assert!(ten_day.scaled_var_99 > 1.5 * ten_day.simulated_var_99);
+    }
+
+    /// Capital reverts halfway to a 10% CAR each step, plus noise
+    struct MeanRevertingCapital;
+
+    impl ShockModel for MeanRevertingCapital {
+        fn shock(&self, base: &BankState, draws: &[f64]) -> BankState {
+            let target = 0.10 * base.total_assets;
+            BankState {
+                tier1_capital: base.tier1_capital + 0.5 * (target - base.tier1_capital) + 525.0 * draws[0],
+                ..base.clone()
+            }
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_intraperiod_breaches_exceed_terminal() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 4_000,
+            ..Default::default()
+        };
+        let path_config = PathConfig {
+            steps: 20,
+            ..Default::default()
+        };
+
+        let result = run_path_simulation_with_model(
+            &base_state(),
+            &LagrangianConfig::default(),
+            &mc_config,
+            &path_config,
+            &MeanRevertingCapital,
+        )
+        .unwrap();
+
+        assert!(result.terminal_breach_probability < 0.1);
+        assert!(result.intraperiod_breach_probability > 0.3);
+        assert!(result.intraperiod_breach_probability > 3.0 * result.terminal_breach_probability);
+        assert_eq!(result.min_capital_ratios.len(), 4_000);
+        assert!(result.peak_fragility.mean >= result.terminal.mean);
+    }
+
+    #[test]
+    fn test_paths_not_stored_by_default() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 200,
+            ..Default::default()
+        };
+
+        let result = run_path_simulation(
+            &base_state(),
+            &LagrangianConfig::default(),
+            &mc_config,
+            &PathConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(result.terminal.fragilities.len(), 200);
+        assert!(result.paths.is_none());
+        assert!(result.volatilities.is_none());
+    }
+}
@@ -0,0 +1,572 @@
+//! Shock Sampling
+//!
+//! Decides where each path's shock draws come from: parametric pseudo-random
+//! or Latin hypercube draws (normal or skew-normal per field), or resampled
+//! historical state changes. All samplers consume a
+//! single seeded RNG stream so runs are reproducible and chunkable.
+
+use rand::distributions::{Distribution, Open01};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_distr::{ChiSquared, Normal, StandardNormal};
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal as StatNormal, StudentsT};
+use std::path::Path;
+
+use crate::simulation::error::SimulationError;
+use crate::simulation::monte_carlo::MonteCarloConfig;
+use crate::simulation::shock::{BALANCE_SHEET_SCALE, ENTROPY_SCALE, LCR_SCALE};
+
+/// One historical change in the regulatory state fields
+///
+/// Capital and assets are relative changes (0.01 = +1%); liquidity coverage and
+/// entropy index are absolute changes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StateShock {
+    pub capital: f64,
+    pub assets: f64,
+    pub lcr: f64,
+    pub entropy: f64,
+}
+
+impl StateShock {
+    /// Express the change as `MultiplicativeShock` draws
+    pub fn to_draws(&self) -> [f64; 4] {
+        [
+            self.capital / BALANCE_SHEET_SCALE,
+            self.assets / BALANCE_SHEET_SCALE,
+            self.lcr / LCR_SCALE,
+            self.entropy / ENTROPY_SCALE,
+        ]
+    }
+}
+
+/// Parametric distribution of one pseudo-random shock draw
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ShockDistribution {
+    /// N(location, scale²)
+    Normal { location: f64, scale: f64 },
+    /// Azzalini skew-normal; `shape` < 0 fattens the left tail, > 0 the right
+    SkewNormal { location: f64, scale: f64, shape: f64 },
+    /// location + scale·T with T Student's t on `df` degrees of freedom, for
+    /// fat symmetric tails; `df` > 2 keeps the variance finite
+    StudentT { location: f64, scale: f64, df: f64 },
+}
+
+impl ShockDistribution {
+    /// Check that the scale is positive and all parameters are finite
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let (location, scale, shape) = match *self {
+            ShockDistribution::Normal { location, scale } => (location, scale, 0.0),
+            ShockDistribution::SkewNormal { location, scale, shape } => (location, scale, shape),
+            ShockDistribution::StudentT { location, scale, df } => {
+                if df.is_nan() || df <= 2.0 {
+                    return Err(SimulationError::InvalidConfig {
+                        reason: format!("Student's t degrees of freedom must be > 2, got {}", df),
+                    });
+                }
+                (location, scale, 0.0)
+            }
+        };
+
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(SimulationError::InvalidConfig {
+                reason: format!("shock distribution scale must be > 0, got {}", scale),
+            });
+        }
+        if !location.is_finite() || !shape.is_finite() {
+            return Err(SimulationError::InvalidConfig {
+                reason: format!("shock distribution parameters must be finite: {:?}", self),
+            });
+        }
+        Ok(())
+    }
+
+    /// Expected value of a draw
+    pub fn mean(&self) -> f64 {
+        match *self {
+            ShockDistribution::Normal { location, .. } | ShockDistribution::StudentT { location, .. } => location,
+            ShockDistribution::SkewNormal { location, scale, shape } => {
+                let delta = shape / (1.0 + shape * shape).sqrt();
+                location + scale * delta * (2.0 / std::f64::consts::PI).sqrt()
+            }
+        }
+    }
+
+    /// Quantile function
+    ///
+    /// The skew-normal CDF Φ(z) - 2·T(z, shape) has no closed-form inverse, so
+    /// it is inverted numerically (Newton steps safeguarded by bisection).
+    pub fn inverse_cdf(&self, p: f64) -> f64 {
+        let standard = StatNormal::new(0.0, 1.0).unwrap();
+        match *self {
+            ShockDistribution::Normal { location, scale } => location + scale * standard.inverse_cdf(p),
+            ShockDistribution::SkewNormal { location, scale, shape } => {
+                let (mut lo, mut hi) = (-40.0, 40.0);
+                let mut z = standard.inverse_cdf(p);
+                for _ in 0..100 {
+                    let error = skew_normal_cdf(z, shape) - p;
+                    if error.abs() < 1e-12 {
+                        break;
+                    }
+                    if error > 0.0 { hi = z } else { lo = z }
+
+                    let density = 2.0 * standard.pdf(z) * standard.cdf(shape * z);
+                    let newton = z - error / density;
+                    z = if newton > lo && newton < hi { newton } else { 0.5 * (lo + hi) };
+                }
+                location + scale * z
+            }
+            ShockDistribution::StudentT { location, scale, df } => {
+                location + scale * StudentsT::new(0.0, 1.0, df).unwrap().inverse_cdf(p)
+            }
+        }
+    }
+
+    /// Draw one value
+    ///
+    /// Skew-normal draws use the two-normal construction: with u0, v ~ N(0, 1)
+    /// and δ = shape / √(1 + shape²), u1 = δ·u0 + √(1 - δ²)·v is skew-normal
+    /// when its sign is flipped whenever u0 < 0. Student's t draws are
+    /// u0 / √(V / df) with V chi-squared on `df` degrees of freedom.
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        let z: f64 = StandardNormal.sample(rng);
+        match *self {
+            ShockDistribution::Normal { location, scale } => location + scale * z,
+            ShockDistribution::SkewNormal { location, scale, shape } => {
+                let delta = shape / (1.0 + shape * shape).sqrt();
+                let v: f64 = StandardNormal.sample(rng);
+                let u1 = delta * z + (1.0 - delta * delta).sqrt() * v;
+                location + scale * if z >= 0.0 { u1 } else { -u1 }
+            }
+            ShockDistribution::StudentT { location, scale, df } => {
+                let v: f64 = ChiSquared::new(df).unwrap().sample(rng);
+                location + scale * z / (v / df).sqrt()
+            }
+        }
+    }
+}
+
+/// Standard skew-normal CDF, Φ(z) - 2·T(z, shape)
+fn skew_normal_cdf(z: f64, shape: f64) -> f64 {
+    let standard = StatNormal::new(0.0, 1.0).unwrap();
+    (standard.cdf(z) - 2.0 * owens_t(z, shape)).clamp(0.0, 1.0)
+}
+
+/// Owen's T function by composite Simpson quadrature
+///
+/// T(h, a) = 1/(2π) ∫₀ᵃ exp(-h²(1 + x²)/2) / (1 + x²) dx
+fn owens_t(h: f64, a: f64) -> f64 {
+    const INTERVALS: usize = 64;
+    let f = |x: f64| (-0.5 * h * h * (1.0 + x * x)).exp() / (1.0 + x * x);
+
+    let step = a / INTERVALS as f64;
+    let interior: f64 = (1..INTERVALS)
+        .map(|i| f(i as f64 * step) * if i % 2 == 1 { 4.0 } else { 2.0 })
+        .sum();
+    (f(0.0) + interior + f(a)) * step / 3.0 / (2.0 * std::f64::consts::PI)
+}
+
+/// Check everything a `ShockStream` needs before it starts drawing
+///
+/// Covers the number of paths, the per-field distributions against the
+/// shock model's dimension, `shock_size`, and the bootstrap history.
+pub(crate) fn validate_sampling(
+    mc_config: &MonteCarloConfig,
+    dimension: usize,
+) -> Result<(), SimulationError> {
+    let invalid = |reason: String| Err(SimulationError::InvalidConfig { reason });
+
+    if mc_config.num_simulations == 0 {
+        return invalid("num_simulations must be > 0".to_string());
+    }
+
+    let distributions = &mc_config.shock_distributions;
+    if !distributions.is_empty() && distributions.len() != dimension {
+        return invalid(format!(
+            "{} shock distributions given for a {}-dimensional shock model",
+            distributions.len(),
+            dimension
+        ));
+    }
+    distributions.iter().try_for_each(ShockDistribution::validate)?;
+
+    if !mc_config.shock_size.is_finite() || mc_config.shock_size <= 0.0 {
+        return invalid(format!("shock size must be > 0, got {}", mc_config.shock_size));
+    }
+
+    if let Sampler::Bootstrap { observations, .. } = &mc_config.sampler {
+        if dimension != 4 {
+            return invalid(format!(
+                "bootstrap sampling requires a 4-dimensional shock model, got {}",
+                dimension
+            ));
+        }
+        if observations.is_empty() {
+            return invalid("bootstrap sampling requires at least one observation".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Source of per-path shock draws
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum Sampler {
+    /// Independent draws from `MonteCarloConfig::shock_distributions`, or
+    /// N(0, shock_size) when none are given
+    #[default]
+    PseudoRandom,
+    /// Latin hypercube design over `num_simulations` strata per draw
+    ///
+    /// Each draw's marginal gets exactly one sample per equal-probability
+    /// stratum, with strata permuted independently per draw and mapped through
+    /// the configured distribution's inverse CDF.
+    LatinHypercube,
+    /// Resample historical changes with replacement
+    ///
+    /// With `block_size`, draws circular blocks of consecutive observations to
+    /// preserve autocorrelation; otherwise rows are drawn iid. Only valid for
+    /// four-dimensional shock models.
+    Bootstrap {
+        observations: Vec<StateShock>,
+        block_size: Option<usize>,
+    },
+}
+
+/// Load historical shocks from CSV with header `capital,assets,lcr,entropy`
+pub fn load_shock_history(path: impl AsRef<Path>) -> Result<Vec<StateShock>, SimulationError> {
+    let path = path.as_ref();
+    let invalid = |reason: String| SimulationError::InvalidConfig {
+        reason: format!("shock history {}: {}", path.display(), reason),
+    };
+
+    let mut reader = csv::Reader::from_path(path).map_err(|e| invalid(e.to_string()))?;
+    let observations = reader
+        .deserialize()
+        .collect::<Result<Vec<StateShock>, _>>()
+        .map_err(|e| invalid(e.to_string()))?;
+
+    if observations.is_empty() {
+        return Err(invalid("no observations".to_string()));
+    }
+    Ok(observations)
+}
+
+/// Sequential stream of shock draws for one run
+///
+/// Path `i` always receives the `i`-th vector of the stream, so runs that are
+/// split into chunks see exactly the same shocks as a single-shot run.
+pub(crate) struct ShockStream<'a> {
+    rng: StdRng,
+    normal: Normal<f64>,
+    distributions: &'a [ShockDistribution],
+    sampler: &'a Sampler,
+    dimension: usize,
+    /// Next observation and rows left in the current bootstrap block
+    block: (usize, usize),
+    /// Full Latin hypercube design and the next path to hand out
+    design: Vec<f64>,
+    cursor: usize,
+}
+
+impl<'a> ShockStream<'a> {
+    /// Start the stream for `mc_config` with `dimension` draws per path
+    ///
+    /// Fails if the configuration does not pass `validate_sampling`.
+    pub(crate) fn new(mc_config: &'a MonteCarloConfig, dimension: usize) -> Result<Self, SimulationError> {
+        validate_sampling(mc_config, dimension)?;
+
+        let mut stream = Self {
+            rng: StdRng::seed_from_u64(mc_config.seed),
+            normal: Normal::new(0.0, mc_config.shock_size).expect("shock size is validated"),
+            distributions: &mc_config.shock_distributions,
+            sampler: &mc_config.sampler,
+            dimension,
+            block: (0, 0),
+            design: Vec::new(),
+            cursor: 0,
+        };
+
+        // Stratification spans the whole run, so the design is built upfront
+        if let Sampler::LatinHypercube = mc_config.sampler {
+            stream.design = stream.latin_hypercube(mc_config);
+        }
+        Ok(stream)
+    }
+
+    /// Draw the next `count` shock vectors, laid out contiguously
+    pub(crate) fn draw(&mut self, count: usize) -> Vec<f64> {
+        match self.sampler {
+            Sampler::PseudoRandom if self.distributions.is_empty() => (0..count * self.dimension)
+                .map(|_| self.normal.sample(&mut self.rng))
+                .collect(),
+            Sampler::PseudoRandom => (0..count * self.dimension)
+                .map(|i| self.distributions[i % self.dimension].sample(&mut self.rng))
+                .collect(),
+            Sampler::LatinHypercube => {
+                let start = self.cursor * self.dimension;
+                self.cursor += count;
+                self.design[start..self.cursor * self.dimension].to_vec()
+            }
+            Sampler::Bootstrap { observations, block_size } => {
+                let mut draws = Vec::with_capacity(count * 4);
+                for _ in 0..count {
+                    let row = self.next_row(observations.len(), block_size.unwrap_or(1));
+                    draws.extend(observations[row].to_draws());
+                }
+                draws
+            }
+        }
+    }
+
+    /// Build the `num_simulations` x `dimension` design, laid out by path
+    fn latin_hypercube(&mut self, mc_config: &MonteCarloConfig) -> Vec<f64> {
+        let n = mc_config.num_simulations;
+        let default = ShockDistribution::Normal { location: 0.0, scale: mc_config.shock_size };
+        let mut design = vec![0.0; n * self.dimension];
+        let mut strata: Vec<usize> = (0..n).collect();
+
+        for k in 0..self.dimension {
+            let distribution = self.distributions.get(k).copied().unwrap_or(default);
+            strata.shuffle(&mut self.rng);
+            for (i, &stratum) in strata.iter().enumerate() {
+                let jitter: f64 = self.rng.sample(Open01);
+                design[i * self.dimension + k] = distribution.inverse_cdf((stratum as f64 + jitter) / n as f64);
+            }
+        }
+        design
+    }
+
+    /// Next observation index, starting a fresh circular block when needed
+    fn next_row(&mut self, n: usize, block_size: usize) -> usize {
+        if self.block.1 == 0 {
+            self.block = (self.rng.gen_range(0..n), block_size.max(1));
+        }
+        let row = self.block.0;
+        self.block = ((row + 1) % n, self.block.1 - 1);
+        row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::simulation::monte_carlo::try_run_simulation;
+
+    fn history(n: usize) -> Vec<StateShock> {
+        (0..n)
+            .map(|i| StateShock {
+                capital: i as f64 * 0.001,
+                assets: -0.002,
+                lcr: 0.01,
+                entropy: 0.0,
+            })
+            .collect()
+    }
+
+    /// Recover the observation index from a drawn capital shock
+    fn row_of(draws: &[f64]) -> usize {
+        (draws[0] * BALANCE_SHEET_SCALE / 0.001).round() as usize
+    }
+
+    #[test]
+    fn test_bootstrap_draws_come_from_history() {
+        let observations = history(20);
+        let mc_config = MonteCarloConfig {
+            sampler: Sampler::Bootstrap { observations: observations.clone(), block_size: None },
+            ..Default::default()
+        };
+
+        let draws = ShockStream::new(&mc_config, 4).unwrap().draw(500);
+        let source: Vec<[f64; 4]> = observations.iter().map(|o| o.to_draws()).collect();
+        for shock in draws.chunks(4) {
+            assert!(source.iter().any(|s| s.as_slice() == shock));
+        }
+    }
+
+    #[test]
+    fn test_load_shock_history() {
+        let path = std::env::temp_dir().join(format!("olo-history-{}.csv", std::process::id()));
+        std::fs::write(&path, "capital,assets,lcr,entropy\n-0.01,0.002,-0.05,0.1\n0.0,0.0,0.0,0.0\n")
+            .unwrap();
+
+        let observations = load_shock_history(&path).unwrap();
+        assert_eq!(observations.len(), 2);
+        assert_eq!(observations[0].lcr, -0.05);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_block_bootstrap_preserves_runs() {
+        let mc_config = MonteCarloConfig {
+            sampler: Sampler::Bootstrap { observations: history(10), block_size: Some(5) },
+            ..Default::default()
+        };
+
+        let rows: Vec<usize> = ShockStream::new(&mc_config, 4).unwrap().draw(100).chunks(4).map(row_of).collect();
+        for block in rows.chunks(5) {
+            for pair in block.windows(2) {
+                assert_eq!(pair[1], (pair[0] + 1) % 10);
+            }
+        }
+    }
+
+    fn skewness(values: &[f64]) -> f64 {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        values.iter().map(|v| (v - mean).powi(3)).sum::<f64>() / n / var.powf(1.5)
+    }
+
+    #[test]
+    fn test_skew_normal_skewness_sign() {
+        let mc_config = MonteCarloConfig {
+            shock_distributions: vec![
+                ShockDistribution::SkewNormal { location: 0.0, scale: 2.0, shape: 5.0 },
+                ShockDistribution::SkewNormal { location: 0.0, scale: 2.0, shape: -5.0 },
+            ],
+            ..Default::default()
+        };
+
+        let draws = ShockStream::new(&mc_config, 2).unwrap().draw(50_000);
+        let right: Vec<f64> = draws.iter().step_by(2).copied().collect();
+        let left: Vec<f64> = draws.iter().skip(1).step_by(2).copied().collect();
+
+        assert!(skewness(&right) > 0.5);
+        assert!(skewness(&left) < -0.5);
+        let expected = mc_config.shock_distributions[0].mean();
+        assert!((right.iter().sum::<f64>() / right.len() as f64 - expected).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_shock_distribution_rejects_non_positive_scale() {
+        let distribution = ShockDistribution::SkewNormal { location: 0.0, scale: 0.0, shape: -3.0 };
+        assert!(matches!(distribution.validate(), Err(SimulationError::InvalidConfig { .. })));
+    }
+
+    #[test]
+    fn test_student_t_has_fatter_tails_than_normal() {
+        let fat = ShockDistribution::StudentT { location: 0.0, scale: 1.0, df: 3.0 };
+        assert!(fat.validate().is_ok());
+        let ShockDistribution::StudentT { location, scale, .. } = fat else { unreachable!() };
+        assert!(ShockDistribution::StudentT { location, scale, df: 2.0 }.validate().is_err());
+        assert!(fat.inverse_cdf(0.5).abs() < 1e-9);
+        let normal = ShockDistribution::Normal { location: 0.0, scale: 1.0 };
+        assert!(fat.inverse_cdf(0.999) > normal.inverse_cdf(0.999) * 2.0);
+
+        let mc_config = MonteCarloConfig { shock_distributions: vec![fat], ..Default::default() };
+        let draws = ShockStream::new(&mc_config, 1).unwrap().draw(50_000);
+        let beyond = |draws: &[f64]| draws.iter().filter(|x| x.abs() > 4.0).count();
+        // P(|T₃| > 4) ≈ 2.8%, against 0.006% for a standard normal
+        assert!(beyond(&draws) > 1_000);
+    }
+
+    #[test]
+    fn test_latin_hypercube_one_sample_per_stratum() {
+        let skewed = ShockDistribution::SkewNormal { location: 0.5, scale: 2.0, shape: -4.0 };
+        let normal = ShockDistribution::Normal { location: 0.0, scale: 2.0 };
+        let mc_config = MonteCarloConfig {
+            num_simulations: 500,
+            sampler: Sampler::LatinHypercube,
+            shock_distributions: vec![normal, skewed, normal, normal],
+            ..Default::default()
+        };
+
+        let draws = ShockStream::new(&mc_config, 4).unwrap().draw(500);
+        let standard = StatNormal::new(0.0, 1.0).unwrap();
+        for k in 0..4 {
+            let mut strata: Vec<usize> = draws
+                .iter()
+                .skip(k)
+                .step_by(4)
+                .map(|&x| {
+                    let p = if k == 1 {
+                        skew_normal_cdf((x - 0.5) / 2.0, -4.0)
+                    } else {
+                        standard.cdf(x / 2.0)
+                    };
+                    (p * 500.0) as usize
+                })
+                .collect();
+            strata.sort_unstable();
+            assert_eq!(strata, (0..500).collect::<Vec<_>>(), "draw {}", k);
+        }
+    }
+
+    #[test]
+    fn test_latin_hypercube_stabilizes_mean() {
+        use crate::simulation::monte_carlo::run_simulation;
+
+        let state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let mean_variance = |sampler: Sampler| {
+            let means: Vec<f64> = (0..30)
+                .map(|seed| {
+                    let mc_config = MonteCarloConfig {
+                        num_simulations: 500,
+                        seed,
+                        sampler: sampler.clone(),
+                        ..Default::default()
+                    };
+                    run_simulation(&state, &LagrangianConfig::default(), &mc_config).mean
+                })
+                .collect();
+            let mean = means.iter().sum::<f64>() / means.len() as f64;
+            means.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / means.len() as f64
+        };
+
+        assert!(mean_variance(Sampler::LatinHypercube) < 0.5 * mean_variance(Sampler::PseudoRandom));
+    }
+
+    #[test]
+    fn test_invalid_sampling_is_rejected() {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let rejected = [
+            MonteCarloConfig {
+                sampler: Sampler::Bootstrap { observations: Vec::new(), block_size: None },
+                ..Default::default()
+            },
+            MonteCarloConfig { shock_size: 0.0, ..Default::default() },
+            MonteCarloConfig { shock_size: f64::NAN, ..Default::default() },
+            MonteCarloConfig { num_simulations: 0, ..Default::default() },
+            MonteCarloConfig { num_simulations: 0, sampler: Sampler::LatinHypercube, ..Default::default() },
+        ];
+
+        for mc_config in &rejected {
+            let err = try_run_simulation(&base_state, &LagrangianConfig::default(), mc_config).unwrap_err();
+            assert!(matches!(err, SimulationError::InvalidConfig { .. }), "{}", err);
+        }
+
+        let bootstrap = MonteCarloConfig {
+            sampler: Sampler::Bootstrap { observations: history(5), block_size: None },
+            ..Default::default()
+        };
+        assert!(ShockStream::new(&bootstrap, 2).is_err());
+    }
+
+    #[test]
+    fn test_chunked_stream_matches_single_draw() {
+        let mc_config = MonteCarloConfig {
+            sampler: Sampler::Bootstrap { observations: history(10), block_size: Some(3) },
+            ..Default::default()
+        };
+
+        let whole = ShockStream::new(&mc_config, 4).unwrap().draw(10);
+        let mut stream = ShockStream::new(&mc_config, 4).unwrap();
+        let mut chunked = stream.draw(4);
+        chunked.extend(stream.draw(6));
+        assert_eq!(whole, chunked);
+    }
+}
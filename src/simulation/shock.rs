@@ -0,0 +1,121 @@
+//! Shock Models
+//!
+//! Maps a vector of standard random draws onto a perturbed bank state.
+//! The simulation engine owns the randomness; a `ShockModel` only decides how
+//! those draws move the balance sheet.
+
+use crate::core::lagrangian::BankState;
+
+/// Pluggable mapping from random draws to a shocked bank state
+///
+/// Implementations must be `Sync` because paths are evaluated in parallel.
+pub trait ShockModel: Sync {
+    /// Apply one path's draws to the base state
+    ///
+    /// `draws` always has exactly `dimension()` elements.
+    fn shock(&self, base: &BankState, draws: &[f64]) -> BankState;
+
+    /// Number of random draws consumed per path
+    fn dimension(&self) -> usize;
+
+    /// Column names for each draw in exported results
+    fn draw_labels(&self) -> Vec<String> {
+        (0..self.dimension()).map(|i| format!("shock_{}", i)).collect()
+    }
+}
+
+/// Relative move in capital and assets per unit draw
+pub(crate) const BALANCE_SHEET_SCALE: f64 = 0.01;
+
+/// Absolute move in liquidity coverage ratio per unit draw
+pub(crate) const LCR_SCALE: f64 = 0.01;
+
+/// Absolute move in entropy index per unit draw
+pub(crate) const ENTROPY_SCALE: f64 = 0.01;
+
+/// Smallest liquidity coverage ratio a shocked state may reach
+///
+/// Keeps the `1 / LCR` liquidity stress term finite.
+pub const LCR_FLOOR: f64 = 1e-3;
+
+/// Upper bound for a shocked entropy index
+pub const ENTROPY_INDEX_MAX: f64 = 10.0;
+
+/// Default shock model over the regulatory state fields
+///
+/// Draws are `[capital, assets, lcr, entropy]`:
+/// - tier 1 capital and total assets move 1% per unit draw, floored at zero
+/// - liquidity coverage moves 0.01 per unit draw, floored at `LCR_FLOOR`
+/// - entropy index moves 0.01 per unit draw, clamped to `[0, ENTROPY_INDEX_MAX]`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultiplicativeShock;
+
+impl ShockModel for MultiplicativeShock {
+    fn shock(&self, base: &BankState, draws: &[f64]) -> BankState {
+        BankState {
+            tier1_capital: (base.tier1_capital * (1.0 + draws[0] * BALANCE_SHEET_SCALE)).max(0.0),
+            total_assets: (base.total_assets * (1.0 + draws[1] * BALANCE_SHEET_SCALE)).max(0.0),
+            liquidity_coverage: (base.liquidity_coverage + draws[2] * LCR_SCALE).max(LCR_FLOOR),
+            entropy_index: (base.entropy_index + draws[3] * ENTROPY_SCALE)
+                .clamp(0.0, ENTROPY_INDEX_MAX),
+        }
+    }
+
+    fn dimension(&self) -> usize {
+        4
+    }
+
+    fn draw_labels(&self) -> Vec<String> {
+        ["shock_capital", "shock_assets", "shock_lcr", "shock_entropy"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_state() -> BankState {
+        BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_multiplicative_shock_semantics() {
+        let shocked = MultiplicativeShock.shock(&base_state(), &[1.0, -2.0, 5.0, -10.0]);
+
+        assert!((shocked.tier1_capital - 10_100.0).abs() < 1e-9);
+        assert!((shocked.total_assets - 98_000.0).abs() < 1e-9);
+        assert!((shocked.liquidity_coverage - 1.25).abs() < 1e-12);
+        assert!((shocked.entropy_index - 1.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_lcr_floor() {
+        let shocked = MultiplicativeShock.shock(&base_state(), &[0.0, 0.0, -500.0, 0.0]);
+        assert_eq!(shocked.liquidity_coverage, LCR_FLOOR);
+    }
+
+    #[test]
+    fn test_entropy_clamped() {
+        let low = MultiplicativeShock.shock(&base_state(), &[0.0, 0.0, 0.0, -1_000.0]);
+        let high = MultiplicativeShock.shock(&base_state(), &[0.0, 0.0, 0.0, 10_000.0]);
+
+        assert_eq!(low.entropy_index, 0.0);
+        assert_eq!(high.entropy_index, ENTROPY_INDEX_MAX);
+    }
+
+    #[test]
+    fn test_balance_sheet_floored_at_zero() {
+        let shocked = MultiplicativeShock.shock(&base_state(), &[-200.0, -150.0, 0.0, 0.0]);
+
+        assert_eq!(shocked.tier1_capital, 0.0);
+        assert_eq!(shocked.total_assets, 0.0);
+    }
+}
@@ -0,0 +1,178 @@
+//! Reusable Shock Sets
+//!
+//! Generates the shock draws for a run once so they can be applied to many
+//! candidate base states. Evaluating every candidate on the same draws (common
+//! random numbers) skips regenerating them and means differences between
+//! candidates come from the states alone, not from sampling noise.
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::core::lagrangian::{BankState, LagrangianConfig};
+use crate::simulation::meta::{sha256_hex, SimulationMeta};
+use crate::simulation::monte_carlo::{finalize, simulate_paths, MonteCarloConfig, SimulationResult};
+use crate::simulation::sampler::ShockStream;
+use crate::simulation::shock::{MultiplicativeShock, ShockModel};
+
+/// Pre-generated shock draws for the default shock model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShockSet {
+    mc_config: MonteCarloConfig,
+    shocks: Vec<f64>,
+    hash: String,
+}
+
+impl ShockSet {
+    /// Configuration the draws were generated from
+    pub fn mc_config(&self) -> &MonteCarloConfig {
+        &self.mc_config
+    }
+
+    /// Number of paths in the set
+    pub fn len(&self) -> usize {
+        self.shocks.len() / MultiplicativeShock.dimension()
+    }
+
+    /// Whether the set holds no paths
+    pub fn is_empty(&self) -> bool {
+        self.shocks.is_empty()
+    }
+
+    /// SHA-256 of the draws, hex encoded
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Draws of every path, back to back
+    pub(crate) fn draws(&self) -> &[f64] {
+        &self.shocks
+    }
+}
+
+/// Draw the shocks `run_simulation` would use for `mc_config`
+///
+/// # Panics
+/// If the sampling configuration is invalid.
+pub fn generate_shocks(mc_config: &MonteCarloConfig) -> ShockSet {
+    let shocks = ShockStream::new(mc_config, MultiplicativeShock.dimension())
+        .unwrap_or_else(|e| panic!("cannot generate shocks: {}", e))
+        .draw(mc_config.num_simulations);
+    let bytes = bincode::serialize(&shocks).expect("shock draws are always serializable");
+    let hash = sha256_hex(&bytes);
+
+    ShockSet {
+        mc_config: mc_config.clone(),
+        shocks,
+        hash,
+    }
+}
+
+/// Run Monte Carlo simulation on pre-generated shocks
+///
+/// Matches `run_simulation` with the set's configuration, and records the
+/// set's hash in `SimulationResult::shock_hash`.
+pub fn run_simulation_with_shocks(
+    base_state: &BankState,
+    lag_config: &LagrangianConfig,
+    shock_set: &ShockSet,
+) -> SimulationResult {
+    let started = Instant::now();
+    let model = MultiplicativeShock;
+    let fragilities = simulate_paths(base_state, lag_config, &model, &shock_set.shocks);
+
+    let mut result = finalize(
+        base_state,
+        lag_config,
+        &shock_set.mc_config,
+        &model,
+        &shock_set.shocks,
+        fragilities,
+    );
+    result.shock_hash = Some(shock_set.hash.clone());
+    result.meta = Some(SimulationMeta::new(
+        base_state,
+        lag_config,
+        &shock_set.mc_config,
+        &model,
+        started.elapsed(),
+    ));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::run_simulation;
+
+    fn candidate(tier1_capital: f64) -> BankState {
+        BankState {
+            tier1_capital,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_shock_set_matches_run_simulation() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 500,
+            ..Default::default()
+        };
+        let lag_config = LagrangianConfig::default();
+
+        let shock_set = generate_shocks(&mc_config);
+        let reused = run_simulation_with_shocks(&candidate(10_000.0), &lag_config, &shock_set);
+        let fresh = run_simulation(&candidate(10_000.0), &lag_config, &mc_config);
+
+        assert_eq!(shock_set.len(), 500);
+        assert_eq!(reused.fragilities, fresh.fragilities);
+        assert!(fresh.shock_hash.is_none());
+    }
+
+    #[test]
+    fn test_candidates_share_draws() {
+        let mc_config = MonteCarloConfig {
+            num_simulations: 1_000,
+            ..Default::default()
+        };
+        let lag_config = LagrangianConfig::default();
+        let shock_set = generate_shocks(&mc_config);
+
+        // Capital far above the minimum: the barrier term is negligible for
+        // both plans, so identical draws give identical fragilities
+        let plan_a = run_simulation_with_shocks(&candidate(20_000.0), &lag_config, &shock_set);
+        let plan_b = run_simulation_with_shocks(&candidate(25_000.0), &lag_config, &shock_set);
+
+        assert_eq!(plan_a.shock_hash, plan_b.shock_hash);
+        assert_eq!(plan_a.shock_hash.as_deref(), Some(shock_set.hash()));
+        assert_eq!(plan_a.fragilities, plan_b.fragilities);
+
+        // Near the minimum, more capital is less fragile on every single path
+        let thin = run_simulation_with_shocks(&candidate(8_500.0), &lag_config, &shock_set);
+        let thick = run_simulation_with_shocks(&candidate(9_000.0), &lag_config, &shock_set);
+        assert!(thin.fragilities.iter().zip(&thick.fragilities).all(|(t, k)| k <= t));
+        assert!(thick.mean < thin.mean);
+
+        // A different seed changes the draws and the hash
+        let other = generate_shocks(&MonteCarloConfig { seed: 7, ..mc_config });
+        assert_ne!(other.hash(), shock_set.hash());
+    }
+
+    #[test]
+    fn test_shock_set_round_trips() {
+        let shock_set = generate_shocks(&MonteCarloConfig {
+            num_simulations: 50,
+            ..Default::default()
+        });
+
+        let bytes = bincode::serialize(&shock_set).unwrap();
+        let restored: ShockSet = bincode::deserialize(&bytes).unwrap();
+        let lag_config = LagrangianConfig::default();
+
+        let before = run_simulation_with_shocks(&candidate(10_000.0), &lag_config, &shock_set);
+        let after = run_simulation_with_shocks(&candidate(10_000.0), &lag_config, &restored);
+        assert_eq!(before.fragilities, after.fragilities);
+        assert_eq!(before.shock_hash, after.shock_hash);
+    }
+}
@@ -0,0 +1,207 @@
+//! Streaming Summaries
+//!
+//! Bounded-memory alternative to storing every path's fragility. Running
+//! moments are kept with Welford's algorithm and quantiles come from a
+//! fixed-bin sketch over the fragility range [0, 100].
+//!
+//! The sketch has `SKETCH_BINS` bins of width 0.01. A sketch quantile lies in
+//! the same bin as the exact sample quantile, so it is within 0.01 of it; an
+//! exceedance probability is exact up to the share of paths in the bin that
+//! contains the threshold, which is interpolated assuming a uniform spread.
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::monte_carlo::SimulationResult;
+
+/// Number of sketch bins over the fragility range
+pub const SKETCH_BINS: usize = 10_000;
+
+/// Upper end of the fragility range
+const FRAGILITY_MAX: f64 = 100.0;
+
+/// Fixed-bin quantile sketch of the fragility distribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuantileSketch {
+    counts: Vec<u64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for QuantileSketch {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; SKETCH_BINS],
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl QuantileSketch {
+    /// Width of one bin in fragility points
+    pub fn bin_width(&self) -> f64 {
+        FRAGILITY_MAX / SKETCH_BINS as f64
+    }
+
+    /// Number of values inserted
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Record one fragility
+    pub fn insert(&mut self, value: f64) {
+        let bin = ((value / self.bin_width()) as usize).min(SKETCH_BINS - 1);
+        self.counts[bin] += 1;
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Combine with a sketch of other paths
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        for (a, b) in self.counts.iter_mut().zip(&other.counts) {
+            *a += b;
+        }
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Approximate sorted value at rank `floor(p * count)`
+    ///
+    /// Uses the same rank convention as the stored-sample VaR.
+    pub fn quantile(&self, p: f64) -> f64 {
+        let rank = ((p * self.count as f64) as u64).min(self.count.saturating_sub(1));
+        let mut below = 0;
+        for (bin, &c) in self.counts.iter().enumerate() {
+            if below + c > rank {
+                let within = (rank - below) as f64 + 0.5;
+                let value = (bin as f64 + within / c as f64) * self.bin_width();
+                return value.clamp(self.min, self.max);
+            }
+            below += c;
+        }
+        self.max
+    }
+
+    /// Approximate share of values strictly above `threshold`
+    pub fn exceedance(&self, threshold: f64) -> f64 {
+        if threshold >= self.max {
+            return 0.0;
+        }
+        if threshold < self.min {
+            return 1.0;
+        }
+
+        let width = self.bin_width();
+        let bin = ((threshold / width) as usize).min(SKETCH_BINS - 1);
+        let above: u64 = self.counts[bin + 1..].iter().sum();
+        let partial = ((bin + 1) as f64 - threshold / width).clamp(0.0, 1.0) * self.counts[bin] as f64;
+        (above as f64 + partial) / self.count as f64
+    }
+
+    /// Approximate threshold whose exceedance probability is `prob`
+    pub fn threshold_for_exceedance(&self, prob: f64) -> f64 {
+        let target = prob * self.count as f64;
+        let mut above = 0.0;
+        for (bin, &c) in self.counts.iter().enumerate().rev() {
+            if c > 0 && above + c as f64 >= target {
+                let value = (bin + 1) as f64 * self.bin_width() - (target - above) / c as f64 * self.bin_width();
+                return value.clamp(self.min, self.max);
+            }
+            above += c as f64;
+        }
+        self.min
+    }
+}
+
+/// Running moments and sketch for a streaming run
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StreamingSummary {
+    mean: f64,
+    m2: f64,
+    sketch: QuantileSketch,
+}
+
+impl StreamingSummary {
+    /// Fold in one path's fragility (Welford update)
+    pub(crate) fn push(&mut self, value: f64) {
+        self.sketch.insert(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.sketch.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Summary statistics without stored samples
+    pub(crate) fn into_result(self) -> SimulationResult {
+        let n = self.sketch.count as f64;
+        let std_dev = (self.m2 / n).sqrt();
+
+        SimulationResult {
+            fragilities: Vec::new(),
+            mean: self.mean,
+            std_dev,
+            std_error: std_dev / n.sqrt(),
+            variance_reduction_ratio: None,
+            var_95: self.sketch.quantile(0.95),
+            var_99: self.sketch.quantile(0.99),
+            max_fragility: self.sketch.max,
+            shock_hash: None,
+            sketch: Some(self.sketch),
+            meta: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lagrangian::{BankState, LagrangianConfig};
+    use crate::simulation::monte_carlo::{run_simulation, MonteCarloConfig};
+
+    #[test]
+    fn test_streaming_matches_stored_within_bin_error() {
+        let base_state = BankState {
+            tier1_capital: 10_000.0,
+            total_assets: 100_000.0,
+            liquidity_coverage: 1.2,
+            entropy_index: 2.0,
+        };
+        let stored_config = MonteCarloConfig {
+            num_simulations: 20_000,
+            ..Default::default()
+        };
+        let streaming_config = MonteCarloConfig {
+            store_samples: false,
+            ..stored_config.clone()
+        };
+
+        let lag_config = LagrangianConfig::default();
+        let stored = run_simulation(&base_state, &lag_config, &stored_config);
+        let streamed = run_simulation(&base_state, &lag_config, &streaming_config);
+
+        assert!(streamed.fragilities.is_empty());
+        assert!((streamed.mean - stored.mean).abs() < 1e-9);
+        assert!((streamed.std_dev - stored.std_dev).abs() < 1e-9);
+        assert_eq!(streamed.max_fragility, stored.max_fragility);
+        assert!((streamed.var_95 - stored.var_95).abs() <= 0.01);
+        assert!((streamed.var_99 - stored.var_99).abs() <= 0.01);
+    }
+
+    #[test]
+    fn test_sketch_merge_equals_single_sketch() {
+        let values: Vec<f64> = (0..1_000).map(|i| (i as f64 * 7.3) % 100.0).collect();
+
+        let mut whole = QuantileSketch::default();
+        values.iter().for_each(|&v| whole.insert(v));
+        let (mut left, mut right) = (QuantileSketch::default(), QuantileSketch::default());
+        values[..400].iter().for_each(|&v| left.insert(v));
+        values[400..].iter().for_each(|&v| right.insert(v));
+        left.merge(&right);
+
+        assert_eq!(left, whole);
+        assert!((whole.quantile(0.5) - 50.0).abs() < 0.2);
+    }
+}
@@ -0,0 +1,331 @@
+//! Multi-Bank System Simulation
+//!
+//! Stress tests several banks at once with dependent asset shocks. A copula
+//! generates the cross-bank uniform draws for each path, which are then mapped
+//! through each bank's marginal shock distribution. Copulas capture joint tail
+//! behaviour (simultaneous crashes) that linear correlation alone misses.
+
+use ndarray::Array2;
+use rayon::prelude::*;
+use rand::distributions::Distribution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{ChiSquared, Exp1, Gamma, StandardNormal};
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+
+use crate::core::lagrangian::{BankState, LagrangianConfig, compute_fragility};
+use crate::simulation::error::SimulationError;
+use crate::simulation::monte_carlo::{summarize, MonteCarloConfig, SimulationResult};
+use crate::simulation::shock::{MultiplicativeShock, ShockModel};
+
+/// Dependence structure between banks' asset shocks
+#[derive(Debug, Clone)]
+pub enum Dependence {
+    /// Banks are shocked independently
+    Independent,
+    /// Gaussian copula with correlation matrix `corr` (no tail dependence)
+    Gaussian { corr: Array2<f64> },
+    /// Student-t copula; symmetric tail dependence that grows as `df` falls
+    TCopula { corr: Array2<f64>, df: f64 },
+    /// Clayton copula; lower-tail dependence 2^(-1/θ), exchangeable across banks
+    Clayton { theta: f64 },
+}
+
+impl Dependence {
+    /// Validate parameters for a system of `n_banks`
+    pub fn validate(&self, n_banks: usize) -> Result<(), SimulationError> {
+        match self {
+            Dependence::Independent => Ok(()),
+            Dependence::Gaussian { corr } => cholesky(corr, n_banks).map(|_| ()),
+            Dependence::TCopula { corr, df } => {
+                if df.is_nan() || *df <= 2.0 {
+                    return Err(invalid(format!("t-copula df must be > 2, got {}", df)));
+                }
+                cholesky(corr, n_banks).map(|_| ())
+            }
+            Dependence::Clayton { theta } => {
+                if theta.is_nan() || *theta <= 0.0 {
+                    return Err(invalid(format!("Clayton theta must be > 0, got {}", theta)));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Multi-bank simulation result
+#[derive(Debug, Clone)]
+pub struct SystemSimulationResult {
+    /// Fragility distribution of each bank, in input order
+    pub per_bank: Vec<SimulationResult>,
+    /// Distribution of the asset-weighted mean fragility across banks
+    pub system: SimulationResult,
+}
+
+/// Run Monte Carlo simulation over a system of banks with dependent asset shocks
+///
+/// Asset shocks are N(0, shock_size) marginally and linked by `dependence`;
+/// capital, liquidity, and entropy shocks are independent per bank.
+pub fn run_system_simulation(
+    states: &[BankState],
+    lag_config: &LagrangianConfig,
+    mc_config: &MonteCarloConfig,
+    dependence: &Dependence,
+) -> Result<SystemSimulationResult, SimulationError> {
+    if states.is_empty() {
+        return Err(invalid("system simulation needs at least one bank".to_string()));
+    }
+
+    let n_banks = states.len();
+    let mut rng = StdRng::seed_from_u64(mc_config.seed);
+    let uniforms = copula_draws(dependence, n_banks, mc_config.num_simulations, &mut rng)?;
+
+    // Idiosyncratic draws for the non-asset dimensions, in path-major order
+    let marginal = Normal::new(0.0, mc_config.shock_size).map_err(|e| invalid(e.to_string()))?;
+    let idiosyncratic: Vec<f64> = (0..mc_config.num_simulations * n_banks * 3)
+        .map(|_| mc_config.shock_size * rng.sample::<f64, _>(StandardNormal))
+        .collect();
+
+    let model = MultiplicativeShock;
+    let total_assets: f64 = states.iter().map(|s| s.total_assets).sum();
+
+    let per_path: Vec<Vec<f64>> = uniforms
+        .par_iter()
+        .zip(idiosyncratic.par_chunks(n_banks * 3))
+        .map(|(u, other)| {
+            states
+                .iter()
+                .enumerate()
+                .map(|(b, state)| {
+                    let asset_shock = marginal.inverse_cdf(u[b]);
+                    let o = &other[b * 3..b * 3 + 3];
+                    let draws = [o[0], asset_shock, o[1], o[2]];
+                    compute_fragility(&model.shock(state, &draws), lag_config)
+                })
+                .collect()
+        })
+        .collect();
+
+    let per_bank = (0..n_banks)
+        .map(|b| summarize(per_path.iter().map(|f| f[b]).collect()))
+        .collect();
+
+    let system = summarize(
+        per_path
+            .iter()
+            .map(|f| {
+                f.iter()
+                    .zip(states)
+                    .map(|(frag, s)| frag * s.total_assets / total_assets)
+                    .sum()
+            })
+            .collect(),
+    );
+
+    Ok(SystemSimulationResult { per_bank, system })
+}
+
+/// Generate `count` vectors of `n_banks` dependent uniforms in (0, 1)
+pub fn copula_draws(
+    dependence: &Dependence,
+    n_banks: usize,
+    count: usize,
+    rng: &mut StdRng,
+) -> Result<Vec<Vec<f64>>, SimulationError> {
+    dependence.validate(n_banks)?;
+    let std_normal = Normal::new(0.0, 1.0).unwrap();
+
+    let draws = match dependence {
+        Dependence::Independent => (0..count)
+            .map(|_| (0..n_banks).map(|_| open_unit(rng.gen())).collect())
+            .collect(),
+
+        Dependence::Gaussian { corr } => {
+            let chol = cholesky(corr, n_banks)?;
+            (0..count)
+                .map(|_| {
+                    correlated_normals(&chol, rng)
+                        .into_iter()
+                        .map(|z| open_unit(std_normal.cdf(z)))
+                        .collect()
+                })
+                .collect()
+        }
+
+        Dependence::TCopula { corr, df } => {
+            let chol = cholesky(corr, n_banks)?;
+            let chi2 = ChiSquared::new(*df).map_err(|e| invalid(e.to_string()))?;
+            let student = StudentsT::new(0.0, 1.0, *df).map_err(|e| invalid(e.to_string()))?;
+            (0..count)
+                .map(|_| {
+                    let z = correlated_normals(&chol, rng);
+                    let scale = (chi2.sample(rng) / df).sqrt();
+                    z.into_iter()
+                        .map(|z| open_unit(student.cdf(z / scale)))
+                        .collect()
+                })
+                .collect()
+        }
+
+        // Marshall-Olkin: a shared Gamma(1/θ) frailty drives joint crashes
+        Dependence::Clayton { theta } => {
+            let frailty = Gamma::new(1.0 / theta, 1.0).map_err(|e| invalid(e.to_string()))?;
+            (0..count)
+                .map(|_| {
+                    let v: f64 = frailty.sample(rng);
+                    (0..n_banks)
+                        .map(|_| {
+                            let e: f64 = Exp1.sample(rng);
+                            open_unit((1.0 + e / v).powf(-1.0 / theta))
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    };
+
+    Ok(draws)
+}
+
+/// Keep uniforms strictly inside (0, 1) so inverse CDFs stay finite
+fn open_unit(u: f64) -> f64 {
+    u.clamp(1e-12, 1.0 - 1e-12)
+}
+
+fn invalid(reason: String) -> SimulationError {
+    SimulationError::InvalidConfig { reason }
+}
+
+/// Draw L·ε for standard normal ε
+fn correlated_normals(chol: &Array2<f64>, rng: &mut StdRng) -> Vec<f64> {
+    let n = chol.nrows();
+    let eps: Vec<f64> = (0..n).map(|_| rng.sample(StandardNormal)).collect();
+    (0..n)
+        .map(|i| (0..=i).map(|j| chol[[i, j]] * eps[j]).sum())
+        .collect()
+}
+
+/// Lower Cholesky factor of a correlation matrix, validating its shape and PSD-ness
+///
+/// Semi-definite matrices (e.g. perfectly correlated banks) are accepted.
+fn cholesky(corr: &Array2<f64>, n: usize) -> Result<Array2<f64>, SimulationError> {
+    const TOL: f64 = 1e-10;
+
+    if corr.dim() != (n, n) {
+        return Err(invalid(format!(
+            "correlation matrix is {:?}, expected {}x{}",
+            corr.dim(),
+            n,
+            n
+        )));
+    }
+    for i in 0..n {
+        if (corr[[i, i]] - 1.0).abs() > TOL {
+            return Err(invalid(format!("correlation diagonal [{}] is {}, expected 1", i, corr[[i, i]])));
+        }
+        for j in 0..i {
+            if (corr[[i, j]] - corr[[j, i]]).abs() > TOL {
+                return Err(invalid(format!("correlation matrix is not symmetric at [{}, {}]", i, j)));
+            }
+        }
+    }
+
+    let mut l = Array2::<f64>::zeros((n, n));
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| l[[i, k]] * l[[j, k]]).sum();
+            if i == j {
+                let pivot = corr[[i, i]] - sum;
+                if pivot < -TOL {
+                    return Err(invalid("correlation matrix is not positive semi-definite".to_string()));
+                }
+                l[[i, i]] = pivot.max(0.0).sqrt();
+            } else if l[[j, j]] > TOL {
+                l[[i, j]] = (corr[[i, j]] - sum) / l[[j, j]];
+            } else if (corr[[i, j]] - sum).abs() > 1e-8 {
+                return Err(invalid("correlation matrix is not positive semi-definite".to_string()));
+            }
+        }
+    }
+
+    Ok(l)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// Empirical P(U2 < q | U1 < q)
+    fn lower_tail_dependence(draws: &[Vec<f64>], q: f64) -> f64 {
+        let joint = draws.iter().filter(|u| u[0] < q && u[1] < q).count();
+        let marginal = draws.iter().filter(|u| u[0] < q).count();
+        joint as f64 / marginal as f64
+    }
+
+    #[test]
+    fn test_clayton_has_stronger_lower_tail_than_gaussian() {
+        // Kendall's tau = 0.5 for both: θ = 2τ/(1-τ), ρ = sin(πτ/2)
+        let tau: f64 = 0.5;
+        let theta = 2.0 * tau / (1.0 - tau);
+        let rho = (std::f64::consts::PI * tau / 2.0).sin();
+
+        let clayton = Dependence::Clayton { theta };
+        let gaussian = Dependence::Gaussian { corr: array![[1.0, rho], [rho, 1.0]] };
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let clayton_draws = copula_draws(&clayton, 2, 100_000, &mut rng).unwrap();
+        let gaussian_draws = copula_draws(&gaussian, 2, 100_000, &mut rng).unwrap();
+
+        let clayton_tail = lower_tail_dependence(&clayton_draws, 0.01);
+        let gaussian_tail = lower_tail_dependence(&gaussian_draws, 0.01);
+        assert!(
+            clayton_tail > gaussian_tail + 0.2,
+            "clayton {} vs gaussian {}",
+            clayton_tail,
+            gaussian_tail
+        );
+    }
+
+    #[test]
+    fn test_parameter_validation() {
+        let not_psd = array![[1.0, 0.9, -0.9], [0.9, 1.0, 0.9], [-0.9, 0.9, 1.0]];
+        assert!(Dependence::Gaussian { corr: not_psd }.validate(3).is_err());
+        assert!(Dependence::Gaussian { corr: Array2::eye(2) }.validate(3).is_err());
+        assert!(Dependence::TCopula { corr: Array2::eye(2), df: 2.0 }.validate(2).is_err());
+        assert!(Dependence::Clayton { theta: 0.0 }.validate(2).is_err());
+        assert!(Dependence::Gaussian { corr: array![[1.0, 1.0], [1.0, 1.0]] }.validate(2).is_ok());
+    }
+
+    #[test]
+    fn test_system_simulation() {
+        let states = vec![
+            BankState {
+                tier1_capital: 10_000.0,
+                total_assets: 100_000.0,
+                liquidity_coverage: 1.2,
+                entropy_index: 2.0,
+            },
+            BankState {
+                tier1_capital: 3_000.0,
+                total_assets: 30_000.0,
+                liquidity_coverage: 0.9,
+                entropy_index: 3.0,
+            },
+        ];
+        let mc_config = MonteCarloConfig {
+            num_simulations: 1000,
+            ..Default::default()
+        };
+        let dependence = Dependence::TCopula { corr: array![[1.0, 0.6], [0.6, 1.0]], df: 4.0 };
+
+        let result =
+            run_system_simulation(&states, &LagrangianConfig::default(), &mc_config, &dependence)
+                .unwrap();
+
+        assert_eq!(result.per_bank.len(), 2);
+        assert_eq!(result.system.fragilities.len(), 1000);
+        assert!(result.system.mean > result.per_bank[0].mean);
+        assert!(result.system.mean < result.per_bank[1].mean);
+    }
+}
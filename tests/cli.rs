@@ -0,0 +1,550 @@
+//! End-to-end tests of the `olo` binary
+
+use assert_cmd::Command;
+use serde_json::Value;
+
+/// The binary, isolated from any configuration file of the user running the tests
+fn olo() -> Command {
+    let mut command = Command::cargo_bin("olo").unwrap();
+    command.env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"));
+    command
+}
+
+/// Run `olo` with `args`, expecting success, and parse stdout as one JSON value
+fn json_output(args: &[&str]) -> Value {
+    let output = olo().args(args).output().unwrap();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).expect("stdout is a single JSON value")
+}
+
+#[test]
+fn test_simulate_json_has_inputs_config_and_outputs() {
+    let report = json_output(&[
+        "--format", "json", "simulate", "-c", "8000", "-a", "100000", "-l", "1.2", "-e", "2.0", "-i", "500",
+    ]);
+    assert_eq!(report["command"], "simulate");
+    assert_eq!(report["inputs"]["tier1_capital"], 8000.0);
+    assert_eq!(report["config"]["monte_carlo"]["num_simulations"], 500);
+    assert!(report["config"]["lagrangian"]["regulatory_min_capital"].is_number());
+    let outputs = &report["outputs"];
+    for field in ["mean", "std_dev", "std_error", "var_95", "var_99", "max_fragility"] {
+        assert!(outputs[field].is_number(), "{}", field);
+    }
+    assert!(outputs["var_99"].as_f64() >= outputs["var_95"].as_f64());
+}
+
+#[test]
+fn test_entropy_json_reports_metrics_and_classification() {
+    let report = json_output(&["entropy", "-w", "0.5", "-w", "0.5", "--format", "json"]);
+    assert_eq!(report["command"], "entropy");
+    assert_eq!(report["inputs"].as_array().unwrap().len(), 2);
+    let outputs = &report["outputs"];
+    assert!((outputs["shannon_entropy"].as_f64().unwrap() - 1.0).abs() < 1e-12);
+    assert!((outputs["normalized_entropy"].as_f64().unwrap() - 1.0).abs() < 1e-12);
+    assert!(outputs["concentration_risk"].as_f64().unwrap().abs() < 1e-12);
+    assert_eq!(outputs["classification"], "low");
+}
+
+#[test]
+fn test_progress_goes_to_stderr_in_text_mode() {
+    let output = olo()
+        .args(["simulate", "-c", "8000", "-a", "100000", "-l", "1.2", "-e", "2.0", "-i", "100"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Running 100 Monte Carlo simulations"));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Running"));
+}
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn test_fragility_batch_from_csv_skips_malformed_rows() {
+    let output = olo().args(["--format", "json", "fragility", "--input", &fixture("banks.csv")]).output().unwrap();
+    assert!(output.status.success());
+    let scores: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let ids: Vec<&str> = scores.as_array().unwrap().iter().map(|score| score["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["alpha", "bravo", "charlie"]);
+    assert_eq!(scores[0]["classification"], "low");
+    assert_eq!(scores[1]["classification"], "medium");
+    assert_eq!(scores[2]["classification"], "high");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("banks.csv:line 5:"));
+
+    let strict = olo().args(["fragility", "--input", &fixture("banks.csv"), "--strict"]).output().unwrap();
+    assert_eq!(strict.status.code(), Some(1));
+    let table = String::from_utf8_lossy(&strict.stdout);
+    assert!(table.lines().next().unwrap().starts_with("ID"));
+    assert_eq!(table.lines().count(), 4);
+}
+
+#[test]
+fn test_fragility_batch_from_ndjson_reports_line_numbers() {
+    let output = olo().args(["fragility", "--input", &fixture("banks.ndjson"), "--format", "json"]).output().unwrap();
+    assert!(output.status.success());
+    let scores: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(scores.as_array().unwrap().len(), 3);
+    assert_eq!(scores[2]["id"], "delta");
+    assert_eq!(scores[2]["classification"], "medium");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("banks.ndjson:line 4: missing field `entropy_index`"), "{}", stderr);
+
+    olo().args(["fragility", "--input", &fixture("banks.ndjson"), "--strict"]).assert().failure();
+}
+
+#[test]
+fn test_fragility_takes_the_regulatory_fields() {
+    let report = json_output(&["fragility", "-c", "10000", "-a", "100000", "-l", "1.2", "-e", "2.5", "--format", "json"]);
+    assert_eq!(report["command"], "fragility");
+    assert_eq!(report["inputs"]["liquidity_coverage"], 1.2);
+    assert!(report["inputs"].get("balance_sheet").is_none());
+    assert!((report["outputs"]["score"].as_f64().unwrap() - 19.4631).abs() < 1e-4);
+    assert_eq!(report["outputs"]["classification"], "medium");
+
+    let text = olo().args(["fragility", "-c", "10000", "-a", "100000", "-l", "1.2", "-e", "2.5"]).output().unwrap();
+    let text = String::from_utf8_lossy(&text.stdout);
+    assert!(text.contains("Capital Ratio: 10.00%"));
+    assert!(text.contains("Fragility Score: 19.4631"));
+
+    let missing = olo().args(["fragility", "-c", "10000", "-a", "100000", "-l", "1.2"]).output().unwrap();
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("--entropy-index is required"));
+}
+
+#[test]
+fn test_fragility_balance_sheet_mode_maps_onto_the_state() {
+    let sheet = ["--balance-sheet", "--assets", "100000", "--liabilities", "90000", "--equity", "10000"];
+    let mut args = vec!["--format", "json", "fragility", "--leverage", "10", "-l", "1.2", "-e", "2.5"];
+    args.extend(sheet);
+    let report = json_output(&args);
+    assert_eq!(report["inputs"]["tier1_capital"], 10000.0);
+    assert_eq!(report["inputs"]["total_assets"], 100000.0);
+    assert_eq!(report["inputs"]["balance_sheet"]["liabilities"], 90000.0);
+    assert!((report["outputs"]["score"].as_f64().unwrap() - 19.4631).abs() < 1e-4);
+
+    // Liquidity and entropy default when only the balance sheet is known
+    let mut args = vec!["--format", "json", "simulate", "-i", "100"];
+    args.extend(sheet);
+    let report = json_output(&args);
+    assert_eq!(report["inputs"]["liquidity_coverage"], 1.0);
+    assert_eq!(report["inputs"]["entropy_index"], 0.0);
+
+    let unbalanced = olo()
+        .args(["fragility", "--balance-sheet", "--assets", "100000", "--liabilities", "80000", "--equity", "10000"])
+        .output()
+        .unwrap();
+    assert!(!unbalanced.status.success());
+    assert!(String::from_utf8_lossy(&unbalanced.stderr).contains("does not balance"));
+
+    let leverage = olo()
+        .args(["fragility", "--balance-sheet", "--leverage", "12"])
+        .args(&sheet[1..])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&leverage.stderr).contains("--leverage 12 does not match"));
+
+    // The old figures alone, without the mode flag
+    olo().args(["fragility", "--assets", "100000", "--equity", "10000"]).assert().failure();
+}
+
+const BANK: [&str; 8] = ["-c", "10000", "-a", "100000", "-l", "1.2", "-e", "2.5"];
+
+fn simulate_json(extra: &[&str]) -> Value {
+    let mut args = vec!["--format", "json", "simulate", "-i", "400"];
+    args.extend(BANK);
+    args.extend(extra);
+    json_output(&args)
+}
+
+#[test]
+fn test_simulate_seed_reproduces_across_invocations() {
+    let first = simulate_json(&["--seed", "7", "--distribution", "student-t", "--df", "4", "--threads", "2"]);
+    let second = simulate_json(&["--seed", "7", "--distribution", "student-t", "--df", "4"]);
+    assert_eq!(first["outputs"], second["outputs"]);
+    assert_eq!(first["config"]["monte_carlo"]["seed"], 7);
+    assert_eq!(first["config"]["monte_carlo"]["num_threads"], 2);
+    let other = simulate_json(&["--seed", "8", "--distribution", "student-t", "--df", "4"]);
+    assert_ne!(first["outputs"]["mean"], other["outputs"]["mean"]);
+}
+
+#[test]
+fn test_simulate_parameters_map_onto_the_config() {
+    let report = simulate_json(&["--shock-size", "1.5", "--percentiles", "0.5,0.9,0.999", "--no-store-samples"]);
+    let config = &report["config"]["monte_carlo"];
+    assert_eq!(config["shock_size"], 1.5);
+    assert_eq!(config["store_samples"], false);
+    assert!(config["shock_distributions"].as_array().unwrap().is_empty());
+    let percentiles = report["outputs"]["percentiles"].as_array().unwrap();
+    assert_eq!(percentiles.iter().map(|p| p["percentile"].as_f64().unwrap()).collect::<Vec<_>>(), [0.5, 0.9, 0.999]);
+    let values: Vec<f64> = percentiles.iter().map(|p| p["fragility"].as_f64().unwrap()).collect();
+    assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let skewed = simulate_json(&["--distribution", "skew-normal", "--skew", "-4"]);
+    let distributions = skewed["config"]["monte_carlo"]["shock_distributions"].as_array().unwrap();
+    assert_eq!(distributions.len(), 4);
+    assert_eq!(distributions[0]["SkewNormal"]["shape"], -4.0);
+}
+
+#[test]
+fn test_simulate_rejects_invalid_parameters() {
+    let invalid: [&[&str]; 6] = [
+        &["--distribution", "student-t", "--df", "2"],
+        &["--distribution", "student-t"],
+        &["--df", "5"],
+        &["--distribution", "skew-normal"],
+        &["--percentiles", "0.95,1.5"],
+        &["--shock-size", "0"],
+    ];
+    for extra in invalid {
+        let output = olo().arg("simulate").args(BANK).args(extra).output().unwrap();
+        // Usage errors are operational errors; 2 is kept for crossed thresholds
+        assert_eq!(output.status.code(), Some(1), "{:?}", extra);
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("error:"), "{:?}", extra);
+    }
+}
+
+/// Fresh scratch directory for one test
+fn scratch(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("olo-cli-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Deterministic parameters need `--features insecure-test-setup`
+#[cfg(feature = "insecure-test-setup")]
+#[test]
+fn test_prove_then_verify_end_to_end() {
+    let dir = scratch("prove");
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    std::fs::write(
+        path("bank.json"),
+        r#"{"tier1_capital": 10000, "total_assets": 100000, "liquidity_coverage": 1.2, "entropy_index": 2.5}"#,
+    )
+    .unwrap();
+
+    let report = json_output(&[
+        "--format", "json", "prove", "--input", &path("bank.json"), "--params", &path("params.bin"),
+        "--out", &path("proof.json"), "--vk", &path("vk.bin"), "--period", "202403", "--insecure-test-seed", "7",
+    ]);
+    let fragility = report["outputs"]["fragility"].as_f64().unwrap();
+    assert!((fragility - 19.4631).abs() < 1e-3);
+
+    let verify = |extra: &[&str]| {
+        olo().args(["verify", "--proof", &path("proof.json"), "--vk", &path("vk.bin")]).args(extra).output().unwrap()
+    };
+    // The score as `prove` prints it
+    let fragility = format!("{:.4}", fragility);
+    let valid = verify(&["--period", "202403", "--expect-fragility", &fragility]);
+    assert!(valid.status.success(), "{}", String::from_utf8_lossy(&valid.stdout));
+    assert!(String::from_utf8_lossy(&valid.stdout).starts_with("✅ VALID"));
+
+    let rejected: [&[&str]; 3] = [&["--period", "202404"], &["--period", "202403", "--expect-fragility", "50"], &[]];
+    for extra in rejected {
+        let invalid = verify(extra);
+        assert_eq!(invalid.status.code(), Some(1), "{:?}", extra);
+        assert!(String::from_utf8_lossy(&invalid.stdout).starts_with("❌ INVALID"));
+    }
+
+    // A second proof reuses the saved parameters, so the exported keys still apply
+    olo()
+        .args(["prove", "--input", &path("bank.json"), "--params", &path("params.bin"), "--out", &path("again.json")])
+        .assert()
+        .success();
+    let again = olo()
+        .args(["--format", "json", "verify", "--proof", &path("again.json"), "--vk", &path("vk.bin")])
+        .output()
+        .unwrap();
+    let verdict: Value = serde_json::from_slice(&again.stdout).unwrap();
+    assert_eq!(verdict["outputs"]["valid"], true);
+    assert_eq!(verdict["outputs"]["circuit"], "Fragility");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_verify_fails_on_a_missing_proof() {
+    let dir = scratch("verify-missing");
+    let output = olo()
+        .args(["verify", "--proof", dir.join("absent.json").to_str().unwrap(), "--vk", "vk.bin"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("❌ INVALID"));
+}
+
+/// `olo config show` entry for `key`
+fn config_entry(report: &Value, key: &str) -> Value {
+    let entries = report["outputs"].as_array().unwrap();
+    entries.iter().find(|entry| entry["key"] == key).unwrap().clone()
+}
+
+#[test]
+fn test_config_layers_flag_over_env_over_file_over_default() {
+    let output = olo()
+        .args(["--config", &fixture("olo.toml"), "--format", "json", "config", "show"])
+        .env("OLO_MONTE_CARLO_SEED", "5")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let seed = config_entry(&report, "monte_carlo.seed");
+    assert_eq!(seed["value"], 5);
+    assert_eq!(seed["source"]["kind"], "env");
+    assert_eq!(seed["source"]["var"], "OLO_MONTE_CARLO_SEED");
+    let capital = config_entry(&report, "lagrangian.regulatory_min_capital");
+    assert_eq!(capital["value"], 0.105);
+    assert_eq!(capital["source"]["kind"], "file");
+    assert_eq!(config_entry(&report, "network.topics")["value"][0], "olo-fragility-eu");
+    let threads = config_entry(&report, "monte_carlo.num_threads");
+    assert_eq!(threads["value"], 0);
+    assert_eq!(threads["source"]["kind"], "default");
+
+    // A flag beats all three, and the commands use what the layers resolve to
+    let output = olo()
+        .args(["--config", &fixture("olo.toml"), "--format", "json", "simulate", "--seed", "9"])
+        .args(BANK)
+        .env("OLO_MONTE_CARLO_SEED", "5")
+        .env("OLO_MONTE_CARLO_SHOCK_SIZE", "2.5")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let config = &report["config"];
+    assert_eq!(config["monte_carlo"]["seed"], 9);
+    assert_eq!(config["monte_carlo"]["shock_size"], 2.5);
+    assert_eq!(config["monte_carlo"]["num_simulations"], 300);
+    assert_eq!(config["lagrangian"]["regulatory_min_capital"], 0.105);
+    assert_eq!(config["lagrangian"]["lambda_sensitivity"], 2.0);
+}
+
+#[test]
+fn test_config_show_text_names_sources_and_rejects_bad_files() {
+    let output = olo().args(["--config", &fixture("olo.toml"), "config", "show"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|line| line.starts_with("monte_carlo.shock_size")).unwrap();
+    assert!(line.contains("= 1.5") && line.ends_with(&format!("# file {}", fixture("olo.toml"))), "{}", line);
+    assert!(stdout.contains("# network.identity_path unset"));
+
+    let output = olo().args(["--config", &fixture("banks.csv"), "config", "show"]).output().unwrap();
+    assert!(!output.status.success());
+    let output = olo().args(["config", "show"]).env("OLO_MONTE_CARLO_SEED", "soon").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("OLO_MONTE_CARLO_SEED"));
+}
+
+#[test]
+fn test_fail_above_exit_codes() {
+    // BANK scores 19.4631
+    let under = olo().arg("fragility").args(BANK).args(["--fail-above", "20"]).output().unwrap();
+    assert_eq!(under.status.code(), Some(0));
+    assert!(under.stderr.is_empty());
+
+    let over = olo().args(["--format", "json", "fragility"]).args(BANK).args(["--fail-above", "15"]).output().unwrap();
+    assert_eq!(over.status.code(), Some(2));
+    let report: Value = serde_json::from_slice(&over.stdout).expect("the result is still printed");
+    assert_eq!(report["outputs"]["classification"], "medium");
+    assert_eq!(String::from_utf8_lossy(&over.stderr), "fragility 19.4631 is above --fail-above 15\n");
+
+    let missing = olo().args(["fragility", "-c", "10000", "--fail-above", "15"]).output().unwrap();
+    assert_eq!(missing.status.code(), Some(1));
+    let unreadable = olo().args(["fragility", "--input", "missing.csv", "--fail-above", "15"]).output().unwrap();
+    assert_eq!(unreadable.status.code(), Some(1));
+
+    // In a batch, every record over the threshold is named
+    let batch = olo().args(["fragility", "--input", &fixture("banks.csv"), "--fail-above", "15"]).output().unwrap();
+    assert_eq!(batch.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&batch.stderr);
+    assert!(stderr.contains("bravo: fragility") && stderr.contains("charlie: fragility"), "{}", stderr);
+    assert!(!stderr.contains("alpha: fragility"), "{}", stderr);
+}
+
+#[test]
+fn test_fail_above_var99_exit_codes() {
+    let var_99 = simulate_json(&[])["outputs"]["var_99"].as_f64().unwrap();
+    let run = |threshold: f64| {
+        let threshold = threshold.to_string();
+        olo().args(["simulate", "-i", "400"]).args(BANK).args(["--fail-above-var99", &threshold]).output().unwrap()
+    };
+    assert_eq!(run(var_99 + 1.0).status.code(), Some(0));
+    let over = run(var_99 - 1.0);
+    assert_eq!(over.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&over.stderr);
+    assert!(stderr.contains(&format!("99% VaR {:.4} is above --fail-above-var99", var_99)), "{}", stderr);
+    let invalid = olo().args(["simulate", "-i", "0"]).args(BANK).args(["--fail-above-var99", "1"]).output().unwrap();
+    assert_eq!(invalid.status.code(), Some(1));
+}
+
+#[test]
+fn test_help_documents_the_exit_codes() {
+    for command in ["fragility", "simulate", "compare"] {
+        let help = olo().args([command, "--help"]).output().unwrap();
+        assert_eq!(help.status.code(), Some(0));
+        let stdout = String::from_utf8_lossy(&help.stdout);
+        assert!(stdout.contains("Exit codes:") && stdout.contains("2  A --fail-above or --fail-on-deterioration"));
+    }
+}
+
+/// Write the states of `tests/fixtures/banks.csv` named `before` and `after` as JSON files
+fn compare_states(name: &str, before: &str, after: &str) -> [String; 2] {
+    let dir = scratch(name);
+    let state = |id: &str| match id {
+        "alpha" => r#"{"tier1_capital": 10000, "total_assets": 100000, "liquidity_coverage": 5.0, "entropy_index": 0.5}"#,
+        "bravo" => r#"{"tier1_capital": 10000, "total_assets": 100000, "liquidity_coverage": 1.2, "entropy_index": 2.5}"#,
+        _ => unreachable!("no fixture state {}", id),
+    };
+    [("before.json", before), ("after.json", after)].map(|(file, id)| {
+        let path = dir.join(file);
+        std::fs::write(&path, state(id)).unwrap();
+        path.display().to_string()
+    })
+}
+
+#[test]
+fn test_compare_attributes_a_deterioration() {
+    let [before, after] = compare_states("compare-worse", "alpha", "bravo");
+    let report = json_output(&["--format", "json", "compare", "--before", &before, "--after", &after]);
+    let outputs = &report["outputs"];
+    assert_eq!(outputs["before"]["classification"], "low");
+    assert_eq!(outputs["after"]["classification"], "medium");
+    assert_eq!(outputs["classification_changed"], true);
+    let delta = outputs["delta"].as_f64().unwrap();
+    assert!(delta > 14.0);
+    let attribution = &outputs["attribution"];
+    let sum: f64 = ["capital", "entropy", "liquidity"].iter().map(|c| attribution[c].as_f64().unwrap()).sum();
+    assert!((sum - delta).abs() < 1e-9);
+    assert_eq!(attribution["capital"], 0.0);
+
+    let gate = |points: &str| {
+        olo().args(["compare", "--before", &before, "--after", &after, "--fail-on-deterioration", points]).output().unwrap()
+    };
+    let output = gate("5");
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Classification changed: low → medium"), "{}", stdout);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("more than --fail-on-deterioration 5"));
+    assert_eq!(gate("20").status.code(), Some(0));
+}
+
+#[test]
+fn test_compare_improvement_passes_the_gate() {
+    let [before, after] = compare_states("compare-better", "bravo", "alpha");
+    let output = olo()
+        .args(["compare", "--before", &before, "--after", &after, "--fail-on-deterioration", "0"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Change: -14."), "{}", stdout);
+    assert!(stdout.contains("Liquidity: -"), "{}", stdout);
+}
+
+#[test]
+fn test_compare_identical_states() {
+    let [before, after] = compare_states("compare-same", "bravo", "bravo");
+    let report = json_output(&["--format", "json", "compare", "--before", &before, "--after", &after]);
+    let outputs = &report["outputs"];
+    assert_eq!(outputs["delta"], 0.0);
+    assert_eq!(outputs["classification_changed"], false);
+    assert_eq!(outputs["before"], outputs["after"]);
+    for component in ["capital", "entropy", "liquidity"] {
+        assert_eq!(outputs["attribution"][component], 0.0);
+    }
+
+    let output = olo().args(["compare", "--before", &before, "--after", &after]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Classification unchanged (medium)"));
+    let missing = olo().args(["compare", "--before", &before, "--after", "missing.json"]).output().unwrap();
+    assert_eq!(missing.status.code(), Some(1));
+}
+
+/// Text output of a seeded simulation charted at a fixed width
+fn histogram_output(columns: &str) -> String {
+    let output = olo()
+        .args(["simulate", "-i", "2000", "--seed", "7", "--histogram", "10"])
+        .args(BANK)
+        .env("COLUMNS", columns)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_simulate_histogram_layout_of_a_seeded_run() {
+    let stdout = histogram_output("60");
+    assert_eq!(stdout, histogram_output("60"));
+    let chart = stdout.split("Distribution:\n").nth(1).expect("a chart follows the results");
+    let lines: Vec<&str> = chart.lines().collect();
+
+    // Ten bins, a blank line, then the percentile table
+    let bins = &lines[..10];
+    assert!(bins.iter().all(|line| line.starts_with('[') && line.len() <= 60), "{:#?}", bins);
+    assert_eq!(bins.iter().map(|line| line.matches('#').count()).max(), Some(60 - 28));
+    let total: usize = bins.iter().map(|line| line[18..27].trim().parse::<usize>().unwrap()).sum();
+    assert_eq!(total, 2000);
+    assert_eq!(lines[10], "");
+    assert_eq!(lines[11], "Percentile  Fragility");
+    let labels: Vec<&str> = lines[12..].iter().map(|line| line.split_whitespace().next().unwrap()).collect();
+    assert_eq!(labels, ["50th", "75th", "90th", "95th", "99th", "99.9th"]);
+
+    // A narrower terminal only shortens the bars
+    let narrow = histogram_output("40");
+    assert_eq!(narrow.lines().count(), stdout.lines().count());
+    assert!(narrow.lines().all(|line| line.matches('#').count() <= 12));
+}
+
+#[test]
+fn test_simulate_histogram_json_has_edges_and_counts() {
+    let report = simulate_json(&["--histogram", "8"]);
+    let histogram = &report["outputs"]["histogram"];
+    assert_eq!(histogram["edges"].as_array().unwrap().len(), 9);
+    let counts = histogram["counts"].as_array().unwrap();
+    assert_eq!(counts.len(), 8);
+    assert_eq!(counts.iter().map(|count| count.as_u64().unwrap()).sum::<u64>(), 400);
+    assert_eq!(histogram["edges"][8], report["outputs"]["max_fragility"]);
+    assert!(simulate_json(&[])["outputs"].get("histogram").is_none());
+
+    let bins = simulate_json(&["--histogram"])["outputs"]["histogram"]["counts"].as_array().unwrap().len();
+    assert_eq!(bins, 20);
+    let streaming = olo().args(["simulate", "--histogram", "--no-store-samples"]).args(BANK).output().unwrap();
+    assert_eq!(streaming.status.code(), Some(1));
+}
+
+#[test]
+fn test_simulate_exports_every_path_to_csv() {
+    let dir = scratch("export");
+    let path = dir.join("paths.csv");
+    let report = simulate_json(&["--seed", "11", "--export-csv", path.to_str().unwrap()]);
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("path_index,fragility"));
+    let fragilities: Vec<f64> = lines.map(|line| line.split(',').nth(1).unwrap().parse().unwrap()).collect();
+    assert_eq!(fragilities.len(), 400);
+    let max = fragilities.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(max, report["outputs"]["max_fragility"].as_f64().unwrap());
+    let export = &report["outputs"]["export"];
+    assert_eq!(export["rows"], 400);
+    assert_eq!(export["bytes"], csv.len() as u64);
+
+    // Shock columns on request, and bounded memory without stored samples
+    let shocks = dir.join("shocks.csv");
+    let output = olo()
+        .args(["simulate", "-i", "400", "--no-store-samples", "--export-shocks", "--export-csv"])
+        .arg(&shocks)
+        .args(BANK)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Exported 400 rows to {}", shocks.display())));
+    let csv = std::fs::read_to_string(&shocks).unwrap();
+    assert_eq!(
+        csv.lines().next(),
+        Some("path_index,shock_capital,shock_assets,shock_lcr,shock_entropy,fragility")
+    );
+    assert_eq!(csv.lines().count(), 401);
+
+    let unpaired = olo().args(["simulate", "--export-shocks"]).args(BANK).output().unwrap();
+    assert_eq!(unpaired.status.code(), Some(1));
+}